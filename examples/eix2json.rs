@@ -1,61 +1,66 @@
-use eix::{Database, PackageReader, DB_VERSION_CURRENT};
+use eix::{Database, JsonFormat, JsonOptions, eix_to_json};
 use std::env;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{self, Read, Write};
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: {} <eix-file> [output-json]", args[0]);
-        process::exit(1);
-    }
+const USAGE: &str = "[--compact|--ndjson] [--header] [--hashes] [--parts] [--fields a,b,...] [eix-file|-] [output-json]";
 
-    let input_path = &args[1];
-    
-    let mut db = match Database::open_read(input_path) {
-        Ok(db) => db,
-        Err(e) => {
-            eprintln!("Error opening {}: {}", input_path, e);
-            process::exit(1);
-        }
-    };
-
-    let header = match db.read_header(DB_VERSION_CURRENT) {
-        Ok(h) => h,
-        Err(e) => {
-            eprintln!("Error reading header: {}", e);
-            process::exit(1);
-        }
-    };
-
-    let mut reader = PackageReader::new(db, header);
-    let mut packages = Vec::new();
-
-    while let Ok(true) = reader.next_category() {
-        while let Ok(Some(pkg)) = reader.read_package() {
-            packages.push(pkg);
+fn main() {
+    let mut options = JsonOptions::default();
+    let mut paths = Vec::new();
+    let mut args = env::args();
+    let program = args.next().unwrap_or_default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                eprintln!("Usage: {} {}", program, USAGE);
+                process::exit(1);
+            }
+            "--compact" => options.format = JsonFormat::Compact,
+            "--ndjson" => options.format = JsonFormat::Ndjson,
+            "--header" => options.include_header = true,
+            "--hashes" => options.serialize.string_hashes = true,
+            "--parts" => options.serialize.version_parts = true,
+            "--fields" => {
+                let fields = args.next().unwrap_or_default();
+                options.fields = fields.split(',').map(str::to_string).collect();
+            }
+            _ => paths.push(arg),
         }
     }
 
-    if args.len() > 2 {
-        let output_path = &args[2];
-        let file = match File::create(output_path) {
-            Ok(f) => f,
+    let input: Box<dyn Read> = match paths.first().map(String::as_str) {
+        // Read from stdin, e.g. `eix2json - < portage.eix`
+        Some("-") => Box::new(io::stdin()),
+        Some(path) => match File::open(path) {
+            Ok(file) => Box::new(file),
             Err(e) => {
-                eprintln!("Error creating {}: {}", output_path, e);
+                eprintln!("Error opening {}: {}", path, e);
                 process::exit(1);
             }
-        };
-        let writer = BufWriter::new(file);
-        if let Err(e) = serde_json::to_writer_pretty(writer, &packages) {
-            eprintln!("Error writing JSON: {}", e);
-            process::exit(1);
-        }
-    } else {
-        if let Err(e) = serde_json::to_writer_pretty(std::io::stdout(), &packages) {
-            eprintln!("Error writing JSON: {}", e);
-            process::exit(1);
-        }
+        },
+        None => match Database::open_default() {
+            Ok((db, _)) => Box::new(db.into_inner()),
+            Err(e) => {
+                eprintln!("Error opening eix database: {}", e);
+                process::exit(1);
+            }
+        },
+    };
+    let output: Box<dyn Write> = match paths.get(1) {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("Error creating {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => Box::new(io::stdout().lock()),
+    };
+
+    if let Err(e) = eix_to_json(input, output, options) {
+        eprintln!("{}", e);
+        process::exit(1);
     }
 }
@@ -0,0 +1,31 @@
+//! Parses an eix database in the browser
+//!
+//! Build with `wasm-pack build --target web` and call
+//! `parse_to_json(new Uint8Array(await file.arrayBuffer()))`.
+
+use eix::{Database, Package, PackageReader};
+use std::io;
+use wasm_bindgen::prelude::*;
+
+fn parse(bytes: &[u8]) -> io::Result<Vec<Package>> {
+    let mut db = Database::from_bytes(bytes);
+    let header = db.read_header(0)?;
+    let mut reader = PackageReader::new(db, header);
+    let mut packages = Vec::new();
+    while reader.next_category()? {
+        while let Some(pkg) = reader.read_package()? {
+            packages.push(pkg);
+        }
+    }
+    Ok(packages)
+}
+
+/// Returns the packages of the database as a JSON array, or
+/// `{"error": "..."}` if it cannot be read
+#[wasm_bindgen]
+pub fn parse_to_json(bytes: &[u8]) -> String {
+    match parse(bytes) {
+        Ok(packages) => serde_json::to_string(&packages).unwrap_or_default(),
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
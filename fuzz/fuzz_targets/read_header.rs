@@ -0,0 +1,8 @@
+#![no_main]
+
+use eix::Database;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Database::from_bytes(data).read_header(0);
+});
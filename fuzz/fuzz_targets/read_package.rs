@@ -0,0 +1,38 @@
+#![no_main]
+
+use eix::{DBHeader, Database, Decoder, EixTestBuilder};
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+use std::sync::OnceLock;
+
+/// A header storing all optional fields, with a few strings in each hash
+fn header() -> &'static DBHeader {
+    static HEADER: OnceLock<DBHeader> = OnceLock::new();
+    HEADER.get_or_init(|| {
+        let bytes = EixTestBuilder::new()
+            .overlay("gentoo", "/var/db/repos/gentoo")
+            .overlay("guru", "/var/db/repos/guru")
+            .category("app-misc")
+            .package("screen", |p| {
+                p.licenses("GPL-3+").version("4.9.1", |v| {
+                    v.keywords("amd64 ~arm64")
+                        .slot("0/4")
+                        .iuse("+pam test")
+                        .required_use("pam")
+                        .depend(eix::Depend {
+                            depend: vec!["sys-libs/ncurses".to_string()],
+                            ..Default::default()
+                        })
+                        .src_uri("https://example.org/screen.tar.gz")
+                })
+            })
+            .build_bytes();
+        Database::from_bytes(bytes).read_header(0).unwrap()
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut decoder = Decoder::new(Cursor::new(data));
+    decoder.set_input_len(data.len() as u64);
+    let _ = decoder.read_package(header(), "app-misc");
+});
@@ -0,0 +1,179 @@
+#ifndef EIX_H
+#define EIX_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MASK_NONE 0
+
+#define MASK_PACKAGE 1
+
+#define MASK_PROFILE 2
+
+#define MASK_HARD (MASK_PACKAGE | MASK_PROFILE)
+
+#define MASK_SYSTEM 4
+
+#define MASK_WORLD 8
+
+#define MASK_WORLD_SETS 16
+
+#define MASK_IN_PROFILE 32
+
+#define MASK_MARKED 64
+
+#define PROPERTIES_NONE 0
+
+#define PROPERTIES_INTERACTIVE 1
+
+#define PROPERTIES_LIVE 2
+
+#define PROPERTIES_VIRTUAL 4
+
+#define PROPERTIES_SET 8
+
+#define RESTRICT_NONE 0
+
+#define RESTRICT_BINCHECKS 1
+
+#define RESTRICT_STRIP 2
+
+#define RESTRICT_TEST 4
+
+#define RESTRICT_USERPRIV 8
+
+#define RESTRICT_INSTALLSOURCES 16
+
+#define RESTRICT_FETCH 32
+
+#define RESTRICT_MIRROR 64
+
+#define RESTRICT_PRIMARYURI 128
+
+#define RESTRICT_BINDIST 256
+
+#define RESTRICT_PARALLEL 512
+
+// An open database
+typedef struct EixDb EixDb;
+
+// A package as returned by [`eix_next_package`]
+typedef struct EixPackage {
+  const char *category;
+  const char *name;
+  const char *description;
+  const char *homepage;
+  const char *licenses;
+  size_t version_count;
+  // Used by the `eix_version_*` accessors
+  const void *handle;
+} EixPackage;
+
+// Opens the database at `path` (UTF-8). Returns NULL on failure; the
+// reason is then available from `eix_last_error(NULL)`.
+//
+// # Safety
+//
+// `path` must be NULL or a NUL-terminated string.
+struct EixDb *eix_open(const char *path);
+
+// Reads the next package into `out`. Returns 1 if a package was read,
+// 0 at the end of the database and -1 on error (see [`eix_last_error`]).
+//
+// # Safety
+//
+// `db` must come from [`eix_open`] and `out` point to an `EixPackage`.
+int eix_next_package(struct EixDb *db, struct EixPackage *out);
+
+// The full version, e.g. "1.2.3-r1". Free with [`eix_free_string`].
+//
+// # Safety
+//
+// `pkg` must be filled by [`eix_next_package`] of a database which was
+// not advanced or freed since.
+char *eix_version_string(const struct EixPackage *pkg, size_t index);
+
+// The slot without subslot ("0" if none). Free with [`eix_free_string`].
+//
+// # Safety
+//
+// See [`eix_version_string`].
+char *eix_version_slot(const struct EixPackage *pkg, size_t index);
+
+// The EAPI. Free with [`eix_free_string`].
+//
+// # Safety
+//
+// See [`eix_version_string`].
+char *eix_version_eapi(const struct EixPackage *pkg, size_t index);
+
+// The keywords, separated by spaces. Free with [`eix_free_string`].
+//
+// # Safety
+//
+// See [`eix_version_string`].
+char *eix_version_keywords(const struct EixPackage *pkg, size_t index);
+
+// The USE flags, separated by spaces. Free with [`eix_free_string`].
+//
+// # Safety
+//
+// See [`eix_version_string`].
+char *eix_version_iuse(const struct EixPackage *pkg, size_t index);
+
+// The label of the repository. Free with [`eix_free_string`].
+//
+// # Safety
+//
+// See [`eix_version_string`].
+char *eix_version_repository(const struct EixPackage *pkg, size_t index);
+
+// The mask flags (`MASK_*`), or -1 if out of range
+//
+// # Safety
+//
+// See [`eix_version_string`].
+int eix_version_mask_flags(const struct EixPackage *pkg, size_t index);
+
+// The properties flags (`PROPERTIES_*`), or 0 if out of range
+//
+// # Safety
+//
+// See [`eix_version_string`].
+uint32_t eix_version_properties_flags(const struct EixPackage *pkg, size_t index);
+
+// The restrict flags (`RESTRICT_*`), or 0 if out of range
+//
+// # Safety
+//
+// See [`eix_version_string`].
+uint64_t eix_version_restrict_flags(const struct EixPackage *pkg, size_t index);
+
+// The last error of `db`, or of the last failed [`eix_open`] of this
+// thread if `db` is NULL. NULL if there was none.
+//
+// # Safety
+//
+// `db` must be NULL or come from [`eix_open`].
+const char *eix_last_error(const struct EixDb *db);
+
+// Closes a database
+//
+// # Safety
+//
+// `db` must be NULL or come from [`eix_open`] and not be freed before.
+void eix_free_db(struct EixDb *db);
+
+// Frees a string returned by an `eix_version_*` accessor
+//
+// # Safety
+//
+// `s` must be NULL or come from an accessor and not be freed before.
+void eix_free_string(char *s);
+
+#endif  /* EIX_H */
@@ -0,0 +1,242 @@
+//! Structurally valid values for fuzzing
+//!
+//! The [`Arbitrary`] implementations of the data model only produce
+//! values the format can hold: version strings that parse into their
+//! parts and render back unchanged, overlay keys within range and
+//! hashes containing every referenced string.
+//!
+//! A single [`Package`] or [`Version`] refers to the first overlay and
+//! stores no REQUIRED_USE, dependencies or SRC_URI. Use
+//! [`ArbitraryDatabase`] for a header and packages which survive
+//! writing and reading unchanged.
+
+use crate::write::complete_hashes;
+use crate::{
+    BasicPart, DB_VERSION_CURRENT, DBHeader, Depend, OverlayIdent, Package, StringHash, Version,
+    parse_version,
+};
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+const NAME_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-+_";
+const WORD_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-+_/.:=<>~!?()|";
+
+/// A word of 1 to `max` characters of `chars`, starting with a letter
+fn word(u: &mut Unstructured, chars: &[u8], max: usize) -> Result<String> {
+    let len = u.int_in_range(1..=max)?;
+    let mut s = String::with_capacity(len);
+    s.push(char::from(*u.choose(&NAME_CHARS[..26])?));
+    for _ in 1..len {
+        s.push(char::from(*u.choose(chars)?));
+    }
+    Ok(s)
+}
+
+fn words(u: &mut Unstructured, max: usize) -> Result<Vec<String>> {
+    let len = u.int_in_range(0..=max)?;
+    (0..len).map(|_| word(u, WORD_CHARS, 12)).collect()
+}
+
+fn category(u: &mut Unstructured) -> Result<String> {
+    Ok(format!(
+        "{}-{}",
+        word(u, NAME_CHARS, 6)?,
+        word(u, NAME_CHARS, 8)?
+    ))
+}
+
+fn number(u: &mut Unstructured) -> Result<String> {
+    Ok(if u.ratio(1, 8)? {
+        // Leading zeros are kept by the parser
+        format!("0{}", u.int_in_range(0..=99u32)?)
+    } else {
+        u.int_in_range(0..=20240101u32)?.to_string()
+    })
+}
+
+/// A version string following the syntax of the Package Manager
+/// Specification, e.g. `1.2.3b_rc1_p2-r1`
+fn version_string(u: &mut Unstructured) -> Result<String> {
+    let mut s = number(u)?;
+    for _ in 0..u.int_in_range(0..=3)? {
+        s.push('.');
+        s.push_str(&number(u)?);
+    }
+    if u.ratio(1, 4)? {
+        s.push(char::from(*u.choose(&NAME_CHARS[..26])?));
+    }
+    for _ in 0..u.int_in_range(0..=2)? {
+        s.push('_');
+        s.push_str(u.choose(&["alpha", "beta", "pre", "rc", "p"])?);
+        if u.arbitrary()? {
+            s.push_str(&number(u)?);
+        }
+    }
+    if u.ratio(1, 3)? {
+        s.push_str("-r");
+        s.push_str(&number(u)?);
+        if u.ratio(1, 8)? {
+            s.push('.');
+            s.push_str(&number(u)?);
+        }
+    }
+    Ok(s)
+}
+
+/// One part of an arbitrary version
+impl<'a> Arbitrary<'a> for BasicPart {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let version = version_string(u)?;
+        let parts = parse_version(&version).expect("generated version is valid");
+        Ok(u.choose(&parts)?.clone())
+    }
+}
+
+impl<'a> Arbitrary<'a> for Depend {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Depend {
+            depend: words(u, 4)?,
+            rdepend: words(u, 4)?,
+            pdepend: words(u, 2)?,
+            bdepend: words(u, 4)?,
+            idepend: words(u, 2)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Version {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let version_string = version_string(u)?;
+        let parts = parse_version(&version_string).expect("generated version is valid");
+        let slot = match u.int_in_range(0..=2)? {
+            0 => String::new(),
+            1 => word(u, NAME_CHARS, 6)?,
+            _ => format!("{}/{}", word(u, NAME_CHARS, 6)?, word(u, NAME_CHARS, 6)?),
+        };
+        Ok(Version {
+            version_string,
+            parts,
+            eapi: u.choose(&["", "7", "8"])?.to_string(),
+            mask_flags: u.arbitrary()?,
+            properties_flags: u.arbitrary()?,
+            restrict_flags: u.arbitrary()?,
+            keywords: words(u, 6)?,
+            slot,
+            iuse: words(u, 6)?,
+            ..Default::default()
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Package {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let versions = u.int_in_range(0..=4)?;
+        Ok(Package {
+            category: category(u)?,
+            name: word(u, NAME_CHARS, 16)?,
+            description: u.arbitrary()?,
+            homepage: u.arbitrary()?,
+            licenses: words(u, 3)?.join(" "),
+            versions: (0..versions)
+                .map(|_| u.arbitrary())
+                .collect::<Result<_>>()?,
+            span: None,
+        })
+    }
+}
+
+fn hash(u: &mut Unstructured) -> Result<StringHash> {
+    let mut hash = StringHash::new();
+    for w in words(u, 4)? {
+        hash.add(w);
+    }
+    Ok(hash)
+}
+
+/// A header of the current format with one to four overlays and a few
+/// unreferenced strings in its hashes
+impl<'a> Arbitrary<'a> for DBHeader {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let overlays = u.int_in_range(1..=4)?;
+        let use_depend = u.arbitrary()?;
+        Ok(DBHeader {
+            version: DB_VERSION_CURRENT,
+            size: 0,
+            overlays: (0..overlays)
+                .map(|i| {
+                    let label = word(u, NAME_CHARS, 10)?;
+                    Ok(OverlayIdent {
+                        path: format!("/var/db/repos/{}", label),
+                        label: label.into(),
+                        priority: i,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            eapi_hash: hash(u)?,
+            license_hash: hash(u)?,
+            keywords_hash: hash(u)?,
+            iuse_hash: hash(u)?,
+            slot_hash: hash(u)?,
+            // Only stored together with the dependencies
+            depend_hash: if use_depend {
+                hash(u)?
+            } else {
+                StringHash::new()
+            },
+            use_depend,
+            use_required_use: u.arbitrary()?,
+            use_src_uri: u.arbitrary()?,
+            world_sets: words(u, 2)?,
+            warnings: Vec::new(),
+        })
+    }
+}
+
+/// A header and packages which are read back exactly as generated
+/// after writing them with [`EixWriter::write_database`](crate::EixWriter::write_database):
+/// the packages are grouped by category, the versions refer to existing
+/// overlays and carry their labels, and the optional fields are present
+/// exactly if the header stores them.
+#[derive(Debug, Clone)]
+pub struct ArbitraryDatabase {
+    pub header: DBHeader,
+    pub packages: Vec<Package>,
+}
+
+impl<'a> Arbitrary<'a> for ArbitraryDatabase {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut header: DBHeader = u.arbitrary()?;
+        let mut categories: Vec<String> = Vec::new();
+        for _ in 0..u.int_in_range(0..=4)? {
+            let c = category(u)?;
+            if !categories.contains(&c) {
+                categories.push(c);
+            }
+        }
+        let mut packages = Vec::new();
+        for category in &categories {
+            for _ in 0..u.int_in_range(1..=3)? {
+                let mut package: Package = u.arbitrary()?;
+                package.category = category.clone();
+                for v in &mut package.versions {
+                    v.overlay_key = u.choose_index(header.overlays.len())? as u64;
+                    let overlay = &header.overlays[v.overlay_key as usize];
+                    v.reponame = overlay.label.clone();
+                    v.priority = overlay.priority;
+                    if header.use_required_use {
+                        v.required_use = words(u, 3)?;
+                    }
+                    if header.use_depend {
+                        v.depend = Some(u.arbitrary()?);
+                    }
+                    if header.use_src_uri {
+                        v.src_uri = Some(u.arbitrary()?);
+                    }
+                }
+                packages.push(package);
+            }
+        }
+        complete_hashes(&mut header, &packages);
+        header.size = categories.len() as _;
+        Ok(ArbitraryDatabase { header, packages })
+    }
+}
@@ -0,0 +1,202 @@
+//! Reading a database from tokio's `AsyncRead`
+//!
+//! The bytes are read in chunks into a buffer and decoded from it by a
+//! [`Decoder`] over a slice, the same code as for files. A record is
+//! decoded again once more bytes arrive if the buffer ended within it.
+
+use crate::decoder::narrow;
+use crate::{DBHeader, DBVersion, Decoder, OpenOptions, Package, Treesize, Version};
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// A database read from an [`AsyncRead`]
+pub struct AsyncDatabase<R> {
+    reader: R,
+    options: OpenOptions,
+    buf: Vec<u8>,
+    // Start of the bytes not yet decoded in `buf`
+    start: usize,
+    // Offset of `buf[start]` in the file
+    position: u64,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDatabase<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, OpenOptions::default())
+    }
+
+    /// Uses the chunk size (`buffer_capacity`), UTF-8 policy, limits
+    /// and span recording of `options`
+    pub fn with_options(reader: R, options: OpenOptions) -> Self {
+        AsyncDatabase {
+            reader,
+            options,
+            buf: Vec::new(),
+            start: 0,
+            position: 0,
+            eof: false,
+        }
+    }
+
+    /// Number of bytes decoded from the start of the file
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Reads more bytes into the buffer: at least a chunk, and at least
+    /// as many as are buffered so that retries stay rare
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.buf.drain(..self.start);
+        self.start = 0;
+        let old_len = self.buf.len();
+        let chunk = self.options.buffer_capacity.max(old_len).max(1);
+        self.buf.resize(old_len + chunk, 0);
+        let mut read_buf = ReadBuf::new(&mut self.buf[old_len..]);
+        let result = Pin::new(&mut self.reader).poll_read(cx, &mut read_buf);
+        let filled = read_buf.filled().len();
+        self.buf.truncate(old_len + filled);
+        ready!(result)?;
+        if filled == 0 {
+            self.eof = true;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Decodes a value with `decode` from the buffer, reading more bytes
+    /// as long as the buffer ends within the value
+    fn poll_decode<T, F>(&mut self, cx: &mut Context<'_>, decode: &mut F) -> Poll<io::Result<T>>
+    where
+        F: FnMut(&mut Decoder<&[u8]>) -> io::Result<T>,
+    {
+        loop {
+            let mut decoder = Decoder::with_options(&self.buf[self.start..], &self.options);
+            decoder.set_position(self.position);
+            match decode(&mut decoder) {
+                Ok(value) => {
+                    let used = decoder.position() - self.position;
+                    self.start += used as usize;
+                    self.position += used;
+                    return Poll::Ready(Ok(value));
+                }
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !self.eof => {}
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+            ready!(self.poll_fill(cx))?;
+        }
+    }
+
+    async fn decode<T, F>(&mut self, mut decode: F) -> io::Result<T>
+    where
+        F: FnMut(&mut Decoder<&[u8]>) -> io::Result<T>,
+    {
+        poll_fn(|cx| self.poll_decode(cx, &mut decode)).await
+    }
+
+    /// Reads the database header, see [`Decoder::read_header`]
+    pub async fn read_header(&mut self, min_version: DBVersion) -> io::Result<DBHeader> {
+        self.decode(|d| d.read_header(min_version)).await
+    }
+
+    /// Reads a version record
+    pub async fn read_version(&mut self, hdr: &DBHeader) -> io::Result<Version> {
+        self.decode(|d| d.read_version(hdr)).await
+    }
+}
+
+/// Reads the packages of an [`AsyncDatabase`], category by category or
+/// as a [`Stream`] of all packages
+pub struct AsyncPackageReader<R> {
+    db: AsyncDatabase<R>,
+    header: DBHeader,
+    frames: Treesize,
+    cat_size: Treesize,
+    cat_name: String,
+}
+
+impl<R: AsyncRead + Unpin> AsyncPackageReader<R> {
+    pub fn new(db: AsyncDatabase<R>, header: DBHeader) -> Self {
+        let frames = header.size;
+        AsyncPackageReader {
+            db,
+            header,
+            frames,
+            cat_size: 0,
+            cat_name: String::new(),
+        }
+    }
+
+    pub fn header(&self) -> &DBHeader {
+        &self.header
+    }
+
+    pub fn current_category(&self) -> &str {
+        &self.cat_name
+    }
+
+    fn poll_next_category(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+        if self.frames == 0 {
+            return Poll::Ready(Ok(false));
+        }
+        let (name, size) = ready!(self.db.poll_decode(cx, &mut |d| {
+            d.set_context("category");
+            let name = d.read_string()?;
+            d.set_context("package count");
+            Ok((name, narrow::<Treesize>(d.read_num()?, "Package count")?))
+        }))?;
+        self.cat_name = name;
+        self.cat_size = size;
+        self.frames -= 1;
+        Poll::Ready(Ok(true))
+    }
+
+    fn poll_read_package(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Option<Package>>> {
+        if self.cat_size == 0 {
+            return Poll::Ready(Ok(None));
+        }
+        let (header, category) = (&self.header, &self.cat_name);
+        let pkg = ready!(
+            self.db
+                .poll_decode(cx, &mut |d| d.read_package(header, category))
+        )?;
+        self.cat_size -= 1;
+        Poll::Ready(Ok(Some(pkg)))
+    }
+
+    /// Moves to the next category
+    pub async fn next_category(&mut self) -> io::Result<bool> {
+        poll_fn(|cx| self.poll_next_category(cx)).await
+    }
+
+    /// Reads the next package in the current category
+    pub async fn read_package(&mut self) -> io::Result<Option<Package>> {
+        poll_fn(|cx| self.poll_read_package(cx)).await
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncPackageReader<R> {
+    type Item = io::Result<Package>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let result = loop {
+            match ready!(this.poll_read_package(cx)) {
+                Ok(Some(pkg)) => return Poll::Ready(Some(Ok(pkg))),
+                Ok(None) => match ready!(this.poll_next_category(cx)) {
+                    Ok(true) => continue,
+                    Ok(false) => return Poll::Ready(None),
+                    Err(e) => break e,
+                },
+                Err(e) => break e,
+            }
+        };
+        // Nothing sensible follows an error
+        this.frames = 0;
+        this.cat_size = 0;
+        Poll::Ready(Some(Err(result)))
+    }
+}
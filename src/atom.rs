@@ -0,0 +1,411 @@
+//! Package atoms
+//!
+//! An atom names a package (`dev-lang/python`) and optionally restricts
+//! its versions, slot and repository, e.g. `>=dev-lang/python-3.12:3.12::gentoo`.
+
+use crate::basicversion::{compare_parts, parse_parts, parse_version};
+use crate::{BasicPart, Package, PartType, Version, split_slot};
+use std::cmp::Ordering;
+use std::fmt;
+use std::io;
+
+/// Version operator of an atom
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AtomOp {
+    Less,
+    LessEqual,
+    /// `=`; combined with a trailing `*` this is a prefix match
+    Equal,
+    /// `~`: equal, ignoring the revision
+    Approx,
+    GreaterEqual,
+    Greater,
+}
+
+impl AtomOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AtomOp::Less => "<",
+            AtomOp::LessEqual => "<=",
+            AtomOp::Equal => "=",
+            AtomOp::Approx => "~",
+            AtomOp::GreaterEqual => ">=",
+            AtomOp::Greater => ">",
+        }
+    }
+}
+
+/// A (possibly versioned, slotted or repository-restricted) package atom
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Atom {
+    pub op: Option<AtomOp>,
+    pub category: String,
+    pub name: String,
+    /// Version string; present exactly if `op` is
+    pub version: Option<String>,
+    /// Parsed parts of `version`
+    pub parts: Vec<BasicPart>,
+    /// `=cat/pkg-1.2*`
+    pub wildcard: bool,
+    pub slot: Option<String>,
+    pub subslot: Option<String>,
+    pub repo: Option<String>,
+}
+
+fn invalid(s: &str, reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid atom {:?}: {}", s, reason),
+    )
+}
+
+/// Splits `name-version` at the first hyphen followed by a valid version.
+/// Package names must start with a letter, digit or underscore.
+pub fn split_name_version(s: &str) -> Option<(&str, &str)> {
+    if !s.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    s.match_indices('-')
+        .map(|(i, _)| (&s[..i], &s[i + 1..]))
+        .find(|(_, version)| parse_parts(version).is_ok())
+}
+
+/// Splits a `category/name-version` string into its three components
+pub fn parse_cpv(s: &str) -> Option<(&str, &str, &str)> {
+    let (category, rest) = s.split_once('/')?;
+    let (name, version) = split_name_version(rest)?;
+    if category.is_empty() {
+        return None;
+    }
+    Some((category, name, version))
+}
+
+impl Atom {
+    /// Creates an unversioned atom for `category/name`
+    pub fn new(category: &str, name: &str) -> Self {
+        Atom {
+            op: None,
+            category: category.to_string(),
+            name: name.to_string(),
+            version: None,
+            parts: Vec::new(),
+            wildcard: false,
+            slot: None,
+            subslot: None,
+            repo: None,
+        }
+    }
+
+    /// Parses an atom like `>=cat/pkg-1.0:slot/subslot::repo[use]`.
+    /// USE dependencies are ignored; blockers are not supported.
+    pub fn parse(s: &str) -> io::Result<Atom> {
+        let input = s;
+        let mut s = s.trim();
+        if s.starts_with('!') {
+            return Err(invalid(input, "blockers are not supported"));
+        }
+        if let Some(i) = s.find('[') {
+            if !s.ends_with(']') {
+                return Err(invalid(input, "unterminated USE dependency"));
+            }
+            s = &s[..i];
+        }
+
+        let mut repo = None;
+        if let Some((rest, r)) = s.split_once("::") {
+            if r.is_empty() {
+                return Err(invalid(input, "empty repository"));
+            }
+            repo = Some(r.to_string());
+            s = rest;
+        }
+
+        let (mut slot, mut subslot) = (None, None);
+        if let Some((rest, slot_dep)) = s.split_once(':') {
+            // Slot operators (`:=`, `:*`, `:2=`) do not restrict matching
+            let slot_dep = slot_dep.trim_end_matches(['=', '*']);
+            if !slot_dep.is_empty() {
+                match slot_dep.split_once('/') {
+                    Some((sl, sub)) => {
+                        slot = Some(sl.to_string());
+                        subslot = Some(sub.to_string());
+                    }
+                    None => slot = Some(slot_dep.to_string()),
+                }
+            }
+            s = rest;
+        }
+
+        let mut op = None;
+        for (prefix, o) in [
+            (">=", AtomOp::GreaterEqual),
+            ("<=", AtomOp::LessEqual),
+            (">", AtomOp::Greater),
+            ("<", AtomOp::Less),
+            ("=", AtomOp::Equal),
+            ("~", AtomOp::Approx),
+        ] {
+            if let Some(rest) = s.strip_prefix(prefix) {
+                op = Some(o);
+                s = rest;
+                break;
+            }
+        }
+
+        let mut wildcard = false;
+        if let Some(rest) = s.strip_suffix('*') {
+            if op != Some(AtomOp::Equal) {
+                return Err(invalid(input, "'*' requires the '=' operator"));
+            }
+            wildcard = true;
+            s = rest;
+        }
+
+        let (category, rest) = s
+            .split_once('/')
+            .ok_or_else(|| invalid(input, "missing category"))?;
+        if category.is_empty() || rest.is_empty() || rest.contains('/') {
+            return Err(invalid(input, "expected category/name"));
+        }
+
+        let mut atom = Atom::new(category, rest);
+        if op.is_some() {
+            let (name, version) =
+                split_name_version(rest).ok_or_else(|| invalid(input, "missing version"))?;
+            atom.name = name.to_string();
+            atom.parts = parse_version(version)?;
+            atom.version = Some(version.to_string());
+        }
+        atom.op = op;
+        atom.wildcard = wildcard;
+        atom.slot = slot;
+        atom.subslot = subslot;
+        atom.repo = repo;
+        Ok(atom)
+    }
+
+    /// Returns `category/name`
+    pub fn cp(&self) -> String {
+        format!("{}/{}", self.category, self.name)
+    }
+
+    pub fn matches_cp(&self, category: &str, name: &str) -> bool {
+        self.category == category && self.name == name
+    }
+
+    /// Checks the version restriction (always true for unversioned atoms)
+    pub fn matches_version(&self, parts: &[BasicPart]) -> bool {
+        let Some(op) = self.op else {
+            return true;
+        };
+        if self.wildcard {
+            return prefix_match(&self.parts, parts);
+        }
+        match op {
+            AtomOp::Equal => compare_parts(parts, &self.parts) == Ordering::Equal,
+            AtomOp::Approx => {
+                compare_parts(without_revision(parts), without_revision(&self.parts))
+                    == Ordering::Equal
+            }
+            AtomOp::Less => compare_parts(parts, &self.parts) == Ordering::Less,
+            AtomOp::LessEqual => compare_parts(parts, &self.parts) != Ordering::Greater,
+            AtomOp::Greater => compare_parts(parts, &self.parts) == Ordering::Greater,
+            AtomOp::GreaterEqual => compare_parts(parts, &self.parts) != Ordering::Less,
+        }
+    }
+
+    /// Checks the slot restriction against a SLOT value as stored by eix
+    pub fn matches_slot(&self, slot: &str) -> bool {
+        let (slot, subslot) = split_slot(slot);
+        if self.slot.as_deref().is_some_and(|s| s != slot) {
+            return false;
+        }
+        match &self.subslot {
+            Some(expected) => subslot == Some(expected.as_str()),
+            None => true,
+        }
+    }
+
+    pub fn matches_repo(&self, repo: &str) -> bool {
+        self.repo.as_deref().is_none_or(|r| r == repo)
+    }
+
+    /// Checks whether `version` of `package` matches the atom
+    pub fn matches(&self, package: &Package, version: &Version) -> bool {
+        self.matches_cp(&package.category, &package.name)
+            && self.matches_version(&version.parts)
+            && self.matches_slot(&version.slot)
+            && self.matches_repo(&version.reponame)
+    }
+}
+
+/// The atom as it would be parsed, without USE dependencies
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(op) = self.op {
+            write!(f, "{}", op.as_str())?;
+        }
+        write!(f, "{}/{}", self.category, self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, "-{}", version)?;
+            if self.wildcard {
+                write!(f, "*")?;
+            }
+        }
+        if self.slot.is_some() || self.subslot.is_some() {
+            write!(f, ":{}", self.slot.as_deref().unwrap_or(""))?;
+            if let Some(subslot) = &self.subslot {
+                write!(f, "/{}", subslot)?;
+            }
+        }
+        if let Some(repo) = &self.repo {
+            write!(f, "::{}", repo)?;
+        }
+        Ok(())
+    }
+}
+
+fn without_revision(parts: &[BasicPart]) -> &[BasicPart] {
+    match parts
+        .iter()
+        .position(|p| matches!(p.part_type, PartType::Revision | PartType::InterRev))
+    {
+        Some(i) => &parts[..i],
+        None => parts,
+    }
+}
+
+/// `=cat/pkg-1.2*`: all parts but the last are equal, the content of the
+/// last part is a prefix of the corresponding candidate part
+fn prefix_match(prefix: &[BasicPart], parts: &[BasicPart]) -> bool {
+    let Some((last, init)) = prefix.split_last() else {
+        return true;
+    };
+    if parts.len() < prefix.len() {
+        return false;
+    }
+    init.iter()
+        .zip(parts)
+        .all(|(a, b)| a.compare(b) == Ordering::Equal)
+        && parts[init.len()].part_type == last.part_type
+        && parts[init.len()]
+            .part_content
+            .starts_with(&last.part_content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: &str, slot: &str, repo: &str) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            slot: slot.to_string(),
+            reponame: repo.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_cpv() {
+        assert_eq!(
+            parse_cpv("dev-lang/python-3.12.1-r1"),
+            Some(("dev-lang", "python", "3.12.1-r1"))
+        );
+        assert_eq!(
+            parse_cpv("x11-libs/gtk+-3.24.41"),
+            Some(("x11-libs", "gtk+", "3.24.41"))
+        );
+        assert_eq!(
+            parse_cpv("dev-libs/foo-bar-2-1.0"),
+            Some(("dev-libs", "foo-bar-2", "1.0"))
+        );
+        assert_eq!(parse_cpv("dev-lang/python"), None);
+        assert_eq!(parse_cpv("python-3.12"), None);
+        assert_eq!(parse_cpv("app-misc/-MERGING-foo-1.0"), None);
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let atom = Atom::parse(">=dev-lang/python-3.12_rc1:3.12/3.12t::gentoo[sqlite]").unwrap();
+        assert_eq!(atom.op, Some(AtomOp::GreaterEqual));
+        assert_eq!(atom.cp(), "dev-lang/python");
+        assert_eq!(atom.version.as_deref(), Some("3.12_rc1"));
+        assert_eq!(atom.slot.as_deref(), Some("3.12"));
+        assert_eq!(atom.subslot.as_deref(), Some("3.12t"));
+        assert_eq!(atom.repo.as_deref(), Some("gentoo"));
+
+        let atom = Atom::parse("dev-lang/python:3.12").unwrap();
+        assert_eq!(atom.op, None);
+        assert_eq!(atom.name, "python");
+        assert_eq!(atom.slot.as_deref(), Some("3.12"));
+
+        let atom = Atom::parse("dev-libs/openssl:=").unwrap();
+        assert_eq!(atom.slot, None);
+
+        let atom = Atom::parse("=sys-kernel/gentoo-sources-6.6*").unwrap();
+        assert!(atom.wildcard);
+        assert_eq!(atom.name, "gentoo-sources");
+
+        for s in [
+            "python",
+            ">=dev-lang/python",
+            "dev-lang/python-3*",
+            "!dev-lang/python",
+            "/python",
+            "dev-lang/python::",
+        ] {
+            assert!(Atom::parse(s).is_err(), "{:?} should be invalid", s);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        for s in [
+            ">=dev-lang/python-3.12_rc1:3.12/3.12t::gentoo",
+            "dev-lang/python:3.12",
+            "=sys-kernel/gentoo-sources-6.6*",
+            "~app-misc/screen-4.9.1",
+            "app-misc/screen::gentoo",
+        ] {
+            assert_eq!(Atom::parse(s).unwrap().to_string(), s);
+        }
+        let atom = Atom::parse("<dev-lang/rust-1.80[llvm]").unwrap();
+        assert_eq!(atom.to_string(), "<dev-lang/rust-1.80");
+    }
+
+    #[test]
+    fn test_matches() {
+        let pkg = Package {
+            category: "dev-lang".to_string(),
+            name: "python".to_string(),
+            ..Default::default()
+        };
+        let v = version("3.12.1-r1", "3.12/3.12", "gentoo");
+        let check = |atom: &str| Atom::parse(atom).unwrap().matches(&pkg, &v);
+
+        assert!(check("dev-lang/python"));
+        assert!(!check("dev-lang/perl"));
+        assert!(check("=dev-lang/python-3.12.1-r1"));
+        assert!(!check("=dev-lang/python-3.12.1"));
+        assert!(check("~dev-lang/python-3.12.1"));
+        assert!(check("=dev-lang/python-3.12*"));
+        assert!(check("=dev-lang/python-3.1*"));
+        assert!(!check("=dev-lang/python-3.13*"));
+        assert!(check(">=dev-lang/python-3.12.1"));
+        assert!(check(">dev-lang/python-3.12.1"));
+        assert!(!check("<dev-lang/python-3.12.1-r1"));
+        assert!(check("<=dev-lang/python-3.12.1-r1"));
+        assert!(check("dev-lang/python:3.12"));
+        assert!(check("dev-lang/python:3.12/3.12"));
+        assert!(!check("dev-lang/python:3.12/3.12t"));
+        assert!(!check("dev-lang/python:3.11"));
+        assert!(check("dev-lang/python::gentoo"));
+        assert!(!check("dev-lang/python::guru"));
+
+        // eix stores the slot "0" as ""
+        let v = version("1.0", "", "gentoo");
+        assert!(Atom::parse("dev-lang/python:0").unwrap().matches(&pkg, &v));
+    }
+}
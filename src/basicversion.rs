@@ -0,0 +1,363 @@
+//! Version ordering
+//!
+//! Mirrors the comparison rules of eix's `BasicVersion`, which in turn
+//! follow the version comparison section of the Package Manager
+//! Specification.
+
+use crate::{
+    BasicPart, DB_VERSION_LIVE_PROPERTY, DBVersion, PROPERTIES_LIVE, Package, PartType, Version,
+};
+use std::cmp::Ordering;
+use std::io;
+
+/// Compares two numeric strings of arbitrary length without overflowing.
+/// An empty string counts as zero.
+fn compare_numeric(left: &str, right: &str) -> Ordering {
+    let left = left.trim_start_matches('0');
+    let right = right.trim_start_matches('0');
+    left.len().cmp(&right.len()).then_with(|| left.cmp(right))
+}
+
+impl BasicPart {
+    /// Compares two parts: first by type, then by content
+    pub fn compare(&self, other: &BasicPart) -> Ordering {
+        let ret = self.part_type.cmp(&other.part_type);
+        if ret != Ordering::Equal {
+            return ret;
+        }
+        match self.part_type {
+            PartType::Primary
+                if self.part_content.starts_with('0') || other.part_content.starts_with('0') =>
+            {
+                // Components with a leading zero are compared as strings
+                // after stripping trailing zeros (PMS 3.3)
+                self.part_content
+                    .trim_end_matches('0')
+                    .cmp(other.part_content.trim_end_matches('0'))
+            }
+            PartType::Character | PartType::Garbage => self.part_content.cmp(&other.part_content),
+            _ => compare_numeric(&self.part_content, &other.part_content),
+        }
+    }
+}
+
+/// Compares two lists of version parts.
+///
+/// A missing part compares like an empty revision, so `1.0` equals
+/// `1.0-r0`, is newer than `1.0_rc1` and older than `1.0.1` or `1.0_p1`.
+pub fn compare_parts(left: &[BasicPart], right: &[BasicPart]) -> Ordering {
+    let missing = BasicPart {
+        part_type: PartType::Revision,
+        part_content: String::new(),
+    };
+    let len = left.len().max(right.len());
+    for i in 0..len {
+        let l = left.get(i).unwrap_or(&missing);
+        let r = right.get(i).unwrap_or(&missing);
+        let ret = l.compare(r);
+        if ret != Ordering::Equal {
+            return ret;
+        }
+    }
+    Ordering::Equal
+}
+
+impl Version {
+    /// Compares two versions by their parts (not by their strings)
+    pub fn compare(&self, other: &Version) -> Ordering {
+        compare_parts(&self.parts, &other.parts)
+    }
+
+    /// True for live ebuilds of a database of version `db_version`: with
+    /// PROPERTIES=live. Databases older than [`DB_VERSION_LIVE_PROPERTY`]
+    /// may lack the flag, so there a version is live as well if it ends
+    /// in a numeric component of at least four nines, as in `9999`,
+    /// `99999999`, `1.2.9999` or `9999-r1`.
+    pub fn is_live(&self, db_version: DBVersion) -> bool {
+        if self.properties_flags & PROPERTIES_LIVE != 0 {
+            return true;
+        }
+        if db_version >= DB_VERSION_LIVE_PROPERTY {
+            false
+        } else if self.parts.is_empty() {
+            parse_parts(&self.version_string).is_ok_and(|parts| live_parts(&parts))
+        } else {
+            live_parts(&self.parts)
+        }
+    }
+}
+
+impl Package {
+    /// True if any version is [live](Version::is_live) in a database of
+    /// version `db_version`
+    pub fn has_live_version(&self, db_version: DBVersion) -> bool {
+        self.versions.iter().any(|v| v.is_live(db_version))
+    }
+}
+
+/// Whether the version ends in a live marker like `9999`, ignoring the
+/// revision
+fn live_parts(parts: &[BasicPart]) -> bool {
+    parts
+        .iter()
+        .rfind(|p| !matches!(p.part_type, PartType::Revision | PartType::InterRev))
+        .filter(|p| matches!(p.part_type, PartType::First | PartType::Primary))
+        .is_some_and(|p| p.part_content.len() >= 4 && p.part_content.bytes().all(|b| b == b'9'))
+}
+
+/// Version parts parsed for a comparison and dropped afterwards; with
+/// the `smallvec` feature, up to 4 parts are stored inline, enough for
+/// 98% of the versions in a full tree
+#[cfg(feature = "smallvec")]
+pub(crate) type Parts = smallvec::SmallVec<[BasicPart; 4]>;
+#[cfg(not(feature = "smallvec"))]
+pub(crate) type Parts = Vec<BasicPart>;
+
+/// Parses a version string (e.g. `1.2.3b_rc1_p2-r1`) into its parts,
+/// following the version syntax of the Package Manager Specification.
+pub fn parse_version(s: &str) -> io::Result<Vec<BasicPart>> {
+    parse_parts(s).map(|parts| parts.into_iter().collect())
+}
+
+/// Parses a version string like [`parse_version`] into [`Parts`]
+pub(crate) fn parse_parts(s: &str) -> io::Result<Parts> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid version: {:?}", s),
+        )
+    };
+    let bytes = s.as_bytes();
+    let mut pos = 0;
+    let mut parts = Parts::new();
+
+    let digits = |pos: usize| {
+        bytes[pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut push = |part_type: PartType, from: usize, to: usize| {
+        parts.push(BasicPart {
+            part_type,
+            part_content: s[from..to].to_string(),
+        })
+    };
+
+    // Numeric components
+    let len = digits(pos);
+    if len == 0 {
+        return Err(invalid());
+    }
+    push(PartType::First, pos, pos + len);
+    pos += len;
+    while pos < bytes.len() && bytes[pos] == b'.' {
+        let len = digits(pos + 1);
+        if len == 0 {
+            return Err(invalid());
+        }
+        push(PartType::Primary, pos + 1, pos + 1 + len);
+        pos += 1 + len;
+    }
+
+    // Optional letter
+    if pos < bytes.len() && bytes[pos].is_ascii_lowercase() {
+        push(PartType::Character, pos, pos + 1);
+        pos += 1;
+    }
+
+    // Suffixes
+    while pos < bytes.len() && bytes[pos] == b'_' {
+        let rest = &s[pos + 1..];
+        let (part_type, word) = [
+            (PartType::Alpha, "alpha"),
+            (PartType::Beta, "beta"),
+            (PartType::Pre, "pre"),
+            (PartType::Rc, "rc"),
+            (PartType::Patch, "p"),
+        ]
+        .into_iter()
+        .find(|(_, word)| rest.starts_with(word))
+        .ok_or_else(invalid)?;
+        let start = pos + 1 + word.len();
+        let len = digits(start);
+        push(part_type, start, start + len);
+        pos = start + len;
+    }
+
+    // Revision, optionally followed by inter-revisions (-r1.2)
+    if s[pos..].starts_with("-r") {
+        let len = digits(pos + 2);
+        if len == 0 {
+            return Err(invalid());
+        }
+        push(PartType::Revision, pos + 2, pos + 2 + len);
+        pos += 2 + len;
+        while pos < bytes.len() && bytes[pos] == b'.' {
+            let len = digits(pos + 1);
+            if len == 0 {
+                return Err(invalid());
+            }
+            push(PartType::InterRev, pos + 1, pos + 1 + len);
+            pos += 1 + len;
+        }
+    }
+
+    if pos != bytes.len() {
+        return Err(invalid());
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_live() {
+        let old = DB_VERSION_LIVE_PROPERTY - 1;
+        let live = |s: &str| {
+            let v = Version {
+                version_string: s.to_string(),
+                parts: parse_version(s).unwrap(),
+                ..Default::default()
+            };
+            let unparsed = Version {
+                parts: Vec::new(),
+                ..v.clone()
+            };
+            assert_eq!(v.is_live(old), unparsed.is_live(old), "{}", s);
+            // Current databases have the flag for every live version
+            assert!(!v.is_live(DB_VERSION_LIVE_PROPERTY), "{}", s);
+            v.is_live(old)
+        };
+        for s in ["9999", "99999999", "1.2.9999", "9999-r1"] {
+            assert!(live(s), "{} is live", s);
+        }
+        // A patch release of 2.9999 is no live version
+        for s in [
+            "2016",
+            "999",
+            "19999",
+            "9999.1",
+            "1.99990",
+            "1.2.3",
+            "2.9999_p1",
+        ] {
+            assert!(!live(s), "{} is not live", s);
+        }
+
+        // The flag is enough
+        let v = Version {
+            version_string: "1.0".to_string(),
+            properties_flags: PROPERTIES_LIVE,
+            ..Default::default()
+        };
+        assert!(v.is_live(old));
+        assert!(v.is_live(DB_VERSION_LIVE_PROPERTY));
+        let mut package = Package {
+            versions: vec![v],
+            ..Default::default()
+        };
+        assert!(package.has_live_version(DB_VERSION_LIVE_PROPERTY));
+        package.versions[0].properties_flags = 0;
+        assert!(!package.has_live_version(old));
+    }
+
+    fn part(part_type: PartType, content: &str) -> BasicPart {
+        BasicPart {
+            part_type,
+            part_content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_compare_parts() {
+        let v1_0 = vec![part(PartType::First, "1"), part(PartType::Primary, "0")];
+        let v1_0_1 = vec![
+            part(PartType::First, "1"),
+            part(PartType::Primary, "0"),
+            part(PartType::Primary, "1"),
+        ];
+        let v1_0_rc1 = vec![
+            part(PartType::First, "1"),
+            part(PartType::Primary, "0"),
+            part(PartType::Rc, "1"),
+        ];
+        let v1_0_r0 = vec![
+            part(PartType::First, "1"),
+            part(PartType::Primary, "0"),
+            part(PartType::Revision, "0"),
+        ];
+        let v1_0_p1 = vec![
+            part(PartType::First, "1"),
+            part(PartType::Primary, "0"),
+            part(PartType::Patch, "1"),
+        ];
+        let v1_10 = vec![part(PartType::First, "1"), part(PartType::Primary, "10")];
+        let v1_09 = vec![part(PartType::First, "1"), part(PartType::Primary, "09")];
+
+        assert_eq!(compare_parts(&v1_0, &v1_0_1), Ordering::Less);
+        assert_eq!(compare_parts(&v1_0, &v1_0_rc1), Ordering::Greater);
+        assert_eq!(compare_parts(&v1_0, &v1_0_r0), Ordering::Equal);
+        assert_eq!(compare_parts(&v1_0, &v1_0_p1), Ordering::Less);
+        assert_eq!(compare_parts(&v1_0_1, &v1_10), Ordering::Less);
+        assert_eq!(compare_parts(&v1_09, &v1_10), Ordering::Less);
+    }
+
+    #[test]
+    fn test_compare_numeric_long() {
+        assert_eq!(
+            compare_numeric("20240101000000000000", "3"),
+            Ordering::Greater
+        );
+        assert_eq!(compare_numeric("", "0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        for s in [
+            "0",
+            "1.2.3",
+            "9999",
+            "1.2.3b",
+            "3.13.5_p1",
+            "1.0_alpha",
+            "2.0_rc1_p20240101",
+            "2.7.18_p16-r2",
+            "1.0-r1.2",
+        ] {
+            let parts = parse_version(s).unwrap();
+            let v = Version {
+                parts,
+                ..Default::default()
+            };
+            assert_eq!(v.get_full_version(), s);
+        }
+        let parts = parse_version("1.2b_beta3-r1").unwrap();
+        let types: Vec<PartType> = parts.iter().map(|p| p.part_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                PartType::First,
+                PartType::Primary,
+                PartType::Character,
+                PartType::Beta,
+                PartType::Revision
+            ]
+        );
+
+        for s in [
+            "",
+            "a",
+            "1.",
+            "1..2",
+            "1.0_gamma",
+            "1.0-r",
+            "1.0-x",
+            "1.0ab",
+        ] {
+            assert!(parse_version(s).is_err(), "{:?} should be invalid", s);
+        }
+    }
+}
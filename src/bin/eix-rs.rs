@@ -0,0 +1,291 @@
+//! A small command-line front end to the library (feature `cli`)
+//!
+//! Exit codes: 0 on success, 1 if nothing matched or the databases
+//! differ, 2 on usage and I/O errors.
+
+use eix::stats::DEFAULT_TOP_CATEGORIES;
+use eix::{
+    ColorChoice, ColorFormatter, ColorScheme, DB_VERSION_CURRENT, DBHeader, Database,
+    DatabaseStats, JsonFormat, JsonOptions, Package, PackageReader, RenderOptions, eix_to_json,
+};
+use regex::Regex;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: eix-rs [OPTIONS] <COMMAND>
+
+Commands:
+  search <REGEX>       List the packages whose category/name matches REGEX
+  show <CAT/NAME>      Show a package and all its versions
+  stats                Count packages, versions and masks
+  json [--ndjson]      Write all packages as JSON, or one per line
+  diff <OLD> <NEW>     List the packages changed between two databases
+
+Options:
+  --file <PATH>        The database to read; default: $EIX_CACHEFILE or
+                       /var/cache/eix/portage.eix
+  --arch <ARCH>        The architecture versions are colored for [default: amd64]
+  --color <WHEN>       auto, always or never [default: auto]
+  -h, --help           Print this help
+  -V, --version        Print the version
+";
+
+/// A failure with its exit code
+enum Error {
+    Usage(String),
+    Io(String, io::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io("Error writing output".to_string(), e)
+    }
+}
+
+struct Args {
+    file: Option<String>,
+    arch: String,
+    color: ColorChoice,
+    ndjson: bool,
+    /// The command and its operands
+    positional: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, Error> {
+    let mut parsed = Args {
+        file: None,
+        arch: "amd64".to_string(),
+        color: ColorChoice::Auto,
+        ndjson: false,
+        positional: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        // `--option=value` is accepted as well
+        let (option, inline) = match arg.split_once('=') {
+            Some((option, v)) if option.starts_with("--") => {
+                (option.to_string(), Some(v.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut take = || match inline.clone() {
+            Some(v) => Ok(v),
+            None => args
+                .next()
+                .ok_or_else(|| Error::Usage(format!("{} needs a value", option))),
+        };
+        match option.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return Ok(None);
+            }
+            "-V" | "--version" => {
+                println!("eix-rs {}", env!("CARGO_PKG_VERSION"));
+                return Ok(None);
+            }
+            "--file" => parsed.file = Some(take()?),
+            "--arch" => parsed.arch = take()?,
+            "--color" => {
+                parsed.color = match take()?.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    other => {
+                        return Err(Error::Usage(format!(
+                            "Invalid value for --color: {} (expected auto, always or never)",
+                            other
+                        )));
+                    }
+                }
+            }
+            "--ndjson" => parsed.ndjson = true,
+            "--" => parsed.positional.extend(args.by_ref()),
+            _ if option.starts_with('-') && option.len() > 1 => {
+                return Err(Error::Usage(format!("Unknown option {}", option)));
+            }
+            _ => parsed.positional.push(arg),
+        }
+    }
+    Ok(Some(parsed))
+}
+
+/// Opens the database at `path`, or the default one
+fn open(path: Option<&str>) -> Result<(Database, PathBuf), Error> {
+    match path {
+        Some(path) => Ok((
+            Database::open_read(path)
+                .map_err(|e| Error::Io(format!("Error opening {}", path), e))?,
+            path.into(),
+        )),
+        None => Database::open_default()
+            .map_err(|e| Error::Io("Error opening eix database".to_string(), e)),
+    }
+}
+
+/// Reads the header and all packages of the database at `path`, or of
+/// the default one
+fn load(path: Option<&str>) -> Result<(DBHeader, Vec<Package>), Error> {
+    let (mut db, path) = open(path)?;
+    let read = |e| Error::Io(format!("Error reading {}", path.display()), e);
+    let header = db.read_header(DB_VERSION_CURRENT).map_err(read)?;
+    let packages = PackageReader::new(db, header.clone())
+        .read_all()
+        .map_err(read)?
+        .into_inner();
+    Ok((header, packages))
+}
+
+/// Checks the number of operands of `command`
+fn operands<'a>(args: &'a Args, command: &str, names: &[&str]) -> Result<&'a [String], Error> {
+    let operands = &args.positional[1..];
+    if operands.len() != names.len() {
+        let usage = format!("eix-rs {} {}", command, names.join(" "));
+        return Err(Error::Usage(format!("Usage: {}", usage.trim_end())));
+    }
+    Ok(operands)
+}
+
+fn search(args: &Args, out: &mut impl Write) -> Result<ExitCode, Error> {
+    let pattern = &operands(args, "search", &["<REGEX>"])?[0];
+    let regex = Regex::new(pattern).map_err(|e| Error::Usage(format!("Invalid regex: {}", e)))?;
+    let (_, packages) = load(args.file.as_deref())?;
+    let formatter = ColorFormatter::new(args.color);
+    let scheme = ColorScheme::default();
+    let mut found = 0;
+    for package in &packages {
+        if regex.is_match(&format!("{}/{}", package.category, package.name)) {
+            if found > 0 {
+                writeln!(out)?;
+            }
+            write!(
+                out,
+                "{}",
+                formatter.format_package(package, &args.arch, &scheme)
+            )?;
+            found += 1;
+        }
+    }
+    if found == 0 {
+        eprintln!("No matches found");
+        return Ok(ExitCode::from(1));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn show(args: &Args, out: &mut impl Write) -> Result<ExitCode, Error> {
+    let atom = &operands(args, "show", &["<CAT/NAME>"])?[0];
+    let Some((category, name)) = atom.split_once('/') else {
+        return Err(Error::Usage(format!(
+            "Expected category/name, got {}",
+            atom
+        )));
+    };
+    let (header, packages) = load(args.file.as_deref())?;
+    let formatter = ColorFormatter::new(args.color);
+    let scheme = ColorScheme::default();
+    let mut found = false;
+    for package in packages
+        .iter()
+        .filter(|p| p.category == category && p.name == name)
+    {
+        write!(
+            out,
+            "{}",
+            formatter.format_package(package, &args.arch, &scheme)
+        )?;
+        writeln!(out, "     License: {}", package.licenses)?;
+        for version in &package.versions {
+            writeln!(out, "       {}", version.describe(Some(&header)))?;
+        }
+        found = true;
+    }
+    if !found {
+        eprintln!("{} not found", atom);
+        return Ok(ExitCode::from(1));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn stats(args: &Args, out: &mut impl Write) -> Result<ExitCode, Error> {
+    operands(args, "stats", &[])?;
+    let (header, packages) = load(args.file.as_deref())?;
+    let stats =
+        DatabaseStats::compute_for_version(&packages, DEFAULT_TOP_CATEGORIES, header.version);
+    write!(out, "{}", stats)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn json(args: &Args, out: &mut impl Write) -> Result<ExitCode, Error> {
+    operands(args, "json", &[])?;
+    let (db, path) = open(args.file.as_deref())?;
+    let options = JsonOptions {
+        format: if args.ndjson {
+            JsonFormat::Ndjson
+        } else {
+            JsonFormat::Pretty
+        },
+        ..Default::default()
+    };
+    eix_to_json(db.into_inner(), &mut *out, options)
+        .map_err(|e| Error::Io(format!("Error converting {}", path.display()), e))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn diff(args: &Args, out: &mut impl Write) -> Result<ExitCode, Error> {
+    let paths = operands(args, "diff", &["<OLD>", "<NEW>"])?;
+    let (_, old) = load(Some(&paths[0]))?;
+    let (_, new) = load(Some(&paths[1]))?;
+    let diff = eix::diff(&old, &new);
+    let options = RenderOptions {
+        color: args.color.enabled(),
+        ..Default::default()
+    };
+    diff.render(&mut *out, options)?;
+    Ok(if diff.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    })
+}
+
+fn run() -> Result<ExitCode, Error> {
+    let Some(args) = parse_args(std::env::args().skip(1))? else {
+        return Ok(ExitCode::SUCCESS);
+    };
+    if args.ndjson && args.positional.first().is_some_and(|c| c != "json") {
+        return Err(Error::Usage("--ndjson only applies to json".to_string()));
+    }
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let code = match args.positional.first().map(String::as_str) {
+        Some("search") => search(&args, &mut out)?,
+        Some("show") => show(&args, &mut out)?,
+        Some("stats") => stats(&args, &mut out)?,
+        Some("json") => json(&args, &mut out)?,
+        Some("diff") => diff(&args, &mut out)?,
+        Some(other) => return Err(Error::Usage(format!("Unknown command {}", other))),
+        None => return Err(Error::Usage("No command given".to_string())),
+    };
+    out.flush()?;
+    Ok(code)
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        // Output piped into e.g. `head` was closed early
+        Err(Error::Io(_, e)) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(Error::Usage(message)) => {
+            eprintln!(
+                "eix-rs: {}\nTry 'eix-rs --help' for more information.",
+                message
+            );
+            ExitCode::from(2)
+        }
+        Err(Error::Io(context, e)) => {
+            eprintln!("eix-rs: {}: {}", context, e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Parsed snapshots of a database file
+//!
+//! [`ParsedDb`] holds everything read from a file. [`CachedDb`] keeps a
+//! snapshot of it and re-parses the file once `eix-update` replaced it,
+//! so long-running programs see new results without a restart.
+
+use crate::{DBHeader, Database, DatabaseInfo, OpenOptions, Package, PackageReader};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock, TryLockError};
+use std::time::{Duration, Instant};
+
+/// Header, packages and file information of a completely read database
+#[derive(Debug, Clone)]
+pub struct ParsedDb {
+    pub info: DatabaseInfo,
+    pub header: DBHeader,
+    pub packages: Vec<Package>,
+}
+
+impl ParsedDb {
+    /// Reads all packages of the database at `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::load_with(path, OpenOptions::default())
+    }
+
+    /// Like [`load`](Self::load), reading with `options`
+    pub fn load_with<P: AsRef<Path>>(path: P, options: OpenOptions) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut db = Database::open_read_with(path, options)?;
+        let header = db.read_header(0)?;
+        let info = DatabaseInfo::from_header(path, &header)?;
+        let mut reader = PackageReader::new(db, header.clone());
+        let mut packages = Vec::new();
+        while reader.next_category()? {
+            while let Some(pkg) = reader.read_package()? {
+                packages.push(pkg);
+            }
+        }
+        Ok(ParsedDb {
+            info,
+            header,
+            packages,
+        })
+    }
+
+    /// Whether the categories of the packages are in sorted order, as
+    /// eix writes them; see [`ReadWarning::UnsortedCategory`](crate::ReadWarning::UnsortedCategory)
+    pub fn is_category_sorted(&self) -> bool {
+        self.packages.is_sorted_by(|a, b| a.category <= b.category)
+    }
+}
+
+/// Default for [`CachedDb::with_min_stat_interval`]
+pub const DEFAULT_STAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A database snapshot which is re-parsed when the file changes.
+///
+/// [`CachedDb::get`] stats the file at most once per stat interval and
+/// re-parses it if its size or modification time differ from the
+/// snapshot. Only one thread re-parses; the others keep getting the
+/// previous snapshot meanwhile.
+#[derive(Debug)]
+pub struct CachedDb {
+    path: PathBuf,
+    min_stat_interval: Duration,
+    snapshot: RwLock<Arc<ParsedDb>>,
+    /// Held while checking the file; holds the time of the last check
+    last_check: Mutex<Instant>,
+    invalidated: AtomicBool,
+}
+
+impl CachedDb {
+    /// Parses the database at `path`
+    pub fn open<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        let parsed = ParsedDb::load(&path)?;
+        Ok(CachedDb {
+            path,
+            min_stat_interval: DEFAULT_STAT_INTERVAL,
+            snapshot: RwLock::new(Arc::new(parsed)),
+            last_check: Mutex::new(Instant::now()),
+            invalidated: AtomicBool::new(false),
+        })
+    }
+
+    /// Sets the minimal time between two checks of the file
+    pub fn with_min_stat_interval(mut self, interval: Duration) -> Self {
+        self.min_stat_interval = interval;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the current snapshot without checking the file
+    pub fn snapshot(&self) -> Arc<ParsedDb> {
+        self.snapshot
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the current snapshot, re-parsing the file first if it
+    /// changed. If another thread is re-parsing or parsing fails, the
+    /// previous snapshot is returned.
+    pub fn get(&self) -> Arc<ParsedDb> {
+        let last_check = match self.last_check.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        };
+        if let Some(mut last_check) = last_check
+            && (self.invalidated.load(Ordering::Acquire)
+                || last_check.elapsed() >= self.min_stat_interval)
+        {
+            let _ = self.reload(&mut last_check);
+        }
+        self.snapshot()
+    }
+
+    /// Checks the file now, regardless of the stat interval.
+    /// Returns whether a new snapshot was parsed.
+    pub fn refresh(&self) -> io::Result<bool> {
+        let mut last_check = self
+            .last_check
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.reload(&mut last_check)
+    }
+
+    /// Forces a re-parse on the next [`get`](Self::get), even if the
+    /// file looks unchanged
+    pub fn invalidate(&self) {
+        self.invalidated.store(true, Ordering::Release);
+    }
+
+    fn reload(&self, last_check: &mut Instant) -> io::Result<bool> {
+        *last_check = Instant::now();
+        let invalidated = self.invalidated.swap(false, Ordering::AcqRel);
+        if !invalidated {
+            let metadata = fs::metadata(&self.path)?;
+            let current = self.snapshot();
+            if metadata.len() == current.info.file_size
+                && metadata.modified()? == current.info.modified
+            {
+                return Ok(false);
+            }
+        }
+        match ParsedDb::load(&self.path) {
+            Ok(parsed) => {
+                *self
+                    .snapshot
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner) = Arc::new(parsed);
+                Ok(true)
+            }
+            Err(e) => {
+                if invalidated {
+                    self.invalidated.store(true, Ordering::Release);
+                }
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Cancelling long-running operations
+//!
+//! A [`CancelToken`] is shared between the thread running a scan and
+//! the threads which may abort it, e.g. a GUI thread when the user types
+//! a new query. Operations check it once per package, so cancelling
+//! takes effect promptly at a negligible cost:
+//!
+//! ```
+//! use eix::{CancelToken, EixTestBuilder, PackageReader};
+//!
+//! let token = CancelToken::new();
+//! let mut db = EixTestBuilder::new()
+//!     .category("app-misc")
+//!     .package("screen", |p| p.version("4.9.1", |v| v))
+//!     .build_database();
+//! let header = db.read_header(0).unwrap();
+//! let mut reader = PackageReader::new(db, header).with_cancel(token.clone());
+//! token.cancel();
+//! let outcome = reader.read_all().unwrap();
+//! assert!(outcome.is_cancelled());
+//! assert!(outcome.into_inner().is_empty());
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A cancellation flag; clones share the flag
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks all operations checking this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Fails with [`Cancelled`] once the token is cancelled
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Uses an existing flag, e.g. one shared with other code
+impl From<Arc<AtomicBool>> for CancelToken {
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        CancelToken(flag)
+    }
+}
+
+/// The error of an operation stopped by a [`CancelToken`]. Operations
+/// returning `io::Result` wrap it, see [`is_cancelled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+impl From<Cancelled> for io::Error {
+    fn from(cancelled: Cancelled) -> Self {
+        io::Error::other(cancelled)
+    }
+}
+
+/// Whether `err` reports a cancelled operation rather than a failure
+pub fn is_cancelled(err: &io::Error) -> bool {
+    err.get_ref().is_some_and(|e| e.is::<Cancelled>())
+}
+
+/// The result of an operation which keeps what it computed when it is
+/// cancelled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome<T> {
+    Complete(T),
+    /// Stopped early; holds the result so far
+    Cancelled(T),
+}
+
+impl<T> Outcome<T> {
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Outcome::Cancelled(_))
+    }
+
+    /// The result, complete or not
+    pub fn into_inner(self) -> T {
+        match self {
+            Outcome::Complete(value) | Outcome::Cancelled(value) => value,
+        }
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Outcome<U> {
+        match self {
+            Outcome::Complete(value) => Outcome::Complete(f(value)),
+            Outcome::Cancelled(value) => Outcome::Cancelled(f(value)),
+        }
+    }
+}
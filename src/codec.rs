@@ -0,0 +1,240 @@
+//! Encoding of the primitive values of the eix format
+//!
+//! Pure functions over byte slices, independent of any reader or
+//! writer. Decoding functions return the value and the number of bytes
+//! consumed. [`Decoder`](crate::Decoder) and
+//! [`EixWriter`](crate::EixWriter) are built on top of these. The
+//! module only needs `alloc`; the conversion into `std::io::Error`
+//! requires the `std` feature.
+
+use crate::{BasicPart, MAGICNUMCHAR, PartType};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The longest encoding of a `u64`: eight escape bytes, the 0x00
+/// standing for a leading 0xFF and the remaining seven bytes
+pub const MAX_NUM_LEN: usize = 16;
+
+/// Error while decoding a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The data ends in the middle of a value
+    UnexpectedEnd,
+    /// A number does not fit into 64 bits
+    NumberTooLarge,
+    /// A string or version part is not valid UTF-8
+    InvalidUtf8,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedEnd => write!(f, "Unexpected end of data"),
+            Error::NumberTooLarge => write!(f, "Number too large"),
+            Error::InvalidUtf8 => write!(f, "Invalid UTF-8"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        let kind = match e {
+            Error::UnexpectedEnd => std::io::ErrorKind::UnexpectedEof,
+            Error::NumberTooLarge | Error::InvalidUtf8 => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Decodes a number in eix format (variable length)
+///
+/// Format:
+/// - Values 0-254: directly as one byte
+/// - Value 255 (0xFF = MAGICNUMCHAR): escape for multi-byte
+/// - After 0xFF: further 0xFF = more bytes follow
+/// - After 0xFF: 0x00 = means the value is 255 itself
+/// - After 0xFF: other value = start of the multi-byte number
+pub fn decode_num(data: &[u8]) -> Result<(u64, usize)> {
+    let &first = data.first().ok_or(Error::UnexpectedEnd)?;
+
+    // Most common case: number < 255
+    if first != MAGICNUMCHAR {
+        return Ok((first as u64, 1));
+    }
+
+    // Count further MAGICNUMCHAR
+    let escapes = data.iter().take_while(|&&b| b == MAGICNUMCHAR).count();
+    // Even with an escaped leading MAGICNUMCHAR, more than 8 bytes follow
+    if escapes > 8 {
+        return Err(Error::NumberTooLarge);
+    }
+    let &lead = data.get(escapes).ok_or(Error::UnexpectedEnd)?;
+    let (mut result, remaining) = if lead != 0 {
+        (lead as u64, escapes)
+    } else {
+        // Leading 0 after MAGICNUMCHAR means MAGICNUMCHAR itself
+        (MAGICNUMCHAR as u64, escapes - 1)
+    };
+    if remaining >= 8 {
+        return Err(Error::NumberTooLarge);
+    }
+
+    // Remaining bytes
+    let start = escapes + 1;
+    let bytes = data
+        .get(start..start + remaining)
+        .ok_or(Error::UnexpectedEnd)?;
+    for &byte in bytes {
+        result = (result << 8) | byte as u64;
+    }
+    Ok((result, start + remaining))
+}
+
+/// Appends the eix encoding of `value`
+pub fn encode_num(value: u64, out: &mut Vec<u8>) {
+    if value < MAGICNUMCHAR as u64 {
+        out.push(value as u8);
+        return;
+    }
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&b| b == 0).count();
+    let bytes = &bytes[skip..];
+    if bytes[0] == MAGICNUMCHAR {
+        // A leading 0xFF byte is escaped as 0xFF 0x00
+        out.extend(core::iter::repeat_n(MAGICNUMCHAR, bytes.len()));
+        out.push(0);
+        out.extend_from_slice(&bytes[1..]);
+    } else {
+        out.extend(core::iter::repeat_n(MAGICNUMCHAR, bytes.len() - 1));
+        out.extend_from_slice(bytes);
+    }
+}
+
+/// Decodes a string (length + data)
+pub fn decode_string(data: &[u8]) -> Result<(&str, usize)> {
+    let (len, start) = decode_num(data)?;
+    let content = usize::try_from(len)
+        .ok()
+        .and_then(|len| data.get(start..start.checked_add(len)?))
+        .ok_or(Error::UnexpectedEnd)?;
+    let s = core::str::from_utf8(content).map_err(|_| Error::InvalidUtf8)?;
+    Ok((s, start + content.len()))
+}
+
+/// Appends a string (length + data)
+pub fn encode_string(s: &str, out: &mut Vec<u8>) {
+    encode_num(s.len() as u64, out);
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Splits the number preceding a version part into type and content length
+pub fn part_header(num: u64) -> (PartType, u64) {
+    (PartType::from_u64(num % 32), num / 32)
+}
+
+/// Decodes a single part of a version
+pub fn decode_part(data: &[u8]) -> Result<(BasicPart, usize)> {
+    let (num, start) = decode_num(data)?;
+    let (part_type, len) = part_header(num);
+    let content = usize::try_from(len)
+        .ok()
+        .and_then(|len| data.get(start..start.checked_add(len)?))
+        .ok_or(Error::UnexpectedEnd)?;
+    let part_content = core::str::from_utf8(content).map_err(|_| Error::InvalidUtf8)?;
+    let part = BasicPart {
+        part_type,
+        part_content: part_content.to_string(),
+    };
+    Ok((part, start + content.len()))
+}
+
+/// Appends a single part of a version
+pub fn encode_part(part: &BasicPart, out: &mut Vec<u8>) {
+    let len = part.part_content.len() as u64;
+    encode_num(len * 32 + part.part_type as u64, out);
+    out.extend_from_slice(part.part_content.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn encoded(value: u64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode_num(value, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn test_max_num_len() {
+        assert_eq!(encoded(u64::MAX).len(), MAX_NUM_LEN);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode_num(&[]), Err(Error::UnexpectedEnd));
+        assert_eq!(decode_num(&[0xFF]), Err(Error::UnexpectedEnd));
+        assert_eq!(decode_num(&[0xFF, 0x01]), Err(Error::UnexpectedEnd));
+        assert_eq!(decode_num(&[0xFF; 8]), Err(Error::UnexpectedEnd));
+        assert_eq!(decode_num(&[0xFF; 9]), Err(Error::NumberTooLarge));
+        let mut too_large = vec![0xFF; 9];
+        too_large.extend([0x01; 9]);
+        assert_eq!(decode_num(&too_large), Err(Error::NumberTooLarge));
+        assert_eq!(decode_string(&[3, b'a', b'b']), Err(Error::UnexpectedEnd));
+        assert_eq!(decode_string(&[1, 0xC3]), Err(Error::InvalidUtf8));
+        assert_eq!(decode_part(&[2 * 32 + 9, b'1']), Err(Error::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_decode_trailing_data() {
+        assert_eq!(decode_num(&[0x05, 0x06]), Ok((5, 1)));
+        assert_eq!(decode_string(&[2, b'o', b'k', 0x00]), Ok(("ok", 3)));
+        let (part, len) = decode_part(&[32 + 10, b'5', 0x00]).unwrap();
+        assert_eq!(part.part_type, PartType::First);
+        assert_eq!(part.part_content, "5");
+        assert_eq!(len, 2);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_num_roundtrip(value: u64, trailing: Vec<u8>) {
+            let mut buf = encoded(value);
+            let len = buf.len();
+            prop_assert!(len <= MAX_NUM_LEN);
+            buf.extend(trailing);
+            prop_assert_eq!(decode_num(&buf), Ok((value, len)));
+        }
+
+        #[test]
+        fn prop_string_roundtrip(s: String) {
+            let mut buf = Vec::new();
+            encode_string(&s, &mut buf);
+            prop_assert_eq!(decode_string(&buf), Ok((s.as_str(), buf.len())));
+        }
+
+        #[test]
+        fn prop_part_roundtrip(part_type in 0u64..=10, content in "[0-9a-z]{0,12}") {
+            let part = BasicPart {
+                part_type: PartType::from_u64(part_type),
+                part_content: content,
+            };
+            let mut buf = Vec::new();
+            encode_part(&part, &mut buf);
+            prop_assert_eq!(decode_part(&buf), Ok((part, buf.len())));
+        }
+
+        #[test]
+        fn prop_decode_never_panics(data: Vec<u8>) {
+            let _ = decode_num(&data);
+            let _ = decode_string(&data);
+            let _ = decode_part(&data);
+        }
+    }
+}
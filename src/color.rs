@@ -0,0 +1,178 @@
+//! ANSI-colored output like eix's (feature `color`)
+//!
+//! Versions are colored by their state for one architecture: stable,
+//! testing, masked or without keyword. The state is also marked in the
+//! text (`~`, `[M]`, `**`), so output without colors loses nothing.
+
+use crate::{InstalledIndex, KeywordState, Package, Version};
+use std::io::IsTerminal;
+
+const RESET: &str = "\x1b[0m";
+
+/// When to use colors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colors if stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to use colors for output to stdout
+    pub fn enabled(self) -> bool {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        self.enabled_with(std::io::stdout().is_terminal(), no_color)
+    }
+
+    fn enabled_with(self, is_terminal: bool, no_color: bool) -> bool {
+        match self {
+            ColorChoice::Auto => is_terminal && !no_color,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Escape sequences for the parts of the output; an empty string leaves
+/// the part uncolored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorScheme {
+    /// `category/name`
+    pub name: String,
+    pub stable: String,
+    pub testing: String,
+    /// Masked by package.mask or the profile
+    pub masked: String,
+    /// No keyword or `-arch`
+    pub missing: String,
+    /// The `[I]` marker of installed versions
+    pub installed: String,
+    /// The `::repo` suffix
+    pub repo: String,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        ColorScheme {
+            name: "\x1b[1;32m".to_string(),
+            stable: "\x1b[32m".to_string(),
+            testing: "\x1b[33m".to_string(),
+            masked: "\x1b[31m".to_string(),
+            missing: "\x1b[31m".to_string(),
+            installed: "\x1b[1;34m".to_string(),
+            repo: "\x1b[2m".to_string(),
+        }
+    }
+}
+
+/// Formats packages like `eix` does on a terminal
+#[derive(Debug, Clone, Copy)]
+pub struct ColorFormatter<'a> {
+    color: bool,
+    installed: Option<&'a InstalledIndex>,
+}
+
+impl<'a> ColorFormatter<'a> {
+    /// The choice is resolved once, see [`ColorChoice::enabled`]
+    pub fn new(color: ColorChoice) -> Self {
+        ColorFormatter {
+            color: color.enabled(),
+            installed: None,
+        }
+    }
+
+    /// Marks the installed versions with `[I]`
+    pub fn installed(mut self, index: &'a InstalledIndex) -> Self {
+        self.installed = Some(index);
+        self
+    }
+
+    /// Whether escape sequences are written
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
+    /// ```text
+    /// * app-misc/screen
+    ///      Available versions: 4.9.1-r1::gentoo ~5.0.1::gentoo **9999::gentoo
+    ///      Homepage: https://www.gnu.org/software/screen/
+    ///      Description: screen manager with VT100/ANSI terminal emulation
+    /// ```
+    pub fn format_package(&self, package: &Package, arch: &str, scheme: &ColorScheme) -> String {
+        let mut out = String::from("* ");
+        self.paint(
+            &mut out,
+            &scheme.name,
+            &format!("{}/{}", package.category, package.name),
+        );
+        out.push_str("\n     Available versions:");
+        for v in &package.versions {
+            out.push(' ');
+            self.format_version(&mut out, package, v, arch, scheme);
+        }
+        out.push_str("\n     Homepage: ");
+        out.push_str(&package.homepage);
+        out.push_str("\n     Description: ");
+        out.push_str(&package.description);
+        out.push('\n');
+        out
+    }
+
+    fn format_version(
+        &self,
+        out: &mut String,
+        package: &Package,
+        v: &Version,
+        arch: &str,
+        scheme: &ColorScheme,
+    ) {
+        if self
+            .installed
+            .is_some_and(|index| v.is_installed_in(index, package))
+        {
+            self.paint(out, &scheme.installed, "[I]");
+        }
+        let (style, marker) = if v.is_masked() {
+            (&scheme.masked, "[M]")
+        } else {
+            match v.keyword_state(arch) {
+                KeywordState::Stable => (&scheme.stable, ""),
+                KeywordState::Testing => (&scheme.testing, "~"),
+                KeywordState::Broken
+                | KeywordState::MaskedAll
+                | KeywordState::Missing
+                | KeywordState::NoKeywords => (&scheme.missing, "**"),
+            }
+        };
+        self.paint(out, style, &format!("{}{}", marker, v.version_string));
+        if !v.reponame.is_empty() {
+            self.paint(out, &scheme.repo, &format!("::{}", v.reponame));
+        }
+    }
+
+    fn paint(&self, out: &mut String, style: &str, text: &str) {
+        if self.color && !style.is_empty() {
+            out.push_str(style);
+            out.push_str(text);
+            out.push_str(RESET);
+        } else {
+            out.push_str(text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_choice() {
+        assert!(ColorChoice::Auto.enabled_with(true, false));
+        assert!(!ColorChoice::Auto.enabled_with(false, false));
+        assert!(!ColorChoice::Auto.enabled_with(true, true));
+        assert!(ColorChoice::Always.enabled_with(false, true));
+        assert!(!ColorChoice::Never.enabled_with(true, false));
+    }
+}
@@ -0,0 +1,378 @@
+//! Field by field comparison of package lists
+//!
+//! Unlike [`diff`](crate::diff()), which reports the changes between two
+//! databases a user cares about, [`compare_packages`] lists every
+//! differing field with its path, e.g. `dev-lang/rust/1.78.0.keywords`,
+//! which makes it suited for tests and consistency checks.
+
+use crate::{Depend, Package, PackageKey, Version};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+
+/// Fields skipped by [`compare_packages`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompareOptions {
+    /// Skip the priority of versions, which depends on the order of
+    /// the overlays
+    pub ignore_priority: bool,
+    /// Skip the byte ranges of packages and versions
+    pub ignore_spans: bool,
+    /// Skip the parsed version parts, e.g. for packages read from JSON,
+    /// which only holds the version strings. The version strings are
+    /// always compared.
+    pub ignore_parts: bool,
+}
+
+/// What differs at the path of a [`Difference`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DifferenceKind {
+    /// The package is only in the first list
+    MissingPackage,
+    /// The package is only in the second list
+    ExtraPackage,
+    /// The version is only in the first list
+    MissingVersion,
+    /// The version is only in the second list
+    ExtraVersion,
+    /// The field has different values, formatted with `Debug`
+    Field { a: String, b: String },
+}
+
+/// A difference found by [`compare_packages`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Difference {
+    /// `category/name`, followed by `/version` and `.field` where
+    /// applicable. A version occurring more than once in a package is
+    /// numbered from the second occurrence on, e.g. `1.0#2`.
+    pub path: String,
+    #[serde(flatten)]
+    pub kind: DifferenceKind,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DifferenceKind::MissingPackage | DifferenceKind::MissingVersion => {
+                write!(f, "{}: only in the first list", self.path)
+            }
+            DifferenceKind::ExtraPackage | DifferenceKind::ExtraVersion => {
+                write!(f, "{}: only in the second list", self.path)
+            }
+            DifferenceKind::Field { a, b } => write!(f, "{}: {} != {}", self.path, a, b),
+        }
+    }
+}
+
+struct Comparison {
+    options: CompareOptions,
+    differences: Vec<Difference>,
+}
+
+impl Comparison {
+    fn push(&mut self, path: String, kind: DifferenceKind) {
+        self.differences.push(Difference { path, kind });
+    }
+
+    fn field<T: PartialEq + Debug>(&mut self, path: &str, name: &str, a: &T, b: &T) {
+        if a != b {
+            let kind = DifferenceKind::Field {
+                a: format!("{:?}", a),
+                b: format!("{:?}", b),
+            };
+            self.push(format!("{}.{}", path, name), kind);
+        }
+    }
+
+    fn package(&mut self, atom: &str, a: &Package, b: &Package) {
+        self.field(atom, "description", &a.description, &b.description);
+        self.field(atom, "homepage", &a.homepage, &b.homepage);
+        self.field(atom, "licenses", &a.licenses, &b.licenses);
+        if !self.options.ignore_spans {
+            self.field(atom, "span", &a.span, &b.span);
+        }
+
+        let b_versions: HashMap<String, &Version> = keyed_versions(b).collect();
+        let mut seen = HashSet::new();
+        for (key, va) in keyed_versions(a) {
+            let path = format!("{}/{}", atom, key);
+            match b_versions.get(&key) {
+                Some(vb) => self.version(&path, va, vb),
+                None => self.push(path, DifferenceKind::MissingVersion),
+            }
+            seen.insert(key);
+        }
+        for (key, _) in keyed_versions(b) {
+            if !seen.contains(&key) {
+                self.push(format!("{}/{}", atom, key), DifferenceKind::ExtraVersion);
+            }
+        }
+    }
+
+    fn version(&mut self, path: &str, a: &Version, b: &Version) {
+        if !self.options.ignore_parts {
+            self.field(path, "parts", &a.parts, &b.parts);
+        }
+        self.field(path, "eapi", &a.eapi, &b.eapi);
+        self.field(path, "mask_flags", &a.mask_flags, &b.mask_flags);
+        self.field(
+            path,
+            "properties_flags",
+            &a.properties_flags,
+            &b.properties_flags,
+        );
+        self.field(path, "restrict_flags", &a.restrict_flags, &b.restrict_flags);
+        self.field(path, "keywords", &a.keywords, &b.keywords);
+        self.field(path, "slot", &a.slot, &b.slot);
+        self.field(path, "overlay_key", &a.overlay_key, &b.overlay_key);
+        self.field(path, "reponame", &a.reponame, &b.reponame);
+        if !self.options.ignore_priority {
+            self.field(path, "priority", &a.priority, &b.priority);
+        }
+        self.field(path, "iuse", &a.iuse, &b.iuse);
+        self.field(path, "required_use", &a.required_use, &b.required_use);
+        match (a.depend(), b.depend()) {
+            (Some(da), Some(db)) => self.depend(&format!("{}.depend", path), da, db),
+            (None, None) => {}
+            (da, db) => {
+                let kind = DifferenceKind::Field {
+                    a: format!("{:?}", da),
+                    b: format!("{:?}", db),
+                };
+                self.push(format!("{}.depend", path), kind);
+            }
+        }
+        self.field(path, "src_uri", &a.src_uri, &b.src_uri);
+        if !self.options.ignore_spans {
+            self.field(path, "span", &a.span, &b.span);
+        }
+    }
+
+    fn depend(&mut self, path: &str, a: &Depend, b: &Depend) {
+        self.field(path, "depend", &a.depend, &b.depend);
+        self.field(path, "rdepend", &a.rdepend, &b.rdepend);
+        self.field(path, "pdepend", &a.pdepend, &b.pdepend);
+        self.field(path, "bdepend", &a.bdepend, &b.bdepend);
+        self.field(path, "idepend", &a.idepend, &b.idepend);
+    }
+}
+
+/// The versions of `package` keyed by version string, numbering
+/// repeated ones
+fn keyed_versions(package: &Package) -> impl Iterator<Item = (String, &Version)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    package.versions.iter().map(move |v| {
+        let count = counts.entry(v.version_string.as_str()).or_default();
+        *count += 1;
+        let key = match *count {
+            1 => v.version_string.clone(),
+            n => format!("{}#{}", v.version_string, n),
+        };
+        (key, v)
+    })
+}
+
+/// Whether `a` and `b` agree in every field compared by
+/// [`compare_packages`] except the spans
+pub(crate) fn versions_identical(a: &Version, b: &Version) -> bool {
+    let mut comparison = Comparison {
+        options: CompareOptions {
+            ignore_spans: true,
+            ..Default::default()
+        },
+        differences: Vec::new(),
+    };
+    comparison.version("", a, b);
+    comparison.differences.is_empty()
+}
+
+/// Compares two package lists, matching packages by category and name
+/// and versions by version string. The order of packages and versions
+/// is not compared.
+///
+/// The differences follow the order of `a`; versions and packages only
+/// in `b` come after those of `a` in the package or the list.
+pub fn compare_packages(a: &[Package], b: &[Package], options: CompareOptions) -> Vec<Difference> {
+    let b_by_atom: HashMap<PackageKey, &Package> = b.iter().map(|p| (p.key(), p)).collect();
+    let mut comparison = Comparison {
+        options,
+        differences: Vec::new(),
+    };
+    for pa in a {
+        let atom = pa.cp();
+        match b_by_atom.get(&pa.key()) {
+            Some(pb) => comparison.package(&atom, pa, pb),
+            None => comparison.push(atom, DifferenceKind::MissingPackage),
+        }
+    }
+    let a_atoms: HashSet<PackageKey> = a.iter().map(|p| p.key()).collect();
+    for pb in b {
+        if !a_atoms.contains(&pb.key()) {
+            comparison.push(pb.cp(), DifferenceKind::ExtraPackage);
+        }
+    }
+    comparison.differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_version;
+
+    fn version(v: &str) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            keywords: vec!["amd64".to_string()],
+            reponame: "gentoo".into(),
+            ..Default::default()
+        }
+    }
+
+    fn package(cp: &str, versions: &[&str]) -> Package {
+        let (category, name) = cp.split_once('/').unwrap();
+        Package {
+            category: category.to_string(),
+            name: name.to_string(),
+            description: "A package".to_string(),
+            versions: versions.iter().map(|v| version(v)).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn paths(differences: &[Difference]) -> Vec<&str> {
+        differences.iter().map(|d| d.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_equal() {
+        let a = vec![package("dev-lang/rust", &["1.78.0", "1.79.0"])];
+        assert!(compare_packages(&a, &a.clone(), CompareOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_missing_and_extra_packages() {
+        let a = vec![
+            package("dev-lang/rust", &["1.78.0"]),
+            package("app-misc/screen", &["4.9.1"]),
+        ];
+        let b = vec![
+            package("app-misc/tmux", &["3.4"]),
+            package("dev-lang/rust", &["1.78.0"]),
+        ];
+        let differences = compare_packages(&a, &b, CompareOptions::default());
+        assert_eq!(
+            differences,
+            [
+                Difference {
+                    path: "app-misc/screen".to_string(),
+                    kind: DifferenceKind::MissingPackage,
+                },
+                Difference {
+                    path: "app-misc/tmux".to_string(),
+                    kind: DifferenceKind::ExtraPackage,
+                },
+            ]
+        );
+        assert_eq!(
+            differences[0].to_string(),
+            "app-misc/screen: only in the first list"
+        );
+    }
+
+    #[test]
+    fn test_missing_and_extra_versions() {
+        let a = vec![package("dev-lang/rust", &["1.78.0", "1.79.0", "1.79.0"])];
+        let b = vec![package("dev-lang/rust", &["1.79.0", "1.80.0"])];
+        let differences = compare_packages(&a, &b, CompareOptions::default());
+        assert_eq!(
+            paths(&differences),
+            [
+                "dev-lang/rust/1.78.0",
+                "dev-lang/rust/1.79.0#2",
+                "dev-lang/rust/1.80.0"
+            ]
+        );
+        assert_eq!(differences[0].kind, DifferenceKind::MissingVersion);
+        assert_eq!(differences[1].kind, DifferenceKind::MissingVersion);
+        assert_eq!(differences[2].kind, DifferenceKind::ExtraVersion);
+    }
+
+    #[test]
+    fn test_field_mismatches() {
+        let a = vec![package("dev-lang/rust", &["1.78.0"])];
+        let mut b = a.clone();
+        b[0].homepage = "https://www.rust-lang.org/".to_string();
+        let v = &mut b[0].versions[0];
+        v.keywords = vec!["~amd64".to_string()];
+        v.mask_flags = 1;
+        v.depend = Some(Depend {
+            rdepend: vec!["sys-libs/zlib".to_string()],
+            ..Default::default()
+        });
+        let differences = compare_packages(&a, &b, CompareOptions::default());
+        assert_eq!(
+            paths(&differences),
+            [
+                "dev-lang/rust.homepage",
+                "dev-lang/rust/1.78.0.mask_flags",
+                "dev-lang/rust/1.78.0.keywords",
+                "dev-lang/rust/1.78.0.depend",
+            ]
+        );
+        assert_eq!(
+            differences[2].kind,
+            DifferenceKind::Field {
+                a: "[\"amd64\"]".to_string(),
+                b: "[\"~amd64\"]".to_string(),
+            }
+        );
+        assert_eq!(
+            differences[2].to_string(),
+            "dev-lang/rust/1.78.0.keywords: [\"amd64\"] != [\"~amd64\"]"
+        );
+
+        b[0].versions[0] = a[0].versions[0].clone();
+        b[0].versions[0].depend = Some(Depend::default());
+        let mut a = a;
+        a[0].versions[0].depend = Some(Depend {
+            bdepend: vec!["dev-build/cmake".to_string()],
+            ..Default::default()
+        });
+        let differences = compare_packages(&a, &b, CompareOptions::default());
+        assert_eq!(
+            paths(&differences),
+            [
+                "dev-lang/rust.homepage",
+                "dev-lang/rust/1.78.0.depend.bdepend"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignored_fields() {
+        let a = vec![package("dev-lang/rust", &["1.78.0"])];
+        let mut b = a.clone();
+        b[0].span = Some(crate::Span { start: 10, end: 20 });
+        let v = &mut b[0].versions[0];
+        v.priority = 2;
+        v.parts.clear();
+        v.span = Some(crate::Span { start: 12, end: 20 });
+        assert_eq!(
+            paths(&compare_packages(&a, &b, CompareOptions::default())),
+            [
+                "dev-lang/rust.span",
+                "dev-lang/rust/1.78.0.parts",
+                "dev-lang/rust/1.78.0.priority",
+                "dev-lang/rust/1.78.0.span",
+            ]
+        );
+        let options = CompareOptions {
+            ignore_priority: true,
+            ignore_spans: true,
+            ignore_parts: true,
+        };
+        assert!(compare_packages(&a, &b, options).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Reading compressed databases (feature `compression`)
+//!
+//! The compression is detected from the magic bytes at the start of the
+//! input. Compressed databases are read sequentially; the random access
+//! of [`Database::seek`] needs an uncompressed, seekable source.
+
+use crate::{Database, OpenOptions};
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+#[cfg(feature = "fs")]
+use std::io::{Seek, SeekFrom};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Number of bytes needed by [`Compression::detect`]
+pub const MAGIC_LEN: usize = 6;
+
+/// Compression format of a database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Not compressed (or not recognized)
+    None,
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the format from the first bytes of the input
+    pub fn detect(prefix: &[u8]) -> Self {
+        if prefix.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if prefix.starts_with(XZ_MAGIC) {
+            Compression::Xz
+        } else if prefix.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Wraps `reader` in the matching decoder
+    fn decoder<R: Read + Send + 'static>(self, reader: R) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        })
+    }
+}
+
+/// Reads up to [`MAGIC_LEN`] bytes; fewer only at the end of the input
+fn read_prefix<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut prefix = Vec::with_capacity(MAGIC_LEN);
+    reader.take(MAGIC_LEN as u64).read_to_end(&mut prefix)?;
+    Ok(prefix)
+}
+
+impl Database {
+    /// Reads a database from `reader`, decompressing it if it is gzip,
+    /// xz or zstd compressed
+    pub fn from_reader_compressed<R: Read + Send + 'static>(reader: R) -> io::Result<Self> {
+        Self::from_reader_compressed_with(reader, OpenOptions::default())
+    }
+
+    /// Like [`from_reader_compressed`](Self::from_reader_compressed) with
+    /// the given buffer capacity, UTF-8 policy and limits
+    pub fn from_reader_compressed_with<R: Read + Send + 'static>(
+        mut reader: R,
+        options: OpenOptions,
+    ) -> io::Result<Self> {
+        let prefix = read_prefix(&mut reader)?;
+        let compression = Compression::detect(&prefix);
+        let reader = compression.decoder(Cursor::new(prefix).chain(reader))?;
+        Ok(Database::from_reader_with(reader, options))
+    }
+
+    /// Opens a database which may be gzip, xz or zstd compressed.
+    /// Uncompressed files are read like with [`open_read`](Self::open_read).
+    #[cfg(feature = "fs")]
+    pub fn open_read_compressed<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_read_compressed_with(path, OpenOptions::default())
+    }
+
+    /// Like [`open_read_compressed`](Self::open_read_compressed) with the
+    /// given buffer capacity, UTF-8 policy and limits
+    #[cfg(feature = "fs")]
+    pub fn open_read_compressed_with<P: AsRef<Path>>(
+        path: P,
+        options: OpenOptions,
+    ) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut file = File::open(path)?;
+        let compression = Compression::detect(&read_prefix(&mut file)?);
+        if compression == Compression::None {
+            return Self::open_read_with(path, options);
+        }
+        file.seek(SeekFrom::Start(0))?;
+        let reader = compression.decoder(file)?;
+        Ok(Database::from_reader_with(reader, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Compression::detect(b"eix\n\x27"), Compression::None);
+        assert_eq!(Compression::detect(&[0x1f, 0x8b, 8]), Compression::Gzip);
+        assert_eq!(Compression::detect(XZ_MAGIC), Compression::Xz);
+        assert_eq!(Compression::detect(&[0xfd, b'7', b'z']), Compression::None);
+        assert_eq!(Compression::detect(ZSTD_MAGIC), Compression::Zstd);
+        assert_eq!(Compression::detect(&[]), Compression::None);
+    }
+}
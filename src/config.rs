@@ -0,0 +1,282 @@
+//! Local portage configuration
+//!
+//! The mask flags in the eix database reflect the configuration at the
+//! time `eix-update` ran. [`LocalConfig`] reads the current
+//! `package.mask`, `package.unmask` and `package.accept_keywords` from
+//! the configuration root (usually `/etc/portage`) so they can be
+//! applied on top of the database.
+
+use crate::{AcceptKeywords, Atom, MASK_HARD, Package, Version};
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+/// Default portage configuration root
+pub const PORTAGE_CONFIG_PATH: &str = "/etc/portage";
+
+/// Effective visibility of a version after applying the local configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectiveMask {
+    Visible,
+    /// Masked by the profile or package.mask and not unmasked
+    Masked,
+    /// Not masked, but none of its keywords is accepted
+    MissingKeyword,
+}
+
+/// Atoms of `package.mask`, `package.unmask` and `package.accept_keywords`
+#[derive(Debug, Clone, Default)]
+pub struct LocalConfig {
+    pub mask: Vec<Atom>,
+    pub unmask: Vec<Atom>,
+    /// Atoms with their accepted keywords; an empty list means `~arch`
+    pub accept_keywords: Vec<(Atom, Vec<String>)>,
+}
+
+/// Collects the files of a config entry which may be a file or a
+/// directory (read recursively in sorted order, skipping hidden files
+/// and editor backups). A missing entry yields no files.
+#[cfg(feature = "fs")]
+fn config_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+    let mut files = Vec::new();
+    for entry in entries {
+        let name = entry.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with('.') || name.ends_with('~') {
+            continue;
+        }
+        files.extend(config_files(&entry)?);
+    }
+    Ok(files)
+}
+
+/// Reads the lines of a config entry, without comments and blank lines
+#[cfg(feature = "fs")]
+fn config_lines(path: &Path) -> io::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for file in config_files(path)? {
+        for line in fs::read_to_string(&file)?.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if !line.is_empty() {
+                lines.push(line.to_string());
+            }
+        }
+    }
+    Ok(lines)
+}
+
+/// Parses lines of `[-]atom [tokens...]`, where `-atom` removes an
+/// earlier identical entry
+#[cfg(feature = "fs")]
+fn parse_atom_lines(lines: Vec<String>) -> io::Result<Vec<(Atom, Vec<String>)>> {
+    let mut entries: Vec<(Atom, Vec<String>)> = Vec::new();
+    for line in lines {
+        let mut words = line.split_whitespace();
+        let first = words.next().unwrap_or_default();
+        match first.strip_prefix('-') {
+            Some(removed) => {
+                let removed = Atom::parse(removed)?;
+                entries.retain(|(atom, _)| *atom != removed);
+            }
+            None => {
+                let atom = Atom::parse(first)?;
+                entries.push((atom, words.map(|w| w.to_string()).collect()));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(feature = "fs")]
+fn parse_atoms(lines: Vec<String>) -> io::Result<Vec<Atom>> {
+    Ok(parse_atom_lines(lines)?
+        .into_iter()
+        .map(|(atom, _)| atom)
+        .collect())
+}
+
+impl LocalConfig {
+    /// Reads the configuration below `config_root` (e.g. `/etc/portage`).
+    /// Each file may also be a directory; missing files are empty.
+    /// The legacy name `package.keywords` is read as well.
+    #[cfg(feature = "fs")]
+    pub fn load<P: AsRef<Path>>(config_root: P) -> io::Result<Self> {
+        let root = config_root.as_ref();
+        let mut accept_keywords = config_lines(&root.join("package.keywords"))?;
+        accept_keywords.extend(config_lines(&root.join("package.accept_keywords"))?);
+        Ok(LocalConfig {
+            mask: parse_atoms(config_lines(&root.join("package.mask"))?)?,
+            unmask: parse_atoms(config_lines(&root.join("package.unmask"))?)?,
+            accept_keywords: parse_atom_lines(accept_keywords)?,
+        })
+    }
+
+    /// Reads the configuration from [`PORTAGE_CONFIG_PATH`]
+    #[cfg(feature = "fs")]
+    pub fn load_default() -> io::Result<Self> {
+        Self::load(PORTAGE_CONFIG_PATH)
+    }
+
+    /// Returns the keywords accepted for `version` of `package` on
+    /// `arch`, as [`AcceptKeywords`] with the global keywords `arch`
+    /// and the entries of `package.accept_keywords`
+    pub fn accepted_keywords(
+        &self,
+        package: &Package,
+        version: &Version,
+        arch: &str,
+    ) -> Vec<String> {
+        AcceptKeywords::accepted_with(&[arch.to_string()], &self.accept_keywords, package, version)
+    }
+}
+
+impl Version {
+    /// Combines the stored mask flags with the local configuration
+    pub fn effective_mask(
+        &self,
+        config: &LocalConfig,
+        package: &Package,
+        arch: &str,
+    ) -> EffectiveMask {
+        let masked = ((self.mask_flags & MASK_HARD) != 0
+            || config.mask.iter().any(|a| a.matches(package, self)))
+            && !config.unmask.iter().any(|a| a.matches(package, self));
+        if masked {
+            return EffectiveMask::Masked;
+        }
+        let accepted = config.accepted_keywords(package, self, arch);
+        if self.keywords_accepted(accepted.iter().map(|k| k.as_str())) {
+            EffectiveMask::Visible
+        } else {
+            EffectiveMask::MissingKeyword
+        }
+    }
+}
+
+impl Package {
+    /// Returns the highest version visible after applying `config`
+    pub fn best_visible_with(&self, config: &LocalConfig, arch: &str) -> Option<&Version> {
+        self.versions
+            .iter()
+            .filter(|v| v.effective_mask(config, self, arch) == EffectiveMask::Visible)
+            .max_by(|a, b| a.compare(b))
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use crate::{MASK_PROFILE, parse_version};
+
+    fn version(v: &str, keywords: &[&str], mask_flags: u8) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            mask_flags,
+            reponame: "gentoo".into(),
+            ..Default::default()
+        }
+    }
+
+    fn package() -> Package {
+        Package {
+            category: "dev-lang".to_string(),
+            name: "rust".to_string(),
+            versions: vec![
+                version("1.77.1", &["amd64"], 0),
+                version("1.78.0", &["~amd64"], 0),
+                version("1.79.0", &["~amd64"], MASK_PROFILE),
+                version("9999", &[], 0),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn config_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("package.mask"),
+            "# local masks\n\n=dev-lang/rust-1.77* # broken\napp-misc/foo\n-app-misc/foo\n",
+        )
+        .unwrap();
+        fs::create_dir(root.join("package.unmask")).unwrap();
+        fs::write(
+            root.join("package.unmask/10-rust"),
+            "~dev-lang/rust-1.78.0\n",
+        )
+        .unwrap();
+        fs::write(root.join("package.unmask/.hidden"), "dev-lang/rust\n").unwrap();
+        fs::write(root.join("package.unmask/20-backup~"), "dev-lang/rust\n").unwrap();
+        fs::create_dir_all(root.join("package.accept_keywords/sub")).unwrap();
+        fs::write(
+            root.join("package.accept_keywords/sub/rust"),
+            "=dev-lang/rust-1.78*\n=dev-lang/rust-9999 **\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = config_tree();
+        let config = LocalConfig::load(dir.path()).unwrap();
+        assert_eq!(config.mask.len(), 1);
+        assert_eq!(config.mask[0].cp(), "dev-lang/rust");
+        assert_eq!(config.unmask.len(), 1);
+        assert_eq!(config.accept_keywords.len(), 2);
+        assert!(config.accept_keywords[0].1.is_empty());
+        assert_eq!(config.accept_keywords[1].1, vec!["**"]);
+
+        let empty = LocalConfig::load(dir.path().join("missing")).unwrap();
+        assert!(empty.mask.is_empty() && empty.unmask.is_empty());
+
+        fs::write(dir.path().join("package.mask"), "not-an-atom\n").unwrap();
+        assert!(LocalConfig::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_effective_mask() {
+        let dir = config_tree();
+        let config = LocalConfig::load(dir.path()).unwrap();
+        let pkg = package();
+        let masks: Vec<EffectiveMask> = pkg
+            .versions
+            .iter()
+            .map(|v| v.effective_mask(&config, &pkg, "amd64"))
+            .collect();
+        assert_eq!(
+            masks,
+            vec![
+                EffectiveMask::Masked,
+                EffectiveMask::Visible,
+                EffectiveMask::Masked,
+                EffectiveMask::Visible,
+            ]
+        );
+        let best = pkg.best_visible_with(&config, "amd64").unwrap();
+        assert_eq!(best.version_string, "9999");
+
+        let default = LocalConfig::default();
+        let best = pkg.best_visible_with(&default, "amd64").unwrap();
+        assert_eq!(best.version_string, "1.77.1");
+        assert_eq!(
+            pkg.versions[1].effective_mask(&default, &pkg, "amd64"),
+            EffectiveMask::MissingKeyword
+        );
+        assert!(pkg.best_visible_with(&default, "arm64").is_none());
+    }
+}
@@ -0,0 +1,1260 @@
+//! Byte-level decoding of the eix format
+//!
+//! [`Decoder`] reads the numbers, strings, hashes and records of a
+//! database from any [`Read`]; [`Database`](crate::Database) is a
+//! decoder over a buffered file.
+
+use crate::codec;
+use crate::options::{HashValidation, OpenOptions, ParseLimits, TrailingData, Utf8Policy};
+#[cfg(feature = "trace")]
+use crate::trace::{TraceEvent, TraceValue, Tracer};
+use crate::{
+    BasicPart, Catsize, DB_MAGIC, DB_VERSION_NUMERIC_PROPERTIES, DBHeader, DBVersion, Depend,
+    FrozenStringHash, HashWarning, LazyDepend, OverlayIdent, Package, PackageSummary,
+    SAVE_BITMASK_DEP, SAVE_BITMASK_REQUIRED_USE, SAVE_BITMASK_SRC_URI, SaveBitmask, Span,
+    StringHash, UChar, Version,
+};
+use std::fmt;
+use std::io::{self, Read};
+
+/// Reports a value read at `$offset` to the tracer, if any
+macro_rules! trace {
+    ($dec:expr, $offset:expr, $value:expr) => {
+        #[cfg(feature = "trace")]
+        if $dec.tracer.is_some() {
+            let value = $value;
+            $dec.emit($offset, value);
+        }
+        #[cfg(not(feature = "trace"))]
+        let _ = $offset;
+    };
+}
+
+/// Upper bound of the capacity reserved for a list before its elements
+/// are read, whatever count the file declares
+const MAX_INITIAL_CAPACITY: usize = 1024;
+
+/// Packages declaring a longer record are read field by field instead
+/// of ahead
+const MAX_READ_AHEAD: u64 = 1024 * 1024;
+
+pub(crate) fn initial_capacity(count: usize) -> usize {
+    count.min(MAX_INITIAL_CAPACITY)
+}
+
+/// Converts a number read from the file into a narrower type, failing
+/// instead of truncating it
+pub(crate) fn narrow<T: TryFrom<u64>>(value: u64, what: &str) -> io::Result<T> {
+    T::try_from(value).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} {} out of range", what, value),
+        )
+    })
+}
+
+/// Error of an index past the end of a hash
+fn invalid_hash_index(index: u64, hash_len: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid hash index: {} (hash size: {})", index, hash_len),
+    )
+}
+
+/// The overlay of `hdr` with key `key`, failing if there is none
+fn checked_overlay(hdr: &DBHeader, key: u64) -> io::Result<&OverlayIdent> {
+    hdr.overlay(key).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid overlay key: {}", key),
+        )
+    })
+}
+
+/// The hash indices of a package record, read by
+/// [`Decoder::read_package_indices`] without looking up any string
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageIndices {
+    /// Index into the license hash
+    pub license: u64,
+    pub versions: Vec<VersionIndices>,
+}
+
+/// The hash indices of a version record, read by
+/// [`Decoder::read_version_indices`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionIndices {
+    /// Index into the EAPI hash; `None` in databases before version 36
+    pub eapi: Option<u64>,
+    /// Indices into the keywords hash
+    pub keywords: Vec<u64>,
+    /// Index into the slot hash
+    pub slot: u64,
+    pub overlay_key: u64,
+}
+
+/// Decoder of the eix format over a reader
+///
+/// The record of a package is read ahead in a single call, using its
+/// length prefix, and decoded from memory. The values are still taken
+/// from the bytes in file order, so a wrong length prefix only costs the
+/// speed: unused bytes read ahead are decoded next, and a record longer
+/// than its prefix continues on the reader.
+pub struct Decoder<R: Read> {
+    reader: R,
+    // Bytes read ahead; those from `window_pos` on are not consumed yet
+    window: Vec<u8>,
+    window_pos: usize,
+    // Number of bytes consumed so far
+    position: u64,
+    utf8: Utf8Policy,
+    limits: ParseLimits,
+    hash_validation: HashValidation,
+    // Total length of the input, if known
+    input_len: Option<u64>,
+    record_spans: bool,
+    lazy_depend: bool,
+    warn_duplicate_versions: bool,
+    trailing_data: TrailingData,
+    // Contexts set and their offsets, if recorded
+    offsets: Option<Vec<(&'static str, u64)>>,
+    #[cfg(feature = "trace")]
+    tracer: Option<Tracer>,
+    #[cfg(feature = "trace")]
+    context: &'static str,
+}
+
+impl<R: Read + fmt::Debug> fmt::Debug for Decoder<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decoder")
+            .field("reader", &self.reader)
+            .field("position", &self.position)
+            .field("utf8", &self.utf8)
+            .field("limits", &self.limits)
+            .field("hash_validation", &self.hash_validation)
+            .field("input_len", &self.input_len)
+            .field("record_spans", &self.record_spans)
+            .field("lazy_depend", &self.lazy_depend)
+            .field("warn_duplicate_versions", &self.warn_duplicate_versions)
+            .field("trailing_data", &self.trailing_data)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, &OpenOptions::default())
+    }
+
+    /// Creates a decoder using the UTF-8 policy and limits of `options`
+    pub fn with_options(reader: R, options: &OpenOptions) -> Self {
+        Decoder {
+            reader,
+            window: Vec::new(),
+            window_pos: 0,
+            position: 0,
+            utf8: options.utf8,
+            limits: options.limits,
+            hash_validation: options.hash_validation,
+            input_len: None,
+            record_spans: options.record_spans,
+            lazy_depend: options.lazy_depend,
+            warn_duplicate_versions: options.warn_duplicate_versions,
+            trailing_data: options.trailing_data,
+            offsets: None,
+            #[cfg(feature = "trace")]
+            tracer: None,
+            #[cfg(feature = "trace")]
+            context: "",
+        }
+    }
+
+    /// Sets the receiver of a [`TraceEvent`] for every value read
+    #[cfg(feature = "trace")]
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// Sets what the following values mean, as reported in trace
+    /// events and recorded by [`record_offsets`](Self::record_offsets)
+    #[inline]
+    pub fn set_context(&mut self, context: &'static str) {
+        if let Some(offsets) = &mut self.offsets {
+            offsets.push((context, self.position));
+        }
+        #[cfg(feature = "trace")]
+        {
+            self.context = context;
+        }
+    }
+
+    /// Starts or stops recording the offset at which each context is
+    /// set, e.g. where the dependencies of a version start
+    pub fn record_offsets(&mut self, on: bool) {
+        self.offsets = on.then(Vec::new);
+    }
+
+    /// The contexts set since the last call and the offsets at which
+    /// they were set, in file order; empty if not recording
+    pub fn take_offsets(&mut self) -> Vec<(&'static str, u64)> {
+        self.offsets
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    #[cfg(feature = "trace")]
+    fn emit(&mut self, offset: u64, value: TraceValue) {
+        if let Some(tracer) = &mut self.tracer {
+            tracer(&TraceEvent {
+                offset,
+                context: self.context,
+                value,
+            });
+        }
+    }
+
+    /// Sets the total length of the input; counts and lengths larger
+    /// than the remaining bytes are then rejected before allocating
+    pub fn set_input_len(&mut self, len: u64) {
+        self.input_len = Some(len);
+    }
+
+    /// Whether readers record duplicate versions, see
+    /// [`OpenOptions::warn_duplicate_versions`]
+    pub(crate) fn warn_duplicate_versions(&self) -> bool {
+        self.warn_duplicate_versions
+    }
+
+    /// What readers do with bytes after the last category, see
+    /// [`OpenOptions::trailing_data`]
+    pub(crate) fn trailing_data(&self) -> TrailingData {
+        self.trailing_data
+    }
+
+    /// Fails if fewer than `needed` bytes remain in an input of known length
+    fn check_remaining(&self, needed: u64, what: &str) -> io::Result<()> {
+        match self.input_len {
+            Some(len) if needed > len.saturating_sub(self.position) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} of {} exceeds the remaining {} bytes",
+                    what,
+                    needed,
+                    len.saturating_sub(self.position)
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the offset of the next byte in the file, discarding the
+    /// bytes read ahead; used after seeking the reader and when decoding
+    /// a part of a file from memory
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+        self.window.clear();
+        self.window_pos = 0;
+    }
+
+    /// Returns the underlying reader; bytes read ahead of the current
+    /// package are lost
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Returns a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying reader; reading
+    /// from it directly makes [`position`](Self::position) wrong, and
+    /// the reader may be ahead of it within a package
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Number of bytes consumed from the reader
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// The bytes read ahead and not consumed yet
+    fn ahead(&self) -> &[u8] {
+        &self.window[self.window_pos..]
+    }
+
+    /// Reads up to `len` bytes ahead in a single call, unless bytes are
+    /// left from before or `len` is implausibly large
+    fn read_ahead(&mut self, len: u64) -> io::Result<()> {
+        if !self.ahead().is_empty() || len > MAX_READ_AHEAD {
+            return Ok(());
+        }
+        self.window.resize(len as usize, 0);
+        self.window_pos = 0;
+        // Stops at the end of the input instead of failing, leaving a
+        // wrong length to the decoding of the values
+        let mut filled = 0;
+        let result = loop {
+            match self.reader.read(&mut self.window[filled..]) {
+                Ok(0) => break Ok(()),
+                Ok(n) => {
+                    filled += n;
+                    if filled == self.window.len() {
+                        break Ok(());
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.window.truncate(filled);
+        result
+    }
+
+    fn read_bytes(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let ahead = self.ahead();
+        let from_window = ahead.len().min(buf.len());
+        buf[..from_window].copy_from_slice(&ahead[..from_window]);
+        self.window_pos += from_window;
+        if from_window < buf.len() {
+            self.reader.read_exact(&mut buf[from_window..])?;
+        }
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Reads `len` bytes of a string or version part
+    fn read_text(&mut self, len: u64, what: &str) -> io::Result<String> {
+        if len > self.limits.max_string_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} length {} exceeds the limit of {} bytes",
+                    what, len, self.limits.max_string_len
+                ),
+            ));
+        }
+        self.check_remaining(len, &format!("{} length", what))?;
+        let len: usize = narrow(len, &format!("{} length", what))?;
+        if let Some(bytes) = self.ahead().get(..len) {
+            let text = match self.utf8 {
+                Utf8Policy::Strict => std::str::from_utf8(bytes).map(str::to_owned).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid UTF-8 in {}: {}", what, e),
+                    )
+                }),
+                Utf8Policy::Lossy => Ok(String::from_utf8_lossy(bytes).into_owned()),
+            };
+            self.window_pos += len;
+            self.position += len as u64;
+            return text;
+        }
+        let mut buf = vec![0u8; len];
+        self.read_bytes(&mut buf)?;
+        match self.utf8 {
+            Utf8Policy::Strict => String::from_utf8(buf).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid UTF-8 in {}: {}", what, e),
+                )
+            }),
+            Utf8Policy::Lossy => Ok(String::from_utf8_lossy(&buf).into_owned()),
+        }
+    }
+
+    /// Reads a single byte
+    pub fn read_uchar(&mut self) -> io::Result<UChar> {
+        let start = self.position;
+        let byte = self.byte()?;
+        trace!(self, start, TraceValue::Byte(byte));
+        Ok(byte)
+    }
+
+    fn byte(&mut self) -> io::Result<UChar> {
+        if let Some(&byte) = self.ahead().first() {
+            self.window_pos += 1;
+            self.position += 1;
+            return Ok(byte);
+        }
+        let mut buf = [0u8; 1];
+        self.read_bytes(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    /// Reads a number in eix format (variable length),
+    /// see [`codec::decode_num`]
+    pub fn read_num(&mut self) -> io::Result<u64> {
+        let start = self.position;
+        let value = self.num()?;
+        trace!(self, start, TraceValue::Num(value));
+        Ok(value)
+    }
+
+    fn num(&mut self) -> io::Result<u64> {
+        match codec::decode_num(self.ahead()) {
+            Ok((value, len)) => {
+                self.window_pos += len;
+                self.position += len as u64;
+                return Ok(value);
+            }
+            // Continues on the reader below
+            Err(codec::Error::UnexpectedEnd) => {}
+            Err(e) => return Err(e.into()),
+        }
+        let mut buf = [0u8; codec::MAX_NUM_LEN + 1];
+        let mut len = 0;
+        loop {
+            buf[len] = self.byte()?;
+            len += 1;
+            match codec::decode_num(&buf[..len]) {
+                Ok((value, _)) => return Ok(value),
+                Err(codec::Error::UnexpectedEnd) if len < buf.len() => {}
+                Err(codec::Error::UnexpectedEnd) => return Err(codec::Error::NumberTooLarge.into()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Reads the number of elements of a list, at most
+    /// [`ParseLimits::max_list_len`]
+    pub fn read_count(&mut self, what: &str) -> io::Result<usize> {
+        self.count(self.limits.max_list_len, what)
+    }
+
+    fn count(&mut self, max: u64, what: &str) -> io::Result<usize> {
+        let count = self.read_num()?;
+        if count > max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} count {} exceeds the limit of {}", what, count, max),
+            ));
+        }
+        // Every element takes at least one byte
+        self.check_remaining(count, &format!("{} count", what))?;
+        narrow(count, &format!("{} count", what))
+    }
+
+    /// Reads a string (length + data)
+    /// Format: <length> <data bytes>
+    /// where length is encoded in eix number format
+    pub fn read_string(&mut self) -> io::Result<String> {
+        let start = self.position;
+        let len = self.num()?;
+        let s = if len == 0 {
+            String::new()
+        } else {
+            self.read_text(len, "string")?
+        };
+        trace!(self, start, TraceValue::String(s.clone()));
+        Ok(s)
+    }
+
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        let from_window = self
+            .ahead()
+            .len()
+            .min(usize::try_from(len).unwrap_or(usize::MAX));
+        self.window_pos += from_window;
+        self.position += from_window as u64;
+        let len = len - from_window as u64;
+        let skipped = io::copy(&mut (&mut self.reader).take(len), &mut io::sink())?;
+        self.position += skipped;
+        if skipped < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Unexpected end of file",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Skips `len` bytes
+    pub fn skip_bytes(&mut self, len: u64) -> io::Result<()> {
+        let start = self.position;
+        self.skip(len)?;
+        trace!(self, start, TraceValue::Skipped(len));
+        Ok(())
+    }
+
+    /// Skips a string without decoding it
+    pub fn skip_string(&mut self) -> io::Result<()> {
+        let start = self.position;
+        let len = self.num()?;
+        self.skip(len)?;
+        trace!(self, start, TraceValue::Skipped(self.position - start));
+        Ok(())
+    }
+
+    /// Skips the rest of the input, returning the number of bytes
+    /// skipped
+    pub fn skip_to_end(&mut self) -> io::Result<u64> {
+        let start = self.position;
+        let ahead = self.ahead().len() as u64;
+        self.window.clear();
+        self.window_pos = 0;
+        let rest = io::copy(&mut self.reader, &mut io::sink())?;
+        self.position += ahead + rest;
+        trace!(self, start, TraceValue::Skipped(ahead + rest));
+        Ok(ahead + rest)
+    }
+
+    /// Reads the properties flags of a version: a number, or in files
+    /// older than [`DB_VERSION_NUMERIC_PROPERTIES`] a single byte
+    pub fn read_properties(&mut self, version: DBVersion) -> io::Result<u32> {
+        if version >= DB_VERSION_NUMERIC_PROPERTIES {
+            narrow(self.read_num()?, "Properties flags")
+        } else {
+            self.read_uchar().map(u32::from)
+        }
+    }
+
+    fn hash_index(&mut self, hash: &StringHash) -> io::Result<u64> {
+        let index = self.num()?;
+        if usize::try_from(index).is_ok_and(|index| index < hash.len()) {
+            Ok(index)
+        } else {
+            Err(invalid_hash_index(index, hash.len()))
+        }
+    }
+
+    /// Reads an index into a hash without looking up its string
+    pub fn read_hash_index(&mut self, hash: &StringHash) -> io::Result<u64> {
+        let start = self.position;
+        let index = self.hash_index(hash)?;
+        trace!(self, start, TraceValue::Num(index));
+        Ok(index)
+    }
+
+    /// Reads a string from a hash (index → string)
+    pub fn read_hash_string(&mut self, hash: &StringHash) -> io::Result<String> {
+        let start = self.position;
+        let index = self.hash_index(hash)?;
+        let value = hash
+            .get_string(index as usize)
+            .unwrap_or_default()
+            .to_string();
+        trace!(
+            self,
+            start,
+            TraceValue::HashString {
+                index,
+                value: value.clone(),
+            }
+        );
+        Ok(value)
+    }
+
+    /// Reads a string hash (list of strings)
+    /// Format <number> <1st string>  ... <nth string>
+    /// <number> is the number of strings in the hash
+    /// where <number> is encoded in eix number format
+    pub fn read_hash(&mut self) -> io::Result<FrozenStringHash> {
+        let count = self.count(self.limits.max_hash_entries, "Hash")?;
+        let mut strings = Vec::with_capacity(initial_capacity(count));
+        for _ in 0..count {
+            strings.push(self.read_string()?);
+        }
+        Ok(FrozenStringHash::from(strings))
+    }
+
+    /// Reads a hash and checks it according to the hash validation option
+    fn read_checked_hash(
+        &mut self,
+        name: &'static str,
+        warnings: &mut Vec<HashWarning>,
+    ) -> io::Result<StringHash> {
+        let hash = self.read_hash()?;
+        if self.hash_validation != HashValidation::Off {
+            let found = hash.check(name, self.limits.max_hash_entry_len);
+            if let Some(warning) = found.first()
+                && self.hash_validation == HashValidation::Strict
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    warning.to_string(),
+                ));
+            }
+            warnings.extend(found);
+        }
+        Ok(hash.into())
+    }
+
+    /// Reads a list of strings from a hash (WordVec)
+    pub fn read_hash_words(&mut self, hash: &StringHash) -> io::Result<Vec<String>> {
+        let count = self.read_count("Word list")?;
+        let mut words = Vec::with_capacity(initial_capacity(count));
+        for _ in 0..count {
+            words.push(self.read_hash_string(hash)?);
+        }
+        Ok(words)
+    }
+
+    /// Reads a list of indices into a hash (WordVec) without looking up
+    /// the words
+    pub fn read_hash_indices(&mut self, hash: &StringHash) -> io::Result<Vec<u64>> {
+        let count = self.read_count("Word list")?;
+        let mut indices = Vec::with_capacity(initial_capacity(count));
+        for _ in 0..count {
+            indices.push(self.read_hash_index(hash)?);
+        }
+        Ok(indices)
+    }
+
+    /// Reads a single part of a version
+    pub fn read_part(&mut self) -> io::Result<BasicPart> {
+        let start = self.position;
+        let (part_type, len) = codec::part_header(self.num()?);
+        let part_content = self.read_text(len, "Part")?;
+        let part = BasicPart {
+            part_type,
+            part_content,
+        };
+        trace!(self, start, TraceValue::Part(part.clone()));
+        Ok(part)
+    }
+
+    /// Reads the database header
+    pub fn read_header(&mut self, min_version: DBVersion) -> io::Result<DBHeader> {
+        // 1. Read magic string (4 bytes)
+        self.set_context("magic");
+        let mut magic = vec![0u8; DB_MAGIC.len()];
+        self.read_bytes(&mut magic)?;
+        trace!(self, 0, TraceValue::Bytes(magic.clone()));
+        if magic != DB_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid magic: expected {:?}, got {:?}", DB_MAGIC, magic),
+            ));
+        }
+
+        // 2. Read version (eix compressed number)
+        self.set_context("database version");
+        let version: DBVersion = narrow(self.read_num()?, "Database version")?;
+        if version < min_version {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Database version {} too old (minimum: {})",
+                    version, min_version
+                ),
+            ));
+        }
+
+        // 3. Read number of categories (eix compressed number)
+        self.set_context("category count");
+        let size: Catsize = narrow(self.read_num()?, "Category count")?;
+
+        // 4. Read number of overlays (compressed number)
+        self.set_context("overlay count");
+        let overlay_count = self.read_count("Overlay list")?;
+
+        // 5. Read overlays
+        let mut overlays = Vec::with_capacity(initial_capacity(overlay_count));
+        for i in 0..overlay_count {
+            self.set_context("overlay path");
+            let path = self.read_string()?;
+            self.set_context("overlay label");
+            let label = self.read_string()?;
+            overlays.push(OverlayIdent {
+                path,
+                label: label.into(),
+                priority: i as i32,
+            });
+        }
+
+        // 6-10. Read string hashes
+        let mut warnings = Vec::new();
+        self.set_context("EAPI hash");
+        let eapi_hash = self.read_checked_hash("eapi", &mut warnings)?;
+        self.set_context("license hash");
+        let license_hash = self.read_checked_hash("license", &mut warnings)?;
+        self.set_context("keywords hash");
+        let keywords_hash = self.read_checked_hash("keywords", &mut warnings)?;
+        self.set_context("IUSE hash");
+        let iuse_hash = self.read_checked_hash("iuse", &mut warnings)?;
+        self.set_context("slot hash");
+        let slot_hash = self.read_checked_hash("slot", &mut warnings)?;
+
+        // 11. Read world sets (IMPORTANT: before the bitmask!)
+        self.set_context("world sets");
+        let world_set_count = self.read_count("World set list")?;
+        let mut world_sets = Vec::with_capacity(initial_capacity(world_set_count));
+        for _ in 0..world_set_count {
+            world_sets.push(self.read_string()?);
+        }
+
+        // 12. Read feature flags
+        self.set_context("save bitmask");
+        let bitmask: SaveBitmask = narrow(self.read_num()?, "Save bitmask")?;
+        let use_depend = (bitmask & SAVE_BITMASK_DEP) != 0;
+        let use_required_use = (bitmask & SAVE_BITMASK_REQUIRED_USE) != 0;
+        let use_src_uri = (bitmask & SAVE_BITMASK_SRC_URI) != 0;
+
+        // 13. Read depend hash (only if enabled in bitmask)
+        let depend_hash = if use_depend {
+            // eix writes a length (offset) before the hash here
+            self.set_context("depend hash length");
+            let _len = self.read_num()?;
+            self.set_context("depend hash");
+            self.read_checked_hash("depend", &mut warnings)?
+        } else {
+            StringHash::new()
+        };
+
+        Ok(DBHeader {
+            version,
+            size,
+            overlays,
+            eapi_hash,
+            license_hash,
+            keywords_hash,
+            iuse_hash,
+            slot_hash,
+            depend_hash,
+            use_depend,
+            use_required_use,
+            use_src_uri,
+            world_sets,
+            warnings,
+        })
+    }
+
+    /// Reads a package record including its length prefix. The spans
+    /// of package and versions are set if the options ask for them.
+    pub fn read_package(&mut self, hdr: &DBHeader, category: &str) -> io::Result<Package> {
+        let start = self.position;
+        // The versions are read up to their count; a length prefix not
+        // matching them is tolerated and reported by validate
+        let (summary, _end) = self.read_package_start(hdr, category)?;
+        let mut versions = Vec::with_capacity(initial_capacity(summary.version_count));
+        for _ in 0..summary.version_count {
+            versions.push(self.read_package_version(hdr)?);
+        }
+        let span = self.record_spans.then_some(Span {
+            start,
+            end: self.position,
+        });
+
+        Ok(Package {
+            name: summary.name,
+            description: summary.description,
+            homepage: summary.homepage,
+            licenses: summary.licenses,
+            versions,
+            category: summary.category,
+            span,
+        })
+    }
+
+    /// Reads the fields of a package record before its versions and
+    /// skips the versions using the length prefix
+    pub fn read_package_summary(
+        &mut self,
+        hdr: &DBHeader,
+        category: &str,
+    ) -> io::Result<PackageSummary> {
+        let (summary, end) = self.read_package_start(hdr, category)?;
+        self.skip_package_rest(end, &summary.category, &summary.name)?;
+        Ok(summary)
+    }
+
+    /// Reads a package record like [`read_package`](Self::read_package),
+    /// but only the hash indices of its license and versions; the
+    /// strings are skipped without decoding
+    pub fn read_package_indices(&mut self, hdr: &DBHeader) -> io::Result<PackageIndices> {
+        self.set_context("package length");
+        let len = self.read_num()?;
+        self.read_ahead(len)?;
+
+        self.set_context("name");
+        self.skip_string()?;
+        self.set_context("description");
+        self.skip_string()?;
+        self.set_context("homepage");
+        self.skip_string()?;
+        self.set_context("license");
+        let license = self.read_hash_index(&hdr.license_hash)?;
+        self.set_context("version count");
+        let version_count = self.read_count("Version list")?;
+        let mut versions = Vec::with_capacity(initial_capacity(version_count));
+        for _ in 0..version_count {
+            versions.push(self.read_version_indices(hdr)?);
+        }
+        Ok(PackageIndices { license, versions })
+    }
+
+    /// Reads the length prefix and the fields before the versions.
+    /// Returns the position where the package record ends according to
+    /// the prefix; the versions follow with
+    /// [`read_package_version`](Self::read_package_version).
+    pub fn read_package_start(
+        &mut self,
+        hdr: &DBHeader,
+        category: &str,
+    ) -> io::Result<(PackageSummary, u64)> {
+        // eix writes a length (offset) before each package
+        self.set_context("package length");
+        let len = self.read_num()?;
+        let end = self.position.saturating_add(len);
+        self.read_ahead(len)?;
+
+        self.set_context("name");
+        let name = self.read_string()?;
+        self.set_context("description");
+        let description = self.read_string()?;
+        self.set_context("homepage");
+        let homepage = self.read_string()?;
+        self.set_context("license");
+        let licenses = self.read_hash_string(&hdr.license_hash)?;
+        self.set_context("version count");
+        let version_count = self.read_count("Version list")?;
+
+        let summary = PackageSummary {
+            category: category.to_string(),
+            name,
+            description,
+            homepage,
+            licenses,
+            version_count,
+        };
+        Ok((summary, end))
+    }
+
+    /// Reads a version of a package record, setting its full version
+    /// string and its span if the options ask for it
+    pub fn read_package_version(&mut self, hdr: &DBHeader) -> io::Result<Version> {
+        let start = self.position;
+        let mut v = self.read_version(hdr)?;
+        v.version_string = v.get_full_version();
+        if self.record_spans {
+            v.span = Some(Span {
+                start,
+                end: self.position,
+            });
+        }
+        Ok(v)
+    }
+
+    /// Skips the rest of the package record of `category/name` ending at
+    /// `end`
+    pub fn skip_package_rest(&mut self, end: u64, category: &str, name: &str) -> io::Result<()> {
+        let rest = end.checked_sub(self.position).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Package {}/{} extends {} bytes past its length prefix",
+                    category,
+                    name,
+                    self.position - end
+                ),
+            )
+        })?;
+        self.set_context("versions");
+        self.skip_bytes(rest)
+    }
+
+    pub fn read_version(&mut self, hdr: &DBHeader) -> io::Result<Version> {
+        let mut eapi = String::new();
+        if hdr.version >= 36 {
+            self.set_context("EAPI");
+            eapi = self.read_hash_string(&hdr.eapi_hash)?;
+        }
+
+        self.set_context("mask flags");
+        let mask_flags = self.read_uchar()?;
+        self.set_context("properties flags");
+        let properties_flags = self.read_properties(hdr.version)?;
+        self.set_context("restrict flags");
+        let restrict_flags = self.read_num()?;
+
+        // HashedWords  Full keywords string of the ebuild
+        self.set_context("keywords");
+        let keywords = self.read_hash_words(&hdr.keywords_hash)?;
+
+        // Vector       VersionPart_\s
+        self.set_context("version parts");
+        let part_count = self.read_count("Version part list")?;
+        let mut parts = Vec::with_capacity(initial_capacity(part_count));
+        for _ in 0..part_count {
+            parts.push(self.read_part()?);
+        }
+
+        // HashedString Slot name. The slot name "0" is stored as ""
+        self.set_context("slot");
+        let slot = self.read_hash_string(&hdr.slot_hash)?;
+
+        // Number       Index of the portage overlay (in the overlays block)
+        self.set_context("overlay key");
+        let overlay_key = self.read_num()?;
+
+        let overlay = checked_overlay(hdr, overlay_key)?;
+        let reponame = overlay.label.clone();
+        let priority = overlay.priority;
+
+        // HashedWords  Useflags of this version
+        self.set_context("IUSE");
+        let iuse = self.read_hash_words(&hdr.iuse_hash)?;
+
+        // The following occurs only if REQUIRED_USE is stored
+
+        // HashedWords  REQUIRED_USE of this version
+        let mut required_use = Vec::new();
+        if hdr.use_required_use {
+            self.set_context("REQUIRED_USE");
+            required_use = self.read_hash_words(&hdr.iuse_hash)?;
+        }
+
+        // The following occurs only if dependencies are stored
+
+        let mut depend = None;
+        let mut lazy_depend = None;
+        if hdr.use_depend && self.lazy_depend {
+            self.set_context("depend length");
+            let _len = self.read_num()?;
+            lazy_depend = Some(self.read_lazy_depend(hdr)?);
+        } else if hdr.use_depend {
+            // Number       Length of the next four entries in bytes
+            self.set_context("depend length");
+            let _len = self.read_num()?; // Offset
+            let mut dep = Depend::default();
+            self.set_context("DEPEND");
+            dep.depend = self.read_hash_words(&hdr.depend_hash)?;
+            self.set_context("RDEPEND");
+            dep.rdepend = self.read_hash_words(&hdr.depend_hash)?;
+            self.set_context("PDEPEND");
+            dep.pdepend = self.read_hash_words(&hdr.depend_hash)?;
+            if hdr.version > 31 {
+                self.set_context("BDEPEND");
+                dep.bdepend = self.read_hash_words(&hdr.depend_hash)?;
+            }
+            if hdr.version > 38 {
+                self.set_context("IDEPEND");
+                dep.idepend = self.read_hash_words(&hdr.depend_hash)?;
+            }
+            depend = Some(dep);
+        }
+
+        // The following occurs only if SRC_URI is stored
+
+        // String       SRC_URI
+        let mut src_uri = None;
+        if hdr.use_src_uri {
+            self.set_context("SRC_URI");
+            src_uri = Some(self.read_string()?);
+        }
+
+        // finished reading version
+
+        Ok(Version {
+            version_string: String::new(),
+            parts,
+            eapi,
+            mask_flags,
+            properties_flags,
+            restrict_flags,
+            keywords,
+            slot,
+            overlay_key,
+            reponame,
+            priority,
+            iuse,
+            required_use,
+            depend,
+            lazy_depend,
+            src_uri,
+            span: None,
+        })
+    }
+
+    /// Reads a version record like [`read_version`](Self::read_version),
+    /// but only the hash indices of its EAPI, keywords and slot and its
+    /// overlay key; the other fields are skipped, checking the indices
+    /// of IUSE and REQUIRED_USE
+    pub fn read_version_indices(&mut self, hdr: &DBHeader) -> io::Result<VersionIndices> {
+        let mut eapi = None;
+        if hdr.version >= 36 {
+            self.set_context("EAPI");
+            eapi = Some(self.read_hash_index(&hdr.eapi_hash)?);
+        }
+
+        self.set_context("mask flags");
+        self.read_uchar()?;
+        self.set_context("properties flags");
+        self.read_properties(hdr.version)?;
+        self.set_context("restrict flags");
+        self.read_num()?;
+
+        self.set_context("keywords");
+        let keywords = self.read_hash_indices(&hdr.keywords_hash)?;
+
+        self.set_context("version parts");
+        let part_count = self.read_count("Version part list")?;
+        for _ in 0..part_count {
+            let start = self.position;
+            let (_, len) = codec::part_header(self.num()?);
+            self.skip(len)?;
+            trace!(self, start, TraceValue::Skipped(self.position - start));
+        }
+
+        self.set_context("slot");
+        let slot = self.read_hash_index(&hdr.slot_hash)?;
+        self.set_context("overlay key");
+        let overlay_key = self.read_num()?;
+        checked_overlay(hdr, overlay_key)?;
+
+        self.set_context("IUSE");
+        self.read_hash_indices(&hdr.iuse_hash)?;
+        if hdr.use_required_use {
+            self.set_context("REQUIRED_USE");
+            self.read_hash_indices(&hdr.iuse_hash)?;
+        }
+        if hdr.use_depend {
+            self.set_context("depend length");
+            let len = self.read_num()?;
+            self.set_context("depend");
+            self.skip_bytes(len)?;
+        }
+        if hdr.use_src_uri {
+            self.set_context("SRC_URI");
+            self.skip_string()?;
+        }
+
+        Ok(VersionIndices {
+            eapi,
+            keywords,
+            slot,
+            overlay_key,
+        })
+    }
+
+    /// Reads the dependency lists of a version without looking up their
+    /// words, checking the indices against the depend hash
+    fn read_lazy_depend(&mut self, hdr: &DBHeader) -> io::Result<LazyDepend> {
+        let (bdepend, idepend) = (hdr.version > 31, hdr.version > 38);
+        let lists = 3 + usize::from(bdepend) + usize::from(idepend);
+        let mut raw = Vec::new();
+        self.set_context("depend");
+        for _ in 0..lists {
+            let count = self.read_count("Word list")?;
+            codec::encode_num(count as u64, &mut raw);
+            for _ in 0..count {
+                let index = self.hash_index(&hdr.depend_hash)?;
+                codec::encode_num(index, &mut raw);
+            }
+        }
+        raw.shrink_to_fit();
+        Ok(LazyDepend::new(
+            raw,
+            bdepend,
+            idepend,
+            hdr.depend_hash.shared_strings(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::words;
+    use crate::{EixWriter, HashWarningKind, Package, parse_version};
+
+    #[test]
+    fn test_decode_in_memory() {
+        let mut header = DBHeader {
+            version: crate::DB_VERSION_CURRENT,
+            size: 0,
+            overlays: vec![OverlayIdent {
+                path: "/var/db/repos/gentoo".to_string(),
+                label: "gentoo".into(),
+                priority: 0,
+            }],
+            eapi_hash: StringHash::new(),
+            license_hash: StringHash::new(),
+            keywords_hash: StringHash::new(),
+            iuse_hash: StringHash::new(),
+            slot_hash: StringHash::new(),
+            depend_hash: StringHash::new(),
+            use_depend: false,
+            use_required_use: false,
+            use_src_uri: true,
+            world_sets: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let version = Version {
+            version_string: "5.4.8".to_string(),
+            parts: parse_version("5.4.8").unwrap(),
+            eapi: "8".to_string(),
+            keywords: words("amd64"),
+            slot: "5.4".to_string(),
+            src_uri: Some("https://www.lua.org/ftp/lua-5.4.8.tar.gz".to_string()),
+            ..Default::default()
+        };
+        let packages = vec![Package {
+            category: "dev-lang".to_string(),
+            name: "lua".to_string(),
+            licenses: "MIT".to_string(),
+            versions: vec![version],
+            ..Default::default()
+        }];
+        let mut writer = EixWriter::new(Vec::new());
+        writer.write_database(&header, &packages).unwrap();
+        let data = writer.into_inner();
+
+        let mut decoder = Decoder::new(data.as_slice());
+        header = decoder.read_header(0).unwrap();
+        assert_eq!(header.size, 1);
+        assert_eq!(header.license_hash.get_string(0), Some("MIT"));
+        assert_eq!(decoder.read_string().unwrap(), "dev-lang");
+        assert_eq!(decoder.read_num().unwrap(), 1);
+        let len = decoder.read_num().unwrap();
+        let start = decoder.position();
+        assert_eq!(decoder.read_string().unwrap(), "lua");
+        decoder.skip_string().unwrap();
+        decoder.skip_string().unwrap();
+        assert_eq!(
+            decoder.read_hash_string(&header.license_hash).unwrap(),
+            "MIT"
+        );
+        assert_eq!(decoder.read_num().unwrap(), 1);
+        decoder.record_offsets(true);
+        let version_start = decoder.position();
+        let v = decoder.read_version(&header).unwrap();
+        assert_eq!(v.get_full_version(), "5.4.8");
+        assert_eq!(v.slot, "5.4");
+        assert_eq!(v.src_uri, packages[0].versions[0].src_uri);
+        // SRC_URI is last, after its length byte
+        let offsets = decoder.take_offsets();
+        assert_eq!(offsets[0], ("EAPI", version_start));
+        let src_uri_len = v.src_uri.as_ref().unwrap().len() as u64;
+        assert_eq!(
+            offsets.last(),
+            Some(&("SRC_URI", data.len() as u64 - 1 - src_uri_len))
+        );
+        assert!(decoder.take_offsets().is_empty());
+        assert_eq!(decoder.position() - start, len);
+        assert_eq!(decoder.position(), data.len() as u64);
+        assert_eq!(
+            decoder.read_uchar().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        // The same package at the level of hash indices
+        let mut decoder = Decoder::new(data.as_slice());
+        let header = decoder.read_header(0).unwrap();
+        decoder.skip_string().unwrap();
+        decoder.read_num().unwrap();
+        let pkg = decoder.read_package_indices(&header).unwrap();
+        let index = |hash: &StringHash, s: &str| hash.get_index(s).unwrap() as u64;
+        assert_eq!(pkg.license, index(&header.license_hash, "MIT"));
+        assert_eq!(
+            pkg.versions,
+            vec![VersionIndices {
+                eapi: Some(index(&header.eapi_hash, "8")),
+                keywords: vec![index(&header.keywords_hash, "amd64")],
+                slot: index(&header.slot_hash, "5.4"),
+                overlay_key: 0,
+            }]
+        );
+        assert_eq!(decoder.position(), data.len() as u64);
+    }
+
+    #[test]
+    fn test_hash_warnings() {
+        let words = |words: &[&str]| -> StringHash {
+            FrozenStringHash::from(words.iter().map(|w| w.to_string()).collect::<Vec<_>>()).into()
+        };
+        let header = DBHeader {
+            version: crate::DB_VERSION_CURRENT,
+            size: 0,
+            overlays: Vec::new(),
+            eapi_hash: words(&["8"]),
+            license_hash: words(&["MIT", &"x".repeat(100)]),
+            keywords_hash: words(&["amd64", "", "", ""]),
+            iuse_hash: words(&["ssl", "a\0b"]),
+            slot_hash: StringHash::new(),
+            depend_hash: StringHash::new(),
+            use_depend: false,
+            use_required_use: false,
+            use_src_uri: false,
+            world_sets: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let mut writer = EixWriter::new(Vec::new());
+        writer.write_header(&header).unwrap();
+        let data = writer.into_inner();
+        let mut options = OpenOptions::default();
+        options.limits.max_hash_entry_len = 64;
+
+        // Off by default
+        let read = Decoder::with_options(data.as_slice(), &options)
+            .read_header(0)
+            .unwrap();
+        assert!(read.warnings().is_empty());
+
+        options.hash_validation = HashValidation::Warn;
+        let read = Decoder::with_options(data.as_slice(), &options)
+            .read_header(0)
+            .unwrap();
+        let warning = |hash, index, kind| HashWarning { hash, index, kind };
+        assert_eq!(
+            read.warnings(),
+            [
+                warning("license", 1, HashWarningKind::TooLong { len: 100 }),
+                warning("keywords", 2, HashWarningKind::Duplicate { first: 1 }),
+                warning("keywords", 3, HashWarningKind::Duplicate { first: 1 }),
+                warning("iuse", 1, HashWarningKind::EmbeddedNul),
+            ]
+        );
+        // The duplicates keep their indices
+        assert_eq!(read.keywords_hash.len(), 4);
+        assert_eq!(read.keywords_hash.get_string(3), Some(""));
+        assert_eq!(
+            read.warnings()[1].to_string(),
+            "keywords hash entry 2: duplicate of entry 1"
+        );
+
+        options.hash_validation = HashValidation::Strict;
+        let err = Decoder::with_options(data.as_slice(), &options)
+            .read_header(0)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "license hash entry 1: implausibly long (100 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_read_num() {
+        let cases = vec![
+            (0x00, vec![0x00]),
+            (0xFE, vec![0xFE]),
+            (0xFF, vec![0xFF, 0x00]),
+            (0x0100, vec![0xFF, 0x01, 0x00]),
+            (0x01FF, vec![0xFF, 0x01, 0xFF]),
+            (0xFEFF, vec![0xFF, 0xFE, 0xFF]),
+            (0xFF00, vec![0xFF, 0xFF, 0x00, 0x00]),
+            (0xFF01, vec![0xFF, 0xFF, 0x00, 0x01]),
+            (0x010000, vec![0xFF, 0xFF, 0x01, 0x00, 0x00]),
+            (0xABCDEF, vec![0xFF, 0xFF, 0xAB, 0xCD, 0xEF]),
+            (0xFFABCD, vec![0xFF, 0xFF, 0xFF, 0x00, 0xAB, 0xCD]),
+            (0x01ABCDEF, vec![0xFF, 0xFF, 0xFF, 0x01, 0xAB, 0xCD, 0xEF]),
+        ];
+
+        for (expected, bytes) in cases {
+            let mut db = Decoder::new(bytes.as_slice());
+
+            let result = db
+                .read_num()
+                .unwrap_or_else(|_| panic!("Failed to read {:?}", bytes));
+            assert_eq!(
+                result, expected,
+                "Case {:?} failed: expected 0x{:X}, got 0x{:X}",
+                bytes, expected, result
+            );
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Versions occurring more than once in a package
+//!
+//! eix writes every version of a repository once, but a broken overlay
+//! can produce a package record holding the same version, slot and
+//! repository twice. [`Package::find_duplicate_versions`] reports such
+//! versions and [`Package::dedup_versions`] removes them; with
+//! [`OpenOptions::warn_duplicate_versions`](crate::OpenOptions::warn_duplicate_versions)
+//! a [`PackageReader`](crate::PackageReader) records them as warnings.
+//!
+//! ```
+//! use eix::{DedupPolicy, EixTestBuilder};
+//!
+//! let (_, mut packages) = EixTestBuilder::new()
+//!     .category("app-misc")
+//!     .package("screen", |p| {
+//!         p.version("4.9.1", |v| v.keywords("amd64"))
+//!             .version("5.0.0", |v| v)
+//!             .version("4.9.1", |v| v.keywords("~amd64"))
+//!     })
+//!     .build();
+//! let screen = &mut packages[0];
+//! assert_eq!(screen.find_duplicate_versions(), [(0, 2)]);
+//! assert_eq!(screen.dedup_versions(DedupPolicy::KeepFirst), 1);
+//! assert_eq!(screen.versions[0].keywords_string(), "amd64");
+//! ```
+
+use crate::compare::versions_identical;
+use crate::{Package, Version, VersionKey};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Which versions [`Package::dedup_versions`] removes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupPolicy {
+    /// Keep the first occurrence of every version
+    #[default]
+    KeepFirst,
+    /// Keep the last occurrence of every version
+    KeepLast,
+    /// Only remove occurrences identical to the first one in every field
+    /// but the span; duplicates differing e.g. in their keywords are kept
+    IdenticalOnly,
+}
+
+/// A version occurring more than once in a package, found while reading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateVersion {
+    /// `category/name`
+    pub atom: String,
+    pub version: String,
+    pub slot: String,
+    pub repo: Arc<str>,
+    /// Index of the first occurrence in the versions of the package
+    pub first: usize,
+    /// Index of the repeated occurrence
+    pub duplicate: usize,
+}
+
+impl fmt::Display for DuplicateVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.atom, self.version)?;
+        if !self.slot.is_empty() {
+            write!(f, ":{}", self.slot)?;
+        }
+        write!(
+            f,
+            "::{}: version {} repeats version {}",
+            self.repo, self.duplicate, self.first
+        )
+    }
+}
+
+impl Version {
+    /// Whether `other` has the same version string, slot and repository;
+    /// the remaining fields are ignored
+    pub fn is_duplicate_of(&self, other: &Version) -> bool {
+        self.version_string == other.version_string
+            && self.slot == other.slot
+            && self.reponame == other.reponame
+    }
+}
+
+impl Package {
+    /// Pairs of the index of a version and of a later version which
+    /// [duplicates](Version::is_duplicate_of) it, ordered by the later
+    /// one. A version occurring three times yields two pairs with the
+    /// same first index.
+    pub fn find_duplicate_versions(&self) -> Vec<(usize, usize)> {
+        let mut first: HashMap<(VersionKey<'_>, &str), usize> = HashMap::new();
+        let mut pairs = Vec::new();
+        for (i, v) in self.versions.iter().enumerate() {
+            let key = (self.version_key(v), v.slot.as_str());
+            match first.get(&key) {
+                Some(&f) => pairs.push((f, i)),
+                None => {
+                    first.insert(key, i);
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Removes duplicate versions according to `policy`, keeping the
+    /// order of the others; returns the number of versions removed
+    pub fn dedup_versions(&mut self, policy: DedupPolicy) -> usize {
+        let pairs = self.find_duplicate_versions();
+        if pairs.is_empty() {
+            return 0;
+        }
+        let mut remove = vec![false; self.versions.len()];
+        match policy {
+            DedupPolicy::KeepFirst => pairs.iter().for_each(|&(_, d)| remove[d] = true),
+            DedupPolicy::KeepLast => {
+                // The pairs are ordered by the later index, so the last
+                // pair of a version names its last occurrence
+                let mut last: HashMap<usize, usize> = HashMap::new();
+                for &(f, d) in &pairs {
+                    remove[*last.get(&f).unwrap_or(&f)] = true;
+                    last.insert(f, d);
+                }
+            }
+            DedupPolicy::IdenticalOnly => {
+                for &(f, d) in &pairs {
+                    remove[d] = versions_identical(&self.versions[f], &self.versions[d]);
+                }
+            }
+        }
+        let before = self.versions.len();
+        let mut index = 0;
+        self.versions.retain(|_| {
+            index += 1;
+            !remove[index - 1]
+        });
+        before - self.versions.len()
+    }
+
+    /// The duplicate versions as warnings
+    pub(crate) fn duplicate_version_warnings(&self) -> Vec<DuplicateVersion> {
+        self.find_duplicate_versions()
+            .into_iter()
+            .map(|(first, duplicate)| {
+                let v = &self.versions[duplicate];
+                DuplicateVersion {
+                    atom: format!("{}/{}", self.category, self.name),
+                    version: v.version_string.clone(),
+                    slot: v.slot.clone(),
+                    repo: v.reponame.clone(),
+                    first,
+                    duplicate,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_version;
+    use crate::testutil::words;
+
+    fn version(v: &str, slot: &str, repo: &str, keywords: &str) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            slot: slot.to_string(),
+            reponame: repo.into(),
+            keywords: words(keywords),
+            ..Default::default()
+        }
+    }
+
+    fn package(versions: Vec<Version>) -> Package {
+        Package {
+            category: "app-misc".to_string(),
+            name: "screen".to_string(),
+            versions,
+            ..Default::default()
+        }
+    }
+
+    fn keywords(package: &Package) -> Vec<String> {
+        package
+            .versions
+            .iter()
+            .map(|v| format!("{} {}", v.version_string, v.keywords_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let package = package(vec![
+            version("1.0", "0", "gentoo", "amd64"),
+            version("1.0", "1", "gentoo", "amd64"),
+            version("1.0", "0", "guru", "amd64"),
+            version("2.0", "0", "gentoo", "amd64"),
+            version("1.0", "0", "gentoo", "~amd64"),
+            version("1.0", "0", "gentoo", "amd64"),
+        ]);
+        assert_eq!(package.find_duplicate_versions(), [(0, 4), (0, 5)]);
+        let warnings = package.duplicate_version_warnings();
+        assert_eq!(
+            warnings[0].to_string(),
+            "app-misc/screen-1.0:0::gentoo: version 4 repeats version 0"
+        );
+        assert!(self::package(vec![]).find_duplicate_versions().is_empty());
+    }
+
+    #[test]
+    fn test_dedup_policies() {
+        let versions = vec![
+            version("1.0", "0", "gentoo", "amd64"),
+            version("2.0", "0", "gentoo", "amd64"),
+            // Differs only in a field the duplicate check ignores
+            version("1.0", "0", "gentoo", "~amd64"),
+            // Identical to the first
+            version("1.0", "0", "gentoo", "amd64"),
+        ];
+
+        let mut first = package(versions.clone());
+        assert_eq!(first.dedup_versions(DedupPolicy::KeepFirst), 2);
+        assert_eq!(keywords(&first), ["1.0 amd64", "2.0 amd64"]);
+        assert_eq!(first.dedup_versions(DedupPolicy::KeepFirst), 0);
+
+        let mut last = package(versions.clone());
+        assert_eq!(last.dedup_versions(DedupPolicy::KeepLast), 2);
+        assert_eq!(keywords(&last), ["2.0 amd64", "1.0 amd64"]);
+
+        let mut identical = package(versions);
+        assert_eq!(identical.dedup_versions(DedupPolicy::IdenticalOnly), 1);
+        assert_eq!(
+            keywords(&identical),
+            ["1.0 amd64", "2.0 amd64", "1.0 ~amd64"]
+        );
+        assert_eq!(identical.find_duplicate_versions(), [(0, 2)]);
+    }
+}
@@ -0,0 +1,883 @@
+//! Differences between two databases
+//!
+//! Compares two package lists (e.g. yesterday's and today's database)
+//! by atom and reports new, removed and changed packages. The lists
+//! need not be sorted. Versions are compared by their parts, so `1.0`
+//! and `1.0-r0` are the same version.
+
+use crate::basicversion::{Parts, parse_parts};
+use crate::{Package, PackageIndex, PackageKey, Version, compare_parts};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Identifies a version within a package
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct VersionId {
+    pub version: String,
+    /// Slot as stored in the database
+    pub slot: String,
+    pub repo: String,
+}
+
+impl VersionId {
+    fn of(version: &Version) -> Self {
+        VersionId {
+            version: version.version_string.clone(),
+            slot: version.slot.clone(),
+            repo: version.reponame.to_string(),
+        }
+    }
+}
+
+/// A package only present in one of the databases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageEntry {
+    /// `category/name`
+    pub atom: String,
+    pub versions: Vec<String>,
+}
+
+/// Changed keywords of a version present in both databases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeywordChange {
+    pub version: VersionId,
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+}
+
+/// Changed mask flags of a version present in both databases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaskChange {
+    pub version: VersionId,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// Package-level metadata compared by [`diff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PackageField {
+    Description,
+    Homepage,
+    Licenses,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub field: PackageField,
+    pub old: String,
+    pub new: String,
+}
+
+/// The highest version of a package before and after
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BestVersionChange {
+    pub old: String,
+    pub new: String,
+}
+
+/// Changes of a package present in both databases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageDiff {
+    pub atom: String,
+    pub old_versions: Vec<String>,
+    pub new_versions: Vec<String>,
+    /// Set if the highest version changed
+    pub best_version: Option<BestVersionChange>,
+    pub added_versions: Vec<VersionId>,
+    pub removed_versions: Vec<VersionId>,
+    pub keyword_changes: Vec<KeywordChange>,
+    pub mask_changes: Vec<MaskChange>,
+    pub field_changes: Vec<FieldChange>,
+}
+
+impl PackageDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_versions.is_empty()
+            && self.removed_versions.is_empty()
+            && self.keyword_changes.is_empty()
+            && self.mask_changes.is_empty()
+            && self.field_changes.is_empty()
+    }
+}
+
+/// Result of [`diff`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DbDiff {
+    /// Packages only in the new database, in its order
+    pub added: Vec<PackageEntry>,
+    /// Packages only in the old database, in its order
+    pub removed: Vec<PackageEntry>,
+    /// Packages with changes, in the order of the new database
+    pub changed: Vec<PackageDiff>,
+}
+
+impl DbDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Writes the diff in the style of `eix-diff`, one line per package
+    /// sorted by atom:
+    ///
+    /// ```text
+    /// [N] app-misc/newpkg (1.0)
+    /// [>] dev-lang/rust (1.77.1 -> 1.78.0)
+    /// [*] dev-libs/foo (1.1)
+    /// [<] old/gone
+    /// ```
+    ///
+    /// `[>]` marks a changed highest version, `[*]` other changes.
+    pub fn render<W: Write>(&self, mut w: W, options: RenderOptions) -> io::Result<()> {
+        let mut lines: Vec<(&str, Marker, String)> = Vec::new();
+        for entry in &self.added {
+            let best = highest(&entry.versions).unwrap_or_default();
+            lines.push((&entry.atom, Marker::New, format!(" ({})", best)));
+        }
+        for entry in &self.removed {
+            lines.push((&entry.atom, Marker::Removed, String::new()));
+        }
+        for change in &self.changed {
+            if let Some(best) = &change.best_version {
+                lines.push((
+                    &change.atom,
+                    Marker::Updated,
+                    format!(" ({} -> {})", best.old, best.new),
+                ));
+            } else {
+                let new = highest(&change.new_versions).unwrap_or_default();
+                lines.push((&change.atom, Marker::Changed, format!(" ({})", new)));
+            }
+        }
+        lines.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (atom, marker, rest) in lines {
+            if options.color {
+                writeln!(
+                    w,
+                    "{}[{}]{} {}{}",
+                    marker.color(),
+                    marker.symbol(),
+                    RESET,
+                    atom,
+                    rest
+                )?;
+            } else {
+                writeln!(w, "[{}] {}{}", marker.symbol(), atom, rest)?;
+            }
+        }
+        if options.summary {
+            writeln!(
+                w,
+                "{} new, {} removed, {} updated",
+                self.added.len(),
+                self.removed.len(),
+                self.changed.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DbDiff {
+    /// Flattens the diff into one event per change, in the order of
+    /// the diff: added, removed, then changed packages
+    pub fn to_events(&self) -> Vec<ChangeEvent> {
+        let mut events = Vec::new();
+        for entry in &self.added {
+            events.push(ChangeEvent::new(EventKind::PackageAdded, &entry.atom));
+            for v in &entry.versions {
+                events.push(ChangeEvent::new(EventKind::VersionAdded, &entry.atom).version(v));
+            }
+        }
+        for entry in &self.removed {
+            events.push(ChangeEvent::new(EventKind::PackageRemoved, &entry.atom));
+            for v in &entry.versions {
+                events.push(ChangeEvent::new(EventKind::VersionRemoved, &entry.atom).version(v));
+            }
+        }
+        for change in &self.changed {
+            let atom = &change.atom;
+            for key in &change.added_versions {
+                events.push(ChangeEvent::new(EventKind::VersionAdded, atom).version(&key.version));
+            }
+            for key in &change.removed_versions {
+                events
+                    .push(ChangeEvent::new(EventKind::VersionRemoved, atom).version(&key.version));
+            }
+            for kw in &change.keyword_changes {
+                for (arch, old, new) in keyword_changes_by_arch(&kw.old, &kw.new) {
+                    let mut event = ChangeEvent::new(EventKind::KeywordChanged, atom)
+                        .version(&kw.version.version);
+                    event.arch = Some(arch.to_string());
+                    event.old = old.map(|k| k.to_string());
+                    event.new = new.map(|k| k.to_string());
+                    events.push(event);
+                }
+            }
+            for mask in &change.mask_changes {
+                let mut event =
+                    ChangeEvent::new(EventKind::MaskChanged, atom).version(&mask.version.version);
+                event.old = Some(mask.old.to_string());
+                event.new = Some(mask.new.to_string());
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Returns the events of [`to_events`](Self::to_events) matching `filter`
+    pub fn events_matching(&self, filter: &EventFilter) -> Vec<ChangeEvent> {
+        self.to_events()
+            .into_iter()
+            .filter(|e| filter.matches(e))
+            .collect()
+    }
+}
+
+/// Kind of a [`ChangeEvent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    PackageAdded,
+    PackageRemoved,
+    VersionAdded,
+    VersionRemoved,
+    KeywordChanged,
+    MaskChanged,
+}
+
+/// A single change as a flat record, e.g. for NDJSON feeds.
+///
+/// All fields are always serialized (`null` if not applicable):
+/// `kind`, `atom`, `version`, `arch`, `old` and `new`. For
+/// `keyword_changed`, `old`/`new` are the keywords for `arch`
+/// (`amd64`, `~amd64`, `-amd64` or `null`); for `mask_changed`
+/// they are the mask flags as decimal numbers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangeEvent {
+    pub kind: EventKind,
+    pub atom: String,
+    pub version: Option<String>,
+    pub arch: Option<String>,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl ChangeEvent {
+    fn new(kind: EventKind, atom: &str) -> Self {
+        ChangeEvent {
+            kind,
+            atom: atom.to_string(),
+            version: None,
+            arch: None,
+            old: None,
+            new: None,
+        }
+    }
+
+    fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+}
+
+/// Returns the architecture of a keyword (`~amd64` and `-amd64` -> `amd64`)
+fn keyword_arch(keyword: &str) -> &str {
+    keyword.trim_start_matches(['~', '-'])
+}
+
+/// Pairs the keywords of both lists by architecture, returning those
+/// which differ, in order of first appearance
+fn keyword_changes_by_arch<'a>(
+    old: &'a [String],
+    new: &'a [String],
+) -> Vec<(&'a str, Option<&'a str>, Option<&'a str>)> {
+    let find = |list: &'a [String], arch: &str| {
+        list.iter()
+            .map(|k| k.as_str())
+            .find(|k| keyword_arch(k) == arch)
+    };
+    let mut archs: Vec<&str> = Vec::new();
+    for kw in old.iter().chain(new) {
+        let arch = keyword_arch(kw);
+        if !archs.contains(&arch) {
+            archs.push(arch);
+        }
+    }
+    archs
+        .into_iter()
+        .map(|arch| (arch, find(old, arch), find(new, arch)))
+        .filter(|(_, old, new)| old != new)
+        .collect()
+}
+
+/// Selects events by atom glob (e.g. `dev-python/*`) and architecture.
+/// With an architecture, only events for that architecture match.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub atom_glob: Option<String>,
+    pub arch: Option<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &ChangeEvent) -> bool {
+        self.atom_glob
+            .as_deref()
+            .is_none_or(|glob| glob_match(glob, &event.atom))
+            && self
+                .arch
+                .as_deref()
+                .is_none_or(|arch| event.arch.as_deref() == Some(arch))
+    }
+}
+
+/// Matches `text` against a pattern where `*` matches any sequence
+/// and `?` any single character
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Options of [`DbDiff::render`]
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    /// Color the markers with ANSI escape sequences
+    pub color: bool,
+    /// Append a line counting new, removed and updated packages
+    pub summary: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            color: false,
+            summary: true,
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy)]
+enum Marker {
+    New,
+    Removed,
+    Updated,
+    Changed,
+}
+
+impl Marker {
+    fn symbol(self) -> char {
+        match self {
+            Marker::New => 'N',
+            Marker::Removed => '<',
+            Marker::Updated => '>',
+            Marker::Changed => '*',
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Marker::New => "\x1b[1;32m",
+            Marker::Removed => "\x1b[1;31m",
+            Marker::Updated => "\x1b[1;36m",
+            Marker::Changed => "\x1b[1;33m",
+        }
+    }
+}
+
+/// Returns the highest of the version strings; unparsable versions
+/// are ignored unless there is nothing else
+pub(crate) fn highest(versions: &[String]) -> Option<&str> {
+    versions
+        .iter()
+        .filter_map(|v| parse_parts(v).ok().map(|parts| (v, parts)))
+        .max_by(|a, b| compare_parts(&a.1, &b.1))
+        .map(|(v, _)| v.as_str())
+        .or_else(|| versions.last().map(|v| v.as_str()))
+}
+
+fn entry(pkg: &Package) -> PackageEntry {
+    PackageEntry {
+        atom: pkg.cp(),
+        versions: version_strings(pkg),
+    }
+}
+
+fn version_strings(pkg: &Package) -> Vec<String> {
+    pkg.versions
+        .iter()
+        .map(|v| v.version_string.clone())
+        .collect()
+}
+
+/// The parts of `version` parsed from its string if not set, e.g. for
+/// versions read from JSON
+fn parsed_parts(version: &Version) -> Option<Parts> {
+    if version.parts.is_empty() {
+        parse_parts(&version.version_string).ok()
+    } else {
+        None
+    }
+}
+
+/// Whether `a` and `b` are the same version in the same slot and
+/// repository
+fn same_version(a: &Version, b: &Version) -> bool {
+    a.slot == b.slot
+        && a.reponame == b.reponame
+        && compare_parts(
+            parsed_parts(a).as_deref().unwrap_or(&a.parts),
+            parsed_parts(b).as_deref().unwrap_or(&b.parts),
+        ) == Ordering::Equal
+}
+
+/// The change of the highest version, compared by parts
+fn best_version_change(old: &Package, new: &Package) -> Option<BestVersionChange> {
+    let old_versions = version_strings(old);
+    let new_versions = version_strings(new);
+    let old_best = highest(&old_versions).unwrap_or_default();
+    let new_best = highest(&new_versions).unwrap_or_default();
+    let same = match (parse_parts(old_best), parse_parts(new_best)) {
+        (Ok(a), Ok(b)) => compare_parts(&a, &b) == Ordering::Equal,
+        _ => old_best == new_best,
+    };
+    (!same).then(|| BestVersionChange {
+        old: old_best.to_string(),
+        new: new_best.to_string(),
+    })
+}
+
+fn diff_package(old: &Package, new: &Package) -> PackageDiff {
+    let mut result = PackageDiff {
+        atom: new.cp(),
+        old_versions: version_strings(old),
+        new_versions: version_strings(new),
+        best_version: best_version_change(old, new),
+        added_versions: Vec::new(),
+        removed_versions: Vec::new(),
+        keyword_changes: Vec::new(),
+        mask_changes: Vec::new(),
+        field_changes: Vec::new(),
+    };
+
+    for (field, old_value, new_value) in [
+        (
+            PackageField::Description,
+            &old.description,
+            &new.description,
+        ),
+        (PackageField::Homepage, &old.homepage, &new.homepage),
+        (PackageField::Licenses, &old.licenses, &new.licenses),
+    ] {
+        if old_value != new_value {
+            result.field_changes.push(FieldChange {
+                field,
+                old: old_value.clone(),
+                new: new_value.clone(),
+            });
+        }
+    }
+
+    let mut matched = vec![false; old.versions.len()];
+    for v in &new.versions {
+        let key = VersionId::of(v);
+        let found = old
+            .versions
+            .iter()
+            .enumerate()
+            .position(|(i, old_v)| !matched[i] && same_version(old_v, v));
+        let Some(index) = found else {
+            result.added_versions.push(key);
+            continue;
+        };
+        matched[index] = true;
+        let old_v = &old.versions[index];
+        if old_v.keywords != v.keywords {
+            result.keyword_changes.push(KeywordChange {
+                version: key.clone(),
+                old: old_v.keywords.to_vec(),
+                new: v.keywords.to_vec(),
+            });
+        }
+        if old_v.mask_flags != v.mask_flags {
+            result.mask_changes.push(MaskChange {
+                version: key,
+                old: old_v.mask_flags,
+                new: v.mask_flags,
+            });
+        }
+    }
+    for (v, matched) in old.versions.iter().zip(matched) {
+        if !matched {
+            result.removed_versions.push(VersionId::of(v));
+        }
+    }
+    result
+}
+
+/// Compares two databases, matching packages by category and name
+/// and versions by version, slot and repository
+pub fn diff(old: &[Package], new: &[Package]) -> DbDiff {
+    diff_packages(old.iter().collect(), new.iter().collect())
+}
+
+/// Like [`diff`] for indexed databases; the packages of the result are
+/// in the order of the indexes
+pub fn diff_index(old: &PackageIndex, new: &PackageIndex) -> DbDiff {
+    diff_packages(old.packages().collect(), new.packages().collect())
+}
+
+fn diff_packages(old: Vec<&Package>, new: Vec<&Package>) -> DbDiff {
+    let old_by_atom: HashMap<PackageKey, &Package> = old.iter().map(|p| (p.key(), *p)).collect();
+    let new_atoms: HashSet<PackageKey> = new.iter().map(|p| p.key()).collect();
+    let mut result = DbDiff::default();
+
+    for pkg in new {
+        match old_by_atom.get(&pkg.key()) {
+            None => result.added.push(entry(pkg)),
+            Some(old_pkg) => {
+                let package_diff = diff_package(old_pkg, pkg);
+                if !package_diff.is_empty() {
+                    result.changed.push(package_diff);
+                }
+            }
+        }
+    }
+    for pkg in old {
+        if !new_atoms.contains(&pkg.key()) {
+            result.removed.push(entry(pkg));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MASK_PACKAGE, parse_version};
+
+    fn version(v: &str, keywords: &[&str], mask_flags: u8) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            mask_flags,
+            reponame: "gentoo".into(),
+            ..Default::default()
+        }
+    }
+
+    fn package(cp: &str, versions: Vec<Version>) -> Package {
+        let (category, name) = cp.split_once('/').unwrap();
+        Package {
+            category: category.to_string(),
+            name: name.to_string(),
+            description: "A package".to_string(),
+            homepage: "https://example.org/".to_string(),
+            licenses: "MIT".to_string(),
+            versions,
+            ..Default::default()
+        }
+    }
+
+    fn key(v: &str) -> VersionId {
+        VersionId {
+            version: v.to_string(),
+            slot: String::new(),
+            repo: "gentoo".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = vec![
+            package("dev-lang/rust", vec![version("1.77.1", &["amd64"], 0)]),
+            package("app-misc/gone", vec![version("1", &[], 0)]),
+            package("app-misc/same", vec![version("2", &["amd64"], 0)]),
+            package(
+                "dev-libs/foo",
+                vec![
+                    version("1.0", &["~amd64"], 0),
+                    version("1.1", &["~amd64"], 0),
+                ],
+            ),
+        ];
+        let mut foo = package(
+            "dev-libs/foo",
+            vec![
+                version("1.0", &["amd64"], 0),
+                version("1.1", &["~amd64"], MASK_PACKAGE),
+            ],
+        );
+        foo.description = "A better package".to_string();
+        foo.licenses = "MIT BSD".to_string();
+        let new = vec![
+            package("app-misc/newpkg", vec![version("1.0", &[], 0)]),
+            package("app-misc/same", vec![version("2", &["amd64"], 0)]),
+            foo,
+            package(
+                "dev-lang/rust",
+                vec![
+                    version("1.77.1", &["amd64"], 0),
+                    version("1.78.0", &["~amd64"], 0),
+                ],
+            ),
+        ];
+
+        let result = diff(&old, &new);
+        assert_eq!(
+            result.added,
+            vec![PackageEntry {
+                atom: "app-misc/newpkg".to_string(),
+                versions: vec!["1.0".to_string()],
+            }]
+        );
+        assert_eq!(result.removed.len(), 1);
+        assert_eq!(result.removed[0].atom, "app-misc/gone");
+        assert_eq!(result.changed.len(), 2);
+
+        let foo = &result.changed[0];
+        assert_eq!(foo.atom, "dev-libs/foo");
+        assert!(foo.added_versions.is_empty() && foo.removed_versions.is_empty());
+        assert_eq!(
+            foo.keyword_changes,
+            vec![KeywordChange {
+                version: key("1.0"),
+                old: vec!["~amd64".to_string()],
+                new: vec!["amd64".to_string()],
+            }]
+        );
+        assert_eq!(
+            foo.mask_changes,
+            vec![MaskChange {
+                version: key("1.1"),
+                old: 0,
+                new: MASK_PACKAGE,
+            }]
+        );
+        let fields: Vec<PackageField> = foo.field_changes.iter().map(|c| c.field).collect();
+        assert_eq!(
+            fields,
+            vec![PackageField::Description, PackageField::Licenses]
+        );
+
+        let rust = &result.changed[1];
+        assert_eq!(rust.added_versions, vec![key("1.78.0")]);
+        assert_eq!(rust.old_versions, vec!["1.77.1"]);
+        assert!(rust.field_changes.is_empty());
+
+        assert!(diff(&new, &new).is_empty());
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["changed"][0]["mask_changes"][0]["new"], 1);
+    }
+
+    #[test]
+    fn test_diff_version_identity() {
+        let old = vec![package("dev-libs/foo", vec![version("1.0", &[], 0)])];
+        let mut moved = version("1.0", &[], 0);
+        moved.reponame = "guru".into();
+        let mut reslotted = version("1.0", &[], 0);
+        reslotted.slot = "2".to_string();
+        let new = vec![package("dev-libs/foo", vec![moved, reslotted])];
+
+        let result = diff(&old, &new);
+        let foo = &result.changed[0];
+        assert_eq!(foo.added_versions.len(), 2);
+        assert_eq!(foo.removed_versions, vec![key("1.0")]);
+    }
+
+    #[test]
+    fn test_diff_compares_parts() {
+        let old = vec![package(
+            "dev-libs/foo",
+            vec![version("1.0", &["~amd64"], 0), version("2.0", &[], 0)],
+        )];
+        // Versions read from JSON have no parts
+        let mut unparsed = version("2.0-r0", &[], 0);
+        unparsed.parts.clear();
+        let new = vec![package(
+            "dev-libs/foo",
+            vec![version("1.0-r0", &["amd64"], 0), unparsed],
+        )];
+
+        let result = diff(&old, &new);
+        let foo = &result.changed[0];
+        assert!(foo.added_versions.is_empty() && foo.removed_versions.is_empty());
+        assert_eq!(foo.keyword_changes[0].version, key("1.0-r0"));
+        assert_eq!(foo.best_version, None);
+
+        let newer = vec![package("dev-libs/foo", vec![version("2.0-r1", &[], 0)])];
+        assert_eq!(
+            diff(&old, &newer).changed[0].best_version,
+            Some(BestVersionChange {
+                old: "2.0".to_string(),
+                new: "2.0-r1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let old = vec![
+            package("dev-lang/rust", vec![version("1.77.1", &["amd64"], 0)]),
+            package("old/gone", vec![version("1", &[], 0)]),
+            package("dev-libs/foo", vec![version("1.1", &["~amd64"], 0)]),
+        ];
+        let new = vec![
+            package(
+                "dev-lang/rust",
+                vec![
+                    version("1.78.0", &["~amd64"], 0),
+                    version("1.77.1", &["amd64"], 0),
+                ],
+            ),
+            package("dev-libs/foo", vec![version("1.1", &["amd64"], 0)]),
+            package("app-misc/newpkg", vec![version("1.0", &[], 0)]),
+        ];
+        let result = diff(&old, &new);
+
+        let mut out = Vec::new();
+        result.render(&mut out, RenderOptions::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[N] app-misc/newpkg (1.0)\n\
+             [>] dev-lang/rust (1.77.1 -> 1.78.0)\n\
+             [*] dev-libs/foo (1.1)\n\
+             [<] old/gone\n\
+             1 new, 1 removed, 2 updated\n"
+        );
+
+        let mut out = Vec::new();
+        let options = RenderOptions {
+            color: true,
+            summary: false,
+        };
+        DbDiff {
+            removed: result.removed.clone(),
+            ..Default::default()
+        }
+        .render(&mut out, options)
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[1;31m[<]\x1b[0m old/gone\n"
+        );
+    }
+
+    #[test]
+    fn test_to_events() {
+        let old = vec![
+            package(
+                "dev-python/requests",
+                vec![version("2.32.3", &["~amd64", "~arm64", "x86"], 0)],
+            ),
+            package("dev-libs/foo", vec![version("1.0", &["amd64"], 0)]),
+        ];
+        let new = vec![
+            package(
+                "dev-python/requests",
+                vec![version("2.32.3", &["amd64", "~arm64", "-x86"], 0)],
+            ),
+            package(
+                "dev-libs/foo",
+                vec![
+                    version("1.0", &["amd64"], MASK_PACKAGE),
+                    version("1.1", &[], 0),
+                ],
+            ),
+            package("app-misc/newpkg", vec![version("1.0", &[], 0)]),
+        ];
+        let events = diff(&old, &new).to_events();
+        let kinds: Vec<EventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::PackageAdded,
+                EventKind::VersionAdded,
+                EventKind::KeywordChanged,
+                EventKind::KeywordChanged,
+                EventKind::VersionAdded,
+                EventKind::MaskChanged,
+            ]
+        );
+
+        // The field names and values are part of the schema
+        let json: Vec<String> = events
+            .iter()
+            .map(|e| serde_json::to_string(e).unwrap())
+            .collect();
+        assert_eq!(
+            json[0],
+            r#"{"kind":"package_added","atom":"app-misc/newpkg","version":null,"arch":null,"old":null,"new":null}"#
+        );
+        assert_eq!(
+            json[2],
+            r#"{"kind":"keyword_changed","atom":"dev-python/requests","version":"2.32.3","arch":"amd64","old":"~amd64","new":"amd64"}"#
+        );
+        assert_eq!(
+            json[3],
+            r#"{"kind":"keyword_changed","atom":"dev-python/requests","version":"2.32.3","arch":"x86","old":"x86","new":"-x86"}"#
+        );
+        assert_eq!(
+            json[5],
+            r#"{"kind":"mask_changed","atom":"dev-libs/foo","version":"1.0","arch":null,"old":"0","new":"1"}"#
+        );
+
+        let removed = diff(&new, &old).to_events();
+        assert_eq!(removed[0].kind, EventKind::PackageRemoved);
+        assert_eq!(removed[1].kind, EventKind::VersionRemoved);
+        assert_eq!(removed[1].version.as_deref(), Some("1.0"));
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let old = vec![
+            package("dev-python/a", vec![version("1", &["~amd64", "~x86"], 0)]),
+            package("dev-libs/b", vec![version("1", &["~amd64"], 0)]),
+        ];
+        let new = vec![
+            package("dev-python/a", vec![version("1", &["amd64", "x86"], 0)]),
+            package("dev-libs/b", vec![version("1", &["amd64"], 0)]),
+        ];
+        let filter = EventFilter {
+            atom_glob: Some("dev-python/*".to_string()),
+            arch: Some("amd64".to_string()),
+        };
+        let events = diff(&old, &new).events_matching(&filter);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].atom, "dev-python/a");
+        assert_eq!(events[0].arch.as_deref(), Some("amd64"));
+        assert_eq!(
+            diff(&old, &new)
+                .events_matching(&EventFilter::default())
+                .len(),
+            3
+        );
+
+        assert!(glob_match("dev-*/a?c", "dev-python/abc"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("dev-python/*", "dev-libs/b"));
+        assert!(!glob_match("a*b", "a-b-c"));
+    }
+}
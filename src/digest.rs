@@ -0,0 +1,130 @@
+//! Fingerprints of databases (feature `digest`)
+//!
+//! [`file_digest`] hashes the bytes of a file, so it changes with every
+//! `eix-update`. [`content_digest`] hashes the packages in a canonical
+//! form instead: the order of packages, versions and string hash
+//! entries, the overlay keys and priorities and the byte ranges are left
+//! out, so two files describing the same tree have the same digest.
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! use eix::content_digest;
+//!
+//! let (_, mut packages) = eix::sample::parsed();
+//! let digest = content_digest(&packages);
+//! packages.reverse();
+//! assert_eq!(content_digest(&packages), digest);
+//! ```
+
+use crate::{Package, Version, compare_parts};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Feeds values to the hash, each with its length, so adjacent values
+/// cannot be confused
+struct Canonical(Sha256);
+
+impl Canonical {
+    fn num(&mut self, n: u64) {
+        self.0.update(n.to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        self.num(s.len() as u64);
+        self.0.update(s.as_bytes());
+    }
+
+    fn words<S: AsRef<str>>(&mut self, words: &[S]) {
+        self.num(words.len() as u64);
+        words.iter().for_each(|w| self.str(w.as_ref()));
+    }
+
+    fn version(&mut self, v: &Version) {
+        self.str(&v.version_string);
+        self.str(&v.slot);
+        self.str(&v.reponame);
+        self.str(&v.eapi);
+        self.num(v.mask_flags.into());
+        self.num(v.properties_flags.into());
+        self.num(v.restrict_flags);
+        self.words(&v.keywords);
+        self.words(&v.iuse);
+        self.words(&v.required_use);
+        match v.depend() {
+            Some(d) => {
+                self.num(1);
+                for list in [&d.depend, &d.rdepend, &d.pdepend, &d.bdepend, &d.idepend] {
+                    self.words(list);
+                }
+            }
+            None => self.num(0),
+        }
+        match &v.src_uri {
+            Some(src_uri) => {
+                self.num(1);
+                self.str(src_uri);
+            }
+            None => self.num(0),
+        }
+    }
+
+    fn package(&mut self, p: &Package) {
+        self.str(&p.category);
+        self.str(&p.name);
+        self.str(&p.description);
+        self.str(&p.homepage);
+        self.str(&p.licenses);
+        let mut versions: Vec<&Version> = p.versions.iter().collect();
+        versions.sort_by(|a, b| compare_canonical(a, b));
+        self.num(versions.len() as u64);
+        versions.into_iter().for_each(|v| self.version(v));
+    }
+}
+
+/// Orders versions by version, then by everything identifying them
+/// apart from the overlay order
+fn compare_canonical(a: &Version, b: &Version) -> Ordering {
+    compare_parts(&a.parts, &b.parts)
+        .then_with(|| a.version_string.cmp(&b.version_string))
+        .then_with(|| a.reponame.cmp(&b.reponame))
+        .then_with(|| a.slot.cmp(&b.slot))
+}
+
+/// SHA-256 of the packages in a canonical form, see the
+/// [module documentation](self)
+pub fn content_digest(packages: &[Package]) -> [u8; 32] {
+    let mut sorted: Vec<&Package> = packages.iter().collect();
+    sorted.sort_by(|a, b| crate::sort::compare_atoms(a, b));
+    let mut canonical = Canonical(Sha256::new());
+    canonical.num(sorted.len() as u64);
+    sorted.into_iter().for_each(|p| canonical.package(p));
+    canonical.0.finalize().into()
+}
+
+/// SHA-256 of the bytes of the file at `path`
+#[cfg(feature = "fs")]
+pub fn file_digest<P: AsRef<Path>>(path: P) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+#[cfg(feature = "fs")]
+impl crate::DatabaseInfo {
+    /// The [`file_digest`] of the database
+    pub fn file_digest(&self) -> io::Result<[u8; 32]> {
+        file_digest(&self.path)
+    }
+
+    /// Reads all packages of the database and returns their
+    /// [`content_digest`]
+    pub fn content_digest(&self) -> io::Result<[u8; 32]> {
+        Ok(content_digest(&crate::ParsedDb::load(&self.path)?.packages))
+    }
+}
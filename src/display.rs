@@ -0,0 +1,517 @@
+//! `Display` for packages, versions, overlays and headers
+//!
+//! The plain form is a single line; the alternate form (`{:#}`) adds
+//! details: all versions of a package, the flags of a version and the
+//! priority of an overlay. `describe` gives a readable one-line summary,
+//! and [`Package::cpv`] and its siblings the canonical atom strings.
+//! [`Package::display_for`] shows the versions with eix's markers for
+//! one architecture:
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! let (_, packages) = eix::sample::parsed();
+//! let line = packages[0].display_for("amd64").to_string();
+//! assert!(line.starts_with("app-misc/screen  Available: 4.9.1 "));
+//! ```
+
+use crate::{
+    DB_VERSION_CURRENT, DBHeader, KeywordState, MASK_FLAG_NAMES, MASK_IN_PROFILE, MASK_MARKED,
+    MASK_PACKAGE, MASK_PROFILE, MASK_SYSTEM, MASK_WORLD, MASK_WORLD_SETS, OverlayIdent,
+    PROPERTIES_FLAG_NAMES, Package, RESTRICT_FLAG_NAMES, Version, flag_names, split_slot,
+};
+use std::fmt;
+
+/// Writes ` label=name1,name2` if any name is given
+fn write_names<'a>(
+    f: &mut fmt::Formatter<'_>,
+    label: &str,
+    mut names: impl Iterator<Item = &'a str>,
+) -> fmt::Result {
+    if let Some(first) = names.next() {
+        write!(f, " {}={}", label, first)?;
+        for name in names {
+            write!(f, ",{}", name)?;
+        }
+    }
+    Ok(())
+}
+
+/// `9.1.0000:0::gentoo [~amd64 x86]`; the alternate form adds the EAPI
+/// and the names of the set flags
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (slot, subslot) = split_slot(&self.slot);
+        write!(f, "{}:{}", self.version_string, slot)?;
+        if let Some(subslot) = subslot {
+            write!(f, "/{}", subslot)?;
+        }
+        if !self.reponame.is_empty() {
+            write!(f, "::{}", self.reponame)?;
+        }
+        if let Some((first, rest)) = self.keywords.split_first() {
+            write!(f, " [{}", first)?;
+            for kw in rest {
+                write!(f, " {}", kw)?;
+            }
+            write!(f, "]")?;
+        }
+        if f.alternate() {
+            write!(f, " EAPI={}", self.eapi)?;
+            write_names(f, "mask", flag_names(self.mask_flags, MASK_FLAG_NAMES))?;
+            write_names(
+                f,
+                "properties",
+                flag_names(self.properties_flags, PROPERTIES_FLAG_NAMES),
+            )?;
+            write_names(
+                f,
+                "restrict",
+                flag_names(self.restrict_flags, RESTRICT_FLAG_NAMES),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `app-editors/vim-9.1.0000: description` with the highest version; the
+/// alternate form adds homepage, license and one line per version
+impl fmt::Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.category, self.name)?;
+        if f.alternate() {
+            write!(f, ": {}", self.description)?;
+            write!(f, "\n  Homepage: {}", self.homepage)?;
+            write!(f, "\n  License: {}", self.licenses)?;
+            for v in &self.versions {
+                write!(f, "\n  {:#}", v)?;
+            }
+            return Ok(());
+        }
+        if let Some(best) = self.versions.iter().max_by(|a, b| a.compare(b)) {
+            write!(f, "-{}", best.version_string)?;
+        }
+        write!(f, ": {}", self.description)
+    }
+}
+
+/// `[gentoo] /var/db/repos/gentoo`; the alternate form adds the priority
+impl fmt::Display for OverlayIdent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.label, self.path)?;
+        if f.alternate() {
+            write!(f, " (priority {})", self.priority)?;
+        }
+        Ok(())
+    }
+}
+
+/// ```text
+/// Database version 39, 174 categories
+/// Overlays:
+///   [0] gentoo /var/db/repos/gentoo/ (priority 0)
+/// Stored: DEPEND, REQUIRED_USE, SRC_URI
+/// Hashes: eapi 2, license 1925, keywords 37, iuse 8288, slot 1859, depend 48053
+/// World sets: (none)
+/// ```
+impl fmt::Display for DBHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Database version {}, {} categories",
+            self.version, self.size
+        )?;
+        writeln!(f, "Overlays:")?;
+        for (key, overlay) in self.overlays.iter().enumerate() {
+            writeln!(
+                f,
+                "  [{}] {} {} (priority {})",
+                key, overlay.label, overlay.path, overlay.priority
+            )?;
+        }
+        let stored: Vec<&str> = [
+            (self.use_depend, "DEPEND"),
+            (self.use_required_use, "REQUIRED_USE"),
+            (self.use_src_uri, "SRC_URI"),
+        ]
+        .into_iter()
+        .filter(|&(used, _)| used)
+        .map(|(_, name)| name)
+        .collect();
+        if stored.is_empty() {
+            writeln!(f, "Stored: (none)")?;
+        } else {
+            writeln!(f, "Stored: {}", stored.join(", "))?;
+        }
+        writeln!(
+            f,
+            "Hashes: eapi {}, license {}, keywords {}, iuse {}, slot {}, depend {}",
+            self.eapi_hash.len(),
+            self.license_hash.len(),
+            self.keywords_hash.len(),
+            self.iuse_hash.len(),
+            self.slot_hash.len(),
+            self.depend_hash.len()
+        )?;
+        if self.world_sets.is_empty() {
+            writeln!(f, "World sets: (none)")
+        } else {
+            writeln!(f, "World sets: {}", self.world_sets.join(" "))
+        }
+    }
+}
+
+/// A package with its versions marked for one architecture, see
+/// [`Package::display_for`]
+#[derive(Debug, Clone, Copy)]
+pub struct PackageLine<'a> {
+    package: &'a Package,
+    arch: &'a str,
+}
+
+/// `dev-lang/rust  Available: 1.74.1 ~1.75.0  Homepage: …  Description: …`
+/// with the versions as [`Version::keyword_marker`] shows them
+impl fmt::Display for PackageLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}  Available:",
+            self.package.category, self.package.name
+        )?;
+        for v in &self.package.versions {
+            write!(f, " {}", v.keyword_marker(self.arch))?;
+        }
+        write!(
+            f,
+            "  Homepage: {}  Description: {}",
+            self.package.homepage, self.package.description
+        )
+    }
+}
+
+/// How [`Version::describe`] words the mask flags which are no masks
+const MARK_CLAUSES: &[(u8, &str)] = &[
+    (MASK_SYSTEM, "in system"),
+    (MASK_WORLD, "in world"),
+    (MASK_WORLD_SETS, "in world sets"),
+    (MASK_IN_PROFILE, "in profile"),
+    (MASK_MARKED, "marked"),
+];
+
+impl Version {
+    /// A readable summary like
+    /// `1.2.3-r1 (slot 2, EAPI 8, ~amd64 arm64, masked by profile, RESTRICT=mirror, ::guru)`.
+    /// Without a repository name, the label of the overlay is taken
+    /// from `header`.
+    pub fn describe(&self, header: Option<&DBHeader>) -> String {
+        let mut clauses = Vec::new();
+        let (slot, subslot) = split_slot(&self.slot);
+        match subslot {
+            Some(subslot) => clauses.push(format!("slot {}/{}", slot, subslot)),
+            None => clauses.push(format!("slot {}", slot)),
+        }
+        if !self.eapi.is_empty() {
+            clauses.push(format!("EAPI {}", self.eapi));
+        }
+        if self.keywords.is_empty() {
+            clauses.push("no keywords".to_string());
+        } else {
+            clauses.push(self.keywords.join(" "));
+        }
+        let masked_by: Vec<&str> = [(MASK_PACKAGE, "package.mask"), (MASK_PROFILE, "profile")]
+            .into_iter()
+            .filter(|&(flag, _)| self.mask_flags & flag != 0)
+            .map(|(_, name)| name)
+            .collect();
+        if !masked_by.is_empty() {
+            clauses.push(format!("masked by {}", masked_by.join(" and ")));
+        }
+        clauses.extend(flag_names(self.mask_flags, MARK_CLAUSES).map(str::to_string));
+        let properties: Vec<&str> =
+            flag_names(self.properties_flags, PROPERTIES_FLAG_NAMES).collect();
+        if !properties.is_empty() {
+            clauses.push(format!("PROPERTIES={}", properties.join(" ")));
+        }
+        let restrict: Vec<&str> = flag_names(self.restrict_flags, RESTRICT_FLAG_NAMES).collect();
+        if !restrict.is_empty() {
+            clauses.push(format!("RESTRICT={}", restrict.join(" ")));
+        }
+        let repo = match (&*self.reponame, header) {
+            ("", Some(header)) => self.overlay(header).map_or("", |overlay| &*overlay.label),
+            (repo, _) => repo,
+        };
+        if !repo.is_empty() {
+            clauses.push(format!("::{}", repo));
+        }
+        format!("{} ({})", self.version_string, clauses.join(", "))
+    }
+}
+
+impl Version {
+    /// The version string with eix's markers for `arch`: `[M]` if masked
+    /// by package.mask or the profile, then `~` for testing, `-` for
+    /// broken, `*` without a keyword for `arch` (or with `-*`) and `**`
+    /// without any keywords, e.g. `[M]~1.75.0` or `**9999`
+    pub fn keyword_marker(&self, arch: &str) -> String {
+        let keyword = match self.keyword_state(arch) {
+            KeywordState::Stable => "",
+            KeywordState::Testing => "~",
+            KeywordState::Broken => "-",
+            KeywordState::MaskedAll | KeywordState::Missing => "*",
+            KeywordState::NoKeywords => "**",
+        };
+        let mask = if self.is_masked() { "[M]" } else { "" };
+        format!("{}{}{}", mask, keyword, self.version_string)
+    }
+}
+
+impl Package {
+    /// Displays the package in one line with its versions marked for
+    /// `arch`, see [`PackageLine`]
+    pub fn display_for<'a>(&'a self, arch: &'a str) -> PackageLine<'a> {
+        PackageLine {
+            package: self,
+            arch,
+        }
+    }
+
+    /// `category/name`
+    pub fn cp(&self) -> String {
+        format!("{}/{}", self.category, self.name)
+    }
+
+    /// `category/name-version` of the version `v` of this package
+    pub fn cpv(&self, v: &Version) -> String {
+        format!("{}/{}-{}", self.category, self.name, v.version_string)
+    }
+
+    /// `category/name-version::repo`; without the repository if
+    /// [`Version::reponame`] is empty
+    pub fn cpv_with_repo(&self, v: &Version) -> String {
+        let mut cpv = self.cpv(v);
+        if !v.reponame.is_empty() {
+            cpv.push_str("::");
+            cpv.push_str(&v.reponame);
+        }
+        cpv
+    }
+
+    /// `category/name-version:slot/subslot::repo` as Portage displays it:
+    /// the slot is left out if it is "0" (stored as "") without subslot
+    pub fn cpv_full(&self, v: &Version) -> String {
+        let mut cpv = self.cpv(v);
+        match (v.normalized_slot(), v.subslot()) {
+            ("0", None) => {}
+            (slot, None) => cpv.push_str(&format!(":{}", slot)),
+            (slot, Some(subslot)) => cpv.push_str(&format!(":{}/{}", slot, subslot)),
+        }
+        if !v.reponame.is_empty() {
+            cpv.push_str("::");
+            cpv.push_str(&v.reponame);
+        }
+        cpv
+    }
+
+    /// [`cpv`](Self::cpv) of each version in order
+    pub fn cpvs(&self) -> impl Iterator<Item = String> + '_ {
+        self.versions.iter().map(|v| self.cpv(v))
+    }
+
+    /// A readable summary like
+    /// `app-misc/screen: 4 versions, slot 0, best 9999, live, 1 masked`.
+    /// The best version is the highest unmasked one; `live` marks a
+    /// version with PROPERTIES=live, as in a current database.
+    pub fn describe(&self) -> String {
+        let mut slots: Vec<&str> = Vec::new();
+        for v in &self.versions {
+            if !slots.contains(&v.normalized_slot()) {
+                slots.push(v.normalized_slot());
+            }
+        }
+        let mut out = format!("{}: ", self.cp());
+        match self.versions.len() {
+            1 => out.push_str("1 version"),
+            n => out.push_str(&format!("{} versions", n)),
+        }
+        match slots.len() {
+            0 => {}
+            1 => out.push_str(&format!(", slot {}", slots[0])),
+            _ => out.push_str(&format!(", slots {}", slots.join(" "))),
+        }
+        match self.best_version(None, false) {
+            Some(best) => out.push_str(&format!(", best {}", best.version_string)),
+            None if !self.versions.is_empty() => out.push_str(", all masked"),
+            None => {}
+        }
+        if self.has_live_version(DB_VERSION_CURRENT) {
+            out.push_str(", live");
+        }
+        let masked = self.versions.iter().filter(|v| v.is_masked()).count();
+        if masked > 0 && masked < self.versions.len() {
+            out.push_str(&format!(", {} masked", masked));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::words;
+    use crate::{
+        PROPERTIES_INTERACTIVE, PROPERTIES_LIVE, RESTRICT_MIRROR, RESTRICT_TEST, StringHash,
+    };
+
+    fn version(version: &str) -> Version {
+        Version {
+            version_string: version.to_string(),
+            parts: crate::parse_version(version).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_keyword_marker() {
+        let mut v = version("1.75.0");
+        assert_eq!(v.keyword_marker("amd64"), "**1.75.0");
+        v.keywords = words("amd64 ~arm64 -sparc");
+        assert_eq!(v.keyword_marker("amd64"), "1.75.0");
+        assert_eq!(v.keyword_marker("arm64"), "~1.75.0");
+        assert_eq!(v.keyword_marker("sparc"), "-1.75.0");
+        assert_eq!(v.keyword_marker("x86"), "*1.75.0");
+        v.keywords = words("-* ~amd64");
+        assert_eq!(v.keyword_marker("amd64"), "~1.75.0");
+        assert_eq!(v.keyword_marker("x86"), "*1.75.0");
+        v.mask_flags = MASK_PACKAGE;
+        assert_eq!(v.keyword_marker("amd64"), "[M]~1.75.0");
+        // Marks in the world file are no masks
+        v.mask_flags = MASK_WORLD;
+        assert_eq!(v.keyword_marker("amd64"), "~1.75.0");
+    }
+
+    #[test]
+    fn test_cpv() {
+        let mut v = version("1.2.3-r1");
+        v.reponame = "gentoo".into();
+        let mut pkg = Package {
+            category: "app-misc".to_string(),
+            name: "screen".to_string(),
+            versions: vec![v.clone(), version("9999")],
+            ..Default::default()
+        };
+        assert_eq!(pkg.cp(), "app-misc/screen");
+        assert_eq!(pkg.cpv(&v), "app-misc/screen-1.2.3-r1");
+        assert_eq!(pkg.cpv_with_repo(&v), "app-misc/screen-1.2.3-r1::gentoo");
+        // The empty slot means "0", which is not shown
+        assert_eq!(pkg.cpv_full(&v), "app-misc/screen-1.2.3-r1::gentoo");
+        v.slot = "0".to_string();
+        assert_eq!(pkg.cpv_full(&v), "app-misc/screen-1.2.3-r1::gentoo");
+        assert_eq!(
+            pkg.cpvs().collect::<Vec<_>>(),
+            ["app-misc/screen-1.2.3-r1", "app-misc/screen-9999"]
+        );
+
+        let mut v = version("2.0");
+        v.reponame = "guru".into();
+        v.slot = "2/2.0".to_string();
+        assert_eq!(pkg.cpv_with_repo(&v), "app-misc/screen-2.0::guru");
+        assert_eq!(pkg.cpv_full(&v), "app-misc/screen-2.0:2/2.0::guru");
+        v.slot = "/1".to_string();
+        assert_eq!(pkg.cpv_full(&v), "app-misc/screen-2.0:0/1::guru");
+        v.slot = "3".to_string();
+        v.reponame = "".into();
+        assert_eq!(pkg.cpv_with_repo(&v), "app-misc/screen-2.0");
+        assert_eq!(pkg.cpv_full(&v), "app-misc/screen-2.0:3");
+
+        pkg.versions.clear();
+        assert_eq!(pkg.cpvs().count(), 0);
+    }
+
+    #[test]
+    fn test_describe_version() {
+        let mut v = version("1.2.3-r1");
+        assert_eq!(v.describe(None), "1.2.3-r1 (slot 0, no keywords)");
+
+        v.slot = "2".to_string();
+        v.eapi = "8".to_string();
+        v.keywords = vec!["~amd64".to_string(), "arm64".to_string()];
+        v.mask_flags = MASK_PROFILE;
+        v.restrict_flags = RESTRICT_MIRROR;
+        v.reponame = "guru".into();
+        assert_eq!(
+            v.describe(None),
+            "1.2.3-r1 (slot 2, EAPI 8, ~amd64 arm64, masked by profile, RESTRICT=mirror, ::guru)"
+        );
+
+        v.slot = "2/2.1".to_string();
+        v.mask_flags = MASK_PACKAGE | MASK_PROFILE | MASK_WORLD;
+        v.properties_flags = PROPERTIES_INTERACTIVE | PROPERTIES_LIVE;
+        v.restrict_flags = RESTRICT_TEST | RESTRICT_MIRROR;
+        assert_eq!(
+            v.describe(None),
+            "1.2.3-r1 (slot 2/2.1, EAPI 8, ~amd64 arm64, masked by package.mask and profile, \
+             in world, PROPERTIES=interactive live, RESTRICT=test mirror, ::guru)"
+        );
+    }
+
+    #[test]
+    fn test_describe_version_repo_from_header() {
+        let mut v = version("1.0");
+        v.overlay_key = 1;
+        let overlay = |label: &str| OverlayIdent {
+            path: format!("/var/db/repos/{}", label),
+            label: label.into(),
+            priority: 0,
+        };
+        let header = DBHeader {
+            version: crate::DB_VERSION_CURRENT,
+            size: 0,
+            overlays: vec![overlay("gentoo"), overlay("guru")],
+            eapi_hash: StringHash::new(),
+            license_hash: StringHash::new(),
+            keywords_hash: StringHash::new(),
+            iuse_hash: StringHash::new(),
+            slot_hash: StringHash::new(),
+            depend_hash: StringHash::new(),
+            use_depend: false,
+            use_required_use: false,
+            use_src_uri: false,
+            world_sets: Vec::new(),
+            warnings: Vec::new(),
+        };
+        assert_eq!(
+            v.describe(Some(&header)),
+            "1.0 (slot 0, no keywords, ::guru)"
+        );
+        v.overlay_key = 2;
+        assert_eq!(v.describe(Some(&header)), "1.0 (slot 0, no keywords)");
+    }
+
+    #[test]
+    fn test_describe_package() {
+        let mut package = Package {
+            category: "app-misc".to_string(),
+            name: "screen".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(package.describe(), "app-misc/screen: 0 versions");
+
+        let mut masked = version("5.0.1");
+        masked.mask_flags = MASK_PACKAGE;
+        package.versions.push(masked);
+        assert_eq!(
+            package.describe(),
+            "app-misc/screen: 1 version, slot 0, all masked"
+        );
+
+        let mut live = version("9999");
+        live.properties_flags = PROPERTIES_LIVE;
+        let mut slotted = version("4.9.1");
+        slotted.slot = "4/4.9".to_string();
+        package.versions.insert(0, slotted);
+        package.versions.push(live);
+        assert_eq!(
+            package.describe(),
+            "app-misc/screen: 3 versions, slots 4 0, best 9999, live, 1 masked"
+        );
+    }
+}
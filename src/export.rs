@@ -0,0 +1,322 @@
+//! Exporting packages while they are read
+//!
+//! [`write_csv`] writes one row per version with the fields of a
+//! [`FlatRecord`], for spreadsheets and SQL imports. `write_ndjson`
+//! (feature `json`) writes one package per line, e.g. to pipe a tree
+//! into `jq` or to bulk-load it. Both hold a single package in memory:
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! use eix::{CsvOptions, Database, PackageReader, write_csv};
+//!
+//! let mut db = Database::from_bytes(eix::sample::bytes());
+//! let header = db.read_header(0).unwrap();
+//! let mut out = Vec::new();
+//! let rows = write_csv(PackageReader::new(db, header), &mut out, &CsvOptions::default()).unwrap();
+//! let csv = String::from_utf8(out).unwrap();
+//! assert_eq!(csv.lines().count() as u64, rows + 1);
+//! assert!(csv.starts_with("category,name,version,slot,repo,"));
+//! ```
+
+#[cfg(feature = "json")]
+use crate::{HeaderView, SerializeOptions};
+use crate::{
+    MASK_FLAG_NAMES, PROPERTIES_FLAG_NAMES, Package, PackageReader, RESTRICT_FLAG_NAMES, Version,
+    flag_names,
+};
+#[cfg(feature = "json")]
+use serde::Serialize;
+use std::io::{self, BufWriter, Read, Write};
+
+/// The fields of a version as a flat record, see [`Version::flat_record`];
+/// flags are the names of those set, separated by spaces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatRecord<'a> {
+    pub category: &'a str,
+    pub name: &'a str,
+    pub version: &'a str,
+    /// Slot and subslot as stored, e.g. `3/3.12`
+    pub slot: &'a str,
+    pub repo: &'a str,
+    pub eapi: &'a str,
+    pub mask: String,
+    /// The keywords in their original order
+    pub keywords: String,
+    pub restrict: String,
+    pub properties: String,
+}
+
+impl FlatRecord<'_> {
+    /// The names of the fields, in the order of [`values`](Self::values)
+    pub const FIELDS: [&'static str; 10] = [
+        "category",
+        "name",
+        "version",
+        "slot",
+        "repo",
+        "eapi",
+        "mask",
+        "keywords",
+        "restrict",
+        "properties",
+    ];
+
+    /// The values of the fields, in the order of [`FIELDS`](Self::FIELDS)
+    pub fn values(&self) -> [&str; 10] {
+        [
+            self.category,
+            self.name,
+            self.version,
+            self.slot,
+            self.repo,
+            self.eapi,
+            &self.mask,
+            &self.keywords,
+            &self.restrict,
+            &self.properties,
+        ]
+    }
+}
+
+fn join(names: impl Iterator<Item = &'static str>) -> String {
+    names.collect::<Vec<_>>().join(" ")
+}
+
+impl Version {
+    /// The fields of the version in package `category/name` as a flat
+    /// record, e.g. for tabular exports
+    pub fn flat_record<'a>(&'a self, category: &'a str, name: &'a str) -> FlatRecord<'a> {
+        FlatRecord {
+            category,
+            name,
+            version: &self.version_string,
+            slot: &self.slot,
+            repo: &self.reponame,
+            eapi: &self.eapi,
+            mask: join(flag_names(self.mask_flags, MASK_FLAG_NAMES)),
+            keywords: self.keywords_string(),
+            restrict: join(flag_names(self.restrict_flags, RESTRICT_FLAG_NAMES)),
+            properties: join(flag_names(self.properties_flags, PROPERTIES_FLAG_NAMES)),
+        }
+    }
+}
+
+/// A column of [`write_csv`]: a field of [`FlatRecord`] or the
+/// description of the package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    Category,
+    Name,
+    Version,
+    Slot,
+    Repo,
+    Eapi,
+    Mask,
+    Keywords,
+    Restrict,
+    Properties,
+    Description,
+}
+
+impl CsvColumn {
+    pub const ALL: &[CsvColumn] = &[
+        CsvColumn::Category,
+        CsvColumn::Name,
+        CsvColumn::Version,
+        CsvColumn::Slot,
+        CsvColumn::Repo,
+        CsvColumn::Eapi,
+        CsvColumn::Mask,
+        CsvColumn::Keywords,
+        CsvColumn::Restrict,
+        CsvColumn::Properties,
+        CsvColumn::Description,
+    ];
+
+    /// The name used in column specs and the header row
+    pub fn name(self) -> &'static str {
+        match self {
+            CsvColumn::Description => "description",
+            column => FlatRecord::FIELDS[column as usize],
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        CsvColumn::ALL.iter().copied().find(|c| c.name() == name)
+    }
+
+    /// Parses a comma separated list of column names, e.g.
+    /// `category,name,version,mask`
+    pub fn parse_list(spec: &str) -> io::Result<Vec<Self>> {
+        spec.split(',')
+            .map(|name| {
+                CsvColumn::from_name(name.trim()).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unknown column: {}", name),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn value<'a>(self, package: &'a Package, record: &'a FlatRecord<'a>) -> &'a str {
+        match self {
+            CsvColumn::Description => &package.description,
+            column => record.values()[column as usize],
+        }
+    }
+}
+
+/// Options for [`write_csv`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// The columns in order; by default the fields of [`FlatRecord`]
+    pub columns: Vec<CsvColumn>,
+    /// Start with a row of the column names
+    pub header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            columns: CsvColumn::ALL[..FlatRecord::FIELDS.len()].to_vec(),
+            header: true,
+        }
+    }
+}
+
+/// Writes `field`, quoted if it contains a separator, a quote or a line
+/// break (RFC 4180)
+fn write_field<W: Write>(out: &mut W, field: &str) -> io::Result<()> {
+    if field.contains([',', '"', '\n', '\r']) {
+        write!(out, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        out.write_all(field.as_bytes())
+    }
+}
+
+fn write_row<'a, W: Write>(out: &mut W, fields: impl Iterator<Item = &'a str>) -> io::Result<()> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write_field(out, field)?;
+    }
+    out.write_all(b"\n")
+}
+
+/// Writes the versions of the remaining packages of `reader` to `out`
+/// as CSV, one row per version ending with a newline, and returns the
+/// number of rows written apart from the header. A read error ends the
+/// output and is returned with the category, the number of the package
+/// and its offset.
+pub fn write_csv<R: Read, W: Write>(
+    mut reader: PackageReader<R>,
+    out: W,
+    options: &CsvOptions,
+) -> io::Result<u64> {
+    let mut out = BufWriter::new(out);
+    if options.header {
+        write_row(&mut out, options.columns.iter().map(|c| c.name()))?;
+    }
+    let mut packages = 0;
+    let mut rows = 0;
+    while let Some(package) = next_package(&mut reader, packages)? {
+        packages += 1;
+        for version in &package.versions {
+            let record = version.flat_record(&package.category, &package.name);
+            let fields = options.columns.iter().map(|c| c.value(&package, &record));
+            write_row(&mut out, fields)?;
+            rows += 1;
+        }
+    }
+    out.flush()?;
+    Ok(rows)
+}
+
+#[cfg(feature = "json")]
+/// Options for [`write_ndjson_with`]
+#[derive(Debug, Clone, Default)]
+pub struct NdjsonOptions {
+    /// Write `{"header": …}` with the overlays and world sets as the
+    /// first line
+    pub include_header: bool,
+    /// The representation of the packages and the header
+    pub serialize: SerializeOptions,
+}
+
+#[cfg(feature = "json")]
+/// The first line with [`NdjsonOptions::include_header`]
+#[derive(Serialize)]
+struct HeaderLine<'a> {
+    header: HeaderView<'a>,
+}
+
+#[cfg(feature = "json")]
+/// Writes the remaining packages of `reader` to `out` as JSON, one
+/// package per line, and returns the number of packages written
+pub fn write_ndjson<R: Read, W: Write>(reader: PackageReader<R>, out: W) -> io::Result<u64> {
+    write_ndjson_with(reader, out, &NdjsonOptions::default())
+}
+
+#[cfg(feature = "json")]
+/// Like [`write_ndjson`] with the given options. A read error ends the
+/// output and is returned with the category, the number of the package
+/// and its offset; the lines written before stay valid.
+pub fn write_ndjson_with<R: Read, W: Write>(
+    mut reader: PackageReader<R>,
+    out: W,
+    options: &NdjsonOptions,
+) -> io::Result<u64> {
+    let mut out = BufWriter::new(out);
+    if options.include_header {
+        let header = options.serialize.view_header(reader.header());
+        serde_json::to_writer(&mut out, &HeaderLine { header })?;
+        out.write_all(b"\n")?;
+    }
+    let mut written = 0;
+    while let Some(package) = next_package(&mut reader, written)? {
+        serde_json::to_writer(&mut out, &options.serialize.view(&package))?;
+        out.write_all(b"\n")?;
+        written += 1;
+    }
+    out.flush()?;
+    Ok(written)
+}
+
+/// Reads the next package, moving on to the next category as needed;
+/// errors name the category and the number and offset of the package
+pub(crate) fn next_package<R: Read>(
+    reader: &mut PackageReader<R>,
+    read: u64,
+) -> io::Result<Option<Package>> {
+    loop {
+        let position = reader.position();
+        match reader.read_package() {
+            Ok(Some(package)) => return Ok(Some(package)),
+            Ok(None) => {}
+            Err(e) => {
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!(
+                        "Error reading package {} in category {} at byte {}: {}",
+                        read + 1,
+                        reader.current_category(),
+                        position,
+                        e
+                    ),
+                ));
+            }
+        }
+        let more = reader.next_category().map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Error reading category at byte {}: {}", position, e),
+            )
+        })?;
+        if !more {
+            return Ok(None);
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! C interface (feature `ffi`)
+//!
+//! Build a shared library with
+//! `cargo rustc --release --features ffi --crate-type cdylib`; the
+//! declarations are in `include/eix.h`, generated with
+//! `cbindgen --config cbindgen.toml --output include/eix.h`.
+//!
+//! Ownership:
+//! - An `EixDb` from [`eix_open`] is freed with [`eix_free_db`].
+//! - The strings of an [`EixPackage`] belong to the database and stay
+//!   valid until the next [`eix_next_package`] or [`eix_free_db`].
+//! - Strings returned by the `eix_version_*` accessors belong to the
+//!   caller and are freed with [`eix_free_string`].
+//! - The string of [`eix_last_error`] belongs to the database (or the
+//!   thread, for a failed [`eix_open`]) and stays valid until the next
+//!   call failing on it.
+//!
+//! Strings are UTF-8; NUL bytes within them are dropped.
+
+use crate::{Database, Package, PackageReader, Version};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::io;
+use std::ptr;
+
+/// An open database
+pub struct EixDb {
+    reader: PackageReader,
+    // The package last returned, boxed so `EixPackage::handle` stays valid
+    current: Option<Box<Current>>,
+    error: Option<CString>,
+}
+
+struct Current {
+    package: Package,
+    strings: [CString; 5],
+}
+
+/// A package as returned by [`eix_next_package`]
+#[repr(C)]
+pub struct EixPackage {
+    pub category: *const c_char,
+    pub name: *const c_char,
+    pub description: *const c_char,
+    pub homepage: *const c_char,
+    pub licenses: *const c_char,
+    pub version_count: usize,
+    /// Used by the `eix_version_*` accessors
+    pub handle: *const c_void,
+}
+
+thread_local! {
+    static OPEN_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).expect("NUL bytes removed")
+}
+
+fn open(path: *const c_char) -> io::Result<PackageReader> {
+    if path.is_null() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Path is NULL"));
+    }
+    // SAFETY: the caller passes a NUL-terminated string
+    let path = unsafe { CStr::from_ptr(path) }
+        .to_str()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut db = Database::open_read(path)?;
+    let header = db.read_header(0)?;
+    Ok(PackageReader::new(db, header))
+}
+
+/// Opens the database at `path` (UTF-8). Returns NULL on failure; the
+/// reason is then available from `eix_last_error(NULL)`.
+///
+/// # Safety
+///
+/// `path` must be NULL or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_open(path: *const c_char) -> *mut EixDb {
+    match open(path) {
+        Ok(reader) => Box::into_raw(Box::new(EixDb {
+            reader,
+            current: None,
+            error: None,
+        })),
+        Err(e) => {
+            OPEN_ERROR.with(|error| *error.borrow_mut() = Some(c_string(&e.to_string())));
+            ptr::null_mut()
+        }
+    }
+}
+
+fn next_package(reader: &mut PackageReader) -> io::Result<Option<Package>> {
+    loop {
+        if let Some(pkg) = reader.read_package()? {
+            return Ok(Some(pkg));
+        }
+        if !reader.next_category()? {
+            return Ok(None);
+        }
+    }
+}
+
+/// Reads the next package into `out`. Returns 1 if a package was read,
+/// 0 at the end of the database and -1 on error (see [`eix_last_error`]).
+///
+/// # Safety
+///
+/// `db` must come from [`eix_open`] and `out` point to an `EixPackage`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_next_package(db: *mut EixDb, out: *mut EixPackage) -> c_int {
+    // SAFETY: guaranteed by the caller
+    let (Some(db), Some(out)) = (unsafe { db.as_mut() }, unsafe { out.as_mut() }) else {
+        return -1;
+    };
+    db.current = None;
+    match next_package(&mut db.reader) {
+        Ok(Some(package)) => {
+            let strings = [
+                &package.category,
+                &package.name,
+                &package.description,
+                &package.homepage,
+                &package.licenses,
+            ]
+            .map(|s| c_string(s));
+            let current = db.current.insert(Box::new(Current { package, strings }));
+            *out = EixPackage {
+                category: current.strings[0].as_ptr(),
+                name: current.strings[1].as_ptr(),
+                description: current.strings[2].as_ptr(),
+                homepage: current.strings[3].as_ptr(),
+                licenses: current.strings[4].as_ptr(),
+                version_count: current.package.versions.len(),
+                handle: &**current as *const Current as *const c_void,
+            };
+            1
+        }
+        Ok(None) => 0,
+        Err(e) => {
+            db.error = Some(c_string(&e.to_string()));
+            -1
+        }
+    }
+}
+
+/// The version `index` of `pkg`, if both are valid
+///
+/// # Safety
+///
+/// `pkg` must be NULL or filled by [`eix_next_package`] of a database
+/// which was not advanced or freed since.
+unsafe fn version<'a>(pkg: *const EixPackage, index: usize) -> Option<&'a Version> {
+    // SAFETY: guaranteed by the caller
+    let pkg = unsafe { pkg.as_ref() }?;
+    let current = unsafe { (pkg.handle as *const Current).as_ref() }?;
+    current.package.versions.get(index)
+}
+
+/// Returns a string of the version `index` of `pkg`, or NULL if out of range
+unsafe fn version_string(
+    pkg: *const EixPackage,
+    index: usize,
+    field: impl FnOnce(&Version) -> String,
+) -> *mut c_char {
+    // SAFETY: guaranteed by the caller
+    match unsafe { version(pkg, index) } {
+        Some(v) => c_string(&field(v)).into_raw(),
+        None => ptr::null_mut(),
+    }
+}
+
+/// The full version, e.g. "1.2.3-r1". Free with [`eix_free_string`].
+///
+/// # Safety
+///
+/// `pkg` must be filled by [`eix_next_package`] of a database which was
+/// not advanced or freed since.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_string(pkg: *const EixPackage, index: usize) -> *mut c_char {
+    unsafe { version_string(pkg, index, |v| v.get_full_version()) }
+}
+
+/// The slot without subslot ("0" if none). Free with [`eix_free_string`].
+///
+/// # Safety
+///
+/// See [`eix_version_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_slot(pkg: *const EixPackage, index: usize) -> *mut c_char {
+    unsafe { version_string(pkg, index, |v| v.normalized_slot().to_string()) }
+}
+
+/// The EAPI. Free with [`eix_free_string`].
+///
+/// # Safety
+///
+/// See [`eix_version_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_eapi(pkg: *const EixPackage, index: usize) -> *mut c_char {
+    unsafe { version_string(pkg, index, |v| v.eapi.clone()) }
+}
+
+/// The keywords, separated by spaces. Free with [`eix_free_string`].
+///
+/// # Safety
+///
+/// See [`eix_version_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_keywords(pkg: *const EixPackage, index: usize) -> *mut c_char {
+    unsafe { version_string(pkg, index, |v| v.keywords.join(" ")) }
+}
+
+/// The USE flags, separated by spaces. Free with [`eix_free_string`].
+///
+/// # Safety
+///
+/// See [`eix_version_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_iuse(pkg: *const EixPackage, index: usize) -> *mut c_char {
+    unsafe { version_string(pkg, index, |v| v.iuse.join(" ")) }
+}
+
+/// The label of the repository. Free with [`eix_free_string`].
+///
+/// # Safety
+///
+/// See [`eix_version_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_repository(
+    pkg: *const EixPackage,
+    index: usize,
+) -> *mut c_char {
+    unsafe { version_string(pkg, index, |v| v.reponame.to_string()) }
+}
+
+/// The mask flags (`MASK_*`), or -1 if out of range
+///
+/// # Safety
+///
+/// See [`eix_version_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_mask_flags(pkg: *const EixPackage, index: usize) -> c_int {
+    unsafe { version(pkg, index) }.map_or(-1, |v| v.mask_flags as c_int)
+}
+
+/// The properties flags (`PROPERTIES_*`), or 0 if out of range
+///
+/// # Safety
+///
+/// See [`eix_version_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_properties_flags(pkg: *const EixPackage, index: usize) -> u32 {
+    unsafe { version(pkg, index) }.map_or(0, |v| v.properties_flags)
+}
+
+/// The restrict flags (`RESTRICT_*`), or 0 if out of range
+///
+/// # Safety
+///
+/// See [`eix_version_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_version_restrict_flags(pkg: *const EixPackage, index: usize) -> u64 {
+    unsafe { version(pkg, index) }.map_or(0, |v| v.restrict_flags)
+}
+
+/// The last error of `db`, or of the last failed [`eix_open`] of this
+/// thread if `db` is NULL. NULL if there was none.
+///
+/// # Safety
+///
+/// `db` must be NULL or come from [`eix_open`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_last_error(db: *const EixDb) -> *const c_char {
+    // SAFETY: guaranteed by the caller
+    match unsafe { db.as_ref() } {
+        Some(db) => db.error.as_ref().map_or(ptr::null(), |e| e.as_ptr()),
+        None => {
+            OPEN_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+        }
+    }
+}
+
+/// Closes a database
+///
+/// # Safety
+///
+/// `db` must be NULL or come from [`eix_open`] and not be freed before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_free_db(db: *mut EixDb) {
+    if !db.is_null() {
+        // SAFETY: guaranteed by the caller
+        drop(unsafe { Box::from_raw(db) });
+    }
+}
+
+/// Frees a string returned by an `eix_version_*` accessor
+///
+/// # Safety
+///
+/// `s` must be NULL or come from an accessor and not be freed before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eix_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: guaranteed by the caller
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
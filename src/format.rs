@@ -0,0 +1,383 @@
+//! Plain text formats: the compact one-line form of `eix -c`, tables
+//! with one row per version and atom lists for shell scripts
+
+use crate::{
+    Atom, InstalledIndex, MASK_FLAG_NAMES, PROPERTIES_FLAG_NAMES, Package, RESTRICT_FLAG_NAMES,
+    Version, flag_names, split_slot,
+};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+/// `[N] app-misc/screen (4.9.1-r2): description`, see
+/// [`format_compact_with`]
+///
+#[cfg_attr(feature = "sample-data", doc = "```")]
+#[cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+/// let (_, packages) = eix::sample::parsed();
+/// assert_eq!(
+///     eix::format_compact(&packages[0], "amd64"),
+///     "[N] app-misc/screen (4.9.1): screen manager with VT100/ANSI terminal emulation"
+/// );
+/// ```
+pub fn format_compact(package: &Package, arch: &str) -> String {
+    compact(package, arch, &[])
+}
+
+/// The compact form with the installed versions:
+///
+/// - `[N] category/name (best): description` if nothing is installed
+/// - `[I] category/name (installed): description` if the best version
+///   is installed
+/// - `[U] category/name (installed -> best): description` otherwise
+///
+/// The best version is the highest stable one for `arch`, or else the
+/// highest testing one, prefixed with `~`.
+pub fn format_compact_with(package: &Package, arch: &str, installed: &InstalledIndex) -> String {
+    let installed: Vec<&str> = package
+        .installed_in(installed)
+        .iter()
+        .map(|i| i.version.as_str())
+        .collect();
+    compact(package, arch, &installed)
+}
+
+fn compact(package: &Package, arch: &str, installed: &[&str]) -> String {
+    let best = match package.best_version(Some(arch), false) {
+        Some(v) => Some(v.version_string.clone()),
+        None => package
+            .best_version(Some(arch), true)
+            .map(|v| format!("~{}", v.version_string)),
+    };
+    let best_installed = best
+        .as_deref()
+        .is_some_and(|best| installed.contains(&best.trim_start_matches('~')));
+    let marker = match (installed.is_empty(), best_installed) {
+        (true, _) => 'N',
+        (false, true) => 'I',
+        (false, false) => 'U',
+    };
+    let mut out = format!("[{}] {}/{}", marker, package.category, package.name);
+    match (installed.is_empty(), best) {
+        (true, Some(best)) => out.push_str(&format!(" ({})", best)),
+        (false, Some(best)) if !best_installed => {
+            out.push_str(&format!(" ({} -> {})", installed.join(", "), best))
+        }
+        (false, _) => out.push_str(&format!(" ({})", installed.join(", "))),
+        (true, None) => {}
+    }
+    out.push_str(": ");
+    out.push_str(&package.description);
+    out
+}
+
+/// A column of a [`TableFormatter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// `category/name`
+    Name,
+    Version,
+    MaskFlags,
+    /// The names of the mask flags, comma separated
+    Mask,
+    PropertiesFlags,
+    Properties,
+    RestrictFlags,
+    Restrict,
+    Priority,
+    /// Slot and subslot, `0` if empty
+    Slot,
+    Overlay,
+    Repo,
+}
+
+impl Column {
+    pub const ALL: &[Column] = &[
+        Column::Name,
+        Column::Version,
+        Column::MaskFlags,
+        Column::Mask,
+        Column::PropertiesFlags,
+        Column::Properties,
+        Column::RestrictFlags,
+        Column::Restrict,
+        Column::Priority,
+        Column::Slot,
+        Column::Overlay,
+        Column::Repo,
+    ];
+
+    /// The name used in column specs and headers
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Version => "version",
+            Column::MaskFlags => "mask_flags",
+            Column::Mask => "mask",
+            Column::PropertiesFlags => "properties_flags",
+            Column::Properties => "properties",
+            Column::RestrictFlags => "restrict_flags",
+            Column::Restrict => "restrict",
+            Column::Priority => "priority",
+            Column::Slot => "slot",
+            Column::Overlay => "overlay",
+            Column::Repo => "repo",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Column::ALL.iter().copied().find(|c| c.name() == name)
+    }
+
+    fn value(self, package: &Package, v: &Version) -> String {
+        match self {
+            Column::Name => format!("{}/{}", package.category, package.name),
+            Column::Version => v.version_string.clone(),
+            Column::MaskFlags => v.mask_flags.to_string(),
+            Column::Mask => join(flag_names(v.mask_flags, MASK_FLAG_NAMES)),
+            Column::PropertiesFlags => v.properties_flags.to_string(),
+            Column::Properties => join(flag_names(v.properties_flags, PROPERTIES_FLAG_NAMES)),
+            Column::RestrictFlags => v.restrict_flags.to_string(),
+            Column::Restrict => join(flag_names(v.restrict_flags, RESTRICT_FLAG_NAMES)),
+            Column::Priority => v.priority.to_string(),
+            Column::Slot => match split_slot(&v.slot) {
+                (slot, Some(subslot)) => format!("{}/{}", slot, subslot),
+                (slot, None) => slot.to_string(),
+            },
+            Column::Overlay => v.overlay_key.to_string(),
+            Column::Repo => v.reponame.to_string(),
+        }
+    }
+}
+
+fn join<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    names.collect::<Vec<_>>().join(",")
+}
+
+/// How [`TableFormatter`] separates the columns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Padded with spaces to the widest cell of each column
+    #[default]
+    Aligned,
+    /// Separated by tabs
+    Tsv,
+}
+
+/// Formats versions as a table with one row per version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableFormatter {
+    columns: Vec<Column>,
+    style: TableStyle,
+}
+
+impl TableFormatter {
+    pub fn new(columns: Vec<Column>, style: TableStyle) -> Self {
+        TableFormatter { columns, style }
+    }
+
+    /// Parses a comma separated list of column names, e.g.
+    /// `version,mask,slot,repo`
+    pub fn parse(spec: &str, style: TableStyle) -> io::Result<Self> {
+        let columns = spec
+            .split(',')
+            .map(|name| {
+                Column::from_name(name.trim()).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unknown column: {}", name),
+                    )
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(TableFormatter { columns, style })
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// The header line with the column names, without newline
+    pub fn header(&self) -> String {
+        let names: Vec<String> = self.columns.iter().map(|c| c.name().to_string()).collect();
+        self.line(&names, &[])
+    }
+
+    /// The cells of the rows of `package`
+    pub fn rows(&self, package: &Package) -> Vec<Vec<String>> {
+        package
+            .versions
+            .iter()
+            .map(|v| self.columns.iter().map(|c| c.value(package, v)).collect())
+            .collect()
+    }
+
+    /// The rows of `package`, each followed by a newline. Aligned rows
+    /// are only aligned among themselves; use [`format`](Self::format)
+    /// to align several packages.
+    pub fn format_package(&self, package: &Package) -> String {
+        self.lines(self.rows(package))
+    }
+
+    /// The header and the rows of all packages
+    pub fn format<'a, I>(&self, packages: I) -> String
+    where
+        I: IntoIterator<Item = &'a Package>,
+    {
+        let names = self.columns.iter().map(|c| c.name().to_string()).collect();
+        let mut rows = vec![names];
+        for package in packages {
+            rows.extend(self.rows(package));
+        }
+        self.lines(rows)
+    }
+
+    fn lines(&self, rows: Vec<Vec<String>>) -> String {
+        let mut widths = vec![0; self.columns.len()];
+        if self.style == TableStyle::Aligned {
+            for row in &rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+        }
+        let mut out = String::new();
+        for row in &rows {
+            out.push_str(&self.line(row, &widths));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Joins the cells; aligned cells are padded to `widths`, except the last
+    fn line(&self, cells: &[String], widths: &[usize]) -> String {
+        match self.style {
+            TableStyle::Tsv => cells.join("\t"),
+            TableStyle::Aligned => {
+                let mut line = String::new();
+                for (i, cell) in cells.iter().enumerate() {
+                    if i > 0 {
+                        line.push_str("  ");
+                    }
+                    line.push_str(cell);
+                    if i + 1 < cells.len() {
+                        let width = widths.get(i).copied().unwrap_or(0);
+                        let len = cell.chars().count();
+                        line.extend(std::iter::repeat_n(' ', width.saturating_sub(len)));
+                    }
+                }
+                line
+            }
+        }
+    }
+}
+
+/// What [`write_atom_list`] writes for a package
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AtomForm {
+    /// `category/name`
+    #[default]
+    Cp,
+    /// `category/name-version` of the best version
+    Cpv,
+    /// `=category/name-version` of the best version, as accepted by emerge
+    EqualsCpv,
+}
+
+/// Options of [`write_atom_list`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AtomListStyle {
+    pub form: AtomForm,
+    /// Sort the lines
+    pub sort: bool,
+    /// Drop repeated lines, keeping the first
+    pub dedup: bool,
+}
+
+impl AtomListStyle {
+    pub fn new(form: AtomForm) -> Self {
+        AtomListStyle {
+            form,
+            ..Default::default()
+        }
+    }
+
+    /// Sorts the lines and drops duplicates
+    pub fn sorted(mut self) -> Self {
+        self.sort = true;
+        self.dedup = true;
+        self
+    }
+}
+
+/// Writes one atom per package, each followed by a newline and nothing
+/// else, so the output can be piped into emerge.
+///
+/// The best version is the highest unmasked one; with
+/// [`AtomForm::Cpv`] and [`AtomForm::EqualsCpv`], packages without one
+/// are left out.
+pub fn write_atom_list<'a, I, W>(packages: I, mut w: W, style: AtomListStyle) -> io::Result<()>
+where
+    I: IntoIterator<Item = &'a Package>,
+    W: Write,
+{
+    let mut lines: Vec<String> = packages
+        .into_iter()
+        .filter_map(|package| {
+            let cp = format!("{}/{}", package.category, package.name);
+            match style.form {
+                AtomForm::Cp => Some(cp),
+                AtomForm::Cpv => package
+                    .best_version(None, false)
+                    .map(|v| format!("{}-{}", cp, v.version_string)),
+                AtomForm::EqualsCpv => package
+                    .best_version(None, false)
+                    .map(|v| format!("={}-{}", cp, v.version_string)),
+            }
+        })
+        .collect();
+    if style.sort {
+        lines.sort();
+    }
+    if style.dedup {
+        let mut seen = HashSet::new();
+        lines.retain(|line| seen.insert(line.clone()));
+    }
+    for line in lines {
+        writeln!(w, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Parses a list of one atom per line, like the output of
+/// [`write_atom_list`]. Blank lines and comments are skipped.
+pub fn read_atom_list<R: BufRead>(r: R) -> io::Result<Vec<Atom>> {
+    let mut atoms = Vec::new();
+    for line in r.lines() {
+        let line = line?;
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if !line.is_empty() {
+            atoms.push(Atom::parse(line)?);
+        }
+    }
+    Ok(atoms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        let table = TableFormatter::parse("version, mask,slot", TableStyle::Tsv).unwrap();
+        assert_eq!(
+            table.columns(),
+            &[Column::Version, Column::Mask, Column::Slot]
+        );
+        assert_eq!(table.header(), "version\tmask\tslot");
+        let e = TableFormatter::parse("version,size", TableStyle::Tsv).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        for c in Column::ALL {
+            assert_eq!(Column::from_name(c.name()), Some(*c));
+        }
+    }
+}
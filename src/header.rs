@@ -0,0 +1,484 @@
+//! The header of a database: format version, overlays and string hashes
+
+use crate::types::{Catsize, UNumber};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Without std, maps are ordered and the lazily built values are not `Sync`
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+#[cfg(not(feature = "std"))]
+pub(crate) use core::cell::OnceCell as Lazy;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(feature = "std")]
+pub(crate) use std::sync::OnceLock as Lazy;
+
+/* Magic Number and Version */
+pub const MAGICNUMCHAR: u8 = 0xFF;
+
+// The file starts with "eix" followed by a newline (0x0A)
+pub const DB_MAGIC: &[u8] = b"eix\n";
+
+// Current database version
+pub const DB_VERSION_CURRENT: DBVersion = 39;
+
+// The first version storing the properties flags as a number; older
+// versions have a single byte
+pub const DB_VERSION_NUMERIC_PROPERTIES: DBVersion = 39;
+
+// The first version whose files are trusted to carry PROPERTIES_LIVE for
+// live ebuilds. Older files hold the flag as well, but were written by
+// eix versions which did not always set it, so Version::is_live guesses
+// from the version number there.
+pub const DB_VERSION_LIVE_PROPERTY: DBVersion = 39;
+
+/*
+ * DBHeader - The main structure for the database header
+ *
+ *
+ * Offset 0x00: Magic "eix\n" (4 bytes)
+ * Offset 0x04: Version as byte (e.g. 0x27 = 39)
+ * Offset 0x05: Number of categories as compressed number
+ * Then: Number of overlays as compressed number
+ * Then: Overlay data (path, label for each overlay)
+ * Then: String hashes (EAPI, License, Keywords, IUSE, Slot, Depend)
+ * Then: Feature flags (bitmask)
+ * Then: World sets
+*/
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DBHeader {
+    // Current database version
+    pub version: DBVersion,
+
+    // Number of categories
+    pub size: Catsize,
+
+    // Overlays (repository directories)
+    pub overlays: Vec<OverlayIdent>,
+
+    // String hash tables for compression; only serialized with
+    // SerializeOptions::string_hashes
+    #[serde(skip_serializing, default)]
+    pub eapi_hash: StringHash,
+    #[serde(skip_serializing, default)]
+    pub license_hash: StringHash,
+    #[serde(skip_serializing, default)]
+    pub keywords_hash: StringHash,
+    #[serde(skip_serializing, default)]
+    pub iuse_hash: StringHash,
+    #[serde(skip_serializing, default)]
+    pub slot_hash: StringHash,
+    #[serde(skip_serializing, default)]
+    pub depend_hash: StringHash,
+
+    // Feature flags (SAVE_BITMASK)
+    pub use_depend: bool,       // SAVE_BITMASK_DEP
+    pub use_required_use: bool, // SAVE_BITMASK_REQUIRED_USE
+    pub use_src_uri: bool,      // SAVE_BITMASK_SRC_URI
+
+    // World sets
+    pub world_sets: Vec<String>,
+
+    // Problems found in the string hashes while reading
+    #[serde(skip)]
+    pub warnings: Vec<HashWarning>,
+}
+
+impl DBHeader {
+    /// Problems found in the string hashes, see [`HashValidation`](crate::HashValidation)
+    pub fn warnings(&self) -> &[HashWarning] {
+        &self.warnings
+    }
+
+    /// The overlay with key `key`, as stored in
+    /// [`Version::overlay_key`](crate::Version::overlay_key)
+    pub fn overlay(&self, key: u64) -> Option<&OverlayIdent> {
+        usize::try_from(key)
+            .ok()
+            .and_then(|key| self.overlays.get(key))
+    }
+
+    /// The key and overlay with label `label`; of several, the first
+    pub fn overlay_by_label(&self, label: &str) -> Option<(u64, &OverlayIdent)> {
+        self.overlays
+            .iter()
+            .enumerate()
+            .find(|(_, o)| &*o.label == label)
+            .map(|(key, o)| (key as u64, o))
+    }
+
+    /// The key and overlay with path `path`; paths are compared by
+    /// their components, so trailing and repeated slashes do not matter
+    #[cfg(feature = "std")]
+    pub fn overlay_by_path(&self, path: &std::path::Path) -> Option<(u64, &OverlayIdent)> {
+        self.overlays
+            .iter()
+            .enumerate()
+            .find(|(_, o)| std::path::Path::new(&o.path) == path)
+            .map(|(key, o)| (key as u64, o))
+    }
+
+    /// True if `name` is one of the world sets; the leading `@` is
+    /// optional in both
+    pub fn contains_set(&self, name: &str) -> bool {
+        let name = name.strip_prefix('@').unwrap_or(name);
+        self.world_sets
+            .iter()
+            .any(|set| set.strip_prefix('@').unwrap_or(set) == name)
+    }
+
+    /// The main tree, conventionally the overlay with key 0
+    pub fn main_tree(&self) -> Option<&OverlayIdent> {
+        self.overlays.first()
+    }
+}
+
+pub type DBVersion = u32;
+
+/*
+ * OverlayIdent - Identification of an overlay/repository
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayIdent {
+    pub path: String,    // Path to the overlay (e.g. "/usr/portage")
+    pub label: Arc<str>, // Label of the overlay (e.g. "gentoo")
+    pub priority: i32,   // Priority of the overlay
+}
+
+#[cfg(feature = "fast-hash")]
+type IndexMap = Map<String, usize, rustc_hash::FxBuildHasher>;
+#[cfg(not(feature = "fast-hash"))]
+type IndexMap = Map<String, usize>;
+
+/*
+ * StringHash - Hash table for string compression
+ *
+ * The map string → index is only needed for writing and built on
+ * first use, so hashes read from a file stay a plain Vec. The Vec is
+ * shared with the versions whose dependencies are decoded lazily.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct StringHash {
+    index_to_string: Arc<Vec<String>>,
+    string_to_index: Lazy<IndexMap>,
+}
+
+impl StringHash {
+    pub fn new() -> Self {
+        StringHash::default()
+    }
+
+    fn index_map(&self) -> &IndexMap {
+        self.string_to_index.get_or_init(|| {
+            let mut map = IndexMap::default();
+            for (idx, s) in self.index_to_string.iter().enumerate() {
+                map.entry(s.clone()).or_insert(idx);
+            }
+            map
+        })
+    }
+
+    pub fn get_index(&self, s: &str) -> Option<usize> {
+        self.index_map().get(s).copied()
+    }
+
+    pub fn get_string(&self, index: usize) -> Option<&str> {
+        self.index_to_string.get(index).map(|s| s.as_str())
+    }
+
+    pub fn add(&mut self, s: String) -> usize {
+        self.index_map();
+        let map = self.string_to_index.get_mut().expect("index map built");
+        if let Some(&idx) = map.get(&s) {
+            return idx;
+        }
+        let idx = self.index_to_string.len();
+        map.insert(s.clone(), idx);
+        Arc::make_mut(&mut self.index_to_string).push(s);
+        idx
+    }
+
+    pub fn len(&self) -> usize {
+        self.index_to_string.len()
+    }
+
+    /// The strings in index order, shared with the hash
+    #[cfg(feature = "std")]
+    pub(crate) fn shared_strings(&self) -> Arc<Vec<String>> {
+        Arc::clone(&self.index_to_string)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index_to_string.is_empty()
+    }
+
+    /// The heap bytes of the strings and of the map string → index if
+    /// built, see the [`heap`](crate::heap) module
+    #[cfg(feature = "std")]
+    pub fn heap_breakdown(&self) -> crate::HeapSize {
+        let mut size = crate::heap::strings(&self.index_to_string);
+        if let Some(map) = self.string_to_index.get() {
+            size += crate::heap::table(map);
+            size += map.keys().map(crate::heap::string).sum();
+        }
+        size
+    }
+
+    /// Drops the map string → index
+    pub fn freeze(self) -> FrozenStringHash {
+        FrozenStringHash {
+            strings: Arc::unwrap_or_clone(self.index_to_string),
+        }
+    }
+}
+
+/// Serialized as the array of strings in index order
+impl Serialize for StringHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.index_to_string.serialize(serializer)
+    }
+}
+
+/// Reads the array of strings in index order; the map string → index
+/// is built on first use as for hashes read from a file
+impl<'de> Deserialize<'de> for StringHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        FrozenStringHash::deserialize(deserializer).map(StringHash::from)
+    }
+}
+
+impl From<FrozenStringHash> for StringHash {
+    fn from(hash: FrozenStringHash) -> Self {
+        StringHash {
+            index_to_string: Arc::new(hash.strings),
+            string_to_index: Lazy::new(),
+        }
+    }
+}
+
+/*
+ * FrozenStringHash - A string hash without the map string → index
+ *
+ * Returned by Decoder::read_hash. DBHeader keeps its hashes as
+ * StringHash, converted without copying; their map is only built when
+ * an index is looked up or a string added.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FrozenStringHash {
+    strings: Vec<String>,
+}
+
+impl FrozenStringHash {
+    pub fn new() -> Self {
+        FrozenStringHash::default()
+    }
+
+    pub fn get_string(&self, index: usize) -> Option<&str> {
+        self.strings.get(index).map(|s| s.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// The strings in index order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.strings.iter().map(|s| s.as_str())
+    }
+}
+
+impl From<Vec<String>> for FrozenStringHash {
+    fn from(strings: Vec<String>) -> Self {
+        FrozenStringHash { strings }
+    }
+}
+
+/// A suspicious entry of a string hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashWarning {
+    /// Name of the hash, e.g. "keywords"
+    pub hash: &'static str,
+    pub index: usize,
+    pub kind: HashWarningKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashWarningKind {
+    /// The entry equals the one at `first`; eix never writes duplicates
+    Duplicate { first: usize },
+    /// The entry contains a NUL byte
+    EmbeddedNul,
+    /// The entry is longer than the limit
+    TooLong { len: usize },
+}
+
+impl fmt::Display for HashWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} hash entry {}: ", self.hash, self.index)?;
+        match self.kind {
+            HashWarningKind::Duplicate { first } => write!(f, "duplicate of entry {}", first),
+            HashWarningKind::EmbeddedNul => write!(f, "embedded NUL byte"),
+            HashWarningKind::TooLong { len } => write!(f, "implausibly long ({} bytes)", len),
+        }
+    }
+}
+
+impl FrozenStringHash {
+    /// Checks the entries for duplicates, NUL bytes and entries longer
+    /// than `max_len` bytes
+    pub fn check(&self, name: &'static str, max_len: u64) -> Vec<HashWarning> {
+        let mut warnings = Vec::new();
+        let mut seen: Map<&str, usize> = Map::new();
+        for (index, s) in self.strings.iter().enumerate() {
+            let mut warn = |kind| {
+                warnings.push(HashWarning {
+                    hash: name,
+                    index,
+                    kind,
+                })
+            };
+            if let Some(&first) = seen.get(s.as_str()) {
+                warn(HashWarningKind::Duplicate { first });
+            } else {
+                seen.insert(s, index);
+            }
+            if s.contains('\0') {
+                warn(HashWarningKind::EmbeddedNul);
+            }
+            if s.len() as u64 > max_len {
+                warn(HashWarningKind::TooLong { len: s.len() });
+            }
+        }
+        warnings
+    }
+}
+
+/*
+ * Bitmask for saved features
+ */
+pub type SaveBitmask = UNumber;
+
+pub const SAVE_BITMASK_DEP: SaveBitmask = 0x01;
+pub const SAVE_BITMASK_REQUIRED_USE: SaveBitmask = 0x02;
+pub const SAVE_BITMASK_SRC_URI: SaveBitmask = 0x04;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_bytes() {
+        assert_eq!(DB_MAGIC, b"eix\n");
+        assert_eq!(DB_MAGIC.len(), 4);
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(DB_VERSION_CURRENT, 39);
+    }
+
+    #[test]
+    fn test_overlay_lookup() {
+        let overlay = |path: &str, label: &str| OverlayIdent {
+            path: path.to_string(),
+            label: label.into(),
+            priority: 0,
+        };
+        let mut header = DBHeader {
+            version: DB_VERSION_CURRENT,
+            size: 0,
+            overlays: vec![
+                overlay("/var/db/repos/gentoo", "gentoo"),
+                overlay("/var/db/repos/guru/", "guru"),
+                overlay("/srv/guru", "guru"),
+            ],
+            eapi_hash: StringHash::new(),
+            license_hash: StringHash::new(),
+            keywords_hash: StringHash::new(),
+            iuse_hash: StringHash::new(),
+            slot_hash: StringHash::new(),
+            depend_hash: StringHash::new(),
+            use_depend: false,
+            use_required_use: false,
+            use_src_uri: false,
+            world_sets: Vec::new(),
+            warnings: Vec::new(),
+        };
+        assert_eq!(header.overlay(1).unwrap().path, "/var/db/repos/guru/");
+        assert!(header.overlay(3).is_none());
+        assert!(header.overlay(u64::MAX).is_none());
+        assert_eq!(&*header.main_tree().unwrap().label, "gentoo");
+
+        // Of duplicate labels, the first
+        let (key, guru) = header.overlay_by_label("guru").unwrap();
+        assert_eq!(key, 1);
+        assert_eq!(guru.path, "/var/db/repos/guru/");
+        assert!(header.overlay_by_label("local").is_none());
+
+        #[cfg(feature = "std")]
+        {
+            use std::path::Path;
+            let by_path = |path: &str| header.overlay_by_path(Path::new(path)).map(|(key, _)| key);
+            assert_eq!(by_path("/var/db/repos/guru"), Some(1));
+            assert_eq!(by_path("/srv/guru/"), Some(2));
+            assert_eq!(by_path("/var/db/repos/gentoo//"), Some(0));
+            assert_eq!(by_path("/var/db/repos"), None);
+        }
+
+        header.overlays.clear();
+        assert!(header.main_tree().is_none());
+    }
+
+    #[test]
+    fn test_string_hash() {
+        let mut hash = StringHash::new();
+        let idx1 = hash.add("test".to_string());
+        let idx2 = hash.add("another".to_string());
+        let idx3 = hash.add("test".to_string());
+
+        assert_eq!(idx1, 0);
+        assert_eq!(idx2, 1);
+        assert_eq!(idx1, idx3);
+        assert_eq!(hash.len(), 2);
+
+        assert_eq!(hash.get_string(0), Some("test"));
+        assert_eq!(hash.get_string(1), Some("another"));
+        assert_eq!(hash.get_string(2), None);
+
+        assert_eq!(hash.get_index("test"), Some(0));
+        assert_eq!(hash.get_index("another"), Some(1));
+        assert_eq!(hash.get_index("nonexistent"), None);
+    }
+
+    #[test]
+    fn test_frozen_string_hash() {
+        let frozen = FrozenStringHash::from(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(frozen.len(), 2);
+        assert!(!frozen.is_empty());
+        assert_eq!(frozen.get_string(1), Some("b"));
+        assert_eq!(frozen.get_string(2), None);
+        assert_eq!(frozen.iter().collect::<Vec<_>>(), ["a", "b"]);
+        assert!(FrozenStringHash::new().is_empty());
+
+        // Thawing builds the map on demand and keeps the indices
+        let mut hash = StringHash::from(frozen.clone());
+        assert_eq!(hash.get_index("b"), Some(1));
+        assert_eq!(hash.add("a".to_string()), 0);
+        assert_eq!(hash.add("c".to_string()), 2);
+        assert_eq!(hash.get_string(2), Some("c"));
+
+        let mut thawed = StringHash::from(frozen);
+        assert_eq!(thawed.add("c".to_string()), 2);
+        assert_eq!(thawed.clone().freeze(), hash.freeze());
+    }
+}
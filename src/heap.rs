@@ -0,0 +1,221 @@
+//! Approximate memory footprint of parsed data
+//!
+//! `approx_heap_size` counts the capacities of the strings and vectors
+//! owned by a value, not the value itself, and estimates the hash tables
+//! from their capacity. The numbers are not exact, as the allocator
+//! rounds up and keeps its own bookkeeping, but they only depend on the
+//! data and its capacities, so comparing two estimates shows how much an
+//! optimization saves:
+//!
+//! ```
+//! use eix::EixTestBuilder;
+//!
+//! let (header, packages) = EixTestBuilder::new()
+//!     .category("app-misc")
+//!     .package("screen", |p| p.version("4.9.1", |v| v.keywords("amd64")))
+//!     .build();
+//! let size = header.heap_breakdown() + packages[0].heap_breakdown();
+//! assert_eq!(size.total(), header.approx_heap_size() + packages[0].approx_heap_size());
+//! ```
+
+use crate::{BasicPart, DBHeader, Depend, LazyDepend, OverlayIdent, Package, Version};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::ops::{Add, AddAssign};
+use std::sync::Arc;
+
+/// Estimated heap bytes of a value by what they hold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct HeapSize {
+    /// The text of strings
+    pub strings: usize,
+    /// The elements of vectors, without the heap data of the elements
+    pub vectors: usize,
+    /// Buckets and control bytes of hash tables, without the heap data
+    /// of the keys and values
+    pub hash_tables: usize,
+}
+
+impl HeapSize {
+    pub fn total(&self) -> usize {
+        self.strings + self.vectors + self.hash_tables
+    }
+}
+
+impl Add for HeapSize {
+    type Output = HeapSize;
+
+    fn add(self, other: HeapSize) -> HeapSize {
+        HeapSize {
+            strings: self.strings + other.strings,
+            vectors: self.vectors + other.vectors,
+            hash_tables: self.hash_tables + other.hash_tables,
+        }
+    }
+}
+
+impl AddAssign for HeapSize {
+    fn add_assign(&mut self, other: HeapSize) {
+        *self = *self + other;
+    }
+}
+
+impl std::iter::Sum for HeapSize {
+    fn sum<I: Iterator<Item = HeapSize>>(iter: I) -> HeapSize {
+        iter.fold(HeapSize::default(), Add::add)
+    }
+}
+
+pub(crate) fn string(s: &String) -> HeapSize {
+    HeapSize {
+        strings: s.capacity(),
+        ..Default::default()
+    }
+}
+
+/// The element storage of `v`
+fn vec<T>(v: &Vec<T>) -> HeapSize {
+    HeapSize {
+        vectors: v.capacity() * size_of::<T>(),
+        ..Default::default()
+    }
+}
+
+/// The text and the reference counts of a shared string
+fn shared(s: &Arc<str>) -> HeapSize {
+    HeapSize {
+        strings: s.len() + 2 * size_of::<usize>(),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn strings(v: &Vec<String>) -> HeapSize {
+    vec(v) + v.iter().map(string).sum()
+}
+
+/// The buckets of `map`: an entry and a control byte each
+pub(crate) fn table<K, V, S>(map: &HashMap<K, V, S>) -> HeapSize {
+    HeapSize {
+        hash_tables: map.capacity() * (size_of::<(K, V)>() + 1),
+        ..Default::default()
+    }
+}
+
+fn part(part: &BasicPart) -> HeapSize {
+    string(&part.part_content)
+}
+
+fn depend(depend: &Depend) -> HeapSize {
+    strings(&depend.depend)
+        + strings(&depend.rdepend)
+        + strings(&depend.pdepend)
+        + strings(&depend.bdepend)
+        + strings(&depend.idepend)
+}
+
+/// The encoded lists, and the decoded ones once decoded; the strings
+/// of the depend hash are shared with the header and counted there
+fn lazy_depend(lazy: &LazyDepend) -> HeapSize {
+    let mut size = vec(lazy.raw());
+    if let Some(d) = lazy.decoded() {
+        size += depend(d);
+    }
+    size
+}
+
+fn overlay(overlay: &OverlayIdent) -> HeapSize {
+    string(&overlay.path) + shared(&overlay.label)
+}
+
+impl Version {
+    /// The heap bytes owned by the version, see the [`heap`](crate::heap)
+    /// module. The repository name is shared with the header and counted
+    /// there.
+    pub fn heap_breakdown(&self) -> HeapSize {
+        let mut size = string(&self.version_string)
+            + vec(&self.parts)
+            + self.parts.iter().map(part).sum()
+            + string(&self.eapi)
+            + strings(&self.keywords)
+            + string(&self.slot)
+            + strings(&self.iuse)
+            + strings(&self.required_use);
+        if let Some(d) = &self.depend {
+            size += depend(d);
+        }
+        if let Some(lazy) = &self.lazy_depend {
+            size += lazy_depend(lazy);
+        }
+        if let Some(s) = &self.src_uri {
+            size += string(s);
+        }
+        size
+    }
+
+    /// The total of [`heap_breakdown`](Self::heap_breakdown)
+    pub fn approx_heap_size(&self) -> usize {
+        self.heap_breakdown().total()
+    }
+}
+
+impl Package {
+    /// The heap bytes owned by the package and its versions, see the
+    /// [`heap`](crate::heap) module
+    pub fn heap_breakdown(&self) -> HeapSize {
+        string(&self.category)
+            + string(&self.name)
+            + string(&self.description)
+            + string(&self.homepage)
+            + string(&self.licenses)
+            + vec(&self.versions)
+            + self.versions.iter().map(Version::heap_breakdown).sum()
+    }
+
+    /// The total of [`heap_breakdown`](Self::heap_breakdown)
+    pub fn approx_heap_size(&self) -> usize {
+        self.heap_breakdown().total()
+    }
+}
+
+impl DBHeader {
+    /// The heap bytes owned by the header, including the string hashes
+    /// and their maps for writing if built, see the [`heap`](crate::heap)
+    /// module
+    pub fn heap_breakdown(&self) -> HeapSize {
+        vec(&self.overlays)
+            + self.overlays.iter().map(overlay).sum()
+            + self.eapi_hash.heap_breakdown()
+            + self.license_hash.heap_breakdown()
+            + self.keywords_hash.heap_breakdown()
+            + self.iuse_hash.heap_breakdown()
+            + self.slot_hash.heap_breakdown()
+            + self.depend_hash.heap_breakdown()
+            + strings(&self.world_sets)
+            + vec(&self.warnings)
+    }
+
+    /// The total of [`heap_breakdown`](Self::heap_breakdown)
+    pub fn approx_heap_size(&self) -> usize {
+        self.heap_breakdown().total()
+    }
+}
+
+#[cfg(feature = "fs")]
+impl crate::ParsedDb {
+    /// The heap bytes owned by the header and the packages, see the
+    /// [`heap`](crate::heap) module
+    pub fn heap_breakdown(&self) -> HeapSize {
+        HeapSize {
+            strings: self.info.path.capacity(),
+            ..Default::default()
+        } + self.header.heap_breakdown()
+            + vec(&self.packages)
+            + self.packages.iter().map(Package::heap_breakdown).sum()
+    }
+
+    /// The total of [`heap_breakdown`](Self::heap_breakdown)
+    pub fn approx_heap_size(&self) -> usize {
+        self.heap_breakdown().total()
+    }
+}
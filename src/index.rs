@@ -0,0 +1,317 @@
+//! Owned lookup containers for packages
+//!
+//! [`PackageMap`] finds packages by `category/name`, [`PackageIndex`]
+//! keeps them sorted by category and name for range and prefix queries.
+//! Both are built from a `Vec<Package>`, any iterator of packages or a
+//! [`PackageReader`], and serialize as a list of packages.
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! use eix::{PackageIndex, PackageQuery};
+//!
+//! let (_, packages) = eix::sample::parsed();
+//! let index = PackageIndex::from(packages);
+//! let names: Vec<&str> = index
+//!     .category_range("app-misc")
+//!     .map(|p| p.name.as_str())
+//!     .collect();
+//! assert_eq!(names, ["screen", "tmux"]);
+//! let query = PackageQuery {
+//!     name: Some("tmux".to_string()),
+//!     ..PackageQuery::category("app-misc")
+//! };
+//! assert_eq!(query.execute_index(&index).len(), 1);
+//! ```
+
+use crate::{Atom, Package, PackageReader};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read};
+use std::ops::Bound;
+
+/// Reads the remaining packages of `reader`
+fn read_packages<R: Read>(
+    reader: &mut PackageReader<R>,
+    mut f: impl FnMut(Package),
+) -> io::Result<()> {
+    loop {
+        match reader.read_package()? {
+            Some(package) => f(package),
+            None if reader.next_category()? => {}
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Packages by their unversioned atom
+///
+/// Of several packages with the same atom, the first one is kept, as in
+/// [`SharedDb`](crate::SharedDb).
+#[derive(Debug, Clone, Default)]
+pub struct PackageMap {
+    packages: HashMap<Atom, Package>,
+}
+
+impl PackageMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the remaining packages of `reader`
+    pub fn from_reader<R: Read>(reader: &mut PackageReader<R>) -> io::Result<Self> {
+        let mut map = PackageMap::new();
+        read_packages(reader, |package| map.insert(package))?;
+        Ok(map)
+    }
+
+    /// Adds `package` unless a package with its atom is present
+    pub fn insert(&mut self, package: Package) {
+        self.packages
+            .entry(Atom::new(&package.category, &package.name))
+            .or_insert(package);
+    }
+
+    /// The package `category/name`
+    pub fn get(&self, cp: &str) -> Option<&Package> {
+        let (category, name) = cp.split_once('/')?;
+        self.packages.get(&Atom::new(category, name))
+    }
+
+    /// Returns the package of `atom` if one of its versions matches it
+    pub fn get_atom(&self, atom: &Atom) -> Option<&Package> {
+        let package = self.packages.get(&Atom::new(&atom.category, &atom.name))?;
+        package
+            .versions
+            .iter()
+            .any(|v| atom.matches(package, v))
+            .then_some(package)
+    }
+
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    /// All packages, in no particular order
+    pub fn packages(&self) -> impl Iterator<Item = &Package> {
+        self.packages.values()
+    }
+}
+
+impl FromIterator<Package> for PackageMap {
+    fn from_iter<I: IntoIterator<Item = Package>>(iter: I) -> Self {
+        let mut map = PackageMap::new();
+        iter.into_iter().for_each(|package| map.insert(package));
+        map
+    }
+}
+
+impl From<Vec<Package>> for PackageMap {
+    fn from(packages: Vec<Package>) -> Self {
+        packages.into_iter().collect()
+    }
+}
+
+impl Serialize for PackageMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.packages.values())
+    }
+}
+
+impl<'de> Deserialize<'de> for PackageMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Package>::deserialize(deserializer).map(PackageMap::from)
+    }
+}
+
+/// Packages sorted by category and name
+///
+/// Of several packages with the same category and name, the first one
+/// is kept.
+#[derive(Debug, Clone, Default)]
+pub struct PackageIndex {
+    /// Category → name → package
+    categories: BTreeMap<String, BTreeMap<String, Package>>,
+    len: usize,
+}
+
+impl PackageIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the remaining packages of `reader`
+    pub fn from_reader<R: Read>(reader: &mut PackageReader<R>) -> io::Result<Self> {
+        let mut index = PackageIndex::new();
+        read_packages(reader, |package| index.insert(package))?;
+        Ok(index)
+    }
+
+    /// Adds `package` unless a package with its category and name is
+    /// present
+    pub fn insert(&mut self, package: Package) {
+        let names = self.categories.entry(package.category.clone()).or_default();
+        if !names.contains_key(&package.name) {
+            names.insert(package.name.clone(), package);
+            self.len += 1;
+        }
+    }
+
+    /// The package `category/name`
+    pub fn get(&self, cp: &str) -> Option<&Package> {
+        let (category, name) = cp.split_once('/')?;
+        self.categories.get(category)?.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// All packages, sorted by category and name
+    pub fn packages(&self) -> impl Iterator<Item = &Package> {
+        self.categories.values().flat_map(BTreeMap::values)
+    }
+
+    /// Names of all categories, sorted
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        self.categories.keys().map(String::as_str)
+    }
+
+    /// The packages of `category`, sorted by name; empty if there is no
+    /// such category
+    pub fn category_range(&self, category: &str) -> impl Iterator<Item = &Package> {
+        self.categories
+            .get(category)
+            .into_iter()
+            .flat_map(BTreeMap::values)
+    }
+
+    /// The packages of all categories starting with `prefix`, e.g.
+    /// `dev-` for `dev-lang` and `dev-python`, sorted by category and
+    /// name
+    pub fn category_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a Package> {
+        self.categories
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(category, _)| category.starts_with(prefix))
+            .flat_map(|(_, names)| names.values())
+    }
+
+    /// The packages whose name starts with `prefix`, sorted by category
+    /// and name
+    pub fn name_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a Package> {
+        self.categories.values().flat_map(move |names| {
+            names
+                .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+                .take_while(move |(name, _)| name.starts_with(prefix))
+                .map(|(_, package)| package)
+        })
+    }
+}
+
+impl FromIterator<Package> for PackageIndex {
+    fn from_iter<I: IntoIterator<Item = Package>>(iter: I) -> Self {
+        let mut index = PackageIndex::new();
+        iter.into_iter().for_each(|package| index.insert(package));
+        index
+    }
+}
+
+impl From<Vec<Package>> for PackageIndex {
+    fn from(packages: Vec<Package>) -> Self {
+        packages.into_iter().collect()
+    }
+}
+
+impl Serialize for PackageIndex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.packages())
+    }
+}
+
+impl<'de> Deserialize<'de> for PackageIndex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Package>::deserialize(deserializer).map(PackageIndex::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(category: &str, name: &str, description: &str) -> Package {
+        Package {
+            category: category.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn packages() -> Vec<Package> {
+        vec![
+            package("dev-python", "pytest", ""),
+            package("dev-lang", "python", ""),
+            package("dev-python", "pyqt6", ""),
+            package("app-misc", "tmux", ""),
+            package("dev-python", "pytest", "duplicate"),
+            package("dev-libs", "openssl", ""),
+        ]
+    }
+
+    fn names<'a>(packages: impl Iterator<Item = &'a Package>) -> Vec<String> {
+        packages
+            .map(|p| format!("{}/{}", p.category, p.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_map_lookup() {
+        let map = PackageMap::from(packages());
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get("dev-python/pytest").unwrap().description, "");
+        assert!(map.get("dev-python/python").is_none());
+        assert!(map.get("pytest").is_none());
+    }
+
+    #[test]
+    fn test_index_ranges() {
+        let index: PackageIndex = packages().into_iter().collect();
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.get("dev-python/pytest").unwrap().description, "");
+        assert_eq!(
+            names(index.packages()),
+            [
+                "app-misc/tmux",
+                "dev-lang/python",
+                "dev-libs/openssl",
+                "dev-python/pyqt6",
+                "dev-python/pytest"
+            ]
+        );
+        assert_eq!(
+            names(index.category_range("dev-python")),
+            ["dev-python/pyqt6", "dev-python/pytest"]
+        );
+        assert_eq!(index.category_range("dev").count(), 0);
+        assert_eq!(
+            names(index.category_prefix("dev-l")),
+            ["dev-lang/python", "dev-libs/openssl"]
+        );
+        assert_eq!(
+            names(index.name_prefix("py")),
+            ["dev-lang/python", "dev-python/pyqt6", "dev-python/pytest"]
+        );
+        assert_eq!(index.name_prefix("z").count(), 0);
+        assert_eq!(
+            index.categories().collect::<Vec<_>>(),
+            ["app-misc", "dev-lang", "dev-libs", "dev-python"]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Metadata of a database file
+//!
+//! Combines `fs::metadata` of the file with the counts of its header,
+//! e.g. for "database generated 3 hours ago, 34 MB, version 39".
+//! [`header_report`] lists the contents of the header in more detail.
+
+use crate::{Catsize, DBHeader, DBVersion, Database, StringHash};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Number of entries of each string hash listed by [`header_report`]
+pub const DEFAULT_HASH_SAMPLE: usize = 5;
+
+/// File and header information of a database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatabaseInfo {
+    pub path: PathBuf,
+    pub file_size: u64,
+    pub modified: SystemTime,
+    pub db_version: DBVersion,
+    pub category_count: Catsize,
+    pub overlay_count: usize,
+}
+
+impl DatabaseInfo {
+    /// Builds the info from an already read header
+    pub fn from_header<P: AsRef<Path>>(path: P, header: &DBHeader) -> io::Result<Self> {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path)?;
+        Ok(DatabaseInfo {
+            path: path.to_path_buf(),
+            file_size: metadata.len(),
+            modified: metadata.modified()?,
+            db_version: header.version,
+            category_count: header.size,
+            overlay_count: header.overlays.len(),
+        })
+    }
+
+    /// Time since the database was written (zero if it lies in the future)
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.modified)
+            .unwrap_or_default()
+    }
+}
+
+impl Database {
+    /// Reads the header of the database at `path` and returns its info
+    pub fn info<P: AsRef<Path>>(path: P) -> io::Result<DatabaseInfo> {
+        let mut db = Database::open_read(path.as_ref())?;
+        let header = db.read_header(0)?;
+        DatabaseInfo::from_header(path, &header)
+    }
+}
+
+/// The contents of a header, see [`header_report`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HeaderReport {
+    pub db_version: DBVersion,
+    pub category_count: Catsize,
+    /// The size of the header in bytes
+    pub header_len: u64,
+    pub overlays: Vec<OverlayReport>,
+    /// The string hashes in the order of the file
+    pub hashes: Vec<HashReport>,
+    pub world_sets: Vec<String>,
+    pub use_depend: bool,
+    pub use_required_use: bool,
+    pub use_src_uri: bool,
+}
+
+/// An overlay of a [`HeaderReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OverlayReport {
+    /// The key versions refer to it by
+    pub index: usize,
+    pub label: String,
+    pub path: String,
+}
+
+/// A string hash of a [`HeaderReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HashReport {
+    /// E.g. "keywords"
+    pub name: &'static str,
+    pub entries: usize,
+    /// The first entries
+    pub sample: Vec<String>,
+}
+
+impl HashReport {
+    fn new(name: &'static str, hash: &StringHash, sample: usize) -> Self {
+        HashReport {
+            name,
+            entries: hash.len(),
+            sample: (0..hash.len().min(sample))
+                .filter_map(|i| hash.get_string(i))
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl HeaderReport {
+    /// Builds the report of an already read header, listing the first
+    /// `sample` entries of each string hash
+    pub fn from_header(header: &DBHeader, header_len: u64, sample: usize) -> Self {
+        HeaderReport {
+            db_version: header.version,
+            category_count: header.size,
+            header_len,
+            overlays: header
+                .overlays
+                .iter()
+                .enumerate()
+                .map(|(index, overlay)| OverlayReport {
+                    index,
+                    label: overlay.label.to_string(),
+                    path: overlay.path.clone(),
+                })
+                .collect(),
+            hashes: vec![
+                HashReport::new("eapi", &header.eapi_hash, sample),
+                HashReport::new("license", &header.license_hash, sample),
+                HashReport::new("keywords", &header.keywords_hash, sample),
+                HashReport::new("iuse", &header.iuse_hash, sample),
+                HashReport::new("slot", &header.slot_hash, sample),
+                HashReport::new("depend", &header.depend_hash, sample),
+            ],
+            world_sets: header.world_sets.clone(),
+            use_depend: header.use_depend,
+            use_required_use: header.use_required_use,
+            use_src_uri: header.use_src_uri,
+        }
+    }
+}
+
+/// ```text
+/// Database version 39, 174 categories, header of 2460770 bytes
+/// Overlays:
+///   [0] gentoo /var/db/repos/gentoo/
+/// Stored: DEPEND, REQUIRED_USE, SRC_URI
+/// Hashes:
+///   eapi           2 entries: "8", "7"
+///   keywords      37 entries: "amd64", "~riscv", "x86", "~amd64", "~x86", …
+///   …
+/// World sets: (none)
+/// ```
+impl fmt::Display for HeaderReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Database version {}, {} categories, header of {} bytes",
+            self.db_version, self.category_count, self.header_len
+        )?;
+        writeln!(f, "Overlays:")?;
+        for overlay in &self.overlays {
+            writeln!(
+                f,
+                "  [{}] {} {}",
+                overlay.index, overlay.label, overlay.path
+            )?;
+        }
+        let stored: Vec<&str> = [
+            (self.use_depend, "DEPEND"),
+            (self.use_required_use, "REQUIRED_USE"),
+            (self.use_src_uri, "SRC_URI"),
+        ]
+        .into_iter()
+        .filter(|&(used, _)| used)
+        .map(|(_, name)| name)
+        .collect();
+        if stored.is_empty() {
+            writeln!(f, "Stored: (none)")?;
+        } else {
+            writeln!(f, "Stored: {}", stored.join(", "))?;
+        }
+        writeln!(f, "Hashes:")?;
+        for hash in &self.hashes {
+            write!(f, "  {:<9} {:>6} entries", hash.name, hash.entries)?;
+            if !hash.sample.is_empty() {
+                let quoted: Vec<String> = hash.sample.iter().map(|s| format!("{:?}", s)).collect();
+                write!(f, ": {}", quoted.join(", "))?;
+                if hash.entries > hash.sample.len() {
+                    write!(f, ", …")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        if self.world_sets.is_empty() {
+            writeln!(f, "World sets: (none)")
+        } else {
+            writeln!(f, "World sets: {}", self.world_sets.join(" "))
+        }
+    }
+}
+
+/// Reads only the header of the database at `path` and reports its
+/// contents with [`DEFAULT_HASH_SAMPLE`] entries of each string hash
+pub fn header_report<P: AsRef<Path>>(path: P) -> io::Result<HeaderReport> {
+    header_report_with(path, DEFAULT_HASH_SAMPLE)
+}
+
+/// Like [`header_report`], listing `sample` entries of each string hash
+pub fn header_report_with<P: AsRef<Path>>(path: P, sample: usize) -> io::Result<HeaderReport> {
+    let mut db = Database::open_read(path)?;
+    let header = db.read_header(0)?;
+    Ok(HeaderReport::from_header(&header, db.position(), sample))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_info_errors() {
+        assert!(Database::info("testdata/missing.eix").is_err());
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), b"not an eix file").unwrap();
+        let err = Database::info(file.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
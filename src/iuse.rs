@@ -0,0 +1,303 @@
+//! IUSE flags and their defaults
+//!
+//! `Version::iuse` keeps the words of IUSE as stored, e.g. `+ssl
+//! -systemd doc`, where a leading `+` enables the flag by default and a
+//! leading `-` disables it. [`Version::iuse_flags`] splits off these
+//! prefixes:
+//!
+//! ```
+//! use eix::{IuseFlag, Version};
+//!
+//! let v = Version {
+//!     iuse: ["+ssl", "-systemd", "doc"].into_iter().map(String::from).collect(),
+//!     ..Default::default()
+//! };
+//! assert_eq!(v.iuse_flags()[0], IuseFlag { name: "ssl".to_string(), default: Some(true) });
+//! assert_eq!(v.iuse_names(), ["ssl", "systemd", "doc"]);
+//! ```
+//!
+//! [`Version::iuse_grouped`] collects the flags of USE_EXPAND variables
+//! like `python_targets_python3_12` by variable, as eix shows them.
+
+use crate::Version;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Common USE_EXPAND variables, used by [`Version::iuse_grouped`] if no
+/// prefixes are given
+pub const DEFAULT_USE_EXPAND: &[&str] = &[
+    "abi_mips",
+    "abi_ppc",
+    "abi_riscv",
+    "abi_s390",
+    "abi_x86",
+    "ada_target",
+    "amdgpu_targets",
+    "apache2_modules",
+    "apache2_mpms",
+    "calligra_features",
+    "collectd_plugins",
+    "cpu_flags_arm",
+    "cpu_flags_ppc",
+    "cpu_flags_x86",
+    "curl_ssl",
+    "elibc",
+    "fftools",
+    "gpsd_protocols",
+    "grub_platforms",
+    "input_devices",
+    "kernel",
+    "l10n",
+    "lcd_devices",
+    "libreoffice_extensions",
+    "llvm_slot",
+    "llvm_targets",
+    "lua_single_target",
+    "lua_targets",
+    "monkeyd_plugins",
+    "nginx_modules_http",
+    "nginx_modules_mail",
+    "nginx_modules_stream",
+    "office_implementation",
+    "openmpi_fabrics",
+    "openmpi_ofed_features",
+    "openmpi_rm",
+    "php_targets",
+    "postgres_targets",
+    "python_single_target",
+    "python_targets",
+    "qemu_softmmu_targets",
+    "qemu_user_targets",
+    "ruby_targets",
+    "sane_backends",
+    "userland",
+    "uwsgi_plugins",
+    "video_cards",
+    "voicemail_storage",
+    "xtables_addons",
+];
+
+/// A flag of IUSE with its default
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct IuseFlag {
+    /// The flag without the prefix
+    pub name: String,
+    /// `Some(true)` for `+flag`, `Some(false)` for `-flag`, `None`
+    /// without a prefix
+    pub default: Option<bool>,
+}
+
+impl IuseFlag {
+    /// Parses an IUSE word. Only a leading `+` or `-` is a prefix, so
+    /// `gtk+` is a flag named `gtk+`.
+    pub fn parse(word: &str) -> IuseFlag {
+        let (name, default) = if let Some(name) = word.strip_prefix('+') {
+            (name, Some(true))
+        } else if let Some(name) = word.strip_prefix('-') {
+            (name, Some(false))
+        } else {
+            (word, None)
+        };
+        IuseFlag {
+            name: name.to_string(),
+            default,
+        }
+    }
+}
+
+/// The IUSE word, with the prefix
+impl fmt::Display for IuseFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.default {
+            Some(true) => write!(f, "+{}", self.name),
+            Some(false) => write!(f, "-{}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// The flags of IUSE split by USE_EXPAND variable
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IuseGroups {
+    /// Flags of no USE_EXPAND variable
+    pub plain: Vec<IuseFlag>,
+    /// The values of each USE_EXPAND variable, keyed by the lowercase
+    /// prefix, e.g. `python3_12` under `python_targets`
+    pub expanded: BTreeMap<String, Vec<IuseFlag>>,
+}
+
+/// Strips the `+`/`-` prefix of an IUSE word
+fn flag_name(word: &str) -> &str {
+    word.strip_prefix(['+', '-']).unwrap_or(word)
+}
+
+impl Version {
+    /// The flags of IUSE with their defaults, in the stored order
+    pub fn iuse_flags(&self) -> Vec<IuseFlag> {
+        self.iuse.iter().map(|word| IuseFlag::parse(word)).collect()
+    }
+
+    /// The names of the flags of IUSE, without the prefixes
+    pub fn iuse_names(&self) -> Vec<&str> {
+        self.iuse.iter().map(|word| flag_name(word)).collect()
+    }
+
+    /// Groups the flags of IUSE by the USE_EXPAND variables in
+    /// `use_expand_prefixes`, or [`DEFAULT_USE_EXPAND`] if it is empty.
+    /// A flag belongs to the longest prefix it starts with, compared
+    /// case-insensitively, followed by `_` and a value; other flags,
+    /// such as `foo_bar`, are plain.
+    pub fn iuse_grouped(&self, use_expand_prefixes: &[&str]) -> IuseGroups {
+        let prefixes = if use_expand_prefixes.is_empty() {
+            DEFAULT_USE_EXPAND
+        } else {
+            use_expand_prefixes
+        };
+        let mut prefixes: Vec<String> = prefixes.iter().map(|p| p.to_ascii_lowercase()).collect();
+        // Longest first, so the first match is the longest
+        prefixes.sort_unstable_by_key(|p| std::cmp::Reverse(p.len()));
+
+        let mut groups = IuseGroups::default();
+        for mut flag in self.iuse_flags() {
+            let matched = prefixes.iter().find_map(|prefix| {
+                let value = flag.name.get(prefix.len()..)?.strip_prefix('_')?;
+                (!value.is_empty() && flag.name[..prefix.len()].eq_ignore_ascii_case(prefix))
+                    .then(|| (prefix, value.to_string()))
+            });
+            match matched {
+                Some((prefix, value)) => {
+                    flag.name = value;
+                    groups
+                        .expanded
+                        .entry(prefix.clone())
+                        .or_default()
+                        .push(flag);
+                }
+                None => groups.plain.push(flag),
+            }
+        }
+        groups
+    }
+
+    /// The flags used in REQUIRED_USE which are not in IUSE, each once
+    pub fn undeclared_required_use(&self) -> Vec<&str> {
+        let declared = self.iuse_names();
+        let mut undeclared: Vec<&str> = Vec::new();
+        for word in &self.required_use {
+            // Skip `||`, `^^`, `??` and the parentheses
+            let flag = word.trim_start_matches('!').trim_end_matches('?');
+            if !flag.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+                continue;
+            }
+            if !declared.contains(&flag) && !undeclared.contains(&flag) {
+                undeclared.push(flag);
+            }
+        }
+        undeclared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::words;
+
+    fn flag(name: &str, default: Option<bool>) -> IuseFlag {
+        IuseFlag {
+            name: name.to_string(),
+            default,
+        }
+    }
+
+    #[test]
+    fn test_iuse_flags() {
+        let v = Version {
+            iuse: words("+ssl -systemd doc gtk+ +foo+bar -a-b"),
+            ..Default::default()
+        };
+        let flags = v.iuse_flags();
+        assert_eq!(
+            flags,
+            [
+                flag("ssl", Some(true)),
+                flag("systemd", Some(false)),
+                flag("doc", None),
+                flag("gtk+", None),
+                flag("foo+bar", Some(true)),
+                flag("a-b", Some(false)),
+            ]
+        );
+        assert_eq!(
+            v.iuse_names(),
+            ["ssl", "systemd", "doc", "gtk+", "foo+bar", "a-b"]
+        );
+        // Display gives back the stored words
+        let back: Vec<String> = flags.iter().map(ToString::to_string).collect();
+        assert_eq!(back[..], v.iuse[..]);
+    }
+
+    #[test]
+    fn test_undeclared_required_use() {
+        let v = Version {
+            iuse: words("+ssl -gnutls doc"),
+            required_use: words("^^ ( ssl gnutls ) doc? ( !static ) || ( X ssl )"),
+            ..Default::default()
+        };
+        assert_eq!(v.undeclared_required_use(), ["static", "X"]);
+    }
+
+    #[test]
+    fn test_iuse_grouped() {
+        let v = Version {
+            iuse: words(
+                "+ssl python_targets_python3_12 -python_targets_python3_13 \
+                 cpu_flags_x86_avx2 cpu_flags_x86_sse4_1 foo_bar l10n_de python_targets_",
+            ),
+            ..Default::default()
+        };
+        let groups = v.iuse_grouped(&[]);
+        assert_eq!(
+            groups.plain,
+            [
+                flag("ssl", Some(true)),
+                flag("foo_bar", None),
+                flag("python_targets_", None)
+            ]
+        );
+        assert_eq!(
+            groups.expanded["python_targets"],
+            [flag("python3_12", None), flag("python3_13", Some(false))]
+        );
+        assert_eq!(
+            groups.expanded["cpu_flags_x86"],
+            [flag("avx2", None), flag("sse4_1", None)]
+        );
+        assert_eq!(groups.expanded["l10n"], [flag("de", None)]);
+        assert_eq!(groups.expanded.len(), 3);
+
+        // The longest prefix wins, whatever the case
+        let groups = v.iuse_grouped(&["CPU_FLAGS", "cpu_flags_X86", "python"]);
+        assert_eq!(
+            groups.expanded["cpu_flags_x86"],
+            [flag("avx2", None), flag("sse4_1", None)]
+        );
+        assert!(!groups.expanded.contains_key("cpu_flags"));
+        assert_eq!(
+            groups.expanded["python"],
+            [
+                flag("targets_python3_12", None),
+                flag("targets_python3_13", Some(false)),
+                flag("targets_", None)
+            ]
+        );
+        assert_eq!(
+            groups.plain,
+            [
+                flag("ssl", Some(true)),
+                flag("foo_bar", None),
+                flag("l10n_de", None)
+            ]
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Converting a database to JSON while it is read (feature `json`)
+//!
+//! [`eix_to_json`] holds one package in memory at a time and reports
+//! errors with the package and the byte offset at which reading failed:
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! use eix::{JsonFormat, JsonOptions, eix_to_json};
+//!
+//! let mut out = Vec::new();
+//! let options = JsonOptions {
+//!     format: JsonFormat::Ndjson,
+//!     fields: vec!["category".to_string(), "name".to_string()],
+//!     ..Default::default()
+//! };
+//! let stats = eix_to_json(eix::sample::bytes(), &mut out, options).unwrap();
+//! let first = String::from_utf8(out).unwrap().lines().next().unwrap().to_string();
+//! assert_eq!(first, r#"{"category":"app-misc","name":"screen"}"#);
+//! assert_eq!(stats.packages, 4);
+//! ```
+
+use crate::{
+    DB_VERSION_CURRENT, DBHeader, Database, HeaderView, PACKAGE_FIELDS, Package, PackageReader,
+    SerializeOptions,
+};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+/// The layout of the output of [`eix_to_json`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonFormat {
+    /// An indented array of packages
+    #[default]
+    Pretty,
+    /// An array of packages on a single line
+    Compact,
+    /// One package per line
+    Ndjson,
+}
+
+/// Options of [`eix_to_json`]
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    pub format: JsonFormat,
+    /// Only these fields of packages, out of [`PACKAGE_FIELDS`]; all if
+    /// empty
+    pub fields: Vec<String>,
+    /// Write `{"header": …, "packages": […]}` instead of the array of
+    /// packages; with [`JsonFormat::Ndjson`], `{"header": …}` is the
+    /// first line
+    pub include_header: bool,
+    /// The representation of the packages and the header
+    pub serialize: SerializeOptions,
+}
+
+/// What [`eix_to_json`] converted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConversionStats {
+    pub categories: usize,
+    pub packages: usize,
+    pub versions: usize,
+    /// The size of the JSON output
+    pub bytes_written: u64,
+}
+
+/// Counts the bytes written to `inner`
+struct Counter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for Counter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads the packages one after the other, counting them
+struct Source<R: Read> {
+    reader: PackageReader<BufReader<R>>,
+    stats: ConversionStats,
+    /// The error which ended a [`PackageStream`]
+    error: Option<io::Error>,
+}
+
+impl<R: Read> Source<R> {
+    fn next(&mut self) -> io::Result<Option<Package>> {
+        let position = self.reader.position();
+        self.read().map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!(
+                    "Error reading package {} at byte {}: {}",
+                    self.stats.packages + 1,
+                    position,
+                    e
+                ),
+            )
+        })
+    }
+
+    fn read(&mut self) -> io::Result<Option<Package>> {
+        loop {
+            if let Some(package) = self.reader.read_package()? {
+                self.stats.packages += 1;
+                self.stats.versions += package.versions.len();
+                return Ok(Some(package));
+            }
+            if !self.reader.next_category()? {
+                return Ok(None);
+            }
+            self.stats.categories += 1;
+        }
+    }
+}
+
+/// The remaining packages of a [`Source`] as a sequence; a read error is
+/// kept in the source and fails the serialization
+struct PackageStream<'a, R: Read> {
+    source: &'a RefCell<Source<R>>,
+    options: &'a JsonOptions,
+}
+
+impl<R: Read> PackageStream<'_, R> {
+    fn next<E: serde::ser::Error>(&self) -> Result<Option<Package>, E> {
+        let mut source = self.source.borrow_mut();
+        source.next().map_err(|e| {
+            let error = E::custom(&e);
+            source.error = Some(e);
+            error
+        })
+    }
+
+    fn view<'a>(&'a self, package: &'a Package) -> crate::PackageView<'a> {
+        if self.options.fields.is_empty() {
+            self.options.serialize.view(package)
+        } else {
+            self.options
+                .serialize
+                .view_fields(package, &self.options.fields)
+        }
+    }
+}
+
+impl<R: Read> Serialize for PackageStream<'_, R> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        while let Some(package) = self.next()? {
+            seq.serialize_element(&self.view(&package))?;
+        }
+        seq.end()
+    }
+}
+
+/// The header followed by the packages
+struct Document<'a, R: Read> {
+    header: &'a DBHeader,
+    packages: PackageStream<'a, R>,
+}
+
+impl<R: Read> Serialize for Document<'_, R> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry(
+            "header",
+            &self.packages.options.serialize.view_header(self.header),
+        )?;
+        map.serialize_entry("packages", &self.packages)?;
+        map.end()
+    }
+}
+
+/// The first line of [`JsonFormat::Ndjson`] with the header
+#[derive(Serialize)]
+struct HeaderLine<'a> {
+    header: HeaderView<'a>,
+}
+
+/// Converts the database read from `input` to JSON written to `output`,
+/// see [`JsonOptions`] for the layout. Neither needs to be buffered.
+///
+/// Reading stops at the first error, which is returned with the number
+/// of the package and its offset, leaving the output incomplete. Fields not in [`PACKAGE_FIELDS`] are rejected as
+/// [`io::ErrorKind::InvalidInput`] before anything is read.
+pub fn eix_to_json<R: Read, W: Write>(
+    input: R,
+    output: W,
+    options: JsonOptions,
+) -> io::Result<ConversionStats> {
+    if let Some(field) = options
+        .fields
+        .iter()
+        .find(|f| !PACKAGE_FIELDS.contains(&f.as_str()))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unknown package field {:?}, expected one of {}",
+                field,
+                PACKAGE_FIELDS.join(", ")
+            ),
+        ));
+    }
+
+    let mut db = Database::new(BufReader::new(input));
+    let header = db
+        .read_header(DB_VERSION_CURRENT)
+        .map_err(|e| io::Error::new(e.kind(), format!("Error reading header: {}", e)))?;
+    let source = RefCell::new(Source {
+        reader: PackageReader::new(db, header.clone()),
+        stats: ConversionStats::default(),
+        error: None,
+    });
+    let mut out = Counter {
+        inner: BufWriter::new(output),
+        count: 0,
+    };
+
+    let written = write(&mut out, &header, &source, &options);
+    let mut source = source.into_inner();
+    if let Some(e) = source.error.take() {
+        return Err(e);
+    }
+    written?;
+    out.flush()?;
+    source.stats.bytes_written = out.count;
+    Ok(source.stats)
+}
+
+fn write<R: Read, W: Write>(
+    out: &mut W,
+    header: &DBHeader,
+    source: &RefCell<Source<R>>,
+    options: &JsonOptions,
+) -> io::Result<()> {
+    let packages = PackageStream { source, options };
+    let pretty = options.format == JsonFormat::Pretty;
+    if options.format == JsonFormat::Ndjson {
+        if options.include_header {
+            let header = options.serialize.view_header(header);
+            line(out, &HeaderLine { header }, false)?;
+        }
+        while let Some(package) = packages.next::<serde_json::Error>()? {
+            line(out, &packages.view(&package), false)?;
+        }
+    } else if options.include_header {
+        line(out, &Document { header, packages }, pretty)?;
+    } else {
+        line(out, &packages, pretty)?;
+    }
+    Ok(())
+}
+
+/// Writes `value` followed by a newline
+fn line<W: Write>(out: &mut W, value: &impl Serialize, pretty: bool) -> io::Result<()> {
+    if pretty {
+        serde_json::to_writer_pretty(&mut *out, value)?;
+    } else {
+        serde_json::to_writer(&mut *out, value)?;
+    }
+    out.write_all(b"\n")
+}
@@ -0,0 +1,392 @@
+//! Keyword and visibility helpers
+//!
+//! The eix database stores the KEYWORDS of every version as a list of
+//! words (e.g. `amd64 ~arm64 -sparc`). These helpers interpret them for
+//! a single architecture.
+
+use crate::{Atom, LocalConfig, MASK_HARD, Package, Version};
+use std::collections::BTreeMap;
+
+/// State of a version's keywords for one architecture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordState {
+    /// Keyworded stable (`amd64`)
+    Stable,
+    /// Keyworded testing (`~amd64`)
+    Testing,
+    /// Explicitly marked as broken (`-amd64`)
+    Broken,
+    /// Marked as broken for all architectures (`-*`) without an entry
+    /// for this one
+    MaskedAll,
+    /// No keyword for this architecture, only for others
+    Missing,
+    /// No keywords at all, as for most live ebuilds
+    NoKeywords,
+}
+
+impl Version {
+    /// Returns the keyword state of this version for `arch`. An entry
+    /// for `arch` takes precedence over `-*`, so `-* ~amd64` is testing
+    /// on amd64 and [`MaskedAll`](KeywordState::MaskedAll) elsewhere.
+    pub fn keyword_state(&self, arch: &str) -> KeywordState {
+        if self.keywords.is_empty() {
+            return KeywordState::NoKeywords;
+        }
+        let mut state = KeywordState::Missing;
+        for kw in &self.keywords {
+            if kw == arch {
+                return KeywordState::Stable;
+            }
+            if kw.strip_prefix('~') == Some(arch) {
+                state = KeywordState::Testing;
+            } else if kw.strip_prefix('-') == Some(arch) && state != KeywordState::Testing {
+                state = KeywordState::Broken;
+            } else if kw == "-*" && state == KeywordState::Missing {
+                state = KeywordState::MaskedAll;
+            }
+        }
+        state
+    }
+
+    /// True if the version is keyworded stable for `arch`
+    pub fn is_stable(&self, arch: &str) -> bool {
+        self.keyword_state(arch) == KeywordState::Stable
+    }
+
+    /// True if the version is keyworded testing for `arch`
+    pub fn is_testing(&self, arch: &str) -> bool {
+        self.keyword_state(arch) == KeywordState::Testing
+    }
+
+    /// True if the version is keyworded (stable or testing) for `arch`
+    pub fn has_keyword(&self, arch: &str) -> bool {
+        matches!(
+            self.keyword_state(arch),
+            KeywordState::Stable | KeywordState::Testing
+        )
+    }
+
+    /// True if the version is masked by package.mask or the profile
+    pub fn is_masked(&self) -> bool {
+        (self.mask_flags & MASK_HARD) != 0
+    }
+
+    /// Checks the keywords against a list of accepted keywords in the
+    /// syntax of ACCEPT_KEYWORDS: `arch`, `~arch`, `*` (any stable),
+    /// `~*` (any testing) and `**` (anything, even without keywords)
+    pub fn keywords_accepted<'a, I>(&self, accepted: I) -> bool
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let accepted: Vec<&str> = accepted.into_iter().collect();
+        if accepted.contains(&"**") {
+            return true;
+        }
+        self.keywords.iter().any(|kw| {
+            if kw.starts_with('-') {
+                return false;
+            }
+            accepted.contains(&kw.as_str())
+                || (kw.starts_with('~') && accepted.contains(&"~*"))
+                || (!kw.starts_with('~') && accepted.contains(&"*"))
+        })
+    }
+
+    /// Checks that the version is not masked and its keywords are
+    /// accepted, see [`keywords_accepted`](Self::keywords_accepted).
+    /// Without keywords, a version is only visible under `**`.
+    pub fn visible_under<'a, I>(&self, accepted: I) -> bool
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        !self.is_masked() && self.keywords_accepted(accepted)
+    }
+
+    /// Returns whether the version is visible: not masked and, if `arch`
+    /// is given, keyworded stable (or testing when `accept_unstable`).
+    pub fn is_visible(&self, arch: Option<&str>, accept_unstable: bool) -> bool {
+        if self.is_masked() {
+            return false;
+        }
+        match arch {
+            None => true,
+            Some(arch) => match self.keyword_state(arch) {
+                KeywordState::Stable => true,
+                KeywordState::Testing => accept_unstable,
+                KeywordState::Broken
+                | KeywordState::MaskedAll
+                | KeywordState::Missing
+                | KeywordState::NoKeywords => false,
+            },
+        }
+    }
+}
+
+/// Accepted keywords: the global ACCEPT_KEYWORDS plus per-package
+/// entries like those of `package.accept_keywords`
+#[derive(Debug, Clone, Default)]
+pub struct AcceptKeywords {
+    pub keywords: Vec<String>,
+    pub package_keywords: Vec<(Atom, Vec<String>)>,
+}
+
+impl AcceptKeywords {
+    /// Parses an ACCEPT_KEYWORDS value (e.g. `amd64 ~amd64`).
+    /// `-keyword` removes an earlier keyword, `-*` all of them.
+    pub fn parse(s: &str) -> Self {
+        let mut keywords: Vec<String> = Vec::new();
+        for word in s.split_whitespace() {
+            match word.strip_prefix('-') {
+                Some("*") => keywords.clear(),
+                Some(removed) => keywords.retain(|k| k != removed),
+                None => {
+                    if !keywords.iter().any(|k| k == word) {
+                        keywords.push(word.to_string());
+                    }
+                }
+            }
+        }
+        AcceptKeywords {
+            keywords,
+            package_keywords: Vec::new(),
+        }
+    }
+
+    /// Adds the entries of `package.accept_keywords` from `config`
+    pub fn with_config(mut self, config: &LocalConfig) -> Self {
+        self.package_keywords
+            .extend(config.accept_keywords.iter().cloned());
+        self
+    }
+
+    /// Returns the keywords accepted for `version` of `package`.
+    /// A package entry without keywords accepts `~arch` for every
+    /// stable `arch` of the global keywords.
+    pub fn accepted_for(&self, package: &Package, version: &Version) -> Vec<String> {
+        Self::accepted_with(&self.keywords, &self.package_keywords, package, version)
+    }
+
+    /// [`accepted_for`](Self::accepted_for) with borrowed global
+    /// keywords and package entries, e.g. those of a [`LocalConfig`]
+    pub(crate) fn accepted_with(
+        keywords: &[String],
+        package_keywords: &[(Atom, Vec<String>)],
+        package: &Package,
+        version: &Version,
+    ) -> Vec<String> {
+        let mut accepted = keywords.to_vec();
+        for (atom, entry) in package_keywords {
+            if !atom.matches(package, version) {
+                continue;
+            }
+            if entry.is_empty() {
+                accepted.extend(
+                    keywords
+                        .iter()
+                        .filter(|k| !k.starts_with('~') && !k.contains('*'))
+                        .map(|k| format!("~{}", k)),
+                );
+            }
+            accepted.extend(entry.iter().cloned());
+        }
+        accepted
+    }
+
+    /// Checks whether the keywords of `version` are accepted
+    pub fn accepts(&self, package: &Package, version: &Version) -> bool {
+        let accepted = self.accepted_for(package, version);
+        version.keywords_accepted(accepted.iter().map(|k| k.as_str()))
+    }
+
+    /// Checks whether `version` is not masked and its keywords are accepted
+    pub fn is_visible(&self, package: &Package, version: &Version) -> bool {
+        !version.is_masked() && self.accepts(package, version)
+    }
+
+    /// Returns the highest visible version for each normalized slot
+    pub fn best_per_slot<'a>(&self, package: &'a Package) -> BTreeMap<&'a str, &'a Version> {
+        let mut best: BTreeMap<&str, &Version> = BTreeMap::new();
+        for v in &package.versions {
+            if !self.is_visible(package, v) {
+                continue;
+            }
+            let current = best.entry(v.normalized_slot()).or_insert(v);
+            if v.compare(current).is_gt() {
+                *current = v;
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EffectiveMask, MASK_PACKAGE, parse_version};
+
+    fn version(keywords: &[&str], mask_flags: u8) -> Version {
+        Version {
+            keywords: keywords.iter().map(|s| s.to_string()).collect(),
+            mask_flags,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_keyword_state() {
+        let v = version(&["amd64", "~arm64", "-sparc"], 0);
+        assert_eq!(v.keyword_state("amd64"), KeywordState::Stable);
+        assert_eq!(v.keyword_state("arm64"), KeywordState::Testing);
+        assert_eq!(v.keyword_state("sparc"), KeywordState::Broken);
+        assert_eq!(v.keyword_state("x86"), KeywordState::Missing);
+        assert!(v.has_keyword("arm64"));
+        assert!(!v.has_keyword("sparc"));
+    }
+
+    #[test]
+    fn test_empty_keywords() {
+        let live = version(&[], 0);
+        assert_eq!(live.keyword_state("amd64"), KeywordState::NoKeywords);
+        assert!(!live.is_stable("amd64"));
+        assert!(!live.has_keyword("amd64"));
+        assert!(!live.is_visible(Some("amd64"), true));
+        assert!(!live.visible_under(["amd64", "~amd64", "*", "~*"]));
+        assert!(live.visible_under(["**"]));
+        assert!(!version(&[], MASK_PACKAGE).visible_under(["**"]));
+    }
+
+    #[test]
+    fn test_masked_all() {
+        let v = version(&["-*"], 0);
+        assert_eq!(v.keyword_state("amd64"), KeywordState::MaskedAll);
+        assert!(!v.has_keyword("amd64"));
+        assert!(!v.is_visible(Some("amd64"), true));
+        assert!(!v.visible_under(["amd64", "~amd64", "*", "~*"]));
+        assert!(v.visible_under(["**"]));
+    }
+
+    #[test]
+    fn test_masked_all_with_arch_entries() {
+        let v = version(&["-*", "~amd64", "-arm64", "x86"], 0);
+        assert_eq!(v.keyword_state("amd64"), KeywordState::Testing);
+        assert_eq!(v.keyword_state("arm64"), KeywordState::Broken);
+        assert_eq!(v.keyword_state("x86"), KeywordState::Stable);
+        assert_eq!(v.keyword_state("sparc"), KeywordState::MaskedAll);
+        assert!(!v.is_stable("amd64"));
+        assert!(v.has_keyword("amd64"));
+        assert!(!v.is_visible(Some("amd64"), false));
+        assert!(v.is_visible(Some("amd64"), true));
+        assert!(!v.visible_under(["amd64"]));
+        assert!(v.visible_under(["amd64", "~amd64"]));
+        assert!(!v.visible_under(["sparc", "~sparc"]));
+
+        // The order of the entries does not matter
+        let v = version(&["~amd64", "-*"], 0);
+        assert_eq!(v.keyword_state("amd64"), KeywordState::Testing);
+    }
+
+    #[test]
+    fn test_is_visible() {
+        let v = version(&["~amd64"], 0);
+        assert!(!v.is_visible(Some("amd64"), false));
+        assert!(v.is_visible(Some("amd64"), true));
+        assert!(v.is_visible(None, false));
+
+        let masked = version(&["amd64"], MASK_PACKAGE);
+        assert!(masked.is_masked());
+        assert!(!masked.is_visible(Some("amd64"), true));
+        assert!(!masked.is_visible(None, true));
+    }
+
+    #[test]
+    fn test_keywords_accepted() {
+        let v = version(&["amd64", "~arm64", "-sparc"], 0);
+        assert!(v.keywords_accepted(["amd64"]));
+        assert!(!v.keywords_accepted(["arm64"]));
+        assert!(v.keywords_accepted(["arm64", "~arm64"]));
+        assert!(v.keywords_accepted(["~*"]));
+        assert!(!v.keywords_accepted(["sparc", "~sparc"]));
+
+        let live = version(&[], 0);
+        assert!(!live.keywords_accepted(["amd64", "~amd64", "*", "~*"]));
+        assert!(live.keywords_accepted(["**"]));
+    }
+
+    #[test]
+    fn test_accept_keywords() {
+        let accept = AcceptKeywords::parse("amd64 ~amd64 x86 -~amd64 amd64");
+        assert_eq!(accept.keywords, vec!["amd64", "x86"]);
+        assert!(AcceptKeywords::parse("amd64 -* ~arm64").keywords == vec!["~arm64"]);
+
+        let pkg = Package {
+            category: "app-misc".to_string(),
+            name: "foo".to_string(),
+            ..Default::default()
+        };
+        let testing = version(&["~amd64"], 0);
+        assert!(!accept.accepts(&pkg, &testing));
+
+        let mut accept = accept;
+        accept
+            .package_keywords
+            .push((Atom::parse("app-misc/foo").unwrap(), Vec::new()));
+        assert_eq!(
+            accept.accepted_for(&pkg, &testing),
+            vec!["amd64", "x86", "~amd64", "~x86"]
+        );
+        assert!(accept.is_visible(&pkg, &testing));
+        assert!(!accept.is_visible(&pkg, &version(&["~amd64"], MASK_PACKAGE)));
+        assert!(!accept.accepts(&pkg, &version(&[], 0)));
+    }
+
+    #[test]
+    fn test_local_config_agrees() {
+        let config = LocalConfig {
+            accept_keywords: vec![
+                (Atom::parse("=app-misc/foo-1*").unwrap(), Vec::new()),
+                (
+                    Atom::parse("app-misc/foo").unwrap(),
+                    vec!["~arm64".to_string()],
+                ),
+                (
+                    Atom::parse("=app-misc/foo-9999").unwrap(),
+                    vec!["**".to_string()],
+                ),
+                (Atom::parse("app-misc/bar").unwrap(), Vec::new()),
+            ],
+            ..Default::default()
+        };
+        let accept = AcceptKeywords::parse("amd64").with_config(&config);
+        let pkg = Package {
+            category: "app-misc".to_string(),
+            name: "foo".to_string(),
+            ..Default::default()
+        };
+        for (v, keywords) in [
+            ("1.0", &["~amd64"][..]),
+            ("2.0", &["~amd64", "~arm64"]),
+            ("2.0", &["amd64"]),
+            ("9999", &[]),
+        ] {
+            let mut version = version(keywords, 0);
+            version.version_string = v.to_string();
+            version.parts = parse_version(v).unwrap();
+            let accepted = config.accepted_keywords(&pkg, &version, "amd64");
+            assert_eq!(accepted, accept.accepted_for(&pkg, &version), "{}", v);
+            assert_eq!(
+                version.effective_mask(&config, &pkg, "amd64") == EffectiveMask::Visible,
+                accept.is_visible(&pkg, &version),
+                "{}",
+                v
+            );
+        }
+        let mut v1 = version(&[], 0);
+        v1.version_string = "1.0".to_string();
+        v1.parts = parse_version("1.0").unwrap();
+        assert_eq!(
+            config.accepted_keywords(&pkg, &v1, "amd64"),
+            ["amd64", "~amd64", "~arm64"]
+        );
+    }
+}
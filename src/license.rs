@@ -0,0 +1,463 @@
+//! License expressions and ACCEPT_LICENSE evaluation
+//!
+//! `Package::licenses` holds the LICENSE variable of the ebuild, e.g.
+//! `GPL-2 || ( MIT BSD ) ssl? ( openssl )`.
+
+use crate::Package;
+use std::collections::{BTreeSet, HashMap, HashSet};
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// A parsed LICENSE expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+    /// A single license name
+    License(String),
+    /// All of the children are required (also the top level)
+    And(Vec<LicenseExpr>),
+    /// Any one of the children suffices: `|| ( ... )`
+    AnyOf(Vec<LicenseExpr>),
+    /// Children apply only if the USE flag is (not) set: `flag? ( ... )`
+    UseConditional {
+        flag: String,
+        negated: bool,
+        children: Vec<LicenseExpr>,
+    },
+}
+
+fn parse_error(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl LicenseExpr {
+    /// Parses a LICENSE expression
+    pub fn parse(s: &str) -> io::Result<LicenseExpr> {
+        let mut tokens = s.split_whitespace();
+        let children = Self::parse_group(&mut tokens, false)?;
+        Ok(LicenseExpr::And(children))
+    }
+
+    /// Parses tokens up to the closing parenthesis (if `nested`) or the end
+    fn parse_group<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        nested: bool,
+    ) -> io::Result<Vec<LicenseExpr>> {
+        let mut children = Vec::new();
+        while let Some(token) = tokens.next() {
+            match token {
+                ")" => {
+                    if !nested {
+                        return Err(parse_error("Unexpected ')' in license".to_string()));
+                    }
+                    return Ok(children);
+                }
+                "(" => children.push(LicenseExpr::And(Self::parse_group(tokens, true)?)),
+                "||" => {
+                    Self::expect_open(tokens, token)?;
+                    children.push(LicenseExpr::AnyOf(Self::parse_group(tokens, true)?));
+                }
+                _ if token.ends_with('?') => {
+                    Self::expect_open(tokens, token)?;
+                    let flag = &token[..token.len() - 1];
+                    let (negated, flag) = match flag.strip_prefix('!') {
+                        Some(f) => (true, f),
+                        None => (false, flag),
+                    };
+                    children.push(LicenseExpr::UseConditional {
+                        flag: flag.to_string(),
+                        negated,
+                        children: Self::parse_group(tokens, true)?,
+                    });
+                }
+                _ => children.push(LicenseExpr::License(token.to_string())),
+            }
+        }
+        if nested {
+            return Err(parse_error("Missing ')' in license".to_string()));
+        }
+        Ok(children)
+    }
+
+    fn expect_open<'a>(tokens: &mut impl Iterator<Item = &'a str>, after: &str) -> io::Result<()> {
+        match tokens.next() {
+            Some("(") => Ok(()),
+            _ => Err(parse_error(format!(
+                "Expected '(' after '{}' in license",
+                after
+            ))),
+        }
+    }
+
+    /// Returns all license names occurring in the expression
+    pub fn licenses(&self) -> BTreeSet<&str> {
+        let mut result = BTreeSet::new();
+        self.collect_licenses(&mut result);
+        result
+    }
+
+    fn collect_licenses<'a>(&'a self, result: &mut BTreeSet<&'a str>) {
+        match self {
+            LicenseExpr::License(name) => {
+                result.insert(name);
+            }
+            LicenseExpr::And(children)
+            | LicenseExpr::AnyOf(children)
+            | LicenseExpr::UseConditional { children, .. } => {
+                for child in children {
+                    child.collect_licenses(result);
+                }
+            }
+        }
+    }
+
+    /// Checks whether the expression is acceptable under `accept`.
+    ///
+    /// USE conditionals are treated as active, so the result holds
+    /// regardless of the USE flags of the installation.
+    pub fn acceptable_under(&self, accept: &AcceptLicense) -> bool {
+        match self {
+            LicenseExpr::License(name) => accept.accepts(name),
+            LicenseExpr::And(children) | LicenseExpr::UseConditional { children, .. } => {
+                children.iter().all(|c| c.acceptable_under(accept))
+            }
+            LicenseExpr::AnyOf(children) => children.iter().any(|c| c.acceptable_under(accept)),
+        }
+    }
+}
+
+impl Package {
+    /// Parses the LICENSE field of the package
+    pub fn license_expr(&self) -> io::Result<LicenseExpr> {
+        LicenseExpr::parse(&self.licenses)
+    }
+}
+
+/// Groups of licenses as defined in `profiles/license_groups`
+#[derive(Debug, Clone, Default)]
+pub struct LicenseGroups {
+    groups: HashMap<String, Vec<String>>,
+}
+
+impl LicenseGroups {
+    /// Reads a `license_groups` file
+    #[cfg(feature = "fs")]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parses the contents of a `license_groups` file: each line holds
+    /// a group name followed by its members; `#` starts a comment
+    pub fn parse(s: &str) -> Self {
+        let mut groups = HashMap::new();
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            if let Some(name) = words.next() {
+                groups.insert(name.to_string(), words.map(|w| w.to_string()).collect());
+            }
+        }
+        LicenseGroups { groups }
+    }
+
+    /// Returns the direct members of a group (given without `@`)
+    pub fn members(&self, group: &str) -> Option<&[String]> {
+        self.groups.get(group).map(|m| m.as_slice())
+    }
+
+    /// Checks whether `license` is a member of `group`, following
+    /// nested `@group` references. Errors as [`expand`](Self::expand).
+    pub fn contains(&self, group: &str, license: &str) -> io::Result<bool> {
+        Ok(self.expand(group)?.contains(license))
+    }
+
+    /// Expands a group (with or without leading `@`) to the set of
+    /// licenses it contains, resolving nested `@group` references.
+    /// Unknown groups and cyclic references are errors.
+    pub fn expand(&self, group: &str) -> io::Result<HashSet<String>> {
+        let mut result = HashSet::new();
+        let mut path = Vec::new();
+        self.expand_inner(group.trim_start_matches('@'), &mut path, &mut result)?;
+        Ok(result)
+    }
+
+    fn expand_inner<'a>(
+        &'a self,
+        group: &'a str,
+        path: &mut Vec<&'a str>,
+        result: &mut HashSet<String>,
+    ) -> io::Result<()> {
+        if path.contains(&group) {
+            path.push(group);
+            return Err(parse_error(format!(
+                "Cyclic license group: @{}",
+                path.join(" -> @")
+            )));
+        }
+        let members = self
+            .members(group)
+            .ok_or_else(|| parse_error(format!("Unknown license group: @{}", group)))?;
+        path.push(group);
+        for m in members {
+            match m.strip_prefix('@') {
+                Some(nested) => self.expand_inner(nested, path, result)?,
+                None => {
+                    result.insert(m.clone());
+                }
+            }
+        }
+        path.pop();
+        Ok(())
+    }
+}
+
+/// Returns the packages whose LICENSE is satisfied by the licenses of
+/// `group` alone, e.g. all free software for `@FREE`.
+/// Packages with an unparsable LICENSE are skipped.
+pub fn find_by_license_group<'a>(
+    packages: &'a [Package],
+    groups: &LicenseGroups,
+    group: &str,
+) -> io::Result<Vec<&'a Package>> {
+    let accept = AcceptLicense::parse(&format!("-* @{}", group.trim_start_matches('@')))
+        .with_groups(groups)?;
+    Ok(packages
+        .iter()
+        .filter(|p| {
+            p.license_expr()
+                .is_ok_and(|expr| expr.acceptable_under(&accept))
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AcceptItem {
+    All,
+    License(String),
+    Group(String),
+}
+
+/// The ACCEPT_LICENSE setting of portage, e.g. `-* @FREE GPL-2`.
+///
+/// Entries are evaluated in order; the last matching entry decides.
+#[derive(Debug, Clone, Default)]
+pub struct AcceptLicense {
+    entries: Vec<(bool, AcceptItem)>,
+    /// Expanded `@GROUP` entries, see [`AcceptLicense::with_groups`]
+    groups: HashMap<String, HashSet<String>>,
+}
+
+impl AcceptLicense {
+    /// Parses an ACCEPT_LICENSE value
+    pub fn parse(s: &str) -> Self {
+        let entries = s
+            .split_whitespace()
+            .map(|word| {
+                let (negated, word) = match word.strip_prefix('-') {
+                    Some(w) => (true, w),
+                    None => (false, word),
+                };
+                let item = if word == "*" {
+                    AcceptItem::All
+                } else if let Some(group) = word.strip_prefix('@') {
+                    AcceptItem::Group(group.to_string())
+                } else {
+                    AcceptItem::License(word.to_string())
+                };
+                (negated, item)
+            })
+            .collect();
+        AcceptLicense {
+            entries,
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Resolves the `@GROUP` entries with `groups`. Without groups,
+    /// `@GROUP` entries match no license.
+    /// Fails if a group is unknown or references itself.
+    pub fn with_groups(mut self, groups: &LicenseGroups) -> io::Result<Self> {
+        for (_, item) in &self.entries {
+            if let AcceptItem::Group(group) = item {
+                self.groups.insert(group.clone(), groups.expand(group)?);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Checks whether a single license is accepted
+    pub fn accepts(&self, license: &str) -> bool {
+        for (negated, item) in self.entries.iter().rev() {
+            let matches = match item {
+                AcceptItem::All => true,
+                AcceptItem::License(name) => name == license,
+                AcceptItem::Group(group) => self
+                    .groups
+                    .get(group)
+                    .is_some_and(|members| members.contains(license)),
+            };
+            if matches {
+                return !negated;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "fs")]
+    use std::io::Write;
+
+    fn lic(name: &str) -> LicenseExpr {
+        LicenseExpr::License(name.to_string())
+    }
+
+    #[test]
+    fn test_parse_nested() {
+        let expr =
+            LicenseExpr::parse("GPL-2 || ( MIT ( BSD ZLIB ) ) !ssl? ( || ( A B ) )").unwrap();
+        assert_eq!(
+            expr,
+            LicenseExpr::And(vec![
+                lic("GPL-2"),
+                LicenseExpr::AnyOf(vec![
+                    lic("MIT"),
+                    LicenseExpr::And(vec![lic("BSD"), lic("ZLIB")])
+                ]),
+                LicenseExpr::UseConditional {
+                    flag: "ssl".to_string(),
+                    negated: true,
+                    children: vec![LicenseExpr::AnyOf(vec![lic("A"), lic("B")])],
+                },
+            ])
+        );
+        let licenses: Vec<&str> = expr.licenses().into_iter().collect();
+        assert_eq!(licenses, vec!["A", "B", "BSD", "GPL-2", "MIT", "ZLIB"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(LicenseExpr::parse("|| ( MIT").is_err());
+        assert!(LicenseExpr::parse("MIT )").is_err());
+        assert!(LicenseExpr::parse("|| MIT").is_err());
+        assert!(LicenseExpr::parse("ssl? MIT").is_err());
+        assert_eq!(LicenseExpr::parse("").unwrap(), LicenseExpr::And(vec![]));
+    }
+
+    #[test]
+    fn test_accept_license() {
+        let accept = AcceptLicense::parse("* -BSD");
+        assert!(accept.accepts("MIT"));
+        assert!(!accept.accepts("BSD"));
+
+        let expr = LicenseExpr::parse("|| ( BSD MIT )").unwrap();
+        assert!(expr.acceptable_under(&accept));
+        let expr = LicenseExpr::parse("GPL-2 ssl? ( BSD )").unwrap();
+        assert!(!expr.acceptable_under(&accept));
+
+        let accept = AcceptLicense::parse("-* MIT");
+        assert!(
+            !LicenseExpr::parse("|| ( BSD ( MIT GPL-2 ) )")
+                .unwrap()
+                .acceptable_under(&accept)
+        );
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_accept_license_groups_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "# comment").unwrap();
+        writeln!(file, "GPL-COMPATIBLE MIT GPL-2 # inline comment").unwrap();
+        writeln!(file, "FREE @GPL-COMPATIBLE Apache-2.0").unwrap();
+        writeln!(file, "BINARY-REDISTRIBUTABLE @FREE nvidia").unwrap();
+
+        let groups = LicenseGroups::load(file.path()).unwrap();
+        assert_eq!(
+            groups.members("FREE").unwrap(),
+            &["@GPL-COMPATIBLE", "Apache-2.0"]
+        );
+        assert!(groups.contains("FREE", "MIT").unwrap());
+        assert!(!groups.contains("FREE", "nvidia").unwrap());
+
+        let accept = AcceptLicense::parse("-* @FREE -GPL-2")
+            .with_groups(&groups)
+            .unwrap();
+        assert!(accept.accepts("Apache-2.0"));
+        assert!(accept.accepts("MIT"));
+        assert!(!accept.accepts("GPL-2"));
+        assert!(!accept.accepts("nvidia"));
+
+        let expr = LicenseExpr::parse("|| ( nvidia || ( GPL-2 Apache-2.0 ) )").unwrap();
+        assert!(expr.acceptable_under(&accept));
+    }
+
+    #[test]
+    fn test_expand_groups() {
+        let groups = LicenseGroups::parse(
+            "GPL-COMPATIBLE MIT GPL-2\n\
+             OSI-APPROVED @GPL-COMPATIBLE Apache-2.0\n\
+             FREE @OSI-APPROVED @GPL-COMPATIBLE CC0-1.0\n\
+             LOOP-A @LOOP-B MIT\n\
+             LOOP-B @LOOP-A\n\
+             BROKEN @MISSING\n",
+        );
+        let free = groups.expand("@FREE").unwrap();
+        let expected: HashSet<String> = ["MIT", "GPL-2", "Apache-2.0", "CC0-1.0"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(free, expected);
+        assert_eq!(groups.expand("GPL-COMPATIBLE").unwrap().len(), 2);
+
+        let err = groups.expand("@LOOP-A").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("@LOOP-A -> @LOOP-B -> @LOOP-A"));
+        assert!(groups.expand("@BROKEN").is_err());
+        assert!(groups.expand("@MISSING").is_err());
+
+        // contains fails the same way instead of answering false
+        assert!(groups.contains("@FREE", "CC0-1.0").unwrap());
+        let err = groups.contains("LOOP-A", "MIT").unwrap_err();
+        assert!(err.to_string().contains("@LOOP-A -> @LOOP-B -> @LOOP-A"));
+        assert!(groups.contains("BROKEN", "MIT").is_err());
+
+        assert!(
+            AcceptLicense::parse("-* @LOOP-B")
+                .with_groups(&groups)
+                .is_err()
+        );
+        let accept = AcceptLicense::parse("-* @FREE")
+            .with_groups(&groups)
+            .unwrap();
+        assert!(accept.accepts("CC0-1.0"));
+        assert!(!AcceptLicense::parse("-* @FREE").accepts("CC0-1.0"));
+    }
+
+    #[test]
+    fn test_find_by_license_group() {
+        let groups = LicenseGroups::parse("FREE MIT GPL-2\n");
+        let pkg = |name: &str, licenses: &str| Package {
+            name: name.to_string(),
+            licenses: licenses.to_string(),
+            ..Default::default()
+        };
+        let packages = vec![
+            pkg("a", "MIT"),
+            pkg("b", "nvidia"),
+            pkg("c", "|| ( nvidia GPL-2 )"),
+            pkg("d", "MIT ( GPL-2"),
+        ];
+        let found: Vec<&str> = find_by_license_group(&packages, &groups, "@FREE")
+            .unwrap()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(found, vec!["a", "c"]);
+        assert!(find_by_license_group(&packages, &groups, "@NONFREE").is_err());
+    }
+}
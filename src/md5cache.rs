@@ -0,0 +1,327 @@
+//! Cross-check of the database against the md5-cache of a repository
+//!
+//! Every ebuild of a repository has a metadata file
+//! `metadata/md5-cache/<category>/<name>-<version>` with lines of
+//! `KEY=value`. Comparing these with the database shows whether the
+//! eix cache is stale.
+
+use crate::atom::parse_cpv;
+use crate::{Package, Version, split_slot};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A metadata field compared by [`crosscheck_md5_cache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CacheField {
+    Slot,
+    Eapi,
+    Keywords,
+    Depend,
+    Rdepend,
+    Pdepend,
+    Bdepend,
+    Idepend,
+}
+
+/// A difference between the database and the md5-cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Discrepancy {
+    /// A field differs; values are whitespace-normalized
+    Mismatch {
+        cpv: String,
+        field: CacheField,
+        database: String,
+        cache: String,
+    },
+    /// The database has a version without a cache entry
+    MissingInCache { cpv: String },
+    /// The cache has an ebuild of a checked package which is not in the database
+    MissingInDatabase { cpv: String },
+}
+
+/// Reads an md5-cache entry into its key/value pairs
+fn read_cache_entry(path: &Path) -> io::Result<Option<HashMap<String, String>>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(Some(
+        content
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    ))
+}
+
+fn normalize(words: &[String]) -> String {
+    words.join(" ")
+}
+
+/// Normalizes a SLOT value; eix stores "0" as ""
+fn full_slot(slot: &str) -> String {
+    match split_slot(slot) {
+        (slot, Some(subslot)) => format!("{}/{}", slot, subslot),
+        (slot, None) => slot.to_string(),
+    }
+}
+
+/// Collects the fields of `version` and the cache entry to compare
+fn compared_fields(
+    version: &Version,
+    cache: &HashMap<String, String>,
+) -> Vec<(CacheField, String, String)> {
+    let cached = |key: &str| {
+        cache
+            .get(key)
+            .map(|v| v.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_default()
+    };
+    let cache_eapi = cache
+        .get("EAPI")
+        .cloned()
+        .unwrap_or_else(|| "0".to_string());
+
+    let mut fields = vec![
+        (
+            CacheField::Slot,
+            full_slot(&version.slot),
+            full_slot(&cached("SLOT")),
+        ),
+        (CacheField::Eapi, version.eapi.clone(), cache_eapi),
+        (
+            CacheField::Keywords,
+            normalize(&version.keywords),
+            cached("KEYWORDS"),
+        ),
+    ];
+    if let Some(depend) = version.depend() {
+        // eix stores DEPEND identical to RDEPEND as a single `"`
+        let depend_words = if depend.depend == ["\""] {
+            &depend.rdepend
+        } else {
+            &depend.depend
+        };
+        fields.extend([
+            (
+                CacheField::Depend,
+                normalize(depend_words),
+                cached("DEPEND"),
+            ),
+            (
+                CacheField::Rdepend,
+                normalize(&depend.rdepend),
+                cached("RDEPEND"),
+            ),
+            (
+                CacheField::Pdepend,
+                normalize(&depend.pdepend),
+                cached("PDEPEND"),
+            ),
+            (
+                CacheField::Bdepend,
+                normalize(&depend.bdepend),
+                cached("BDEPEND"),
+            ),
+            (
+                CacheField::Idepend,
+                normalize(&depend.idepend),
+                cached("IDEPEND"),
+            ),
+        ]);
+    }
+    fields
+}
+
+/// Reads the repository name from `profiles/repo_name`, if present
+fn repo_name(repo_path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(repo_path.join("profiles").join("repo_name")) {
+        Ok(name) => Ok(Some(name.trim().to_string())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Lists the `name-version` entries of a category directory of the cache
+fn cache_category(cache_root: &Path, category: &str) -> io::Result<BTreeSet<String>> {
+    let dir = cache_root.join(category);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(e) => return Err(e),
+    };
+    let mut names = BTreeSet::new();
+    for entry in entries {
+        names.insert(entry?.file_name().to_string_lossy().into_owned());
+    }
+    Ok(names)
+}
+
+/// Compares `packages` (all of the database or a sample) with the
+/// md5-cache of the repository at `repo_path`.
+///
+/// SLOT, EAPI, KEYWORDS and, if the database stores them, the
+/// dependencies are compared. If the repository has a
+/// `profiles/repo_name`, only versions of that repository are checked.
+pub fn crosscheck_md5_cache<P: AsRef<Path>>(
+    packages: &[Package],
+    repo_path: P,
+) -> io::Result<Vec<Discrepancy>> {
+    let repo_path = repo_path.as_ref();
+    let cache_root = repo_path.join("metadata").join("md5-cache");
+    let repo = repo_name(repo_path)?;
+    let mut categories: HashMap<&str, BTreeSet<String>> = HashMap::new();
+    let mut result = Vec::new();
+
+    for pkg in packages {
+        if !categories.contains_key(pkg.category.as_str()) {
+            categories.insert(&pkg.category, cache_category(&cache_root, &pkg.category)?);
+        }
+        let mut known = BTreeSet::new();
+        for v in &pkg.versions {
+            if repo.as_deref().is_some_and(|r| r != &*v.reponame) {
+                continue;
+            }
+            let pv = format!("{}-{}", pkg.name, v.version_string);
+            let cpv = format!("{}/{}", pkg.category, pv);
+            let Some(cache) = read_cache_entry(&cache_root.join(&pkg.category).join(&pv))? else {
+                result.push(Discrepancy::MissingInCache { cpv });
+                continue;
+            };
+            for (field, database, cache) in compared_fields(v, &cache) {
+                if database != cache {
+                    result.push(Discrepancy::Mismatch {
+                        cpv: cpv.clone(),
+                        field,
+                        database,
+                        cache,
+                    });
+                }
+            }
+            known.insert(pv);
+        }
+
+        for pv in &categories[pkg.category.as_str()] {
+            let cpv = format!("{}/{}", pkg.category, pv);
+            let is_this_package = parse_cpv(&cpv).is_some_and(|(_, name, _)| name == pkg.name);
+            if is_this_package && !known.contains(pv) {
+                result.push(Discrepancy::MissingInDatabase { cpv });
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Depend, parse_version};
+
+    fn version(v: &str, slot: &str, keywords: &[&str]) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            eapi: "8".to_string(),
+            slot: slot.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            reponame: "gentoo".into(),
+            ..Default::default()
+        }
+    }
+
+    fn write_cache(root: &Path, cpv: &str, content: &str) {
+        let path = root.join("metadata/md5-cache").join(cpv);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_crosscheck() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("profiles")).unwrap();
+        fs::write(root.join("profiles/repo_name"), "gentoo\n").unwrap();
+        write_cache(
+            root,
+            "dev-lang/lua-5.4.8",
+            "DEPEND=>=app-eselect/eselect-lua-3  readline? ( sys-libs/readline:= )\n\
+             EAPI=8\nKEYWORDS=amd64 ~arm64\n\
+             RDEPEND=>=app-eselect/eselect-lua-3 readline? ( sys-libs/readline:= )\n\
+             SLOT=5.4\n_md5_=0123\n",
+        );
+        write_cache(root, "dev-lang/lua-5.4.9", "EAPI=8\nSLOT=5.4\n");
+        write_cache(
+            root,
+            "dev-lang/lua-5.1.5",
+            "EAPI=7\nSLOT=5.1\nKEYWORDS=amd64\n",
+        );
+        write_cache(root, "dev-lang/luajit-2.1", "EAPI=8\nSLOT=2\n");
+        write_cache(root, "app-misc/foo-1", "EAPI=8\nSLOT=0/1\n");
+
+        let mut lua_548 = version("5.4.8", "5.4", &["amd64", "~arm64"]);
+        lua_548.depend = Some(Depend {
+            depend: vec!["\"".to_string()],
+            rdepend: ">=app-eselect/eselect-lua-3 readline? ( sys-libs/readline:= )"
+                .split_whitespace()
+                .map(|w| w.to_string())
+                .collect(),
+            ..Default::default()
+        });
+        let mut overlay = version("5.4.10", "5.4", &[]);
+        overlay.reponame = "guru".into();
+        let packages = vec![
+            Package {
+                category: "dev-lang".to_string(),
+                name: "lua".to_string(),
+                versions: vec![
+                    version("5.1.5", "5.1", &["~amd64"]),
+                    lua_548,
+                    version("5.4.7", "5.4", &[]),
+                    overlay,
+                ],
+                ..Default::default()
+            },
+            Package {
+                category: "app-misc".to_string(),
+                name: "foo".to_string(),
+                versions: vec![version("1", "/1", &[])],
+                ..Default::default()
+            },
+        ];
+
+        let result = crosscheck_md5_cache(&packages, root).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Discrepancy::Mismatch {
+                    cpv: "dev-lang/lua-5.1.5".to_string(),
+                    field: CacheField::Eapi,
+                    database: "8".to_string(),
+                    cache: "7".to_string(),
+                },
+                Discrepancy::Mismatch {
+                    cpv: "dev-lang/lua-5.1.5".to_string(),
+                    field: CacheField::Keywords,
+                    database: "~amd64".to_string(),
+                    cache: "amd64".to_string(),
+                },
+                Discrepancy::MissingInCache {
+                    cpv: "dev-lang/lua-5.4.7".to_string()
+                },
+                Discrepancy::MissingInDatabase {
+                    cpv: "dev-lang/lua-5.4.9".to_string()
+                },
+            ]
+        );
+
+        assert!(
+            crosscheck_md5_cache(&packages[1..], root)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
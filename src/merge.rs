@@ -0,0 +1,836 @@
+//! Merging of package lists
+//!
+//! Used when the same category/name is read from several databases
+//! (e.g. the main tree database and a locally generated overlay database).
+
+use crate::{Catsize, DBHeader, Package, StringHash, Version};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// How versions of packages with identical atoms are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// Concatenate the version lists
+    #[default]
+    KeepAll,
+    /// Concatenate the version lists, but keep only one version per
+    /// version string and slot, preferring the higher overlay priority
+    DedupPreferHigherPriority,
+    /// Concatenate the version lists, but keep only the first version
+    /// per version string and repository, e.g. for the same repository
+    /// read on several machines
+    DedupByRepository,
+}
+
+/// Which description, homepage and licenses a combined package gets
+/// when the inputs disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataPolicy {
+    /// Those of the first package
+    #[default]
+    KeepFirst,
+    /// Those of the last package
+    KeepLast,
+    /// Those of the package whose versions have the highest overlay
+    /// priority, the first of equal ones
+    HighestPriority,
+}
+
+/// Options of [`merge_databases_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeOptions {
+    pub versions: MergePolicy,
+    pub metadata: MetadataPolicy,
+}
+
+impl Default for MergeOptions {
+    /// The options of [`merge_databases`]
+    fn default() -> Self {
+        MergeOptions {
+            versions: MergePolicy::DedupPreferHigherPriority,
+            metadata: MetadataPolicy::KeepFirst,
+        }
+    }
+}
+
+/// Kind of a conflict found while merging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ConflictKind {
+    Description,
+    Homepage,
+    Licenses,
+    DuplicateVersion,
+}
+
+/// A conflict found while merging; which value is kept depends on the
+/// [`MetadataPolicy`] for metadata and the [`MergePolicy`] for versions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    pub atom: String,
+    pub kind: ConflictKind,
+    pub kept: String,
+    pub discarded: String,
+}
+
+/// Result information of [`merge_packages`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeReport {
+    /// Merged overlay table: the overlay key of a version is its index
+    pub overlays: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Builds a merged overlay table from the repository labels of all versions.
+/// Overlays are ordered by their original priority, then by first appearance.
+fn merged_overlay_table(packages: &[Package]) -> Vec<String> {
+    let mut seen: HashMap<&str, (i32, usize)> = HashMap::new();
+    for v in packages.iter().flat_map(|p| &p.versions) {
+        let first_seen = seen.len();
+        seen.entry(&v.reponame).or_insert((v.priority, first_seen));
+    }
+    let mut labels: Vec<_> = seen.into_iter().collect();
+    labels.sort_by_key(|&(_, (priority, first_seen))| (priority, first_seen));
+    labels.into_iter().map(|(l, _)| l.to_string()).collect()
+}
+
+fn check_metadata(
+    conflicts: &mut Vec<Conflict>,
+    atom: &str,
+    kind: ConflictKind,
+    kept: &str,
+    other: &str,
+) {
+    if kept != other {
+        conflicts.push(Conflict {
+            atom: atom.to_string(),
+            kind,
+            kept: kept.to_string(),
+            discarded: other.to_string(),
+        });
+    }
+}
+
+/// The group of each of `keys`: equal keys get the group of the first
+/// one, and groups are numbered in order of first appearance
+fn group_indices<K: Hash + Eq>(keys: impl Iterator<Item = K>) -> Vec<usize> {
+    let mut groups = HashMap::new();
+    keys.map(|key| {
+        let next = groups.len();
+        *groups.entry(key).or_insert(next)
+    })
+    .collect()
+}
+
+/// Drops the versions of `pkg` repeated by version and slot, or by version
+/// and repository, reporting each as a conflict; unlike
+/// [`Package::dedup_versions`] the higher priority wins
+fn resolve_duplicates(pkg: &mut Package, by_repository: bool, conflicts: &mut Vec<Conflict>) {
+    let groups = if by_repository {
+        group_indices(pkg.versions.iter().map(|v| pkg.version_key(v)))
+    } else {
+        // Versions of different repositories are duplicates here, so
+        // Package::version_key does not fit
+        group_indices(
+            pkg.versions
+                .iter()
+                .map(|v| (v.version_string.as_str(), v.slot.as_str())),
+        )
+    };
+    let atom = format!("{}/{}", pkg.category, pkg.name);
+    let mut kept: Vec<Version> = Vec::with_capacity(pkg.versions.len());
+    for (v, i) in pkg.versions.drain(..).zip(groups) {
+        if i == kept.len() {
+            kept.push(v);
+            continue;
+        }
+        let (winner, loser) = if !by_repository && v.priority > kept[i].priority {
+            (v, std::mem::take(&mut kept[i]))
+        } else {
+            let current = kept[i].clone();
+            (current, v)
+        };
+        conflicts.push(Conflict {
+            atom: atom.clone(),
+            kind: ConflictKind::DuplicateVersion,
+            kept: format!("{}::{}", winner.version_string, winner.reponame),
+            discarded: format!("{}::{}", loser.version_string, loser.reponame),
+        });
+        kept[i] = winner;
+    }
+    pkg.versions = kept;
+}
+
+/// The highest overlay priority of the versions of `pkg`
+fn package_priority(pkg: &Package) -> Option<i32> {
+    pkg.versions.iter().map(|v| v.priority).max()
+}
+
+/// Combines packages with identical category/name; the overlay keys
+/// and priorities of the versions must already refer to one table
+fn combine_packages(
+    packages: Vec<Package>,
+    policy: MergePolicy,
+    metadata: MetadataPolicy,
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<Package> {
+    let mut merged: Vec<Package> = Vec::new();
+    // The priority of the package whose metadata was taken
+    let mut priorities: Vec<Option<i32>> = Vec::new();
+    let groups = group_indices(packages.iter().map(Package::key));
+
+    for (mut pkg, i) in packages.into_iter().zip(groups) {
+        if i == merged.len() {
+            priorities.push(package_priority(&pkg));
+            merged.push(pkg);
+            continue;
+        }
+        let priority = package_priority(&pkg);
+        let replace = match metadata {
+            MetadataPolicy::KeepFirst => false,
+            MetadataPolicy::KeepLast => true,
+            MetadataPolicy::HighestPriority => priority > priorities[i],
+        };
+        let target = &mut merged[i];
+        if replace {
+            std::mem::swap(&mut target.description, &mut pkg.description);
+            std::mem::swap(&mut target.homepage, &mut pkg.homepage);
+            std::mem::swap(&mut target.licenses, &mut pkg.licenses);
+            priorities[i] = priority;
+        }
+        let name = format!("{}/{}", target.category, target.name);
+        check_metadata(
+            conflicts,
+            &name,
+            ConflictKind::Description,
+            &target.description,
+            &pkg.description,
+        );
+        check_metadata(
+            conflicts,
+            &name,
+            ConflictKind::Homepage,
+            &target.homepage,
+            &pkg.homepage,
+        );
+        check_metadata(
+            conflicts,
+            &name,
+            ConflictKind::Licenses,
+            &target.licenses,
+            &pkg.licenses,
+        );
+        target.versions.append(&mut pkg.versions);
+    }
+
+    if policy != MergePolicy::KeepAll {
+        let by_repository = policy == MergePolicy::DedupByRepository;
+        for pkg in &mut merged {
+            resolve_duplicates(pkg, by_repository, conflicts);
+        }
+    }
+    merged
+}
+
+/// Combines packages with identical category/name by concatenating their
+/// version lists. The overlay keys of all versions are remapped against
+/// a merged overlay table built from the repository labels; priorities
+/// are kept, so duplicates are resolved by them, the first of equal ones
+/// winning.
+///
+/// Packages keep the order of their first appearance, which need not be
+/// sorted; metadata of the first appearance wins and differences are
+/// reported as conflicts.
+pub fn merge_packages(
+    mut packages: Vec<Package>,
+    policy: MergePolicy,
+) -> (Vec<Package>, MergeReport) {
+    let overlays = merged_overlay_table(&packages);
+    let keys: HashMap<String, u64> = overlays
+        .iter()
+        .enumerate()
+        .map(|(i, l)| (l.clone(), i as u64))
+        .collect();
+    for v in packages.iter_mut().flat_map(|p| &mut p.versions) {
+        v.overlay_key = keys[&*v.reponame];
+    }
+
+    let mut conflicts = Vec::new();
+    let merged = combine_packages(packages, policy, MetadataPolicy::KeepFirst, &mut conflicts);
+    (
+        merged,
+        MergeReport {
+            overlays,
+            conflicts,
+        },
+    )
+}
+
+fn union_hash(target: &mut StringHash, other: &StringHash) {
+    for i in 0..other.len() {
+        target.add(other.get_string(i).unwrap_or_default().to_string());
+    }
+}
+
+/// Concatenates databases: overlay tables (overlays with identical label
+/// and path are the same), string hashes and world sets are united and
+/// the overlay keys of all versions remapped. Packages are not combined.
+///
+/// An overlay keeps its priority, the highest one if the inputs disagree,
+/// and so do its versions. The hashes get every string once, in order of
+/// appearance; a single input is therefore not returned unchanged.
+pub(crate) fn unite_databases(inputs: Vec<(DBHeader, Vec<Package>)>) -> (DBHeader, Vec<Package>) {
+    let mut header = DBHeader {
+        version: 0,
+        size: 0,
+        overlays: Vec::new(),
+        eapi_hash: StringHash::new(),
+        license_hash: StringHash::new(),
+        keywords_hash: StringHash::new(),
+        iuse_hash: StringHash::new(),
+        slot_hash: StringHash::new(),
+        depend_hash: StringHash::new(),
+        use_depend: false,
+        use_required_use: false,
+        use_src_uri: false,
+        world_sets: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let mut all_packages = Vec::new();
+
+    for (input_header, mut packages) in inputs {
+        header.version = header.version.max(input_header.version);
+        header.use_depend |= input_header.use_depend;
+        header.use_required_use |= input_header.use_required_use;
+        header.use_src_uri |= input_header.use_src_uri;
+        union_hash(&mut header.eapi_hash, &input_header.eapi_hash);
+        union_hash(&mut header.license_hash, &input_header.license_hash);
+        union_hash(&mut header.keywords_hash, &input_header.keywords_hash);
+        union_hash(&mut header.iuse_hash, &input_header.iuse_hash);
+        union_hash(&mut header.slot_hash, &input_header.slot_hash);
+        union_hash(&mut header.depend_hash, &input_header.depend_hash);
+        for set in input_header.world_sets {
+            if !header.world_sets.contains(&set) {
+                header.world_sets.push(set);
+            }
+        }
+
+        // Map the overlay keys of this input to the merged table
+        let mut keys = Vec::with_capacity(input_header.overlays.len());
+        for overlay in input_header.overlays {
+            let key = match header
+                .overlays
+                .iter()
+                .position(|o| o.label == overlay.label && o.path == overlay.path)
+            {
+                Some(key) => {
+                    let merged = &mut header.overlays[key];
+                    merged.priority = merged.priority.max(overlay.priority);
+                    key
+                }
+                None => {
+                    header.overlays.push(overlay);
+                    header.overlays.len() - 1
+                }
+            };
+            keys.push(key);
+        }
+        for v in packages.iter_mut().flat_map(|p| &mut p.versions) {
+            if let Some(&key) = keys.get(v.overlay_key as usize) {
+                v.overlay_key = key as u64;
+            }
+        }
+        all_packages.extend(packages);
+    }
+    for v in all_packages.iter_mut().flat_map(|p| &mut p.versions) {
+        if let Some(overlay) = header.overlays.get(v.overlay_key as usize) {
+            v.priority = overlay.priority;
+        }
+    }
+
+    let mut categories: Vec<&str> = all_packages.iter().map(|p| p.category.as_str()).collect();
+    categories.sort_unstable();
+    categories.dedup();
+    header.size = categories.len() as Catsize;
+    (header, all_packages)
+}
+
+/// Merges complete databases into one which can be written with
+/// [`EixWriter`](crate::EixWriter).
+///
+/// The overlay tables are united (overlays with identical label and
+/// path are the same, with the highest of their priorities), and the
+/// overlay keys of all versions remapped. String hashes and world sets are united, and packages with the same
+/// category/name are combined with
+/// [`MergePolicy::DedupPreferHigherPriority`], so a version of an
+/// overlay shared by several inputs is kept once. Packages are sorted
+/// by category and name.
+pub fn merge_databases(inputs: Vec<(DBHeader, Vec<Package>)>) -> (DBHeader, Vec<Package>) {
+    let (header, packages, _) = merge_databases_with(inputs, MergeOptions::default());
+    (header, packages)
+}
+
+/// Like [`merge_databases`], combining packages according to `options`
+/// and reporting the conflicts. The overlay table of the report is that
+/// of the merged header.
+pub fn merge_databases_with(
+    inputs: Vec<(DBHeader, Vec<Package>)>,
+    options: MergeOptions,
+) -> (DBHeader, Vec<Package>, MergeReport) {
+    let (mut header, all_packages) = unite_databases(inputs);
+    let mut conflicts = Vec::new();
+    let mut packages = combine_packages(
+        all_packages,
+        options.versions,
+        options.metadata,
+        &mut conflicts,
+    );
+    packages.sort_by(|a, b| (&a.category, &a.name).cmp(&(&b.category, &b.name)));
+    let mut categories: Vec<&str> = packages.iter().map(|p| p.category.as_str()).collect();
+    categories.dedup();
+    header.size = categories.len() as Catsize;
+    let report = MergeReport {
+        overlays: header
+            .overlays
+            .iter()
+            .map(|o| o.label.to_string())
+            .collect(),
+        conflicts,
+    };
+    (header, packages, report)
+}
+
+/// Merges the databases of several machines into one combined view,
+/// like [`merge_databases_with`]: versions are kept once per version
+/// string and repository ([`MergePolicy::DedupByRepository`]), and
+/// differing descriptions, homepages and licenses are resolved by
+/// `metadata`.
+pub fn merge(
+    inputs: Vec<(DBHeader, Vec<Package>)>,
+    metadata: MetadataPolicy,
+) -> (DBHeader, Vec<Package>) {
+    let options = MergeOptions {
+        versions: MergePolicy::DedupByRepository,
+        metadata,
+    };
+    let (header, packages, _) = merge_databases_with(inputs, options);
+    (header, packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OverlayIdent;
+
+    fn version(version: &str, slot: &str, overlay_key: u64, reponame: &str) -> Version {
+        Version {
+            version_string: version.to_string(),
+            slot: slot.to_string(),
+            overlay_key,
+            reponame: reponame.into(),
+            priority: overlay_key as i32,
+            ..Default::default()
+        }
+    }
+
+    fn package(name: &str, description: &str, versions: Vec<Version>) -> Package {
+        Package {
+            category: "app-misc".to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            versions,
+            ..Default::default()
+        }
+    }
+
+    /// Main tree database: gentoo only
+    fn main_db() -> Vec<Package> {
+        vec![
+            package("foo", "Foo", vec![version("1.0", "0", 0, "gentoo")]),
+            package("bar", "Bar", vec![version("2.0", "0", 0, "gentoo")]),
+        ]
+    }
+
+    /// Overlay database: the overlay is at key 0 in its own header, with
+    /// a higher priority than gentoo
+    fn overlay_db() -> Vec<Package> {
+        let mut packages = vec![
+            package(
+                "foo",
+                "Foo from overlay",
+                vec![
+                    version("1.0", "0", 0, "local"),
+                    version("1.1", "0", 0, "local"),
+                ],
+            ),
+            package("baz", "Baz", vec![version("0.1", "0", 0, "local")]),
+        ];
+        for v in packages.iter_mut().flat_map(|p| &mut p.versions) {
+            v.priority = 10;
+        }
+        packages
+    }
+
+    #[test]
+    fn test_merge_keep_all() {
+        let mut input = main_db();
+        input.extend(overlay_db());
+        let (merged, report) = merge_packages(input, MergePolicy::KeepAll);
+
+        let names: Vec<&str> = merged.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["foo", "bar", "baz"]);
+        assert_eq!(report.overlays, vec!["gentoo", "local"]);
+
+        let foo = &merged[0];
+        assert_eq!(foo.versions.len(), 3);
+        assert_eq!(foo.description, "Foo");
+        for v in &foo.versions {
+            assert_eq!(report.overlays[v.overlay_key as usize], *v.reponame);
+        }
+        assert_eq!(merged[2].versions[0].overlay_key, 1);
+
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kind, ConflictKind::Description);
+        assert_eq!(report.conflicts[0].atom, "app-misc/foo");
+        assert_eq!(report.conflicts[0].discarded, "Foo from overlay");
+    }
+
+    #[test]
+    fn test_merge_dedup() {
+        let mut input = main_db();
+        input.extend(overlay_db());
+        let (merged, report) = merge_packages(input, MergePolicy::DedupPreferHigherPriority);
+
+        let foo = &merged[0];
+        let versions: Vec<(&str, &str)> = foo
+            .versions
+            .iter()
+            .map(|v| (v.version_string.as_str(), &*v.reponame))
+            .collect();
+        assert_eq!(versions, vec![("1.0", "local"), ("1.1", "local")]);
+
+        let duplicate = report
+            .conflicts
+            .iter()
+            .find(|c| c.kind == ConflictKind::DuplicateVersion)
+            .unwrap();
+        assert_eq!(duplicate.kept, "1.0::local");
+        assert_eq!(duplicate.discarded, "1.0::gentoo");
+    }
+
+    #[test]
+    fn test_merge_by_priority() {
+        // The overlay of the first input has the higher priority
+        let mut input = overlay_db();
+        input.extend(main_db());
+        let (merged, report) = merge_packages(input, MergePolicy::DedupPreferHigherPriority);
+        assert_eq!(report.overlays, vec!["gentoo", "local"]);
+
+        let foo = &merged[0];
+        let versions: Vec<(&str, &str, i32)> = foo
+            .versions
+            .iter()
+            .map(|v| (v.version_string.as_str(), &*v.reponame, v.priority))
+            .collect();
+        assert_eq!(versions, vec![("1.0", "local", 10), ("1.1", "local", 10)]);
+        assert_eq!(foo.versions[0].overlay_key, 1);
+
+        // Of equal priorities, the first one is kept
+        let input = vec![
+            package("foo", "Foo", vec![version("1.0", "0", 0, "gentoo")]),
+            package("foo", "Foo", vec![version("1.0", "0", 0, "other")]),
+        ];
+        let (merged, report) = merge_packages(input, MergePolicy::DedupPreferHigherPriority);
+        assert_eq!(report.overlays, vec!["gentoo", "other"]);
+        assert_eq!(&*merged[0].versions[0].reponame, "gentoo");
+        assert_eq!(report.conflicts[0].discarded, "1.0::other");
+    }
+
+    #[test]
+    fn test_merge_different_slots_are_kept() {
+        let input = vec![
+            package("foo", "Foo", vec![version("1.0", "1", 0, "gentoo")]),
+            package("foo", "Foo", vec![version("1.0", "2", 0, "gentoo")]),
+        ];
+        let (merged, report) = merge_packages(input, MergePolicy::DedupPreferHigherPriority);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].versions.len(), 2);
+        assert!(report.conflicts.is_empty());
+    }
+
+    fn header(overlays: &[(&str, &str)], world_sets: &[&str]) -> DBHeader {
+        let mut keywords_hash = StringHash::new();
+        keywords_hash.add("amd64".to_string());
+        DBHeader {
+            version: 39,
+            size: 1,
+            overlays: overlays
+                .iter()
+                .enumerate()
+                .map(|(i, (path, label))| OverlayIdent {
+                    path: path.to_string(),
+                    label: (*label).into(),
+                    priority: i as i32,
+                })
+                .collect(),
+            eapi_hash: StringHash::new(),
+            license_hash: StringHash::new(),
+            keywords_hash,
+            iuse_hash: StringHash::new(),
+            slot_hash: StringHash::new(),
+            depend_hash: StringHash::new(),
+            use_depend: false,
+            use_required_use: false,
+            use_src_uri: false,
+            world_sets: world_sets.iter().map(|s| s.to_string()).collect(),
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_merge_databases() {
+        let host_a = (
+            header(&[("/var/db/repos/gentoo", "gentoo")], &["@kde"]),
+            main_db(),
+        );
+        let host_b = (
+            header(
+                &[
+                    ("/var/db/repos/local", "local"),
+                    ("/var/db/repos/gentoo", "gentoo"),
+                ],
+                &["@kde", "@gnome"],
+            ),
+            vec![
+                package(
+                    "foo",
+                    "Foo",
+                    vec![
+                        version("1.0", "0", 1, "gentoo"),
+                        version("1.1", "0", 0, "local"),
+                    ],
+                ),
+                package("baz", "Baz", vec![version("0.1", "0", 0, "local")]),
+            ],
+        );
+        let (merged_header, packages) = merge_databases(vec![host_a, host_b]);
+
+        let labels: Vec<&str> = merged_header.overlays.iter().map(|o| &*o.label).collect();
+        assert_eq!(labels, vec!["gentoo", "local"]);
+        assert_eq!(merged_header.world_sets, vec!["@kde", "@gnome"]);
+        assert_eq!(merged_header.keywords_hash.len(), 1);
+        assert_eq!(merged_header.size, 1);
+
+        let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["bar", "baz", "foo"]);
+        let foo = &packages[2];
+        let versions: Vec<(&str, u64)> = foo
+            .versions
+            .iter()
+            .map(|v| (v.version_string.as_str(), v.overlay_key))
+            .collect();
+        // 1.0 of the shared gentoo overlay is kept once
+        assert_eq!(versions, vec![("1.0", 0), ("1.1", 1)]);
+        for v in packages.iter().flat_map(|p| &p.versions) {
+            assert_eq!(
+                merged_header.overlays[v.overlay_key as usize].label,
+                v.reponame
+            );
+        }
+
+        // Same label with a different path is a different overlay
+        let other = (header(&[("/srv/gentoo", "gentoo")], &[]), main_db());
+        let (merged_header, _) = merge_databases(vec![
+            other,
+            (
+                header(&[("/var/db/repos/gentoo", "gentoo")], &[]),
+                main_db(),
+            ),
+        ]);
+        assert_eq!(merged_header.overlays.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_databases_keeps_priorities() {
+        let mut local_header = header(
+            &[
+                ("/var/db/repos/local", "local"),
+                ("/var/db/repos/gentoo", "gentoo"),
+            ],
+            &[],
+        );
+        local_header.overlays[0].priority = 10;
+        local_header.overlays[1].priority = 0;
+        let host_local = (
+            local_header,
+            vec![package("foo", "Foo", vec![version("1.0", "0", 0, "local")])],
+        );
+        let host_gentoo = (
+            header(&[("/var/db/repos/gentoo", "gentoo")], &[]),
+            vec![package(
+                "foo",
+                "Foo",
+                vec![version("1.0", "0", 0, "gentoo")],
+            )],
+        );
+
+        // The overlay wins whichever input comes first
+        for inputs in [
+            vec![host_local.clone(), host_gentoo.clone()],
+            vec![host_gentoo.clone(), host_local.clone()],
+        ] {
+            let (merged_header, packages) = merge_databases(inputs);
+            let priorities: Vec<(&str, i32)> = merged_header
+                .overlays
+                .iter()
+                .map(|o| (&*o.label, o.priority))
+                .collect();
+            assert!(priorities.contains(&("local", 10)));
+            assert!(priorities.contains(&("gentoo", 0)));
+            let versions = &packages[0].versions;
+            assert_eq!(versions.len(), 1);
+            assert_eq!(&*versions[0].reponame, "local");
+            assert_eq!(versions[0].priority, 10);
+        }
+
+        // Of disagreeing priorities of one overlay, the highest is kept
+        let mut raised = host_gentoo.clone();
+        raised.0.overlays[0].priority = 5;
+        let (merged_header, packages) = merge_databases(vec![host_gentoo, raised]);
+        assert_eq!(merged_header.overlays[0].priority, 5);
+        assert_eq!(packages[0].versions[0].priority, 5);
+    }
+
+    #[test]
+    fn test_merge_databases_with() {
+        let host_a = (
+            header(&[("/var/db/repos/gentoo", "gentoo")], &[]),
+            vec![package(
+                "foo",
+                "Foo",
+                vec![
+                    version("0.9", "0", 0, "gentoo"),
+                    version("1.0", "0", 0, "gentoo"),
+                ],
+            )],
+        );
+        let host_b = (
+            header(
+                &[
+                    ("/var/db/repos/gentoo", "gentoo"),
+                    ("/var/db/repos/local", "local"),
+                ],
+                &[],
+            ),
+            vec![package(
+                "foo",
+                "Foo from overlay",
+                vec![
+                    version("1.0", "0", 0, "gentoo"),
+                    version("1.0", "0", 1, "local"),
+                    version("1.1", "0", 1, "local"),
+                ],
+            )],
+        );
+        let options = MergeOptions {
+            versions: MergePolicy::DedupByRepository,
+            metadata: MetadataPolicy::HighestPriority,
+        };
+        let (merged_header, packages, report) =
+            merge_databases_with(vec![host_a.clone(), host_b.clone()], options);
+        assert_eq!(report.overlays, vec!["gentoo", "local"]);
+        assert_eq!(packages.len(), 1);
+
+        // The shared 1.0::gentoo is kept once, 1.0::local is another version
+        let foo = &packages[0];
+        let versions: Vec<(&str, &str)> = foo
+            .versions
+            .iter()
+            .map(|v| (v.version_string.as_str(), &*v.reponame))
+            .collect();
+        assert_eq!(
+            versions,
+            vec![
+                ("0.9", "gentoo"),
+                ("1.0", "gentoo"),
+                ("1.0", "local"),
+                ("1.1", "local")
+            ]
+        );
+        // The second input has the higher priority overlay
+        assert_eq!(foo.description, "Foo from overlay");
+        let conflict = &report.conflicts[0];
+        assert_eq!(conflict.kind, ConflictKind::Description);
+        assert_eq!(conflict.kept, "Foo from overlay");
+        assert_eq!(conflict.discarded, "Foo");
+        let duplicates: Vec<&str> = report
+            .conflicts
+            .iter()
+            .filter(|c| c.kind == ConflictKind::DuplicateVersion)
+            .map(|c| c.discarded.as_str())
+            .collect();
+        assert_eq!(duplicates, vec!["1.0::gentoo"]);
+
+        // The merged database can be written and read back
+        let mut writer = crate::EixWriter::new(Vec::new());
+        writer.write_database(&merged_header, &packages).unwrap();
+        let mut db = crate::Database::from_bytes(writer.into_inner());
+        let header = db.read_header(0).unwrap();
+        let read = crate::PackageReader::new(db, header).read_all().unwrap();
+        assert_eq!(read.into_inner()[0].versions.len(), 4);
+
+        // By default, the first description is kept
+        let (_, packages, _) = merge_databases_with(
+            vec![host_a, host_b],
+            MergeOptions {
+                metadata: MetadataPolicy::KeepFirst,
+                ..options
+            },
+        );
+        assert_eq!(packages[0].description, "Foo");
+    }
+
+    #[test]
+    fn test_merge_highest_priority() {
+        let mut local_header = header(
+            &[
+                ("/var/db/repos/local", "local"),
+                ("/var/db/repos/gentoo", "gentoo"),
+            ],
+            &[],
+        );
+        local_header.overlays[0].priority = 10;
+        local_header.overlays[1].priority = 0;
+        let host_local = (
+            local_header,
+            vec![package(
+                "foo",
+                "Foo from overlay",
+                vec![version("1.1", "0", 0, "local")],
+            )],
+        );
+        let host_gentoo = (
+            header(&[("/var/db/repos/gentoo", "gentoo")], &[]),
+            vec![package(
+                "foo",
+                "Foo",
+                vec![
+                    version("1.0", "0", 0, "gentoo"),
+                    version("1.1", "0", 0, "gentoo"),
+                ],
+            )],
+        );
+
+        // The metadata of the higher priority overlay is taken whichever
+        // input comes first; versions are only shared within a repository
+        for inputs in [
+            vec![host_local.clone(), host_gentoo.clone()],
+            vec![host_gentoo.clone(), host_local.clone()],
+        ] {
+            let (_, packages) = merge(inputs, MetadataPolicy::HighestPriority);
+            assert_eq!(packages.len(), 1);
+            assert_eq!(packages[0].description, "Foo from overlay");
+            assert_eq!(packages[0].versions.len(), 3);
+        }
+    }
+}
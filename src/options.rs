@@ -0,0 +1,103 @@
+//! Configuration of reading a database
+
+/// Default for [`OpenOptions::buffer_capacity`], the default of `BufReader`
+pub const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+
+/// How strings which are not valid UTF-8 are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Utf8Policy {
+    /// Fail with `InvalidData`
+    #[default]
+    Strict,
+    /// Replace invalid sequences with U+FFFD
+    Lossy,
+}
+
+/// Whether the string hashes are checked after reading, see
+/// [`DBHeader::warnings`](crate::DBHeader::warnings)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashValidation {
+    #[default]
+    Off,
+    /// Record problems as warnings in the header
+    Warn,
+    /// Fail with `InvalidData` at the first problem
+    Strict,
+}
+
+/// What [`PackageReader::finish`](crate::PackageReader::finish) does
+/// with bytes after the last category, e.g. when the category count of
+/// the header is too small
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingData {
+    /// Record them as a [warning](crate::PackageReader::warnings)
+    #[default]
+    Warn,
+    /// Fail with `InvalidData`
+    Strict,
+}
+
+/// Bounds on values declared in the file, protecting against corrupt
+/// or crafted files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximal length of a single string or version part in bytes
+    pub max_string_len: u64,
+    /// Longest plausible entry of a string hash, see [`HashValidation`]
+    pub max_hash_entry_len: u64,
+    /// Maximal number of entries of a string hash
+    pub max_hash_entries: u64,
+    /// Maximal number of elements of any other list: overlays, world
+    /// sets, versions of a package, version parts and hashed words
+    pub max_list_len: u64,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_string_len: 16 * 1024 * 1024,
+            max_hash_entry_len: 64 * 1024,
+            max_hash_entries: 1 << 22,
+            max_list_len: 1 << 16,
+        }
+    }
+}
+
+/// Options for [`Database::open_read_with`](crate::Database::open_read_with)
+/// and [`Decoder::with_options`](crate::Decoder::with_options)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenOptions {
+    /// Capacity of the read buffer in bytes; larger buffers help on
+    /// slow or network file systems
+    pub buffer_capacity: usize,
+    pub utf8: Utf8Policy,
+    pub limits: ParseLimits,
+    /// Record the byte ranges of packages and versions read by a
+    /// [`PackageReader`](crate::PackageReader) in their `span` fields
+    pub record_spans: bool,
+    pub hash_validation: HashValidation,
+    /// Keep the dependencies of versions encoded and decode them on the
+    /// first call of [`Version::depend`](crate::Version::depend); saves
+    /// time and memory when few of them are looked at
+    pub lazy_depend: bool,
+    /// Record versions occurring more than once in a package as
+    /// [warnings](crate::PackageReader::warnings) of the reader, see
+    /// [`Package::find_duplicate_versions`](crate::Package::find_duplicate_versions)
+    pub warn_duplicate_versions: bool,
+    pub trailing_data: TrailingData,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        OpenOptions {
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            utf8: Utf8Policy::default(),
+            limits: ParseLimits::default(),
+            record_spans: false,
+            hash_validation: HashValidation::Off,
+            lazy_depend: false,
+            warn_duplicate_versions: false,
+            trailing_data: TrailingData::Warn,
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Overlay (repository) related queries over a list of packages
+
+use crate::{DBHeader, Package, Version, compare_parts};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// `pkg` with only `versions`
+fn with_versions(pkg: &Package, versions: Vec<Version>) -> Package {
+    Package {
+        versions,
+        // The filtered package does not match a record of the file
+        span: None,
+        ..pkg.clone()
+    }
+}
+
+/// Returns the packages providing versions from the repository `label`.
+///
+/// Each returned package retains only the matching versions; packages
+/// without any matching version are dropped.
+pub fn packages_in_repo(packages: &[Package], label: &str) -> Vec<Package> {
+    packages
+        .iter()
+        .filter_map(|pkg| {
+            let versions: Vec<_> = pkg.versions_in_repo(label).cloned().collect();
+            (!versions.is_empty()).then(|| with_versions(pkg, versions))
+        })
+        .collect()
+}
+
+/// Which side of a shadowed package has the newer version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NewerSide {
+    Overlay,
+    MainTree,
+    Equal,
+}
+
+/// A package provided by an overlay and by the main tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Shadowing {
+    pub atom: String,
+    /// Highest version of the overlay
+    pub overlay_version: String,
+    /// Highest version of the main tree (overlay key 0)
+    pub main_version: String,
+    pub newer: NewerSide,
+}
+
+fn highest<'a, I: Iterator<Item = &'a Version>>(versions: I) -> Option<&'a Version> {
+    versions.max_by(|a, b| compare_parts(&a.parts, &b.parts))
+}
+
+/// Returns the atoms of the packages whose versions all come from the
+/// repository `label`, e.g. the packages of an overlay which are not in
+/// the main tree.
+pub fn overlay_exclusive(packages: &[Package], label: &str) -> Vec<String> {
+    packages
+        .iter()
+        .filter(|pkg| {
+            !pkg.versions.is_empty() && pkg.versions.iter().all(|v| &*v.reponame == label)
+        })
+        .map(|pkg| format!("{}/{}", pkg.category, pkg.name))
+        .collect()
+}
+
+/// Returns the packages which have versions both in the repository
+/// `label` and in the main tree (overlay key 0), comparing their
+/// highest versions.
+pub fn overlay_shadowing(packages: &[Package], label: &str) -> Vec<Shadowing> {
+    packages
+        .iter()
+        .filter_map(|pkg| {
+            let overlay = highest(pkg.versions_in_repo(label).filter(|v| !v.in_main_tree()))?;
+            let main = highest(pkg.versions.iter().filter(|v| v.in_main_tree()))?;
+            let newer = match compare_parts(&overlay.parts, &main.parts) {
+                Ordering::Greater => NewerSide::Overlay,
+                Ordering::Less => NewerSide::MainTree,
+                Ordering::Equal => NewerSide::Equal,
+            };
+            Some(Shadowing {
+                atom: format!("{}/{}", pkg.category, pkg.name),
+                overlay_version: overlay.version_string.clone(),
+                main_version: main.version_string.clone(),
+                newer,
+            })
+        })
+        .collect()
+}
+
+/// Rank of the repository of `v`: its repos.conf priority, then its
+/// position in the overlays of `header`
+fn repo_rank(
+    v: &Version,
+    header: &DBHeader,
+    repos_conf_priorities: Option<&HashMap<String, i32>>,
+) -> (i32, i32) {
+    // Portage gives repositories without a priority 0
+    let configured = repos_conf_priorities.map_or(0, |p| p.get(&*v.reponame).copied().unwrap_or(0));
+    let order = header
+        .overlays
+        .get(v.overlay_key as usize)
+        .map_or(v.priority, |o| o.priority);
+    (configured, order)
+}
+
+impl Package {
+    /// The versions portage would use: of equal versions with the same
+    /// slot (without the subslot, `""` being `"0"`) from several
+    /// repositories, only the one from the repository with the highest
+    /// priority in `repos_conf_priorities` is kept. Without priorities,
+    /// or between repositories of equal priority, the one listed last in
+    /// the overlays of `header` wins. The versions keep their order.
+    pub fn resolve_shadowing(
+        &self,
+        header: &DBHeader,
+        repos_conf_priorities: Option<&HashMap<String, i32>>,
+    ) -> Vec<&Version> {
+        // The same version as compared by `overlay_shadowing`
+        let same = |a: &Version, b: &Version| {
+            a.normalized_slot() == b.normalized_slot()
+                && compare_parts(&a.parts, &b.parts) == Ordering::Equal
+        };
+        let rank = |v: &Version| repo_rank(v, header, repos_conf_priorities);
+        self.versions
+            .iter()
+            .enumerate()
+            .filter(|&(i, v)| {
+                // Beaten by a higher rank, or by an equal one listed first
+                !self.versions.iter().enumerate().any(|(j, w)| {
+                    j != i && same(v, w) && (rank(w) > rank(v) || (rank(w) == rank(v) && j < i))
+                })
+            })
+            .map(|(_, v)| v)
+            .collect()
+    }
+}
+
+/// Applies [`Package::resolve_shadowing`] to every package, returning
+/// the packages with the versions portage would use
+pub fn resolve_shadowing(
+    packages: &[Package],
+    header: &DBHeader,
+    repos_conf_priorities: Option<&HashMap<String, i32>>,
+) -> Vec<Package> {
+    packages
+        .iter()
+        .map(|pkg| {
+            let versions = pkg
+                .resolve_shadowing(header, repos_conf_priorities)
+                .into_iter()
+                .cloned()
+                .collect();
+            with_versions(pkg, versions)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Version;
+
+    fn version(version: &str, overlay_key: u64, reponame: &str) -> Version {
+        Version {
+            version_string: version.to_string(),
+            parts: crate::parse_version(version).unwrap(),
+            overlay_key,
+            reponame: reponame.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_packages_in_repo() {
+        let packages = vec![
+            Package {
+                category: "app-misc".to_string(),
+                name: "both".to_string(),
+                versions: vec![version("1.0", 0, "gentoo"), version("2.0", 1, "guru")],
+                ..Default::default()
+            },
+            Package {
+                category: "app-misc".to_string(),
+                name: "main-only".to_string(),
+                versions: vec![version("1.0", 0, "gentoo")],
+                ..Default::default()
+            },
+        ];
+
+        let guru = packages_in_repo(&packages, "guru");
+        assert_eq!(guru.len(), 1);
+        assert_eq!(guru[0].name, "both");
+        assert_eq!(guru[0].versions.len(), 1);
+        assert_eq!(guru[0].versions[0].version_string, "2.0");
+
+        let gentoo = packages_in_repo(&packages, "gentoo");
+        assert_eq!(gentoo.len(), 2);
+        assert!(
+            gentoo
+                .iter()
+                .all(|p| p.versions.iter().all(|v| v.in_main_tree()))
+        );
+
+        assert_eq!(packages[0].versions_in_overlay(1).count(), 1);
+        assert!(packages_in_repo(&packages, "unknown").is_empty());
+    }
+
+    fn package(name: &str, versions: Vec<Version>) -> Package {
+        Package {
+            category: "app-misc".to_string(),
+            name: name.to_string(),
+            versions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_overlay_exclusive_and_shadowing() {
+        let packages = vec![
+            package("main-only", vec![version("1.0", 0, "gentoo")]),
+            package(
+                "exclusive",
+                vec![version("1.0", 1, "guru"), version("1.1", 1, "guru")],
+            ),
+            package(
+                "newer",
+                vec![version("1.0", 0, "gentoo"), version("2.0", 1, "guru")],
+            ),
+            package(
+                "older",
+                vec![version("3.0", 0, "gentoo"), version("2.0", 1, "guru")],
+            ),
+            package(
+                "same",
+                vec![version("1.0-r1", 0, "gentoo"), version("1.0-r1", 1, "guru")],
+            ),
+            package(
+                "other-overlay",
+                vec![version("1.0", 0, "gentoo"), version("2.0", 2, "local")],
+            ),
+            package("empty", Vec::new()),
+        ];
+
+        assert_eq!(overlay_exclusive(&packages, "guru"), ["app-misc/exclusive"]);
+        assert_eq!(
+            overlay_exclusive(&packages, "gentoo"),
+            ["app-misc/main-only"]
+        );
+        assert!(overlay_exclusive(&packages, "unknown").is_empty());
+
+        let shadowing = overlay_shadowing(&packages, "guru");
+        let sides: Vec<_> = shadowing
+            .iter()
+            .map(|s| (s.atom.as_str(), s.newer))
+            .collect();
+        assert_eq!(
+            sides,
+            [
+                ("app-misc/newer", NewerSide::Overlay),
+                ("app-misc/older", NewerSide::MainTree),
+                ("app-misc/same", NewerSide::Equal),
+            ]
+        );
+        assert_eq!(shadowing[1].overlay_version, "2.0");
+        assert_eq!(shadowing[1].main_version, "3.0");
+        assert_eq!(overlay_shadowing(&packages, "local").len(), 1);
+        // The main tree does not shadow itself
+        assert!(overlay_shadowing(&packages, "gentoo").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_shadowing() {
+        let (header, packages) = crate::EixTestBuilder::new()
+            .overlay("gentoo", "/var/db/repos/gentoo")
+            .overlay("guru", "/var/db/repos/guru")
+            .category("app-misc")
+            .package("screen", |p| {
+                p.version("4.9.1", |v| v)
+                    .version("4.9.1", |v| v.overlay(1))
+                    // Another slot is not shadowed
+                    .version("4.9.1", |v| v.slot("1"))
+                    .version("5.0.0", |v| v.overlay(1))
+                    // The subslot and an explicit slot 0 do not matter
+                    .version("5.0.0", |v| v.slot("0/5"))
+            })
+            .build();
+        let screen = &packages[0];
+        let used = |priorities: Option<&HashMap<String, i32>>| -> Vec<String> {
+            screen
+                .resolve_shadowing(&header, priorities)
+                .iter()
+                .map(|v| format!("{}:{}::{}", v.version_string, v.slot, v.reponame))
+                .collect()
+        };
+
+        // By default the overlay listed last wins
+        assert_eq!(
+            used(None),
+            ["4.9.1:::guru", "4.9.1:1::gentoo", "5.0.0:::guru"]
+        );
+        let gentoo_first = HashMap::from([("gentoo".to_string(), 10), ("guru".to_string(), 0)]);
+        assert_eq!(
+            used(Some(&gentoo_first)),
+            ["4.9.1:::gentoo", "4.9.1:1::gentoo", "5.0.0:0/5::gentoo"]
+        );
+        let guru_first = HashMap::from([("gentoo".to_string(), -1000)]);
+        assert_eq!(used(Some(&guru_first)), used(None));
+
+        let resolved = resolve_shadowing(&packages, &header, Some(&gentoo_first));
+        assert_eq!(resolved[0].versions.len(), 3);
+        assert_eq!(&*resolved[0].versions[0].reponame, "gentoo");
+    }
+}
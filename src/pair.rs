@@ -0,0 +1,85 @@
+//! The database before and after the last sync
+//!
+//! `eix-sync` copies the database to `previous.eix` before updating it;
+//! comparing both is what `eix-diff` does. [`TreePair`] reads both files
+//! with the same options and compares them with [`diff`].
+
+use crate::{
+    ChangeEvent, Database, DbDiff, EventFilter, OpenOptions, Package, ParsedDb,
+    default_cache_paths, default_previous_paths, diff,
+};
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+
+/// The current database and the one before the last sync
+#[derive(Debug, Clone)]
+pub struct TreePair {
+    pub current: ParsedDb,
+    pub previous: ParsedDb,
+}
+
+/// The packages of `packages` whose atom is not in `other`
+fn missing_from<'a>(packages: &'a [Package], other: &[Package]) -> Vec<&'a Package> {
+    let atoms: HashSet<(&str, &str)> = other
+        .iter()
+        .map(|p| (p.category.as_str(), p.name.as_str()))
+        .collect();
+    packages
+        .iter()
+        .filter(|p| !atoms.contains(&(p.category.as_str(), p.name.as_str())))
+        .collect()
+}
+
+impl TreePair {
+    /// Reads the databases at `current` and `previous`
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(current: P, previous: Q) -> io::Result<Self> {
+        Self::open_with(current, previous, OpenOptions::default())
+    }
+
+    /// Like [`open`](Self::open), reading both databases with `options`
+    pub fn open_with<P: AsRef<Path>, Q: AsRef<Path>>(
+        current: P,
+        previous: Q,
+        options: OpenOptions,
+    ) -> io::Result<Self> {
+        Ok(TreePair {
+            current: ParsedDb::load_with(current, options)?,
+            previous: ParsedDb::load_with(previous, options)?,
+        })
+    }
+
+    /// Reads the databases from the standard locations, see
+    /// [`default_cache_paths`] and [`default_previous_paths`]
+    pub fn open_default() -> io::Result<Self> {
+        let (_, current) = Database::open_first(default_cache_paths())?;
+        let (_, previous) = Database::open_first(default_previous_paths())?;
+        Self::open(current, previous)
+    }
+
+    /// The changes since the last sync
+    pub fn diff(&self) -> DbDiff {
+        diff(&self.previous.packages, &self.current.packages)
+    }
+
+    /// Packages new since the last sync, in the order of the current
+    /// database
+    pub fn added_since_sync(&self) -> Vec<&Package> {
+        missing_from(&self.current.packages, &self.previous.packages)
+    }
+
+    /// Packages removed since the last sync, in the order of the
+    /// previous database
+    pub fn removed_since_sync(&self) -> Vec<&Package> {
+        missing_from(&self.previous.packages, &self.current.packages)
+    }
+
+    /// Keywords for `arch` changed since the last sync, e.g. `~amd64`
+    /// to `amd64` for a stabilization
+    pub fn keyword_changes_since_sync(&self, arch: &str) -> Vec<ChangeEvent> {
+        self.diff().events_matching(&EventFilter {
+            atom_glob: None,
+            arch: Some(arch.to_string()),
+        })
+    }
+}
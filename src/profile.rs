@@ -0,0 +1,186 @@
+//! The @system set and the profile flags of versions
+//!
+//! eix flags the versions matching an entry of the `packages` files of
+//! the active profile: [`MASK_SYSTEM`] for the entries marked with `*`,
+//! which form the @system set, and [`MASK_IN_PROFILE`] for versions
+//! provided by the profile otherwise. [`system_set`] reconstructs the
+//! @system set from these flags, and [`system_packages`] lists the
+//! flagged versions while reading a database.
+
+use crate::export::next_package;
+use crate::{Atom, MASK_IN_PROFILE, MASK_SYSTEM, Package, PackageIndex, PackageReader, Version};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{self, Read};
+
+/// How the active profile relates to a version, see [`Version::profile_status`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileStatus {
+    /// Part of the @system set
+    System,
+    /// Provided by the profile, but not part of @system
+    Profile,
+    Neither,
+}
+
+impl Version {
+    /// Whether the version is part of the @system set ([`MASK_SYSTEM`])
+    pub fn in_system(&self) -> bool {
+        self.mask_flags & MASK_SYSTEM != 0
+    }
+
+    /// Whether the version is provided by the profile ([`MASK_IN_PROFILE`])
+    pub fn in_profile(&self) -> bool {
+        self.mask_flags & MASK_IN_PROFILE != 0
+    }
+
+    /// The relation to the active profile; [`MASK_SYSTEM`] takes
+    /// precedence over [`MASK_IN_PROFILE`]
+    pub fn profile_status(&self) -> ProfileStatus {
+        if self.in_system() {
+            ProfileStatus::System
+        } else if self.in_profile() {
+            ProfileStatus::Profile
+        } else {
+            ProfileStatus::Neither
+        }
+    }
+}
+
+impl Package {
+    /// The versions which are part of the @system set
+    pub fn system_versions(&self) -> impl Iterator<Item = &Version> {
+        self.versions.iter().filter(|v| v.in_system())
+    }
+}
+
+impl PackageIndex {
+    /// The packages with a version in the @system set, see
+    /// [`Package::system_versions`]
+    pub fn system_packages(&self) -> impl Iterator<Item = &Package> {
+        self.packages()
+            .filter(|p| p.system_versions().next().is_some())
+    }
+}
+
+/// A package of the @system set with its versions flagged
+/// [`MASK_SYSTEM`], see [`system_packages`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SystemPackage {
+    pub category: String,
+    pub name: String,
+    /// The version strings in the order of the package
+    pub versions: Vec<String>,
+}
+
+impl SystemPackage {
+    /// The system versions of `package`, `None` if there are none
+    pub fn from_package(package: &Package) -> Option<Self> {
+        let versions: Vec<String> = package
+            .system_versions()
+            .map(|v| v.version_string.clone())
+            .collect();
+        if versions.is_empty() {
+            return None;
+        }
+        Some(SystemPackage {
+            category: package.category.clone(),
+            name: package.name.clone(),
+            versions,
+        })
+    }
+}
+
+/// The packages of the @system set among the remaining packages of
+/// `reader`, holding only one package in memory at a time
+pub fn system_packages<R: Read>(reader: &mut PackageReader<R>) -> io::Result<Vec<SystemPackage>> {
+    let mut system = Vec::new();
+    let mut read = 0;
+    while let Some(package) = next_package(reader, read)? {
+        read += 1;
+        system.extend(SystemPackage::from_package(&package));
+    }
+    Ok(system)
+}
+
+/// The @system set: an unversioned atom for every package with a
+/// version flagged [`MASK_SYSTEM`], in the order of `packages`
+pub fn system_set(packages: &[Package]) -> Vec<Atom> {
+    let mut seen = HashSet::new();
+    packages
+        .iter()
+        .filter(|p| p.system_versions().next().is_some())
+        .filter(|p| seen.insert((p.category.as_str(), p.name.as_str())))
+        .map(|p| Atom::new(&p.category, &p.name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MASK_HARD, MASK_WORLD};
+
+    fn version(v: &str, mask_flags: u8) -> Version {
+        Version {
+            version_string: v.to_string(),
+            mask_flags,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_profile_status() {
+        let cases = [
+            (0, false, false, ProfileStatus::Neither),
+            (MASK_SYSTEM, true, false, ProfileStatus::System),
+            (MASK_IN_PROFILE, false, true, ProfileStatus::Profile),
+            (
+                MASK_SYSTEM | MASK_IN_PROFILE,
+                true,
+                true,
+                ProfileStatus::System,
+            ),
+            (MASK_HARD | MASK_WORLD, false, false, ProfileStatus::Neither),
+            (
+                MASK_HARD | MASK_IN_PROFILE,
+                false,
+                true,
+                ProfileStatus::Profile,
+            ),
+            (0xFF, true, true, ProfileStatus::System),
+        ];
+        for (flags, in_system, in_profile, status) in cases {
+            let v = version("1.0", flags);
+            assert_eq!(v.in_system(), in_system, "{:#x}", flags);
+            assert_eq!(v.in_profile(), in_profile, "{:#x}", flags);
+            assert_eq!(v.profile_status(), status, "{:#x}", flags);
+        }
+    }
+
+    #[test]
+    fn test_system_set() {
+        let package = |cp: &str, versions: Vec<Version>| {
+            let (category, name) = cp.split_once('/').unwrap();
+            Package {
+                category: category.to_string(),
+                name: name.to_string(),
+                versions,
+                ..Default::default()
+            }
+        };
+        let packages = [
+            package(
+                "sys-apps/sed",
+                vec![version("4.8", 0), version("4.9", MASK_SYSTEM)],
+            ),
+            package("app-misc/screen", vec![version("4.9.1", MASK_IN_PROFILE)]),
+            package("app-shells/bash", vec![version("5.2", MASK_SYSTEM)]),
+            // Listed once, e.g. after concatenating two package lists
+            package("sys-apps/sed", vec![version("4.9", MASK_SYSTEM)]),
+        ];
+        assert_eq!(packages[0].system_versions().count(), 1);
+        let atoms: Vec<String> = system_set(&packages).iter().map(Atom::cp).collect();
+        assert_eq!(atoms, ["sys-apps/sed", "app-shells/bash"]);
+    }
+}
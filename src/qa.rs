@@ -0,0 +1,195 @@
+//! Quality checks over the whole tree
+
+use crate::{MASK_HARD, Package};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Limits above which a package is reported by [`tree_outliers`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub max_slots: usize,
+    pub max_versions: usize,
+    pub max_eapis: usize,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            max_slots: 5,
+            max_versions: 20,
+            max_eapis: 2,
+        }
+    }
+}
+
+/// A package and the offending values, e.g. its slots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Outlier {
+    pub atom: String,
+    pub values: Vec<String>,
+}
+
+/// Packages exceeding the [`Thresholds`], one list per check
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TreeOutliers {
+    /// Packages with more slots than allowed; values are the slots
+    pub many_slots: Vec<Outlier>,
+    /// Packages with more versions than allowed; values are the
+    /// versions in ascending order
+    pub many_versions: Vec<Outlier>,
+    /// Packages whose versions use more EAPIs than allowed; values are
+    /// the EAPIs
+    pub many_eapis: Vec<Outlier>,
+    /// Packages with every version hard masked; values are the versions
+    pub all_masked: Vec<Outlier>,
+}
+
+impl TreeOutliers {
+    pub fn is_empty(&self) -> bool {
+        self.many_slots.is_empty()
+            && self.many_versions.is_empty()
+            && self.many_eapis.is_empty()
+            && self.all_masked.is_empty()
+    }
+}
+
+/// Lists the packages exceeding `thresholds`
+pub fn tree_outliers(packages: &[Package], thresholds: Thresholds) -> TreeOutliers {
+    let mut outliers = TreeOutliers::default();
+    for pkg in packages {
+        let outlier = |values: Vec<String>| Outlier {
+            atom: format!("{}/{}", pkg.category, pkg.name),
+            values,
+        };
+
+        let slots = pkg.slots();
+        if slots.len() > thresholds.max_slots {
+            outliers
+                .many_slots
+                .push(outlier(slots.into_iter().map(String::from).collect()));
+        }
+
+        if pkg.versions.len() > thresholds.max_versions {
+            let mut versions: Vec<_> = pkg.versions.iter().collect();
+            versions.sort_by(|a, b| a.compare(b));
+            outliers.many_versions.push(outlier(
+                versions.iter().map(|v| v.version_string.clone()).collect(),
+            ));
+        }
+
+        let eapis: BTreeSet<&str> = pkg.versions.iter().map(|v| v.eapi.as_str()).collect();
+        if eapis.len() > thresholds.max_eapis {
+            outliers
+                .many_eapis
+                .push(outlier(eapis.into_iter().map(String::from).collect()));
+        }
+
+        if !pkg.versions.is_empty() && pkg.versions.iter().all(|v| v.mask_flags & MASK_HARD != 0) {
+            outliers.all_masked.push(outlier(
+                pkg.versions
+                    .iter()
+                    .map(|v| v.version_string.clone())
+                    .collect(),
+            ));
+        }
+    }
+    outliers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MASK_PACKAGE, MASK_PROFILE, Version, parse_version};
+
+    fn version(v: &str, slot: &str, eapi: &str, mask_flags: u8) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            slot: slot.to_string(),
+            eapi: eapi.to_string(),
+            mask_flags,
+            ..Default::default()
+        }
+    }
+
+    fn package(name: &str, versions: Vec<Version>) -> Package {
+        Package {
+            category: "app-misc".to_string(),
+            name: name.to_string(),
+            versions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tree_outliers() {
+        let thresholds = Thresholds {
+            max_slots: 2,
+            max_versions: 3,
+            max_eapis: 1,
+        };
+        let packages = vec![
+            package(
+                "ok",
+                vec![
+                    version("1", "0", "8", 0),
+                    version("2", "0", "8", MASK_PACKAGE),
+                ],
+            ),
+            package(
+                "slots",
+                vec![
+                    version("1", "1", "8", 0),
+                    version("2", "2", "8", 0),
+                    version("3", "3/1", "8", 0),
+                ],
+            ),
+            package(
+                "versions",
+                vec![
+                    version("1.10", "0", "8", 0),
+                    version("1.2", "0", "8", 0),
+                    version("1.9", "0", "8", 0),
+                    version("1.2-r1", "0", "8", 0),
+                ],
+            ),
+            package(
+                "eapis",
+                vec![version("1", "0", "7", 0), version("2", "0", "8", 0)],
+            ),
+            package(
+                "masked",
+                vec![
+                    version("1", "0", "8", MASK_PACKAGE),
+                    version("2", "0", "8", MASK_PROFILE),
+                ],
+            ),
+            package("empty", Vec::new()),
+        ];
+
+        let outliers = tree_outliers(&packages, thresholds);
+        assert_eq!(
+            outliers.many_slots,
+            [Outlier {
+                atom: "app-misc/slots".to_string(),
+                values: vec!["1".to_string(), "2".to_string(), "3".to_string()],
+            }]
+        );
+        assert_eq!(outliers.many_versions.len(), 1);
+        assert_eq!(outliers.many_versions[0].atom, "app-misc/versions");
+        assert_eq!(
+            outliers.many_versions[0].values,
+            ["1.2", "1.2-r1", "1.9", "1.10"]
+        );
+        assert_eq!(outliers.many_eapis.len(), 1);
+        assert_eq!(outliers.many_eapis[0].values, ["7", "8"]);
+        assert_eq!(outliers.all_masked.len(), 1);
+        assert_eq!(outliers.all_masked[0].atom, "app-misc/masked");
+
+        assert!(tree_outliers(&packages[..1], thresholds).is_empty());
+        let defaults = tree_outliers(&packages, Thresholds::default());
+        assert!(defaults.many_slots.is_empty() && defaults.many_versions.is_empty());
+        assert!(defaults.many_eapis.is_empty());
+        assert_eq!(defaults.all_masked, outliers.all_masked);
+    }
+}
@@ -0,0 +1,211 @@
+//! Filtering packages by their attributes
+
+use crate::{Atom, Package, PackageIndex, PackageMap};
+#[cfg(feature = "fs")]
+use crate::{CancelToken, Outcome, SharedDb};
+
+/// Criteria for selecting packages; unset fields match everything
+///
+#[cfg_attr(feature = "sample-data", doc = "```")]
+#[cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+/// use eix::PackageQuery;
+///
+/// let (_, packages) = eix::sample::parsed();
+/// let query = PackageQuery {
+///     keyword: Some("~arm64".to_string()),
+///     ..PackageQuery::category("app-misc")
+/// };
+/// let names: Vec<&str> = packages
+///     .iter()
+///     .filter(|p| query.matches(p))
+///     .map(|p| p.name.as_str())
+///     .collect();
+/// assert_eq!(names, ["screen", "tmux"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageQuery {
+    /// Exact category
+    pub category: Option<String>,
+    /// Substring of the name, ignoring ASCII case
+    pub name: Option<String>,
+    /// Substring of the description, ignoring ASCII case
+    pub description: Option<String>,
+    /// Only packages with a version from this repository
+    pub repo: Option<String>,
+    /// Only packages with a version having this keyword, e.g. "~amd64"
+    pub keyword: Option<String>,
+    /// Only packages with a version matching one of these atoms, e.g.
+    /// from [`read_atom_list`](crate::read_atom_list)
+    pub atoms: Option<Vec<Atom>>,
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack
+        .to_ascii_lowercase()
+        .contains(&needle.to_ascii_lowercase())
+}
+
+impl PackageQuery {
+    /// A query for all packages of `category`
+    pub fn category(category: &str) -> Self {
+        PackageQuery {
+            category: Some(category.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, package: &Package) -> bool {
+        self.category
+            .as_deref()
+            .is_none_or(|c| package.category == c)
+            && self
+                .name
+                .as_deref()
+                .is_none_or(|n| contains_ignore_case(&package.name, n))
+            && self
+                .description
+                .as_deref()
+                .is_none_or(|d| contains_ignore_case(&package.description, d))
+            && self
+                .repo
+                .as_deref()
+                .is_none_or(|r| package.versions.iter().any(|v| &*v.reponame == r))
+            && self.keyword.as_deref().is_none_or(|k| {
+                package
+                    .versions
+                    .iter()
+                    .any(|v| v.keywords.iter().any(|kw| kw == k))
+            })
+            && self.atoms.as_deref().is_none_or(|atoms| {
+                atoms
+                    .iter()
+                    .any(|a| package.versions.iter().any(|v| a.matches(package, v)))
+            })
+    }
+
+    /// The packages of `db` which may match, using its category index
+    #[cfg(feature = "fs")]
+    fn candidates<'a>(&self, db: &'a SharedDb) -> &'a [Package] {
+        match &self.category {
+            Some(category) => db.category(category),
+            None => db.packages(),
+        }
+    }
+
+    /// The matching packages of `db`, like [`SharedDb::query`]
+    #[cfg(feature = "fs")]
+    pub fn execute<'a>(&self, db: &'a SharedDb) -> Vec<&'a Package> {
+        self.candidates(db)
+            .iter()
+            .filter(|pkg| self.matches(pkg))
+            .collect()
+    }
+
+    /// Like [`execute`](Self::execute), checking `token` before every
+    /// package. Once it is cancelled, the matches so far are returned as
+    /// [`Outcome::Cancelled`].
+    #[cfg(feature = "fs")]
+    pub fn execute_cancellable<'a>(
+        &self,
+        db: &'a SharedDb,
+        token: &CancelToken,
+    ) -> Outcome<Vec<&'a Package>> {
+        let mut matches = Vec::new();
+        for pkg in self.candidates(db) {
+            if token.is_cancelled() {
+                return Outcome::Cancelled(matches);
+            }
+            if self.matches(pkg) {
+                matches.push(pkg);
+            }
+        }
+        Outcome::Complete(matches)
+    }
+
+    /// The matching packages of `index`, sorted by category and name. A
+    /// category restricts the search to the packages of that category.
+    pub fn execute_index<'a>(&self, index: &'a PackageIndex) -> Vec<&'a Package> {
+        let candidates: Box<dyn Iterator<Item = &'a Package>> = match &self.category {
+            Some(category) => Box::new(index.category_range(category)),
+            None => Box::new(index.packages()),
+        };
+        candidates.filter(|pkg| self.matches(pkg)).collect()
+    }
+
+    /// The matching packages of `map`, in no particular order. With
+    /// atoms, only their packages are looked up and the result is sorted
+    /// by category and name.
+    pub fn execute_map<'a>(&self, map: &'a PackageMap) -> Vec<&'a Package> {
+        match &self.atoms {
+            Some(atoms) => {
+                let mut matches: Vec<&Package> = atoms
+                    .iter()
+                    .filter_map(|atom| map.get_atom(atom))
+                    .filter(|pkg| self.matches(pkg))
+                    .collect();
+                // Several atoms may name the same package
+                matches.sort_by(|a, b| crate::sort::compare_atoms(a, b));
+                matches.dedup_by(|a, b| std::ptr::eq(*a, *b));
+                matches
+            }
+            None => map.packages().filter(|pkg| self.matches(pkg)).collect(),
+        }
+    }
+
+    /// Like [`execute`](Self::execute), testing the packages in parallel;
+    /// the result is identical
+    #[cfg(all(feature = "fs", feature = "rayon"))]
+    pub fn execute_par<'a>(&self, db: &'a SharedDb) -> Vec<&'a Package> {
+        use rayon::prelude::*;
+
+        // Collecting keeps the order of the slice
+        self.candidates(db)
+            .par_iter()
+            .filter(|pkg| self.matches(pkg))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Version;
+
+    #[test]
+    fn test_matches() {
+        let package = Package {
+            category: "dev-lang".to_string(),
+            name: "Python".to_string(),
+            description: "An interpreted language".to_string(),
+            versions: vec![Version {
+                reponame: "gentoo".into(),
+                keywords: vec!["~amd64".to_string()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(PackageQuery::default().matches(&package));
+        assert!(PackageQuery::category("dev-lang").matches(&package));
+        assert!(!PackageQuery::category("dev").matches(&package));
+        let query = PackageQuery {
+            name: Some("pyth".to_string()),
+            description: Some("INTERPRETED".to_string()),
+            repo: Some("gentoo".to_string()),
+            keyword: Some("~amd64".to_string()),
+            ..Default::default()
+        };
+        assert!(query.matches(&package));
+        let query = PackageQuery {
+            keyword: Some("amd64".to_string()),
+            ..Default::default()
+        };
+        assert!(!query.matches(&package));
+        let atoms = |atoms: &[&str]| PackageQuery {
+            atoms: Some(atoms.iter().map(|a| Atom::parse(a).unwrap()).collect()),
+            ..Default::default()
+        };
+        assert!(atoms(&["dev-lang/ruby", "dev-lang/Python::gentoo"]).matches(&package));
+        assert!(!atoms(&["dev-lang/Python::guru"]).matches(&package));
+        assert!(!atoms(&[]).matches(&package));
+    }
+}
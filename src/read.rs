@@ -0,0 +1,738 @@
+//! Reading a database file
+//!
+//! Files are opened with the `fs` feature (on by default); databases in
+//! memory or behind any [`Read`] work without it, e.g. on
+//! `wasm32-unknown-unknown`.
+//!
+//! A [`Database`] over any [`Read`] (e.g. stdin) supports parsing from
+//! start to end; skipping reads and discards the bytes. Random access
+//! with [`Database::seek`] and [`Database::read_package_at`] is only
+//! available if the reader also implements [`Seek`].
+//!
+//! Packages and versions are returned in the order of the file, which
+//! differs between eix runs; the readers never reorder them. See
+//! [`sort_packages`](crate::sort_packages) for a canonical order.
+
+use crate::cancel::{CancelToken, Outcome, is_cancelled};
+use crate::decoder::{Decoder, narrow};
+use crate::options::{OpenOptions, TrailingData};
+use crate::{
+    BasicPart, DBHeader, DBVersion, DuplicateVersion, Package, PackageSummary, StringHash,
+    Treesize, UChar, Version,
+};
+use serde::Serialize;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+/* Standard locations of the database */
+pub const EIX_CACHEFILE_ENV: &str = "EIX_CACHEFILE";
+pub const EIX_CACHE_PATH: &str = "/var/cache/eix/portage.eix";
+// Older eix versions wrote the database to a single file /var/cache/eix
+pub const EIX_CACHE_LEGACY_PATH: &str = "/var/cache/eix";
+
+/// Returns the locations checked by [`Database::open_default`], in order:
+/// `$EIX_CACHEFILE` (if set), [`EIX_CACHE_PATH`] and [`EIX_CACHE_LEGACY_PATH`]
+#[cfg(feature = "fs")]
+pub fn default_cache_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(path) = std::env::var_os(EIX_CACHEFILE_ENV).filter(|p| !p.is_empty()) {
+        paths.push(PathBuf::from(path));
+    }
+    paths.push(PathBuf::from(EIX_CACHE_PATH));
+    paths.push(PathBuf::from(EIX_CACHE_LEGACY_PATH));
+    paths
+}
+
+/* Standard location of the database before the last sync */
+pub const EIX_PREVIOUS_ENV: &str = "EIX_PREVIOUS";
+pub const EIX_PREVIOUS_PATH: &str = "/var/cache/eix/previous.eix";
+
+/// Returns the locations of the database before the last sync, as
+/// written by `eix-sync`, in order: `$EIX_PREVIOUS` (if set) and
+/// [`EIX_PREVIOUS_PATH`]
+#[cfg(feature = "fs")]
+pub fn default_previous_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(path) = std::env::var_os(EIX_PREVIOUS_ENV).filter(|p| !p.is_empty()) {
+        paths.push(PathBuf::from(path));
+    }
+    paths.push(PathBuf::from(EIX_PREVIOUS_PATH));
+    paths
+}
+
+enum InputKind {
+    #[cfg(feature = "fs")]
+    File(BufReader<File>),
+    Bytes(Cursor<Vec<u8>>),
+    Reader(BufReader<Box<dyn Read + Send>>),
+}
+
+/// The source of the bytes of a [`Database`] created by
+/// [`open_read`](Database::open_read), [`from_bytes`](Database::from_bytes)
+/// or [`from_reader`](Database::from_reader). It only supports
+/// sequential reading; use [`Database::new`] with a seekable reader for
+/// random access.
+pub struct Input(InputKind);
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            #[cfg(feature = "fs")]
+            InputKind::File(r) => r.read(buf),
+            InputKind::Bytes(r) => r.read(buf),
+            InputKind::Reader(r) => r.read(buf),
+        }
+    }
+
+    #[inline]
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        match &mut self.0 {
+            #[cfg(feature = "fs")]
+            InputKind::File(r) => r.read_exact(buf),
+            InputKind::Bytes(r) => r.read_exact(buf),
+            InputKind::Reader(r) => r.read_exact(buf),
+        }
+    }
+}
+
+/*
+ * Database - The main I/O class
+ */
+pub struct Database<R: Read = Input> {
+    decoder: Decoder<R>,
+}
+
+impl<R: Read> Database<R> {
+    /// Reads a database from `reader`, which should be buffered
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, OpenOptions::default())
+    }
+
+    /// Reads a database from `reader` with the given UTF-8 policy and
+    /// limits; the buffer capacity is ignored
+    pub fn with_options(reader: R, options: OpenOptions) -> Self {
+        Database {
+            decoder: Decoder::with_options(reader, &options),
+        }
+    }
+
+    /// Declares the total length of the input, enabling the checks of
+    /// counts against the remaining bytes
+    pub fn set_input_len(&mut self, len: u64) {
+        self.decoder.set_input_len(len)
+    }
+
+    /// Returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.decoder.into_inner()
+    }
+}
+
+impl<R: Read + Seek> Database<R> {
+    /// Moves to `offset` from the start of the file, e.g. the start of
+    /// a [`Span`](crate::Span) recorded before
+    pub fn seek(&mut self, offset: u64) -> io::Result<()> {
+        self.decoder.get_mut().seek(SeekFrom::Start(offset))?;
+        self.decoder.set_position(offset);
+        Ok(())
+    }
+
+    /// Reads the package whose record (including its length prefix)
+    /// starts at `offset`, e.g. at the start of [`Package::span`]
+    pub fn read_package_at(
+        &mut self,
+        header: &DBHeader,
+        category: &str,
+        offset: u64,
+    ) -> io::Result<Package> {
+        self.seek(offset)?;
+        self.decoder.read_package(header, category)
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Database<BufReader<File>> {
+    /// Opens a database for random access, see [`Database::seek`]
+    pub fn open_seekable<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_seekable_with(path, OpenOptions::default())
+    }
+
+    /// Opens a database for random access with the given buffer
+    /// capacity, UTF-8 policy and limits
+    pub fn open_seekable_with<P: AsRef<Path>>(path: P, options: OpenOptions) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let reader = BufReader::with_capacity(options.buffer_capacity, file);
+        let mut db = Database::with_options(reader, options);
+        db.set_input_len(len);
+        Ok(db)
+    }
+}
+
+impl Database {
+    /// Reads a database held in memory
+    ///
+    #[cfg_attr(feature = "sample-data", doc = "```")]
+    #[cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+    /// use eix::{Database, PackageReader};
+    ///
+    /// let mut db = Database::from_bytes(eix::sample::bytes());
+    /// let header = db.read_header(0).unwrap();
+    /// let mut reader = PackageReader::new(db, header);
+    /// assert!(reader.next_category().unwrap());
+    /// let package = reader.read_package().unwrap().unwrap();
+    /// assert_eq!((package.category.as_str(), package.name.as_str()), ("app-misc", "screen"));
+    /// ```
+    pub fn from_bytes<B: Into<Vec<u8>>>(data: B) -> Self {
+        Self::from_bytes_with(data, OpenOptions::default())
+    }
+
+    /// Reads a database held in memory with the given UTF-8 policy and
+    /// limits; the buffer capacity is ignored
+    pub fn from_bytes_with<B: Into<Vec<u8>>>(data: B, options: OpenOptions) -> Self {
+        let data = data.into();
+        let len = data.len() as u64;
+        let input = Input(InputKind::Bytes(Cursor::new(data)));
+        let mut decoder = Decoder::with_options(input, &options);
+        decoder.set_input_len(len);
+        Database { decoder }
+    }
+
+    /// Reads a database from `reader`, buffered
+    pub fn from_reader<R: Read + Send + 'static>(reader: R) -> Self {
+        Self::from_reader_with(reader, OpenOptions::default())
+    }
+
+    /// Reads a database from `reader` with the given buffer capacity,
+    /// UTF-8 policy and limits
+    pub fn from_reader_with<R: Read + Send + 'static>(reader: R, options: OpenOptions) -> Self {
+        let reader: Box<dyn Read + Send> = Box::new(reader);
+        let input = Input(InputKind::Reader(BufReader::with_capacity(
+            options.buffer_capacity,
+            reader,
+        )));
+        Database {
+            decoder: Decoder::with_options(input, &options),
+        }
+    }
+
+    /// Opens a database for reading
+    #[cfg(feature = "fs")]
+    pub fn open_read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_read_with(path, OpenOptions::default())
+    }
+
+    /// Opens a database for reading with the given buffer capacity,
+    /// UTF-8 policy and limits
+    #[cfg(feature = "fs")]
+    pub fn open_read_with<P: AsRef<Path>>(path: P, options: OpenOptions) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let reader = BufReader::with_capacity(options.buffer_capacity, file);
+        let mut decoder = Decoder::with_options(Input(InputKind::File(reader)), &options);
+        decoder.set_input_len(len);
+        Ok(Database { decoder })
+    }
+
+    /// Capacity of the read buffer; for databases in memory the size
+    /// of the data
+    pub fn buffer_capacity(&self) -> usize {
+        match &self.decoder.get_ref().0 {
+            #[cfg(feature = "fs")]
+            InputKind::File(r) => r.capacity(),
+            InputKind::Bytes(r) => r.get_ref().len(),
+            InputKind::Reader(r) => r.capacity(),
+        }
+    }
+
+    /// Opens the first regular file of `paths`, returning the path used
+    #[cfg(feature = "fs")]
+    pub fn open_first<I: IntoIterator<Item = PathBuf>>(paths: I) -> io::Result<(Self, PathBuf)> {
+        let mut tried = Vec::new();
+        for path in paths {
+            if path.is_file() {
+                return Ok((Self::open_read(&path)?, path));
+            }
+            tried.push(path.display().to_string());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No eix database found (tried {})", tried.join(", ")),
+        ))
+    }
+
+    /// Opens the database from the standard locations, see [`default_cache_paths`]
+    #[cfg(feature = "fs")]
+    pub fn open_default() -> io::Result<(Self, PathBuf)> {
+        Self::open_first(default_cache_paths())
+    }
+}
+
+impl<R: Read> Database<R> {
+    /// Number of bytes consumed from the start of the file
+    pub fn position(&self) -> u64 {
+        self.decoder.position()
+    }
+
+    /// Sets the receiver of a trace event for every value read
+    #[cfg(feature = "trace")]
+    pub fn set_tracer(&mut self, tracer: Option<crate::trace::Tracer>) {
+        self.decoder.set_tracer(tracer)
+    }
+
+    /// The decoder, for walks of the file beyond the methods above
+    pub(crate) fn decoder_mut(&mut self) -> &mut Decoder<R> {
+        &mut self.decoder
+    }
+
+    /// Sets what the following values mean, see [`Decoder::set_context`]
+    pub fn set_context(&mut self, context: &'static str) {
+        self.decoder.set_context(context)
+    }
+
+    /// Reads a single byte
+    pub fn read_uchar(&mut self) -> io::Result<UChar> {
+        self.decoder.read_uchar()
+    }
+
+    /// Reads a number in eix format (variable length), see [`Decoder::read_num`]
+    pub fn read_num(&mut self) -> io::Result<u64> {
+        self.decoder.read_num()
+    }
+
+    /// Reads the number of elements of a list, see [`Decoder::read_count`]
+    pub fn read_count(&mut self, what: &str) -> io::Result<usize> {
+        self.decoder.read_count(what)
+    }
+
+    /// Reads a string (length + data)
+    pub fn read_string(&mut self) -> io::Result<String> {
+        self.decoder.read_string()
+    }
+
+    /// Skips `len` bytes
+    pub fn skip_bytes(&mut self, len: u64) -> io::Result<()> {
+        self.decoder.skip_bytes(len)
+    }
+
+    /// Skips a string without decoding it
+    pub fn skip_string(&mut self) -> io::Result<()> {
+        self.decoder.skip_string()
+    }
+
+    /// Reads the properties flags of a version, see
+    /// [`Decoder::read_properties`]
+    pub fn read_properties(&mut self, version: DBVersion) -> io::Result<u32> {
+        self.decoder.read_properties(version)
+    }
+
+    /// Reads a string from a hash (index → string)
+    pub fn read_hash_string(&mut self, hash: &StringHash) -> io::Result<String> {
+        self.decoder.read_hash_string(hash)
+    }
+
+    /// Reads a list of strings from a hash (WordVec)
+    pub fn read_hash_words(&mut self, hash: &StringHash) -> io::Result<Vec<String>> {
+        self.decoder.read_hash_words(hash)
+    }
+
+    /// Reads a single part of a version
+    pub fn read_part(&mut self) -> io::Result<BasicPart> {
+        self.decoder.read_part()
+    }
+
+    /// Reads the database header
+    pub fn read_header(&mut self, min_version: DBVersion) -> io::Result<DBHeader> {
+        self.decoder.read_header(min_version)
+    }
+
+    /// Reads a version record
+    pub fn read_version(&mut self, hdr: &DBHeader) -> io::Result<Version> {
+        self.decoder.read_version(hdr)
+    }
+}
+
+/// A problem found by a [`PackageReader`] which did not stop reading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReadWarning {
+    /// A category sorts before the category preceding it; eix writes
+    /// them sorted, but the format does not require it
+    UnsortedCategory {
+        category: String,
+        previous: String,
+    },
+    DuplicateVersion(DuplicateVersion),
+    /// Bytes after the last category, see [`PackageReader::finish`]
+    TrailingBytes {
+        len: u64,
+    },
+}
+
+impl fmt::Display for ReadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadWarning::UnsortedCategory { category, previous } => {
+                write!(f, "category {} follows {}", category, previous)
+            }
+            ReadWarning::DuplicateVersion(duplicate) => duplicate.fmt(f),
+            ReadWarning::TrailingBytes { len } => {
+                write!(f, "{} bytes after the last category", len)
+            }
+        }
+    }
+}
+
+/*
+ * PackageReader - Iterator over packages in the database
+ */
+pub struct PackageReader<R: Read = Input> {
+    db: Database<R>,
+    header: DBHeader,
+    frames: Treesize,
+    cat_size: Treesize,
+    cat_name: String,
+    cancel: Option<CancelToken>,
+    /// An error of a [`VersionIter`] dropped before it could report it
+    deferred: Option<io::Error>,
+    warnings: Vec<ReadWarning>,
+}
+
+impl<R: Read> PackageReader<R> {
+    pub fn new(db: Database<R>, header: DBHeader) -> Self {
+        let frames = header.size;
+        PackageReader {
+            db,
+            header,
+            frames,
+            cat_size: 0,
+            cat_name: String::new(),
+            cancel: None,
+            deferred: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Checks `token` before every package; once it is cancelled,
+    /// reading fails with [`Cancelled`](crate::Cancelled)
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Moves to the next category
+    pub fn next_category(&mut self) -> io::Result<bool> {
+        if let Some(e) = self.deferred.take() {
+            return Err(e);
+        }
+        if self.frames == 0 {
+            return Ok(false);
+        }
+
+        self.db.set_context("category");
+        let name = self.db.read_string().map_err(|e| self.premature_eof(e))?;
+        let previous = std::mem::replace(&mut self.cat_name, name);
+        if self.frames < self.header.size && self.cat_name < previous {
+            self.warnings.push(ReadWarning::UnsortedCategory {
+                category: self.cat_name.clone(),
+                previous,
+            });
+        }
+        self.db.set_context("package count");
+        let count = self.db.read_num().map_err(|e| self.premature_eof(e))?;
+        self.cat_size = narrow(count, "Package count")?;
+        self.frames -= 1;
+
+        Ok(true)
+    }
+
+    /// Says how many categories were still expected if `e` is the end
+    /// of the input
+    fn premature_eof(&self, e: io::Error) -> io::Error {
+        if e.kind() != io::ErrorKind::UnexpectedEof {
+            return e;
+        }
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "File ends with {} of {} categories still expected",
+                self.frames, self.header.size
+            ),
+        )
+    }
+
+    /// Checks that the input ends after the last category and returns
+    /// the number of bytes after it. Depending on
+    /// [`OpenOptions::trailing_data`], trailing bytes are recorded as a
+    /// [warning](Self::warnings) or fail with `InvalidData`. Fails with
+    /// `InvalidInput` if packages are left to read.
+    ///
+    #[cfg_attr(feature = "sample-data", doc = "```")]
+    #[cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+    /// use eix::{Database, PackageReader};
+    ///
+    /// let mut bytes = eix::sample::bytes().to_vec();
+    /// bytes.extend_from_slice(b"junk");
+    /// let mut db = Database::from_bytes(bytes);
+    /// let header = db.read_header(0).unwrap();
+    /// let mut reader = PackageReader::new(db, header);
+    /// reader.read_all().unwrap();
+    /// assert_eq!(reader.finish().unwrap(), 4);
+    /// assert_eq!(reader.warnings().len(), 1);
+    /// ```
+    pub fn finish(&mut self) -> io::Result<u64> {
+        if let Some(e) = self.deferred.take() {
+            return Err(e);
+        }
+        if self.frames > 0 || self.cat_size > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} packages and {} categories left to read",
+                    self.cat_size, self.frames
+                ),
+            ));
+        }
+        let len = self.db.decoder.skip_to_end()?;
+        if len > 0 {
+            match self.db.decoder.trailing_data() {
+                TrailingData::Warn => self.warnings.push(ReadWarning::TrailingBytes { len }),
+                TrailingData::Strict => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} bytes after the last category", len),
+                    ));
+                }
+            }
+        }
+        Ok(len)
+    }
+
+    pub fn current_category(&self) -> &str {
+        &self.cat_name
+    }
+
+    /// The header the packages are read with
+    pub fn header(&self) -> &DBHeader {
+        &self.header
+    }
+
+    /// Number of bytes consumed from the start of the file
+    pub fn position(&self) -> u64 {
+        self.db.position()
+    }
+
+    /// Reads the next package in the current category
+    pub fn read_package(&mut self) -> io::Result<Option<Package>> {
+        if !self.begin_package()? {
+            return Ok(None);
+        }
+
+        let pkg = self.db.decoder.read_package(&self.header, &self.cat_name)?;
+        self.cat_size -= 1;
+        if self.db.decoder.warn_duplicate_versions() {
+            let found = pkg.duplicate_version_warnings();
+            self.warnings
+                .extend(found.into_iter().map(ReadWarning::DuplicateVersion));
+        }
+        Ok(Some(pkg))
+    }
+
+    /// Problems found so far which did not stop reading. Duplicate
+    /// versions are only recorded with
+    /// [`OpenOptions::warn_duplicate_versions`], for packages read by
+    /// [`read_package`](Self::read_package) or [`read_all`](Self::read_all).
+    pub fn warnings(&self) -> &[ReadWarning] {
+        &self.warnings
+    }
+
+    /// Reads the fields of the next package in the current category
+    /// before its versions and skips the versions, which is much faster
+    /// than [`read_package`](Self::read_package) if only names and
+    /// descriptions are needed
+    pub fn read_summary(&mut self) -> io::Result<Option<PackageSummary>> {
+        if !self.begin_package()? {
+            return Ok(None);
+        }
+
+        let summary = self
+            .db
+            .decoder
+            .read_package_summary(&self.header, &self.cat_name)?;
+        self.cat_size -= 1;
+        Ok(Some(summary))
+    }
+
+    /// Reads the fields of the next package in the current category
+    /// before its versions, leaving the versions to be decoded one at a
+    /// time by the returned iterator. The iterator may be dropped early;
+    /// it then skips the remaining versions, so the reader stays at the
+    /// next package.
+    ///
+    #[cfg_attr(feature = "sample-data", doc = "```")]
+    #[cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+    /// use eix::{Database, PackageReader};
+    ///
+    /// let mut db = Database::from_bytes(eix::sample::bytes());
+    /// let header = db.read_header(0).unwrap();
+    /// let mut reader = PackageReader::new(db, header);
+    /// while reader.next_category().unwrap() {
+    ///     while let Some((package, mut versions)) = reader.read_package_lazy().unwrap() {
+    ///         let first = versions.next().transpose().unwrap();
+    ///         assert_eq!(first.is_some(), package.version_count > 0);
+    ///     }
+    /// }
+    /// ```
+    pub fn read_package_lazy(
+        &mut self,
+    ) -> io::Result<Option<(PackageSummary, VersionIter<'_, R>)>> {
+        if !self.begin_package()? {
+            return Ok(None);
+        }
+
+        let (summary, end) = self
+            .db
+            .decoder
+            .read_package_start(&self.header, &self.cat_name)?;
+        self.cat_size -= 1;
+        let versions = VersionIter {
+            remaining: summary.version_count,
+            end,
+            name: summary.name.clone(),
+            reader: self,
+            finished: false,
+        };
+        Ok(Some((summary, versions)))
+    }
+
+    /// Whether a package is left in the current category, reporting a
+    /// deferred error and checking the cancel token first
+    fn begin_package(&mut self) -> io::Result<bool> {
+        if let Some(e) = self.deferred.take() {
+            return Err(e);
+        }
+        if self.cat_size == 0 {
+            return Ok(false);
+        }
+        if let Some(token) = &self.cancel {
+            token.check()?;
+        }
+        Ok(true)
+    }
+
+    /// Reads all remaining packages. Once the token of
+    /// [`with_cancel`](Self::with_cancel) is cancelled, the packages read
+    /// so far are returned as [`Outcome::Cancelled`].
+    pub fn read_all(&mut self) -> io::Result<Outcome<Vec<Package>>> {
+        let mut packages = Vec::new();
+        loop {
+            match self.read_package() {
+                Ok(Some(pkg)) => packages.push(pkg),
+                Ok(None) => {
+                    if !self.next_category()? {
+                        return Ok(Outcome::Complete(packages));
+                    }
+                }
+                Err(e) if is_cancelled(&e) => return Ok(Outcome::Cancelled(packages)),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// The versions of a package read by
+/// [`PackageReader::read_package_lazy`], decoded one at a time
+///
+/// Dropping the iterator skips the versions not read yet. If skipping
+/// fails, the next call on the reader returns the error; call
+/// [`finish`](Self::finish) to get it right away. After a version fails
+/// to decode, the iterator ends and skipping resumes after the package
+/// if the error left the position inside it.
+pub struct VersionIter<'a, R: Read = Input> {
+    reader: &'a mut PackageReader<R>,
+    remaining: usize,
+    end: u64,
+    name: String,
+    finished: bool,
+}
+
+impl<R: Read> VersionIter<'_, R> {
+    /// The number of versions not read yet
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Skips the versions not read yet
+    pub fn finish(mut self) -> io::Result<()> {
+        self.skip_rest()
+    }
+
+    fn skip_rest(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.remaining = 0;
+        let reader = &mut *self.reader;
+        reader
+            .db
+            .decoder
+            .skip_package_rest(self.end, &reader.cat_name, &self.name)
+    }
+}
+
+impl<R: Read> Iterator for VersionIter<'_, R> {
+    type Item = io::Result<Version>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let reader = &mut *self.reader;
+        match reader.db.decoder.read_package_version(&reader.header) {
+            Ok(v) => {
+                self.remaining -= 1;
+                Some(Ok(v))
+            }
+            Err(e) => {
+                self.remaining = 0;
+                Some(Err(e))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+impl<R: Read> Drop for VersionIter<'_, R> {
+    fn drop(&mut self) {
+        if let Err(e) = self.skip_rest() {
+            self.reader.deferred = Some(e);
+        }
+    }
+}
+
+/// Reads the header and all packages of a database held in memory
+pub fn parse_bytes(data: &[u8]) -> io::Result<(DBHeader, Vec<Package>)> {
+    parse_bytes_with(data, OpenOptions::default())
+}
+
+/// Like [`parse_bytes`] with the given UTF-8 policy and limits
+pub fn parse_bytes_with(data: &[u8], options: OpenOptions) -> io::Result<(DBHeader, Vec<Package>)> {
+    let mut db = Database::from_bytes_with(data, options);
+    let header = db.read_header(0)?;
+    let mut reader = PackageReader::new(db, header.clone());
+    let mut packages = Vec::new();
+    while reader.next_category()? {
+        while let Some(pkg) = reader.read_package()? {
+            packages.push(pkg);
+        }
+    }
+    Ok((header, packages))
+}
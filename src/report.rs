@@ -0,0 +1,481 @@
+//! Markdown and HTML reports
+//!
+//! A report of a package list, a [`DbDiff`] or [`DatabaseStats`] starts
+//! with a summary and continues with one table per category, each with
+//! an anchor to link to. Package names link to their homepage.
+//!
+//! Both formats are rendered from the same blocks, so the templates
+//! live here in the code and not in files read at runtime.
+
+use crate::diff::{PackageField, highest};
+use crate::{DatabaseStats, DbDiff, Package};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// What a report is about
+#[derive(Debug, Clone, Copy)]
+pub enum ReportInput<'a> {
+    /// One row per package, grouped by category
+    Packages(&'a [Package]),
+    /// One row per new, removed or changed package, grouped by category
+    Diff(&'a DbDiff),
+    Stats(&'a DatabaseStats),
+}
+
+/// Writes the report as CommonMark with GitHub tables
+pub fn render_markdown<W: Write>(input: &ReportInput, mut w: W) -> io::Result<()> {
+    for (i, block) in blocks(input).iter().enumerate() {
+        if i > 0 {
+            writeln!(w)?;
+        }
+        match block {
+            Block::Heading {
+                level,
+                text,
+                anchor,
+            } => {
+                write!(w, "{} ", "#".repeat(*level))?;
+                if let Some(anchor) = anchor {
+                    write!(w, "<a id=\"{}\"></a>", anchor)?;
+                }
+                writeln!(w, "{}", markdown_escape(text))?;
+            }
+            Block::Paragraph(inlines) => writeln!(w, "{}", markdown_inlines(inlines))?,
+            Block::Table { header, rows } => {
+                writeln!(w, "| {} |", header.join(" | "))?;
+                writeln!(w, "|{}", " --- |".repeat(header.len()))?;
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|c| markdown_inlines(c)).collect();
+                    writeln!(w, "| {} |", cells.join(" | "))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes the report as a complete HTML document
+#[cfg(feature = "html")]
+pub fn render_html<W: Write>(input: &ReportInput, mut w: W) -> io::Result<()> {
+    let blocks = blocks(input);
+    let title = match blocks.first() {
+        Some(Block::Heading { text, .. }) => text.as_str(),
+        _ => "",
+    };
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html>")?;
+    writeln!(w, "<head>")?;
+    writeln!(w, "<meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>{}</title>", html_escape(title))?;
+    writeln!(w, "</head>")?;
+    writeln!(w, "<body>")?;
+    for block in &blocks {
+        match block {
+            Block::Heading {
+                level,
+                text,
+                anchor,
+            } => match anchor {
+                Some(anchor) => writeln!(
+                    w,
+                    "<h{0} id=\"{1}\">{2}</h{0}>",
+                    level,
+                    anchor,
+                    html_escape(text)
+                )?,
+                None => writeln!(w, "<h{0}>{1}</h{0}>", level, html_escape(text))?,
+            },
+            Block::Paragraph(inlines) => writeln!(w, "<p>{}</p>", html_inlines(inlines))?,
+            Block::Table { header, rows } => {
+                writeln!(w, "<table>")?;
+                write!(w, "<thead><tr>")?;
+                for name in header.iter() {
+                    write!(w, "<th>{}</th>", html_escape(name))?;
+                }
+                writeln!(w, "</tr></thead>")?;
+                writeln!(w, "<tbody>")?;
+                for row in rows {
+                    write!(w, "<tr>")?;
+                    for cell in row {
+                        write!(w, "<td>{}</td>", html_inlines(cell))?;
+                    }
+                    writeln!(w, "</tr>")?;
+                }
+                writeln!(w, "</tbody>")?;
+                writeln!(w, "</table>")?;
+            }
+        }
+    }
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")
+}
+
+#[derive(Debug)]
+enum Inline {
+    Text(String),
+    Link { text: String, href: String },
+}
+
+impl Inline {
+    fn text(text: impl Into<String>) -> Self {
+        Inline::Text(text.into())
+    }
+}
+
+/// A table cell
+type Cell = Vec<Inline>;
+
+#[derive(Debug)]
+enum Block {
+    Heading {
+        level: usize,
+        text: String,
+        anchor: Option<String>,
+    },
+    Paragraph(Vec<Inline>),
+    Table {
+        header: &'static [&'static str],
+        rows: Vec<Vec<Cell>>,
+    },
+}
+
+impl Block {
+    fn heading(level: usize, text: &str) -> Self {
+        Block::Heading {
+            level,
+            text: text.to_string(),
+            anchor: None,
+        }
+    }
+
+    fn category(category: &str) -> Self {
+        Block::Heading {
+            level: 2,
+            text: category.to_string(),
+            anchor: Some(anchor(category)),
+        }
+    }
+}
+
+fn blocks(input: &ReportInput) -> Vec<Block> {
+    match input {
+        ReportInput::Packages(packages) => package_blocks(packages),
+        ReportInput::Diff(diff) => diff_blocks(diff),
+        ReportInput::Stats(stats) => stats_blocks(stats),
+    }
+}
+
+fn package_blocks(packages: &[Package]) -> Vec<Block> {
+    let mut by_category: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+    for pkg in packages {
+        by_category.entry(&pkg.category).or_default().push(pkg);
+    }
+    let versions: usize = packages.iter().map(|p| p.versions.len()).sum();
+
+    let mut blocks = vec![
+        Block::heading(1, "Packages"),
+        Block::Paragraph(vec![Inline::text(format!(
+            "{} packages with {} versions in {} categories",
+            packages.len(),
+            versions,
+            by_category.len()
+        ))]),
+    ];
+    if !by_category.is_empty() {
+        blocks.push(Block::Paragraph(category_index(
+            by_category.keys().copied(),
+        )));
+    }
+    for (category, packages) in by_category {
+        blocks.push(Block::category(category));
+        let rows = packages
+            .iter()
+            .map(|pkg| {
+                let versions: Vec<String> = pkg
+                    .versions
+                    .iter()
+                    .map(|v| {
+                        if v.is_masked() {
+                            format!("({})", v.version_string)
+                        } else {
+                            v.version_string.clone()
+                        }
+                    })
+                    .collect();
+                vec![
+                    vec![homepage_link(&pkg.name, &pkg.homepage)],
+                    vec![Inline::text(versions.join(" "))],
+                    vec![Inline::text(&pkg.description)],
+                ]
+            })
+            .collect();
+        blocks.push(Block::Table {
+            header: &["Package", "Versions", "Description"],
+            rows,
+        });
+    }
+    blocks
+}
+
+fn diff_blocks(diff: &DbDiff) -> Vec<Block> {
+    // (name, change, versions, details) by category
+    type Row<'a> = (&'a str, &'static str, String, String);
+    let mut by_category: BTreeMap<&str, Vec<Row>> = BTreeMap::new();
+    for entry in &diff.added {
+        let (category, name) = split_atom(&entry.atom);
+        by_category.entry(category).or_default().push((
+            name,
+            "new",
+            entry.versions.join(" "),
+            String::new(),
+        ));
+    }
+    for entry in &diff.removed {
+        let (category, name) = split_atom(&entry.atom);
+        by_category.entry(category).or_default().push((
+            name,
+            "removed",
+            entry.versions.join(" "),
+            String::new(),
+        ));
+    }
+    for change in &diff.changed {
+        let (category, name) = split_atom(&change.atom);
+        let old = highest(&change.old_versions).unwrap_or_default();
+        let new = highest(&change.new_versions).unwrap_or_default();
+        let (kind, versions) = if old != new {
+            ("updated", format!("{} -> {}", old, new))
+        } else {
+            ("changed", new.to_string())
+        };
+        let mut details: Vec<String> = Vec::new();
+        for key in &change.added_versions {
+            details.push(format!("+{}", key.version));
+        }
+        for key in &change.removed_versions {
+            details.push(format!("-{}", key.version));
+        }
+        for kw in &change.keyword_changes {
+            details.push(format!("keywords of {}", kw.version.version));
+        }
+        for mask in &change.mask_changes {
+            details.push(format!("mask of {}", mask.version.version));
+        }
+        for field in &change.field_changes {
+            details.push(
+                match field.field {
+                    PackageField::Description => "description",
+                    PackageField::Homepage => "homepage",
+                    PackageField::Licenses => "licenses",
+                }
+                .to_string(),
+            );
+        }
+        by_category
+            .entry(category)
+            .or_default()
+            .push((name, kind, versions, details.join(", ")));
+    }
+
+    let mut blocks = vec![
+        Block::heading(1, "Database changes"),
+        Block::Paragraph(vec![Inline::text(format!(
+            "{} new, {} removed, {} updated",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        ))]),
+    ];
+    if !by_category.is_empty() {
+        blocks.push(Block::Paragraph(category_index(
+            by_category.keys().copied(),
+        )));
+    }
+    for (category, mut rows) in by_category {
+        rows.sort_by(|a, b| a.0.cmp(b.0));
+        blocks.push(Block::category(category));
+        blocks.push(Block::Table {
+            header: &["Package", "Change", "Versions", "Details"],
+            rows: rows
+                .into_iter()
+                .map(|(name, kind, versions, details)| {
+                    vec![
+                        vec![Inline::text(name)],
+                        vec![Inline::text(kind)],
+                        vec![Inline::text(versions)],
+                        vec![Inline::text(details)],
+                    ]
+                })
+                .collect(),
+        });
+    }
+    blocks
+}
+
+fn stats_blocks(stats: &DatabaseStats) -> Vec<Block> {
+    let counts: [(&str, String); 6] = [
+        (
+            "Versions per package",
+            format!("{:.2}", stats.avg_versions_per_package),
+        ),
+        ("Distinct slots", stats.distinct_slots.to_string()),
+        ("Distinct licenses", stats.distinct_licenses.to_string()),
+        ("Hard masked versions", stats.hard_masked.to_string()),
+        ("Live versions", stats.live.to_string()),
+        (
+            "Fetch restricted versions",
+            stats.fetch_restricted.to_string(),
+        ),
+    ];
+    let mut blocks = vec![
+        Block::heading(1, "Database statistics"),
+        Block::Paragraph(vec![Inline::text(format!(
+            "{} packages with {} versions in {} categories",
+            stats.packages, stats.versions, stats.categories
+        ))]),
+        Block::Table {
+            header: &["Statistic", "Value"],
+            rows: counts
+                .into_iter()
+                .map(|(name, value)| vec![vec![Inline::text(name)], vec![Inline::text(value)]])
+                .collect(),
+        },
+    ];
+    if !stats.versions_per_overlay.is_empty() {
+        blocks.push(Block::heading(2, "Overlays"));
+        blocks.push(Block::Table {
+            header: &["Overlay", "Versions"],
+            rows: stats
+                .versions_per_overlay
+                .iter()
+                .map(|(overlay, count)| {
+                    vec![
+                        vec![Inline::text(overlay)],
+                        vec![Inline::text(count.to_string())],
+                    ]
+                })
+                .collect(),
+        });
+    }
+    if !stats.top_categories.is_empty() {
+        blocks.push(Block::heading(2, "Top categories"));
+        blocks.push(Block::Table {
+            header: &["Category", "Packages"],
+            rows: stats
+                .top_categories
+                .iter()
+                .map(|c| {
+                    vec![
+                        vec![Inline::text(&c.category)],
+                        vec![Inline::text(c.packages.to_string())],
+                    ]
+                })
+                .collect(),
+        });
+    }
+    blocks
+}
+
+/// `Categories: app-misc, dev-lang` with links to the anchors
+fn category_index<'a, I: Iterator<Item = &'a str>>(categories: I) -> Vec<Inline> {
+    let mut inlines = vec![Inline::text("Categories: ")];
+    for (i, category) in categories.enumerate() {
+        if i > 0 {
+            inlines.push(Inline::text(", "));
+        }
+        inlines.push(Inline::Link {
+            text: category.to_string(),
+            href: format!("#{}", anchor(category)),
+        });
+    }
+    inlines
+}
+
+/// `name` linked to the first http(s) URL of `homepage`
+fn homepage_link(name: &str, homepage: &str) -> Inline {
+    match homepage
+        .split_whitespace()
+        .find(|url| url.starts_with("https://") || url.starts_with("http://"))
+    {
+        Some(url) => Inline::Link {
+            text: name.to_string(),
+            href: url.to_string(),
+        },
+        None => Inline::text(name),
+    }
+}
+
+fn split_atom(atom: &str) -> (&str, &str) {
+    atom.split_once('/').unwrap_or(("", atom))
+}
+
+/// An id for `category`; category names are already valid ids, other
+/// characters become `-`
+fn anchor(category: &str) -> String {
+    category
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn markdown_inlines(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&markdown_escape(text)),
+            Inline::Link { text, href } => {
+                let href = href.replace('(', "%28").replace(')', "%29");
+                out.push_str(&format!("[{}]({})", markdown_escape(text), href));
+            }
+        }
+    }
+    out
+}
+
+fn markdown_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(feature = "html")]
+fn html_inlines(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&html_escape(text)),
+            Inline::Link { text, href } => out.push_str(&format!(
+                "<a href=\"{}\">{}</a>",
+                html_escape(href),
+                html_escape(text)
+            )),
+        }
+    }
+    out
+}
+
+#[cfg(feature = "html")]
+fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
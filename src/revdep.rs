@@ -0,0 +1,173 @@
+//! Reverse dependencies: which versions depend on a package
+//!
+//! The dependency words stored by eix are scanned for atoms; USE
+//! conditionals, groups and blockers are skipped.
+
+use crate::{Atom, CancelToken, Cancelled, Package};
+use std::collections::HashMap;
+
+/// The dependency variable an atom was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DepKind {
+    Depend,
+    Rdepend,
+    Pdepend,
+    Bdepend,
+    Idepend,
+}
+
+/// A version depending on a package
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReverseDep {
+    /// Index into the package slice the index was built from
+    pub package: usize,
+    /// Index into the versions of that package
+    pub version: usize,
+    pub kind: DepKind,
+}
+
+/// Maps `category/name` to the versions depending on it, in the order
+/// of the packages and versions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReverseDepIndex {
+    dependents: HashMap<String, Vec<ReverseDep>>,
+}
+
+impl ReverseDepIndex {
+    /// Scans the dependencies of all versions of `packages`
+    pub fn build(packages: &[Package]) -> Self {
+        let mut index = ReverseDepIndex::default();
+        index.add_packages(packages, 0);
+        index
+    }
+
+    /// Like [`build`](Self::build), checking `token` before every package.
+    /// A partial index would silently miss dependents, so none is
+    /// returned when cancelled.
+    pub fn build_cancellable(packages: &[Package], token: &CancelToken) -> Result<Self, Cancelled> {
+        let mut index = ReverseDepIndex::default();
+        for (i, pkg) in packages.iter().enumerate() {
+            token.check()?;
+            index.add_packages(std::slice::from_ref(pkg), i);
+        }
+        Ok(index)
+    }
+
+    /// Like [`build`](Self::build), scanning chunks of packages in
+    /// parallel; the result is identical
+    #[cfg(feature = "rayon")]
+    pub fn build_par(packages: &[Package]) -> Self {
+        use rayon::prelude::*;
+
+        let chunk_size = packages.len().div_ceil(rayon::current_num_threads()).max(1);
+        let parts: Vec<ReverseDepIndex> = packages
+            .par_chunks(chunk_size)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let mut index = ReverseDepIndex::default();
+                index.add_packages(chunk, i * chunk_size);
+                index
+            })
+            .collect();
+        // Merging in chunk order keeps the order of the sequential build
+        let mut index = ReverseDepIndex::default();
+        for part in parts {
+            for (cp, deps) in part.dependents {
+                index.dependents.entry(cp).or_default().extend(deps);
+            }
+        }
+        index
+    }
+
+    fn add_packages(&mut self, packages: &[Package], offset: usize) {
+        for (i, pkg) in packages.iter().enumerate() {
+            for (v, version) in pkg.versions.iter().enumerate() {
+                let Some(depend) = version.depend() else {
+                    continue;
+                };
+                let lists = [
+                    (DepKind::Depend, &depend.depend),
+                    (DepKind::Rdepend, &depend.rdepend),
+                    (DepKind::Pdepend, &depend.pdepend),
+                    (DepKind::Bdepend, &depend.bdepend),
+                    (DepKind::Idepend, &depend.idepend),
+                ];
+                for (kind, words) in lists {
+                    let dep = ReverseDep {
+                        package: offset + i,
+                        version: v,
+                        kind,
+                    };
+                    for word in words {
+                        let Ok(atom) = Atom::parse(word) else {
+                            continue;
+                        };
+                        let deps = self.dependents.entry(atom.cp()).or_default();
+                        // An atom may occur several times, e.g. in USE conditionals
+                        if deps.last() != Some(&dep) {
+                            deps.push(dep);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The versions depending on `cp` (`category/name`)
+    pub fn dependents(&self, cp: &str) -> &[ReverseDep] {
+        self.dependents.get(cp).map_or(&[], Vec::as_slice)
+    }
+
+    /// Number of packages with dependents
+    pub fn len(&self) -> usize {
+        self.dependents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dependents.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Depend, Version};
+
+    fn package(name: &str, rdepend: &[&str]) -> Package {
+        Package {
+            category: "app-misc".to_string(),
+            name: name.to_string(),
+            versions: vec![Version {
+                depend: Some(Depend {
+                    rdepend: rdepend.iter().map(|s| s.to_string()).collect(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let packages = [
+            package(
+                "a",
+                &["dev-lang/python", "foo?", "(", ">=dev-lang/python-3", ")"],
+            ),
+            package(
+                "b",
+                &["||", "(", "dev-lang/python:3.12", "!app-misc/a", ")"],
+            ),
+        ];
+        let index = ReverseDepIndex::build(&packages);
+        let deps = index.dependents("dev-lang/python");
+        assert_eq!(deps.len(), 2);
+        assert_eq!(deps[0].package, 0);
+        assert_eq!(deps[1].package, 1);
+        assert_eq!(deps[1].kind, DepKind::Rdepend);
+        // Blockers are no dependencies
+        assert!(index.dependents("app-misc/a").is_empty());
+        assert_eq!(index.len(), 1);
+    }
+}
@@ -0,0 +1,129 @@
+//! Writing a reduced copy of a database
+//!
+//! [`filter_database`] keeps the packages selected by a [`FilterSpec`]
+//! and can drop the dependency, REQUIRED_USE and SRC_URI data, e.g. to
+//! ship a small database to containers which only query a few
+//! categories. The string hashes of the copy contain only the strings
+//! still referenced, so it is a valid database for eix as well:
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! use eix::{Database, FilterSpec, PackageReader, filter_database};
+//!
+//! let mut db = Database::from_bytes(eix::sample::bytes());
+//! let header = db.read_header(0).unwrap();
+//! let spec = FilterSpec {
+//!     include_categories: vec!["app-*".to_string()],
+//!     strip_depend: true,
+//!     ..Default::default()
+//! };
+//! let mut out = Vec::new();
+//! let written = filter_database(PackageReader::new(db, header), &mut out, &spec).unwrap();
+//!
+//! let mut db = Database::from_bytes(out);
+//! let header = db.read_header(0).unwrap();
+//! assert!(!header.use_depend);
+//! let packages = PackageReader::new(db, header).read_all().unwrap().into_inner();
+//! assert_eq!(packages.len() as u64, written);
+//! assert!(packages.iter().all(|p| p.category.starts_with("app-")));
+//! ```
+
+use crate::diff::glob_match;
+use crate::export::next_package;
+use crate::write::complete_hashes;
+use crate::{DBHeader, EixWriter, Package, PackageReader, StringHash};
+use std::io::{self, BufWriter, Read, Write};
+
+/// Which packages and data [`filter_database`] keeps. Patterns are
+/// globs where `*` matches any sequence and `?` any single character.
+#[derive(Debug, Clone, Default)]
+pub struct FilterSpec {
+    /// Categories to keep; all if empty
+    pub include_categories: Vec<String>,
+    /// Categories to drop, even if included
+    pub exclude_categories: Vec<String>,
+    /// Package names to keep; all if `None`
+    pub name_pattern: Option<String>,
+    /// Drop the dependencies and clear the header flag
+    pub strip_depend: bool,
+    /// Drop REQUIRED_USE and clear the header flag
+    pub strip_required_use: bool,
+    /// Drop SRC_URI and clear the header flag
+    pub strip_src_uri: bool,
+}
+
+impl FilterSpec {
+    /// Whether the package `category/name` is kept
+    pub fn matches(&self, category: &str, name: &str) -> bool {
+        (self.include_categories.is_empty()
+            || self
+                .include_categories
+                .iter()
+                .any(|glob| glob_match(glob, category)))
+            && !self
+                .exclude_categories
+                .iter()
+                .any(|glob| glob_match(glob, category))
+            && self
+                .name_pattern
+                .as_deref()
+                .is_none_or(|glob| glob_match(glob, name))
+    }
+
+    /// Drops the data of `pkg` which is stripped
+    fn strip(&self, pkg: &mut Package) {
+        for v in &mut pkg.versions {
+            if self.strip_depend {
+                v.depend = None;
+                v.lazy_depend = None;
+            }
+            if self.strip_required_use {
+                v.required_use.clear();
+            }
+            if self.strip_src_uri {
+                v.src_uri = None;
+            }
+        }
+    }
+
+    /// The header of the copy with the hashes rebuilt from `packages`
+    fn header(&self, header: &DBHeader, packages: &[Package]) -> DBHeader {
+        let mut header = DBHeader {
+            eapi_hash: StringHash::new(),
+            license_hash: StringHash::new(),
+            keywords_hash: StringHash::new(),
+            iuse_hash: StringHash::new(),
+            slot_hash: StringHash::new(),
+            depend_hash: StringHash::new(),
+            use_depend: header.use_depend && !self.strip_depend,
+            use_required_use: header.use_required_use && !self.strip_required_use,
+            use_src_uri: header.use_src_uri && !self.strip_src_uri,
+            ..header.clone()
+        };
+        complete_hashes(&mut header, packages);
+        header
+    }
+}
+
+/// Writes the packages of `reader` selected by `spec` as a new database
+/// to `output`, keeping their order, the overlays and the world sets.
+/// Returns the number of packages written.
+pub fn filter_database<R: Read, W: Write>(
+    mut reader: PackageReader<R>,
+    output: W,
+    spec: &FilterSpec,
+) -> io::Result<u64> {
+    let mut packages = Vec::new();
+    let mut read = 0;
+    while let Some(mut pkg) = next_package(&mut reader, read)? {
+        read += 1;
+        if spec.matches(&pkg.category, &pkg.name) {
+            spec.strip(&mut pkg);
+            packages.push(pkg);
+        }
+    }
+    let header = spec.header(reader.header(), &packages);
+    let mut writer = EixWriter::new(BufWriter::new(output));
+    writer.write_database(&header, &packages)?;
+    Ok(packages.len() as u64)
+}
@@ -0,0 +1,30 @@
+//! A small database to try the library without an eix file
+//!
+//! The sample has two overlays, `gentoo` and `guru`, and a few packages
+//! in three categories, among them `app-misc/screen` with a stable, a
+//! testing and a masked version:
+//!
+//! ```
+//! let (header, packages) = eix::sample::parsed();
+//! assert_eq!(header.overlays.len(), 2);
+//! let screen = packages.iter().find(|p| p.name == "screen").unwrap();
+//! assert_eq!(screen.versions.len(), 3);
+//! ```
+//!
+//! It is generated with the [`EixTestBuilder`](crate::EixTestBuilder)
+//! by `tests/sample_test.rs`; run it with `EIX_UPDATE_SAMPLE=1` after
+//! changing the writer.
+
+use crate::{DBHeader, Package, parse_bytes};
+
+static SAMPLE: &[u8] = include_bytes!("../testdata/sample.eix");
+
+/// The database file
+pub fn bytes() -> &'static [u8] {
+    SAMPLE
+}
+
+/// The header and the packages of the database
+pub fn parsed() -> (DBHeader, Vec<Package>) {
+    parse_bytes(SAMPLE).expect("The sample database is valid")
+}
@@ -0,0 +1,160 @@
+//! A single pass over a database calling back for every match (feature
+//! `regex`)
+//!
+//! [`scan`] keeps no packages: each one is tested against the
+//! [`Matcher`] as soon as its name and description are read, and the
+//! versions are only decoded for a hit, which the callback receives as a
+//! borrowed [`Hit`]. Returning [`ControlFlow::Break`] stops reading.
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! use eix::{Database, Matcher, PackageReader, scan_reader};
+//! use std::ops::ControlFlow;
+//!
+//! let mut db = Database::from_bytes(eix::sample::bytes());
+//! let header = db.read_header(0).unwrap();
+//! let mut reader = PackageReader::new(db, header);
+//! let matcher = Matcher::new().name("^t").unwrap();
+//! let mut names = Vec::new();
+//! scan_reader(&mut reader, &matcher, |hit| {
+//!     names.push(hit.package.name.clone());
+//!     ControlFlow::Continue(())
+//! })
+//! .unwrap();
+//! assert_eq!(names, ["tmux"]);
+//! ```
+
+use crate::{Package, PackageReader, PackageSummary};
+use regex::Regex;
+use std::io::{self, Read};
+use std::ops::ControlFlow;
+
+/// Patterns a package has to match; unset patterns match everything
+#[derive(Debug, Clone, Default)]
+pub struct Matcher {
+    /// Searched in the name, e.g. `^python` or `(?i)qt`
+    pub name: Option<Regex>,
+    pub description: Option<Regex>,
+    pub category: Option<Regex>,
+}
+
+fn compile(pattern: &str) -> io::Result<Regex> {
+    Regex::new(pattern).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+impl Matcher {
+    /// A matcher for all packages
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, pattern: &str) -> io::Result<Self> {
+        self.name = Some(compile(pattern)?);
+        Ok(self)
+    }
+
+    pub fn description(mut self, pattern: &str) -> io::Result<Self> {
+        self.description = Some(compile(pattern)?);
+        Ok(self)
+    }
+
+    pub fn category(mut self, pattern: &str) -> io::Result<Self> {
+        self.category = Some(compile(pattern)?);
+        Ok(self)
+    }
+
+    fn matches_fields(&self, category: &str, name: &str, description: &str) -> bool {
+        self.category.as_ref().is_none_or(|r| r.is_match(category))
+            && self.name.as_ref().is_none_or(|r| r.is_match(name))
+            && self
+                .description
+                .as_ref()
+                .is_none_or(|r| r.is_match(description))
+    }
+
+    pub fn matches(&self, package: &Package) -> bool {
+        self.matches_fields(&package.category, &package.name, &package.description)
+    }
+
+    pub fn matches_summary(&self, summary: &PackageSummary) -> bool {
+        self.matches_fields(&summary.category, &summary.name, &summary.description)
+    }
+}
+
+/// A matching package, valid during the callback
+#[derive(Debug, Clone, Copy)]
+pub struct Hit<'a> {
+    pub package: &'a Package,
+    /// The number of the package in the file, counting from 0
+    pub index: usize,
+}
+
+/// What a [`scan`] went through
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// Packages tested against the matcher
+    pub packages: usize,
+    pub hits: usize,
+    /// Whether the callback stopped the scan
+    pub stopped: bool,
+}
+
+/// Scans the database at `path`, calling `on_hit` for every package
+/// `matcher` matches, in the order of the file
+#[cfg(feature = "fs")]
+pub fn scan<P, F>(path: P, matcher: &Matcher, on_hit: F) -> io::Result<ScanStats>
+where
+    P: AsRef<std::path::Path>,
+    F: FnMut(Hit<'_>) -> ControlFlow<()>,
+{
+    let mut db = crate::Database::open_read(path)?;
+    let header = db.read_header(0)?;
+    scan_reader(&mut PackageReader::new(db, header), matcher, on_hit)
+}
+
+/// Like [`scan`], for the remaining packages of `reader`. After a
+/// [`ControlFlow::Break`], the reader is at the package after the hit.
+pub fn scan_reader<R, F>(
+    reader: &mut PackageReader<R>,
+    matcher: &Matcher,
+    mut on_hit: F,
+) -> io::Result<ScanStats>
+where
+    R: Read,
+    F: FnMut(Hit<'_>) -> ControlFlow<()>,
+{
+    let mut stats = ScanStats::default();
+    loop {
+        let Some((summary, versions)) = reader.read_package_lazy()? else {
+            if reader.next_category()? {
+                continue;
+            }
+            return Ok(stats);
+        };
+        let index = stats.packages;
+        stats.packages += 1;
+        // Dropping the versions of other packages skips them undecoded
+        if !matcher.matches_summary(&summary) {
+            continue;
+        }
+        let package = Package {
+            category: summary.category,
+            name: summary.name,
+            description: summary.description,
+            homepage: summary.homepage,
+            licenses: summary.licenses,
+            versions: versions.collect::<io::Result<_>>()?,
+            span: None,
+        };
+        stats.hits += 1;
+        if on_hit(Hit {
+            package: &package,
+            index,
+        })
+        .is_break()
+        {
+            stats.stopped = true;
+            return Ok(stats);
+        }
+    }
+}
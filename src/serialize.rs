@@ -0,0 +1,384 @@
+//! Configurable serialization
+//!
+//! The `Serialize` implementations of the types produce the default
+//! JSON format; that of [`Version`] goes through the view of this module
+//! with default options. [`SerializeOptions`] selects alternative
+//! representations; with default options the output is the same.
+//!
+//! With [`SerializeOptions::version_parts`], each version gets a `parts`
+//! array after `version`, holding the parsed version as objects with the
+//! [`PartType`] name and the text of the part:
+//!
+//! ```json
+//! "version": "1.2_rc3",
+//! "parts": [
+//!   {"type": "First", "content": "1"},
+//!   {"type": "Primary", "content": "2"},
+//!   {"type": "Rc", "content": "3"}
+//! ],
+//! ```
+//!
+//! Deserializing a [`Version`] reads the parts back.
+
+use crate::{BasicPart, DBHeader, Package, PartType, Version};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Options for serializing packages through a [`PackageView`]
+#[derive(Debug, Clone, Default)]
+pub struct SerializeOptions {
+    /// Emit the homepage as an array of URLs instead of the raw string
+    pub homepage_as_array: bool,
+    /// Emit the keywords and the USE flags of versions as strings joined
+    /// with spaces instead of arrays
+    pub joined_words: bool,
+    /// Emit packages and versions in the order of
+    /// [`SortOrder::Canonical`](crate::SortOrder::Canonical) instead of
+    /// the order of the file
+    #[cfg(feature = "std")]
+    pub canonical_order: bool,
+    /// Emit the [parts](Version::parts) of versions, see the
+    /// [module documentation](self)
+    pub version_parts: bool,
+    /// Emit the string hashes of a header viewed with
+    /// [`view_header`](Self::view_header) as arrays in index order, so
+    /// entry N of a hash stays at index N
+    pub string_hashes: bool,
+}
+
+impl SerializeOptions {
+    /// Returns a serializable view of `package` using these options
+    pub fn view<'a>(&'a self, package: &'a Package) -> PackageView<'a> {
+        PackageView {
+            package,
+            options: self,
+            fields: None,
+        }
+    }
+
+    /// Like [`view`](Self::view), serializing only the `fields` of
+    /// [`PACKAGE_FIELDS`]
+    #[cfg(feature = "json")]
+    pub(crate) fn view_fields<'a>(
+        &'a self,
+        package: &'a Package,
+        fields: &'a [String],
+    ) -> PackageView<'a> {
+        PackageView {
+            package,
+            options: self,
+            fields: Some(fields),
+        }
+    }
+
+    /// Returns a serializable view of `header` using these options
+    pub fn view_header<'a>(&'a self, header: &'a DBHeader) -> HeaderView<'a> {
+        HeaderView {
+            header,
+            options: self,
+        }
+    }
+
+    /// Returns a serializable view of `packages` using these options,
+    /// serialized as a sequence of [`PackageView`]s
+    pub fn view_list<'a>(&'a self, packages: &'a [Package]) -> PackageListView<'a> {
+        PackageListView {
+            packages,
+            options: self,
+        }
+    }
+}
+
+/// The serialized fields of a package, in order
+pub const PACKAGE_FIELDS: [&str; 6] = [
+    "category",
+    "name",
+    "description",
+    "homepage",
+    "licenses",
+    "versions",
+];
+
+/// A package serialized according to [`SerializeOptions`]
+#[derive(Debug, Clone, Copy)]
+pub struct PackageView<'a> {
+    package: &'a Package,
+    options: &'a SerializeOptions,
+    /// Only these fields if set
+    fields: Option<&'a [String]>,
+}
+
+impl Serialize for PackageView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let pkg = self.package;
+        let len = PACKAGE_FIELDS.iter().filter(|f| self.has(f)).count();
+        let mut s = serializer.serialize_struct("Package", len)?;
+        if self.has("category") {
+            s.serialize_field("category", &pkg.category)?;
+        }
+        if self.has("name") {
+            s.serialize_field("name", &pkg.name)?;
+        }
+        if self.has("description") {
+            s.serialize_field("description", &pkg.description)?;
+        }
+        if self.has("homepage") {
+            if self.options.homepage_as_array {
+                s.serialize_field("homepage", &pkg.homepages())?;
+            } else {
+                s.serialize_field("homepage", &pkg.homepage)?;
+            }
+        }
+        if self.has("licenses") {
+            s.serialize_field("licenses", &pkg.licenses)?;
+        }
+        if self.has("versions") {
+            let versions: Vec<VersionView> = self
+                .versions()
+                .into_iter()
+                .map(|version| VersionView {
+                    version,
+                    options: self.options,
+                })
+                .collect();
+            s.serialize_field("versions", &versions)?;
+        }
+        s.end()
+    }
+}
+
+impl PackageView<'_> {
+    /// Whether `field` is serialized
+    fn has(&self, field: &str) -> bool {
+        self.fields
+            .is_none_or(|fields| fields.iter().any(|f| f == field))
+    }
+
+    /// The versions in the order to serialize them
+    fn versions(&self) -> Vec<&Version> {
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
+        let mut versions: Vec<&Version> = self.package.versions.iter().collect();
+        #[cfg(feature = "std")]
+        if self.options.canonical_order {
+            versions.sort_by(|a, b| crate::sort::compare_versions(a, b));
+        }
+        versions
+    }
+}
+
+/// A version serialized according to [`SerializeOptions`]
+struct VersionView<'a> {
+    version: &'a Version,
+    options: &'a SerializeOptions,
+}
+
+impl Serialize for VersionView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let v = self.version;
+        let len = if self.options.version_parts { 15 } else { 14 };
+        let mut s = serializer.serialize_struct("Version", len)?;
+        s.serialize_field("version", &v.version_string)?;
+        if self.options.version_parts {
+            let parts: Vec<PartView> = v.parts.iter().map(PartView::from).collect();
+            s.serialize_field("parts", &parts)?;
+        }
+        s.serialize_field("eapi", &v.eapi)?;
+        s.serialize_field("mask_flags", &v.mask_flags)?;
+        s.serialize_field("properties_flags", &v.properties_flags)?;
+        s.serialize_field("restrict_flags", &v.restrict_flags)?;
+        if self.options.joined_words {
+            s.serialize_field("keywords", &v.keywords_string())?;
+        } else {
+            s.serialize_field("keywords", &v.keywords)?;
+        }
+        s.serialize_field("slot", &v.slot)?;
+        s.serialize_field("overlay_key", &v.overlay_key)?;
+        s.serialize_field("reponame", &v.reponame)?;
+        s.serialize_field("priority", &v.priority)?;
+        if self.options.joined_words {
+            s.serialize_field("iuse", &v.iuse.join(" "))?;
+        } else {
+            s.serialize_field("iuse", &v.iuse)?;
+        }
+        s.serialize_field("required_use", &v.required_use)?;
+        s.serialize_field("depend", &v.depend())?;
+        s.serialize_field("src_uri", &v.src_uri)?;
+        s.end()
+    }
+}
+
+/// A header serialized according to [`SerializeOptions`]
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderView<'a> {
+    header: &'a DBHeader,
+    options: &'a SerializeOptions,
+}
+
+impl Serialize for HeaderView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let h = self.header;
+        let len = if self.options.string_hashes { 13 } else { 7 };
+        let mut s = serializer.serialize_struct("DBHeader", len)?;
+        s.serialize_field("version", &h.version)?;
+        s.serialize_field("size", &h.size)?;
+        s.serialize_field("overlays", &h.overlays)?;
+        if self.options.string_hashes {
+            s.serialize_field("eapi_hash", &h.eapi_hash)?;
+            s.serialize_field("license_hash", &h.license_hash)?;
+            s.serialize_field("keywords_hash", &h.keywords_hash)?;
+            s.serialize_field("iuse_hash", &h.iuse_hash)?;
+            s.serialize_field("slot_hash", &h.slot_hash)?;
+            s.serialize_field("depend_hash", &h.depend_hash)?;
+        }
+        s.serialize_field("use_depend", &h.use_depend)?;
+        s.serialize_field("use_required_use", &h.use_required_use)?;
+        s.serialize_field("use_src_uri", &h.use_src_uri)?;
+        s.serialize_field("world_sets", &h.world_sets)?;
+        s.end()
+    }
+}
+
+/// A version part as serialized with [`SerializeOptions::version_parts`]
+#[derive(Serialize)]
+struct PartView<'a> {
+    #[serde(rename = "type")]
+    part_type: PartType,
+    content: &'a str,
+}
+
+impl<'a> From<&'a BasicPart> for PartView<'a> {
+    fn from(part: &'a BasicPart) -> Self {
+        PartView {
+            part_type: part.part_type,
+            content: &part.part_content,
+        }
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VersionView {
+            version: self,
+            options: &SerializeOptions::default(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A list of packages serialized according to [`SerializeOptions`]
+#[derive(Debug, Clone, Copy)]
+pub struct PackageListView<'a> {
+    packages: &'a [Package],
+    options: &'a SerializeOptions,
+}
+
+impl Serialize for PackageListView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
+        let mut packages: Vec<&Package> = self.packages.iter().collect();
+        #[cfg(feature = "std")]
+        if self.options.canonical_order {
+            packages.sort_by(|a, b| crate::sort::compare_atoms(a, b));
+        }
+        serializer.collect_seq(packages.into_iter().map(|p| self.options.view(p)))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrWords {
+    String(String),
+    Words(Vec<String>),
+}
+
+/// Deserializes a string which may also be given as an array of words
+/// (joined with a single space)
+pub(crate) fn string_or_words<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<String, D::Error> {
+    Ok(match StringOrWords::deserialize(deserializer)? {
+        StringOrWords::String(s) => s,
+        StringOrWords::Words(words) => words.join(" "),
+    })
+}
+
+/// Deserializes a list of words which may also be given as a string
+/// (split at whitespace)
+pub(crate) fn words_or_string<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    Ok(match StringOrWords::deserialize(deserializer)? {
+        StringOrWords::String(s) => s.split_whitespace().map(String::from).collect(),
+        StringOrWords::Words(words) => words,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package() -> Package {
+        Package {
+            category: "dev-lang".to_string(),
+            name: "python".to_string(),
+            homepage: "https://www.python.org/ https://github.com/python/cpython/".to_string(),
+            versions: vec![Version {
+                version_string: "3.12.12".to_string(),
+                keywords: vec!["amd64".to_string(), "~arm64".to_string()],
+                iuse: vec!["+ssl".to_string(), "test".to_string()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_options_match_derive() {
+        let pkg = package();
+        let options = SerializeOptions::default();
+        assert_eq!(
+            serde_json::to_string(&options.view(&pkg)).unwrap(),
+            serde_json::to_string(&pkg).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_homepage_as_array() {
+        let pkg = package();
+        let options = SerializeOptions {
+            homepage_as_array: true,
+            ..Default::default()
+        };
+        let value = serde_json::to_value(options.view(&pkg)).unwrap();
+        assert_eq!(
+            value["homepage"],
+            serde_json::json!([
+                "https://www.python.org/",
+                "https://github.com/python/cpython/"
+            ])
+        );
+
+        // Both representations deserialize to the same package
+        let back: Package = serde_json::from_value(value).unwrap();
+        assert_eq!(back.homepage, pkg.homepage);
+    }
+
+    #[test]
+    fn test_joined_words() {
+        let pkg = package();
+        let options = SerializeOptions {
+            joined_words: true,
+            ..Default::default()
+        };
+        let value = serde_json::to_value(options.view(&pkg)).unwrap();
+        assert_eq!(value["versions"][0]["keywords"], "amd64 ~arm64");
+        assert_eq!(value["versions"][0]["iuse"], "+ssl test");
+        assert_eq!(value["versions"][0]["required_use"], serde_json::json!([]));
+
+        let back: Package = serde_json::from_value(value).unwrap();
+        assert_eq!(back.versions[0].keywords, pkg.versions[0].keywords);
+        assert_eq!(back.versions[0].iuse, pkg.versions[0].iuse);
+    }
+}
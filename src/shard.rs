@@ -0,0 +1,123 @@
+//! Splitting a database into one file per category and back
+//!
+//! Each shard is a complete, valid database of a single category whose
+//! header only contains the strings and overlays its packages refer to.
+
+use crate::merge::unite_databases;
+use crate::{
+    DBHeader, Database, EixWriter, OverlayIdent, Package, PackageReader, ParsedDb, StringHash,
+};
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Builds the header of a shard holding `packages` and remaps their
+/// overlay keys to the overlays kept
+fn shard_header(header: &DBHeader, packages: &mut [Package]) -> DBHeader {
+    let mut used = vec![false; header.overlays.len()];
+    for v in packages.iter().flat_map(|p| &p.versions) {
+        if let Some(used) = used.get_mut(v.overlay_key as usize) {
+            *used = true;
+        }
+    }
+    let mut keys = vec![0; header.overlays.len()];
+    let mut overlays = Vec::new();
+    for (key, overlay) in header.overlays.iter().enumerate() {
+        if used[key] {
+            keys[key] = overlays.len();
+            overlays.push(OverlayIdent {
+                priority: overlays.len() as i32,
+                ..overlay.clone()
+            });
+        }
+    }
+    for v in packages.iter_mut().flat_map(|p| &mut p.versions) {
+        if let Some(&key) = keys.get(v.overlay_key as usize) {
+            v.overlay_key = key as u64;
+            v.priority = key as i32;
+        }
+    }
+
+    // The writer fills the hashes with the strings actually referenced
+    DBHeader {
+        version: header.version,
+        size: 1,
+        overlays,
+        eapi_hash: StringHash::new(),
+        license_hash: StringHash::new(),
+        keywords_hash: StringHash::new(),
+        iuse_hash: StringHash::new(),
+        slot_hash: StringHash::new(),
+        depend_hash: StringHash::new(),
+        use_depend: header.use_depend,
+        use_required_use: header.use_required_use,
+        use_src_uri: header.use_src_uri,
+        world_sets: header.world_sets.clone(),
+        warnings: Vec::new(),
+    }
+}
+
+/// Reads the database at `input` once and writes every category for
+/// which `filter` returns true to `<out_dir>/<category>.eix`.
+///
+/// Returns the paths of the written files in database order.
+pub fn split_by_category<P, Q, F>(input: P, out_dir: Q, mut filter: F) -> io::Result<Vec<PathBuf>>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(&str) -> bool,
+{
+    let out_dir = out_dir.as_ref();
+    fs::create_dir_all(out_dir)?;
+    let mut db = Database::open_read(input)?;
+    let header = db.read_header(0)?;
+    let mut reader = PackageReader::new(db, header.clone());
+    let mut written = Vec::new();
+
+    while reader.next_category()? {
+        let category = reader.current_category().to_string();
+        let wanted = filter(&category);
+        let mut packages = Vec::new();
+        // The packages of skipped categories have to be read anyway
+        while let Some(pkg) = reader.read_package()? {
+            if wanted {
+                packages.push(pkg);
+            }
+        }
+        if !wanted {
+            continue;
+        }
+        if category.is_empty() || category.contains(['/', '\\']) || category.starts_with('.') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Category name not usable as file name: {:?}", category),
+            ));
+        }
+
+        let shard = shard_header(&header, &mut packages);
+        let path = out_dir.join(format!("{}.eix", category));
+        let mut writer = EixWriter::new(BufWriter::new(File::create(&path)?));
+        writer.write_database(&shard, &packages)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Reads the databases `files` (e.g. written by [`split_by_category`])
+/// and concatenates them into one database which can be written with
+/// [`EixWriter`].
+///
+/// Overlays with identical label and path are the same; packages are
+/// kept in file order and not combined.
+pub fn concat_categories<I, P>(files: I) -> io::Result<(DBHeader, Vec<Package>)>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut inputs = Vec::new();
+    for file in files {
+        let parsed = ParsedDb::load(file)?;
+        inputs.push((parsed.header, parsed.packages));
+    }
+    Ok(unite_databases(inputs))
+}
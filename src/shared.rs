@@ -0,0 +1,104 @@
+//! An immutable database snapshot shared between threads
+//!
+//! [`SharedDb`] wraps a [`ParsedDb`] and its lookup indexes in an `Arc`:
+//! cloning it is cheap and every clone can be queried concurrently.
+
+use crate::{Atom, DBHeader, DatabaseInfo, Package, PackageQuery, ParsedDb};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::sync::Arc;
+
+#[derive(Debug)]
+struct Snapshot {
+    parsed: ParsedDb,
+    /// `category/name` → index into the packages
+    by_cp: HashMap<String, usize>,
+    /// Category → range of its packages
+    categories: BTreeMap<String, Range<usize>>,
+}
+
+/// A parsed database with lookup indexes, cheap to clone and `Send + Sync`
+#[derive(Debug, Clone)]
+pub struct SharedDb {
+    inner: Arc<Snapshot>,
+}
+
+impl SharedDb {
+    /// Builds the indexes. The packages are ordered by category
+    /// (stable, so already sorted files keep their order), which also
+    /// sorts files failing [`ParsedDb::is_category_sorted`].
+    pub fn new(mut parsed: ParsedDb) -> Self {
+        parsed.packages.sort_by(|a, b| a.category.cmp(&b.category));
+        let mut by_cp = HashMap::with_capacity(parsed.packages.len());
+        let mut categories: BTreeMap<String, Range<usize>> = BTreeMap::new();
+        for (i, pkg) in parsed.packages.iter().enumerate() {
+            by_cp
+                .entry(format!("{}/{}", pkg.category, pkg.name))
+                .or_insert(i);
+            match categories.get_mut(&pkg.category) {
+                Some(range) => range.end = i + 1,
+                None => {
+                    categories.insert(pkg.category.clone(), i..i + 1);
+                }
+            }
+        }
+        SharedDb {
+            inner: Arc::new(Snapshot {
+                parsed,
+                by_cp,
+                categories,
+            }),
+        }
+    }
+
+    pub fn header(&self) -> &DBHeader {
+        &self.inner.parsed.header
+    }
+
+    pub fn info(&self) -> &DatabaseInfo {
+        &self.inner.parsed.info
+    }
+
+    /// All packages, ordered by category
+    pub fn packages(&self) -> &[Package] {
+        &self.inner.parsed.packages
+    }
+
+    /// Returns the package of `atom` if one of its versions matches it
+    pub fn get(&self, atom: &Atom) -> Option<&Package> {
+        let pkg = &self.packages()[*self.inner.by_cp.get(&atom.cp())?];
+        pkg.versions
+            .iter()
+            .any(|v| atom.matches(pkg, v))
+            .then_some(pkg)
+    }
+
+    /// The packages of `category`; empty if there is no such category
+    pub fn category(&self, category: &str) -> &[Package] {
+        match self.inner.categories.get(category) {
+            Some(range) => &self.packages()[range.clone()],
+            None => &[],
+        }
+    }
+
+    /// Names of all categories, sorted
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        self.inner.categories.keys().map(String::as_str)
+    }
+
+    /// The packages matching `query`, using the category index if the
+    /// query names a category
+    pub fn query<'a>(&'a self, query: &'a PackageQuery) -> impl Iterator<Item = &'a Package> {
+        let candidates = match &query.category {
+            Some(category) => self.category(category),
+            None => self.packages(),
+        };
+        candidates.iter().filter(|pkg| query.matches(pkg))
+    }
+}
+
+impl From<ParsedDb> for SharedDb {
+    fn from(parsed: ParsedDb) -> Self {
+        SharedDb::new(parsed)
+    }
+}
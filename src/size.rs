@@ -0,0 +1,232 @@
+//! Where the bytes of a database file go
+//!
+//! [`SizeReport::analyze`] walks a file section by section and
+//! attributes every byte to the header, a category, or trailing data.
+
+use crate::decoder::initial_capacity;
+use crate::{DBHeader, Database, Decoder, OpenOptions};
+use serde::Serialize;
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io::{self, Read};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Number of packages listed in [`SizeReport::largest_packages`]
+pub const LARGEST_PACKAGES: usize = 10;
+
+/// Bytes of the sections of the header
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HeaderSizes {
+    /// Magic, version, category and overlay count
+    pub preamble: u64,
+    pub overlays: u64,
+    pub eapi_hash: u64,
+    pub license_hash: u64,
+    pub keywords_hash: u64,
+    pub iuse_hash: u64,
+    pub slot_hash: u64,
+    pub world_sets: u64,
+    pub bitmask: u64,
+    /// The dependency hash including its length prefix
+    pub depend_hash: u64,
+    pub total: u64,
+}
+
+/// Bytes spent on the parts of the packages of a category
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SectionSizes {
+    /// Category name and package count
+    pub framing: u64,
+    /// Length prefix, name, description, homepage, license, version count
+    pub package_metadata: u64,
+    /// Version records without dependencies and SRC_URI
+    pub versions: u64,
+    /// Dependency blocks including their length prefixes
+    pub depends: u64,
+    pub src_uri: u64,
+    pub total: u64,
+}
+
+impl SectionSizes {
+    fn add(&mut self, other: &SectionSizes) {
+        self.framing += other.framing;
+        self.package_metadata += other.package_metadata;
+        self.versions += other.versions;
+        self.depends += other.depends;
+        self.src_uri += other.src_uri;
+        self.total += other.total;
+    }
+}
+
+/// The sizes of one category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategorySize {
+    pub category: String,
+    pub packages: usize,
+    #[serde(flatten)]
+    pub sizes: SectionSizes,
+}
+
+/// The encoded size of a package, including its length prefix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageSize {
+    pub atom: String,
+    pub bytes: u64,
+}
+
+/// Breakdown of the bytes of a database file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeReport {
+    pub file_size: u64,
+    pub header: HeaderSizes,
+    pub categories: Vec<CategorySize>,
+    /// Sum over all categories
+    pub packages: SectionSizes,
+    /// Bytes after the last category
+    pub trailing: u64,
+    /// The largest packages, largest first
+    pub largest_packages: Vec<PackageSize>,
+}
+
+/// Options of the walk: the dependencies are only needed as encoded
+fn options() -> OpenOptions {
+    OpenOptions {
+        lazy_depend: true,
+        ..Default::default()
+    }
+}
+
+/// The offset at which `context` was first set, see
+/// [`Decoder::record_offsets`]
+fn offset_of(offsets: &[(&'static str, u64)], context: &str) -> Option<u64> {
+    offsets
+        .iter()
+        .find(|&&(c, _)| c == context)
+        .map(|&(_, offset)| offset)
+}
+
+/// Reads the header, measuring its sections by the offsets of their
+/// contexts
+fn measure_header<R: Read>(db: &mut Decoder<R>) -> io::Result<(DBHeader, HeaderSizes)> {
+    db.record_offsets(true);
+    let header = db.read_header(0)?;
+    let offsets = db.take_offsets();
+    let end = db.position();
+    let at = |context| offset_of(&offsets, context).unwrap_or(end);
+    let overlays = offset_of(&offsets, "overlay path").unwrap_or(at("EAPI hash"));
+    let depend = at("depend hash length");
+    let sizes = HeaderSizes {
+        preamble: overlays,
+        overlays: at("EAPI hash") - overlays,
+        eapi_hash: at("license hash") - at("EAPI hash"),
+        license_hash: at("keywords hash") - at("license hash"),
+        keywords_hash: at("IUSE hash") - at("keywords hash"),
+        iuse_hash: at("slot hash") - at("IUSE hash"),
+        slot_hash: at("world sets") - at("slot hash"),
+        world_sets: at("save bitmask") - at("world sets"),
+        bitmask: depend - at("save bitmask"),
+        depend_hash: end - depend,
+        total: end,
+    };
+    Ok((header, sizes))
+}
+
+impl SizeReport {
+    /// Walks the database at `path` and attributes its bytes to the
+    /// sections of the header and the parts of every category.
+    ///
+    /// The length prefix of every package is checked against the bytes
+    /// actually consumed.
+    #[cfg(feature = "fs")]
+    pub fn analyze<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file_size = fs::metadata(path)?.len();
+        Self::analyze_database(Database::open_read_with(path, options())?, file_size)
+    }
+
+    /// Like [`SizeReport::analyze`] for a database in memory
+    pub fn analyze_bytes(data: &[u8]) -> io::Result<Self> {
+        Self::analyze_database(
+            Database::from_bytes_with(data, options()),
+            data.len() as u64,
+        )
+    }
+
+    fn analyze_database(mut db: Database, file_size: u64) -> io::Result<Self> {
+        let db = db.decoder_mut();
+        let (header, header_sizes) = measure_header(db)?;
+
+        let mut categories = Vec::with_capacity(initial_capacity(header.size as usize));
+        let mut all_packages = Vec::new();
+        let mut totals = SectionSizes::default();
+        for _ in 0..header.size {
+            let start = db.position();
+            db.set_context("category");
+            let category = db.read_string()?;
+            db.set_context("package count");
+            let package_count = db.read_count("Package list")?;
+            let mut sizes = SectionSizes {
+                framing: db.position() - start,
+                ..Default::default()
+            };
+
+            for _ in 0..package_count {
+                let package_start = db.position();
+                let (summary, end) = db.read_package_start(&header, &category)?;
+                sizes.package_metadata += db.position() - package_start;
+
+                for _ in 0..summary.version_count {
+                    db.take_offsets();
+                    let version_start = db.position();
+                    db.read_package_version(&header)?;
+                    let version_end = db.position();
+                    let offsets = db.take_offsets();
+                    let src_uri = offset_of(&offsets, "SRC_URI").unwrap_or(version_end);
+                    let depend = offset_of(&offsets, "depend length").unwrap_or(src_uri);
+                    sizes.versions += depend - version_start;
+                    sizes.depends += src_uri - depend;
+                    sizes.src_uri += version_end - src_uri;
+                }
+
+                if db.position() != end {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Package {}/{}: ends at offset {} instead of {} as its length says",
+                            category,
+                            summary.name,
+                            db.position(),
+                            end
+                        ),
+                    ));
+                }
+                all_packages.push(PackageSize {
+                    atom: format!("{}/{}", category, summary.name),
+                    bytes: db.position() - package_start,
+                });
+            }
+
+            sizes.total = db.position() - start;
+            totals.add(&sizes);
+            categories.push(CategorySize {
+                category,
+                packages: package_count,
+                sizes,
+            });
+        }
+
+        all_packages.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.atom.cmp(&b.atom)));
+        all_packages.truncate(LARGEST_PACKAGES);
+        let trailing = file_size.saturating_sub(db.position());
+
+        Ok(SizeReport {
+            file_size,
+            header: header_sizes,
+            categories,
+            packages: totals,
+            trailing,
+            largest_packages: all_packages,
+        })
+    }
+}
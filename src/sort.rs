@@ -0,0 +1,105 @@
+//! Canonical order of packages and versions
+//!
+//! Readers return packages and versions in file order, which depends on
+//! the eix run that wrote the file. [`sort_packages`] brings them into
+//! an order which only depends on their content, so output generated
+//! from two files can be compared line by line. [`EixWriter::sorted`]
+//! and [`SerializeOptions::canonical_order`] apply the same order.
+//!
+//! [`EixWriter::sorted`]: crate::EixWriter::sorted
+//! [`SerializeOptions::canonical_order`]: crate::SerializeOptions::canonical_order
+
+use crate::{Package, Version, compare_parts};
+use std::cmp::Ordering;
+
+/// Order established by [`sort_packages`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Packages by category and name, the versions of each package by
+    /// version, then by the priority of their overlay
+    #[default]
+    Canonical,
+    /// Packages by category and name; versions keep their order
+    Atom,
+}
+
+/// Orders packages by category and name
+pub(crate) fn compare_atoms(a: &Package, b: &Package) -> Ordering {
+    (&a.category, &a.name).cmp(&(&b.category, &b.name))
+}
+
+/// Orders versions by version, then by overlay priority. The version
+/// strings break the remaining ties, e.g. between `1.0` and `1.0-r0`.
+pub(crate) fn compare_versions(a: &Version, b: &Version) -> Ordering {
+    compare_parts(&a.parts, &b.parts)
+        .then_with(|| a.priority.cmp(&b.priority))
+        .then_with(|| a.version_string.cmp(&b.version_string))
+}
+
+/// Sorts `packages` in place. The sort is stable: packages with the
+/// same atom keep their relative order.
+pub fn sort_packages(packages: &mut [Package], order: SortOrder) {
+    packages.sort_by(compare_atoms);
+    if order == SortOrder::Canonical {
+        for package in packages {
+            package.versions.sort_by(compare_versions);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_version;
+
+    fn version(v: &str, priority: i32) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            priority,
+            ..Default::default()
+        }
+    }
+
+    fn package(cp: &str, versions: Vec<Version>) -> Package {
+        let (category, name) = cp.split_once('/').unwrap();
+        Package {
+            category: category.to_string(),
+            name: name.to_string(),
+            versions,
+            ..Default::default()
+        }
+    }
+
+    fn versions(package: &Package) -> Vec<(&str, i32)> {
+        package
+            .versions
+            .iter()
+            .map(|v| (v.version_string.as_str(), v.priority))
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_packages() {
+        let unsorted = vec![
+            package(
+                "dev-lang/rust",
+                vec![version("1.80.0", 0), version("1.9", 0)],
+            ),
+            package("app-misc/tmux", vec![version("3.4", 1), version("3.4", 0)]),
+            package("app-misc/screen", vec![version("4.9.1", 0)]),
+        ];
+
+        let mut packages = unsorted.clone();
+        sort_packages(&mut packages, SortOrder::Canonical);
+        let atoms: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(atoms, ["screen", "tmux", "rust"]);
+        assert_eq!(versions(&packages[1]), [("3.4", 0), ("3.4", 1)]);
+        assert_eq!(versions(&packages[2]), [("1.9", 0), ("1.80.0", 0)]);
+
+        let mut packages = unsorted;
+        sort_packages(&mut packages, SortOrder::Atom);
+        assert_eq!(versions(&packages[1]), [("3.4", 1), ("3.4", 0)]);
+        assert_eq!(versions(&packages[2]), [("1.80.0", 0), ("1.9", 0)]);
+    }
+}
@@ -0,0 +1,90 @@
+//! Splitting a database per overlay
+//!
+//! [`by_overlay`] gives each overlay its own database holding only its
+//! versions, e.g. to write one file per repository:
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! use eix::{EixWriter, split};
+//!
+//! let (header, packages) = eix::sample::parsed();
+//! for (label, (header, packages)) in split::by_overlay(&header, &packages) {
+//!     let mut out = Vec::new();
+//!     EixWriter::new(&mut out).write_database(&header, &packages).unwrap();
+//!     assert_eq!(&*header.overlays[0].label, label);
+//! }
+//! ```
+
+use crate::{Catsize, DBHeader, Package};
+use std::collections::HashMap;
+
+/// Splits `packages` into one database per overlay label of `header`.
+///
+/// Each database has a single overlay, at key 0, and keeps the hashes
+/// and world sets of `header`. Its packages retain only the versions of
+/// that overlay; packages without any are dropped, so a package with
+/// versions in several overlays appears in each of their databases.
+/// Overlays sharing a label share a database, with the first of them as
+/// its overlay. Versions with an overlay key not in `header` are
+/// dropped.
+pub fn by_overlay(
+    header: &DBHeader,
+    packages: &[Package],
+) -> HashMap<String, (DBHeader, Vec<Package>)> {
+    // The label of each overlay key
+    let labels: Vec<&str> = header.overlays.iter().map(|o| &*o.label).collect();
+    let mut split: HashMap<String, (DBHeader, Vec<Package>)> = HashMap::new();
+    for overlay in &header.overlays {
+        split.entry(overlay.label.to_string()).or_insert_with(|| {
+            let header = DBHeader {
+                overlays: vec![overlay.clone()],
+                ..header.clone()
+            };
+            (header, Vec::new())
+        });
+    }
+
+    for pkg in packages {
+        let mut by_label: Vec<(&str, Package)> = Vec::new();
+        for v in &pkg.versions {
+            let Some(&label) = labels.get(v.overlay_key as usize) else {
+                continue;
+            };
+            let i = match by_label.iter().position(|(l, _)| *l == label) {
+                Some(i) => i,
+                None => {
+                    by_label.push((
+                        label,
+                        Package {
+                            category: pkg.category.clone(),
+                            name: pkg.name.clone(),
+                            description: pkg.description.clone(),
+                            homepage: pkg.homepage.clone(),
+                            licenses: pkg.licenses.clone(),
+                            versions: Vec::new(),
+                            // The split package does not match a record of the file
+                            span: None,
+                        },
+                    ));
+                    by_label.len() - 1
+                }
+            };
+            let mut version = v.clone();
+            version.overlay_key = 0;
+            by_label[i].1.versions.push(version);
+        }
+        for (label, pkg) in by_label {
+            if let Some((_, packages)) = split.get_mut(label) {
+                packages.push(pkg);
+            }
+        }
+    }
+
+    for (header, packages) in split.values_mut() {
+        let mut categories: Vec<&str> = packages.iter().map(|p| p.category.as_str()).collect();
+        categories.sort_unstable();
+        categories.dedup();
+        header.size = categories.len() as Catsize;
+    }
+    split
+}
@@ -0,0 +1,436 @@
+//! SRC_URI expressions and the distfiles they name
+//!
+//! If the database stores SRC_URI (see [`DBHeader::use_src_uri`]), every
+//! version holds the variable of its ebuild, e.g.
+//! `https://example.org/v1.tar.gz -> foo-1.tar.gz doc? ( https://example.org/doc.tar.gz )`.
+//!
+//! [`Mirrors`] expands the `mirror://name/path` URIs with the
+//! `thirdpartymirrors` file of the repository.
+//!
+//! [`DBHeader::use_src_uri`]: crate::DBHeader::use_src_uri
+
+use crate::Version;
+use std::collections::{BTreeSet, HashMap, HashSet};
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Default location of the mirror list of the Gentoo repository
+pub const THIRDPARTYMIRRORS_PATH: &str = "/var/db/repos/gentoo/profiles/thirdpartymirrors";
+
+/// A URI with the name of the file it is saved as if given (`uri -> name`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrcUri {
+    pub uri: String,
+    pub rename: Option<String>,
+}
+
+/// A parsed SRC_URI expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SrcUriExpr {
+    Uri(SrcUri),
+    /// All of the children (also the top level and plain groups)
+    All(Vec<SrcUriExpr>),
+    /// Children apply only if the USE flag is (not) set: `flag? ( ... )`
+    UseConditional {
+        flag: String,
+        negated: bool,
+        children: Vec<SrcUriExpr>,
+    },
+}
+
+/// A file to fetch with the URIs providing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Distfile {
+    pub filename: String,
+    pub uris: Vec<String>,
+}
+
+fn parse_error(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl SrcUri {
+    /// Parses a SRC_URI expression
+    pub fn parse(s: &str) -> io::Result<SrcUriExpr> {
+        let mut tokens = s.split_whitespace().peekable();
+        let children = Self::parse_group(&mut tokens, false)?;
+        Ok(SrcUriExpr::All(children))
+    }
+
+    /// Parses tokens up to the closing parenthesis (if `nested`) or the end
+    fn parse_group<'a, I>(
+        tokens: &mut std::iter::Peekable<I>,
+        nested: bool,
+    ) -> io::Result<Vec<SrcUriExpr>>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let mut children = Vec::new();
+        while let Some(token) = tokens.next() {
+            match token {
+                ")" => {
+                    if !nested {
+                        return Err(parse_error("Unexpected ')' in SRC_URI".to_string()));
+                    }
+                    return Ok(children);
+                }
+                "(" => children.push(SrcUriExpr::All(Self::parse_group(tokens, true)?)),
+                "->" | "||" => {
+                    return Err(parse_error(format!("Unexpected '{}' in SRC_URI", token)));
+                }
+                _ if token.ends_with('?') => {
+                    if tokens.next() != Some("(") {
+                        return Err(parse_error(format!(
+                            "Expected '(' after '{}' in SRC_URI",
+                            token
+                        )));
+                    }
+                    let flag = &token[..token.len() - 1];
+                    let (negated, flag) = match flag.strip_prefix('!') {
+                        Some(f) => (true, f),
+                        None => (false, flag),
+                    };
+                    children.push(SrcUriExpr::UseConditional {
+                        flag: flag.to_string(),
+                        negated,
+                        children: Self::parse_group(tokens, true)?,
+                    });
+                }
+                _ => {
+                    let mut rename = None;
+                    if tokens.next_if_eq(&"->").is_some() {
+                        match tokens.next() {
+                            Some(name) if !matches!(name, "(" | ")" | "->") => {
+                                rename = Some(name.to_string());
+                            }
+                            _ => {
+                                return Err(parse_error(format!(
+                                    "Expected a file name after '{} ->' in SRC_URI",
+                                    token
+                                )));
+                            }
+                        }
+                    }
+                    children.push(SrcUriExpr::Uri(SrcUri {
+                        uri: token.to_string(),
+                        rename,
+                    }));
+                }
+            }
+        }
+        if nested {
+            return Err(parse_error("Missing ')' in SRC_URI".to_string()));
+        }
+        Ok(children)
+    }
+
+    /// The name of the fetched file: the rename, else the last path
+    /// component of the URI
+    pub fn filename(&self) -> &str {
+        match &self.rename {
+            Some(name) => name,
+            None => self.uri.rsplit('/').next().unwrap_or_default(),
+        }
+    }
+}
+
+impl SrcUriExpr {
+    /// All URIs regardless of USE flags, in order
+    pub fn all_uris(&self) -> Vec<&SrcUri> {
+        match self {
+            SrcUriExpr::Uri(uri) => vec![uri],
+            SrcUriExpr::All(children) | SrcUriExpr::UseConditional { children, .. } => {
+                children.iter().flat_map(|c| c.all_uris()).collect()
+            }
+        }
+    }
+
+    /// All URIs regardless of USE flags with `mirror://` URIs replaced
+    /// by the URIs of every configured mirror. URIs of unknown mirrors
+    /// are kept as they are, see [`unknown_mirrors`](Self::unknown_mirrors).
+    pub fn expand_mirrors(&self, mirrors: &Mirrors) -> Vec<String> {
+        self.all_uris()
+            .into_iter()
+            .flat_map(|uri| {
+                mirrors
+                    .expand(&uri.uri)
+                    .unwrap_or_else(|| vec![uri.uri.clone()])
+            })
+            .collect()
+    }
+
+    /// The names of the mirrors used by `mirror://` URIs which are not
+    /// in `mirrors`
+    pub fn unknown_mirrors<'a>(&'a self, mirrors: &Mirrors) -> BTreeSet<&'a str> {
+        self.all_uris()
+            .into_iter()
+            .filter_map(|uri| split_mirror(&uri.uri))
+            .map(|(name, _)| name)
+            .filter(|name| mirrors.urls(name).is_none())
+            .collect()
+    }
+
+    /// The URIs which apply with the USE flags `enabled_use`, in order
+    pub fn uris<'a>(&'a self, enabled_use: &HashSet<&str>) -> Vec<&'a SrcUri> {
+        let mut result = Vec::new();
+        self.collect_uris(enabled_use, &mut result);
+        result
+    }
+
+    fn collect_uris<'a>(&'a self, enabled_use: &HashSet<&str>, result: &mut Vec<&'a SrcUri>) {
+        let children = match self {
+            SrcUriExpr::Uri(uri) => {
+                result.push(uri);
+                return;
+            }
+            SrcUriExpr::All(children) => children,
+            SrcUriExpr::UseConditional {
+                flag,
+                negated,
+                children,
+            } => {
+                if enabled_use.contains(flag.as_str()) == *negated {
+                    return;
+                }
+                children
+            }
+        };
+        for child in children {
+            child.collect_uris(enabled_use, result);
+        }
+    }
+
+    /// The files to fetch with the USE flags `enabled_use`, in the order
+    /// of their first URI; URIs saved as the same file are grouped
+    pub fn distfiles<'a, I>(&self, enabled_use: I) -> Vec<Distfile>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let enabled_use: HashSet<&str> = enabled_use.into_iter().collect();
+        let mut distfiles: Vec<Distfile> = Vec::new();
+        for uri in self.uris(&enabled_use) {
+            let filename = uri.filename();
+            match distfiles.iter_mut().find(|d| d.filename == filename) {
+                Some(distfile) => distfile.uris.push(uri.uri.clone()),
+                None => distfiles.push(Distfile {
+                    filename: filename.to_string(),
+                    uris: vec![uri.uri.clone()],
+                }),
+            }
+        }
+        distfiles
+    }
+}
+
+/// Splits `mirror://name/path` into the name and the path
+fn split_mirror(uri: &str) -> Option<(&str, &str)> {
+    let rest = uri.strip_prefix("mirror://")?;
+    Some(rest.split_once('/').unwrap_or((rest, "")))
+}
+
+/// The base URIs of the mirrors named in `mirror://` URIs, as listed
+/// in `profiles/thirdpartymirrors`
+#[derive(Debug, Clone, Default)]
+pub struct Mirrors {
+    mirrors: HashMap<String, Vec<String>>,
+}
+
+impl Mirrors {
+    /// Reads a `thirdpartymirrors` file
+    #[cfg(feature = "fs")]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Reads the mirrors from [`THIRDPARTYMIRRORS_PATH`]
+    #[cfg(feature = "fs")]
+    pub fn load_default() -> io::Result<Self> {
+        Self::load(THIRDPARTYMIRRORS_PATH)
+    }
+
+    /// Parses the contents of a `thirdpartymirrors` file: each line
+    /// holds a mirror name followed by its base URIs; `#` starts a
+    /// comment
+    pub fn parse(s: &str) -> Self {
+        let mut mirrors = HashMap::new();
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            if let Some(name) = words.next() {
+                mirrors.insert(name.to_string(), words.map(|w| w.to_string()).collect());
+            }
+        }
+        Mirrors { mirrors }
+    }
+
+    /// The base URIs of the mirror `name`
+    pub fn urls(&self, name: &str) -> Option<&[String]> {
+        self.mirrors.get(name).map(|m| m.as_slice())
+    }
+
+    /// Expands `mirror://name/path` to the URIs of the mirror `name`.
+    /// Other URIs are returned unchanged; `None` for unknown mirrors.
+    pub fn expand(&self, uri: &str) -> Option<Vec<String>> {
+        let Some((name, path)) = split_mirror(uri) else {
+            return Some(vec![uri.to_string()]);
+        };
+        let urls = self.urls(name)?;
+        Some(
+            urls.iter()
+                .map(|base| format!("{}/{}", base.trim_end_matches('/'), path))
+                .collect(),
+        )
+    }
+}
+
+impl Version {
+    /// Parses the stored SRC_URI; `None` if the database does not store it
+    pub fn src_uri_expr(&self) -> io::Result<Option<SrcUriExpr>> {
+        self.src_uri.as_deref().map(SrcUri::parse).transpose()
+    }
+
+    /// The files to fetch with the USE flags `enabled_use`, see
+    /// [`SrcUriExpr::distfiles`]; empty if the database does not store
+    /// SRC_URI
+    pub fn distfiles<'a, I>(&self, enabled_use: I) -> io::Result<Vec<Distfile>>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        Ok(match self.src_uri_expr()? {
+            Some(expr) => expr.distfiles(enabled_use),
+            None => Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filenames(distfiles: &[Distfile]) -> Vec<&str> {
+        distfiles.iter().map(|d| d.filename.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let expr =
+            SrcUri::parse("https://example.org/v1.0.tar.gz -> foo-1.0.tar.gz doc? ( mirror://gentoo/foo-doc.tar.xz )")
+                .unwrap();
+        assert_eq!(
+            expr,
+            SrcUriExpr::All(vec![
+                SrcUriExpr::Uri(SrcUri {
+                    uri: "https://example.org/v1.0.tar.gz".to_string(),
+                    rename: Some("foo-1.0.tar.gz".to_string()),
+                }),
+                SrcUriExpr::UseConditional {
+                    flag: "doc".to_string(),
+                    negated: false,
+                    children: vec![SrcUriExpr::Uri(SrcUri {
+                        uri: "mirror://gentoo/foo-doc.tar.xz".to_string(),
+                        rename: None,
+                    })],
+                },
+            ])
+        );
+        assert_eq!(SrcUri::parse("").unwrap(), SrcUriExpr::All(Vec::new()));
+
+        for invalid in [
+            "-> foo.tar.gz",
+            "https://example.org/a.tar.gz ->",
+            "https://example.org/a.tar.gz -> ( b )",
+            "https://example.org/a -> b -> c",
+            "doc? https://example.org/a",
+            "doc? ( https://example.org/a",
+            "https://example.org/a )",
+            "|| ( a b )",
+        ] {
+            assert!(SrcUri::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_distfiles() {
+        let expr = SrcUri::parse(
+            "https://example.org/v1.0.tar.gz -> foo-1.0.tar.gz
+             https://mirror.example.net/foo/v1.0.tar.gz -> foo-1.0.tar.gz
+             !minimal? ( https://example.org/data.zip
+                 doc? ( https://example.org/doc/foo-doc.tar.xz ) )
+             ( https://example.org/patches/fix.patch )
+             amd64? ( https://example.org/bin/foo-amd64 )",
+        )
+        .unwrap();
+
+        let distfiles = expr.distfiles(["doc"]);
+        assert_eq!(
+            filenames(&distfiles),
+            ["foo-1.0.tar.gz", "data.zip", "foo-doc.tar.xz", "fix.patch"]
+        );
+        assert_eq!(
+            distfiles[0].uris,
+            [
+                "https://example.org/v1.0.tar.gz",
+                "https://mirror.example.net/foo/v1.0.tar.gz"
+            ]
+        );
+
+        assert_eq!(
+            filenames(&expr.distfiles(["minimal", "doc", "amd64"])),
+            ["foo-1.0.tar.gz", "fix.patch", "foo-amd64"]
+        );
+    }
+
+    #[test]
+    fn test_version_distfiles() {
+        let mut v = Version::default();
+        assert!(v.distfiles([]).unwrap().is_empty());
+        v.src_uri = Some("https://example.org/a.tar.gz ssl? ( b.tar.gz )".to_string());
+        assert_eq!(filenames(&v.distfiles([]).unwrap()), ["a.tar.gz"]);
+        assert_eq!(
+            filenames(&v.distfiles(["ssl"]).unwrap()),
+            ["a.tar.gz", "b.tar.gz"]
+        );
+        v.src_uri = Some("ssl? (".to_string());
+        assert!(v.distfiles([]).is_err());
+    }
+
+    #[test]
+    fn test_expand_mirrors() {
+        let mirrors = Mirrors::parse(
+            "# Comment\n\
+             sourceforge https://downloads.sourceforge.net/ https://sf.example.org\n\
+             \n\
+             gnu https://ftpmirror.gnu.org # main\n",
+        );
+        assert_eq!(mirrors.urls("gnu").unwrap(), ["https://ftpmirror.gnu.org"]);
+        assert!(mirrors.urls("main").is_none());
+
+        let expr = SrcUri::parse(
+            "mirror://sourceforge/foo/foo-1.0.tar.gz
+             doc? ( mirror://gnu/foo/doc.tar.gz -> foo-doc.tar.gz )
+             https://example.org/fix.patch
+             mirror://kernel/linux/foo.tar.xz",
+        )
+        .unwrap();
+        assert_eq!(
+            expr.expand_mirrors(&mirrors),
+            [
+                "https://downloads.sourceforge.net/foo/foo-1.0.tar.gz",
+                "https://sf.example.org/foo/foo-1.0.tar.gz",
+                "https://ftpmirror.gnu.org/foo/doc.tar.gz",
+                "https://example.org/fix.patch",
+                "mirror://kernel/linux/foo.tar.xz",
+            ]
+        );
+        assert_eq!(
+            expr.unknown_mirrors(&mirrors)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["kernel"]
+        );
+        assert!(mirrors.expand("mirror://kernel/a").is_none());
+    }
+}
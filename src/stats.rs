@@ -0,0 +1,574 @@
+//! Aggregate statistics of a database
+//!
+//! The statistics are accumulated package by package, so they can be
+//! computed while streaming through a [`PackageReader`] without
+//! holding all packages in memory. [`DatabaseStats`] serializes to JSON
+//! and displays as a summary table:
+//!
+#![cfg_attr(feature = "sample-data", doc = "```")]
+#![cfg_attr(not(feature = "sample-data"), doc = "```ignore")]
+//! use eix::DatabaseStats;
+//!
+//! let (_, packages) = eix::sample::parsed();
+//! let stats = DatabaseStats::compute(&packages, 3);
+//! assert_eq!(stats.largest_packages.len(), 3);
+//! assert!(stats.to_string().starts_with("Categories:"));
+//! ```
+//!
+//! [`AttributeStats`] goes one step further and reads the raw hash
+//! indices of the file, resolving them to names only at the end.
+
+use crate::cancel::{Outcome, is_cancelled};
+use crate::{
+    DB_VERSION_CURRENT, DBHeader, DBVersion, Database, MASK_HARD, Package, PackageIndices,
+    PackageReader, RESTRICT_FETCH, StringHash,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::{self, Read};
+
+/// Number of categories and packages listed by default in
+/// [`DatabaseStats::top_categories`] and
+/// [`DatabaseStats::largest_packages`]
+pub const DEFAULT_TOP_CATEGORIES: usize = 10;
+
+/// Number of packages of a category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategoryCount {
+    pub category: String,
+    pub packages: usize,
+}
+
+/// Number of packages and versions of a category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CategoryTotals {
+    pub packages: usize,
+    pub versions: usize,
+}
+
+/// Number of versions of a package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageCount {
+    /// `category/name`
+    pub atom: String,
+    pub versions: usize,
+}
+
+/// Headline counts of a database
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatabaseStats {
+    /// Categories with at least one package
+    pub categories: usize,
+    pub packages: usize,
+    pub versions: usize,
+    /// Number of versions per overlay label
+    pub versions_per_overlay: BTreeMap<String, usize>,
+    /// Packages and versions per category
+    pub per_category: BTreeMap<String, CategoryTotals>,
+    /// The categories with the most packages, largest first
+    pub top_categories: Vec<CategoryCount>,
+    /// The packages with the most versions, largest first; of equal
+    /// ones, those read first
+    pub largest_packages: Vec<PackageCount>,
+    /// Number of versions per EAPI
+    pub eapi: BTreeMap<String, usize>,
+    /// Number of versions per license string of their package
+    pub licenses: BTreeMap<String, usize>,
+    /// Number of slots without their subslots, `""` being `"0"`
+    pub distinct_slots: usize,
+    pub distinct_licenses: usize,
+    /// Versions masked by package.mask or the profile
+    pub hard_masked: usize,
+    /// [Live](crate::Version::is_live) versions; the 9999 versions only
+    /// count in databases older than
+    /// [`DB_VERSION_LIVE_PROPERTY`](crate::DB_VERSION_LIVE_PROPERTY)
+    pub live: usize,
+    /// Versions with RESTRICT=fetch
+    pub fetch_restricted: usize,
+    pub avg_versions_per_package: f64,
+}
+
+/// Running counts while packages are added
+#[derive(Debug, Default)]
+struct Accumulator {
+    top_n: usize,
+    /// Version of the database the packages come from
+    db_version: DBVersion,
+    packages: usize,
+    versions: usize,
+    versions_per_overlay: BTreeMap<String, usize>,
+    per_category: BTreeMap<String, CategoryTotals>,
+    largest_packages: Vec<PackageCount>,
+    eapi: BTreeMap<String, usize>,
+    licenses_per_version: BTreeMap<String, usize>,
+    slots: HashSet<String>,
+    licenses: HashSet<String>,
+    hard_masked: usize,
+    live: usize,
+    fetch_restricted: usize,
+}
+
+impl Accumulator {
+    fn new(top_n: usize, db_version: DBVersion) -> Self {
+        Accumulator {
+            top_n,
+            db_version,
+            ..Default::default()
+        }
+    }
+
+    /// Adds the remaining packages of `reader`; on errors, the packages
+    /// read so far stay added
+    fn add_remaining(&mut self, reader: &mut PackageReader) -> io::Result<()> {
+        while reader.next_category()? {
+            while let Some(pkg) = reader.read_package()? {
+                self.add(&pkg);
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, pkg: &Package) {
+        let versions = pkg.versions.len();
+        self.packages += 1;
+        if let Some(totals) = self.per_category.get_mut(&pkg.category) {
+            totals.packages += 1;
+            totals.versions += versions;
+        } else {
+            self.per_category.insert(
+                pkg.category.clone(),
+                CategoryTotals {
+                    packages: 1,
+                    versions,
+                },
+            );
+        }
+        if !self.licenses.contains(&pkg.licenses) {
+            self.licenses.insert(pkg.licenses.clone());
+        }
+        if versions > 0 {
+            if let Some(count) = self.licenses_per_version.get_mut(&pkg.licenses) {
+                *count += versions;
+            } else {
+                self.licenses_per_version
+                    .insert(pkg.licenses.clone(), versions);
+            }
+        }
+        // Keep the list sorted, inserting after packages of equal size
+        let at = self
+            .largest_packages
+            .partition_point(|p| p.versions >= versions);
+        if at < self.top_n {
+            self.largest_packages.insert(
+                at,
+                PackageCount {
+                    atom: format!("{}/{}", pkg.category, pkg.name),
+                    versions,
+                },
+            );
+            self.largest_packages.truncate(self.top_n);
+        }
+        for v in &pkg.versions {
+            self.versions += 1;
+            if let Some(count) = self.eapi.get_mut(&v.eapi) {
+                *count += 1;
+            } else {
+                self.eapi.insert(v.eapi.clone(), 1);
+            }
+            if let Some(count) = self.versions_per_overlay.get_mut(&*v.reponame) {
+                *count += 1;
+            } else {
+                self.versions_per_overlay.insert(v.reponame.to_string(), 1);
+            }
+            let slot = v.normalized_slot();
+            if !self.slots.contains(slot) {
+                self.slots.insert(slot.to_string());
+            }
+            if v.mask_flags & MASK_HARD != 0 {
+                self.hard_masked += 1;
+            }
+            if v.is_live(self.db_version) {
+                self.live += 1;
+            }
+            if v.restrict_flags & RESTRICT_FETCH != 0 {
+                self.fetch_restricted += 1;
+            }
+        }
+    }
+
+    fn finish(self) -> DatabaseStats {
+        let mut top_categories: Vec<CategoryCount> = self
+            .per_category
+            .iter()
+            .map(|(category, totals)| CategoryCount {
+                category: category.clone(),
+                packages: totals.packages,
+            })
+            .collect();
+        // Stable sort keeps categories with equal counts in name order
+        top_categories.sort_by_key(|c| std::cmp::Reverse(c.packages));
+        top_categories.truncate(self.top_n);
+
+        DatabaseStats {
+            categories: self.per_category.len(),
+            packages: self.packages,
+            versions: self.versions,
+            versions_per_overlay: self.versions_per_overlay,
+            per_category: self.per_category,
+            top_categories,
+            largest_packages: self.largest_packages,
+            eapi: self.eapi,
+            licenses: self.licenses_per_version,
+            distinct_slots: self.slots.len(),
+            distinct_licenses: self.licenses.len(),
+            hard_masked: self.hard_masked,
+            live: self.live,
+            fetch_restricted: self.fetch_restricted,
+            avg_versions_per_package: if self.packages == 0 {
+                0.0
+            } else {
+                self.versions as f64 / self.packages as f64
+            },
+        }
+    }
+}
+
+impl DatabaseStats {
+    /// Computes the statistics of already parsed `packages` of a current
+    /// database, listing the `top_n` largest categories and packages
+    pub fn compute<'a, I>(packages: I, top_n: usize) -> Self
+    where
+        I: IntoIterator<Item = &'a Package>,
+    {
+        Self::compute_for_version(packages, top_n, DB_VERSION_CURRENT)
+    }
+
+    /// Like [`compute`](Self::compute) for `packages` read from a
+    /// database of version `db_version`, which decides the
+    /// [live](crate::Version::is_live) versions
+    pub fn compute_for_version<'a, I>(packages: I, top_n: usize, db_version: DBVersion) -> Self
+    where
+        I: IntoIterator<Item = &'a Package>,
+    {
+        let mut acc = Accumulator::new(top_n, db_version);
+        for pkg in packages {
+            acc.add(pkg);
+        }
+        acc.finish()
+    }
+
+    /// Computes the statistics of the remaining packages of `reader`,
+    /// holding only one package in memory at a time
+    pub fn from_reader(reader: &mut PackageReader, top_n: usize) -> io::Result<Self> {
+        let mut acc = Accumulator::new(top_n, reader.header().version);
+        acc.add_remaining(reader)?;
+        Ok(acc.finish())
+    }
+
+    /// Like [`from_reader`](Self::from_reader); once the token of
+    /// [`PackageReader::with_cancel`] is cancelled, the statistics of the
+    /// packages read so far are returned as [`Outcome::Cancelled`]
+    pub fn from_reader_cancellable(
+        reader: &mut PackageReader,
+        top_n: usize,
+    ) -> io::Result<Outcome<Self>> {
+        let mut acc = Accumulator::new(top_n, reader.header().version);
+        match acc.add_remaining(reader) {
+            Ok(()) => Ok(Outcome::Complete(acc.finish())),
+            Err(e) if is_cancelled(&e) => Ok(Outcome::Cancelled(acc.finish())),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// ```text
+/// Categories:        174
+/// Packages:          19176
+/// …
+/// Versions per overlay:
+///   gentoo           31730
+/// Versions per EAPI:
+///   7                3915
+///   8                27815
+/// Largest categories:
+///   dev-perl         1764
+///   …
+/// Largest packages:
+///   app-doc/phrack               71
+///   sys-kernel/gentoo-sources    53
+///   …
+/// ```
+impl fmt::Display for DatabaseStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Categories:        {}", self.categories)?;
+        writeln!(f, "Packages:          {}", self.packages)?;
+        writeln!(f, "Versions:          {}", self.versions)?;
+        writeln!(f, "Slots:             {}", self.distinct_slots)?;
+        writeln!(f, "Licenses:          {}", self.distinct_licenses)?;
+        writeln!(f, "Hard masked:       {}", self.hard_masked)?;
+        writeln!(f, "Live:              {}", self.live)?;
+        writeln!(f, "Fetch restricted:  {}", self.fetch_restricted)?;
+        writeln!(f, "Versions/package:  {:.2}", self.avg_versions_per_package)?;
+        writeln!(f, "Versions per overlay:")?;
+        for (overlay, count) in &self.versions_per_overlay {
+            writeln!(f, "  {:<16} {}", overlay, count)?;
+        }
+        writeln!(f, "Versions per EAPI:")?;
+        for (eapi, count) in &self.eapi {
+            writeln!(f, "  {:<16} {}", eapi, count)?;
+        }
+        writeln!(f, "Largest categories:")?;
+        for c in &self.top_categories {
+            writeln!(f, "  {:<16} {}", c.category, c.packages)?;
+        }
+        writeln!(f, "Largest packages:")?;
+        for p in &self.largest_packages {
+            writeln!(f, "  {:<28} {}", p.atom, p.versions)?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of versions keyworded stable and testing for an arch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ArchCoverage {
+    pub stable: usize,
+    pub testing: usize,
+}
+
+/// Distribution of versions over the hashed attributes of a database
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AttributeStats {
+    pub versions: usize,
+    /// Number of versions per EAPI (empty for databases before version 36)
+    pub eapi: BTreeMap<String, usize>,
+    /// Number of versions per license string of their package
+    pub licenses: BTreeMap<String, usize>,
+    /// Number of versions per slot; the slot "0" is stored as ""
+    pub slots: BTreeMap<String, usize>,
+    /// Number of versions per keyword token (e.g. "~amd64" or "-*")
+    pub keywords: BTreeMap<String, usize>,
+    /// Stable and testing versions per arch
+    pub arches: BTreeMap<String, ArchCoverage>,
+}
+
+/// Counters indexed like the entries of a hash
+struct HashCounts<'a> {
+    hash: &'a StringHash,
+    counts: Vec<usize>,
+}
+
+impl<'a> HashCounts<'a> {
+    fn new(hash: &'a StringHash) -> Self {
+        HashCounts {
+            hash,
+            counts: vec![0; hash.len()],
+        }
+    }
+
+    /// Adds `n` to the count of `index`, checked by the decoder
+    fn add(&mut self, index: u64, n: usize) {
+        self.counts[index as usize] += n;
+    }
+
+    fn resolve(&self) -> BTreeMap<String, usize> {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .filter_map(|(index, &count)| Some((self.hash.get_string(index)?.to_string(), count)))
+            .collect()
+    }
+}
+
+/// Running counts of [`AttributeStats`] by hash index
+struct AttributeCounts<'a> {
+    versions: usize,
+    eapi: HashCounts<'a>,
+    licenses: HashCounts<'a>,
+    slots: HashCounts<'a>,
+    keywords: HashCounts<'a>,
+}
+
+impl<'a> AttributeCounts<'a> {
+    fn new(header: &'a DBHeader) -> Self {
+        AttributeCounts {
+            versions: 0,
+            eapi: HashCounts::new(&header.eapi_hash),
+            licenses: HashCounts::new(&header.license_hash),
+            slots: HashCounts::new(&header.slot_hash),
+            keywords: HashCounts::new(&header.keywords_hash),
+        }
+    }
+
+    fn add(&mut self, pkg: &PackageIndices) {
+        self.versions += pkg.versions.len();
+        self.licenses.add(pkg.license, pkg.versions.len());
+        for v in &pkg.versions {
+            if let Some(eapi) = v.eapi {
+                self.eapi.add(eapi, 1);
+            }
+            self.slots.add(v.slot, 1);
+            for &keyword in &v.keywords {
+                self.keywords.add(keyword, 1);
+            }
+        }
+    }
+
+    /// Resolves the hash indices to their strings
+    fn finish(self) -> AttributeStats {
+        let keywords = self.keywords.resolve();
+        let mut arches: BTreeMap<String, ArchCoverage> = BTreeMap::new();
+        for (keyword, &count) in &keywords {
+            if keyword.contains('*') {
+                continue;
+            }
+            if let Some(arch) = keyword.strip_prefix('~') {
+                arches.entry(arch.to_string()).or_default().testing += count;
+            } else if !keyword.starts_with('-') {
+                arches.entry(keyword.clone()).or_default().stable += count;
+            }
+        }
+
+        AttributeStats {
+            versions: self.versions,
+            eapi: self.eapi.resolve(),
+            licenses: self.licenses.resolve(),
+            slots: self.slots.resolve(),
+            keywords,
+            arches,
+        }
+    }
+}
+
+impl AttributeStats {
+    /// Counts the versions of the database `db`, positioned directly
+    /// after `header`, in a single pass.
+    ///
+    /// Only the hash indices are read, see
+    /// [`Decoder::read_package_indices`](crate::Decoder::read_package_indices);
+    /// they are resolved to names at the end.
+    pub fn from_database<R: Read>(db: &mut Database<R>, header: &DBHeader) -> io::Result<Self> {
+        let mut counts = AttributeCounts::new(header);
+        for _ in 0..header.size {
+            db.set_context("category");
+            db.skip_string()?;
+            db.set_context("package count");
+            let package_count = db.read_count("Package list")?;
+            for _ in 0..package_count {
+                let pkg = db.decoder_mut().read_package_indices(header)?;
+                counts.add(&pkg);
+            }
+        }
+        Ok(counts.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DB_VERSION_LIVE_PROPERTY, MASK_PACKAGE, PROPERTIES_LIVE, Version};
+
+    fn version(slot: &str, reponame: &str) -> Version {
+        Version {
+            slot: slot.to_string(),
+            reponame: reponame.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compute() {
+        let mut masked = version("0", "gentoo");
+        masked.mask_flags = MASK_PACKAGE;
+        let mut live = version("0", "guru");
+        live.properties_flags = PROPERTIES_LIVE;
+        live.restrict_flags = RESTRICT_FETCH;
+        let packages = vec![
+            Package {
+                category: "dev-lang".to_string(),
+                name: "lua".to_string(),
+                licenses: "MIT".to_string(),
+                versions: vec![version("5.1", "gentoo"), version("5.4/5.4.6", "gentoo")],
+                ..Default::default()
+            },
+            Package {
+                category: "dev-lang".to_string(),
+                name: "rust".to_string(),
+                licenses: "MIT".to_string(),
+                versions: vec![masked, live],
+                ..Default::default()
+            },
+            Package {
+                category: "app-misc".to_string(),
+                name: "foo".to_string(),
+                licenses: "GPL-2".to_string(),
+                // The same slot as "0"
+                versions: vec![version("", "gentoo")],
+                ..Default::default()
+            },
+        ];
+
+        let stats = DatabaseStats::compute(&packages, 1);
+        assert_eq!(stats.categories, 2);
+        assert_eq!(stats.packages, 3);
+        assert_eq!(stats.versions, 5);
+        assert_eq!(stats.versions_per_overlay["gentoo"], 4);
+        assert_eq!(stats.versions_per_overlay["guru"], 1);
+        assert_eq!(
+            stats.top_categories,
+            vec![CategoryCount {
+                category: "dev-lang".to_string(),
+                packages: 2
+            }]
+        );
+        assert_eq!(
+            stats.per_category["dev-lang"],
+            CategoryTotals {
+                packages: 2,
+                versions: 4
+            }
+        );
+        // Of equal sizes, the package read first
+        assert_eq!(
+            stats.largest_packages,
+            vec![PackageCount {
+                atom: "dev-lang/lua".to_string(),
+                versions: 2
+            }]
+        );
+        assert_eq!(stats.licenses["MIT"], 4);
+        assert_eq!(stats.licenses["GPL-2"], 1);
+        assert_eq!(stats.eapi[""], 5);
+        assert_eq!(stats.distinct_slots, 3);
+        assert_eq!(stats.distinct_licenses, 2);
+        assert_eq!(stats.hard_masked, 1);
+        assert_eq!(stats.live, 1);
+        assert_eq!(stats.fetch_restricted, 1);
+        assert!((stats.avg_versions_per_package - 5.0 / 3.0).abs() < 1e-9);
+
+        let empty = DatabaseStats::compute(&[], DEFAULT_TOP_CATEGORIES);
+        assert_eq!(empty.avg_versions_per_package, 0.0);
+        assert!(empty.top_categories.is_empty());
+        assert!(empty.largest_packages.is_empty());
+    }
+
+    #[test]
+    fn test_compute_for_version() {
+        let packages = vec![Package {
+            category: "dev-vcs".to_string(),
+            name: "git".to_string(),
+            versions: vec![Version {
+                version_string: "9999".to_string(),
+                ..version("0", "gentoo")
+            }],
+            ..Default::default()
+        }];
+        let old = DB_VERSION_LIVE_PROPERTY - 1;
+        let stats = DatabaseStats::compute_for_version(&packages, DEFAULT_TOP_CATEGORIES, old);
+        assert_eq!(stats.live, 1);
+        let stats = DatabaseStats::compute(&packages, DEFAULT_TOP_CATEGORIES);
+        assert_eq!(stats.live, 0);
+    }
+}
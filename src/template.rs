@@ -0,0 +1,293 @@
+//! Output templates like `eix --format`
+//!
+//! A template is text with placeholders in angle brackets, e.g.
+//! `<category>/<name> <bestversion>\n`. Text between `{versions}` and
+//! `{/versions}` is repeated for every version of the package. `\n`,
+//! `\t`, `\\`, `\<` and `\{` are escapes.
+//!
+//! Package placeholders: `category`, `name`, `description`, `homepage`,
+//! `license`, `bestversion` and `installed` (`[I]` if a version is
+//! installed). Within `{versions}` additionally: `version`, `slot`,
+//! `keywords`, `repo`, `eapi`, and `installed` refers to the version.
+
+use crate::{InstalledIndex, Package, Version, split_slot};
+use std::fmt::{self, Write};
+use std::io;
+
+/// Error in a template, with the byte offset where it was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<TemplateError> for io::Error {
+    fn from(e: TemplateError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Category,
+    Name,
+    Description,
+    Homepage,
+    License,
+    BestVersion,
+    Installed,
+    Version,
+    Slot,
+    Keywords,
+    Repo,
+    Eapi,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "category" => Field::Category,
+            "name" => Field::Name,
+            "description" => Field::Description,
+            "homepage" => Field::Homepage,
+            "license" => Field::License,
+            "bestversion" => Field::BestVersion,
+            "installed" => Field::Installed,
+            "version" => Field::Version,
+            "slot" => Field::Slot,
+            "keywords" => Field::Keywords,
+            "repo" => Field::Repo,
+            "eapi" => Field::Eapi,
+            _ => return None,
+        })
+    }
+
+    /// Whether the field needs a version, i.e. is only valid in a loop
+    fn of_version(self) -> bool {
+        matches!(
+            self,
+            Field::Version | Field::Slot | Field::Keywords | Field::Repo | Field::Eapi
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Field(Field),
+    Versions(Vec<Segment>),
+}
+
+/// What [`OutputTemplate::render`] needs besides the package
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderContext<'a> {
+    /// Architecture for `bestversion`, see [`Package::best_version`]
+    pub arch: Option<&'a str>,
+    pub accept_unstable: bool,
+    /// Installed versions for `installed`; without them nothing is
+    /// marked installed
+    pub installed: Option<&'a InstalledIndex>,
+}
+
+/// A parsed template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    segments: Vec<Segment>,
+}
+
+/// The list new segments are added to: the loop body within a loop
+fn target<'a>(
+    outer: &'a mut Vec<Segment>,
+    body: &'a mut Option<(usize, Vec<Segment>)>,
+) -> &'a mut Vec<Segment> {
+    match body {
+        Some((_, body)) => body,
+        None => outer,
+    }
+}
+
+fn error(position: usize, message: impl Into<String>) -> TemplateError {
+    TemplateError {
+        position,
+        message: message.into(),
+    }
+}
+
+impl OutputTemplate {
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        // The outer list and, within a loop, the loop body
+        let mut outer: Vec<Segment> = Vec::new();
+        let mut body: Option<(usize, Vec<Segment>)> = None;
+        let mut text = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => text.push('\n'),
+                    Some((_, 't')) => text.push('\t'),
+                    Some((_, c @ ('\\' | '<' | '{'))) => text.push(c),
+                    Some((_, c)) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => text.push('\\'),
+                },
+                '<' | '{' => {
+                    let close = if c == '<' { '>' } else { '}' };
+                    let rest = &template[pos + 1..];
+                    let Some(len) = rest.find(close) else {
+                        return Err(error(pos, format!("Unterminated {}", c)));
+                    };
+                    let name = &rest[..len];
+                    // Skip the name and the closing bracket
+                    while chars.next_if(|&(p, _)| p <= pos + 1 + len).is_some() {}
+                    if !text.is_empty() {
+                        let text = Segment::Text(std::mem::take(&mut text));
+                        target(&mut outer, &mut body).push(text);
+                    }
+                    if c == '<' {
+                        let field = Field::from_name(name)
+                            .ok_or_else(|| error(pos, format!("Unknown placeholder <{}>", name)))?;
+                        if field.of_version() && body.is_none() {
+                            return Err(error(pos, format!("<{}> outside of {{versions}}", name)));
+                        }
+                        target(&mut outer, &mut body).push(Segment::Field(field));
+                    } else {
+                        match (name, body.take()) {
+                            ("versions", None) => body = Some((pos, Vec::new())),
+                            ("/versions", Some((_, segments))) => {
+                                outer.push(Segment::Versions(segments))
+                            }
+                            ("versions", Some(_)) => {
+                                return Err(error(pos, "Nested {versions}"));
+                            }
+                            ("/versions", None) => {
+                                return Err(error(pos, "{/versions} without {versions}"));
+                            }
+                            _ => return Err(error(pos, format!("Unknown block {{{}}}", name))),
+                        }
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+        if let Some((start, _)) = body {
+            return Err(error(start, "Unterminated {versions}"));
+        }
+        if !text.is_empty() {
+            outer.push(Segment::Text(text));
+        }
+        Ok(OutputTemplate { segments: outer })
+    }
+
+    pub fn render(&self, package: &Package, context: &RenderContext) -> String {
+        let mut out = String::new();
+        render_segments(&mut out, &self.segments, package, None, context);
+        out
+    }
+}
+
+fn render_segments(
+    out: &mut String,
+    segments: &[Segment],
+    package: &Package,
+    version: Option<&Version>,
+    context: &RenderContext,
+) {
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => out.push_str(text),
+            Segment::Field(field) => render_field(out, *field, package, version, context),
+            Segment::Versions(body) => {
+                for v in &package.versions {
+                    render_segments(out, body, package, Some(v), context);
+                }
+            }
+        }
+    }
+}
+
+fn render_field(
+    out: &mut String,
+    field: Field,
+    package: &Package,
+    version: Option<&Version>,
+    context: &RenderContext,
+) {
+    match (field, version) {
+        (Field::Category, _) => out.push_str(&package.category),
+        (Field::Name, _) => out.push_str(&package.name),
+        (Field::Description, _) => out.push_str(&package.description),
+        (Field::Homepage, _) => out.push_str(&package.homepage),
+        (Field::License, _) => out.push_str(&package.licenses),
+        (Field::BestVersion, _) => {
+            if let Some(best) = package.best_version(context.arch, context.accept_unstable) {
+                out.push_str(&best.version_string);
+            }
+        }
+        (Field::Installed, _) => {
+            let installed = context.installed.is_some_and(|index| match version {
+                Some(v) => v.is_installed_in(index, package),
+                None => !package.installed_in(index).is_empty(),
+            });
+            if installed {
+                out.push_str("[I]");
+            }
+        }
+        // The parser only allows the remaining fields within a loop
+        (_, None) => {}
+        (Field::Version, Some(v)) => out.push_str(&v.version_string),
+        (Field::Slot, Some(v)) => {
+            let (slot, subslot) = split_slot(&v.slot);
+            out.push_str(slot);
+            if let Some(subslot) = subslot {
+                let _ = write!(out, "/{}", subslot);
+            }
+        }
+        (Field::Keywords, Some(v)) => out.push_str(&v.keywords.join(" ")),
+        (Field::Repo, Some(v)) => out.push_str(&v.reponame),
+        (Field::Eapi, Some(v)) => out.push_str(&v.eapi),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(template: &str) -> TemplateError {
+        OutputTemplate::parse(template).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let e = parse_error("<category>/<nmae>");
+        assert_eq!(e.position, 11);
+        assert_eq!(e.to_string(), "Unknown placeholder <nmae> at position 11");
+        assert_eq!(parse_error("<version>").position, 0);
+        assert_eq!(parse_error("ab<name").message, "Unterminated <");
+        assert_eq!(parse_error("{versions}<version>").position, 0);
+        assert_eq!(parse_error("{versions}{versions}").position, 10);
+        assert_eq!(parse_error("x{/versions}").position, 1);
+        assert_eq!(parse_error("{loop}").message, "Unknown block {loop}");
+    }
+
+    #[test]
+    fn test_render_escapes() {
+        let template = OutputTemplate::parse(r"\<<name>\>\t\{x}\\\n\q").unwrap();
+        let package = Package {
+            name: "vim".to_string(),
+            ..Default::default()
+        };
+        let out = template.render(&package, &RenderContext::default());
+        assert_eq!(out, "<vim\\>\t{x}\\\n\\q");
+    }
+}
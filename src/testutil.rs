@@ -0,0 +1,268 @@
+//! Small databases for tests
+//!
+//! [`EixTestBuilder`] describes packages in a few lines and writes them
+//! with [`EixWriter`], which fills the string hashes and counts the
+//! categories, so tests do not need a database file:
+//!
+//! ```
+//! use eix::EixTestBuilder;
+//!
+//! let bytes = EixTestBuilder::new()
+//!     .overlay("gentoo", "/usr/portage")
+//!     .category("dev-lang")
+//!     .package("rust", |p| {
+//!         p.version("1.78.0", |v| v.keywords("~amd64").slot("stable"))
+//!     })
+//!     .build_bytes();
+//! assert!(bytes.starts_with(b"eix\n"));
+//! ```
+
+use crate::{
+    DB_VERSION_CURRENT, DBHeader, Database, Depend, EixWriter, OverlayIdent, Package, StringHash,
+    Version, parse_version,
+};
+
+/// Builds a database in memory, see the [module documentation](self)
+#[derive(Debug, Clone, Default)]
+pub struct EixTestBuilder {
+    overlays: Vec<OverlayIdent>,
+    world_sets: Vec<String>,
+    category: Option<String>,
+    packages: Vec<Package>,
+}
+
+impl EixTestBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an overlay; versions refer to it by its position, see
+    /// [`VersionBuilder::overlay`]. Without any, the database has a
+    /// single `gentoo` overlay.
+    pub fn overlay(mut self, label: &str, path: &str) -> Self {
+        self.overlays.push(OverlayIdent {
+            path: path.to_string(),
+            label: label.into(),
+            priority: self.overlays.len() as i32,
+        });
+        self
+    }
+
+    /// Adds a world set, e.g. `@selected`
+    pub fn world_set(mut self, name: &str) -> Self {
+        self.world_sets.push(name.to_string());
+        self
+    }
+
+    /// Sets the category of the following packages
+    pub fn category(mut self, category: &str) -> Self {
+        self.category = Some(category.to_string());
+        self
+    }
+
+    /// Adds a package to the current category.
+    ///
+    /// # Panics
+    ///
+    /// If no category was set before.
+    pub fn package<F>(mut self, name: &str, build: F) -> Self
+    where
+        F: FnOnce(PackageBuilder) -> PackageBuilder,
+    {
+        let category = self
+            .category
+            .clone()
+            .expect("EixTestBuilder::category must be called before package");
+        let package = Package {
+            category,
+            name: name.to_string(),
+            ..Default::default()
+        };
+        self.packages.push(build(PackageBuilder(package)).0);
+        self
+    }
+
+    /// The header and the packages as they are read back from
+    /// [`build_bytes`](Self::build_bytes)
+    pub fn build(self) -> (DBHeader, Vec<Package>) {
+        let mut overlays = self.overlays;
+        if overlays.is_empty() {
+            overlays.push(OverlayIdent {
+                path: "/var/db/repos/gentoo".to_string(),
+                label: "gentoo".into(),
+                priority: 0,
+            });
+        }
+        let mut packages = self.packages;
+        for v in packages.iter_mut().flat_map(|p| &mut p.versions) {
+            let overlay = overlays
+                .get(v.overlay_key as usize)
+                .unwrap_or_else(|| panic!("No overlay with key {}", v.overlay_key));
+            v.reponame = overlay.label.clone();
+            v.priority = overlay.priority;
+        }
+        let versions = || packages.iter().flat_map(|p| &p.versions);
+        let use_depend = versions().any(|v| v.depend.is_some());
+        let use_src_uri = versions().any(|v| v.src_uri.is_some());
+        let header = DBHeader {
+            version: DB_VERSION_CURRENT,
+            size: 0,
+            overlays,
+            eapi_hash: StringHash::new(),
+            license_hash: StringHash::new(),
+            keywords_hash: StringHash::new(),
+            iuse_hash: StringHash::new(),
+            slot_hash: StringHash::new(),
+            depend_hash: StringHash::new(),
+            use_depend,
+            use_required_use: versions().any(|v| !v.required_use.is_empty()),
+            use_src_uri,
+            world_sets: self.world_sets,
+            warnings: Vec::new(),
+        };
+        // Once stored for one version, the fields are read for all
+        for v in packages.iter_mut().flat_map(|p| &mut p.versions) {
+            if use_depend && v.depend.is_none() {
+                v.depend = Some(Depend::default());
+            }
+            if use_src_uri && v.src_uri.is_none() {
+                v.src_uri = Some(String::new());
+            }
+        }
+        (header, packages)
+    }
+
+    /// The database file; the hashes and the number of categories are
+    /// derived from the packages
+    pub fn build_bytes(self) -> Vec<u8> {
+        let (header, packages) = self.build();
+        let mut writer = EixWriter::new(Vec::new());
+        writer
+            .write_database(&header, &packages)
+            .expect("Writing to memory failed");
+        writer.into_inner()
+    }
+
+    /// The database file, opened for reading
+    pub fn build_database(self) -> Database {
+        Database::from_bytes(self.build_bytes())
+    }
+}
+
+/// Describes a package of an [`EixTestBuilder`]
+#[derive(Debug, Clone)]
+pub struct PackageBuilder(Package);
+
+impl PackageBuilder {
+    pub fn description(mut self, description: &str) -> Self {
+        self.0.description = description.to_string();
+        self
+    }
+
+    pub fn homepage(mut self, homepage: &str) -> Self {
+        self.0.homepage = homepage.to_string();
+        self
+    }
+
+    pub fn licenses(mut self, licenses: &str) -> Self {
+        self.0.licenses = licenses.to_string();
+        self
+    }
+
+    /// Adds a version with EAPI 8 in the first overlay.
+    ///
+    /// # Panics
+    ///
+    /// If `version` is not a valid version.
+    pub fn version<F>(mut self, version: &str, build: F) -> Self
+    where
+        F: FnOnce(VersionBuilder) -> VersionBuilder,
+    {
+        let parts = parse_version(version)
+            .unwrap_or_else(|e| panic!("Invalid version {:?}: {}", version, e));
+        let v = Version {
+            version_string: version.to_string(),
+            parts,
+            eapi: "8".to_string(),
+            ..Default::default()
+        };
+        self.0.versions.push(build(VersionBuilder(v)).0);
+        self
+    }
+}
+
+/// Describes a version of a [`PackageBuilder`]
+#[derive(Debug, Clone)]
+pub struct VersionBuilder(Version);
+
+pub(crate) fn words(s: &str) -> Vec<String> {
+    s.split_whitespace().map(str::to_string).collect()
+}
+
+impl VersionBuilder {
+    pub fn eapi(mut self, eapi: &str) -> Self {
+        self.0.eapi = eapi.to_string();
+        self
+    }
+
+    /// Space separated keywords, e.g. `amd64 ~arm64`
+    pub fn keywords(mut self, keywords: &str) -> Self {
+        self.0.keywords = words(keywords);
+        self
+    }
+
+    /// `slot` or `slot/subslot`
+    pub fn slot(mut self, slot: &str) -> Self {
+        self.0.slot = slot.to_string();
+        self
+    }
+
+    /// The `MASK_*` flags
+    pub fn mask(mut self, flags: u8) -> Self {
+        self.0.mask_flags = flags;
+        self
+    }
+
+    /// The `PROPERTIES_*` flags
+    pub fn properties(mut self, flags: u32) -> Self {
+        self.0.properties_flags = flags;
+        self
+    }
+
+    /// The `RESTRICT_*` flags
+    pub fn restrict(mut self, flags: u64) -> Self {
+        self.0.restrict_flags = flags;
+        self
+    }
+
+    /// The position of the overlay in the order of
+    /// [`EixTestBuilder::overlay`]
+    pub fn overlay(mut self, key: u64) -> Self {
+        self.0.overlay_key = key;
+        self
+    }
+
+    /// Space separated USE flags, e.g. `+ssl test`
+    pub fn iuse(mut self, iuse: &str) -> Self {
+        self.0.iuse = words(iuse);
+        self
+    }
+
+    /// Stores REQUIRED_USE in the database
+    pub fn required_use(mut self, required_use: &str) -> Self {
+        self.0.required_use = words(required_use);
+        self
+    }
+
+    /// Stores the dependencies in the database
+    pub fn depend(mut self, depend: Depend) -> Self {
+        self.0.depend = Some(depend);
+        self
+    }
+
+    /// Stores SRC_URI in the database
+    pub fn src_uri(mut self, src_uri: &str) -> Self {
+        self.0.src_uri = Some(src_uri.to_string());
+        self
+    }
+}
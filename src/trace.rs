@@ -0,0 +1,164 @@
+//! Tracing how the decoder interprets the bytes of a file
+//!
+//! With the `trace` feature, a [`Decoder`](crate::Decoder) can report
+//! every value it reads as a [`TraceEvent`] to a [`Tracer`].
+//! [`dump_structure`] uses this to print an annotated walk of a file.
+
+use crate::BasicPart;
+#[cfg(feature = "fs")]
+use crate::{Database, PackageReader};
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::Write;
+#[cfg(feature = "fs")]
+use std::io::{self, Read, Seek, SeekFrom};
+#[cfg(feature = "fs")]
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::sync::{Arc, Mutex};
+
+/// Number of bytes shown before and after the offset of an error
+pub const HEX_CONTEXT: u64 = 32;
+
+/// A value read by the decoder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceValue {
+    /// Raw bytes, e.g. the magic
+    Bytes(Vec<u8>),
+    /// A single byte
+    Byte(u8),
+    Num(u64),
+    String(String),
+    /// An index into a string hash and the string it refers to
+    HashString {
+        index: u64,
+        value: String,
+    },
+    Part(BasicPart),
+    /// Bytes skipped without decoding
+    Skipped(u64),
+}
+
+/// A value and where it was read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// Offset of the first byte of the value
+    pub offset: u64,
+    /// What the value means, e.g. "category count"
+    pub context: &'static str,
+    pub value: TraceValue,
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "offset {:#06x}: ", self.offset)?;
+        match &self.value {
+            TraceValue::Bytes(bytes) => {
+                write!(f, "bytes")?;
+                for b in bytes {
+                    write!(f, " {:02x}", b)?;
+                }
+            }
+            TraceValue::Byte(b) => write!(f, "byte {:#04x}", b)?,
+            TraceValue::Num(n) => write!(f, "num {}", n)?,
+            TraceValue::String(s) => write!(f, "string {:?}", s)?,
+            TraceValue::HashString { index, value } => write!(f, "hash #{} {:?}", index, value)?,
+            TraceValue::Part(part) => {
+                write!(f, "part {:?} {:?}", part.part_type, part.part_content)?
+            }
+            TraceValue::Skipped(len) => write!(f, "skipped {} bytes", len)?,
+        }
+        write!(f, " ({})", self.context)
+    }
+}
+
+/// Receiver of the events of a decoder
+pub type Tracer = Box<dyn FnMut(&TraceEvent) + Send>;
+
+/// A tracer writing one line per event to `w`; write errors are ignored
+pub fn write_tracer<W: Write + Send + 'static>(mut w: W) -> Tracer {
+    Box::new(move |event| {
+        let _ = writeln!(w, "{}", event);
+    })
+}
+
+/// Writes a hex dump of the bytes of `path` around `offset`
+#[cfg(feature = "fs")]
+fn write_hex_context<W: Write>(path: &Path, offset: u64, w: &mut W) -> io::Result<()> {
+    let start = offset.saturating_sub(HEX_CONTEXT) & !0xf;
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.take(offset + HEX_CONTEXT - start)
+        .read_to_end(&mut bytes)?;
+    for (i, row) in bytes.chunks(16).enumerate() {
+        let row_start = start + 16 * i as u64;
+        write!(w, "{:#010x}:", row_start)?;
+        for (j, b) in row.iter().enumerate() {
+            let marker = if row_start + j as u64 == offset {
+                '>'
+            } else {
+                ' '
+            };
+            write!(w, "{}{:02x}", marker, b)?;
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+/// Writes an annotated walk of every value of the database at `path`
+/// to `w`, one line per value.
+///
+/// At the first error, the offset where decoding stopped, the error and
+/// the surrounding bytes (the byte at the offset marked with `>`) are
+/// written and the error is returned.
+#[cfg(feature = "fs")]
+pub fn dump_structure<P: AsRef<Path>, W: Write>(path: P, w: &mut W) -> io::Result<()> {
+    let path = path.as_ref();
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let mut db = Database::open_read(path)?;
+    let sink = Arc::clone(&lines);
+    db.set_tracer(Some(Box::new(move |event| {
+        sink.lock().unwrap().push(event.to_string());
+    })));
+
+    // Writes the collected lines; called after every package to bound memory
+    let flush = |w: &mut W| -> io::Result<()> {
+        for line in lines.lock().unwrap().drain(..) {
+            writeln!(w, "{}", line)?;
+        }
+        Ok(())
+    };
+
+    let result = match db.read_header(0) {
+        Err(e) => Err((db.position(), e)),
+        Ok(header) => {
+            flush(w)?;
+            let mut reader = PackageReader::new(db, header);
+            loop {
+                let step = match reader.read_package() {
+                    Ok(Some(_)) => Ok(true),
+                    Ok(None) => reader.next_category(),
+                    Err(e) => Err(e),
+                };
+                match step {
+                    Ok(true) => flush(w)?,
+                    Ok(false) => break Ok(reader.position()),
+                    Err(e) => break Err((reader.position(), e)),
+                }
+            }
+        }
+    };
+    flush(w)?;
+
+    match result {
+        Ok(end) => writeln!(w, "offset {:#06x}: end", end),
+        Err((offset, e)) => {
+            writeln!(w, "offset {:#06x}: error: {}", offset, e)?;
+            write_hex_context(path, offset, w)?;
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,929 @@
+//! The data types of a database: packages, versions and their flags
+//!
+//! This module does no I/O; reading and writing files is done by
+//! [`read`](crate::read) and [`write`](crate::write).
+
+use crate::codec;
+use crate::header::{DBHeader, Lazy, OverlayIdent};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+/* Basic types */
+pub type UChar = u8;
+pub type UNumber = u32;
+pub type Catsize = u32;
+pub type Treesize = u32;
+pub type OffsetType = i64;
+
+/* Mask Flags constants */
+pub const MASK_NONE: u8 = 0x00;
+pub const MASK_PACKAGE: u8 = 0x01;
+pub const MASK_PROFILE: u8 = 0x02;
+pub const MASK_HARD: u8 = MASK_PACKAGE | MASK_PROFILE;
+pub const MASK_SYSTEM: u8 = 0x04;
+pub const MASK_WORLD: u8 = 0x08;
+pub const MASK_WORLD_SETS: u8 = 0x10;
+pub const MASK_IN_PROFILE: u8 = 0x20;
+pub const MASK_MARKED: u8 = 0x40;
+
+/* Properties Flags constants; stored as a number, like the restrict
+ * flags, so that flags beyond the eighth can be added */
+pub const PROPERTIES_NONE: u32 = 0x00;
+pub const PROPERTIES_INTERACTIVE: u32 = 0x01;
+pub const PROPERTIES_LIVE: u32 = 0x02;
+pub const PROPERTIES_VIRTUAL: u32 = 0x04;
+pub const PROPERTIES_SET: u32 = 0x08;
+
+/* Restrict Flags constants */
+pub const RESTRICT_NONE: u64 = 0x0000;
+pub const RESTRICT_BINCHECKS: u64 = 0x0001;
+pub const RESTRICT_STRIP: u64 = 0x0002;
+pub const RESTRICT_TEST: u64 = 0x0004;
+pub const RESTRICT_USERPRIV: u64 = 0x0008;
+pub const RESTRICT_INSTALLSOURCES: u64 = 0x0010;
+pub const RESTRICT_FETCH: u64 = 0x0020;
+pub const RESTRICT_MIRROR: u64 = 0x0040;
+pub const RESTRICT_PRIMARYURI: u64 = 0x0080;
+pub const RESTRICT_BINDIST: u64 = 0x0100;
+pub const RESTRICT_PARALLEL: u64 = 0x0200;
+
+/* Names of the flags, as used by eix */
+pub const MASK_FLAG_NAMES: &[(u8, &str)] = &[
+    (MASK_PACKAGE, "package"),
+    (MASK_PROFILE, "profile"),
+    (MASK_SYSTEM, "system"),
+    (MASK_WORLD, "world"),
+    (MASK_WORLD_SETS, "world_sets"),
+    (MASK_IN_PROFILE, "in_profile"),
+    (MASK_MARKED, "marked"),
+];
+pub const PROPERTIES_FLAG_NAMES: &[(u32, &str)] = &[
+    (PROPERTIES_INTERACTIVE, "interactive"),
+    (PROPERTIES_LIVE, "live"),
+    (PROPERTIES_VIRTUAL, "virtual"),
+    (PROPERTIES_SET, "set"),
+];
+pub const RESTRICT_FLAG_NAMES: &[(u64, &str)] = &[
+    (RESTRICT_BINCHECKS, "binchecks"),
+    (RESTRICT_STRIP, "strip"),
+    (RESTRICT_TEST, "test"),
+    (RESTRICT_USERPRIV, "userpriv"),
+    (RESTRICT_INSTALLSOURCES, "installsources"),
+    (RESTRICT_FETCH, "fetch"),
+    (RESTRICT_MIRROR, "mirror"),
+    (RESTRICT_PRIMARYURI, "primaryuri"),
+    (RESTRICT_BINDIST, "bindist"),
+    (RESTRICT_PARALLEL, "parallel"),
+];
+
+/// The names of the flags set in `flags`, given a table like [`RESTRICT_FLAG_NAMES`]
+pub fn flag_names<T>(
+    flags: T,
+    names: &'static [(T, &'static str)],
+) -> impl Iterator<Item = &'static str>
+where
+    T: Copy + core::ops::BitAnd<Output = T> + PartialEq + Default,
+{
+    names
+        .iter()
+        .filter(move |&&(flag, _)| flags & flag != T::default())
+        .map(|&(_, name)| name)
+}
+
+/*
+ * BasicPart - A part of a version string
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BasicPart {
+    #[serde(alias = "type")]
+    pub part_type: PartType,
+    #[serde(alias = "content")]
+    pub part_content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum PartType {
+    Garbage = 0,
+    Alpha = 1,
+    Beta = 2,
+    Pre = 3,
+    Rc = 4,
+    Revision = 5,
+    InterRev = 6,
+    Patch = 7,
+    Character = 8,
+    Primary = 9,
+    First = 10,
+}
+
+impl PartType {
+    pub fn from_u64(v: u64) -> Self {
+        match v {
+            1 => PartType::Alpha,
+            2 => PartType::Beta,
+            3 => PartType::Pre,
+            4 => PartType::Rc,
+            5 => PartType::Revision,
+            6 => PartType::InterRev,
+            7 => PartType::Patch,
+            8 => PartType::Character,
+            9 => PartType::Primary,
+            10 => PartType::First,
+            _ => PartType::Garbage,
+        }
+    }
+
+    /// The part type with the given variant name, e.g. `"Alpha"`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "Garbage" => PartType::Garbage,
+            "Alpha" => PartType::Alpha,
+            "Beta" => PartType::Beta,
+            "Pre" => PartType::Pre,
+            "Rc" => PartType::Rc,
+            "Revision" => PartType::Revision,
+            "InterRev" => PartType::InterRev,
+            "Patch" => PartType::Patch,
+            "Character" => PartType::Character,
+            "Primary" => PartType::Primary,
+            "First" => PartType::First,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PartTypeRepr {
+    Num(u64),
+    Name(String),
+}
+
+/// Accepts the variant name as serialized, or the discriminant as
+/// stored in the file
+impl<'de> Deserialize<'de> for PartType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        match PartTypeRepr::deserialize(deserializer)? {
+            PartTypeRepr::Num(n) if n <= PartType::First as u64 => Ok(PartType::from_u64(n)),
+            PartTypeRepr::Num(n) => Err(D::Error::custom(format_args!("invalid part type {}", n))),
+            PartTypeRepr::Name(name) => PartType::from_name(&name)
+                .ok_or_else(|| D::Error::custom(format_args!("unknown part type {:?}", name))),
+        }
+    }
+}
+
+/*
+ * Span - Byte range of a record in the file
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Span {
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/*
+ * Package - Representation of a package
+ */
+/// Packages are equal if all their fields but the spans are, with the
+/// versions compared in order as [`Version`]s. The hash covers the
+/// category and name; as a map key, use [`Package::key`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Package {
+    pub category: String,
+    pub name: String,
+    pub description: String,
+    #[serde(deserialize_with = "crate::serialize::string_or_words")]
+    pub homepage: String,
+    pub licenses: String,
+    pub versions: Vec<Version>,
+    /// Position in the file, including the length prefix; only recorded
+    /// with [`OpenOptions::record_spans`](crate::OpenOptions::record_spans)
+    #[serde(skip)]
+    pub span: Option<Span>,
+}
+
+/// The fields of a package before its versions, read by
+/// [`PackageReader::read_summary`](crate::PackageReader::read_summary)
+/// without decoding the versions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageSummary {
+    pub category: String,
+    pub name: String,
+    pub description: String,
+    pub homepage: String,
+    pub licenses: String,
+    pub version_count: usize,
+}
+
+/*
+ * Version - A specific version of a package
+ */
+/// Serialized like the versions of a
+/// [`PackageView`](crate::serialize::PackageView) with default options,
+/// decoding dependencies read lazily.
+///
+/// Versions are equal if all their fields but the parts and the span
+/// are: the version string is authoritative, the parts are derived from
+/// it and may be missing, e.g. when read from JSON. Dependencies are
+/// compared decoded, whether read lazily or not. The hash covers the
+/// version string, slot and repository.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Version {
+    #[serde(rename = "version")]
+    pub version_string: String,
+    #[serde(default)]
+    pub parts: Vec<BasicPart>,
+    pub eapi: String,
+    pub mask_flags: u8,
+    pub properties_flags: u32,
+    pub restrict_flags: u64,
+    #[serde(deserialize_with = "crate::serialize::words_or_string")]
+    pub keywords: Vec<String>,
+    pub slot: String,
+    pub overlay_key: u64,
+    /// The label of the overlay, shared with
+    /// [`OverlayIdent::label`](crate::OverlayIdent::label) of the header
+    /// the version was read with
+    pub reponame: Arc<str>,
+    pub priority: i32,
+    #[serde(deserialize_with = "crate::serialize::words_or_string")]
+    pub iuse: Vec<String>,
+    pub required_use: Vec<String>,
+    /// The dependencies if decoded when read, see
+    /// [`depend()`](Version::depend) for those of both kinds
+    pub depend: Option<Depend>,
+    /// The dependencies if read with
+    /// [`OpenOptions::lazy_depend`](crate::OpenOptions::lazy_depend)
+    #[serde(skip)]
+    pub lazy_depend: Option<LazyDepend>,
+    pub src_uri: Option<String>,
+    /// Position in the file; only recorded with
+    /// [`OpenOptions::record_spans`](crate::OpenOptions::record_spans)
+    #[serde(skip)]
+    pub span: Option<Span>,
+}
+
+impl Version {
+    /// The dependencies, decoding them on the first call if they were
+    /// read lazily
+    pub fn depend(&self) -> Option<&Depend> {
+        self.depend
+            .as_ref()
+            .or_else(|| self.lazy_depend.as_ref().map(LazyDepend::get))
+    }
+
+    /// The overlay of the version in `header`, e.g. for its path; `None`
+    /// if the overlay key is not in the header
+    pub fn overlay<'a>(&self, header: &'a DBHeader) -> Option<&'a OverlayIdent> {
+        header.overlay(self.overlay_key)
+    }
+
+    /// The keywords joined with spaces in their original order, as in
+    /// the KEYWORDS of the ebuild
+    pub fn keywords_string(&self) -> String {
+        self.keywords.join(" ")
+    }
+
+    pub fn get_full_version(&self) -> String {
+        let mut s = String::new();
+        for part in &self.parts {
+            match part.part_type {
+                PartType::First | PartType::Character | PartType::Garbage => {
+                    s.push_str(&part.part_content);
+                }
+                PartType::Alpha => {
+                    s.push_str("_alpha");
+                    s.push_str(&part.part_content);
+                }
+                PartType::Beta => {
+                    s.push_str("_beta");
+                    s.push_str(&part.part_content);
+                }
+                PartType::Pre => {
+                    s.push_str("_pre");
+                    s.push_str(&part.part_content);
+                }
+                PartType::Rc => {
+                    s.push_str("_rc");
+                    s.push_str(&part.part_content);
+                }
+                PartType::Patch => {
+                    s.push_str("_p");
+                    s.push_str(&part.part_content);
+                }
+                PartType::Revision => {
+                    s.push_str("-r");
+                    s.push_str(&part.part_content);
+                }
+                PartType::InterRev | PartType::Primary => {
+                    s.push('.');
+                    s.push_str(&part.part_content);
+                }
+            }
+        }
+        s
+    }
+
+    /// Returns the slot without subslot; the empty slot is reported as "0"
+    pub fn normalized_slot(&self) -> &str {
+        split_slot(&self.slot).0
+    }
+
+    /// Returns the subslot, if any
+    pub fn subslot(&self) -> Option<&str> {
+        split_slot(&self.slot).1
+    }
+
+    /// True if the version comes from the main tree (overlay key 0)
+    pub fn in_main_tree(&self) -> bool {
+        self.overlay_key == 0
+    }
+}
+
+impl Package {
+    /// Returns the sorted set of (normalized) slots of all versions
+    pub fn slots(&self) -> BTreeSet<&str> {
+        self.versions.iter().map(|v| v.normalized_slot()).collect()
+    }
+
+    /// Returns the highest visible version, see [`Version::is_visible`]
+    #[cfg(feature = "std")]
+    pub fn best_version(&self, arch: Option<&str>, accept_unstable: bool) -> Option<&Version> {
+        self.versions
+            .iter()
+            .filter(|v| v.is_visible(arch, accept_unstable))
+            .max_by(|a, b| a.compare(b))
+    }
+
+    /// Returns the highest visible version for each normalized slot,
+    /// like eix displays one line per slot. Subslots do not split a slot.
+    #[cfg(feature = "std")]
+    pub fn best_per_slot(
+        &self,
+        arch: Option<&str>,
+        accept_unstable: bool,
+    ) -> BTreeMap<String, &Version> {
+        let mut best: BTreeMap<String, &Version> = BTreeMap::new();
+        for v in &self.versions {
+            if !v.is_visible(arch, accept_unstable) {
+                continue;
+            }
+            match best.get_mut(v.normalized_slot()) {
+                Some(current) => {
+                    if v.compare(current).is_ge() {
+                        *current = v;
+                    }
+                }
+                None => {
+                    best.insert(v.normalized_slot().to_string(), v);
+                }
+            }
+        }
+        best
+    }
+
+    /// Returns the versions provided by the overlay with the given key
+    pub fn versions_in_overlay(&self, key: u64) -> impl Iterator<Item = &Version> {
+        self.versions.iter().filter(move |v| v.overlay_key == key)
+    }
+
+    /// Returns the versions provided by the repository with the given label
+    pub fn versions_in_repo<'a>(&'a self, label: &'a str) -> impl Iterator<Item = &'a Version> {
+        self.versions.iter().filter(move |v| &*v.reponame == label)
+    }
+
+    /// Splits the homepage field into its URLs.
+    /// Whitespace escaped with a backslash does not split an URL.
+    pub fn homepages(&self) -> Vec<&str> {
+        let s = self.homepage.as_str();
+        let mut urls = Vec::new();
+        let mut start = None;
+        let mut escaped = false;
+        for (i, c) in s.char_indices() {
+            if c.is_whitespace() && !escaped {
+                if let Some(st) = start.take() {
+                    urls.push(&s[st..i]);
+                }
+            } else if start.is_none() {
+                start = Some(i);
+            }
+            escaped = c == '\\';
+        }
+        if let Some(st) = start {
+            urls.push(&s[st..]);
+        }
+        urls
+    }
+
+    /// Returns the homepage entries which do not look like URLs (for QA)
+    pub fn invalid_homepages(&self) -> Vec<&str> {
+        self.homepages()
+            .into_iter()
+            .filter(|url| !looks_like_url(url))
+            .collect()
+    }
+}
+
+/// Checks for `<scheme>://<something>`
+fn looks_like_url(s: &str) -> bool {
+    let Some((scheme, rest)) = s.split_once("://") else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    let valid_scheme = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid_scheme && !rest.is_empty()
+}
+
+/// Splits a SLOT value into slot and subslot.
+/// eix stores the slot "0" as "", so an empty slot is reported as "0".
+pub fn split_slot(slot: &str) -> (&str, Option<&str>) {
+    let (slot, subslot) = match slot.split_once('/') {
+        Some((slot, subslot)) => (slot, Some(subslot)),
+        None => (slot, None),
+    };
+    (if slot.is_empty() { "0" } else { slot }, subslot)
+}
+
+impl PartialEq for Package {
+    fn eq(&self, other: &Self) -> bool {
+        self.category == other.category
+            && self.name == other.name
+            && self.description == other.description
+            && self.homepage == other.homepage
+            && self.licenses == other.licenses
+            && self.versions == other.versions
+    }
+}
+
+impl Eq for Package {}
+
+impl Hash for Package {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.version_string == other.version_string
+            && self.eapi == other.eapi
+            && self.mask_flags == other.mask_flags
+            && self.properties_flags == other.properties_flags
+            && self.restrict_flags == other.restrict_flags
+            && self.keywords == other.keywords
+            && self.slot == other.slot
+            && self.overlay_key == other.overlay_key
+            && self.reponame == other.reponame
+            && self.priority == other.priority
+            && self.iuse == other.iuse
+            && self.required_use == other.required_use
+            && self.depend() == other.depend()
+            && self.src_uri == other.src_uri
+    }
+}
+
+impl Eq for Version {}
+
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.version_string.hash(state);
+        self.slot.hash(state);
+        self.reponame.hash(state);
+    }
+}
+
+/// Identifies a package by `category/name`, see [`Package::key`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackageKey<'a> {
+    pub category: &'a str,
+    pub name: &'a str,
+}
+
+/// Identifies a version by `category/name-version::repo`, see
+/// [`Package::version_key`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VersionKey<'a> {
+    pub category: &'a str,
+    pub name: &'a str,
+    pub version: &'a str,
+    pub reponame: &'a str,
+}
+
+impl Package {
+    /// The category and name, e.g. as a map key
+    pub fn key(&self) -> PackageKey<'_> {
+        PackageKey {
+            category: &self.category,
+            name: &self.name,
+        }
+    }
+
+    /// The category, name, version string and repository of the
+    /// version `v` of this package
+    pub fn version_key<'a>(&'a self, v: &'a Version) -> VersionKey<'a> {
+        VersionKey {
+            category: &self.category,
+            name: &self.name,
+            version: &v.version_string,
+            reponame: &v.reponame,
+        }
+    }
+}
+
+/*
+ * Depend - Dependencies of a package
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Depend {
+    pub depend: Vec<String>,
+    pub rdepend: Vec<String>,
+    pub pdepend: Vec<String>,
+    pub bdepend: Vec<String>,
+    pub idepend: Vec<String>,
+}
+
+/// The dependencies of a version kept as encoded in the file and decoded
+/// on first access, see
+/// [`OpenOptions::lazy_depend`](crate::OpenOptions::lazy_depend)
+#[derive(Clone)]
+pub struct LazyDepend {
+    // For every list a count followed by the indices of the words in
+    // `hash`, checked against it when read
+    raw: Vec<u8>,
+    // Whether BDEPEND and IDEPEND are stored, depending on the version
+    // of the file
+    bdepend: bool,
+    idepend: bool,
+    hash: Arc<Vec<String>>,
+    decoded: Lazy<Depend>,
+}
+
+impl LazyDepend {
+    #[cfg(feature = "std")]
+    pub(crate) fn new(raw: Vec<u8>, bdepend: bool, idepend: bool, hash: Arc<Vec<String>>) -> Self {
+        LazyDepend {
+            raw,
+            bdepend,
+            idepend,
+            hash,
+            decoded: Lazy::new(),
+        }
+    }
+
+    /// The dependencies, decoded on the first call
+    pub fn get(&self) -> &Depend {
+        self.decoded.get_or_init(|| {
+            let mut data = self.raw.as_slice();
+            let mut depend = Depend {
+                depend: self.words(&mut data),
+                rdepend: self.words(&mut data),
+                pdepend: self.words(&mut data),
+                ..Default::default()
+            };
+            if self.bdepend {
+                depend.bdepend = self.words(&mut data);
+            }
+            if self.idepend {
+                depend.idepend = self.words(&mut data);
+            }
+            depend
+        })
+    }
+
+    /// Whether [`get`](Self::get) was called
+    pub fn is_decoded(&self) -> bool {
+        self.decoded.get().is_some()
+    }
+
+    /// The size of the encoded lists in bytes
+    pub fn encoded_len(&self) -> usize {
+        self.raw.len()
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn raw(&self) -> &Vec<u8> {
+        &self.raw
+    }
+
+    /// The dependencies if already decoded
+    #[cfg(feature = "std")]
+    pub(crate) fn decoded(&self) -> Option<&Depend> {
+        self.decoded.get()
+    }
+
+    /// Decodes the next list from `data`
+    fn words(&self, data: &mut &[u8]) -> Vec<String> {
+        let mut next = || {
+            let (value, len) = codec::decode_num(data).ok()?;
+            *data = &data[len..];
+            usize::try_from(value).ok()
+        };
+        let count = next().unwrap_or(0);
+        let mut words = Vec::with_capacity(count);
+        for _ in 0..count {
+            if let Some(word) = next().and_then(|index| self.hash.get(index)) {
+                words.push(word.clone());
+            }
+        }
+        words
+    }
+}
+
+impl fmt::Debug for LazyDepend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyDepend")
+            .field("encoded_len", &self.raw.len())
+            .field("decoded", &self.decoded.get())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_type_from_u64() {
+        assert_eq!(PartType::from_u64(1), PartType::Alpha);
+        assert_eq!(PartType::from_u64(5), PartType::Revision);
+        assert_eq!(PartType::from_u64(10), PartType::First);
+        assert_eq!(PartType::from_u64(0), PartType::Garbage);
+        assert_eq!(PartType::from_u64(99), PartType::Garbage);
+    }
+
+    #[test]
+    fn test_version_full_string() {
+        let v = Version {
+            version_string: "1.2.3".to_string(),
+            parts: vec![
+                BasicPart {
+                    part_type: PartType::First,
+                    part_content: "1".to_string(),
+                },
+                BasicPart {
+                    part_type: PartType::Primary,
+                    part_content: "2".to_string(),
+                },
+                BasicPart {
+                    part_type: PartType::Primary,
+                    part_content: "3".to_string(),
+                },
+                BasicPart {
+                    part_type: PartType::Alpha,
+                    part_content: "1".to_string(),
+                },
+                BasicPart {
+                    part_type: PartType::Revision,
+                    part_content: "1".to_string(),
+                },
+            ],
+            eapi: "8".to_string(),
+            mask_flags: 0,
+            properties_flags: 0,
+            restrict_flags: 0,
+            keywords: vec![],
+            slot: "0".to_string(),
+            overlay_key: 0,
+            reponame: "gentoo".into(),
+            priority: 0,
+            iuse: vec![],
+            required_use: vec![],
+            depend: None,
+            lazy_depend: None,
+            src_uri: None,
+            span: None,
+        };
+        assert_eq!(v.get_full_version(), "1.2.3_alpha1-r1");
+    }
+
+    #[test]
+    fn test_part_type_deserialize() {
+        let from = |json: &str| serde_json::from_str::<PartType>(json);
+        assert_eq!(from("1").unwrap(), PartType::Alpha);
+        assert_eq!(from("\"InterRev\"").unwrap(), PartType::InterRev);
+        assert_eq!(from("0").unwrap(), PartType::Garbage);
+        assert!(from("11").is_err());
+        assert!(from("\"alpha\"").is_err());
+        for n in 0..=10 {
+            let part_type = PartType::from_u64(n);
+            let json = serde_json::to_string(&part_type).unwrap();
+            assert_eq!(from(&json).unwrap(), part_type);
+        }
+
+        let part: BasicPart =
+            serde_json::from_str(r#"{"part_type": "Patch", "part_content": "2"}"#).unwrap();
+        assert_eq!(part.part_type, PartType::Patch);
+        assert_eq!(part.part_content, "2");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_version_parts_round_trip() {
+        let v = Version {
+            version_string: "1.2.3_alpha1-r1".to_string(),
+            parts: crate::parse_version("1.2.3_alpha1-r1").unwrap(),
+            reponame: "gentoo".into(),
+            ..Default::default()
+        };
+        let mut json = serde_json::to_value(&v).unwrap();
+        json["parts"] = serde_json::to_value(&v.parts).unwrap();
+        let copy: Version = serde_json::from_value(json).unwrap();
+        assert!(crate::compare::versions_identical(&copy, &v));
+        assert_eq!(copy.parts, v.parts);
+        assert_eq!(copy.get_full_version(), v.get_full_version());
+
+        // Without parts, as serialized, the parts stay empty
+        let copy: Version = serde_json::from_value(serde_json::to_value(&v).unwrap()).unwrap();
+        assert!(copy.parts.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_equality_and_keys() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let v = Version {
+            version_string: "1.2.3".to_string(),
+            parts: crate::parse_version("1.2.3").unwrap(),
+            slot: "1".to_string(),
+            reponame: "gentoo".into(),
+            depend: Some(Depend {
+                rdepend: vec!["dev-libs/foo".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        // The parts are derived from the version string and not compared
+        let without_parts = Version {
+            parts: Vec::new(),
+            ..v.clone()
+        };
+        assert_eq!(v, without_parts);
+        let mut other = v.clone();
+        other.keywords.push("~amd64".to_string());
+        assert_ne!(v, other);
+        other = v.clone();
+        other.depend = None;
+        assert_ne!(v, other);
+
+        let package = Package {
+            category: "app-misc".to_string(),
+            name: "foo".to_string(),
+            versions: vec![v.clone()],
+            ..Default::default()
+        };
+        let mut copy = package.clone();
+        copy.span = Some(Span { start: 0, end: 1 });
+        assert_eq!(package, copy);
+        copy.description = "Foo".to_string();
+        assert_ne!(package, copy);
+
+        // Equal values have equal hashes
+        let state = RandomState::new();
+        assert_eq!(state.hash_one(&package), state.hash_one(package.clone()));
+        assert_eq!(state.hash_one(&v), state.hash_one(&without_parts));
+
+        assert_eq!(
+            package.key(),
+            PackageKey {
+                category: "app-misc",
+                name: "foo"
+            }
+        );
+        assert_eq!(package.key(), copy.key());
+        let key = package.version_key(&v);
+        assert_eq!(key.version, "1.2.3");
+        assert_eq!(key.reponame, "gentoo");
+        let mut guru = v.clone();
+        guru.reponame = "guru".into();
+        assert_ne!(key, package.version_key(&guru));
+        assert!(key < package.version_key(&guru));
+    }
+
+    fn simple_version(version: &str, slot: &str, keywords: &[&str]) -> Version {
+        let parts = version
+            .split('.')
+            .enumerate()
+            .map(|(i, p)| BasicPart {
+                part_type: if i == 0 {
+                    PartType::First
+                } else {
+                    PartType::Primary
+                },
+                part_content: p.to_string(),
+            })
+            .collect();
+        Version {
+            version_string: version.to_string(),
+            parts,
+            slot: slot.to_string(),
+            keywords: keywords.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_flag_names() {
+        let names: Vec<&str> = flag_names(MASK_HARD | MASK_WORLD, MASK_FLAG_NAMES).collect();
+        assert_eq!(names, ["package", "profile", "world"]);
+        let names: Vec<&str> =
+            flag_names(RESTRICT_FETCH | RESTRICT_MIRROR, RESTRICT_FLAG_NAMES).collect();
+        assert_eq!(names, ["fetch", "mirror"]);
+        assert_eq!(
+            flag_names(PROPERTIES_NONE, PROPERTIES_FLAG_NAMES).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_normalized_slot() {
+        assert_eq!(simple_version("1", "", &[]).normalized_slot(), "0");
+        assert_eq!(simple_version("1", "/2", &[]).normalized_slot(), "0");
+        assert_eq!(simple_version("1", "6/6.21", &[]).normalized_slot(), "6");
+        assert_eq!(simple_version("1", "3.12", &[]).normalized_slot(), "3.12");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_best_per_slot() {
+        let pkg = Package {
+            category: "dev-lang".to_string(),
+            name: "lua".to_string(),
+            versions: vec![
+                simple_version("5.1.5", "5.1", &["amd64"]),
+                simple_version("5.3.6", "5.3/5.3.6", &["amd64"]),
+                simple_version("5.3.10", "5.3/5.3.10", &["~amd64"]),
+                simple_version("5.4.6", "5.4", &["~amd64"]),
+            ],
+            ..Default::default()
+        };
+
+        let slots: Vec<&str> = pkg.slots().into_iter().collect();
+        assert_eq!(slots, vec!["5.1", "5.3", "5.4"]);
+
+        let stable = pkg.best_per_slot(Some("amd64"), false);
+        assert_eq!(stable.len(), 2);
+        assert_eq!(stable["5.1"].version_string, "5.1.5");
+        assert_eq!(stable["5.3"].version_string, "5.3.6");
+
+        let testing = pkg.best_per_slot(Some("amd64"), true);
+        assert_eq!(testing.len(), 3);
+        assert_eq!(testing["5.3"].version_string, "5.3.10");
+        assert_eq!(testing["5.4"].version_string, "5.4.6");
+
+        let best = pkg.best_version(Some("amd64"), false).unwrap();
+        assert_eq!(best.version_string, "5.3.6");
+    }
+
+    fn homepage_package(homepage: &str) -> Package {
+        Package {
+            homepage: homepage.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_homepages() {
+        assert!(homepage_package("").homepages().is_empty());
+        assert_eq!(
+            homepage_package("https://a.org/").homepages(),
+            vec!["https://a.org/"]
+        );
+        assert_eq!(
+            homepage_package(" https://a.org/\thttps://b.org/  ").homepages(),
+            vec!["https://a.org/", "https://b.org/"]
+        );
+        assert_eq!(
+            homepage_package("https://a.org/my\\ page https://b.org/").homepages(),
+            vec!["https://a.org/my\\ page", "https://b.org/"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_homepages() {
+        let pkg = homepage_package("https://a.org/ www.b.org ftp://c.org/ http:// 1http://d.org/");
+        assert_eq!(
+            pkg.invalid_homepages(),
+            vec!["www.b.org", "http://", "1http://d.org/"]
+        );
+    }
+}
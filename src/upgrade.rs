@@ -0,0 +1,250 @@
+//! Comparison of installed versions with the database
+//!
+//! For every installed version the best visible version of the same
+//! slot is looked up, like `eix -u` does. Installed packages missing
+//! from the database are listed by [`orphaned_installed`].
+
+use crate::{AcceptKeywords, InstalledIndex, InstalledVersion, Package, compare_parts};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// An installed version together with the best visible version of its slot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Upgrade {
+    /// `category/name`
+    pub atom: String,
+    /// The installed version as `category/name-version`
+    pub cpv: String,
+    pub installed: String,
+    pub candidate: String,
+    /// Normalized slot of the installed version
+    pub slot: String,
+}
+
+/// Result of [`update_report`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateReport {
+    /// The best visible version is newer than the installed one
+    pub upgrades: Vec<Upgrade>,
+    /// The installed version is newer than the best visible one
+    pub downgrades: Vec<Upgrade>,
+    /// Installed packages which are not in the database anymore
+    pub removed: Vec<InstalledVersion>,
+}
+
+impl UpdateReport {
+    pub fn is_empty(&self) -> bool {
+        self.upgrades.is_empty() && self.downgrades.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Compares all installed versions with the best visible version of
+/// their slot. Slots without any visible version are not reported.
+pub fn update_report(
+    packages: &[Package],
+    installed: &InstalledIndex,
+    accept: &AcceptKeywords,
+) -> UpdateReport {
+    let by_atom: HashMap<(&str, &str), &Package> = packages
+        .iter()
+        .map(|p| ((p.category.as_str(), p.name.as_str()), p))
+        .collect();
+    let mut report = UpdateReport::default();
+
+    for inst in installed.iter() {
+        let Some(pkg) = by_atom.get(&(inst.category.as_str(), inst.name.as_str())) else {
+            report.removed.push(inst.clone());
+            continue;
+        };
+        let best = accept.best_per_slot(pkg);
+        let Some(candidate) = best.get(inst.normalized_slot()) else {
+            continue;
+        };
+        let entry = || Upgrade {
+            atom: format!("{}/{}", inst.category, inst.name),
+            cpv: inst.cpv(),
+            installed: inst.version.clone(),
+            candidate: candidate.version_string.clone(),
+            slot: inst.normalized_slot().to_string(),
+        };
+        match compare_parts(&candidate.parts, &inst.parts) {
+            Ordering::Greater => report.upgrades.push(entry()),
+            Ordering::Less => report.downgrades.push(entry()),
+            Ordering::Equal => {}
+        }
+    }
+    report
+}
+
+/// Lists installed versions for which a newer version is visible in
+/// the same slot
+pub fn upgrades(
+    packages: &[Package],
+    installed: &InstalledIndex,
+    accept: &AcceptKeywords,
+) -> Vec<Upgrade> {
+    update_report(packages, installed, accept).upgrades
+}
+
+/// Lists installed versions which are newer than the best visible
+/// version of their slot, e.g. after a version was masked or removed
+pub fn downgrades(
+    packages: &[Package],
+    installed: &InstalledIndex,
+    accept: &AcceptKeywords,
+) -> Vec<Upgrade> {
+    update_report(packages, installed, accept).downgrades
+}
+
+/// An installed version of a package which is not in the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Orphaned {
+    /// `category/name`
+    pub atom: String,
+    /// `category/name-version`
+    pub cpv: String,
+    /// Normalized slot of the installed version
+    pub slot: String,
+    /// The repository it was installed from, if recorded; the package
+    /// may still exist there if the overlay is not in the database
+    pub repository: Option<String>,
+}
+
+/// Lists the installed versions of packages which are not in the
+/// database anymore, like [`UpdateReport::removed`]
+pub fn orphaned_installed(packages: &[Package], installed: &InstalledIndex) -> Vec<Orphaned> {
+    let atoms: HashSet<(&str, &str)> = packages
+        .iter()
+        .map(|p| (p.category.as_str(), p.name.as_str()))
+        .collect();
+    installed
+        .iter()
+        .filter(|inst| !atoms.contains(&(inst.category.as_str(), inst.name.as_str())))
+        .map(|inst| Orphaned {
+            atom: format!("{}/{}", inst.category, inst.name),
+            cpv: inst.cpv(),
+            slot: inst.normalized_slot().to_string(),
+            repository: inst.repository.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Atom, Version, parse_version};
+
+    fn version(v: &str, slot: &str, keywords: &[&str]) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            slot: slot.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn packages() -> Vec<Package> {
+        vec![Package {
+            category: "dev-libs".to_string(),
+            name: "foo".to_string(),
+            versions: vec![
+                version("1.0", "1", &["amd64"]),
+                version("1.1", "1", &["~amd64"]),
+                version("2.0", "2/2.0", &["amd64"]),
+                version("2.1", "2/2.1", &["amd64"]),
+            ],
+            ..Default::default()
+        }]
+    }
+
+    fn installed(entries: &[(&str, &str, &str)]) -> InstalledIndex {
+        let mut index = InstalledIndex::new();
+        for (cp, version, slot) in entries {
+            let (category, name) = cp.split_once('/').unwrap();
+            index.insert(InstalledVersion::new(category, name, version, slot, None).unwrap());
+        }
+        index
+    }
+
+    #[test]
+    fn test_upgrades() {
+        let index = installed(&[
+            ("dev-libs/foo", "1.0", "1"),
+            ("dev-libs/foo", "2.0", "2/2.0"),
+            ("dev-libs/gone", "0.1", "0"),
+        ]);
+        let stable = AcceptKeywords::parse("amd64");
+        assert_eq!(
+            upgrades(&packages(), &index, &stable),
+            vec![Upgrade {
+                atom: "dev-libs/foo".to_string(),
+                cpv: "dev-libs/foo-2.0".to_string(),
+                installed: "2.0".to_string(),
+                candidate: "2.1".to_string(),
+                slot: "2".to_string(),
+            }]
+        );
+
+        let testing = AcceptKeywords::parse("amd64 ~amd64");
+        let report = update_report(&packages(), &index, &testing);
+        assert_eq!(report.upgrades.len(), 2);
+        assert_eq!(report.upgrades[0].candidate, "1.1");
+        assert!(report.downgrades.is_empty());
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].cpv(), "dev-libs/gone-0.1");
+    }
+
+    #[test]
+    fn test_downgrades() {
+        let index = installed(&[("dev-libs/foo", "1.1", "1"), ("dev-libs/foo", "3.0", "3")]);
+        let stable = AcceptKeywords::parse("amd64");
+        let report = update_report(&packages(), &index, &stable);
+        assert!(report.upgrades.is_empty());
+        // Slot 3 has no visible version at all
+        assert_eq!(report.downgrades.len(), 1);
+        assert_eq!(report.downgrades[0].installed, "1.1");
+        assert_eq!(report.downgrades[0].candidate, "1.0");
+
+        assert_eq!(downgrades(&packages(), &index, &stable), report.downgrades);
+        assert_eq!(report.downgrades[0].cpv, "dev-libs/foo-1.1");
+
+        let mut accept = AcceptKeywords::parse("amd64");
+        accept
+            .package_keywords
+            .push((Atom::parse("dev-libs/foo:1").unwrap(), Vec::new()));
+        assert!(update_report(&packages(), &index, &accept).is_empty());
+    }
+
+    #[test]
+    fn test_orphaned_installed() {
+        let mut index = installed(&[
+            ("dev-libs/foo", "9.9", "9"),
+            ("dev-libs/gone", "0.1", "0/1"),
+        ]);
+        index.insert(
+            InstalledVersion::new("app-misc", "local", "1.0", "0", Some("my-overlay")).unwrap(),
+        );
+        let orphaned = orphaned_installed(&packages(), &index);
+        assert_eq!(
+            orphaned,
+            vec![
+                Orphaned {
+                    atom: "app-misc/local".to_string(),
+                    cpv: "app-misc/local-1.0".to_string(),
+                    slot: "0".to_string(),
+                    repository: Some("my-overlay".to_string()),
+                },
+                Orphaned {
+                    atom: "dev-libs/gone".to_string(),
+                    cpv: "dev-libs/gone-0.1".to_string(),
+                    slot: "0".to_string(),
+                    repository: None,
+                },
+            ]
+        );
+        let removed = update_report(&packages(), &index, &AcceptKeywords::parse("amd64")).removed;
+        assert_eq!(removed.len(), orphaned.len());
+    }
+}
@@ -0,0 +1,535 @@
+//! Structural validation of a whole database file
+//!
+//! [`validate`] walks every byte of a file without building packages
+//! and collects all problems it finds, each with the offset of the
+//! offending field, instead of stopping at the first error like the
+//! readers do:
+//!
+//! ```
+//! use eix::{EixTestBuilder, ValidateOptions, validate_bytes};
+//!
+//! let bytes = EixTestBuilder::new()
+//!     .category("app-misc")
+//!     .package("screen", |p| p.version("4.9.1", |v| v))
+//!     .build_bytes();
+//! assert!(validate_bytes(&bytes, &ValidateOptions::default()).is_clean());
+//! ```
+//!
+//! A damaged package is skipped using its length prefix, so findings in
+//! later packages are still reported. Problems which leave no way to
+//! find the next field, like a truncated file, end the walk.
+
+use crate::codec::{self, decode_num, part_header};
+use crate::{
+    DB_MAGIC, DB_VERSION_CURRENT, DB_VERSION_NUMERIC_PROPERTIES, DBVersion, ParseLimits,
+    SAVE_BITMASK_DEP,
+};
+use crate::{SAVE_BITMASK_REQUIRED_USE, SAVE_BITMASK_SRC_URI};
+use serde::Serialize;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Options of [`validate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidateOptions {
+    /// Oldest accepted database version; newer versions than
+    /// [`DB_VERSION_CURRENT`] are always reported
+    pub min_version: DBVersion,
+    /// Bounds on declared lengths and counts, as for reading
+    pub limits: ParseLimits,
+    /// The walk ends after this many findings
+    pub max_findings: usize,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        ValidateOptions {
+            min_version: 0,
+            limits: ParseLimits::default(),
+            max_findings: 1000,
+        }
+    }
+}
+
+/// What is wrong at a [`Finding`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FindingKind {
+    InvalidMagic,
+    /// Older than [`ValidateOptions::min_version`] or newer than
+    /// [`DB_VERSION_CURRENT`]
+    UnsupportedVersion {
+        version: u64,
+    },
+    /// The file ends inside the field, or before all declared
+    /// categories and packages
+    UnexpectedEof,
+    /// More than 64 bits
+    NumberTooLarge,
+    /// A length or count above [`ValidateOptions::limits`]
+    LimitExceeded {
+        value: u64,
+        limit: u64,
+    },
+    /// A length prefix differs from the bytes of the record
+    LengthMismatch {
+        declared: u64,
+        consumed: u64,
+    },
+    HashIndexOutOfRange {
+        hash: &'static str,
+        index: u64,
+        len: usize,
+    },
+    OverlayKeyOutOfRange {
+        key: u64,
+        overlays: usize,
+    },
+    InvalidUtf8,
+    /// Bytes after the last declared category
+    TrailingBytes {
+        len: u64,
+    },
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FindingKind::InvalidMagic => write!(f, "invalid magic"),
+            FindingKind::UnsupportedVersion { version } => {
+                write!(f, "unsupported database version {}", version)
+            }
+            FindingKind::UnexpectedEof => write!(f, "unexpected end of file"),
+            FindingKind::NumberTooLarge => write!(f, "number too large"),
+            FindingKind::LimitExceeded { value, limit } => {
+                write!(f, "{} exceeds the limit of {}", value, limit)
+            }
+            FindingKind::LengthMismatch { declared, consumed } => write!(
+                f,
+                "length {} does not match {} bytes read",
+                declared, consumed
+            ),
+            FindingKind::HashIndexOutOfRange { hash, index, len } => write!(
+                f,
+                "index {} out of range of the {} hash with {} entries",
+                index, hash, len
+            ),
+            FindingKind::OverlayKeyOutOfRange { key, overlays } => {
+                write!(
+                    f,
+                    "overlay key {} out of range of {} overlays",
+                    key, overlays
+                )
+            }
+            FindingKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            FindingKind::TrailingBytes { len } => write!(f, "{} trailing bytes", len),
+        }
+    }
+}
+
+/// A problem found by [`validate`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// Offset of the field in the file
+    pub offset: u64,
+    /// The field, e.g. `app-misc/screen version 1: keywords`
+    pub location: String,
+    #[serde(flatten)]
+    pub kind: FindingKind,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}: {}: {}", self.offset, self.location, self.kind)
+    }
+}
+
+/// Result of [`validate`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub file_size: u64,
+    /// Version of the file if the header could be read that far
+    pub version: Option<u64>,
+    /// Categories, packages and versions walked
+    pub categories: u64,
+    pub packages: u64,
+    pub versions: u64,
+    pub findings: Vec<Finding>,
+    /// Whether the walk ended at [`ValidateOptions::max_findings`]
+    pub truncated: bool,
+}
+
+impl ValidationReport {
+    /// Whether nothing was found
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/// Checks the database at `path` completely, see the
+/// [module documentation](self)
+#[cfg(feature = "fs")]
+pub fn validate<P: AsRef<Path>>(
+    path: P,
+    options: &ValidateOptions,
+) -> io::Result<ValidationReport> {
+    Ok(validate_bytes(&fs::read(path)?, options))
+}
+
+/// Like [`validate`] for a database in memory
+pub fn validate_bytes(data: &[u8], options: &ValidateOptions) -> ValidationReport {
+    let mut walker = Walker {
+        data,
+        pos: 0,
+        options,
+        path: String::new(),
+        report: ValidationReport {
+            file_size: data.len() as u64,
+            version: None,
+            categories: 0,
+            packages: 0,
+            versions: 0,
+            findings: Vec::new(),
+            truncated: false,
+        },
+    };
+    // A fatal finding is already recorded
+    let _ = walker.walk();
+    walker.report
+}
+
+/// The walk cannot continue
+struct Stop;
+
+type Step<T> = Result<T, Stop>;
+
+/// The header fields needed to walk the packages
+struct Layout {
+    version: u64,
+    categories: u64,
+    overlays: usize,
+    eapi_hash: usize,
+    license_hash: usize,
+    keywords_hash: usize,
+    iuse_hash: usize,
+    slot_hash: usize,
+    depend_hash: usize,
+    use_depend: bool,
+    use_required_use: bool,
+    use_src_uri: bool,
+}
+
+struct Walker<'a> {
+    data: &'a [u8],
+    pos: usize,
+    options: &'a ValidateOptions,
+    /// The record being walked, e.g. `app-misc/screen version 1`
+    path: String,
+    report: ValidationReport,
+}
+
+impl Walker<'_> {
+    /// Records a finding in `field` of the current record, stopping
+    /// once there are enough
+    fn find(&mut self, offset: usize, field: &str, kind: FindingKind) -> Step<()> {
+        let location = if field.is_empty() {
+            self.path.clone()
+        } else {
+            format!("{}: {}", self.path, field)
+        };
+        self.report.findings.push(Finding {
+            offset: offset as u64,
+            location,
+            kind,
+        });
+        if self.report.findings.len() >= self.options.max_findings {
+            self.report.truncated = true;
+            return Err(Stop);
+        }
+        Ok(())
+    }
+
+    /// Records a finding which ends the walk
+    fn fatal<T>(&mut self, offset: usize, field: &str, kind: FindingKind) -> Step<T> {
+        self.find(offset, field, kind)?;
+        Err(Stop)
+    }
+
+    fn num(&mut self, field: &str) -> Step<u64> {
+        match decode_num(&self.data[self.pos..]) {
+            Ok((value, len)) => {
+                self.pos += len;
+                Ok(value)
+            }
+            Err(codec::Error::NumberTooLarge) => {
+                self.fatal(self.pos, field, FindingKind::NumberTooLarge)
+            }
+            Err(_) => self.fatal(self.pos, field, FindingKind::UnexpectedEof),
+        }
+    }
+
+    fn byte(&mut self, field: &str) -> Step<u8> {
+        match self.data.get(self.pos) {
+            Some(&byte) => {
+                self.pos += 1;
+                Ok(byte)
+            }
+            None => self.fatal(self.pos, field, FindingKind::UnexpectedEof),
+        }
+    }
+
+    /// A count of at most `limit`
+    fn count(&mut self, limit: u64, field: &str) -> Step<u64> {
+        let start = self.pos;
+        let value = self.num(field)?;
+        if value > limit {
+            return self.fatal(start, field, FindingKind::LimitExceeded { value, limit });
+        }
+        Ok(value)
+    }
+
+    /// `len` bytes of text following a length prefix at `start`
+    fn text(&mut self, start: usize, len: u64, field: &str) -> Step<String> {
+        let limit = self.options.limits.max_string_len;
+        if len > limit {
+            self.find(
+                start,
+                field,
+                FindingKind::LimitExceeded { value: len, limit },
+            )?;
+        }
+        let content = usize::try_from(len)
+            .ok()
+            .and_then(|len| self.data.get(self.pos..self.pos.checked_add(len)?));
+        let Some(content) = content else {
+            return self.fatal(start, field, FindingKind::UnexpectedEof);
+        };
+        self.pos += content.len();
+        match std::str::from_utf8(content) {
+            Ok(s) => Ok(s.to_string()),
+            Err(_) => {
+                let lossy = String::from_utf8_lossy(content).into_owned();
+                self.find(start, field, FindingKind::InvalidUtf8)?;
+                Ok(lossy)
+            }
+        }
+    }
+
+    fn string(&mut self, field: &str) -> Step<String> {
+        let start = self.pos;
+        let len = self.num(field)?;
+        self.text(start, len, field)
+    }
+
+    /// A list of strings, returning its length
+    fn strings(&mut self, limit: u64, field: &str) -> Step<usize> {
+        let count = self.count(limit, field)?;
+        for _ in 0..count {
+            self.string(field)?;
+        }
+        Ok(count as usize)
+    }
+
+    fn hash_index(&mut self, hash: &'static str, len: usize, field: &str) -> Step<()> {
+        let start = self.pos;
+        let index = self.num(field)?;
+        if !usize::try_from(index).is_ok_and(|index| index < len) {
+            let kind = FindingKind::HashIndexOutOfRange { hash, index, len };
+            self.find(start, field, kind)?;
+        }
+        Ok(())
+    }
+
+    fn hash_words(&mut self, hash: &'static str, len: usize, field: &str) -> Step<()> {
+        let count = self.count(self.options.limits.max_list_len, field)?;
+        for _ in 0..count {
+            self.hash_index(hash, len, field)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the record starting at `start` took `declared` bytes
+    fn check_length(&mut self, start: usize, declared: u64, field: &str) -> Step<()> {
+        let consumed = (self.pos - start) as u64;
+        if consumed != declared {
+            let kind = FindingKind::LengthMismatch { declared, consumed };
+            self.find(start, field, kind)?;
+        }
+        Ok(())
+    }
+
+    fn walk(&mut self) -> Step<()> {
+        let layout = self.header()?;
+        for i in 0..layout.categories {
+            self.path = format!("category {} of {}", i + 1, layout.categories);
+            let category = self.string("name")?;
+            self.path = format!("category {}", category);
+            let packages = self.num("package count")?;
+            for j in 0..packages {
+                self.path = format!("category {}, package {} of {}", category, j + 1, packages);
+                self.package(&layout, &category)?;
+            }
+            self.report.categories += 1;
+        }
+        if self.pos < self.data.len() {
+            self.path = "end".to_string();
+            let len = (self.data.len() - self.pos) as u64;
+            self.find(self.pos, "", FindingKind::TrailingBytes { len })?;
+        }
+        Ok(())
+    }
+
+    fn header(&mut self) -> Step<Layout> {
+        self.path = "header".to_string();
+        if !self.data.starts_with(DB_MAGIC) {
+            return self.fatal(0, "magic", FindingKind::InvalidMagic);
+        }
+        self.pos = DB_MAGIC.len();
+        let start = self.pos;
+        let version = self.num("version")?;
+        self.report.version = Some(version);
+        if version < self.options.min_version as u64 || version > DB_VERSION_CURRENT as u64 {
+            // The layout of the rest is unknown
+            return self.fatal(
+                start,
+                "version",
+                FindingKind::UnsupportedVersion { version },
+            );
+        }
+        let categories = self.num("category count")?;
+        let limits = self.options.limits;
+        let overlays = self.count(limits.max_list_len, "overlays")?;
+        for _ in 0..overlays {
+            self.string("overlay path")?;
+            self.string("overlay label")?;
+        }
+        let eapi_hash = self.strings(limits.max_hash_entries, "EAPI hash")?;
+        let license_hash = self.strings(limits.max_hash_entries, "license hash")?;
+        let keywords_hash = self.strings(limits.max_hash_entries, "keywords hash")?;
+        let iuse_hash = self.strings(limits.max_hash_entries, "IUSE hash")?;
+        let slot_hash = self.strings(limits.max_hash_entries, "slot hash")?;
+        self.strings(limits.max_list_len, "world sets")?;
+        let bitmask = self.num("save bitmask")?;
+        let use_depend = bitmask & SAVE_BITMASK_DEP as u64 != 0;
+        let mut depend_hash = 0;
+        if use_depend {
+            let declared = self.num("depend hash length")?;
+            let start = self.pos;
+            depend_hash = self.strings(limits.max_hash_entries, "depend hash")?;
+            self.check_length(start, declared, "depend hash")?;
+        }
+        Ok(Layout {
+            version,
+            categories,
+            overlays: overlays as usize,
+            eapi_hash,
+            license_hash,
+            keywords_hash,
+            iuse_hash,
+            slot_hash,
+            depend_hash,
+            use_depend,
+            use_required_use: bitmask & SAVE_BITMASK_REQUIRED_USE as u64 != 0,
+            use_src_uri: bitmask & SAVE_BITMASK_SRC_URI as u64 != 0,
+        })
+    }
+
+    /// Walks a package including its length prefix. After an error
+    /// inside the package, the walk goes on behind it.
+    fn package(&mut self, layout: &Layout, category: &str) -> Step<()> {
+        let declared = self.num("package length")?;
+        let start = self.pos;
+        let end = usize::try_from(declared)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .filter(|&end| end <= self.data.len());
+        match self.package_content(layout, category) {
+            Ok(()) => self.check_length(start, declared, "package length")?,
+            Err(Stop) if !self.report.truncated && end.is_some() => {
+                self.pos = end.unwrap_or(start);
+            }
+            Err(stop) => return Err(stop),
+        }
+        self.report.packages += 1;
+        Ok(())
+    }
+
+    fn package_content(&mut self, layout: &Layout, category: &str) -> Step<()> {
+        let name = self.string("name")?;
+        self.path = format!("{}/{}", category, name);
+        self.string("description")?;
+        self.string("homepage")?;
+        self.hash_index("license", layout.license_hash, "license")?;
+        let versions = self.count(self.options.limits.max_list_len, "version count")?;
+        for i in 0..versions {
+            self.path = format!("{}/{} version {}", category, name, i + 1);
+            self.version(layout)?;
+            self.report.versions += 1;
+        }
+        self.path = format!("{}/{}", category, name);
+        Ok(())
+    }
+
+    fn version(&mut self, layout: &Layout) -> Step<()> {
+        let limits = self.options.limits;
+        if layout.version >= 36 {
+            self.hash_index("eapi", layout.eapi_hash, "EAPI")?;
+        }
+        self.byte("mask flags")?;
+        if layout.version >= DB_VERSION_NUMERIC_PROPERTIES as u64 {
+            let start = self.pos;
+            if u32::try_from(self.num("properties flags")?).is_err() {
+                self.find(start, "properties flags", FindingKind::NumberTooLarge)?;
+            }
+        } else {
+            self.byte("properties flags")?;
+        }
+        self.num("restrict flags")?;
+        self.hash_words("keywords", layout.keywords_hash, "keywords")?;
+        let parts = self.count(limits.max_list_len, "version parts")?;
+        for _ in 0..parts {
+            let start = self.pos;
+            let (_, len) = part_header(self.num("version part")?);
+            self.text(start, len, "version part")?;
+        }
+        self.hash_index("slot", layout.slot_hash, "slot")?;
+        let start = self.pos;
+        let key = self.num("overlay key")?;
+        if !usize::try_from(key).is_ok_and(|key| key < layout.overlays) {
+            let overlays = layout.overlays;
+            self.find(
+                start,
+                "overlay key",
+                FindingKind::OverlayKeyOutOfRange { key, overlays },
+            )?;
+        }
+        self.hash_words("iuse", layout.iuse_hash, "IUSE")?;
+        if layout.use_required_use {
+            self.hash_words("iuse", layout.iuse_hash, "REQUIRED_USE")?;
+        }
+        if layout.use_depend {
+            let declared = self.num("depend length")?;
+            let start = self.pos;
+            let mut fields = vec!["DEPEND", "RDEPEND", "PDEPEND"];
+            if layout.version > 31 {
+                fields.push("BDEPEND");
+            }
+            if layout.version > 38 {
+                fields.push("IDEPEND");
+            }
+            for field in fields {
+                self.hash_words("depend", layout.depend_hash, field)?;
+            }
+            self.check_length(start, declared, "depend length")?;
+        }
+        if layout.use_src_uri {
+            self.string("SRC_URI")?;
+        }
+        Ok(())
+    }
+}
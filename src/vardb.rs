@@ -0,0 +1,298 @@
+//! Installed packages
+//!
+//! The eix database does not know what is installed. Portage records
+//! installed packages in `/var/db/pkg/<category>/<name>-<version>/`,
+//! with the SLOT and the source repository in files of that directory.
+
+#[cfg(feature = "fs")]
+use crate::atom::parse_cpv;
+use crate::basicversion::{compare_parts, parse_version};
+use crate::{Atom, BasicPart, Package, Version, split_slot};
+use serde::Serialize;
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Default location of the installed package database
+pub const VARDB_PATH: &str = "/var/db/pkg";
+
+/// An installed version of a package
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledVersion {
+    pub category: String,
+    pub name: String,
+    pub version: String,
+    #[serde(skip)]
+    pub parts: Vec<BasicPart>,
+    /// Content of the SLOT file (empty if missing)
+    pub slot: String,
+    /// Content of the repository file, if present
+    pub repository: Option<String>,
+}
+
+impl InstalledVersion {
+    pub fn new(
+        category: &str,
+        name: &str,
+        version: &str,
+        slot: &str,
+        repository: Option<&str>,
+    ) -> io::Result<Self> {
+        Ok(InstalledVersion {
+            category: category.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+            parts: parse_version(version)?,
+            slot: slot.to_string(),
+            repository: repository.map(|r| r.to_string()),
+        })
+    }
+
+    /// Returns `category/name-version`
+    pub fn cpv(&self) -> String {
+        format!("{}/{}-{}", self.category, self.name, self.version)
+    }
+
+    /// Returns the slot without subslot, "0" if empty
+    pub fn normalized_slot(&self) -> &str {
+        split_slot(&self.slot).0
+    }
+
+    /// Checks whether this is the installed instance of `version`.
+    /// If the repository is known, it has to match as well.
+    pub fn is_version(&self, version: &Version) -> bool {
+        compare_parts(&self.parts, &version.parts).is_eq()
+            && self
+                .repository
+                .as_deref()
+                .is_none_or(|r| r == &*version.reponame)
+    }
+}
+
+/// Index of installed versions by category and name
+#[derive(Debug, Clone, Default)]
+pub struct InstalledIndex {
+    packages: BTreeMap<(String, String), Vec<InstalledVersion>>,
+}
+
+#[cfg(feature = "fs")]
+fn read_trimmed(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(s) => Ok(Some(s.trim().to_string())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl InstalledIndex {
+    pub fn new() -> Self {
+        InstalledIndex::default()
+    }
+
+    /// Scans an installed package database laid out like `/var/db/pkg`.
+    ///
+    /// Entries whose directory name is not a valid `name-version`
+    /// (e.g. `-MERGING-` leftovers) are skipped.
+    #[cfg(feature = "fs")]
+    pub fn scan<P: AsRef<Path>>(root: P) -> io::Result<Self> {
+        let mut index = InstalledIndex::new();
+        for cat_entry in fs::read_dir(root)? {
+            let cat_entry = cat_entry?;
+            if !cat_entry.file_type()?.is_dir() {
+                continue;
+            }
+            let category = cat_entry.file_name().to_string_lossy().into_owned();
+            for pkg_entry in fs::read_dir(cat_entry.path())? {
+                let pkg_entry = pkg_entry?;
+                if !pkg_entry.file_type()?.is_dir() {
+                    continue;
+                }
+                let pv = pkg_entry.file_name().to_string_lossy().into_owned();
+                let cpv = format!("{}/{}", category, pv);
+                let Some((_, name, version)) = parse_cpv(&cpv) else {
+                    continue;
+                };
+                let dir = pkg_entry.path();
+                let slot = read_trimmed(&dir.join("SLOT"))?.unwrap_or_default();
+                let repository = read_trimmed(&dir.join("repository"))?;
+                index.insert(InstalledVersion::new(
+                    &category,
+                    name,
+                    version,
+                    &slot,
+                    repository.as_deref(),
+                )?);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Scans the default location [`VARDB_PATH`]
+    #[cfg(feature = "fs")]
+    pub fn scan_default() -> io::Result<Self> {
+        Self::scan(VARDB_PATH)
+    }
+
+    pub fn insert(&mut self, version: InstalledVersion) {
+        let versions = self
+            .packages
+            .entry((version.category.clone(), version.name.clone()))
+            .or_default();
+        versions.push(version);
+        versions.sort_by(|a, b| compare_parts(&a.parts, &b.parts));
+    }
+
+    /// Returns the installed versions of `category/name`, sorted by version
+    pub fn get(&self, category: &str, name: &str) -> &[InstalledVersion] {
+        self.packages
+            .get(&(category.to_string(), name.to_string()))
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns the installed versions matching `atom`
+    pub fn installed_versions(&self, atom: &Atom) -> Vec<&InstalledVersion> {
+        self.get(&atom.category, &atom.name)
+            .iter()
+            .filter(|v| {
+                atom.matches_version(&v.parts)
+                    && atom.matches_slot(&v.slot)
+                    && (atom.repo.is_none() || v.repository == atom.repo)
+            })
+            .collect()
+    }
+
+    /// Iterates over all installed versions, sorted by category and name
+    pub fn iter(&self) -> impl Iterator<Item = &InstalledVersion> {
+        self.packages.values().flatten()
+    }
+
+    /// Number of installed versions
+    pub fn len(&self) -> usize {
+        self.packages.values().map(|v| v.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+}
+
+impl Package {
+    /// Returns the installed versions of this package
+    pub fn installed_in<'a>(&self, index: &'a InstalledIndex) -> &'a [InstalledVersion] {
+        index.get(&self.category, &self.name)
+    }
+}
+
+impl Version {
+    /// Checks whether this version of `package` is installed
+    pub fn is_installed_in(&self, index: &InstalledIndex, package: &Package) -> bool {
+        package
+            .installed_in(index)
+            .iter()
+            .any(|i| i.is_version(self))
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    fn write_entry(root: &Path, cpv: &str, slot: Option<&str>, repo: Option<&str>) {
+        let dir = root.join(cpv);
+        fs::create_dir_all(&dir).unwrap();
+        if let Some(slot) = slot {
+            fs::write(dir.join("SLOT"), format!("{}\n", slot)).unwrap();
+        }
+        if let Some(repo) = repo {
+            fs::write(dir.join("repository"), format!("{}\n", repo)).unwrap();
+        }
+    }
+
+    fn vardb() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write_entry(
+            root,
+            "dev-lang/python-3.12.12",
+            Some("3.12/3.12"),
+            Some("gentoo"),
+        );
+        write_entry(
+            root,
+            "dev-lang/python-3.13.9",
+            Some("3.13/3.13"),
+            Some("gentoo"),
+        );
+        write_entry(root, "app-misc/foo-bar-1.0-r1", None, None);
+        write_entry(root, "app-misc/-MERGING-baz-2.0", Some("0"), None);
+        fs::write(root.join("app-misc/stray-file"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_scan() {
+        let dir = vardb();
+        let index = InstalledIndex::scan(dir.path()).unwrap();
+        assert_eq!(index.len(), 3);
+
+        let python = index.get("dev-lang", "python");
+        assert_eq!(python.len(), 2);
+        assert_eq!(python[0].version, "3.12.12");
+        assert_eq!(python[0].normalized_slot(), "3.12");
+        assert_eq!(python[0].repository.as_deref(), Some("gentoo"));
+
+        let foo = index.get("app-misc", "foo-bar");
+        assert_eq!(foo[0].cpv(), "app-misc/foo-bar-1.0-r1");
+        assert_eq!(foo[0].slot, "");
+        assert_eq!(foo[0].repository, None);
+
+        assert!(index.get("app-misc", "baz").is_empty());
+        assert!(InstalledIndex::scan(dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_installed_versions() {
+        let dir = vardb();
+        let index = InstalledIndex::scan(dir.path()).unwrap();
+        let query = |atom: &str| index.installed_versions(&Atom::parse(atom).unwrap()).len();
+        assert_eq!(query("dev-lang/python"), 2);
+        assert_eq!(query("dev-lang/python:3.13"), 1);
+        assert_eq!(query(">dev-lang/python-3.12.12"), 1);
+        assert_eq!(query("dev-lang/python::guru"), 0);
+        assert_eq!(query("app-misc/foo-bar:0"), 1);
+        assert_eq!(query("app-misc/unknown"), 0);
+    }
+
+    #[test]
+    fn test_package_installed_in() {
+        let dir = vardb();
+        let index = InstalledIndex::scan(dir.path()).unwrap();
+        let version = |v: &str, repo: &str| Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            reponame: repo.into(),
+            ..Default::default()
+        };
+        let pkg = Package {
+            category: "dev-lang".to_string(),
+            name: "python".to_string(),
+            versions: vec![
+                version("3.12.12", "gentoo"),
+                version("3.12.12", "guru"),
+                version("3.13.10", "gentoo"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(pkg.installed_in(&index).len(), 2);
+        let installed: Vec<bool> = pkg
+            .versions
+            .iter()
+            .map(|v| v.is_installed_in(&index, &pkg))
+            .collect();
+        assert_eq!(installed, vec![true, false, false]);
+    }
+}
@@ -0,0 +1,258 @@
+//! The world file of portage (`/var/lib/portage/world`)
+//!
+//! eix flags the versions matching a world entry with [`MASK_WORLD`];
+//! comparing these flags with the current world file detects a stale cache.
+//! Versions in one of the [world sets](crate::DBHeader::world_sets) are
+//! flagged with [`MASK_WORLD_SETS`]. Composed with the keyword helpers,
+//! the flags answer questions like "which world packages have no stable
+//! version for amd64":
+//!
+//! ```
+//! use eix::{EixTestBuilder, MASK_WORLD, PackageIndex};
+//!
+//! let (_, packages) = EixTestBuilder::new()
+//!     .category("app-misc")
+//!     .package("screen", |p| p.version("5.0", |v| v.keywords("~amd64").mask(MASK_WORLD)))
+//!     .package("tmux", |p| p.version("3.4", |v| v.keywords("amd64")))
+//!     .build();
+//! let index: PackageIndex = packages.into_iter().collect();
+//! let unstable: Vec<&str> = index
+//!     .world_packages()
+//!     .filter(|p| !p.versions.iter().any(|v| v.is_stable("amd64")))
+//!     .map(|p| p.name.as_str())
+//!     .collect();
+//! assert_eq!(unstable, ["screen"]);
+//! ```
+
+use crate::{Atom, MASK_WORLD, MASK_WORLD_SETS, Package, PackageIndex, Version};
+use serde::Serialize;
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Default location of the world file
+pub const WORLD_PATH: &str = "/var/lib/portage/world";
+
+/// Parsed world file: one atom per line, possibly slotted
+#[derive(Debug, Clone, Default)]
+pub struct WorldFile {
+    pub atoms: Vec<Atom>,
+}
+
+impl WorldFile {
+    /// Reads a world file
+    #[cfg(feature = "fs")]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Reads the world file from [`WORLD_PATH`]
+    #[cfg(feature = "fs")]
+    pub fn load_default() -> io::Result<Self> {
+        Self::load(WORLD_PATH)
+    }
+
+    /// Parses the contents of a world file.
+    /// Blank lines, comments and set references (`@set`) are skipped.
+    pub fn parse(s: &str) -> io::Result<Self> {
+        let atoms = s
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('@'))
+            .map(Atom::parse)
+            .collect::<io::Result<_>>()?;
+        Ok(WorldFile { atoms })
+    }
+
+    /// Checks whether the world file has an entry for `atom`.
+    ///
+    /// An unslotted entry covers all slots; a slotted query only
+    /// matches entries for the same slot or unslotted ones.
+    pub fn contains(&self, atom: &Atom) -> bool {
+        self.atoms.iter().any(|entry| {
+            entry.matches_cp(&atom.category, &atom.name)
+                && (entry.slot.is_none() || entry.slot == atom.slot)
+        })
+    }
+
+    /// Checks whether `version` of `package` matches a world entry
+    pub fn matches(&self, package: &Package, version: &Version) -> bool {
+        self.atoms.iter().any(|a| a.matches(package, version))
+    }
+}
+
+/// A version whose MASK_WORLD flag disagrees with the world file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorldMismatch {
+    pub cpv: String,
+    pub in_world_file: bool,
+    pub flagged: bool,
+}
+
+/// Result of [`world_consistency`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WorldReport {
+    /// World entries matching no version of the database
+    pub missing: Vec<String>,
+    pub mismatches: Vec<WorldMismatch>,
+}
+
+impl WorldReport {
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.mismatches.is_empty()
+    }
+}
+
+impl Version {
+    /// True if the version matches an entry of the world file
+    pub fn in_world(&self) -> bool {
+        self.mask_flags & MASK_WORLD != 0
+    }
+
+    /// True if the version is in one of the world sets
+    pub fn in_world_sets(&self) -> bool {
+        self.mask_flags & MASK_WORLD_SETS != 0
+    }
+}
+
+impl Package {
+    /// True if any version is in the world file or a world set, i.e.
+    /// selected by `@world`
+    pub fn has_world_version(&self) -> bool {
+        self.versions
+            .iter()
+            .any(|v| v.in_world() || v.in_world_sets())
+    }
+}
+
+impl PackageIndex {
+    /// The packages with a version in the world file or a world set, see
+    /// [`Package::has_world_version`]
+    pub fn world_packages(&self) -> impl Iterator<Item = &Package> {
+        self.packages().filter(|p| p.has_world_version())
+    }
+}
+
+/// Compares the world file with the MASK_WORLD flags of the database
+pub fn world_consistency(world: &WorldFile, packages: &[Package]) -> WorldReport {
+    let mut report = WorldReport::default();
+    let mut found = vec![false; world.atoms.len()];
+
+    for pkg in packages {
+        for v in &pkg.versions {
+            let mut in_world_file = false;
+            for (i, atom) in world.atoms.iter().enumerate() {
+                if atom.matches(pkg, v) {
+                    found[i] = true;
+                    in_world_file = true;
+                }
+            }
+            let flagged = v.in_world();
+            if flagged != in_world_file {
+                report.mismatches.push(WorldMismatch {
+                    cpv: pkg.cpv(v),
+                    in_world_file,
+                    flagged,
+                });
+            }
+        }
+    }
+
+    for (atom, found) in world.atoms.iter().zip(found) {
+        if !found {
+            let mut entry = atom.cp();
+            if let Some(slot) = &atom.slot {
+                entry.push(':');
+                entry.push_str(slot);
+            }
+            report.missing.push(entry);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_version;
+    #[cfg(feature = "fs")]
+    use std::io::Write;
+
+    fn version(v: &str, slot: &str, mask_flags: u8) -> Version {
+        Version {
+            version_string: v.to_string(),
+            parts: parse_version(v).unwrap(),
+            slot: slot.to_string(),
+            mask_flags,
+            ..Default::default()
+        }
+    }
+
+    fn packages() -> Vec<Package> {
+        vec![
+            Package {
+                category: "dev-lang".to_string(),
+                name: "python".to_string(),
+                versions: vec![
+                    version("3.12.12", "3.12/3.12", MASK_WORLD),
+                    version("3.13.9", "3.13/3.13", 0),
+                ],
+                ..Default::default()
+            },
+            Package {
+                category: "app-editors".to_string(),
+                name: "vim".to_string(),
+                versions: vec![version("9.1", "", 0)],
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_load() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "dev-lang/python:3.12").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "app-editors/vim").unwrap();
+        let world = WorldFile::load(file.path()).unwrap();
+        assert_eq!(world.atoms.len(), 2);
+
+        assert!(world.contains(&Atom::parse("dev-lang/python:3.12").unwrap()));
+        assert!(!world.contains(&Atom::parse("dev-lang/python:3.13").unwrap()));
+        assert!(!world.contains(&Atom::parse("dev-lang/python").unwrap()));
+        assert!(world.contains(&Atom::parse("app-editors/vim:0").unwrap()));
+        assert!(world.contains(&Atom::parse("app-editors/vim").unwrap()));
+        assert!(!world.contains(&Atom::parse("app-editors/emacs").unwrap()));
+
+        assert!(WorldFile::parse("not an atom").is_err());
+    }
+
+    #[test]
+    fn test_consistency() {
+        let world =
+            WorldFile::parse("dev-lang/python:3.12\napp-editors/vim\napp-misc/gone\n").unwrap();
+        let report = world_consistency(&world, &packages());
+        assert!(!report.is_consistent());
+        assert_eq!(report.missing, vec!["app-misc/gone"]);
+        assert_eq!(
+            report.mismatches,
+            vec![WorldMismatch {
+                cpv: "app-editors/vim-9.1".to_string(),
+                in_world_file: true,
+                flagged: false,
+            }]
+        );
+
+        let world = WorldFile::parse("dev-lang/python:3.12").unwrap();
+        assert!(world_consistency(&world, &packages()).is_consistent());
+
+        let world = WorldFile::parse("dev-lang/python:3.11").unwrap();
+        let report = world_consistency(&world, &packages());
+        assert_eq!(report.missing, vec!["dev-lang/python:3.11"]);
+        assert_eq!(report.mismatches.len(), 1);
+        assert!(report.mismatches[0].flagged);
+    }
+}
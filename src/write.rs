@@ -0,0 +1,407 @@
+//! Writing of eix database files
+//!
+//! [`EixWriter`] is the counterpart of [`Database`](crate::Database) and
+//! [`PackageReader`](crate::PackageReader): it writes the same format,
+//! so the packages of a file written in the order read with its own
+//! header give the same bytes again. A header from
+//! [`merge_databases`](crate::merge_databases) is built anew, with each
+//! string hashed once, so even a single merged database may differ from
+//! its file. With [`EixWriter::sorted`], the packages are written in the
+//! same order however they were given.
+
+use crate::codec;
+use crate::sort::{SortOrder, sort_packages};
+use crate::{
+    BasicPart, DB_MAGIC, DB_VERSION_NUMERIC_PROPERTIES, DBHeader, Package, SAVE_BITMASK_DEP,
+    SAVE_BITMASK_REQUIRED_USE, SAVE_BITMASK_SRC_URI, SaveBitmask, StringHash, UChar, Version,
+};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Writes an eix database
+pub struct EixWriter<W: Write> {
+    writer: W,
+    order: Option<SortOrder>,
+}
+
+/// Buffer for data which is preceded by its length in the file
+#[derive(Default)]
+struct Buffer {
+    data: Vec<u8>,
+}
+
+impl Buffer {
+    fn num(&mut self, value: u64) {
+        codec::encode_num(value, &mut self.data);
+    }
+
+    fn string(&mut self, s: &str) {
+        codec::encode_string(s, &mut self.data);
+    }
+
+    fn hash_index(&mut self, hash: &StringHash, s: &str) -> io::Result<()> {
+        let index = hash.get_index(s).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("String not in hash: {:?}", s),
+            )
+        })?;
+        self.num(index as u64);
+        Ok(())
+    }
+
+    fn hash_words(&mut self, hash: &StringHash, words: &[String]) -> io::Result<()> {
+        self.num(words.len() as u64);
+        for word in words {
+            self.hash_index(hash, word)?;
+        }
+        Ok(())
+    }
+
+    fn hash(&mut self, hash: &StringHash) {
+        self.num(hash.len() as u64);
+        for i in 0..hash.len() {
+            self.string(hash.get_string(i).unwrap_or_default());
+        }
+    }
+
+    fn part(&mut self, part: &BasicPart) {
+        codec::encode_part(part, &mut self.data);
+    }
+}
+
+/// Adds all strings of the packages which are stored hashed to the
+/// hashes of `header`. Existing entries keep their index.
+pub(crate) fn complete_hashes(header: &mut DBHeader, packages: &[Package]) {
+    for pkg in packages {
+        header.license_hash.add(pkg.licenses.clone());
+        for v in &pkg.versions {
+            header.eapi_hash.add(v.eapi.clone());
+            header.slot_hash.add(v.slot.clone());
+            for kw in &v.keywords {
+                header.keywords_hash.add(kw.clone());
+            }
+            for word in v.iuse.iter().chain(&v.required_use) {
+                header.iuse_hash.add(word.clone());
+            }
+            if let Some(depend) = v.depend() {
+                for word in depend
+                    .depend
+                    .iter()
+                    .chain(&depend.rdepend)
+                    .chain(&depend.pdepend)
+                    .chain(&depend.bdepend)
+                    .chain(&depend.idepend)
+                {
+                    header.depend_hash.add(word.clone());
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write> EixWriter<W> {
+    pub fn new(writer: W) -> Self {
+        EixWriter {
+            writer,
+            order: None,
+        }
+    }
+
+    /// Sorts the packages written by [`write_database`](Self::write_database)
+    /// in `order` instead of keeping the given order
+    pub fn sorted(mut self, order: SortOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes a single byte
+    pub fn write_uchar(&mut self, value: UChar) -> io::Result<()> {
+        self.writer.write_all(&[value])
+    }
+
+    /// Writes a number in eix format (variable length)
+    pub fn write_num(&mut self, value: u64) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(codec::MAX_NUM_LEN);
+        codec::encode_num(value, &mut buf);
+        self.writer.write_all(&buf)
+    }
+
+    /// Writes a string (length + data)
+    pub fn write_string(&mut self, s: &str) -> io::Result<()> {
+        self.write_num(s.len() as u64)?;
+        self.writer.write_all(s.as_bytes())
+    }
+
+    fn write_buffer(&mut self, buffer: &Buffer) -> io::Result<()> {
+        self.writer.write_all(&buffer.data)
+    }
+
+    /// Writes the header. The number of categories is taken from
+    /// `header.size`; the hashes must contain all strings of the packages.
+    pub fn write_header(&mut self, header: &DBHeader) -> io::Result<()> {
+        let mut buf = Buffer::default();
+        buf.data.extend_from_slice(DB_MAGIC);
+        buf.num(header.version as u64);
+        buf.num(header.size as u64);
+        buf.num(header.overlays.len() as u64);
+        for overlay in &header.overlays {
+            buf.string(&overlay.path);
+            buf.string(&overlay.label);
+        }
+        buf.hash(&header.eapi_hash);
+        buf.hash(&header.license_hash);
+        buf.hash(&header.keywords_hash);
+        buf.hash(&header.iuse_hash);
+        buf.hash(&header.slot_hash);
+        buf.num(header.world_sets.len() as u64);
+        for set in &header.world_sets {
+            buf.string(set);
+        }
+
+        let mut bitmask: SaveBitmask = 0;
+        if header.use_depend {
+            bitmask |= SAVE_BITMASK_DEP;
+        }
+        if header.use_required_use {
+            bitmask |= SAVE_BITMASK_REQUIRED_USE;
+        }
+        if header.use_src_uri {
+            bitmask |= SAVE_BITMASK_SRC_URI;
+        }
+        buf.num(bitmask as u64);
+        if header.use_depend {
+            let mut depend = Buffer::default();
+            depend.hash(&header.depend_hash);
+            buf.num(depend.data.len() as u64);
+            buf.data.extend(depend.data);
+        }
+        self.write_buffer(&buf)
+    }
+
+    fn encode_version(buf: &mut Buffer, header: &DBHeader, v: &Version) -> io::Result<()> {
+        if header.version >= 36 {
+            buf.hash_index(&header.eapi_hash, &v.eapi)?;
+        }
+        buf.data.push(v.mask_flags);
+        if header.version >= DB_VERSION_NUMERIC_PROPERTIES {
+            buf.num(v.properties_flags as u64);
+        } else {
+            let flags = u8::try_from(v.properties_flags).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Properties flags {:#x} do not fit the byte of database version {}",
+                        v.properties_flags, header.version
+                    ),
+                )
+            })?;
+            buf.data.push(flags);
+        }
+        buf.num(v.restrict_flags);
+        buf.hash_words(&header.keywords_hash, &v.keywords)?;
+        buf.num(v.parts.len() as u64);
+        for part in &v.parts {
+            buf.part(part);
+        }
+        buf.hash_index(&header.slot_hash, &v.slot)?;
+        buf.num(v.overlay_key);
+        buf.hash_words(&header.iuse_hash, &v.iuse)?;
+        if header.use_required_use {
+            buf.hash_words(&header.iuse_hash, &v.required_use)?;
+        }
+        if header.use_depend {
+            let default = Default::default();
+            let depend = v.depend().unwrap_or(&default);
+            let mut dep = Buffer::default();
+            dep.hash_words(&header.depend_hash, &depend.depend)?;
+            dep.hash_words(&header.depend_hash, &depend.rdepend)?;
+            dep.hash_words(&header.depend_hash, &depend.pdepend)?;
+            if header.version > 31 {
+                dep.hash_words(&header.depend_hash, &depend.bdepend)?;
+            }
+            if header.version > 38 {
+                dep.hash_words(&header.depend_hash, &depend.idepend)?;
+            }
+            buf.num(dep.data.len() as u64);
+            buf.data.extend(dep.data);
+        }
+        if header.use_src_uri {
+            buf.string(v.src_uri.as_deref().unwrap_or_default());
+        }
+        Ok(())
+    }
+
+    /// Writes a single package (preceded by its length)
+    pub fn write_package(&mut self, header: &DBHeader, pkg: &Package) -> io::Result<()> {
+        let mut buf = Buffer::default();
+        buf.string(&pkg.name);
+        buf.string(&pkg.description);
+        buf.string(&pkg.homepage);
+        buf.hash_index(&header.license_hash, &pkg.licenses)?;
+        buf.num(pkg.versions.len() as u64);
+        for v in &pkg.versions {
+            Self::encode_version(&mut buf, header, v)?;
+        }
+        self.write_num(buf.data.len() as u64)?;
+        self.write_buffer(&buf)
+    }
+
+    /// Writes a complete database.
+    ///
+    /// Packages are grouped by category in order of first appearance,
+    /// unless the writer is [`sorted`](Self::sorted). Strings missing in
+    /// the hashes of `header` are appended to them and the number of
+    /// categories is recomputed.
+    pub fn write_database(&mut self, header: &DBHeader, packages: &[Package]) -> io::Result<()> {
+        let mut sorted = Vec::new();
+        let packages = match self.order {
+            Some(order) => {
+                sorted.extend_from_slice(packages);
+                sort_packages(&mut sorted, order);
+                &sorted
+            }
+            None => packages,
+        };
+        let mut categories: Vec<(&str, Vec<&Package>)> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for pkg in packages {
+            let i = *index.entry(&pkg.category).or_insert_with(|| {
+                categories.push((&pkg.category, Vec::new()));
+                categories.len() - 1
+            });
+            categories[i].1.push(pkg);
+        }
+
+        let mut header = header.clone();
+        complete_hashes(&mut header, packages);
+        header.size = categories.len() as _;
+
+        self.write_header(&header)?;
+        for (category, packages) in categories {
+            self.write_string(category)?;
+            self.write_num(packages.len() as u64)?;
+            for pkg in packages {
+                self.write_package(&header, pkg)?;
+            }
+        }
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "fs")]
+    use crate::{Database, OverlayIdent, PackageReader, parse_version};
+
+    #[test]
+    fn test_encode_num() {
+        // Same cases as the read_num test
+        let cases = vec![
+            (0x00, vec![0x00]),
+            (0xFE, vec![0xFE]),
+            (0xFF, vec![0xFF, 0x00]),
+            (0x0100, vec![0xFF, 0x01, 0x00]),
+            (0x01FF, vec![0xFF, 0x01, 0xFF]),
+            (0xFEFF, vec![0xFF, 0xFE, 0xFF]),
+            (0xFF00, vec![0xFF, 0xFF, 0x00, 0x00]),
+            (0xFF01, vec![0xFF, 0xFF, 0x00, 0x01]),
+            (0x010000, vec![0xFF, 0xFF, 0x01, 0x00, 0x00]),
+            (0xABCDEF, vec![0xFF, 0xFF, 0xAB, 0xCD, 0xEF]),
+            (0xFFABCD, vec![0xFF, 0xFF, 0xFF, 0x00, 0xAB, 0xCD]),
+            (0x01ABCDEF, vec![0xFF, 0xFF, 0xFF, 0x01, 0xAB, 0xCD, 0xEF]),
+        ];
+        for (value, expected) in cases {
+            let mut buf = Vec::new();
+            codec::encode_num(value, &mut buf);
+            assert_eq!(buf, expected, "encoding {:#x}", value);
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_write_and_read() {
+        let header = DBHeader {
+            version: 39,
+            size: 0,
+            overlays: vec![OverlayIdent {
+                path: "/var/db/repos/gentoo".to_string(),
+                label: "gentoo".into(),
+                priority: 0,
+            }],
+            eapi_hash: StringHash::new(),
+            license_hash: StringHash::new(),
+            keywords_hash: StringHash::new(),
+            iuse_hash: StringHash::new(),
+            slot_hash: StringHash::new(),
+            depend_hash: StringHash::new(),
+            use_depend: true,
+            use_required_use: true,
+            use_src_uri: true,
+            world_sets: vec!["@selected".to_string()],
+            warnings: Vec::new(),
+        };
+        let version = Version {
+            version_string: "1.2_rc3-r1".to_string(),
+            parts: parse_version("1.2_rc3-r1").unwrap(),
+            eapi: "8".to_string(),
+            mask_flags: 1,
+            restrict_flags: 300,
+            keywords: vec!["~amd64".to_string()],
+            slot: "0/1.2".to_string(),
+            reponame: "gentoo".into(),
+            iuse: vec!["+ssl".to_string()],
+            required_use: vec!["ssl".to_string()],
+            src_uri: Some("https://example.org/foo-1.2.tar.gz".to_string()),
+            ..Default::default()
+        };
+        let packages = vec![
+            Package {
+                category: "dev-libs".to_string(),
+                name: "foo".to_string(),
+                description: "Foo".to_string(),
+                licenses: "MIT".to_string(),
+                versions: vec![version],
+                ..Default::default()
+            },
+            Package {
+                category: "app-misc".to_string(),
+                name: "bar".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = EixWriter::new(std::fs::File::create(file.path()).unwrap());
+        writer.write_database(&header, &packages).unwrap();
+        drop(writer);
+
+        let mut db = Database::open_read(file.path()).unwrap();
+        let read_header = db.read_header(0).unwrap();
+        assert_eq!(read_header.size, 2);
+        assert_eq!(read_header.world_sets, header.world_sets);
+        let mut reader = PackageReader::new(db, read_header);
+        let mut read = Vec::new();
+        while reader.next_category().unwrap() {
+            while let Some(pkg) = reader.read_package().unwrap() {
+                read.push(pkg);
+            }
+        }
+        assert_eq!(read.len(), 2);
+        let v = &read[0].versions[0];
+        assert_eq!(v.version_string, "1.2_rc3-r1");
+        assert_eq!(v.slot, "0/1.2");
+        assert_eq!(v.restrict_flags, 300);
+        assert_eq!(v.required_use, vec!["ssl"]);
+        assert_eq!(v.depend.as_ref().unwrap().rdepend, Vec::<String>::new());
+        assert_eq!(v.src_uri, packages[0].versions[0].src_uri);
+        assert_eq!(read[1].category, "app-misc");
+        assert!(read[1].versions.is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "arbitrary")]
+
+mod common;
+
+use arbitrary::{Arbitrary, Unstructured};
+use common::read_database;
+use eix::{
+    ArbitraryDatabase, BasicPart, DBHeader, Database, EixWriter, Package, Version, parse_version,
+};
+use proptest::prelude::*;
+
+/// The strings of all hashes of `header`
+fn hashes(header: &DBHeader) -> Vec<Vec<&str>> {
+    [
+        &header.eapi_hash,
+        &header.license_hash,
+        &header.keywords_hash,
+        &header.iuse_hash,
+        &header.slot_hash,
+        &header.depend_hash,
+    ]
+    .into_iter()
+    .map(|hash| (0..hash.len()).filter_map(|i| hash.get_string(i)).collect())
+    .collect()
+}
+
+proptest! {
+    #[test]
+    fn prop_versions_reparse(data in proptest::collection::vec(any::<u8>(), 0..256)) {
+        let mut u = Unstructured::new(&data);
+        let v = Version::arbitrary(&mut u).unwrap();
+        prop_assert_eq!(&parse_version(&v.version_string).unwrap(), &v.parts);
+        prop_assert_eq!(v.get_full_version(), v.version_string);
+        let part = BasicPart::arbitrary(&mut u).unwrap();
+        prop_assert!(!part.part_content.contains(['.', '_', '-']));
+        let package = Package::arbitrary(&mut u).unwrap();
+        prop_assert!(package.versions.iter().all(|v| v.overlay_key == 0));
+    }
+
+    #[test]
+    fn prop_write_read_round_trip(data in proptest::collection::vec(any::<u8>(), 0..4096)) {
+        let db = ArbitraryDatabase::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let mut writer = EixWriter::new(Vec::new());
+        writer.write_database(&db.header, &db.packages).unwrap();
+        let (header, packages) = read_database(Database::from_bytes(writer.into_inner()));
+
+        prop_assert_eq!(header.size, db.header.size);
+        prop_assert_eq!(header.overlays.len(), db.header.overlays.len());
+        for (read, generated) in hashes(&header).into_iter().zip(hashes(&db.header)) {
+            prop_assert_eq!(read, generated);
+        }
+        prop_assert_eq!(
+            serde_json::to_value(&header).unwrap(),
+            serde_json::to_value(&db.header).unwrap()
+        );
+        prop_assert_eq!(
+            serde_json::to_value(&packages).unwrap(),
+            serde_json::to_value(&db.packages).unwrap()
+        );
+        for (read, generated) in packages.iter().zip(&db.packages) {
+            for (a, b) in read.versions.iter().zip(&generated.versions) {
+                prop_assert_eq!(&a.parts, &b.parts);
+            }
+        }
+    }
+}
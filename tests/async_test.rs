@@ -0,0 +1,74 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{AsyncDatabase, AsyncPackageReader, DB_VERSION_CURRENT, EixWriter, OpenOptions, Package};
+use futures_util::StreamExt;
+use std::io;
+
+#[tokio::test]
+async fn test_stream_matches_sync_parser() {
+    let (header, packages) = fixture();
+    let file = tokio::fs::File::open(FIXTURE_EIX).await.unwrap();
+    let mut db = AsyncDatabase::new(file);
+    let async_header = db.read_header(DB_VERSION_CURRENT).await.unwrap();
+    assert_eq!(async_header.size, header.size);
+    assert_eq!(async_header.depend_hash.len(), header.depend_hash.len());
+
+    let reader = AsyncPackageReader::new(db, async_header);
+    let read: Vec<Package> = reader.map(|p| p.unwrap()).collect().await;
+    assert_eq!(
+        serde_json::to_string(&read).unwrap(),
+        serde_json::to_string(packages).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_categories_with_tiny_chunks() {
+    let (header, packages) = fixture();
+    let dev_lang: Vec<Package> = packages
+        .iter()
+        .filter(|p| p.category == "dev-lang")
+        .cloned()
+        .collect();
+    let mut writer = EixWriter::new(Vec::new());
+    writer.write_database(header, &dev_lang).unwrap();
+    let data = writer.into_inner();
+
+    let options = OpenOptions {
+        buffer_capacity: 1,
+        record_spans: true,
+        ..Default::default()
+    };
+    let mut db = AsyncDatabase::with_options(data.as_slice(), options);
+    let header = db.read_header(0).await.unwrap();
+    let mut reader = AsyncPackageReader::new(db, header);
+    assert!(reader.next_category().await.unwrap());
+    assert_eq!(reader.current_category(), "dev-lang");
+    let mut read = Vec::new();
+    while let Some(pkg) = reader.read_package().await.unwrap() {
+        read.push(pkg);
+    }
+    assert!(!reader.next_category().await.unwrap());
+    assert_eq!(read.len(), dev_lang.len());
+    for (a, b) in read.iter().zip(&dev_lang) {
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.versions.len(), b.versions.len());
+    }
+    // Spans are offsets in the whole input
+    let last = read.last().unwrap().span.unwrap();
+    assert_eq!(last.end, data.len() as u64);
+}
+
+#[tokio::test]
+async fn test_truncated_input_fails() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let truncated = &data[..data.len() / 2];
+    let mut db = AsyncDatabase::new(truncated);
+    let header = db.read_header(0).await.unwrap();
+    let results: Vec<io::Result<Package>> = AsyncPackageReader::new(db, header).collect().await;
+    let err = results.last().unwrap().as_ref().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    assert!(results[..results.len() - 1].iter().all(|r| r.is_ok()));
+}
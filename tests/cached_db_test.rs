@@ -0,0 +1,79 @@
+mod common;
+
+use common::FIXTURE_EIX;
+use eix::{CachedDb, ParsedDb};
+use std::fs::{self, File};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+fn touch(path: &std::path::Path, seconds_ago: u64) {
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(seconds_ago))
+        .unwrap();
+}
+
+#[test]
+fn test_parsed_db() {
+    let parsed = ParsedDb::load(FIXTURE_EIX).unwrap();
+    assert_eq!(parsed.packages.len(), 19176);
+    assert_eq!(parsed.info.category_count, parsed.header.size);
+}
+
+#[test]
+fn test_cached_db_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("portage.eix");
+    fs::copy(FIXTURE_EIX, &path).unwrap();
+    touch(&path, 3600);
+
+    let cached = CachedDb::open(&path)
+        .unwrap()
+        .with_min_stat_interval(Duration::ZERO);
+    let first = cached.get();
+    assert_eq!(first.packages.len(), 19176);
+    assert!(Arc::ptr_eq(&first, &cached.get()));
+
+    // Touching the file yields a new snapshot
+    touch(&path, 60);
+    let second = cached.get();
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert_eq!(second.packages.len(), 19176);
+    assert!(Arc::ptr_eq(&second, &cached.get()));
+
+    // A broken replacement keeps the old snapshot
+    fs::write(&path, b"garbage").unwrap();
+    assert!(cached.refresh().is_err());
+    assert!(Arc::ptr_eq(&second, &cached.get()));
+
+    fs::copy(FIXTURE_EIX, &path).unwrap();
+    assert!(cached.refresh().unwrap());
+    assert!(!cached.refresh().unwrap());
+}
+
+#[test]
+fn test_cached_db_stat_interval() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("portage.eix");
+    fs::copy(FIXTURE_EIX, &path).unwrap();
+
+    let cached = CachedDb::open(&path)
+        .unwrap()
+        .with_min_stat_interval(Duration::from_secs(3600));
+    let first = cached.get();
+    touch(&path, 60);
+    assert!(Arc::ptr_eq(&first, &cached.get()));
+
+    cached.invalidate();
+    let second = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..4).map(|_| s.spawn(|| cached.get())).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        cached.get()
+    });
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert!(Arc::ptr_eq(&second, &cached.snapshot()));
+}
@@ -0,0 +1,141 @@
+#![cfg(feature = "fs")]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture, fixture_packages};
+use eix::{
+    CancelToken, Cancelled, Database, DatabaseStats, Outcome, PackageQuery, PackageReader,
+    ParsedDb, ReverseDepIndex, SharedDb, is_cancelled,
+};
+use std::io::{self, Read};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Serves `data`, pausing once at `gate` until the other side answers
+struct Gate {
+    data: io::Cursor<Vec<u8>>,
+    gate: u64,
+    reached: Option<(Sender<()>, Receiver<()>)>,
+}
+
+impl Read for Gate {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.data.position() >= self.gate
+            && let Some((reached, resume)) = self.reached.take()
+        {
+            reached.send(()).unwrap();
+            resume.recv().unwrap();
+        }
+        self.data.read(buf)
+    }
+}
+
+fn reader(data: Vec<u8>, gate: u64) -> (PackageReader, Receiver<()>, Sender<()>) {
+    let (reached_tx, reached_rx) = channel();
+    let (resume_tx, resume_rx) = channel();
+    let gate = Gate {
+        data: io::Cursor::new(data),
+        gate,
+        reached: Some((reached_tx, resume_rx)),
+    };
+    let mut db = Database::from_reader(gate);
+    let header = db.read_header(0).unwrap();
+    (PackageReader::new(db, header), reached_rx, resume_tx)
+}
+
+#[test]
+fn test_cancel_parse_from_another_thread() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let len = data.len() as u64;
+    let (reader, reached, resume) = reader(data, len / 2);
+    let token = CancelToken::new();
+    let mut reader = reader.with_cancel(token.clone());
+
+    let canceller = thread::spawn(move || {
+        reached.recv().unwrap();
+        token.cancel();
+        resume.send(()).unwrap();
+        Instant::now()
+    });
+    let outcome = reader.read_all().unwrap();
+    let finished = Instant::now();
+    let cancelled_at = canceller.join().unwrap();
+
+    assert!(outcome.is_cancelled());
+    let packages = outcome.into_inner();
+    assert!(!packages.is_empty());
+    assert!(packages.len() < fixture_packages().len());
+    // The packages read match the start of the file
+    for (read, expected) in packages.iter().zip(fixture_packages()) {
+        assert_eq!(
+            (&read.category, &read.name),
+            (&expected.category, &expected.name)
+        );
+    }
+    // At most the package being read and the read buffer follow the gate
+    assert!(reader.position() < len / 2 + 64 * 1024);
+    assert!(finished.duration_since(cancelled_at) < Duration::from_secs(1));
+
+    // Reading on fails with the distinct error
+    let err = reader.read_package().unwrap_err();
+    assert!(is_cancelled(&err));
+}
+
+#[test]
+fn test_read_all_without_cancel() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let mut db = Database::from_bytes(data);
+    let header = db.read_header(0).unwrap();
+    let outcome = PackageReader::new(db, header)
+        .with_cancel(CancelToken::new())
+        .read_all()
+        .unwrap();
+    assert!(!outcome.is_cancelled());
+    assert_eq!(outcome.into_inner().len(), fixture_packages().len());
+}
+
+#[test]
+fn test_partial_stats() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let len = data.len() as u64;
+    let (reader, reached, resume) = reader(data, len / 4);
+    let token = CancelToken::new();
+    let mut reader = reader.with_cancel(token.clone());
+    let canceller = thread::spawn(move || {
+        reached.recv().unwrap();
+        token.cancel();
+        resume.send(()).unwrap();
+    });
+    let outcome = DatabaseStats::from_reader_cancellable(&mut reader, 5).unwrap();
+    canceller.join().unwrap();
+    let Outcome::Cancelled(stats) = outcome else {
+        panic!("not cancelled");
+    };
+    assert!(stats.packages > 0);
+    assert!(stats.packages < fixture_packages().len());
+}
+
+#[test]
+fn test_cancel_query_and_index() {
+    let token = CancelToken::new();
+    let (_, packages) = fixture();
+    assert!(ReverseDepIndex::build_cancellable(packages, &token).is_ok());
+
+    let db = SharedDb::new(ParsedDb::load(FIXTURE_EIX).unwrap());
+    let query = PackageQuery::category("dev-lang");
+    let outcome = query.execute_cancellable(&db, &token);
+    assert!(!outcome.is_cancelled());
+    let found = outcome.into_inner();
+    assert!(!found.is_empty());
+    assert_eq!(found.len(), query.execute(&db).len());
+
+    token.cancel();
+    assert_eq!(
+        ReverseDepIndex::build_cancellable(packages, &token),
+        Err(Cancelled)
+    );
+    let outcome = query.execute_cancellable(&db, &token);
+    assert!(outcome.is_cancelled());
+    assert!(outcome.into_inner().is_empty());
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use assert_cmd::Command;
+use common::FIXTURE_EIX;
+use eix::{EIX_CACHEFILE_ENV, EixTestBuilder, Package};
+
+fn eix_rs(args: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin("eix-rs").unwrap();
+    cmd.args(args)
+        .env_remove(EIX_CACHEFILE_ENV)
+        .env_remove("NO_COLOR");
+    cmd
+}
+
+/// Runs `eix-rs --file <fixture> --color never <args>`, expecting `code`
+fn run(args: &[&str], code: i32) -> String {
+    let mut all = vec!["--file", FIXTURE_EIX, "--color", "never"];
+    all.extend(args);
+    stdout(&mut eix_rs(&all), code)
+}
+
+fn stdout(cmd: &mut Command, code: i32) -> String {
+    let output = cmd.assert().code(code).get_output().clone();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn stderr(cmd: &mut Command, code: i32) -> String {
+    let output = cmd.assert().code(code).get_output().clone();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn test_help() {
+    let help = stdout(&mut eix_rs(&["--help"]), 0);
+    for command in [
+        "search", "show", "stats", "json", "diff", "--file", "--arch",
+    ] {
+        assert!(help.contains(command), "{}", command);
+    }
+    eix_rs(&["-V"]).assert().success();
+}
+
+#[test]
+fn test_usage_errors() {
+    for args in [
+        &[][..],
+        &["frobnicate"],
+        &["--verbose", "stats"],
+        &["--file"],
+        &["--color", "sometimes", "stats"],
+        &["search"],
+        &["show", "screen"],
+        &["stats", "extra"],
+        &["--ndjson", "stats"],
+        &["search", "("],
+    ] {
+        let err = stderr(&mut eix_rs(args), 2);
+        assert!(err.contains("--help"), "{:?}: {}", args, err);
+    }
+    let err = stderr(&mut eix_rs(&["--file", "testdata/missing.eix", "stats"]), 2);
+    assert!(
+        err.contains("Error opening testdata/missing.eix"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_search() {
+    let out = run(&["search", "^app-misc/screen"], 0);
+    assert!(out.starts_with("* app-misc/screen\n"));
+    assert!(out.contains("[M]5.0.1::gentoo **9999::gentoo"));
+    assert!(out.contains("* app-misc/screenfetch\n"));
+    assert!(!out.contains('\x1b'));
+
+    run(&["search", "^nonexistent/"], 1);
+}
+
+#[test]
+fn test_show() {
+    let out = run(&["show", "app-misc/screen"], 0);
+    assert!(out.contains("     License: GPL-3+\n"));
+    assert!(out.contains("       9999 (slot 0, EAPI 8, no keywords, PROPERTIES=live"));
+    assert!(out.contains("masked by package.mask"));
+
+    // The keyword state depends on the architecture
+    let amd64 = run(&["--arch", "amd64", "show", "app-misc/screen"], 0);
+    let hppa = run(&["--arch=hppa", "show", "app-misc/screen"], 0);
+    assert!(amd64.contains(" 4.9.1-r1::gentoo"));
+    assert!(hppa.contains(" ~4.9.1-r1::gentoo"));
+
+    let args = [
+        "--file",
+        FIXTURE_EIX,
+        "--color",
+        "always",
+        "show",
+        "app-misc/screen",
+    ];
+    assert!(stdout(&mut eix_rs(&args), 0).contains('\x1b'));
+
+    run(&["show", "app-misc/nonexistent"], 1);
+}
+
+#[test]
+fn test_stats_default_path() {
+    let out = stdout(eix_rs(&["stats"]).env(EIX_CACHEFILE_ENV, FIXTURE_EIX), 0);
+    assert!(out.contains("Packages:          19176\n"));
+    assert!(out.contains("Versions:          31730\n"));
+    assert!(out.contains("  gentoo           31730\n"));
+}
+
+#[test]
+fn test_json() {
+    let (_, packages) = common::fixture();
+    let ndjson = run(&["json", "--ndjson"], 0);
+    let lines: Vec<&str> = ndjson.lines().collect();
+    assert_eq!(lines.len(), packages.len());
+    assert_eq!(lines[0], serde_json::to_string(&packages[0]).unwrap());
+
+    let parsed: Vec<Package> = serde_json::from_str(&run(&["json"], 0)).unwrap();
+    assert_eq!(parsed.len(), packages.len());
+    assert_eq!(parsed[100].name, packages[100].name);
+}
+
+#[test]
+fn test_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| p.version("4.9.1", |v| v.keywords("amd64")))
+        .package("gone", |p| p.version("1.0", |v| v));
+    let new = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| {
+            p.version("4.9.1", |v| v.keywords("amd64"))
+                .version("5.0.1", |v| v.keywords("~amd64"))
+        })
+        .package("tmux", |p| p.version("3.4", |v| v));
+    let old_path = dir.path().join("old.eix");
+    let new_path = dir.path().join("new.eix");
+    std::fs::write(&old_path, old.build_bytes()).unwrap();
+    std::fs::write(&new_path, new.build_bytes()).unwrap();
+    let (old_path, new_path) = (old_path.to_str().unwrap(), new_path.to_str().unwrap());
+
+    assert_eq!(
+        stdout(
+            &mut eix_rs(&["--color", "never", "diff", old_path, new_path]),
+            1
+        ),
+        "[<] app-misc/gone\n\
+         [>] app-misc/screen (4.9.1 -> 5.0.1)\n\
+         [N] app-misc/tmux (3.4)\n\
+         1 new, 1 removed, 1 updated\n"
+    );
+
+    eix_rs(&["diff", old_path, old_path]).assert().success();
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "color")]
+
+mod common;
+
+use common::fixture_package;
+use eix::{ColorChoice, ColorFormatter, ColorScheme, InstalledIndex, InstalledVersion};
+
+/// Removes the escape sequences
+fn strip(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[test]
+fn test_format_without_color() {
+    let screen = fixture_package("app-misc", "screen");
+    let formatter = ColorFormatter::new(ColorChoice::Never);
+    assert!(!formatter.color());
+    assert_eq!(
+        formatter.format_package(screen, "amd64", &ColorScheme::default()),
+        "* app-misc/screen\n     \
+         Available versions: 4.9.1-r1::gentoo 4.9.1-r2::gentoo [M]5.0.1::gentoo **9999::gentoo\n     \
+         Homepage: https://www.gnu.org/software/screen/\n     \
+         Description: screen manager with VT100/ANSI terminal emulation\n"
+    );
+}
+
+#[test]
+fn test_format_with_color() {
+    let screen = fixture_package("app-misc", "screen");
+    let mut installed = InstalledIndex::new();
+    installed.insert(
+        InstalledVersion::new("app-misc", "screen", "4.9.1-r2", "0", Some("gentoo")).unwrap(),
+    );
+    let scheme = ColorScheme::default();
+    let plain = ColorFormatter::new(ColorChoice::Never)
+        .installed(&installed)
+        .format_package(screen, "amd64", &scheme);
+    let colored = ColorFormatter::new(ColorChoice::Always)
+        .installed(&installed)
+        .format_package(screen, "amd64", &scheme);
+    assert_ne!(plain, colored);
+    assert_eq!(strip(&colored), plain);
+    assert!(plain.contains(" [I]4.9.1-r2::gentoo "));
+    assert!(colored.starts_with("* \x1b[1;32mapp-misc/screen\x1b[0m\n"));
+    assert!(colored.contains("\x1b[1;34m[I]\x1b[0m\x1b[32m4.9.1-r2\x1b[0m\x1b[2m::gentoo\x1b[0m"));
+    assert!(colored.contains("\x1b[31m[M]5.0.1\x1b[0m"));
+
+    // Testing keywords for an arch without stable ones
+    let testing = ColorFormatter::new(ColorChoice::Always).format_package(screen, "riscv", &scheme);
+    assert!(testing.contains("\x1b[33m~4.9.1-r1\x1b[0m"));
+
+    // Empty styles are not colored
+    let scheme = ColorScheme {
+        repo: String::new(),
+        ..Default::default()
+    };
+    let colored = ColorFormatter::new(ColorChoice::Always).format_package(screen, "amd64", &scheme);
+    assert!(colored.contains("\x1b[32m4.9.1-r1\x1b[0m::gentoo "));
+}
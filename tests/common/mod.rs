@@ -0,0 +1,40 @@
+#![allow(dead_code)]
+
+use eix::{DB_VERSION_CURRENT, DBHeader, Database, Package, PackageReader};
+use std::sync::OnceLock;
+
+pub const FIXTURE_EIX: &str = "testdata/portage.eix";
+
+/// Parses the fixture once per test binary
+pub fn fixture() -> &'static (DBHeader, Vec<Package>) {
+    static FIXTURE: OnceLock<(DBHeader, Vec<Package>)> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        read_database(Database::open_read(FIXTURE_EIX).expect("Failed to open eix file"))
+    })
+}
+
+pub fn fixture_packages() -> &'static [Package] {
+    &fixture().1
+}
+
+pub fn fixture_package(category: &str, name: &str) -> &'static Package {
+    fixture_packages()
+        .iter()
+        .find(|p| p.category == category && p.name == name)
+        .unwrap_or_else(|| panic!("{}/{} not in fixture", category, name))
+}
+
+/// Reads the header and all packages of `db`
+pub fn read_database(mut db: Database) -> (DBHeader, Vec<Package>) {
+    let header = db
+        .read_header(DB_VERSION_CURRENT)
+        .expect("Failed to read header");
+    let mut reader = PackageReader::new(db, header.clone());
+    let mut packages = Vec::new();
+    while reader.next_category().expect("Failed to read category") {
+        while let Some(pkg) = reader.read_package().expect("Failed to read package") {
+            packages.push(pkg);
+        }
+    }
+    (header, packages)
+}
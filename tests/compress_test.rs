@@ -0,0 +1,79 @@
+#![cfg(feature = "compression")]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{Compression, DB_VERSION_CURRENT, Database, PackageReader};
+use std::fs;
+use std::io::{Cursor, Write};
+
+fn compress(data: &[u8], compression: Compression) -> Vec<u8> {
+    match compression {
+        Compression::None => data.to_vec(),
+        Compression::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        Compression::Xz => {
+            let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        }
+        Compression::Zstd => zstd::encode_all(data, 3).unwrap(),
+    }
+}
+
+fn assert_matches_fixture(mut db: Database) {
+    let (header, packages) = fixture();
+    let read_header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    assert_eq!(read_header.size, header.size);
+    let mut reader = PackageReader::new(db, read_header);
+    let mut count = 0;
+    while reader.next_category().unwrap() {
+        while let Some(pkg) = reader.read_package().unwrap() {
+            assert_eq!(pkg.name, packages[count].name);
+            assert_eq!(pkg.versions.len(), packages[count].versions.len());
+            count += 1;
+        }
+    }
+    assert_eq!(count, packages.len());
+}
+
+#[test]
+fn test_compressed_fixture() {
+    let data = fs::read(FIXTURE_EIX).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    for compression in [
+        Compression::None,
+        Compression::Gzip,
+        Compression::Xz,
+        Compression::Zstd,
+    ] {
+        let compressed = compress(&data, compression);
+        assert_eq!(Compression::detect(&compressed), compression);
+
+        let path = dir.path().join(format!("portage.eix.{:?}", compression));
+        fs::write(&path, &compressed).unwrap();
+        assert_matches_fixture(Database::open_read_compressed(&path).unwrap());
+        assert_matches_fixture(Database::from_reader_compressed(Cursor::new(compressed)).unwrap());
+    }
+}
+
+#[test]
+fn test_corrupt_compressed_data() {
+    let data = fs::read(FIXTURE_EIX).unwrap();
+    let mut compressed = compress(&data, Compression::Gzip);
+    compressed.truncate(compressed.len() / 2);
+    let mut db = Database::from_reader_compressed(Cursor::new(compressed)).unwrap();
+    let header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    let mut reader = PackageReader::new(db, header);
+    let result = (|| {
+        while reader.next_category()? {
+            while reader.read_package()?.is_some() {}
+        }
+        Ok::<_, std::io::Error>(())
+    })();
+    assert!(result.is_err());
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::fixture_packages;
+use eix::{EventKind, PackageIndex, RenderOptions, diff, diff_index};
+
+#[test]
+fn test_diff_fixture_against_modified_copy() {
+    let old = fixture_packages();
+    assert!(diff(old, old).is_empty());
+
+    let mut new = old.to_vec();
+    let removed = new.remove(10);
+    let mut added = new[20].clone();
+    added.name.push_str("-ng");
+    new.push(added.clone());
+
+    // A new highest version and a stabilized keyword
+    let bumped = new
+        .iter()
+        .position(|p| {
+            p.versions
+                .iter()
+                .any(|v| v.keywords.iter().any(|k| k == "~amd64"))
+        })
+        .unwrap();
+    let package = &mut new[bumped];
+    let mut version = package.versions.last().unwrap().clone();
+    version.version_string.push_str("-r99");
+    version.parts = eix::parse_version(&version.version_string).unwrap();
+    let stabilized = package
+        .versions
+        .iter_mut()
+        .find(|v| v.keywords.iter().any(|k| k == "~amd64"))
+        .unwrap();
+    let keyword = stabilized
+        .keywords
+        .iter_mut()
+        .find(|k| *k == "~amd64")
+        .unwrap();
+    *keyword = "amd64".to_string();
+    let stabilized = stabilized.version_string.clone();
+    package.versions.push(version.clone());
+
+    let result = diff(old, &new);
+    let atom = |p: &eix::Package| format!("{}/{}", p.category, p.name);
+    assert_eq!(result.added.len(), 1);
+    assert_eq!(result.added[0].atom, atom(&added));
+    assert_eq!(result.removed.len(), 1);
+    assert_eq!(result.removed[0].atom, atom(&removed));
+    assert_eq!(result.changed.len(), 1);
+
+    let change = &result.changed[0];
+    assert_eq!(change.atom, atom(&new[bumped]));
+    assert_eq!(change.added_versions.len(), 1);
+    assert_eq!(change.added_versions[0].version, version.version_string);
+    assert_eq!(change.keyword_changes.len(), 1);
+    assert_eq!(change.keyword_changes[0].version.version, stabilized);
+    let best = change.best_version.as_ref().unwrap();
+    assert_eq!(best.new, version.version_string);
+
+    let events = result.to_events();
+    assert!(
+        events
+            .iter()
+            .any(|e| e.kind == EventKind::KeywordChanged && e.new.as_deref() == Some("amd64"))
+    );
+
+    let mut out = Vec::new();
+    result.render(&mut out, RenderOptions::default()).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains(&format!("[N] {} (", atom(&added))));
+    assert!(text.contains(&format!("[<] {}\n", atom(&removed))));
+    assert!(text.contains(&format!(
+        "[>] {} ({} -> {})",
+        change.atom, best.old, best.new
+    )));
+    assert!(text.ends_with("1 new, 1 removed, 1 updated\n"));
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["changed"][0]["best_version"]["new"], best.new);
+
+    // Indexed databases give the same changes
+    let indexed = diff_index(
+        &PackageIndex::from(old.to_vec()),
+        &PackageIndex::from(new.clone()),
+    );
+    assert_eq!(indexed.added, result.added);
+    assert_eq!(indexed.removed, result.removed);
+    assert_eq!(indexed.changed, result.changed);
+}
@@ -0,0 +1,63 @@
+#![cfg(all(feature = "digest", feature = "fs"))]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::{Database, Package, content_digest, file_digest};
+
+#[test]
+fn test_content_digest_ignores_order() {
+    let packages = fixture_packages();
+    let digest = content_digest(packages);
+
+    // Packages and versions in another order, versions of other overlay
+    // keys and priorities
+    let mut shuffled: Vec<Package> = packages.to_vec();
+    shuffled.reverse();
+    shuffled.rotate_left(packages.len() / 3);
+    for package in &mut shuffled {
+        package.versions.reverse();
+        for version in &mut package.versions {
+            version.priority += 7;
+        }
+    }
+    assert_eq!(content_digest(&shuffled), digest);
+
+    // A changed keyword changes the digest
+    let index = shuffled
+        .iter()
+        .position(|p| p.versions.iter().any(|v| !v.keywords.is_empty()))
+        .unwrap();
+    let version = shuffled[index]
+        .versions
+        .iter_mut()
+        .find(|v| !v.keywords.is_empty())
+        .unwrap();
+    version.keywords[0].insert(0, '~');
+    assert_ne!(content_digest(&shuffled), digest);
+
+    // So does a missing package
+    assert_ne!(content_digest(&packages[1..]), digest);
+    assert_ne!(content_digest(&[]), digest);
+}
+
+#[test]
+fn test_file_digest() {
+    let digest = file_digest(FIXTURE_EIX).unwrap();
+    let info = Database::info(FIXTURE_EIX).unwrap();
+    assert_eq!(info.file_digest().unwrap(), digest);
+    assert_eq!(
+        info.content_digest().unwrap(),
+        content_digest(fixture_packages())
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let copy = dir.path().join("portage.eix");
+    let mut bytes = std::fs::read(FIXTURE_EIX).unwrap();
+    std::fs::write(&copy, &bytes).unwrap();
+    assert_eq!(file_digest(&copy).unwrap(), digest);
+    bytes.push(0);
+    std::fs::write(&copy, &bytes).unwrap();
+    assert_ne!(file_digest(&copy).unwrap(), digest);
+    assert!(file_digest(dir.path().join("missing.eix")).is_err());
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{fixture, fixture_package};
+use eix::Atom;
+
+#[test]
+fn test_version_display() {
+    let screen = fixture_package("app-misc", "screen");
+    let stable = &screen.versions[0];
+    assert_eq!(
+        stable.to_string(),
+        "4.9.1-r1:0::gentoo [~alpha amd64 arm arm64 ~hppa ~loong ~m68k ~mips ppc ppc64 \
+         ~riscv ~s390 ~sparc x86 ~arm64-macos ~x64-macos ~x64-solaris]"
+    );
+    let live = &screen.versions[3];
+    assert_eq!(live.to_string(), "9999:0::gentoo");
+    assert_eq!(
+        format!("{:#}", live),
+        "9999:0::gentoo EAPI=8 properties=live restrict=test"
+    );
+    let masked = &screen.versions[2];
+    assert!(format!("{:#}", masked).ends_with(" EAPI=8 mask=package restrict=test"));
+
+    let zstd = fixture_package("app-arch", "zstd");
+    assert!(
+        zstd.versions[0]
+            .to_string()
+            .starts_with("1.5.7:0/1::gentoo [")
+    );
+}
+
+#[test]
+fn test_package_display() {
+    let screen = fixture_package("app-misc", "screen");
+    assert_eq!(
+        screen.to_string(),
+        "app-misc/screen-9999: screen manager with VT100/ANSI terminal emulation"
+    );
+    let detailed = format!("{:#}", screen);
+    let lines: Vec<&str> = detailed.lines().collect();
+    assert_eq!(lines.len(), 3 + screen.versions.len());
+    assert_eq!(
+        lines[..3],
+        [
+            "app-misc/screen: screen manager with VT100/ANSI terminal emulation",
+            "  Homepage: https://www.gnu.org/software/screen/",
+            "  License: GPL-3+",
+        ]
+    );
+    assert_eq!(
+        lines[6],
+        "  9999:0::gentoo EAPI=8 properties=live restrict=test"
+    );
+}
+
+#[test]
+fn test_overlay_and_atom_display() {
+    let overlay = &fixture().0.overlays[0];
+    assert_eq!(overlay.to_string(), "[gentoo] /var/db/repos/gentoo/");
+    assert_eq!(
+        format!("{:#}", overlay),
+        "[gentoo] /var/db/repos/gentoo/ (priority 0)"
+    );
+
+    let atom = Atom::parse(">=app-misc/screen-5:0::gentoo").unwrap();
+    assert_eq!(atom.to_string(), ">=app-misc/screen-5:0::gentoo");
+}
+
+#[test]
+fn test_package_line_display() {
+    let screen = fixture_package("app-misc", "screen");
+    assert_eq!(
+        screen.display_for("amd64").to_string(),
+        "app-misc/screen  Available: 4.9.1-r1 4.9.1-r2 [M]~5.0.1 **9999  \
+         Homepage: https://www.gnu.org/software/screen/  \
+         Description: screen manager with VT100/ANSI terminal emulation"
+    );
+    assert!(
+        screen
+            .display_for("hppa")
+            .to_string()
+            .contains(" Available: ~4.9.1-r1 ")
+    );
+}
+
+#[test]
+fn test_header_display() {
+    assert_eq!(
+        fixture().0.to_string(),
+        "Database version 39, 174 categories\n\
+         Overlays:\n  [0] gentoo /var/db/repos/gentoo/ (priority 0)\n\
+         Stored: DEPEND, REQUIRED_USE, SRC_URI\n\
+         Hashes: eapi 2, license 1925, keywords 37, iuse 8288, slot 1859, depend 48053\n\
+         World sets: (none)\n"
+    );
+}
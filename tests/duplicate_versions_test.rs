@@ -0,0 +1,101 @@
+mod common;
+
+use eix::{Database, DedupPolicy, EixTestBuilder, OpenOptions, Package, PackageReader};
+
+fn duplicates_db() -> Vec<u8> {
+    EixTestBuilder::new()
+        .overlay("gentoo", "/var/db/repos/gentoo")
+        .overlay("guru", "/var/db/repos/guru")
+        .category("app-misc")
+        .package("screen", |p| {
+            p.version("4.9.1", |v| v.keywords("amd64"))
+                // Another repository: not a duplicate
+                .version("4.9.1", |v| v.keywords("amd64").overlay(1))
+                .version("5.0.0", |v| v.keywords("~amd64"))
+                // Differs only in its keywords
+                .version("4.9.1", |v| v.keywords("~amd64"))
+                // Identical to the first version
+                .version("4.9.1", |v| v.keywords("amd64"))
+        })
+        .package("tmux", |p| {
+            p.version("3.4", |v| v.slot("0"))
+                .version("3.4", |v| v.slot("1"))
+        })
+        .build_bytes()
+}
+
+fn read(options: OpenOptions) -> (Vec<Package>, Vec<String>) {
+    let mut db = Database::from_bytes_with(duplicates_db(), options);
+    let header = db.read_header(0).unwrap();
+    let mut reader = PackageReader::new(db, header);
+    let packages = reader.read_all().unwrap().into_inner();
+    let warnings = reader.warnings().iter().map(|w| w.to_string()).collect();
+    (packages, warnings)
+}
+
+#[test]
+fn test_parse_warnings() {
+    let (packages, warnings) = read(OpenOptions {
+        warn_duplicate_versions: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        warnings,
+        [
+            "app-misc/screen-4.9.1::gentoo: version 3 repeats version 0",
+            "app-misc/screen-4.9.1::gentoo: version 4 repeats version 0",
+        ]
+    );
+    assert_eq!(packages[0].find_duplicate_versions(), [(0, 3), (0, 4)]);
+    assert!(packages[1].find_duplicate_versions().is_empty());
+
+    let (_, warnings) = read(OpenOptions::default());
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_dedup_read_versions() {
+    let (packages, _) = read(OpenOptions::default());
+    let versions = |p: &Package| -> Vec<String> {
+        p.versions
+            .iter()
+            .map(|v| {
+                format!(
+                    "{}::{} {}",
+                    v.version_string,
+                    v.reponame,
+                    v.keywords_string()
+                )
+            })
+            .collect()
+    };
+
+    // Spans differ between the occurrences and are not compared
+    let mut identical = packages[0].clone();
+    assert_eq!(identical.dedup_versions(DedupPolicy::IdenticalOnly), 1);
+    assert_eq!(
+        versions(&identical),
+        [
+            "4.9.1::gentoo amd64",
+            "4.9.1::guru amd64",
+            "5.0.0::gentoo ~amd64",
+            "4.9.1::gentoo ~amd64",
+        ]
+    );
+
+    let mut first = packages[0].clone();
+    assert_eq!(first.dedup_versions(DedupPolicy::KeepFirst), 2);
+    assert_eq!(
+        versions(&first),
+        [
+            "4.9.1::gentoo amd64",
+            "4.9.1::guru amd64",
+            "5.0.0::gentoo ~amd64",
+        ]
+    );
+
+    // The fixture has no duplicates
+    for package in common::fixture_packages() {
+        assert!(package.find_duplicate_versions().is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use eix::{CompareOptions, compare_packages};
 use eix::{Database, PackageReader, DB_VERSION_CURRENT, Package};
 use std::fs::File;
 use std::io::BufReader;
@@ -13,8 +14,8 @@ fn test_eix2json_consistency() {
     let mut reader = PackageReader::new(db, header);
     let mut packages = Vec::new();
 
-    while let Ok(true) = reader.next_category() {
-        while let Ok(Some(pkg)) = reader.read_package() {
+    while reader.next_category().expect("Failed to read category") {
+        while let Some(pkg) = reader.read_package().expect("Failed to read package") {
             packages.push(pkg);
         }
     }
@@ -24,19 +25,27 @@ fn test_eix2json_consistency() {
     let reader = BufReader::new(json_file);
     let reference_packages: Vec<Package> = serde_json::from_reader(reader).expect("Failed to parse reference json");
 
-    // 3. Compare
-    assert_eq!(packages.len(), reference_packages.len(), "Number of packages differs");
-    
-    for (i, (pkg, ref_pkg)) in packages.iter().zip(reference_packages.iter()).enumerate() {
-        assert_eq!(pkg.name, ref_pkg.name, "Package name mismatch at index {}", i);
-        assert_eq!(pkg.category, ref_pkg.category, "Package category mismatch for {}", pkg.name);
-        assert_eq!(pkg.versions.len(), ref_pkg.versions.len(), "Version count mismatch for {}", pkg.name);
-        
-        for (j, (v, ref_v)) in pkg.versions.iter().zip(ref_pkg.versions.iter()).enumerate() {
-            assert_eq!(v.version_string, ref_v.version_string, "Version string mismatch for {} version index {}", pkg.name, j);
-            assert_eq!(v.eapi, ref_v.eapi, "EAPI mismatch for {} version {}", pkg.name, v.version_string);
-            assert_eq!(v.slot, ref_v.slot, "Slot mismatch for {} version {}", pkg.name, v.version_string);
-            assert_eq!(v.reponame, ref_v.reponame, "Reponame mismatch for {} version {}", pkg.name, v.version_string);
-        }
-    }
+    // 3. Compare; the JSON holds no version parts
+    let options = CompareOptions {
+        ignore_parts: true,
+        ..Default::default()
+    };
+    let differences = compare_packages(&packages, &reference_packages, options);
+    let report: Vec<String> = differences.iter().map(ToString::to_string).collect();
+    assert!(
+        differences.is_empty(),
+        "{} differences:\n{}",
+        differences.len(),
+        report.join("\n")
+    );
+
+    assert!(
+        packages
+            .iter()
+            .map(Package::key)
+            .eq(reference_packages.iter().map(Package::key)),
+        "Order of packages differs"
+    );
+    // Equality ignores the parts as well
+    assert!(packages == reference_packages);
 }
@@ -0,0 +1,170 @@
+mod common;
+
+use common::fixture_packages;
+use eix::{CsvColumn, CsvOptions, Database, EixTestBuilder, PackageReader, write_csv};
+#[cfg(feature = "json")]
+use eix::{NdjsonOptions, Package, SerializeOptions, write_ndjson, write_ndjson_with};
+#[cfg(feature = "json")]
+use serde_json::Value;
+
+fn reader(bytes: Vec<u8>) -> PackageReader {
+    let mut db = Database::from_bytes(bytes);
+    let header = db.read_header(0).unwrap();
+    PackageReader::new(db, header)
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_ndjson_one_package_per_line() {
+    let packages = fixture_packages();
+    let mut out = Vec::new();
+    let written = write_ndjson(
+        reader(std::fs::read(common::FIXTURE_EIX).unwrap()),
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(written, packages.len() as u64);
+
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(text.lines().count(), packages.len());
+    for (line, package) in text.lines().zip(packages) {
+        let parsed: Package = serde_json::from_str(line).unwrap();
+        assert_eq!(parsed.category, package.category);
+        assert_eq!(parsed.name, package.name);
+        assert_eq!(parsed.versions.len(), package.versions.len());
+        assert_eq!(line, serde_json::to_string(package).unwrap());
+    }
+}
+
+#[cfg(all(feature = "json", feature = "sample-data"))]
+#[test]
+fn test_ndjson_header_line() {
+    let options = NdjsonOptions {
+        include_header: true,
+        serialize: SerializeOptions {
+            joined_words: true,
+            ..Default::default()
+        },
+    };
+    let mut out = Vec::new();
+    let bytes = eix::sample::bytes().to_vec();
+    let written = write_ndjson_with(reader(bytes), &mut out, &options).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len() as u64, written + 1);
+
+    let header: Value = serde_json::from_str(lines[0]).unwrap();
+    assert!(header["header"]["overlays"].is_array());
+    assert!(header["header"]["world_sets"].is_array());
+    let package: Value = serde_json::from_str(lines[1]).unwrap();
+    assert!(package["versions"][0]["keywords"].is_string());
+}
+
+#[cfg(all(feature = "json", feature = "sample-data"))]
+#[test]
+fn test_ndjson_reports_errors_with_context() {
+    let mut bytes = eix::sample::bytes().to_vec();
+    bytes.truncate(bytes.len() - 10);
+    let mut out = Vec::new();
+    let err = write_ndjson(reader(bytes), &mut out).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Error reading package 4 in category "),
+        "{}",
+        err
+    );
+    assert!(err.to_string().contains(" at byte "));
+
+    // The packages before the error are complete lines
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(text.lines().count(), 3);
+    for line in text.lines() {
+        serde_json::from_str::<Package>(line).unwrap();
+    }
+}
+
+/// Splits CSV into rows of fields, unquoting them
+fn parse_csv(csv: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    rows
+}
+
+#[test]
+fn test_csv_quotes_fields() {
+    let description = "Terminal multiplexer, \"screen\" for short,\nwith a second line";
+    let bytes = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| {
+            p.description(description)
+                .version("4.9.1", |v| v.keywords("amd64 ~arm64").slot("0"))
+                .version("5.0.0", |v| v.keywords("~amd64").slot("0"))
+        })
+        .build_bytes();
+    let options = CsvOptions {
+        columns: CsvColumn::parse_list("name, version,keywords,description").unwrap(),
+        header: true,
+    };
+    let mut out = Vec::new();
+    assert_eq!(write_csv(reader(bytes), &mut out, &options).unwrap(), 2);
+    let csv = String::from_utf8(out).unwrap();
+    assert!(csv.contains(r#""Terminal multiplexer, ""screen"" for short,"#));
+    assert_eq!(
+        parse_csv(&csv),
+        [
+            vec!["name", "version", "keywords", "description"],
+            vec!["screen", "4.9.1", "amd64 ~arm64", description],
+            vec!["screen", "5.0.0", "~amd64", description],
+        ]
+    );
+
+    let err = CsvColumn::parse_list("name,size").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_csv_rows_are_flat_records() {
+    let packages = fixture_packages();
+    let mut out = Vec::new();
+    let options = CsvOptions::default();
+    let rows = write_csv(
+        reader(std::fs::read(common::FIXTURE_EIX).unwrap()),
+        &mut out,
+        &options,
+    )
+    .unwrap();
+    let versions = packages.iter().map(|p| p.versions.len()).sum::<usize>();
+    assert_eq!(rows, versions as u64);
+
+    let parsed = parse_csv(&String::from_utf8(out).unwrap());
+    assert_eq!(parsed[0], eix::FlatRecord::FIELDS);
+    let records = packages.iter().flat_map(|p| {
+        p.versions
+            .iter()
+            .map(|v| v.flat_record(&p.category, &p.name))
+    });
+    for (row, record) in parsed[1..].iter().zip(records) {
+        assert_eq!(*row, record.values());
+    }
+    assert_eq!(parsed.len(), versions + 1);
+}
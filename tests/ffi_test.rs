@@ -0,0 +1,110 @@
+#![cfg(feature = "ffi")]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::ffi::*;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+/// Copies a string owned by the library
+unsafe fn borrowed(s: *const c_char) -> String {
+    assert!(!s.is_null());
+    unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string()
+}
+
+/// Copies and frees a string returned by an accessor
+unsafe fn owned(s: *mut c_char) -> String {
+    let copy = unsafe { borrowed(s) };
+    unsafe { eix_free_string(s) };
+    copy
+}
+
+fn empty_package() -> EixPackage {
+    EixPackage {
+        category: ptr::null(),
+        name: ptr::null(),
+        description: ptr::null(),
+        homepage: ptr::null(),
+        licenses: ptr::null(),
+        version_count: 0,
+        handle: ptr::null(),
+    }
+}
+
+#[test]
+fn test_ffi_matches_parser() {
+    let path = CString::new(FIXTURE_EIX).unwrap();
+    let mut expected = fixture_packages().iter();
+    unsafe {
+        let db = eix_open(path.as_ptr());
+        assert!(!db.is_null());
+        let mut pkg = empty_package();
+        while eix_next_package(db, &mut pkg) == 1 {
+            let want = expected.next().unwrap();
+            assert_eq!(borrowed(pkg.category), want.category);
+            assert_eq!(borrowed(pkg.name), want.name);
+            assert_eq!(borrowed(pkg.description), want.description);
+            assert_eq!(borrowed(pkg.licenses), want.licenses);
+            assert_eq!(pkg.version_count, want.versions.len());
+            for (i, v) in want.versions.iter().enumerate() {
+                assert_eq!(owned(eix_version_string(&pkg, i)), v.get_full_version());
+                assert_eq!(owned(eix_version_slot(&pkg, i)), v.normalized_slot());
+                assert_eq!(owned(eix_version_eapi(&pkg, i)), v.eapi);
+                assert_eq!(owned(eix_version_keywords(&pkg, i)), v.keywords.join(" "));
+                assert_eq!(owned(eix_version_iuse(&pkg, i)), v.iuse.join(" "));
+                assert_eq!(owned(eix_version_repository(&pkg, i)), *v.reponame);
+                assert_eq!(eix_version_mask_flags(&pkg, i), v.mask_flags as i32);
+                assert_eq!(eix_version_properties_flags(&pkg, i), v.properties_flags);
+                assert_eq!(eix_version_restrict_flags(&pkg, i), v.restrict_flags);
+            }
+            let past_end = pkg.version_count;
+            assert!(eix_version_string(&pkg, past_end).is_null());
+            assert_eq!(eix_version_mask_flags(&pkg, past_end), -1);
+            assert_eq!(eix_version_properties_flags(&pkg, past_end), 0);
+        }
+        assert!(expected.next().is_none());
+        assert_eq!(eix_next_package(db, &mut pkg), 0);
+        assert!(eix_last_error(db).is_null());
+        eix_free_db(db);
+    }
+}
+
+#[test]
+fn test_ffi_errors() {
+    let missing = CString::new("testdata/missing.eix").unwrap();
+    unsafe {
+        assert!(eix_open(missing.as_ptr()).is_null());
+        assert!(borrowed(eix_last_error(ptr::null())).contains("No such file"));
+        assert!(eix_open(ptr::null()).is_null());
+        assert_eq!(borrowed(eix_last_error(ptr::null())), "Path is NULL");
+        assert_eq!(eix_next_package(ptr::null_mut(), &mut empty_package()), -1);
+        assert!(eix_version_string(ptr::null(), 0).is_null());
+        eix_free_db(ptr::null_mut());
+        eix_free_string(ptr::null_mut());
+    }
+}
+
+#[test]
+fn test_ffi_truncated_file() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let truncated = dir.path().join("truncated.eix");
+    std::fs::write(&truncated, &data[..data.len() / 2]).unwrap();
+    let path = CString::new(truncated.to_str().unwrap()).unwrap();
+    unsafe {
+        let db = eix_open(path.as_ptr());
+        assert!(!db.is_null());
+        let mut pkg = empty_package();
+        let mut status;
+        loop {
+            status = eix_next_package(db, &mut pkg);
+            if status != 1 {
+                break;
+            }
+        }
+        assert_eq!(status, -1);
+        assert!(!borrowed(eix_last_error(db)).is_empty());
+        eix_free_db(db);
+    }
+}
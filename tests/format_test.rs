@@ -0,0 +1,146 @@
+mod common;
+
+use common::{fixture_package, fixture_packages};
+use eix::{
+    AtomForm, AtomListStyle, Column, InstalledIndex, InstalledVersion, PackageQuery,
+    TableFormatter, TableStyle, format_compact, format_compact_with, read_atom_list,
+    write_atom_list,
+};
+
+fn installed(version: &str) -> InstalledIndex {
+    let mut index = InstalledIndex::new();
+    index
+        .insert(InstalledVersion::new("app-misc", "screen", version, "0", Some("gentoo")).unwrap());
+    index
+}
+
+#[test]
+fn test_format_compact() {
+    let screen = fixture_package("app-misc", "screen");
+    assert_eq!(
+        format_compact(screen, "amd64"),
+        "[N] app-misc/screen (4.9.1-r2): screen manager with VT100/ANSI terminal emulation"
+    );
+    assert_eq!(
+        format_compact_with(screen, "amd64", &installed("4.9.1-r1")),
+        "[U] app-misc/screen (4.9.1-r1 -> 4.9.1-r2): screen manager with VT100/ANSI terminal emulation"
+    );
+    assert_eq!(
+        format_compact_with(screen, "amd64", &installed("4.9.1-r2")),
+        "[I] app-misc/screen (4.9.1-r2): screen manager with VT100/ANSI terminal emulation"
+    );
+    // Only testing keywords
+    assert!(format_compact(screen, "riscv").starts_with("[N] app-misc/screen (~4.9.1-r2): "));
+    // Not keyworded at all
+    assert_eq!(
+        format_compact(screen, "x86-winnt"),
+        "[N] app-misc/screen: screen manager with VT100/ANSI terminal emulation"
+    );
+}
+
+#[test]
+fn test_table_aligned() {
+    let table = TableFormatter::parse("version,mask_flags,restrict,slot,repo", TableStyle::Aligned)
+        .unwrap();
+    assert_eq!(
+        table.format([fixture_package("app-misc", "screen")]),
+        "version   mask_flags  restrict  slot  repo\n\
+         4.9.1-r1  0                     0     gentoo\n\
+         4.9.1-r2  0                     0     gentoo\n\
+         5.0.1     1           test      0     gentoo\n\
+         9999      0           test      0     gentoo\n"
+    );
+}
+
+#[test]
+fn test_table_tsv() {
+    let table = TableFormatter::new(
+        vec![Column::Name, Column::Version, Column::Mask, Column::Slot],
+        TableStyle::Tsv,
+    );
+    let zstd = fixture_package("app-arch", "zstd");
+    assert_eq!(table.header(), "name\tversion\tmask\tslot");
+    assert_eq!(
+        table.format_package(zstd),
+        "app-arch/zstd\t1.5.7\t\t0/1\napp-arch/zstd\t1.5.7-r1\t\t0/1\n"
+    );
+    assert_eq!(
+        table.format([zstd]),
+        format!("{}\n{}", table.header(), table.format_package(zstd))
+    );
+}
+
+fn atom_list<'a>(
+    packages: impl IntoIterator<Item = &'a eix::Package>,
+    style: AtomListStyle,
+) -> String {
+    let mut out = Vec::new();
+    write_atom_list(packages, &mut out, style).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_atom_list_forms() {
+    let screen = fixture_package("app-misc", "screen");
+    let zstd = fixture_package("app-arch", "zstd");
+    let packages = [screen, zstd, screen];
+    assert_eq!(
+        atom_list(packages, AtomListStyle::default()),
+        "app-misc/screen\napp-arch/zstd\napp-misc/screen\n"
+    );
+    assert_eq!(
+        atom_list(packages, AtomListStyle::new(AtomForm::Cp).sorted()),
+        "app-arch/zstd\napp-misc/screen\n"
+    );
+    assert_eq!(
+        atom_list(packages, AtomListStyle::new(AtomForm::Cpv).sorted()),
+        "app-arch/zstd-1.5.7-r1\napp-misc/screen-9999\n"
+    );
+    let style = AtomListStyle {
+        dedup: true,
+        ..AtomListStyle::new(AtomForm::EqualsCpv)
+    };
+    assert_eq!(
+        atom_list(packages, style),
+        "=app-misc/screen-9999\n=app-arch/zstd-1.5.7-r1\n"
+    );
+    assert_eq!(atom_list([], style), "");
+}
+
+#[test]
+fn test_atom_list_round_trip() {
+    let query = PackageQuery::category("app-misc");
+    let matching: Vec<_> = fixture_packages()
+        .iter()
+        .filter(|p| query.matches(p))
+        .collect();
+    assert!(!matching.is_empty());
+
+    for form in [AtomForm::Cp, AtomForm::EqualsCpv] {
+        let list = atom_list(matching.iter().copied(), AtomListStyle::new(form));
+        assert!(list.lines().all(|line| line.contains("app-misc/")));
+        let atoms = read_atom_list(list.as_bytes()).unwrap();
+        let written: Vec<String> = list.lines().map(str::to_string).collect();
+        let parsed: Vec<String> = atoms.iter().map(|a| a.to_string()).collect();
+        assert_eq!(parsed, written);
+
+        // The list selects the same packages again
+        let by_atoms = PackageQuery {
+            atoms: Some(atoms),
+            ..Default::default()
+        };
+        assert!(
+            fixture_packages()
+                .iter()
+                .filter(|p| by_atoms.matches(p))
+                .map(|p| p as *const _)
+                .eq(matching.iter().map(|&p| p as *const _))
+        );
+    }
+
+    let atoms = read_atom_list("# comment\n\n  dev-lang/python:3.12  \n".as_bytes()).unwrap();
+    assert_eq!(atoms.len(), 1);
+    assert_eq!(atoms[0].slot.as_deref(), Some("3.12"));
+    let e = read_atom_list("!app-misc/screen\n".as_bytes()).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
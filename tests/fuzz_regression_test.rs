@@ -0,0 +1,61 @@
+//! Inputs which the parse path used to misread; each must now fail as
+//! invalid data. Inputs found by the targets in `fuzz/` belong here,
+//! minimized and inline.
+
+use eix::{Database, EixTestBuilder, parse_bytes};
+use std::io;
+
+fn assert_invalid(data: &[u8]) {
+    let err = parse_bytes(data).expect_err("invalid input accepted");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", err);
+}
+
+#[test]
+fn test_parse_bytes_round_trip() {
+    let bytes = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| p.version("4.9.1", |v| v.keywords("amd64")))
+        .build_bytes();
+    let (header, packages) = parse_bytes(&bytes).unwrap();
+    assert_eq!(header.size, 1);
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].name, "screen");
+    assert_eq!(packages[0].versions[0].keywords, ["amd64"]);
+}
+
+#[test]
+fn test_long_escape_run() {
+    // More than eight 0xFF bytes in a row as the database version
+    assert_invalid(b"eix\n\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01");
+    assert_invalid(b"eix\n\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff");
+}
+
+#[test]
+fn test_database_version_out_of_range() {
+    // 2^32 + 39 used to be truncated to the current version 39
+    assert_invalid(b"eix\n\xff\xff\xff\xff\x01\x00\x00\x00\x27\x01\x00");
+}
+
+#[test]
+fn test_category_count_out_of_range() {
+    // 2^32 + 1 categories used to be truncated to one
+    assert_invalid(b"eix\n\x27\xff\xff\xff\xff\x01\x00\x00\x00\x01\x00");
+}
+
+#[test]
+fn test_package_count_out_of_range() {
+    let mut bytes = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| p.version("4.9.1", |v| v))
+        .build_bytes();
+    // Replace everything after the header by a category with 2^32
+    // packages, which used to be truncated to none
+    let header_len = {
+        let mut db = Database::from_bytes(bytes.as_slice());
+        db.read_header(0).unwrap();
+        db.position() as usize
+    };
+    bytes.truncate(header_len);
+    bytes.extend(b"\x08app-misc\xff\xff\xff\xff\x01\x00\x00\x00\x00");
+    assert_invalid(&bytes);
+}
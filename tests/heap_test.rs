@@ -0,0 +1,78 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{EixTestBuilder, HeapSize, ParsedDb};
+
+fn sum(size: HeapSize) -> usize {
+    size.strings + size.vectors + size.hash_tables
+}
+
+#[test]
+fn test_estimate_grows_with_data() {
+    let builder = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| p.version("4.9.1", |v| v.keywords("amd64")));
+    let (_, packages) = builder.clone().build();
+    let (_, more) = builder
+        .package("tmux", |p| p.version("3.4", |v| v.keywords("amd64 ~arm64")))
+        .build();
+
+    let total = |packages: &[eix::Package]| -> usize {
+        packages.iter().map(|p| p.approx_heap_size()).sum()
+    };
+    assert!(total(&more) > total(&packages));
+
+    let mut package = packages[0].clone();
+    let before = package.heap_breakdown();
+    package
+        .description
+        .push_str("screen manager with VT100/ANSI terminal emulation");
+    let after = package.heap_breakdown();
+    assert!(after.strings > before.strings);
+    assert_eq!(after.vectors, before.vectors);
+
+    let mut version = package.versions[0].clone();
+    version.keywords.reserve(16);
+    assert!(version.approx_heap_size() > package.versions[0].approx_heap_size());
+
+    // Building the map for writing adds a hash table
+    let header = fixture().0.clone();
+    let before = header.heap_breakdown();
+    header.keywords_hash.get_index("amd64");
+    let after = header.heap_breakdown();
+    assert_eq!(before.hash_tables, 0);
+    assert!(after.hash_tables > 0);
+    assert!(after.strings > before.strings);
+}
+
+#[test]
+fn test_breakdown_sums_to_total() {
+    let (header, packages) = fixture();
+    assert_eq!(sum(header.heap_breakdown()), header.approx_heap_size());
+    for package in packages.iter().take(100) {
+        assert_eq!(sum(package.heap_breakdown()), package.approx_heap_size());
+        for version in &package.versions {
+            assert_eq!(sum(version.heap_breakdown()), version.approx_heap_size());
+        }
+    }
+
+    let db = ParsedDb::load(FIXTURE_EIX).unwrap();
+    let breakdown = db.heap_breakdown();
+    assert_eq!(sum(breakdown), db.approx_heap_size());
+    let parts = db.header.approx_heap_size()
+        + db.packages
+            .iter()
+            .map(|p| p.approx_heap_size())
+            .sum::<usize>();
+    assert!(db.approx_heap_size() > parts);
+    assert!(breakdown.strings > 0 && breakdown.vectors > 0);
+}
+
+#[test]
+fn test_estimate_is_consistent() {
+    let a = ParsedDb::load(FIXTURE_EIX).unwrap();
+    let b = ParsedDb::load(FIXTURE_EIX).unwrap();
+    assert_eq!(a.heap_breakdown(), b.heap_breakdown());
+    // The file is 20 MB; the parsed tree is several times larger
+    assert!(a.approx_heap_size() > a.info.file_size as usize);
+}
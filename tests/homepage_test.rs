@@ -0,0 +1,41 @@
+mod common;
+
+use common::{fixture_package, fixture_packages};
+use eix::SerializeOptions;
+
+#[test]
+fn test_fixture_homepages() {
+    let lua = fixture_package("dev-lang", "lua");
+    assert_eq!(lua.homepages(), vec!["https://www.lua.org/"]);
+
+    let python = fixture_package("dev-lang", "python");
+    assert_eq!(
+        python.homepages(),
+        vec![
+            "https://www.python.org/",
+            "https://github.com/python/cpython/"
+        ]
+    );
+
+    let group = fixture_package("acct-group", "3proxy");
+    assert!(group.homepages().is_empty());
+    assert!(group.invalid_homepages().is_empty());
+}
+
+#[test]
+fn test_fixture_homepage_array_serialization() {
+    let options = SerializeOptions {
+        homepage_as_array: true,
+        ..Default::default()
+    };
+    for pkg in fixture_packages().iter().take(500) {
+        let value = serde_json::to_value(options.view(pkg)).unwrap();
+        let urls: Vec<&str> = value["homepage"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|u| u.as_str().unwrap())
+            .collect();
+        assert_eq!(urls, pkg.homepages());
+    }
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "fs")]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::{Atom, Database, Package, PackageIndex, PackageMap, PackageQuery, PackageReader};
+
+fn reader() -> PackageReader {
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    let header = db.read_header(0).unwrap();
+    PackageReader::new(db, header)
+}
+
+fn cps<'a>(packages: impl IntoIterator<Item = &'a Package>) -> Vec<String> {
+    packages
+        .into_iter()
+        .map(|p| format!("{}/{}", p.category, p.name))
+        .collect()
+}
+
+#[test]
+fn test_map_lookups() {
+    let map = PackageMap::from_reader(&mut reader()).unwrap();
+    assert_eq!(map.len(), fixture_packages().len());
+    for package in fixture_packages().iter().step_by(97) {
+        let cp = format!("{}/{}", package.category, package.name);
+        let found = map.get(&cp).unwrap();
+        assert_eq!(found.versions.len(), package.versions.len());
+    }
+    assert!(map.get("dev-lang/python").is_some());
+    assert!(map.get("dev-lang/no-such-package").is_none());
+    assert!(
+        map.get_atom(&Atom::parse(">=dev-lang/python-3").unwrap())
+            .is_some()
+    );
+    assert!(
+        map.get_atom(&Atom::parse(">=dev-lang/python-999").unwrap())
+            .is_none()
+    );
+
+    let json = serde_json::to_string(&map).unwrap();
+    let back: PackageMap = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.len(), map.len());
+    assert_eq!(
+        back.get("dev-lang/python").unwrap().description,
+        map.get("dev-lang/python").unwrap().description
+    );
+}
+
+#[test]
+fn test_index_ranges() {
+    let index = PackageIndex::from_reader(&mut reader()).unwrap();
+    assert_eq!(index.len(), fixture_packages().len());
+    assert_eq!(
+        cps(index.packages()),
+        cps(PackageIndex::from(fixture_packages().to_vec()).packages())
+    );
+
+    let mut expected: Vec<&Package> = fixture_packages()
+        .iter()
+        .filter(|p| p.category == "dev-python")
+        .collect();
+    expected.sort_by(|a, b| a.name.cmp(&b.name));
+    assert!(!expected.is_empty());
+    assert_eq!(cps(index.category_range("dev-python")), cps(expected));
+
+    let dev = cps(index.category_prefix("dev-"));
+    assert!(dev.len() > 1000);
+    assert!(dev.is_sorted());
+    assert_eq!(
+        dev.len(),
+        fixture_packages()
+            .iter()
+            .filter(|p| p.category.starts_with("dev-"))
+            .count()
+    );
+
+    let py: Vec<&Package> = index.name_prefix("py").collect();
+    assert!(py.iter().any(|p| p.name == "python"));
+    assert!(py.iter().all(|p| p.name.starts_with("py")));
+    assert_eq!(
+        py.len(),
+        fixture_packages()
+            .iter()
+            .filter(|p| p.name.starts_with("py"))
+            .count()
+    );
+
+    let json = serde_json::to_string(&index).unwrap();
+    let back: PackageIndex = serde_json::from_str(&json).unwrap();
+    assert_eq!(cps(back.packages()), cps(index.packages()));
+}
+
+#[test]
+fn test_query_execution() {
+    let index = PackageIndex::from(fixture_packages().to_vec());
+    let map: PackageMap = fixture_packages().iter().cloned().collect();
+    let reference = |query: &PackageQuery| {
+        let mut matches: Vec<&Package> = fixture_packages()
+            .iter()
+            .filter(|p| query.matches(p))
+            .collect();
+        matches.sort_by(|a, b| (&a.category, &a.name).cmp(&(&b.category, &b.name)));
+        cps(matches)
+    };
+
+    let pruned = PackageQuery {
+        name: Some("python".to_string()),
+        ..PackageQuery::category("dev-lang")
+    };
+    let unpruned = PackageQuery {
+        name: Some("py".to_string()),
+        ..Default::default()
+    };
+    let atoms = PackageQuery {
+        atoms: Some(vec![
+            Atom::parse("dev-lang/python").unwrap(),
+            Atom::parse(">=dev-lang/python-3").unwrap(),
+            Atom::parse("app-misc/no-such-package").unwrap(),
+        ]),
+        ..Default::default()
+    };
+    for query in [&pruned, &unpruned, &atoms] {
+        let expected = reference(query);
+        assert!(!expected.is_empty());
+        assert_eq!(cps(query.execute_index(&index)), expected);
+        let mut from_map = cps(query.execute_map(&map));
+        from_map.sort();
+        assert_eq!(from_map, expected);
+    }
+    assert_eq!(cps(atoms.execute_map(&map)), ["dev-lang/python"]);
+    assert!(
+        PackageQuery::category("no-such-category")
+            .execute_index(&index)
+            .is_empty()
+    );
+}
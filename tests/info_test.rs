@@ -0,0 +1,75 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{DEFAULT_HASH_SAMPLE, Database, header_report, header_report_with};
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_fixture_info() {
+    let info = Database::info(FIXTURE_EIX).unwrap();
+    assert_eq!(
+        info.file_size,
+        std::fs::metadata(FIXTURE_EIX).unwrap().len()
+    );
+    assert_eq!(info.db_version, 39);
+    assert_eq!(info.category_count, 174);
+    assert_eq!(info.overlay_count, 1);
+    assert!(info.modified <= SystemTime::now());
+    assert!(info.age() > Duration::ZERO);
+
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["db_version"], 39);
+    assert_eq!(json["path"], FIXTURE_EIX);
+}
+
+#[test]
+fn test_fixture_header_report() {
+    let (header, _) = fixture();
+    let report = header_report(FIXTURE_EIX).unwrap();
+    assert_eq!(report.db_version, 39);
+    assert_eq!(report.category_count, 174);
+    assert_eq!(report.overlays.len(), 1);
+    assert_eq!(report.overlays[0].index, 0);
+    assert_eq!(report.overlays[0].label, "gentoo");
+    assert_eq!(report.overlays[0].path, "/var/db/repos/gentoo/");
+    assert!(report.use_depend && report.use_required_use && report.use_src_uri);
+
+    let sizes: Vec<(&str, usize)> = report.hashes.iter().map(|h| (h.name, h.entries)).collect();
+    assert_eq!(
+        sizes,
+        [
+            ("eapi", header.eapi_hash.len()),
+            ("license", 1925),
+            ("keywords", header.keywords_hash.len()),
+            ("iuse", header.iuse_hash.len()),
+            ("slot", 1859),
+            ("depend", 48053),
+        ]
+    );
+    assert_eq!(report.hashes[2].sample[..3], ["amd64", "~riscv", "x86"]);
+    assert!(
+        report
+            .hashes
+            .iter()
+            .all(|h| h.sample.len() == h.entries.min(DEFAULT_HASH_SAMPLE))
+    );
+
+    // The header ends where the first category starts
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    db.read_header(0).unwrap();
+    assert_eq!(report.header_len, db.position());
+
+    let text = report.to_string();
+    assert!(text.starts_with("Database version 39, 174 categories"));
+    assert!(text.contains("  [0] gentoo /var/db/repos/gentoo/\n"));
+    assert!(text.contains("  depend     48053 entries: \"(\", \")\""));
+    assert!(text.contains("  license     1925 entries: "));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["overlays"][0]["label"], "gentoo");
+    assert_eq!(json["hashes"][5]["entries"], 48053);
+
+    let sampled = header_report_with(FIXTURE_EIX, 0).unwrap();
+    assert!(sampled.hashes.iter().all(|h| h.sample.is_empty()));
+    assert!(sampled.to_string().contains("  eapi           2 entries\n"));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::fixture_packages;
+use eix::{Package, SerializeOptions};
+
+/// Serializes `packages` with `options` and reads them back
+fn round_trip(packages: &[Package], options: &SerializeOptions) -> (String, Vec<Package>) {
+    let json = serde_json::to_string(&options.view_list(packages)).unwrap();
+    let back = serde_json::from_str(&json).unwrap();
+    (json, back)
+}
+
+#[test]
+fn test_fixture_round_trips_through_both_forms() {
+    let packages = &fixture_packages()[..2000];
+    let arrays = SerializeOptions::default();
+    let joined = SerializeOptions {
+        joined_words: true,
+        ..Default::default()
+    };
+
+    let (array_json, from_arrays) = round_trip(packages, &arrays);
+    let (joined_json, from_joined) = round_trip(packages, &joined);
+    assert_ne!(array_json, joined_json);
+    assert_eq!(array_json, serde_json::to_string(packages).unwrap());
+
+    // Both read back to the same packages, which serialize as before
+    assert_eq!(round_trip(&from_arrays, &joined).0, joined_json);
+    assert_eq!(round_trip(&from_joined, &arrays).0, array_json);
+    assert_eq!(round_trip(&from_joined, &joined).0, joined_json);
+
+    let values: Vec<serde_json::Value> = serde_json::from_str(&joined_json).unwrap();
+    for (package, value) in packages.iter().zip(&values) {
+        for (version, value) in package
+            .versions
+            .iter()
+            .zip(value["versions"].as_array().unwrap())
+        {
+            assert_eq!(value["keywords"], version.keywords_string());
+            assert_eq!(value["iuse"], version.iuse.join(" "));
+        }
+    }
+}
+
+#[test]
+fn test_keywords_string() {
+    let python = common::fixture_package("dev-lang", "python");
+    for version in &python.versions {
+        let joined = version.keywords_string();
+        let words: Vec<&str> = joined.split(' ').filter(|w| !w.is_empty()).collect();
+        assert_eq!(words, version.keywords);
+    }
+    assert!(
+        python
+            .versions
+            .iter()
+            .any(|v| v.keywords_string().contains(" ~"))
+    );
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{
+    ConversionStats, JsonFormat, JsonOptions, PACKAGE_FIELDS, Package, SerializeOptions,
+    eix_to_json,
+};
+use serde_json::Value;
+use std::io::{self, Write};
+use std::sync::OnceLock;
+
+fn fixture_bytes() -> &'static [u8] {
+    static BYTES: OnceLock<Vec<u8>> = OnceLock::new();
+    BYTES.get_or_init(|| std::fs::read(FIXTURE_EIX).unwrap())
+}
+
+fn convert(options: JsonOptions) -> (String, ConversionStats) {
+    let mut out = Vec::new();
+    let stats = eix_to_json(fixture_bytes(), &mut out, options).unwrap();
+    assert_eq!(stats.bytes_written, out.len() as u64);
+    (String::from_utf8(out).unwrap(), stats)
+}
+
+#[test]
+fn test_default_matches_serde() {
+    let (_, packages) = fixture();
+    let (pretty, stats) = convert(JsonOptions::default());
+    assert_eq!(
+        pretty,
+        serde_json::to_string_pretty(packages).unwrap() + "\n"
+    );
+    assert_eq!(stats.packages, 19176);
+    assert_eq!(stats.versions, 31730);
+    assert_eq!(stats.categories, 174);
+
+    let (compact, _) = convert(JsonOptions {
+        format: JsonFormat::Compact,
+        ..Default::default()
+    });
+    assert_eq!(compact, serde_json::to_string(packages).unwrap() + "\n");
+
+    let (ndjson, _) = convert(JsonOptions {
+        format: JsonFormat::Ndjson,
+        ..Default::default()
+    });
+    let expected: String = packages
+        .iter()
+        .map(|p| serde_json::to_string(p).unwrap() + "\n")
+        .collect();
+    assert_eq!(ndjson, expected);
+
+    // The representation of the packages is passed on
+    let serialize = SerializeOptions {
+        joined_words: true,
+        ..Default::default()
+    };
+    let (joined, _) = convert(JsonOptions {
+        format: JsonFormat::Compact,
+        serialize: serialize.clone(),
+        ..Default::default()
+    });
+    assert_eq!(
+        joined,
+        serde_json::to_string(&serialize.view_list(packages)).unwrap() + "\n"
+    );
+}
+
+/// The package objects of `output`, and the header if there is one
+fn documents(output: &str, format: JsonFormat, header: bool) -> (Option<Value>, Vec<Value>) {
+    if format == JsonFormat::Ndjson {
+        let mut lines = output
+            .lines()
+            .map(|l| serde_json::from_str::<Value>(l).unwrap());
+        let first = header.then(|| lines.next().unwrap()["header"].clone());
+        return (first, lines.collect());
+    }
+    assert_eq!(output.contains("\n  "), format == JsonFormat::Pretty);
+    let value: Value = serde_json::from_str(output).unwrap();
+    if header {
+        let packages = value["packages"].as_array().unwrap().clone();
+        (Some(value["header"].clone()), packages)
+    } else {
+        (None, value.as_array().unwrap().clone())
+    }
+}
+
+#[test]
+fn test_option_combinations() {
+    let (header, packages) = fixture();
+    let selections: [&[&str]; 3] = [&[], &["name", "category"], &["versions"]];
+    for format in [JsonFormat::Pretty, JsonFormat::Compact, JsonFormat::Ndjson] {
+        for include_header in [false, true] {
+            for fields in selections {
+                let options = JsonOptions {
+                    format,
+                    fields: fields.iter().map(|f| f.to_string()).collect(),
+                    include_header,
+                    ..Default::default()
+                };
+                let context = format!("{:?}", options);
+                let (output, stats) = convert(options);
+                let (doc_header, docs) = documents(&output, format, include_header);
+                assert_eq!(docs.len(), packages.len(), "{}", context);
+                assert_eq!(stats.packages, packages.len(), "{}", context);
+
+                if let Some(doc_header) = doc_header {
+                    assert_eq!(doc_header["version"], header.version, "{}", context);
+                    assert_eq!(doc_header["overlays"][0]["label"], "gentoo");
+                }
+                let expected = if fields.is_empty() {
+                    PACKAGE_FIELDS.len()
+                } else {
+                    fields.len()
+                };
+                assert_eq!(docs[0].as_object().unwrap().len(), expected, "{}", context);
+                for (doc, package) in docs.iter().zip(packages).step_by(997) {
+                    if fields.is_empty() || fields.contains(&"name") {
+                        assert_eq!(doc["name"], package.name.as_str(), "{}", context);
+                    } else {
+                        assert!(doc.get("name").is_none(), "{}", context);
+                    }
+                    if fields.is_empty() {
+                        let back: Package = serde_json::from_value(doc.clone()).unwrap();
+                        assert_eq!(back.versions.len(), package.versions.len());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_selected_fields_order() {
+    let options = JsonOptions {
+        format: JsonFormat::Ndjson,
+        fields: vec!["licenses".to_string(), "name".to_string()],
+        ..Default::default()
+    };
+    let (output, _) = convert(options);
+    assert!(
+        output
+            .lines()
+            .next()
+            .unwrap()
+            .starts_with(r#"{"name":"3proxy","licenses":"#)
+    );
+
+    let options = JsonOptions {
+        fields: vec!["name".to_string(), "slot".to_string()],
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    let err = eix_to_json(fixture_bytes(), &mut out, options).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("\"slot\""), "{}", err);
+    assert!(out.is_empty());
+}
+
+#[test]
+fn test_errors_surface() {
+    let data = fixture_bytes();
+    for format in [JsonFormat::Pretty, JsonFormat::Compact, JsonFormat::Ndjson] {
+        let options = JsonOptions {
+            format,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let err = eix_to_json(&data[..data.len() / 2], &mut out, options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "{:?}", format);
+        let message = err.to_string();
+        assert!(message.starts_with("Error reading package "), "{}", message);
+        assert!(message.contains(" at byte "), "{}", message);
+        // The packages before the error were written
+        assert!(out.len() > data.len() / 4, "{:?}", format);
+    }
+
+    let err = eix_to_json(&data[..100], io::sink(), JsonOptions::default()).unwrap_err();
+    assert!(
+        err.to_string().starts_with("Error reading header: "),
+        "{}",
+        err
+    );
+
+    /// Accepts `self.0` bytes, then fails
+    struct Full(usize);
+
+    impl Write for Full {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.0 < buf.len() {
+                return Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"));
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    for format in [JsonFormat::Pretty, JsonFormat::Ndjson] {
+        let options = JsonOptions {
+            format,
+            ..Default::default()
+        };
+        let err = eix_to_json(data, Full(100_000), options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+    }
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{
+    DBHeader, Depend, EixTestBuilder, EixWriter, OpenOptions, Package, SerializeOptions, Version,
+    parse_bytes_with,
+};
+
+fn lazy_options() -> OpenOptions {
+    OpenOptions {
+        lazy_depend: true,
+        ..Default::default()
+    }
+}
+
+fn parse_lazy(data: &[u8]) -> Vec<Package> {
+    parse_bytes_with(data, lazy_options()).unwrap().1
+}
+
+fn assert_same(a: &Depend, b: &Depend) {
+    assert_eq!(a.depend, b.depend);
+    assert_eq!(a.rdepend, b.rdepend);
+    assert_eq!(a.pdepend, b.pdepend);
+    assert_eq!(a.bdepend, b.bdepend);
+    assert_eq!(a.idepend, b.idepend);
+}
+
+fn versions(packages: &[Package]) -> impl Iterator<Item = &Version> {
+    packages.iter().flat_map(|p| &p.versions)
+}
+
+#[test]
+fn test_fixture_lazy_depend() {
+    let (_, eager) = fixture();
+    let lazy = parse_lazy(&std::fs::read(FIXTURE_EIX).unwrap());
+    assert_eq!(lazy.len(), eager.len());
+
+    let mut decoded = 0;
+    for (l, e) in versions(&lazy).zip(versions(eager)) {
+        assert_eq!(l.version_string, e.version_string);
+        assert!(l.depend.is_none());
+        let raw = l.lazy_depend.as_ref().unwrap();
+        assert!(!raw.is_decoded());
+        assert!(raw.encoded_len() > 0);
+        assert_same(l.depend().unwrap(), e.depend().unwrap());
+        assert!(raw.is_decoded());
+        decoded += 1;
+    }
+    assert_eq!(decoded, versions(eager).count());
+}
+
+#[test]
+fn test_lazy_depend_output() {
+    let (header, eager) = fixture();
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let lazy = parse_lazy(&data);
+    let first = lazy[0].versions[0].lazy_depend.as_ref().unwrap();
+    assert!(!first.is_decoded());
+    // Serializing decodes the lists as needed
+    assert_eq!(
+        serde_json::to_string(&lazy).unwrap(),
+        serde_json::to_string(eager).unwrap()
+    );
+    assert!(first.is_decoded());
+    let options = SerializeOptions {
+        joined_words: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        serde_json::to_string(&options.view_list(&lazy)).unwrap(),
+        serde_json::to_string(&options.view_list(eager)).unwrap()
+    );
+
+    let write = |packages: &[Package]| {
+        let mut writer = EixWriter::new(Vec::new());
+        writer.write_database(header, packages).unwrap();
+        writer.into_inner()
+    };
+    let fresh = parse_lazy(&data);
+    assert_eq!(write(&fresh), write(eager));
+}
+
+#[test]
+fn test_lazy_depend_file_versions() {
+    let depend = Depend {
+        depend: vec!["dev-libs/openssl".to_string()],
+        rdepend: vec!["dev-libs/openssl".to_string(), "sys-libs/zlib".to_string()],
+        bdepend: vec!["virtual/pkgconfig".to_string()],
+        idepend: vec!["acct-user/screen".to_string()],
+        ..Default::default()
+    };
+    let builder = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| {
+            p.version("4.9.1", |v| v.depend(depend.clone()))
+                .version("5.0.0", |v| v)
+        });
+    // BDEPEND is stored from version 32 and IDEPEND from version 39 on
+    let (header, packages) = builder.build();
+    for (version, bdepend, idepend) in [(31, false, false), (38, true, false), (39, true, true)] {
+        let header = DBHeader {
+            version,
+            ..header.clone()
+        };
+        let mut writer = EixWriter::new(Vec::new());
+        writer.write_database(&header, &packages).unwrap();
+        let lazy = parse_lazy(&writer.into_inner());
+        let dep = lazy[0].versions[0].depend().unwrap();
+        assert_eq!(dep.rdepend, depend.rdepend);
+        assert_eq!(dep.bdepend.is_empty(), !bdepend, "{}", version);
+        assert_eq!(dep.idepend.is_empty(), !idepend, "{}", version);
+        assert_eq!(lazy[0].versions[1].depend().unwrap().depend.len(), 0);
+    }
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::{
+    DB_VERSION_CURRENT, Database, EixTestBuilder, OpenOptions, PackageReader, parse_bytes_with,
+};
+use std::io;
+
+fn reader(data: &[u8]) -> PackageReader {
+    let mut db = Database::from_bytes(data);
+    let header = db.read_header(0).unwrap();
+    PackageReader::new(db, header)
+}
+
+#[test]
+fn test_partial_consumption_keeps_alignment() {
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    let header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    let mut reader = PackageReader::new(db, header);
+    let packages = fixture_packages();
+    let mut i = 0;
+    while reader.next_category().unwrap() {
+        while let Some((summary, mut versions)) = reader.read_package_lazy().unwrap() {
+            let expected = &packages[i];
+            assert_eq!(summary.category, expected.category);
+            assert_eq!(summary.name, expected.name);
+            assert_eq!(summary.version_count, expected.versions.len());
+            assert_eq!(versions.remaining(), expected.versions.len());
+
+            // None, the first one, or all of the versions
+            let take = match i % 3 {
+                0 => 0,
+                1 => 1,
+                _ => usize::MAX,
+            };
+            let read: Vec<_> = versions.by_ref().take(take).map(Result::unwrap).collect();
+            assert_eq!(read.len(), take.min(expected.versions.len()));
+            for (v, e) in read.iter().zip(&expected.versions) {
+                assert_eq!(v.version_string, e.version_string);
+                assert_eq!(v.keywords, e.keywords);
+                assert_eq!(v.slot, e.slot);
+            }
+            if i % 5 == 0 {
+                versions.finish().unwrap();
+            }
+            i += 1;
+        }
+    }
+    assert_eq!(i, packages.len());
+}
+
+#[test]
+fn test_stop_at_first_match() {
+    let data = EixTestBuilder::new()
+        .category("sys-kernel")
+        .package("gentoo-sources", |mut p| {
+            for minor in 0..40 {
+                let keywords = if minor == 12 { "amd64" } else { "~amd64" };
+                p = p.version(&format!("6.{}.1", minor), |v| v.keywords(keywords));
+            }
+            p
+        })
+        .package("vanilla-sources", |p| p.version("6.12.1", |v| v))
+        .build_bytes();
+
+    let mut reader = reader(&data);
+    assert!(reader.next_category().unwrap());
+    let (summary, mut versions) = reader.read_package_lazy().unwrap().unwrap();
+    assert_eq!(summary.version_count, 40);
+    let stable = versions
+        .find(|v| v.as_ref().unwrap().keywords == ["amd64"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(stable.version_string, "6.12.1");
+    assert_eq!(versions.remaining(), 27);
+    drop(versions);
+
+    let next = reader.read_package().unwrap().unwrap();
+    assert_eq!(next.name, "vanilla-sources");
+    assert_eq!(next.versions[0].version_string, "6.12.1");
+    assert!(reader.read_package_lazy().unwrap().is_none());
+    assert!(!reader.next_category().unwrap());
+}
+
+#[test]
+fn test_overlong_versions() {
+    let data = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| {
+            p.version("4.9.1", |v| v.keywords("amd64"))
+                .version("5.0.0", |v| v.keywords("~amd64"))
+        })
+        .package("tmux", |p| p.version("3.4", |v| v))
+        .build_bytes();
+    let options = OpenOptions {
+        record_spans: true,
+        ..Default::default()
+    };
+    let (_, packages) = parse_bytes_with(&data, options).unwrap();
+    let start = packages[0].span.unwrap().start as usize;
+    let version = packages[0].versions[0].span.unwrap();
+
+    // The single byte length prefix ends the record one byte into the
+    // first version
+    let mut corrupt = data.clone();
+    corrupt[start] = (version.start - start as u64) as u8;
+    let mut reader = reader(&corrupt);
+    assert!(reader.next_category().unwrap());
+    let (_, mut versions) = reader.read_package_lazy().unwrap().unwrap();
+    versions.next().unwrap().unwrap();
+    let err = versions.finish().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("app-misc/screen"), "{}", err);
+
+    // Dropped without finish, the error is reported by the next read
+    let mut reader = self::reader(&corrupt);
+    assert!(reader.next_category().unwrap());
+    let (_, mut versions) = reader.read_package_lazy().unwrap().unwrap();
+    versions.next().unwrap().unwrap();
+    drop(versions);
+    let err = reader.read_package().unwrap_err();
+    assert!(err.to_string().contains("app-misc/screen"), "{}", err);
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{fixture_package, fixture_packages};
+use eix::{AcceptLicense, LicenseExpr, LicenseGroups, find_by_license_group};
+
+#[test]
+fn test_all_fixture_licenses_parse() {
+    for pkg in fixture_packages() {
+        if let Err(e) = pkg.license_expr() {
+            panic!("{}/{}: {:?}: {}", pkg.category, pkg.name, pkg.licenses, e);
+        }
+    }
+}
+
+#[test]
+fn test_fixture_license_acceptance() {
+    let lua = fixture_package("dev-lang", "lua");
+    let expr = lua.license_expr().unwrap();
+    assert_eq!(expr, LicenseExpr::parse("MIT").unwrap());
+    assert!(expr.acceptable_under(&AcceptLicense::parse("-* MIT")));
+    assert!(!expr.acceptable_under(&AcceptLicense::parse("* -MIT")));
+}
+
+#[test]
+fn test_fixture_find_by_license_group() {
+    let groups = LicenseGroups::parse("PERMISSIVE MIT BSD\nFREE @PERMISSIVE GPL-2\n");
+    let free = find_by_license_group(fixture_packages(), &groups, "@FREE").unwrap();
+    assert!(
+        free.iter()
+            .any(|p| p.category == "dev-lang" && p.name == "lua")
+    );
+    assert!(free.len() < fixture_packages().len());
+    let permissive = find_by_license_group(fixture_packages(), &groups, "PERMISSIVE").unwrap();
+    assert!(permissive.len() <= free.len());
+}
@@ -0,0 +1,109 @@
+//! Absurd counts must be rejected or fail without reserving memory for
+//! the declared number of elements.
+
+use eix::codec::encode_num;
+use eix::{Database, Decoder, StringHash};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Records the largest single allocation
+struct Counting;
+
+static LARGEST: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LARGEST.fetch_max(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LARGEST.fetch_max(new_size, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const LIMIT: usize = 1024 * 1024;
+
+/// Runs `f`, asserting that it fails with `kind` and allocates no
+/// block larger than [`LIMIT`]
+fn assert_fails_small<T>(kind: io::ErrorKind, f: impl FnOnce() -> io::Result<T>) {
+    LARGEST.store(0, Ordering::Relaxed);
+    let err = f().err().expect("absurd count accepted");
+    assert_eq!(err.kind(), kind, "{}", err);
+    assert!(LARGEST.load(Ordering::Relaxed) < LIMIT);
+}
+
+fn num(value: u64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    encode_num(value, &mut buf);
+    buf
+}
+
+/// A header up to the overlay count
+fn preamble(overlays: u64) -> Vec<u8> {
+    let mut data = b"eix\n".to_vec();
+    data.extend(num(39));
+    data.extend(num(1));
+    data.extend(num(overlays));
+    data
+}
+
+// Everything runs in one test so that no other test allocates meanwhile
+#[test]
+fn test_absurd_counts() {
+    let hash = StringHash::new();
+
+    // Above the limits
+    assert_fails_small(io::ErrorKind::InvalidData, || {
+        Decoder::new(num(u64::MAX).as_slice()).read_hash_words(&hash)
+    });
+    assert_fails_small(io::ErrorKind::InvalidData, || {
+        Decoder::new(num(1 << 40).as_slice()).read_hash()
+    });
+    assert_fails_small(io::ErrorKind::InvalidData, || {
+        Decoder::new(preamble(1 << 50).as_slice()).read_header(0)
+    });
+
+    // Within the limits, but the data ends
+    assert_fails_small(io::ErrorKind::UnexpectedEof, || {
+        Decoder::new(num(60_000).as_slice()).read_hash_words(&hash)
+    });
+    assert_fails_small(io::ErrorKind::UnexpectedEof, || {
+        Decoder::new(num(4_000_000).as_slice()).read_hash()
+    });
+    assert_fails_small(io::ErrorKind::UnexpectedEof, || {
+        Decoder::new(preamble(60_000).as_slice()).read_header(0)
+    });
+
+    // A string longer than the input
+    let mut data = preamble(1);
+    data.extend(num(10 * 1024 * 1024));
+    let mut decoder = Decoder::new(data.as_slice());
+    decoder.set_input_len(data.len() as u64);
+    assert_fails_small(io::ErrorKind::InvalidData, || decoder.read_header(0));
+
+    // The length of a file is known, so counts are checked against it
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("crafted.eix");
+    std::fs::write(&path, preamble(60_000)).unwrap();
+    assert_fails_small(io::ErrorKind::InvalidData, || {
+        Database::open_read(&path)?.read_header(0)
+    });
+    let err = Database::open_read(&path)
+        .unwrap()
+        .read_header(0)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Overlay list count of 60000 exceeds the remaining 0 bytes"
+    );
+}
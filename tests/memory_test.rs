@@ -0,0 +1,51 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{Database, OpenOptions, Package, PackageReader, SizeReport};
+use std::io::{self, Cursor};
+
+fn read_all(mut db: Database) -> io::Result<Vec<Package>> {
+    let header = db.read_header(0)?;
+    let mut reader = PackageReader::new(db, header);
+    let mut packages = Vec::new();
+    while reader.next_category()? {
+        while let Some(pkg) = reader.read_package()? {
+            packages.push(pkg);
+        }
+    }
+    Ok(packages)
+}
+
+#[test]
+fn test_in_memory_sources_match_file() {
+    let expected = serde_json::to_string(&fixture().1).unwrap();
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+
+    let db = Database::from_bytes(data.clone());
+    assert_eq!(db.buffer_capacity(), data.len());
+    let packages = read_all(db).unwrap();
+    assert_eq!(serde_json::to_string(&packages).unwrap(), expected);
+
+    let options = OpenOptions {
+        buffer_capacity: 4096,
+        ..Default::default()
+    };
+    let db = Database::from_reader_with(Cursor::new(data.clone()), options);
+    assert_eq!(db.buffer_capacity(), 4096);
+    let packages = read_all(db).unwrap();
+    assert_eq!(serde_json::to_string(&packages).unwrap(), expected);
+
+    assert_eq!(
+        SizeReport::analyze_bytes(&data).unwrap(),
+        SizeReport::analyze(FIXTURE_EIX).unwrap()
+    );
+}
+
+#[test]
+fn test_truncated_bytes_fail() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let err = read_all(Database::from_bytes(&data[..data.len() - 1])).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = read_all(Database::from_reader(Cursor::new(data[..1000].to_vec()))).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
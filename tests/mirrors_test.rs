@@ -0,0 +1,36 @@
+#![cfg(feature = "fs")]
+
+use eix::{Mirrors, SrcUri};
+use std::io::Write;
+
+#[test]
+fn test_load_thirdpartymirrors() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "gentoo https://distfiles.gentoo.org/distfiles").unwrap();
+    writeln!(file, "# Projects").unwrap();
+    writeln!(
+        file,
+        "sourceforge https://downloads.sourceforge.net https://netcologne.dl.sourceforge.net"
+    )
+    .unwrap();
+    let mirrors = Mirrors::load(file.path()).unwrap();
+
+    let expr = SrcUri::parse(
+        "mirror://sourceforge/screen/screen-4.9.1.tar.gz \
+         mirror://gentoo/screen-4.9.1-patches.tar.xz \
+         mirror://unknown/x.tar.gz",
+    )
+    .unwrap();
+    assert_eq!(
+        expr.expand_mirrors(&mirrors),
+        [
+            "https://downloads.sourceforge.net/screen/screen-4.9.1.tar.gz",
+            "https://netcologne.dl.sourceforge.net/screen/screen-4.9.1.tar.gz",
+            "https://distfiles.gentoo.org/distfiles/screen-4.9.1-patches.tar.xz",
+            "mirror://unknown/x.tar.gz",
+        ]
+    );
+    assert!(expr.unknown_mirrors(&mirrors).contains("unknown"));
+
+    assert!(Mirrors::load(file.path().with_extension("missing")).is_err());
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::FIXTURE_EIX;
+use eix::{
+    DB_VERSION_CURRENT, Database, EIX_CACHE_LEGACY_PATH, EIX_CACHE_PATH, EIX_CACHEFILE_ENV,
+    default_cache_paths,
+};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn test_open_first_resolution_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.eix");
+    let as_dir = dir.path().join("eix");
+    fs::create_dir(&as_dir).unwrap();
+    let copy = dir.path().join("portage.eix");
+    fs::copy(FIXTURE_EIX, &copy).unwrap();
+
+    let (mut db, used) =
+        Database::open_first([missing.clone(), as_dir.clone(), copy.clone()]).unwrap();
+    assert_eq!(used, copy);
+    assert_eq!(db.read_header(DB_VERSION_CURRENT).unwrap().version, 39);
+
+    let (_, used) = Database::open_first([PathBuf::from(FIXTURE_EIX), copy.clone()]).unwrap();
+    assert_eq!(used, PathBuf::from(FIXTURE_EIX));
+
+    let err = Database::open_first([missing, as_dir]).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(err.to_string().contains("missing.eix"));
+}
+
+// The only test of this binary touching the environment
+#[test]
+fn test_default_cache_paths_env_override() {
+    let dir = tempfile::tempdir().unwrap();
+    let copy = dir.path().join("custom.eix");
+    fs::copy(FIXTURE_EIX, &copy).unwrap();
+
+    unsafe { env::set_var(EIX_CACHEFILE_ENV, &copy) };
+    assert_eq!(
+        default_cache_paths(),
+        vec![
+            copy.clone(),
+            PathBuf::from(EIX_CACHE_PATH),
+            PathBuf::from(EIX_CACHE_LEGACY_PATH)
+        ]
+    );
+    let (_, used) = Database::open_default().unwrap();
+    assert_eq!(used, copy);
+
+    unsafe { env::set_var(EIX_CACHEFILE_ENV, "") };
+    assert_eq!(
+        default_cache_paths(),
+        vec![
+            PathBuf::from(EIX_CACHE_PATH),
+            PathBuf::from(EIX_CACHE_LEGACY_PATH)
+        ]
+    );
+    unsafe { env::remove_var(EIX_CACHEFILE_ENV) };
+}
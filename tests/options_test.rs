@@ -0,0 +1,100 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{
+    DB_VERSION_CURRENT, Database, Decoder, EixWriter, HashValidation, OpenOptions, Package,
+    PackageReader, ParseLimits, Utf8Policy,
+};
+use std::io;
+
+fn read_all(db: Database) -> io::Result<Vec<Package>> {
+    let mut db = db;
+    let header = db.read_header(DB_VERSION_CURRENT)?;
+    let mut reader = PackageReader::new(db, header);
+    let mut packages = Vec::new();
+    while reader.next_category()? {
+        while let Some(pkg) = reader.read_package()? {
+            packages.push(pkg);
+        }
+    }
+    Ok(packages)
+}
+
+#[test]
+fn test_buffer_capacity() {
+    assert_eq!(
+        Database::open_read(FIXTURE_EIX).unwrap().buffer_capacity(),
+        8 * 1024
+    );
+    let expected = serde_json::to_string(&fixture().1).unwrap();
+    for capacity in [16, 1 << 20] {
+        let options = OpenOptions {
+            buffer_capacity: capacity,
+            ..Default::default()
+        };
+        let db = Database::open_read_with(FIXTURE_EIX, options).unwrap();
+        assert_eq!(db.buffer_capacity(), capacity);
+        let packages = read_all(db).unwrap();
+        assert_eq!(serde_json::to_string(&packages).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_utf8_policy() {
+    let invalid = [3, b'a', 0xC3, 0x28];
+    let mut strict = Decoder::new(&invalid[..]);
+    let err = strict.read_string().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let lossy_options = OpenOptions {
+        utf8: Utf8Policy::Lossy,
+        ..Default::default()
+    };
+    let mut lossy = Decoder::with_options(&invalid[..], &lossy_options);
+    assert_eq!(lossy.read_string().unwrap(), "a\u{FFFD}(");
+
+    // A database with an invalid description
+    let (header, packages) = fixture();
+    let mut lua = common::fixture_package("dev-lang", "lua").clone();
+    lua.description = "@@marker@@".to_string();
+    let mut writer = EixWriter::new(Vec::new());
+    writer
+        .write_database(header, &[packages[0].clone(), lua])
+        .unwrap();
+    let mut data = writer.into_inner();
+    let pos = data.windows(10).position(|w| w == b"@@marker@@").unwrap();
+    data[pos] = 0xFF;
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), &data).unwrap();
+
+    let err = read_all(Database::open_read(file.path()).unwrap()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let packages = read_all(Database::open_read_with(file.path(), lossy_options).unwrap()).unwrap();
+    assert_eq!(packages[1].description, "\u{FFFD}@marker@@");
+}
+
+#[test]
+fn test_parse_limits() {
+    let options = OpenOptions {
+        limits: ParseLimits {
+            max_string_len: 4,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = Database::open_read_with(FIXTURE_EIX, options).unwrap();
+    let err = db.read_header(DB_VERSION_CURRENT).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("exceeds the limit"));
+}
+
+#[test]
+fn test_fixture_hashes_pass_strict_validation() {
+    let options = OpenOptions {
+        hash_validation: HashValidation::Strict,
+        ..Default::default()
+    };
+    let mut db = Database::open_read_with(FIXTURE_EIX, options).unwrap();
+    let header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    assert!(header.warnings().is_empty());
+}
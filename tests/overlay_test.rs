@@ -0,0 +1,61 @@
+mod common;
+
+use common::{fixture, fixture_packages};
+use eix::packages_in_repo;
+
+#[test]
+fn test_packages_in_repo_matches_manual_scan() {
+    let (header, packages) = fixture();
+    for (key, overlay) in header.overlays.iter().enumerate() {
+        let manual_packages = packages
+            .iter()
+            .filter(|p| p.versions.iter().any(|v| v.reponame == overlay.label))
+            .count();
+        let manual_versions: usize = packages
+            .iter()
+            .map(|p| {
+                p.versions
+                    .iter()
+                    .filter(|v| v.overlay_key == key as u64)
+                    .count()
+            })
+            .sum();
+
+        let filtered = packages_in_repo(packages, &overlay.label);
+        assert_eq!(filtered.len(), manual_packages);
+        let versions: usize = filtered.iter().map(|p| p.versions.len()).sum();
+        assert_eq!(versions, manual_versions);
+    }
+}
+
+#[test]
+fn test_main_tree_versions() {
+    let packages = fixture_packages();
+    let gentoo = packages_in_repo(packages, "gentoo");
+    // The fixture only contains the gentoo repository
+    assert_eq!(gentoo.len(), packages.len());
+    for pkg in &gentoo {
+        assert_eq!(pkg.versions_in_overlay(0).count(), pkg.versions.len());
+        assert_eq!(pkg.versions_in_repo("gentoo").count(), pkg.versions.len());
+        assert!(pkg.versions.iter().all(|v| v.in_main_tree()));
+    }
+    assert!(packages_in_repo(packages, "guru").is_empty());
+}
+
+#[test]
+fn test_version_overlay() {
+    let (header, packages) = fixture();
+    let main_tree = header.main_tree().unwrap();
+    assert_eq!(header.overlay_by_label("gentoo").unwrap().0, 0);
+    let path = std::path::Path::new(&main_tree.path);
+    assert_eq!(header.overlay_by_path(path).unwrap().0, 0);
+    for v in packages.iter().flat_map(|p| &p.versions).take(100) {
+        let overlay = v.overlay(header).unwrap();
+        assert_eq!(overlay.label, v.reponame);
+        assert_eq!(overlay.path, main_tree.path);
+    }
+
+    let mut version = packages[0].versions[0].clone();
+    version.overlay_key = header.overlays.len() as u64;
+    assert!(version.overlay(header).is_none());
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "rayon")]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::{PackageQuery, ParsedDb, ReverseDepIndex, SharedDb};
+
+#[test]
+fn test_execute_par_matches_execute() {
+    let db = SharedDb::new(ParsedDb::load(FIXTURE_EIX).unwrap());
+    let queries = [
+        PackageQuery::default(),
+        PackageQuery::category("dev-python"),
+        PackageQuery {
+            name: Some("lib".to_string()),
+            keyword: Some("~amd64".to_string()),
+            ..Default::default()
+        },
+        PackageQuery {
+            description: Some("no such description".to_string()),
+            ..Default::default()
+        },
+    ];
+    for query in &queries {
+        let sequential = query.execute(&db);
+        let parallel = query.execute_par(&db);
+        assert_eq!(sequential.len(), parallel.len());
+        assert!(
+            sequential
+                .iter()
+                .zip(&parallel)
+                .all(|(a, b)| std::ptr::eq(*a, *b))
+        );
+    }
+}
+
+#[test]
+fn test_build_par_matches_build() {
+    let packages = fixture_packages();
+    let index = ReverseDepIndex::build(packages);
+    assert!(!index.dependents("dev-lang/python").is_empty());
+    assert_eq!(ReverseDepIndex::build_par(packages), index);
+    assert_eq!(
+        ReverseDepIndex::build_par(&packages[..3]),
+        ReverseDepIndex::build(&packages[..3])
+    );
+    assert!(ReverseDepIndex::build_par(&[]).is_empty());
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::{
+    Database, EixTestBuilder, MASK_IN_PROFILE, MASK_SYSTEM, MASK_WORLD, PackageIndex,
+    PackageReader, ProfileStatus, SystemPackage, system_packages, system_set,
+};
+
+#[test]
+fn test_fixture_system_set() {
+    let atoms: Vec<String> = system_set(fixture_packages())
+        .iter()
+        .map(|a| a.cp())
+        .collect();
+    assert!(!atoms.is_empty());
+    for expected in [
+        "app-shells/bash",
+        "sys-apps/baselayout",
+        "sys-apps/coreutils",
+        "sys-devel/gcc",
+        "virtual/libc",
+    ] {
+        assert!(
+            atoms.iter().any(|a| a == expected),
+            "{} not in @system",
+            expected
+        );
+    }
+    assert!(!atoms.iter().any(|a| a == "app-misc/screen"));
+
+    for package in fixture_packages() {
+        let in_set = atoms.contains(&format!("{}/{}", package.category, package.name));
+        assert_eq!(package.system_versions().next().is_some(), in_set);
+        for v in package.system_versions() {
+            assert_eq!(v.profile_status(), ProfileStatus::System);
+        }
+    }
+}
+
+fn system_database() -> Database {
+    EixTestBuilder::new()
+        .category("app-shells")
+        .package("bash", |p| {
+            p.version("5.1", |v| v.mask(MASK_SYSTEM))
+                .version("5.2", |v| v.mask(MASK_SYSTEM | MASK_WORLD))
+                .version("9999", |v| v)
+        })
+        .category("app-misc")
+        .package("screen", |p| p.version("5.0", |v| v.mask(MASK_IN_PROFILE)))
+        .category("sys-apps")
+        .package("coreutils", |p| {
+            p.version("9.4", |v| v)
+                .version("9.5", |v| v.mask(MASK_SYSTEM))
+        })
+        .build_database()
+}
+
+#[test]
+fn test_system_packages() {
+    let mut db = system_database();
+    let header = db.read_header(0).unwrap();
+    let mut reader = PackageReader::new(db, header);
+    let system = system_packages(&mut reader).unwrap();
+    assert_eq!(
+        system,
+        [
+            SystemPackage {
+                category: "app-shells".to_string(),
+                name: "bash".to_string(),
+                versions: vec!["5.1".to_string(), "5.2".to_string()],
+            },
+            SystemPackage {
+                category: "sys-apps".to_string(),
+                name: "coreutils".to_string(),
+                versions: vec!["9.5".to_string()],
+            },
+        ]
+    );
+
+    let mut db = system_database();
+    let header = db.read_header(0).unwrap();
+    let index = PackageIndex::from_reader(&mut PackageReader::new(db, header)).unwrap();
+    let from_index: Vec<SystemPackage> = index
+        .system_packages()
+        .filter_map(SystemPackage::from_package)
+        .collect();
+    assert_eq!(from_index, system);
+    let screen = index.get("app-misc/screen").unwrap();
+    assert!(!screen.versions[0].in_system());
+    assert_eq!(screen.versions[0].profile_status(), ProfileStatus::Profile);
+}
+
+#[test]
+fn test_fixture_system_packages() {
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    let header = db.read_header(0).unwrap();
+    let system = system_packages(&mut PackageReader::new(db, header)).unwrap();
+    let atoms: Vec<String> = system_set(fixture_packages())
+        .iter()
+        .map(|a| a.cp())
+        .collect();
+    let cps: Vec<String> = system
+        .iter()
+        .map(|p| format!("{}/{}", p.category, p.name))
+        .collect();
+    assert_eq!(cps, atoms);
+}
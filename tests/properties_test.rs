@@ -0,0 +1,108 @@
+use eix::{
+    DB_VERSION_NUMERIC_PROPERTIES, EixTestBuilder, EixWriter, PROPERTIES_LIVE, PROPERTIES_SET,
+    RESTRICT_FETCH, RESTRICT_MIRROR, SizeReport, ValidateOptions, Version, parse_bytes,
+    validate_bytes,
+};
+
+/// A package with a version carrying `properties`, followed by a second
+/// version with different values in every field
+fn builder(properties: u32) -> EixTestBuilder {
+    EixTestBuilder::new()
+        .overlay("gentoo", "/var/db/repos/gentoo")
+        .overlay("guru", "/var/db/repos/guru")
+        .category("app-misc")
+        .package("screen", |p| {
+            p.version("4.9.1", |v| {
+                v.properties(properties)
+                    .restrict(RESTRICT_FETCH | RESTRICT_MIRROR)
+                    .keywords("amd64 ~arm64")
+                    .slot("0/1")
+                    .iuse("pam")
+            })
+            .version("9999", |v| v.overlay(1).keywords("").iuse("+nls"))
+        })
+}
+
+fn record(properties: u32) -> Vec<u8> {
+    builder(properties).build_bytes()
+}
+
+/// The record in the format before [`DB_VERSION_NUMERIC_PROPERTIES`]
+fn old_record(properties: u32) -> Vec<u8> {
+    let (mut header, packages) = builder(properties).build();
+    header.version = DB_VERSION_NUMERIC_PROPERTIES - 1;
+    let mut writer = EixWriter::new(Vec::new());
+    writer.write_database(&header, &packages).unwrap();
+    writer.into_inner()
+}
+
+fn versions(data: &[u8]) -> Vec<Version> {
+    let (_, mut packages) = parse_bytes(data).unwrap();
+    assert_eq!(packages.len(), 1);
+    packages.pop().unwrap().versions
+}
+
+/// The fields following the properties flags are read where they were written
+fn assert_aligned(data: &[u8], properties: u32) {
+    let versions = versions(data);
+    assert_eq!(versions[0].properties_flags, properties);
+    assert_eq!(versions[0].restrict_flags, RESTRICT_FETCH | RESTRICT_MIRROR);
+    assert_eq!(versions[0].keywords, ["amd64", "~arm64"]);
+    assert_eq!(versions[0].version_string, "4.9.1");
+    assert_eq!(versions[0].slot, "0/1");
+    assert_eq!(versions[0].iuse, ["pam"]);
+    assert_eq!(versions[1].properties_flags, 0);
+    assert_eq!(versions[1].version_string, "9999");
+    assert_eq!(&*versions[1].reponame, "guru");
+    assert_eq!(versions[1].iuse, ["+nls"]);
+
+    assert!(validate_bytes(data, &ValidateOptions::default()).is_clean());
+    let sizes = SizeReport::analyze_bytes(data).unwrap();
+    assert_eq!(sizes.file_size, data.len() as u64);
+}
+
+#[test]
+fn test_byte_wide_properties() {
+    let narrow = record(PROPERTIES_LIVE | PROPERTIES_SET);
+    assert_aligned(&narrow, PROPERTIES_LIVE | PROPERTIES_SET);
+
+    // Below 0xFF, the flags take the single byte older files have
+    let other = record(PROPERTIES_LIVE);
+    assert_eq!(narrow.len(), other.len());
+    let differing: Vec<usize> = (0..narrow.len())
+        .filter(|&i| narrow[i] != other[i])
+        .collect();
+    assert_eq!(differing.len(), 1);
+    assert_eq!(narrow[differing[0]], 0x0A);
+}
+
+#[test]
+fn test_wider_properties() {
+    let narrow = record(PROPERTIES_LIVE);
+    for (properties, extra) in [(0xFF, 1), (0x100, 2), (0x1_0002, 4)] {
+        let wide = record(properties);
+        assert_eq!(wide.len(), narrow.len() + extra, "{:#x}", properties);
+        assert_aligned(&wide, properties);
+    }
+}
+
+#[test]
+fn test_old_format_properties_byte() {
+    // All bits set take one byte, where a number would need two
+    let all = old_record(0xFF);
+    let other = old_record(PROPERTIES_LIVE);
+    assert_eq!(all.len(), other.len());
+    let differing: Vec<usize> = (0..all.len()).filter(|&i| all[i] != other[i]).collect();
+    assert_eq!(differing.len(), 1);
+    assert_eq!(all[differing[0]], 0xFF);
+    assert_eq!(record(0xFF).len(), all.len() + 1);
+    assert_aligned(&all, 0xFF);
+    assert_aligned(&other, PROPERTIES_LIVE);
+
+    // Wider flags cannot be written in the old format
+    let (mut header, packages) = builder(0x100).build();
+    header.version = DB_VERSION_NUMERIC_PROPERTIES - 1;
+    let mut writer = EixWriter::new(Vec::new());
+    let err = writer.write_database(&header, &packages).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::codec::encode_num;
+use eix::{
+    CompareOptions, Database, EixTestBuilder, FindingKind, OpenOptions, Package, PackageReader,
+    ValidateOptions, compare_packages, parse_bytes, parse_bytes_with, validate_bytes,
+};
+use std::io::{self, Read};
+
+/// Hands out a single byte per call
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(1).min(self.0.len());
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+fn read_all<R: Read>(reader: R) -> io::Result<Vec<Package>> {
+    let mut db = Database::new(reader);
+    let header = db.read_header(0)?;
+    let mut reader = PackageReader::new(db, header);
+    let mut packages = Vec::new();
+    while reader.next_category()? {
+        while let Some(pkg) = reader.read_package()? {
+            packages.push(pkg);
+        }
+    }
+    Ok(packages)
+}
+
+/// Parses `data` from memory, from a plain reader and from a reader
+/// returning short reads, expecting `expected` each time
+fn assert_parses_to(data: &[u8], expected: &[Package]) {
+    let (_, from_bytes) = parse_bytes(data).unwrap();
+    let from_reader = read_all(data).unwrap();
+    let trickled = read_all(Trickle(data)).unwrap();
+    for packages in [from_bytes, from_reader, trickled] {
+        assert!(compare_packages(expected, &packages, CompareOptions::default()).is_empty());
+    }
+}
+
+fn sample() -> (Vec<u8>, Vec<Package>) {
+    let data = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| {
+            p.description("screen manager")
+                .version("4.9.1", |v| v.keywords("amd64").iuse("pam"))
+                .version("9999", |v| v)
+        })
+        .package("tmux", |p| {
+            p.version("3.4", |v| v.keywords("amd64 ~arm64"))
+                .version("3.5", |v| v.keywords("~amd64"))
+        })
+        .category("app-shells")
+        .package("bash", |p| p.version("5.2", |v| v.slot("0")))
+        .build_bytes();
+    let (_, packages) = parse_bytes(&data).unwrap();
+    (data, packages)
+}
+
+/// The offset of the length prefix of the `index`th package
+fn prefix_offset(data: &[u8], index: usize) -> usize {
+    let options = OpenOptions {
+        record_spans: true,
+        ..Default::default()
+    };
+    let (_, packages) = parse_bytes_with(data, options).unwrap();
+    packages[index].span.unwrap().start as usize
+}
+
+#[test]
+fn test_full_parse_unchanged() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    assert_parses_to(&data, fixture_packages());
+}
+
+#[test]
+fn test_wrong_length_prefix() {
+    let (data, packages) = sample();
+    let start = prefix_offset(&data, 1);
+    let declared = data[start];
+    assert!(declared > 4 && declared < 0xE0);
+
+    // Too short: the record continues on the reader; too long: the bytes
+    // read ahead of the next package are decoded next
+    for wrong in [0, 4, declared - 1, declared + 1, declared + 0x1F] {
+        let mut corrupt = data.clone();
+        corrupt[start] = wrong;
+        assert_parses_to(&corrupt, &packages);
+
+        let report = validate_bytes(&corrupt, &ValidateOptions::default());
+        assert!(
+            report
+                .findings
+                .iter()
+                .any(|f| matches!(f.kind, FindingKind::LengthMismatch { .. })),
+            "{:?}",
+            report.findings
+        );
+    }
+}
+
+#[test]
+fn test_implausible_length_prefix() {
+    let (data, packages) = sample();
+    for index in [0, 2] {
+        let start = prefix_offset(&data, index);
+        let mut huge = Vec::new();
+        encode_num(u64::from(u32::MAX), &mut huge);
+        let mut corrupt = data[..start].to_vec();
+        corrupt.extend(&huge);
+        corrupt.extend(&data[start + 1..]);
+        assert_parses_to(&corrupt, &packages);
+    }
+}
+
+#[test]
+fn test_truncated_package() {
+    let (data, _) = sample();
+    let start = prefix_offset(&data, 2);
+    for end in [start + 2, data.len() - 1] {
+        let truncated = &data[..end];
+        let err = read_all(truncated).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        // Knowing the length, the remaining bytes are checked first
+        assert!(parse_bytes(truncated).is_err());
+    }
+}
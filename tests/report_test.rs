@@ -0,0 +1,153 @@
+use eix::{
+    DatabaseStats, MASK_PACKAGE, Package, ReportInput, Version, diff, parse_version,
+    render_markdown,
+};
+
+fn version(version: &str, mask_flags: u8) -> Version {
+    Version {
+        version_string: version.to_string(),
+        parts: parse_version(version).unwrap(),
+        mask_flags,
+        reponame: "gentoo".into(),
+        ..Default::default()
+    }
+}
+
+fn package(category: &str, name: &str, homepage: &str, versions: Vec<Version>) -> Package {
+    Package {
+        category: category.to_string(),
+        name: name.to_string(),
+        description: format!("the {} package", name),
+        homepage: homepage.to_string(),
+        licenses: "MIT".to_string(),
+        versions,
+        ..Default::default()
+    }
+}
+
+fn packages() -> Vec<Package> {
+    vec![
+        package(
+            "app-misc",
+            "screen",
+            "https://www.gnu.org/software/screen/",
+            vec![version("4.9.1", 0), version("5.0.1", MASK_PACKAGE)],
+        ),
+        package("dev-lang", "my_lang", "", vec![version("1.0", 0)]),
+    ]
+}
+
+fn markdown(input: ReportInput) -> String {
+    let mut out = Vec::new();
+    render_markdown(&input, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_packages_markdown() {
+    assert_eq!(
+        markdown(ReportInput::Packages(&packages())),
+        "# Packages\n\
+         \n\
+         2 packages with 3 versions in 2 categories\n\
+         \n\
+         Categories: [app-misc](#app-misc), [dev-lang](#dev-lang)\n\
+         \n\
+         ## <a id=\"app-misc\"></a>app-misc\n\
+         \n\
+         | Package | Versions | Description |\n\
+         | --- | --- | --- |\n\
+         | [screen](https://www.gnu.org/software/screen/) | 4.9.1 (5.0.1) | the screen package |\n\
+         \n\
+         ## <a id=\"dev-lang\"></a>dev-lang\n\
+         \n\
+         | Package | Versions | Description |\n\
+         | --- | --- | --- |\n\
+         | my\\_lang | 1.0 | the my\\_lang package |\n"
+    );
+}
+
+#[test]
+fn test_diff_markdown() {
+    let old = packages();
+    let mut new = packages();
+    new[0].versions.push(version("5.1", 0));
+    new[1].category = "dev-util".to_string();
+    assert_eq!(
+        markdown(ReportInput::Diff(&diff(&old, &new))),
+        "# Database changes\n\
+         \n\
+         1 new, 1 removed, 1 updated\n\
+         \n\
+         Categories: [app-misc](#app-misc), [dev-lang](#dev-lang), [dev-util](#dev-util)\n\
+         \n\
+         ## <a id=\"app-misc\"></a>app-misc\n\
+         \n\
+         | Package | Change | Versions | Details |\n\
+         | --- | --- | --- | --- |\n\
+         | screen | updated | 5.0.1 -> 5.1 | +5.1 |\n\
+         \n\
+         ## <a id=\"dev-lang\"></a>dev-lang\n\
+         \n\
+         | Package | Change | Versions | Details |\n\
+         | --- | --- | --- | --- |\n\
+         | my\\_lang | removed | 1.0 |  |\n\
+         \n\
+         ## <a id=\"dev-util\"></a>dev-util\n\
+         \n\
+         | Package | Change | Versions | Details |\n\
+         | --- | --- | --- | --- |\n\
+         | my\\_lang | new | 1.0 |  |\n"
+    );
+}
+
+#[test]
+fn test_stats_markdown() {
+    let stats = DatabaseStats::compute(&packages(), 1);
+    assert_eq!(
+        markdown(ReportInput::Stats(&stats)),
+        "# Database statistics\n\
+         \n\
+         2 packages with 3 versions in 2 categories\n\
+         \n\
+         | Statistic | Value |\n\
+         | --- | --- |\n\
+         | Versions per package | 1.50 |\n\
+         | Distinct slots | 1 |\n\
+         | Distinct licenses | 1 |\n\
+         | Hard masked versions | 1 |\n\
+         | Live versions | 0 |\n\
+         | Fetch restricted versions | 0 |\n\
+         \n\
+         ## Overlays\n\
+         \n\
+         | Overlay | Versions |\n\
+         | --- | --- |\n\
+         | gentoo | 3 |\n\
+         \n\
+         ## Top categories\n\
+         \n\
+         | Category | Packages |\n\
+         | --- | --- |\n\
+         | app-misc | 1 |\n"
+    );
+}
+
+#[cfg(feature = "html")]
+#[test]
+fn test_packages_html() {
+    let mut packages = packages();
+    packages[1].description = "<fast> & safe".to_string();
+    let mut out = Vec::new();
+    eix::render_html(&ReportInput::Packages(&packages), &mut out).unwrap();
+    let html = String::from_utf8(out).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>\n<html>\n"));
+    assert!(html.contains("<title>Packages</title>"));
+    assert!(html.contains("<h2 id=\"dev-lang\">dev-lang</h2>"));
+    assert!(html.contains(
+        "<tr><td><a href=\"https://www.gnu.org/software/screen/\">screen</a></td>\
+         <td>4.9.1 (5.0.1)</td><td>the screen package</td></tr>"
+    ));
+    assert!(html.contains("<td>&lt;fast&gt; &amp; safe</td>"));
+    assert!(html.ends_with("</body>\n</html>\n"));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture, read_database};
+use eix::{Database, FilterSpec, Package, PackageReader, filter_database};
+
+fn filter(spec: &FilterSpec) -> (Vec<u8>, u64) {
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    let header = db.read_header(0).unwrap();
+    let mut out = Vec::new();
+    let written = filter_database(PackageReader::new(db, header), &mut out, spec).unwrap();
+    (out, written)
+}
+
+fn json(packages: &[&Package]) -> serde_json::Value {
+    serde_json::to_value(packages).unwrap()
+}
+
+#[test]
+fn test_filter_categories_and_names() {
+    let spec = FilterSpec {
+        include_categories: vec!["app-*".to_string(), "dev-python".to_string()],
+        exclude_categories: vec!["app-emacs".to_string()],
+        name_pattern: Some("*e*".to_string()),
+        ..Default::default()
+    };
+    let (bytes, written) = filter(&spec);
+    let (header, packages) = read_database(Database::from_bytes(bytes));
+
+    let (original_header, original) = fixture();
+    let expected: Vec<&Package> = original
+        .iter()
+        .filter(|p| spec.matches(&p.category, &p.name))
+        .collect();
+    assert!(!expected.is_empty());
+    assert!(expected.len() < original.len());
+    assert_eq!(written, expected.len() as u64);
+    assert_eq!(json(&packages.iter().collect::<Vec<_>>()), json(&expected));
+
+    let mut categories: Vec<&str> = expected.iter().map(|p| p.category.as_str()).collect();
+    categories.dedup();
+    assert_eq!(header.size as usize, categories.len());
+    assert_eq!(header.overlays.len(), original_header.overlays.len());
+    assert_eq!(header.world_sets, original_header.world_sets);
+    assert_eq!(header.use_depend, original_header.use_depend);
+    // Only the strings of the remaining packages are left
+    assert!(header.keywords_hash.len() <= original_header.keywords_hash.len());
+    assert!(header.license_hash.len() < original_header.license_hash.len());
+}
+
+#[test]
+fn test_strip_data() {
+    let spec = FilterSpec {
+        strip_depend: true,
+        strip_required_use: true,
+        strip_src_uri: true,
+        ..Default::default()
+    };
+    let (bytes, written) = filter(&spec);
+    let (header, packages) = read_database(Database::from_bytes(bytes.clone()));
+    assert!(!header.use_depend && !header.use_required_use && !header.use_src_uri);
+    assert!(header.depend_hash.is_empty());
+
+    let original = &fixture().1;
+    assert_eq!(written, original.len() as u64);
+    let stripped: Vec<Package> = original
+        .iter()
+        .cloned()
+        .map(|mut p| {
+            for v in &mut p.versions {
+                v.depend = None;
+                v.lazy_depend = None;
+                v.required_use.clear();
+                v.src_uri = None;
+            }
+            p
+        })
+        .collect();
+    assert_eq!(
+        json(&packages.iter().collect::<Vec<_>>()),
+        json(&stripped.iter().collect::<Vec<_>>())
+    );
+    assert!(bytes.len() < std::fs::metadata(FIXTURE_EIX).unwrap().len() as usize);
+
+    // Filtering the copy again gives the same bytes
+    let mut db = Database::from_bytes(bytes.clone());
+    let header = db.read_header(0).unwrap();
+    let mut again = Vec::new();
+    filter_database(PackageReader::new(db, header), &mut again, &spec).unwrap();
+    assert_eq!(again, bytes);
+}
@@ -0,0 +1,62 @@
+use eix::{CompareOptions, EixTestBuilder, MASK_PACKAGE, compare_packages, sample};
+
+/// The content of `testdata/sample.eix`
+fn sample_builder() -> EixTestBuilder {
+    EixTestBuilder::new()
+        .overlay("gentoo", "/var/db/repos/gentoo")
+        .overlay("guru", "/var/db/repos/guru")
+        .category("app-misc")
+        .package("screen", |p| {
+            p.description("screen manager with VT100/ANSI terminal emulation")
+                .homepage("https://www.gnu.org/software/screen/")
+                .licenses("GPL-3+")
+                .version("4.9.1", |v| v.keywords("amd64 arm64 x86").iuse("pam"))
+                .version("5.0.0", |v| v.keywords("~amd64 ~arm64").iuse("pam"))
+                .version("9999", |v| v.iuse("pam").mask(MASK_PACKAGE))
+        })
+        .package("tmux", |p| {
+            p.description("Terminal multiplexer")
+                .homepage("https://tmux.github.io/")
+                .licenses("ISC")
+                .version("3.4", |v| v.keywords("amd64 ~arm64 x86"))
+        })
+        .category("dev-lang")
+        .package("rust", |p| {
+            p.description("Systems programming language")
+                .homepage("https://www.rust-lang.org/")
+                .licenses("|| ( MIT Apache-2.0 )")
+                .version("1.80.1", |v| v.keywords("amd64 arm64").slot("stable"))
+                .version("1.81.0", |v| v.keywords("~amd64 ~arm64").slot("stable"))
+        })
+        .category("dev-util")
+        .package("mise", |p| {
+            p.description("Dev tools, env vars, task runner")
+                .homepage("https://mise.jdx.dev/")
+                .licenses("MIT")
+                .version("2024.9.6", |v| v.keywords("~amd64").overlay(1))
+        })
+}
+
+#[test]
+fn test_sample_is_up_to_date() {
+    let bytes = sample_builder().build_bytes();
+    if std::env::var_os("EIX_UPDATE_SAMPLE").is_some() {
+        std::fs::write("testdata/sample.eix", &bytes).unwrap();
+    }
+    assert!(
+        bytes == sample::bytes(),
+        "testdata/sample.eix is outdated, run with EIX_UPDATE_SAMPLE=1"
+    );
+    assert!(bytes.len() < 4096);
+}
+
+#[test]
+fn test_sample_parses() {
+    let (header, packages) = sample::parsed();
+    let (expected_header, expected) = sample_builder().build();
+    let labels = |h: &eix::DBHeader| -> Vec<String> {
+        h.overlays.iter().map(|o| o.label.to_string()).collect()
+    };
+    assert_eq!(labels(&header), labels(&expected_header));
+    assert!(compare_packages(&expected, &packages, CompareOptions::default()).is_empty());
+}
@@ -0,0 +1,144 @@
+#![cfg(all(feature = "regex", feature = "fs"))]
+
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::{Database, Matcher, Package, PackageReader, scan, scan_reader};
+use regex::Regex;
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::ops::ControlFlow;
+use std::rc::Rc;
+
+/// The hits of a scan of the fixture as (index, package)
+fn hits(matcher: &Matcher) -> Vec<(usize, Package)> {
+    let mut hits = Vec::new();
+    let stats = scan(FIXTURE_EIX, matcher, |hit| {
+        hits.push((hit.index, hit.package.clone()));
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(stats.packages, fixture_packages().len());
+    assert_eq!(stats.hits, hits.len());
+    assert!(!stats.stopped);
+    hits
+}
+
+/// Asserts that the hits are the packages of the fixture selected by `keep`
+fn assert_reference(matcher: &Matcher, keep: impl Fn(&Package) -> bool) {
+    let hits = hits(matcher);
+    let expected: Vec<(usize, &Package)> = fixture_packages()
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| keep(p))
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(hits.len(), expected.len());
+    for ((index, hit), (expected_index, package)) in hits.iter().zip(expected) {
+        assert_eq!(*index, expected_index);
+        assert_eq!(hit.category, package.category);
+        assert_eq!(hit.name, package.name);
+        assert_eq!(hit.homepage, package.homepage);
+        let versions = |p: &Package| -> Vec<String> {
+            p.versions.iter().map(|v| v.get_full_version()).collect()
+        };
+        assert_eq!(versions(hit), versions(package));
+        assert_eq!(
+            serde_json::to_string(hit).unwrap(),
+            serde_json::to_string(package).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_hits_match_reference() {
+    let name = Regex::new("^py(test|qt)").unwrap();
+    assert_reference(&Matcher::new().name("^py(test|qt)").unwrap(), |p| {
+        name.is_match(&p.name)
+    });
+
+    let description = Regex::new("(?i)terminal emulat").unwrap();
+    let matcher = Matcher::new()
+        .description("(?i)terminal emulat")
+        .unwrap()
+        .category("^(app|x11)-")
+        .unwrap();
+    assert_reference(&matcher, |p| {
+        description.is_match(&p.description)
+            && (p.category.starts_with("app-") || p.category.starts_with("x11-"))
+    });
+
+    // Without patterns, every package is a hit
+    assert_eq!(hits(&Matcher::new()).len(), fixture_packages().len());
+}
+
+#[test]
+fn test_invalid_pattern() {
+    let err = Matcher::new().name("(unclosed").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+/// Counts the bytes read from the file
+struct Counting {
+    file: File,
+    read: Rc<Cell<u64>>,
+}
+
+impl Read for Counting {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.file.read(buf)?;
+        self.read.set(self.read.get() + n as u64);
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_break_stops_reading() {
+    let read = Rc::new(Cell::new(0));
+    let file = Counting {
+        file: File::open(FIXTURE_EIX).unwrap(),
+        read: Rc::clone(&read),
+    };
+    let mut db = Database::new(BufReader::new(file));
+    let header = db.read_header(0).unwrap();
+    let header_len = db.position();
+    let mut reader = PackageReader::new(db, header);
+
+    let matcher = Matcher::new().category("^app-misc$").unwrap();
+    let mut seen = Vec::new();
+    let stats = scan_reader(&mut reader, &matcher, |hit| {
+        seen.push(hit.package.name.clone());
+        if seen.len() == 3 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+    .unwrap();
+    assert!(stats.stopped);
+    assert_eq!(stats.hits, 3);
+    let expected: Vec<&str> = fixture_packages()
+        .iter()
+        .filter(|p| p.category == "app-misc")
+        .map(|p| p.name.as_str())
+        .take(3)
+        .collect();
+    assert_eq!(seen, expected);
+
+    // Reading stopped shortly after the header, far from the end
+    let file_size = std::fs::metadata(FIXTURE_EIX).unwrap().len();
+    let position = reader.position();
+    assert!(position < file_size / 4, "{} of {}", position, file_size);
+    assert!(read.get() >= position);
+    assert!(read.get() - header_len < file_size / 4, "{}", read.get());
+
+    // The reader continues with the package after the last hit
+    let next = reader.read_package().unwrap().unwrap();
+    let all = fixture_packages();
+    let last = all
+        .iter()
+        .position(|p| p.category == "app-misc" && p.name == seen[2])
+        .unwrap();
+    assert_eq!(next.name, all[last + 1].name);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{EixWriter, ParsedDb, concat_categories, split_by_category};
+use std::collections::BTreeSet;
+
+fn package_set(packages: &[eix::Package]) -> BTreeSet<(String, String, Vec<String>)> {
+    packages
+        .iter()
+        .map(|p| {
+            let versions = p
+                .versions
+                .iter()
+                .map(|v| format!("{}:{}::{}", v.version_string, v.slot, v.reponame))
+                .collect();
+            (p.category.clone(), p.name.clone(), versions)
+        })
+        .collect()
+}
+
+#[test]
+fn test_split_and_concat_fixture() {
+    let (header, packages) = fixture();
+    let dir = tempfile::tempdir().unwrap();
+    let files = split_by_category(FIXTURE_EIX, dir.path(), |_| true).unwrap();
+    assert_eq!(files.len(), header.size as usize);
+
+    let lang = ParsedDb::load(dir.path().join("dev-lang.eix")).unwrap();
+    assert_eq!(lang.header.size, 1);
+    assert_eq!(lang.header.overlays.len(), 1);
+    assert!(lang.header.license_hash.len() < header.license_hash.len());
+    assert!(lang.packages.iter().all(|p| p.category == "dev-lang"));
+
+    let (concat_header, concat) = concat_categories(&files).unwrap();
+    assert_eq!(concat_header.size, header.size);
+    assert_eq!(concat.len(), packages.len());
+    assert_eq!(package_set(&concat), package_set(packages));
+
+    // The concatenation is a valid database again
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let mut writer = EixWriter::new(std::fs::File::create(file.path()).unwrap());
+    writer.write_database(&concat_header, &concat).unwrap();
+    drop(writer);
+    let parsed = ParsedDb::load(file.path()).unwrap();
+    assert_eq!(package_set(&parsed.packages), package_set(packages));
+}
+
+#[test]
+fn test_split_filter() {
+    let dir = tempfile::tempdir().unwrap();
+    let files = split_by_category(FIXTURE_EIX, dir.path(), |c| c.starts_with("dev-")).unwrap();
+    assert!(!files.is_empty());
+    let (_, packages) = concat_categories(&files).unwrap();
+    let expected: Vec<_> = fixture()
+        .1
+        .iter()
+        .filter(|p| p.category.starts_with("dev-"))
+        .cloned()
+        .collect();
+    assert_eq!(package_set(&packages), package_set(&expected));
+}
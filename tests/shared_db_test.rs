@@ -0,0 +1,70 @@
+#![cfg(feature = "fs")]
+
+mod common;
+
+use common::FIXTURE_EIX;
+use eix::{Atom, PackageQuery, ParsedDb, SharedDb};
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_shared_db_lookups() {
+    assert_send_sync::<SharedDb>();
+    let db = SharedDb::new(ParsedDb::load(FIXTURE_EIX).unwrap());
+    assert_eq!(db.packages().len(), 19176);
+    assert_eq!(db.categories().count(), db.header().size as usize);
+
+    let python = db.get(&Atom::parse("dev-lang/python").unwrap()).unwrap();
+    assert_eq!(python.name, "python");
+    assert!(
+        db.get(&Atom::parse(">=dev-lang/python-999").unwrap())
+            .is_none()
+    );
+    assert!(
+        db.get(&Atom::parse("dev-lang/no-such-package").unwrap())
+            .is_none()
+    );
+
+    let dev_lang = db.category("dev-lang");
+    assert!(!dev_lang.is_empty());
+    assert!(dev_lang.iter().all(|p| p.category == "dev-lang"));
+    assert!(db.category("no-such-category").is_empty());
+
+    let query = PackageQuery {
+        name: Some("python".to_string()),
+        ..PackageQuery::category("dev-lang")
+    };
+    assert!(db.query(&query).any(|p| p.name == "python"));
+    assert_eq!(
+        db.query(&PackageQuery::category("dev-lang")).count(),
+        dev_lang.len()
+    );
+}
+
+#[test]
+fn test_shared_db_threads() {
+    let db = SharedDb::from(ParsedDb::load(FIXTURE_EIX).unwrap());
+    let categories: Vec<String> = db.categories().map(str::to_string).collect();
+    let handles: Vec<_> = (0..8)
+        .map(|n| {
+            let db = db.clone();
+            let categories = categories.clone();
+            thread::spawn(move || {
+                let mut found = 0;
+                for category in categories.iter().skip(n).step_by(8) {
+                    for pkg in db.category(category) {
+                        let atom = Atom::new(&pkg.category, &pkg.name);
+                        assert!(std::ptr::eq(db.get(&atom).unwrap(), pkg));
+                        found += 1;
+                    }
+                    let query = PackageQuery::category(category);
+                    assert_eq!(db.query(&query).count(), db.category(category).len());
+                }
+                found
+            })
+        })
+        .collect();
+    let total: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    assert_eq!(total, db.packages().len());
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::fixture;
+use eix::{EixTestBuilder, Version};
+use std::sync::Arc;
+
+#[test]
+fn test_versions_share_the_header_label() {
+    let (header, packages) = fixture();
+    let mut versions = 0;
+    for v in packages.iter().flat_map(|p| &p.versions) {
+        let overlay = &header.overlays[v.overlay_key as usize];
+        assert!(Arc::ptr_eq(&v.reponame, &overlay.label));
+        versions += 1;
+    }
+    let references: usize = header
+        .overlays
+        .iter()
+        .map(|o| Arc::strong_count(&o.label) - 1)
+        .sum();
+    assert_eq!(references, versions);
+}
+
+#[test]
+fn test_serialized_as_string() {
+    let (_, packages) = EixTestBuilder::new()
+        .overlay("gentoo", "/var/db/repos/gentoo")
+        .overlay("guru", "/var/db/repos/guru")
+        .category("app-misc")
+        .package("screen", |p| p.version("4.9.1", |v| v.overlay(1)))
+        .build();
+    let version = &packages[0].versions[0];
+
+    let value = serde_json::to_value(version).unwrap();
+    assert_eq!(value["reponame"], "guru");
+    assert_eq!(value["priority"], 1);
+    assert_eq!(value["overlay_key"], 1);
+
+    let json = serde_json::to_string(version).unwrap();
+    assert!(
+        json.contains(r#""reponame":"guru","priority":1,"#),
+        "{}",
+        json
+    );
+    let back: Version = serde_json::from_str(&json).unwrap();
+    assert_eq!(&*back.reponame, "guru");
+    assert_eq!(serde_json::to_string(&back).unwrap(), json);
+}
+
+#[test]
+fn test_label_not_counted_per_version() {
+    let build = |label: &str| {
+        EixTestBuilder::new()
+            .overlay(label, "/var/db/repos/overlay")
+            .category("app-misc")
+            .package("screen", |p| {
+                p.version("4.9.1", |v| v.keywords("amd64"))
+                    .version("9999", |v| v)
+            })
+            .build()
+    };
+    let (short_header, short) = build("a");
+    let (long_header, long) = build("a-very-long-overlay-name");
+    assert_eq!(short[0].approx_heap_size(), long[0].approx_heap_size());
+    assert!(long_header.approx_heap_size() > short_header.approx_heap_size());
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::SizeReport;
+
+#[test]
+fn test_fixture_sizes_sum_to_file_size() {
+    let (header, _) = fixture();
+    let report = SizeReport::analyze(FIXTURE_EIX).unwrap();
+    assert_eq!(
+        report.file_size,
+        std::fs::metadata(FIXTURE_EIX).unwrap().len()
+    );
+    assert_eq!(report.trailing, 0);
+    assert_eq!(
+        report.header.total + report.packages.total + report.trailing,
+        report.file_size
+    );
+
+    let h = &report.header;
+    assert_eq!(
+        h.preamble
+            + h.overlays
+            + h.eapi_hash
+            + h.license_hash
+            + h.keywords_hash
+            + h.iuse_hash
+            + h.slot_hash
+            + h.world_sets
+            + h.bitmask
+            + h.depend_hash,
+        h.total
+    );
+    assert!(h.depend_hash > h.slot_hash);
+
+    assert_eq!(report.categories.len(), header.size as usize);
+    let p = &report.packages;
+    assert_eq!(
+        p.framing + p.package_metadata + p.versions + p.depends + p.src_uri,
+        p.total
+    );
+    assert_eq!(
+        report.categories.iter().map(|c| c.sizes.total).sum::<u64>(),
+        p.total
+    );
+    assert_eq!(
+        report.categories.iter().map(|c| c.packages).sum::<usize>(),
+        19176
+    );
+    assert!(p.depends > 0 && p.src_uri > 0);
+
+    assert_eq!(report.largest_packages.len(), 10);
+    assert!(
+        report
+            .largest_packages
+            .windows(2)
+            .all(|w| w[0].bytes >= w[1].bytes)
+    );
+}
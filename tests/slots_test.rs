@@ -0,0 +1,67 @@
+mod common;
+
+use common::read_database;
+use eix::{EixTestBuilder, MASK_PACKAGE, Package};
+
+/// A few versions of dev-lang/python covering stable, testing, masked
+/// and unkeyworded versions in several slots
+fn python() -> Package {
+    let (_, mut packages) = read_database(
+        EixTestBuilder::new()
+            .category("dev-lang")
+            .package("python", |p| {
+                p.version("2.7.18_p16", |v| v.slot("2.7").keywords("amd64 x86"))
+                    .version("3.12.11", |v| v.slot("3.12/3.12").keywords("amd64"))
+                    .version("3.12.12", |v| v.slot("3.12/3.12").keywords("amd64 ~arm64"))
+                    .version("3.13.9", |v| v.slot("3.13/3.13").keywords("amd64"))
+                    .version("3.13.10", |v| {
+                        v.slot("3.13/3.13").keywords("~amd64").mask(MASK_PACKAGE)
+                    })
+                    .version("3.13.11", |v| v.slot("3.13/3.13").keywords("~amd64"))
+                    .version("3.13.9999", |v| v.slot("3.13/3.13"))
+                    .version("3.13.11", |v| v.slot("3.13t/3.13").keywords("~amd64"))
+                    .version("3.14.0_p1", |v| v.slot("3.14/3.14").keywords("~amd64"))
+                    .version("3.15.0_alpha1", |v| v.slot("3.15/3.15"))
+            })
+            .build_database(),
+    );
+    packages.remove(0)
+}
+
+#[test]
+fn test_python_slots() {
+    let python = python();
+    let slots = python.slots();
+    assert!(slots.contains("2.7"));
+    assert!(slots.contains("3.13"));
+    assert!(slots.contains("3.13t"));
+
+    // Every slot has at least one version, so without arch all slots appear
+    let all = python.best_per_slot(None, true);
+    assert_eq!(all.len(), slots.len());
+}
+
+#[test]
+fn test_python_best_per_slot() {
+    let python = python();
+
+    let stable = python.best_per_slot(Some("amd64"), false);
+    assert_eq!(stable["3.12"].version_string, "3.12.12");
+    // 3.13.10 and 3.13.11 are testing-only, 3.13.9999 is unkeyworded
+    assert_eq!(stable["3.13"].version_string, "3.13.9");
+    assert!(!stable.contains_key("3.15"));
+
+    let testing = python.best_per_slot(Some("amd64"), true);
+    // 3.13.10 is masked and must be skipped in favour of 3.13.11
+    assert_eq!(testing["3.13"].version_string, "3.13.11");
+    assert_eq!(testing["3.14"].version_string, "3.14.0_p1");
+}
+
+#[test]
+fn test_subslots_do_not_split() {
+    let python = python();
+    for (slot, v) in python.best_per_slot(None, true) {
+        assert!(!slot.contains('/'));
+        assert_eq!(v.normalized_slot(), slot);
+    }
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::fixture_packages;
+use eix::{BasicPart, Version, compare_parts, parse_version};
+use std::cmp::Ordering;
+
+fn versions() -> impl Iterator<Item = &'static Version> {
+    fixture_packages().iter().flat_map(|p| &p.versions)
+}
+
+/// The feature only changes temporary storage; the public lists are
+/// vectors either way
+#[test]
+fn test_lists_are_vecs() {
+    let v = versions().next().unwrap().clone();
+    let _: Vec<BasicPart> = v.parts;
+    let _: Vec<String> = v.keywords;
+    let _: Vec<String> = v.iuse;
+}
+
+#[test]
+fn test_parse_version_matches_decoded_parts() {
+    for v in versions() {
+        let parts = parse_version(&v.version_string).unwrap();
+        assert_eq!(compare_parts(&parts, &v.parts), Ordering::Equal);
+    }
+}
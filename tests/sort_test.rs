@@ -0,0 +1,79 @@
+mod common;
+
+use common::{fixture, fixture_packages, read_database};
+use eix::{Database, EixWriter, Package, SerializeOptions, SortOrder, sort_packages};
+
+/// Reorders packages and versions deterministically
+fn shuffle(packages: &mut [Package]) {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+    for i in (1..packages.len()).rev() {
+        packages.swap(i, next(i + 1));
+    }
+    for package in packages.iter_mut() {
+        package.versions.reverse();
+    }
+}
+
+fn sorted(mut packages: Vec<Package>) -> Vec<Package> {
+    sort_packages(&mut packages, SortOrder::Canonical);
+    packages
+}
+
+#[test]
+fn test_shuffled_fixture_sorts_identically() {
+    let mut shuffled = fixture_packages().to_vec();
+    shuffle(&mut shuffled);
+    let expected = serde_json::to_string(&sorted(fixture_packages().to_vec())).unwrap();
+    assert_ne!(serde_json::to_string(&shuffled).unwrap(), expected);
+    assert_eq!(
+        serde_json::to_string(&sorted(shuffled.clone())).unwrap(),
+        expected
+    );
+
+    // The serializer sorts on its own
+    let options = SerializeOptions {
+        canonical_order: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        serde_json::to_string(&options.view_list(&shuffled)).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn test_sorted_writer() {
+    let (header, packages) = fixture();
+    let mut shuffled = packages.clone();
+    shuffle(&mut shuffled);
+
+    let write = |packages: &[Package]| {
+        let mut writer = EixWriter::new(Vec::new()).sorted(SortOrder::Canonical);
+        writer.write_database(header, packages).unwrap();
+        writer.into_inner()
+    };
+    let bytes = write(&shuffled);
+    assert_eq!(bytes, write(packages));
+
+    let (_, read) = read_database(Database::from_bytes(bytes));
+    assert_eq!(
+        serde_json::to_string(&read).unwrap(),
+        serde_json::to_string(&sorted(packages.clone())).unwrap()
+    );
+}
+
+#[test]
+fn test_default_serialization_keeps_file_order() {
+    let packages = &fixture_packages()[..50];
+    let options = SerializeOptions::default();
+    assert_eq!(
+        serde_json::to_string(&options.view_list(packages)).unwrap(),
+        serde_json::to_string(packages).unwrap()
+    );
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::FIXTURE_EIX;
+use eix::codec::decode_num;
+use eix::{DB_VERSION_CURRENT, Database, OpenOptions, PackageReader};
+
+#[test]
+fn test_fixture_spans() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let options = OpenOptions {
+        record_spans: true,
+        ..Default::default()
+    };
+    let mut db = Database::open_read_with(FIXTURE_EIX, options).unwrap();
+    let header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    let header_end = db.position();
+    let mut reader = PackageReader::new(db, header);
+
+    let mut last_end = header_end;
+    let mut count = 0;
+    while reader.next_category().unwrap() {
+        let mut previous_end = None;
+        while let Some(pkg) = reader.read_package().unwrap() {
+            let span = pkg.span.unwrap();
+            assert!(span.start >= last_end);
+            // Packages of a category follow each other directly
+            if let Some(end) = previous_end {
+                assert_eq!(span.start, end);
+            }
+
+            // The length prefix covers the rest of the package
+            let (declared, prefix_len) = decode_num(&data[span.start as usize..]).unwrap();
+            assert_eq!(prefix_len as u64 + declared, span.len());
+
+            let mut version_end = None;
+            for v in &pkg.versions {
+                let vspan = v.span.unwrap();
+                assert!(!vspan.is_empty());
+                assert!(vspan.start > span.start && vspan.end <= span.end);
+                if let Some(end) = version_end {
+                    assert_eq!(vspan.start, end);
+                }
+                version_end = Some(vspan.end);
+            }
+            assert_eq!(version_end, Some(span.end));
+
+            previous_end = Some(span.end);
+            last_end = span.end;
+            count += 1;
+        }
+    }
+    assert_eq!(count, 19176);
+    assert_eq!(last_end, data.len() as u64);
+}
+
+#[test]
+fn test_spans_off_by_default() {
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    let header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    let mut reader = PackageReader::new(db, header);
+    assert!(reader.next_category().unwrap());
+    let pkg = reader.read_package().unwrap().unwrap();
+    assert!(pkg.span.is_none());
+    assert!(pkg.versions.iter().all(|v| v.span.is_none()));
+}
@@ -0,0 +1,106 @@
+use eix::{Database, EixTestBuilder, EixWriter, PackageReader, split};
+
+fn builder() -> EixTestBuilder {
+    EixTestBuilder::new()
+        .overlay("gentoo", "/var/db/repos/gentoo")
+        .overlay("local", "/var/db/repos/local")
+        .category("app-misc")
+        .package("screen", |p| {
+            p.description("Screen manager")
+                .version("4.9.0", |v| v)
+                .version("4.9.1", |v| v.overlay(1))
+                .version("5.0.0", |v| v.overlay(1))
+        })
+        .package("tmux", |p| p.version("3.4", |v| v))
+        .category("dev-lang")
+        .package("mylang", |p| p.version("0.1", |v| v.overlay(1)))
+}
+
+fn versions(packages: &[eix::Package]) -> Vec<String> {
+    packages
+        .iter()
+        .flat_map(|p| {
+            p.versions
+                .iter()
+                .map(move |v| format!("{}/{}-{}", p.category, p.name, v.version_string))
+        })
+        .collect()
+}
+
+#[test]
+fn test_split_by_overlay() {
+    let mut db = Database::from_bytes(builder().build_bytes());
+    let header = db.read_header(0).unwrap();
+    let packages = PackageReader::new(db, header.clone())
+        .read_all()
+        .unwrap()
+        .into_inner();
+    let split = split::by_overlay(&header, &packages);
+    assert_eq!(split.len(), 2);
+
+    let (gentoo_header, gentoo) = &split["gentoo"];
+    assert_eq!(
+        versions(gentoo),
+        ["app-misc/screen-4.9.0", "app-misc/tmux-3.4"]
+    );
+    assert_eq!(gentoo_header.size, 1);
+    assert_eq!(gentoo_header.overlays.len(), 1);
+    assert_eq!(&*gentoo_header.overlays[0].label, "gentoo");
+
+    let (local_header, local) = &split["local"];
+    assert_eq!(
+        versions(local),
+        [
+            "app-misc/screen-4.9.1",
+            "app-misc/screen-5.0.0",
+            "dev-lang/mylang-0.1"
+        ]
+    );
+    assert_eq!(local_header.size, 2);
+    assert_eq!(&*local_header.overlays[0].path, "/var/db/repos/local");
+    // The package keeps its metadata in each output
+    assert_eq!(local[0].description, "Screen manager");
+
+    for (label, (header, packages)) in &split {
+        assert!(
+            packages
+                .iter()
+                .flat_map(|p| &p.versions)
+                .all(|v| v.overlay_key == 0 && &*v.reponame == label)
+        );
+
+        // Each output is a valid database
+        let mut out = Vec::new();
+        EixWriter::new(&mut out)
+            .write_database(header, packages)
+            .unwrap();
+        let mut db = Database::from_bytes(out);
+        let read_header = db.read_header(0).unwrap();
+        assert_eq!(read_header.size, header.size);
+        let read = PackageReader::new(db, read_header)
+            .read_all()
+            .unwrap()
+            .into_inner();
+        assert_eq!(versions(&read), versions(packages));
+        assert!(
+            read.iter()
+                .flat_map(|p| &p.versions)
+                .all(|v| &*v.reponame == label)
+        );
+    }
+}
+
+#[test]
+fn test_split_empty_overlay() {
+    let (header, packages) = EixTestBuilder::new()
+        .overlay("gentoo", "/var/db/repos/gentoo")
+        .overlay("unused", "/var/db/repos/unused")
+        .category("app-misc")
+        .package("screen", |p| p.version("4.9.1", |v| v))
+        .build();
+    let split = split::by_overlay(&header, &packages);
+    let (unused_header, unused) = &split["unused"];
+    assert!(unused.is_empty());
+    assert_eq!(unused_header.size, 0);
+    assert_eq!(split["gentoo"].1.len(), 1);
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{
+    ArchCoverage, AttributeStats, DB_VERSION_CURRENT, Database, DatabaseStats, PackageReader,
+};
+use std::collections::BTreeMap;
+
+#[test]
+fn test_streaming_stats_match_full_parse() {
+    let (header, packages) = fixture();
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    let header_read = db.read_header(DB_VERSION_CURRENT).unwrap();
+    let mut reader = PackageReader::new(db, header_read);
+    let streamed = DatabaseStats::from_reader(&mut reader, 5).unwrap();
+    let parsed = DatabaseStats::compute(packages, 5);
+    assert_eq!(streamed, parsed);
+
+    let versions: usize = packages.iter().map(|p| p.versions.len()).sum();
+    assert_eq!(streamed.categories, header.size as usize);
+    assert_eq!(streamed.categories, 174);
+    assert_eq!(streamed.packages, 19176);
+    assert_eq!(streamed.versions, versions);
+    assert_eq!(streamed.versions_per_overlay.len(), 1);
+    assert_eq!(streamed.versions_per_overlay["gentoo"], versions);
+    assert_eq!(streamed.top_categories.len(), 5);
+    assert!(
+        streamed
+            .top_categories
+            .windows(2)
+            .all(|w| w[0].packages >= w[1].packages)
+    );
+    assert!(streamed.live > 0);
+    assert!(streamed.fetch_restricted > 0);
+    assert!(streamed.distinct_slots > 1);
+    assert!(streamed.distinct_licenses > 1);
+    assert!(streamed.avg_versions_per_package > 1.0);
+}
+
+#[test]
+fn test_attribute_stats_match_full_parse() {
+    let (header, packages) = fixture();
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    db.read_header(DB_VERSION_CURRENT).unwrap();
+    let stats = AttributeStats::from_database(&mut db, header).unwrap();
+
+    let mut eapi: BTreeMap<String, usize> = BTreeMap::new();
+    let mut amd64 = ArchCoverage::default();
+    for v in packages.iter().flat_map(|p| &p.versions) {
+        *eapi.entry(v.eapi.clone()).or_default() += 1;
+        if v.keywords.iter().any(|k| k == "amd64") {
+            amd64.stable += 1;
+        }
+        if v.keywords.iter().any(|k| k == "~amd64") {
+            amd64.testing += 1;
+        }
+    }
+    assert_eq!(stats.versions, eapi.values().sum::<usize>());
+    assert_eq!(stats.eapi, eapi);
+    assert!(stats.eapi["8"] > stats.eapi["7"]);
+    assert_eq!(stats.arches["amd64"], amd64);
+    assert_eq!(stats.slots.values().sum::<usize>(), stats.versions);
+    assert_eq!(stats.licenses.values().sum::<usize>(), stats.versions);
+    // At least the versions of dev-lang/lua
+    assert!(stats.licenses["MIT"] >= 4);
+
+    // Any reader will do
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let mut db = Database::new(data.as_slice());
+    let header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    assert_eq!(
+        AttributeStats::from_database(&mut db, &header).unwrap(),
+        stats
+    );
+}
+
+#[test]
+fn test_stats_distributions() {
+    let (_, packages) = fixture();
+    let stats = DatabaseStats::compute(packages, 3);
+    assert_eq!(stats.hard_masked, 275);
+    // dev-util/buildbot-worker-9999 lacks PROPERTIES=live
+    assert_eq!(stats.live, 1770);
+    assert_eq!(stats.eapi.len(), 2);
+    assert_eq!(stats.eapi["7"], 3915);
+    assert_eq!(stats.eapi["8"], 27815);
+    assert_eq!(stats.licenses.values().sum::<usize>(), stats.versions);
+
+    assert_eq!(stats.per_category.len(), stats.categories);
+    assert_eq!(stats.per_category["dev-perl"].packages, 1764);
+    let dev_perl: usize = packages
+        .iter()
+        .filter(|p| p.category == "dev-perl")
+        .map(|p| p.versions.len())
+        .sum();
+    assert_eq!(stats.per_category["dev-perl"].versions, dev_perl);
+
+    let largest: Vec<(&str, usize)> = stats
+        .largest_packages
+        .iter()
+        .map(|p| (p.atom.as_str(), p.versions))
+        .collect();
+    assert_eq!(
+        largest,
+        [
+            ("app-doc/phrack", 71),
+            ("sys-kernel/gentoo-sources", 53),
+            ("dev-lang/python", 47)
+        ]
+    );
+
+    let json = serde_json::to_value(&stats).unwrap();
+    assert_eq!(json["per_category"]["dev-perl"]["packages"], 1764);
+    assert_eq!(json["largest_packages"][0]["atom"], "app-doc/phrack");
+
+    let text = stats.to_string();
+    assert!(text.contains("Hard masked:       275\n"));
+    assert!(
+        text.contains("Versions per EAPI:\n  7                3915\n  8                27815\n")
+    );
+    assert!(text.ends_with("  dev-lang/python              47\n"));
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture, fixture_package};
+use eix::{DB_VERSION_CURRENT, Database, OpenOptions, PackageReader};
+use std::io::{self, Cursor, Read};
+
+/// A reader without `Seek`, like stdin or a pipe
+struct NoSeek<R>(R);
+
+impl<R: Read> Read for NoSeek<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn test_parse_without_seek() {
+    let (header, packages) = fixture();
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let mut db = Database::new(NoSeek(Cursor::new(data)));
+    let stream_header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    assert_eq!(stream_header.size, header.size);
+
+    let mut reader = PackageReader::new(db, stream_header);
+    let mut count = 0;
+    while reader.next_category().unwrap() {
+        while let Some(pkg) = reader.read_package().unwrap() {
+            let expected = &packages[count];
+            assert_eq!(pkg.category, expected.category);
+            assert_eq!(pkg.name, expected.name);
+            assert_eq!(pkg.versions.len(), expected.versions.len());
+            count += 1;
+        }
+    }
+    assert_eq!(count, packages.len());
+}
+
+#[test]
+fn test_read_package_at() {
+    let options = OpenOptions {
+        record_spans: true,
+        ..Default::default()
+    };
+    let mut db = Database::open_read_with(FIXTURE_EIX, options).unwrap();
+    let header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    let mut reader = PackageReader::new(db, header.clone());
+    let mut spans = Vec::new();
+    while reader.next_category().unwrap() {
+        while let Some(pkg) = reader.read_package().unwrap() {
+            if pkg.name == "python" || pkg.name == "zstd" {
+                spans.push((pkg.category, pkg.name, pkg.span.unwrap()));
+            }
+        }
+    }
+    assert!(!spans.is_empty());
+
+    // Backwards, to actually seek
+    let mut db = Database::open_seekable(FIXTURE_EIX).unwrap();
+    for (category, name, span) in spans.iter().rev() {
+        let pkg = db.read_package_at(&header, category, span.start).unwrap();
+        assert_eq!(&pkg.name, name);
+        assert_eq!(db.position(), span.end);
+        let expected = fixture_package(category, name);
+        assert_eq!(pkg.versions.len(), expected.versions.len());
+    }
+}
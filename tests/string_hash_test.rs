@@ -0,0 +1,89 @@
+mod common;
+
+use common::FIXTURE_EIX;
+use eix::{DBHeader, Database, FrozenStringHash, SerializeOptions, StringHash};
+
+fn hashes(header: &DBHeader) -> [(&'static str, &StringHash); 6] {
+    [
+        ("eapi", &header.eapi_hash),
+        ("license", &header.license_hash),
+        ("keywords", &header.keywords_hash),
+        ("iuse", &header.iuse_hash),
+        ("slot", &header.slot_hash),
+        ("depend", &header.depend_hash),
+    ]
+}
+
+#[test]
+fn test_frozen_and_bidirectional_hashes_agree() {
+    let header = Database::open_read(FIXTURE_EIX)
+        .unwrap()
+        .read_header(0)
+        .unwrap();
+
+    // The bidirectional variant as built by the writer
+    let built: Vec<StringHash> = hashes(&header)
+        .iter()
+        .map(|(_, hash)| {
+            let mut built = StringHash::new();
+            for i in 0..hash.len() {
+                built.add(hash.get_string(i).unwrap().to_string());
+            }
+            built
+        })
+        .collect();
+
+    for ((name, hash), built) in hashes(&header).into_iter().zip(&built) {
+        assert_eq!(built.len(), hash.len(), "{} hash", name);
+        let strings: Vec<String> = (0..hash.len())
+            .map(|i| hash.get_string(i).unwrap().to_string())
+            .collect();
+        let frozen = FrozenStringHash::from(strings);
+        for i in 0..hash.len() {
+            assert_eq!(built.get_string(i), hash.get_string(i), "{} hash", name);
+            assert_eq!(frozen.get_string(i), hash.get_string(i), "{} hash", name);
+            let s = hash.get_string(i).unwrap();
+            assert_eq!(hash.get_index(s), Some(i), "{} hash", name);
+        }
+        assert_eq!(hash.clone().freeze(), frozen);
+    }
+    assert!(header.depend_hash.len() > 10_000);
+}
+
+#[test]
+fn test_hashes_serialize_in_index_order() {
+    let header = &common::fixture().0;
+    let default = serde_json::to_value(header).unwrap();
+    assert!(default.get("license_hash").is_none());
+    assert_eq!(
+        serde_json::to_value(SerializeOptions::default().view_header(header)).unwrap(),
+        default
+    );
+
+    let options = SerializeOptions {
+        string_hashes: true,
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&options.view_header(header)).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let licenses = value["license_hash"].as_array().unwrap();
+    assert_eq!(licenses.len(), header.license_hash.len());
+
+    let back: DBHeader = serde_json::from_str(&json).unwrap();
+    for ((name, hash), (_, copy)) in hashes(header).into_iter().zip(hashes(&back)) {
+        assert_eq!(copy.len(), hash.len(), "{} hash", name);
+        for index in 0..hash.len() {
+            let s = hash.get_string(index).unwrap();
+            assert_eq!(copy.get_string(index), Some(s), "{} hash", name);
+            assert_eq!(copy.get_index(s), hash.get_index(s), "{} hash", name);
+        }
+    }
+    assert_eq!(
+        serde_json::to_string(&options.view_header(&back)).unwrap(),
+        json
+    );
+
+    // Without hashes, a header reads back with empty ones
+    let back: DBHeader = serde_json::from_value(default).unwrap();
+    assert!(hashes(&back).iter().all(|(_, hash)| hash.is_empty()));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture_packages};
+use eix::{
+    DB_VERSION_CURRENT, Database, EixTestBuilder, OpenOptions, Package, PackageReader,
+    PackageSummary, parse_bytes_with,
+};
+
+fn summaries(data: &[u8]) -> std::io::Result<Vec<PackageSummary>> {
+    let mut db = Database::from_bytes(data);
+    let header = db.read_header(0)?;
+    let mut reader = PackageReader::new(db, header);
+    let mut summaries = Vec::new();
+    while reader.next_category()? {
+        while let Some(summary) = reader.read_summary()? {
+            summaries.push(summary);
+        }
+    }
+    Ok(summaries)
+}
+
+fn assert_summarizes(summary: &PackageSummary, package: &Package) {
+    assert_eq!(summary.category, package.category);
+    assert_eq!(summary.name, package.name);
+    assert_eq!(summary.description, package.description);
+    assert_eq!(summary.homepage, package.homepage);
+    assert_eq!(summary.licenses, package.licenses);
+    assert_eq!(summary.version_count, package.versions.len());
+}
+
+#[test]
+fn test_summaries_match_full_parse() {
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    let summaries = summaries(&data).unwrap();
+    let packages = fixture_packages();
+    assert_eq!(summaries.len(), packages.len());
+    for (summary, package) in summaries.iter().zip(packages) {
+        assert_summarizes(summary, package);
+    }
+}
+
+#[test]
+fn test_mixed_with_full_reads() {
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    let header = db.read_header(DB_VERSION_CURRENT).unwrap();
+    let mut reader = PackageReader::new(db, header);
+    let packages = fixture_packages();
+    let mut i = 0;
+    while reader.next_category().unwrap() {
+        loop {
+            if i % 2 == 0 {
+                let Some(summary) = reader.read_summary().unwrap() else {
+                    break;
+                };
+                assert_summarizes(&summary, &packages[i]);
+            } else {
+                let Some(package) = reader.read_package().unwrap() else {
+                    break;
+                };
+                assert_eq!(package.name, packages[i].name);
+                assert_eq!(package.versions.len(), packages[i].versions.len());
+            }
+            i += 1;
+        }
+    }
+    assert_eq!(i, packages.len());
+}
+
+#[test]
+fn test_short_package_length() {
+    let data = EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| {
+            p.description("screen manager")
+                .version("4.9.1", |v| v.keywords("amd64"))
+        })
+        .build_bytes();
+    let options = OpenOptions {
+        record_spans: true,
+        ..Default::default()
+    };
+    let (_, packages) = parse_bytes_with(&data, options).unwrap();
+    let start = packages[0].span.unwrap().start as usize;
+
+    let mut corrupt = data.clone();
+    corrupt[start] = 4;
+    let err = summaries(&corrupt).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("app-misc/screen"), "{}", err);
+}
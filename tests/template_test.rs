@@ -0,0 +1,92 @@
+mod common;
+
+use common::fixture_package;
+use eix::{InstalledIndex, InstalledVersion, OutputTemplate, RenderContext};
+
+fn render(template: &str, category: &str, name: &str, context: &RenderContext) -> String {
+    OutputTemplate::parse(template)
+        .unwrap()
+        .render(fixture_package(category, name), context)
+}
+
+#[test]
+fn test_render_package_fields() {
+    let context = RenderContext {
+        arch: Some("amd64"),
+        ..Default::default()
+    };
+    assert_eq!(
+        render(
+            r"<category>/<name> <bestversion>\n",
+            "app-misc",
+            "screen",
+            &context
+        ),
+        "app-misc/screen 4.9.1-r2\n"
+    );
+    assert_eq!(
+        render(
+            r"<name>\t<license>\t<homepage>\t<description>",
+            "app-arch",
+            "zstd",
+            &context
+        ),
+        "zstd\t|| ( BSD GPL-2 )\thttps://facebook.github.io/zstd/\tzstd fast compression library"
+    );
+    let testing = RenderContext {
+        accept_unstable: true,
+        ..context
+    };
+    assert_eq!(
+        render("<bestversion>", "app-editors", "vim", &testing),
+        "9.1.1652-r2"
+    );
+}
+
+#[test]
+fn test_render_versions() {
+    let template = r"<category>/<name>:{versions} <version>:<slot>::<repo>(<eapi>){/versions}\n";
+    assert_eq!(
+        render(template, "app-misc", "screen", &RenderContext::default()),
+        "app-misc/screen: 4.9.1-r1:0::gentoo(8) 4.9.1-r2:0::gentoo(8) 5.0.1:0::gentoo(8) \
+         9999:0::gentoo(8)\n"
+    );
+    assert_eq!(
+        render(
+            "{versions}<slot> {/versions}",
+            "app-arch",
+            "zstd",
+            &RenderContext::default()
+        ),
+        "0/1 0/1 "
+    );
+    let keywords = render(
+        r"{versions}<version>: <keywords>\n{/versions}",
+        "app-misc",
+        "screen",
+        &RenderContext::default(),
+    );
+    assert!(keywords.ends_with("9999: \n"));
+    assert!(keywords.starts_with("4.9.1-r1: ~alpha amd64 arm "));
+}
+
+#[test]
+fn test_render_installed() {
+    let mut installed = InstalledIndex::new();
+    installed.insert(
+        InstalledVersion::new("app-misc", "screen", "4.9.1-r2", "0", Some("gentoo")).unwrap(),
+    );
+    let context = RenderContext {
+        installed: Some(&installed),
+        ..Default::default()
+    };
+    let template = r"<installed><name>{versions} <installed><version>{/versions}";
+    assert_eq!(
+        render(template, "app-misc", "screen", &context),
+        "[I]screen 4.9.1-r1 [I]4.9.1-r2 5.0.1 9999"
+    );
+    assert_eq!(
+        render(template, "app-arch", "zstd", &context),
+        "zstd 1.5.7 1.5.7-r1"
+    );
+}
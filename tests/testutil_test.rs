@@ -0,0 +1,67 @@
+mod common;
+
+use common::read_database;
+use eix::{Depend, EixTestBuilder, RESTRICT_TEST};
+
+#[test]
+fn test_builder_matches_read_back() {
+    let builder = EixTestBuilder::new()
+        .overlay("gentoo", "/usr/portage")
+        .overlay("guru", "/var/db/repos/guru")
+        .world_set("@selected")
+        .category("dev-lang")
+        .package("rust", |p| {
+            p.description("Systems programming language")
+                .homepage("https://www.rust-lang.org/")
+                .licenses("|| ( MIT Apache-2.0 )")
+                .version("1.78.0", |v| v.keywords("~amd64").slot("stable"))
+                .version("9999", |v| {
+                    v.overlay(1)
+                        .iuse("+doc test")
+                        .required_use("doc")
+                        .restrict(RESTRICT_TEST)
+                        .src_uri("https://example.org/rust.tar.gz")
+                })
+        })
+        .category("app-misc")
+        .package("screen", |p| {
+            p.version("4.9.1", |v| {
+                v.eapi("7").depend(Depend {
+                    rdepend: vec!["sys-libs/ncurses".to_string()],
+                    ..Default::default()
+                })
+            })
+        })
+        .package("empty", |p| p);
+    let (header, packages) = builder.clone().build();
+    let (read_header, read) = read_database(builder.build_database());
+
+    assert_eq!(read_header.size, 2);
+    assert_eq!(
+        serde_json::to_value(&read_header.overlays).unwrap(),
+        serde_json::to_value(&header.overlays).unwrap()
+    );
+    assert_eq!(read_header.world_sets, vec!["@selected"]);
+    assert!(read_header.use_depend && read_header.use_required_use && read_header.use_src_uri);
+    assert_eq!(
+        serde_json::to_value(&read).unwrap(),
+        serde_json::to_value(&packages).unwrap()
+    );
+    assert_eq!(&*read[0].versions[1].reponame, "guru");
+    assert_eq!(read[1].versions[0].eapi, "7");
+    assert_eq!(read[1].versions[0].src_uri.as_deref(), Some(""));
+}
+
+#[test]
+fn test_builder_without_overlays() {
+    let (header, packages) = read_database(
+        EixTestBuilder::new()
+            .category("app-misc")
+            .package("screen", |p| p.version("4.9.1", |v| v))
+            .build_database(),
+    );
+    assert_eq!(header.overlays.len(), 1);
+    assert_eq!(&*packages[0].versions[0].reponame, "gentoo");
+    assert!(!header.use_depend && !header.use_src_uri);
+    assert!(packages[0].versions[0].depend.is_none());
+}
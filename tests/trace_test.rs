@@ -0,0 +1,113 @@
+#![cfg(feature = "trace")]
+
+mod common;
+
+use common::FIXTURE_EIX;
+use eix::{Database, TraceValue, dump_structure};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// Keeps the first `limit` lines written and counts the rest
+struct Head {
+    limit: usize,
+    lines: Vec<String>,
+    partial: Vec<u8>,
+    total: usize,
+}
+
+impl Write for Head {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            if b != b'\n' {
+                if self.lines.len() < self.limit {
+                    self.partial.push(b);
+                }
+                continue;
+            }
+            if self.lines.len() < self.limit {
+                self.lines
+                    .push(String::from_utf8(std::mem::take(&mut self.partial)).unwrap());
+            }
+            self.total += 1;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn head(limit: usize) -> Head {
+    Head {
+        limit,
+        lines: Vec::new(),
+        partial: Vec::new(),
+        total: 0,
+    }
+}
+
+#[test]
+fn test_dump_structure_snapshot() {
+    let mut out = head(10);
+    dump_structure(FIXTURE_EIX, &mut out).unwrap();
+    assert_eq!(
+        out.lines,
+        [
+            "offset 0x0000: bytes 65 69 78 0a (magic)",
+            "offset 0x0004: num 39 (database version)",
+            "offset 0x0005: num 174 (category count)",
+            "offset 0x0006: num 1 (overlay count)",
+            "offset 0x0007: string \"/var/db/repos/gentoo/\" (overlay path)",
+            "offset 0x001d: string \"gentoo\" (overlay label)",
+            "offset 0x0024: num 2 (EAPI hash)",
+            "offset 0x0025: string \"8\" (EAPI hash)",
+            "offset 0x0027: string \"7\" (EAPI hash)",
+            "offset 0x0029: num 1925 (license hash)",
+        ]
+    );
+    assert!(out.total > 19176);
+}
+
+#[test]
+fn test_dump_structure_stops_with_hex_context() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("truncated.eix");
+    let data = std::fs::read(FIXTURE_EIX).unwrap();
+    std::fs::write(&path, &data[..0x32]).unwrap();
+
+    let mut out = Vec::new();
+    let err = dump_structure(&path, &mut out).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[9], "offset 0x0029: num 1925 (license hash)");
+    assert_eq!(
+        lines[10],
+        "offset 0x002c: error: Hash count of 1925 exceeds the remaining 6 bytes"
+    );
+    assert_eq!(lines.len(), 15);
+    assert!(lines[11].starts_with("0x00000000: 65 69 78 0a"));
+    assert!(lines[13].ends_with(" ff 07 85>05 47 50 4c"));
+    // The file ends shortly after the error
+    assert_eq!(lines[14], "0x00000030: 2d 32");
+}
+
+#[test]
+fn test_tracer_receives_events() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let mut db = Database::open_read(FIXTURE_EIX).unwrap();
+    db.set_tracer(Some(Box::new(move |event| {
+        sink.lock().unwrap().push(event.clone())
+    })));
+    let header = db.read_header(0).unwrap();
+    let events = events.lock().unwrap();
+    let count = events
+        .iter()
+        .find(|e| e.context == "category count")
+        .unwrap();
+    assert_eq!(count.offset, 5);
+    assert_eq!(count.value, TraceValue::Num(header.size as u64));
+    assert_eq!(events.last().unwrap().context, "depend hash");
+}
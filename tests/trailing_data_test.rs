@@ -0,0 +1,73 @@
+use eix::{Database, EixTestBuilder, OpenOptions, PackageReader, ReadWarning, TrailingData};
+use std::io;
+
+fn builder() -> EixTestBuilder {
+    EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| p.version("4.9.1", |v| v))
+        .category("dev-lang")
+        .package("rust", |p| p.version("1.78.0", |v| v))
+}
+
+fn open(bytes: Vec<u8>, options: OpenOptions, extra_frames: i64) -> PackageReader {
+    let mut db = Database::from_bytes_with(bytes, options);
+    let mut header = db.read_header(0).unwrap();
+    header.size = (header.size as i64 + extra_frames) as _;
+    PackageReader::new(db, header)
+}
+
+#[test]
+fn test_finish_at_end() {
+    let mut reader = open(builder().build_bytes(), OpenOptions::default(), 0);
+    assert_eq!(reader.read_all().unwrap().into_inner().len(), 2);
+    assert_eq!(reader.finish().unwrap(), 0);
+    assert!(reader.warnings().is_empty());
+}
+
+#[test]
+fn test_trailing_bytes() {
+    let mut bytes = builder().build_bytes();
+    bytes.extend_from_slice(b"junk");
+    let mut reader = open(bytes, OpenOptions::default(), 0);
+    reader.read_all().unwrap();
+    assert_eq!(reader.finish().unwrap(), 4);
+    assert_eq!(reader.warnings(), [ReadWarning::TrailingBytes { len: 4 }]);
+    assert_eq!(
+        reader.warnings()[0].to_string(),
+        "4 bytes after the last category"
+    );
+
+    // A category count one too small leaves the last category unread
+    let bytes = builder().build_bytes();
+    let options = OpenOptions {
+        trailing_data: TrailingData::Strict,
+        ..Default::default()
+    };
+    let mut reader = open(bytes, options, -1);
+    let packages = reader.read_all().unwrap().into_inner();
+    assert_eq!(packages.len(), 1);
+    let err = reader.finish().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().ends_with("bytes after the last category"));
+    assert!(reader.warnings().is_empty());
+}
+
+#[test]
+fn test_finish_before_end() {
+    let mut reader = open(builder().build_bytes(), OpenOptions::default(), 0);
+    assert!(reader.next_category().unwrap());
+    let err = reader.finish().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(err.to_string(), "1 packages and 1 categories left to read");
+}
+
+#[test]
+fn test_premature_eof() {
+    let mut reader = open(builder().build_bytes(), OpenOptions::default(), 2);
+    let err = reader.read_all().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(
+        err.to_string(),
+        "File ends with 2 of 4 categories still expected"
+    );
+}
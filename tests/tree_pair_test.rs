@@ -0,0 +1,120 @@
+#![cfg(feature = "fs")]
+
+use eix::{
+    EIX_CACHEFILE_ENV, EIX_PREVIOUS_ENV, EIX_PREVIOUS_PATH, EixTestBuilder, EixWriter, EventKind,
+    OpenOptions, Package, TreePair, default_previous_paths,
+};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn write(path: &Path, builder: EixTestBuilder) {
+    let (header, packages) = builder.build();
+    let mut bytes = Vec::new();
+    EixWriter::new(&mut bytes)
+        .write_database(&header, &packages)
+        .unwrap();
+    fs::write(path, bytes).unwrap();
+}
+
+/// Writes `previous.eix` and `portage.eix` into `dir`
+fn snapshots(dir: &Path) -> (PathBuf, PathBuf) {
+    let previous = dir.join("previous.eix");
+    write(
+        &previous,
+        EixTestBuilder::new()
+            .category("app-misc")
+            .package("screen", |p| {
+                p.version("4.9.1", |v| v.keywords("~amd64 ~arm64"))
+            })
+            .package("gone", |p| p.version("1.0", |v| v.keywords("amd64")))
+            .category("dev-lang")
+            .package("rust", |p| p.version("1.78.0", |v| v.keywords("amd64"))),
+    );
+    let current = dir.join("portage.eix");
+    write(
+        &current,
+        EixTestBuilder::new()
+            .category("app-misc")
+            .package("screen", |p| {
+                p.version("4.9.1", |v| v.keywords("amd64 ~arm64"))
+            })
+            .package("tmux", |p| p.version("3.4", |v| v.keywords("~amd64")))
+            .category("dev-lang")
+            .package("rust", |p| {
+                p.version("1.78.0", |v| v.keywords("amd64"))
+                    .version("1.79.0", |v| v.keywords("~amd64"))
+            }),
+    );
+    (current, previous)
+}
+
+fn names(packages: Vec<&Package>) -> Vec<&str> {
+    packages.into_iter().map(|p| p.name.as_str()).collect()
+}
+
+#[test]
+fn test_changes_since_sync() {
+    let dir = tempfile::tempdir().unwrap();
+    let (current, previous) = snapshots(dir.path());
+    let pair = TreePair::open(&current, &previous).unwrap();
+    assert_eq!(pair.current.packages.len(), 3);
+    assert_eq!(pair.previous.packages.len(), 3);
+
+    assert_eq!(names(pair.added_since_sync()), ["tmux"]);
+    assert_eq!(names(pair.removed_since_sync()), ["gone"]);
+
+    let diff = pair.diff();
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.removed.len(), 1);
+    let changed: Vec<&str> = diff.changed.iter().map(|c| c.atom.as_str()).collect();
+    assert_eq!(changed, ["app-misc/screen", "dev-lang/rust"]);
+
+    let stabilized = pair.keyword_changes_since_sync("amd64");
+    assert_eq!(stabilized.len(), 1);
+    assert_eq!(stabilized[0].kind, EventKind::KeywordChanged);
+    assert_eq!(stabilized[0].atom, "app-misc/screen");
+    assert_eq!(stabilized[0].old.as_deref(), Some("~amd64"));
+    assert_eq!(stabilized[0].new.as_deref(), Some("amd64"));
+    assert!(pair.keyword_changes_since_sync("arm64").is_empty());
+}
+
+#[test]
+fn test_open_with_shares_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let (current, previous) = snapshots(dir.path());
+    let options = OpenOptions {
+        record_spans: true,
+        ..Default::default()
+    };
+    let pair = TreePair::open_with(&current, &previous, options).unwrap();
+    assert!(pair.current.packages.iter().all(|p| p.span.is_some()));
+    assert!(pair.previous.packages.iter().all(|p| p.span.is_some()));
+
+    let err = TreePair::open(&current, dir.path().join("missing.eix")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+// The only test of this binary touching the environment
+#[test]
+fn test_open_default_env_override() {
+    let dir = tempfile::tempdir().unwrap();
+    let (current, previous) = snapshots(dir.path());
+
+    unsafe {
+        env::set_var(EIX_CACHEFILE_ENV, &current);
+        env::set_var(EIX_PREVIOUS_ENV, &previous);
+    }
+    assert_eq!(
+        default_previous_paths(),
+        [previous.clone(), PathBuf::from(EIX_PREVIOUS_PATH)]
+    );
+    let pair = TreePair::open_default().unwrap();
+    assert_eq!(pair.current.info.path, current);
+    assert_eq!(pair.previous.info.path, previous);
+    assert_eq!(names(pair.added_since_sync()), ["tmux"]);
+    unsafe {
+        env::remove_var(EIX_CACHEFILE_ENV);
+        env::remove_var(EIX_PREVIOUS_ENV);
+    }
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "fs")]
+
+use eix::{
+    DBHeader, Database, EixTestBuilder, EixWriter, Package, PackageIndex, PackageReader, ParsedDb,
+    ReadWarning, SharedDb, SortOrder, diff, merge_databases,
+};
+
+fn builder() -> EixTestBuilder {
+    EixTestBuilder::new()
+        .category("x11-terms")
+        .package("kitty", |p| p.version("0.35.2", |v| v))
+        .category("app-misc")
+        .package("tmux", |p| p.version("3.4", |v| v))
+        .package("screen", |p| p.version("4.9.1", |v| v))
+        .category("dev-lang")
+        .package("rust", |p| p.version("1.78.0", |v| v))
+}
+
+fn categories(packages: &[Package]) -> Vec<&str> {
+    let mut categories: Vec<&str> = packages.iter().map(|p| p.category.as_str()).collect();
+    categories.dedup();
+    categories
+}
+
+fn write(dir: &tempfile::TempDir, name: &str, header: &DBHeader, packages: &[Package]) -> ParsedDb {
+    let path = dir.path().join(name);
+    let mut bytes = Vec::new();
+    EixWriter::new(&mut bytes)
+        .write_database(header, packages)
+        .unwrap();
+    std::fs::write(&path, bytes).unwrap();
+    ParsedDb::load(&path).unwrap()
+}
+
+#[test]
+fn test_unsorted_categories_are_reported() {
+    let mut db = builder().build_database();
+    let header = db.read_header(0).unwrap();
+    let mut reader = PackageReader::new(db, header);
+    let packages = reader.read_all().unwrap().into_inner();
+    assert_eq!(categories(&packages), ["x11-terms", "app-misc", "dev-lang"]);
+    assert_eq!(
+        reader.warnings(),
+        [ReadWarning::UnsortedCategory {
+            category: "app-misc".to_string(),
+            previous: "x11-terms".to_string(),
+        }]
+    );
+    assert_eq!(
+        reader.warnings()[0].to_string(),
+        "category app-misc follows x11-terms"
+    );
+
+    // Sorted files have no warnings; the first category has no
+    // predecessor
+    let (header, packages) = builder().build();
+    let mut sorted_bytes = Vec::new();
+    EixWriter::new(&mut sorted_bytes)
+        .sorted(SortOrder::Atom)
+        .write_database(&header, &packages)
+        .unwrap();
+    let mut sorted = Database::from_bytes(sorted_bytes);
+    let sorted_header = sorted.read_header(0).unwrap();
+    let mut reader = PackageReader::new(sorted, sorted_header);
+    reader.read_all().unwrap();
+    assert!(reader.warnings().is_empty());
+}
+
+#[test]
+fn test_apis_handle_unsorted_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let (header, packages) = builder().build();
+    let unsorted = write(&dir, "unsorted.eix", &header, &packages);
+    assert!(!unsorted.is_category_sorted());
+    let mut sorted_packages = packages.clone();
+    eix::sort_packages(&mut sorted_packages, SortOrder::Atom);
+    let sorted = write(&dir, "sorted.eix", &header, &sorted_packages);
+    assert!(sorted.is_category_sorted());
+
+    // The order of the input does not matter
+    assert!(diff(&unsorted.packages, &sorted.packages).is_empty());
+
+    let (merged_header, merged) = merge_databases(vec![(header.clone(), packages.clone())]);
+    assert_eq!(merged_header.size, 3);
+    assert_eq!(categories(&merged), ["app-misc", "dev-lang", "x11-terms"]);
+
+    let index = PackageIndex::from(unsorted.packages.clone());
+    let names: Vec<&str> = index
+        .category_range("app-misc")
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(names, ["screen", "tmux"]);
+
+    let shared = SharedDb::new(unsorted);
+    assert_eq!(
+        shared.categories().collect::<Vec<_>>(),
+        ["app-misc", "dev-lang", "x11-terms"]
+    );
+    assert_eq!(shared.category("app-misc").len(), 2);
+    assert_eq!(
+        categories(shared.packages()),
+        ["app-misc", "dev-lang", "x11-terms"]
+    );
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::fixture_packages;
+use eix::{
+    AcceptKeywords, InstalledIndex, InstalledVersion, downgrades, orphaned_installed, update_report,
+};
+
+fn installed(entries: &[(&str, &str, &str)]) -> InstalledIndex {
+    let mut index = InstalledIndex::new();
+    for (cp, version, slot) in entries {
+        let (category, name) = cp.split_once('/').unwrap();
+        index.insert(InstalledVersion::new(category, name, version, slot, Some("gentoo")).unwrap());
+    }
+    index
+}
+
+#[test]
+fn test_fixture_update_report() {
+    let index = installed(&[
+        ("dev-lang/python", "3.12.11_p1", "3.12/3.12"),
+        ("dev-lang/python", "3.13.9", "3.13/3.13"),
+        ("dev-lang/python", "3.15.0_alpha1", "3.15/3.15"),
+        ("dev-lang/lua", "5.4.6", "5.4"),
+        ("sys-apps/portage", "3.0.74", "0"),
+        ("app-misc/not-in-tree", "1.0", "0"),
+    ]);
+
+    let stable = AcceptKeywords::parse("amd64");
+    let report = update_report(fixture_packages(), &index, &stable);
+    let upgrades: Vec<(&str, &str, &str)> = report
+        .upgrades
+        .iter()
+        .map(|u| (u.atom.as_str(), u.installed.as_str(), u.candidate.as_str()))
+        .collect();
+    assert_eq!(
+        upgrades,
+        vec![
+            ("dev-lang/lua", "5.4.6", "5.4.8"),
+            ("dev-lang/python", "3.12.11_p1", "3.12.12"),
+        ]
+    );
+    assert_eq!(report.downgrades.len(), 1);
+    assert_eq!(report.downgrades[0].atom, "sys-apps/portage");
+    assert_eq!(report.downgrades[0].candidate, "3.0.72-r1");
+    assert_eq!(report.removed.len(), 1);
+    assert_eq!(report.removed[0].cpv(), "app-misc/not-in-tree-1.0");
+
+    // 3.13.10 is masked, so the testing candidate is 3.13.11
+    let testing = AcceptKeywords::parse("amd64 ~amd64");
+    let report = update_report(fixture_packages(), &index, &testing);
+    assert!(report.downgrades.is_empty());
+    assert!(
+        report
+            .upgrades
+            .iter()
+            .any(|u| u.atom == "dev-lang/python" && u.slot == "3.13" && u.candidate == "3.13.11")
+    );
+    assert!(
+        report
+            .upgrades
+            .iter()
+            .any(|u| u.atom == "sys-apps/portage" && u.candidate == "3.0.75")
+    );
+}
+
+#[test]
+fn test_fixture_downgrades_and_orphans() {
+    let mut index = installed(&[
+        // Newer than anything in the tree
+        ("app-misc/screen", "99.0", "0"),
+        // 3.13.10 is masked; 3.13.9 is the best stable version
+        ("dev-lang/python", "3.13.10", "3.13/3.13"),
+        ("dev-lang/lua", "5.4.8", "5.4"),
+        ("app-misc/not-in-tree", "1.0", "0"),
+    ]);
+    index.insert(
+        InstalledVersion::new("dev-util", "from-overlay", "2.0", "0/2", Some("guru")).unwrap(),
+    );
+    let stable = AcceptKeywords::parse("amd64");
+
+    let found = downgrades(fixture_packages(), &index, &stable);
+    let cpvs: Vec<&str> = found.iter().map(|d| d.cpv.as_str()).collect();
+    assert_eq!(cpvs, ["app-misc/screen-99.0", "dev-lang/python-3.13.10"]);
+    assert!(found.iter().all(|d| d.candidate != d.installed));
+    let json = serde_json::to_value(&found[1]).unwrap();
+    assert_eq!(json["cpv"], "dev-lang/python-3.13.10");
+    assert_eq!(json["candidate"], "3.13.9");
+
+    let orphaned = orphaned_installed(fixture_packages(), &index);
+    let cpvs: Vec<&str> = orphaned.iter().map(|o| o.cpv.as_str()).collect();
+    assert_eq!(
+        cpvs,
+        ["app-misc/not-in-tree-1.0", "dev-util/from-overlay-2.0"]
+    );
+    assert_eq!(orphaned[1].repository.as_deref(), Some("guru"));
+    let json = serde_json::to_value(&orphaned).unwrap();
+    assert_eq!(json[1]["slot"], "0");
+}
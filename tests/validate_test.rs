@@ -0,0 +1,235 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{
+    Database, EixTestBuilder, FindingKind, OpenOptions, PackageReader, ValidateOptions,
+    ValidationReport, validate, validate_bytes,
+};
+
+fn check(data: &[u8]) -> ValidationReport {
+    validate_bytes(data, &ValidateOptions::default())
+}
+
+/// Two packages in one overlay without keywords or IUSE
+fn small() -> Vec<u8> {
+    EixTestBuilder::new()
+        .category("app-misc")
+        .package("screen", |p| p.description("desc").version("1.0", |v| v))
+        .package("tmux", |p| p.description("desc").version("2.0", |v| v))
+        .build_bytes()
+}
+
+fn offset(data: &[u8], needle: &[u8]) -> usize {
+    data.windows(needle.len())
+        .position(|w| w == needle)
+        .expect("needle not found")
+}
+
+/// The offset just behind the last version of the first package
+fn first_version_end(data: &[u8]) -> usize {
+    let options = OpenOptions {
+        record_spans: true,
+        ..Default::default()
+    };
+    let mut db = Database::from_bytes_with(data, options);
+    let header = db.read_header(0).unwrap();
+    let mut reader = PackageReader::new(db, header);
+    reader.next_category().unwrap();
+    let package = reader.read_package().unwrap().unwrap();
+    package.versions[0].span.unwrap().end as usize
+}
+
+#[test]
+fn test_fixture_is_clean() {
+    let report = validate(FIXTURE_EIX, &ValidateOptions::default()).unwrap();
+    assert!(report.is_clean(), "{:?}", report.findings);
+    let (header, packages) = fixture();
+    assert_eq!(report.version, Some(header.version as u64));
+    assert_eq!(report.categories, header.size as u64);
+    assert_eq!(report.packages, packages.len() as u64);
+    let versions: usize = packages.iter().map(|p| p.versions.len()).sum();
+    assert_eq!(report.versions, versions as u64);
+    assert!(!report.truncated);
+}
+
+#[test]
+fn test_invalid_magic() {
+    let mut data = small();
+    data[0] = b'E';
+    let report = check(&data);
+    assert_eq!(report.findings.len(), 1);
+    assert_eq!(report.findings[0].kind, FindingKind::InvalidMagic);
+    assert_eq!(report.findings[0].offset, 0);
+    assert_eq!(report.version, None);
+}
+
+#[test]
+fn test_unsupported_version() {
+    let mut data = small();
+    data[4] = 40;
+    let report = check(&data);
+    assert_eq!(
+        report.findings[0].kind,
+        FindingKind::UnsupportedVersion { version: 40 }
+    );
+    assert_eq!(
+        report.findings[0].to_string(),
+        "0x4: header: version: unsupported database version 40"
+    );
+}
+
+#[test]
+fn test_truncated() {
+    let data = small();
+    let report = check(&data[..data.len() - 3]);
+    assert_eq!(report.findings.len(), 1);
+    assert_eq!(report.findings[0].kind, FindingKind::UnexpectedEof);
+    assert_eq!(report.packages, 1);
+
+    // The last category is missing
+    let mut data = small();
+    let categories = offset(&data, b"eix\n") + 5;
+    data[categories] = 2;
+    let report = check(&data);
+    assert_eq!(report.findings.len(), 1);
+    assert_eq!(report.findings[0].kind, FindingKind::UnexpectedEof);
+    assert_eq!(report.findings[0].location, "category 2 of 2: name");
+    assert_eq!(report.findings[0].offset, data.len() as u64);
+    assert_eq!(report.categories, 1);
+}
+
+#[test]
+fn test_trailing_bytes() {
+    let mut data = small();
+    let len = data.len() as u64;
+    data.extend(b"abc");
+    let report = check(&data);
+    assert_eq!(report.findings.len(), 1);
+    assert_eq!(
+        report.findings[0].kind,
+        FindingKind::TrailingBytes { len: 3 }
+    );
+    assert_eq!(report.findings[0].offset, len);
+    assert_eq!(report.packages, 2);
+}
+
+#[test]
+fn test_findings_in_several_packages() {
+    let mut data = small();
+    // Overlay key of screen-1.0, followed by the empty IUSE
+    let key = first_version_end(&data) - 2;
+    data[key] = 3;
+    // Invalid UTF-8 in the description of screen
+    let screen = offset(&data, b"\x06screen\x04desc");
+    data[screen + 8] = 0xC3;
+    // License index of tmux
+    let tmux = offset(&data, b"\x04tmux\x04desc\x00");
+    data[tmux + 11] = 9;
+
+    let report = check(&data);
+    let kinds: Vec<_> = report.findings.iter().map(|f| &f.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            &FindingKind::InvalidUtf8,
+            &FindingKind::OverlayKeyOutOfRange {
+                key: 3,
+                overlays: 1
+            },
+            &FindingKind::HashIndexOutOfRange {
+                hash: "license",
+                index: 9,
+                len: 1
+            },
+        ]
+    );
+    let locations: Vec<_> = report
+        .findings
+        .iter()
+        .map(|f| f.location.as_str())
+        .collect();
+    assert_eq!(
+        locations,
+        [
+            "app-misc/screen: description",
+            "app-misc/screen version 1: overlay key",
+            "app-misc/tmux: license"
+        ]
+    );
+    assert_eq!(report.findings[0].offset, screen as u64 + 7);
+    assert_eq!(report.findings[1].offset, key as u64);
+    assert_eq!(report.packages, 2);
+}
+
+#[test]
+fn test_length_mismatch_and_skip() {
+    let mut data = small();
+    // Shorten the length prefix of screen by one byte
+    let screen = offset(&data, b"\x06screen");
+    data[screen - 1] -= 1;
+    let report = check(&data);
+    assert_eq!(report.findings.len(), 1);
+    let declared = data[screen - 1] as u64;
+    assert_eq!(
+        report.findings[0].kind,
+        FindingKind::LengthMismatch {
+            declared,
+            consumed: declared + 1
+        }
+    );
+    assert_eq!(
+        report.findings[0].location,
+        "app-misc/screen: package length"
+    );
+
+    // A version count beyond the limit ends the package, the walk goes
+    // on behind it
+    let mut data = small();
+    let screen = offset(&data, b"\x06screen\x04desc\x00");
+    data[screen + 14] = 0xFE;
+    let options = ValidateOptions {
+        limits: eix::ParseLimits {
+            max_list_len: 100,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let report = validate_bytes(&data, &options);
+    assert_eq!(report.findings.len(), 1);
+    assert_eq!(
+        report.findings[0].kind,
+        FindingKind::LimitExceeded {
+            value: 0xFE,
+            limit: 100
+        }
+    );
+    assert_eq!(report.packages, 2);
+}
+
+#[test]
+fn test_max_findings_and_json() {
+    let mut data = small();
+    data.extend(b"abc");
+    let options = ValidateOptions {
+        max_findings: 1,
+        ..Default::default()
+    };
+    let mut bad = data.clone();
+    let tmux = offset(&bad, b"\x04tmux\x04desc\x00");
+    bad[tmux + 11] = 9;
+    let report = validate_bytes(&bad, &options);
+    assert_eq!(report.findings.len(), 1);
+    assert!(report.truncated);
+
+    let report = check(&data);
+    let json = serde_json::to_value(&report.findings[0]).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "offset": data.len() - 3,
+            "location": "end",
+            "kind": "trailing_bytes",
+            "len": 3
+        })
+    );
+}
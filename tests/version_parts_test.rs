@@ -0,0 +1,72 @@
+mod common;
+
+use common::fixture_packages;
+use eix::{Package, SerializeOptions};
+use serde_json::{Value, json};
+
+#[test]
+fn test_default_output_has_no_parts() {
+    let packages = &fixture_packages()[..500];
+    let json = serde_json::to_string(&SerializeOptions::default().view_list(packages)).unwrap();
+    assert_eq!(json, serde_json::to_string(packages).unwrap());
+    assert!(!json.contains("\"parts\""));
+
+    // Read back without parts, the parts stay empty
+    let back: Vec<Package> = serde_json::from_str(&json).unwrap();
+    assert!(
+        back.iter()
+            .flat_map(|p| &p.versions)
+            .all(|v| v.parts.is_empty())
+    );
+}
+
+#[test]
+fn test_version_parts() {
+    let packages = &fixture_packages()[..500];
+    let options = SerializeOptions {
+        version_parts: true,
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&options.view_list(packages)).unwrap();
+    // The parts follow the version string
+    let first = &packages[0].versions[0];
+    assert!(json.contains(&format!(
+        r#""version":{},"parts":[{{"type":"{:?}","content":{}}}"#,
+        json!(first.version_string),
+        first.parts[0].part_type,
+        json!(first.parts[0].part_content),
+    )));
+    let values: Vec<Value> = serde_json::from_str(&json).unwrap();
+    for (package, value) in packages.iter().zip(&values) {
+        for (version, value) in package
+            .versions
+            .iter()
+            .zip(value["versions"].as_array().unwrap())
+        {
+            let parts = value["parts"].as_array().unwrap();
+            assert_eq!(parts.len(), version.parts.len());
+            for (part, value) in version.parts.iter().zip(parts) {
+                assert_eq!(
+                    *value,
+                    json!({
+                        "type": format!("{:?}", part.part_type),
+                        "content": part.part_content,
+                    })
+                );
+            }
+        }
+    }
+
+    // The parts are read back
+    let back: Vec<Package> = serde_json::from_str(&json).unwrap();
+    for (package, copy) in packages.iter().zip(&back) {
+        for (version, copy) in package.versions.iter().zip(&copy.versions) {
+            assert_eq!(copy.parts, version.parts);
+            assert_eq!(copy.get_full_version(), version.get_full_version());
+        }
+    }
+    assert_eq!(
+        serde_json::to_string(&options.view_list(&back)).unwrap(),
+        json
+    );
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::read_database;
+use eix::{EixTestBuilder, compare_parts, parse_version};
+
+const VERSIONS: &[&str] = &[
+    "0",
+    "1.0",
+    "1.0a",
+    "1.2_rc3-r1",
+    "1.2.3_alpha1_beta2",
+    "2.0_pre20240101",
+    "3.14.0_p1",
+    "20240101",
+    "1.78.0-r100",
+    "9999",
+];
+
+#[test]
+fn test_versions_decode() {
+    let mut builder = EixTestBuilder::new().category("app-misc");
+    for (i, &version) in VERSIONS.iter().enumerate() {
+        builder = builder.package(&format!("pkg{}", i), |p| p.version(version, |v| v));
+    }
+    let (_, packages) = read_database(builder.build_database());
+    assert_eq!(packages.len(), VERSIONS.len());
+    for (pkg, &version) in packages.iter().zip(VERSIONS) {
+        let v = &pkg.versions[0];
+        assert_eq!(v.version_string, version);
+        let parts = parse_version(&v.version_string).unwrap_or_else(|e| {
+            panic!("{}/{}: {}", pkg.category, pkg.name, e);
+        });
+        assert_eq!(parts, v.parts, "{}/{}", pkg.category, pkg.name);
+        assert!(compare_parts(&parts, &v.parts).is_eq());
+    }
+}
+
+#[test]
+fn test_versions_keep_their_order() {
+    let (_, packages) = read_database(
+        EixTestBuilder::new()
+            .category("dev-lang")
+            .package("rust", |p| {
+                p.version("1.77.1", |v| v)
+                    .version("1.78.0_beta1", |v| v)
+                    .version("1.78.0", |v| v)
+                    .version("1.78.0-r1", |v| v)
+            })
+            .build_database(),
+    );
+    let versions = &packages[0].versions;
+    assert_eq!(versions.len(), 4);
+    assert!(
+        versions
+            .windows(2)
+            .all(|w| compare_parts(&w[0].parts, &w[1].parts).is_lt())
+    );
+}
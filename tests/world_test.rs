@@ -0,0 +1,116 @@
+mod common;
+
+use common::fixture_packages;
+use eix::{
+    Database, EixTestBuilder, MASK_PACKAGE, MASK_WORLD, MASK_WORLD_SETS, PackageIndex,
+    PackageReader, WorldFile, world_consistency,
+};
+use std::collections::BTreeSet;
+
+/// Reconstructs a world file from the MASK_WORLD flags of the fixture
+fn fixture_world_entries() -> BTreeSet<String> {
+    fixture_packages()
+        .iter()
+        .filter(|p| p.versions.iter().any(|v| v.mask_flags & MASK_WORLD != 0))
+        .map(|p| format!("{}/{}", p.category, p.name))
+        .collect()
+}
+
+#[test]
+fn test_fixture_world_consistency() {
+    let entries = fixture_world_entries();
+    assert!(entries.contains("app-editors/vim"));
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(
+        file.path(),
+        entries.iter().cloned().collect::<Vec<_>>().join("\n"),
+    )
+    .unwrap();
+    let world = WorldFile::load(file.path()).unwrap();
+    let report = world_consistency(&world, fixture_packages());
+    assert!(report.is_consistent(), "{:?}", report);
+
+    // Drop vim from the world file and add an unknown package
+    let mut entries = entries;
+    entries.remove("app-editors/vim");
+    entries.insert("app-misc/not-in-tree".to_string());
+    std::fs::write(
+        file.path(),
+        entries.into_iter().collect::<Vec<_>>().join("\n"),
+    )
+    .unwrap();
+    let world = WorldFile::load(file.path()).unwrap();
+    let report = world_consistency(&world, fixture_packages());
+    assert_eq!(report.missing, vec!["app-misc/not-in-tree"]);
+    assert!(!report.mismatches.is_empty());
+    assert!(
+        report
+            .mismatches
+            .iter()
+            .all(|m| m.cpv.starts_with("app-editors/vim-") && m.flagged && !m.in_world_file)
+    );
+}
+
+#[test]
+fn test_world_membership() {
+    let bytes = EixTestBuilder::new()
+        .world_set("@kde")
+        .world_set("games")
+        .category("app-editors")
+        .package("vim", |p| {
+            p.version("9.0", |v| v.keywords("amd64").mask(MASK_WORLD))
+                .version("9.1", |v| v.keywords("~amd64").mask(MASK_WORLD))
+        })
+        .category("app-misc")
+        .package("screen", |p| {
+            p.version("4.9", |v| v.keywords("amd64"))
+                .version("5.0", |v| v.keywords("~amd64").mask(MASK_WORLD))
+        })
+        .package("tmux", |p| p.version("3.4", |v| v.keywords("amd64")))
+        .category("kde-apps")
+        .package("kate", |p| {
+            p.version("24.05", |v| v.keywords("~amd64").mask(MASK_WORLD_SETS))
+                .version("24.08", |v| {
+                    v.keywords("~amd64").mask(MASK_WORLD_SETS | MASK_PACKAGE)
+                })
+        })
+        .build_bytes();
+    let mut db = Database::from_bytes(bytes);
+    let header = db.read_header(0).unwrap();
+    assert!(header.contains_set("@kde"));
+    assert!(header.contains_set("kde"));
+    assert!(header.contains_set("@games"));
+    assert!(!header.contains_set("@gnome"));
+    let mut reader = PackageReader::new(db, header);
+    let index = PackageIndex::from_reader(&mut reader).unwrap();
+
+    let vim = index.get("app-editors/vim").unwrap();
+    assert!(
+        vim.versions
+            .iter()
+            .all(|v| v.in_world() && !v.in_world_sets())
+    );
+    let kate = index.get("kde-apps/kate").unwrap();
+    assert!(
+        kate.versions
+            .iter()
+            .all(|v| !v.in_world() && v.in_world_sets())
+    );
+    let tmux = index.get("app-misc/tmux").unwrap();
+    assert!(!tmux.has_world_version());
+
+    let world: Vec<String> = index.world_packages().map(|p| p.cp()).collect();
+    assert_eq!(
+        world,
+        ["app-editors/vim", "app-misc/screen", "kde-apps/kate"]
+    );
+
+    // In @world without a stable version for amd64
+    let unstable: Vec<String> = index
+        .world_packages()
+        .filter(|p| !p.versions.iter().any(|v| v.is_stable("amd64")))
+        .map(|p| p.cp())
+        .collect();
+    assert_eq!(unstable, ["kde-apps/kate"]);
+}
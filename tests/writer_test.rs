@@ -0,0 +1,72 @@
+mod common;
+
+use common::{FIXTURE_EIX, fixture};
+use eix::{EixWriter, OverlayIdent, Package, ParsedDb, merge_databases};
+
+#[test]
+fn test_fixture_roundtrip_is_identical() {
+    let (header, packages) = fixture();
+    let mut writer = EixWriter::new(Vec::new());
+    writer.write_database(header, packages).unwrap();
+    let written = writer.into_inner();
+    let original = std::fs::read(FIXTURE_EIX).unwrap();
+    assert_eq!(written.len(), original.len());
+    assert!(written == original, "written file differs from the fixture");
+}
+
+#[test]
+fn test_merged_database_is_writable() {
+    let (header, packages) = fixture();
+    let mut overlay_header = header.clone();
+    overlay_header.overlays.push(OverlayIdent {
+        path: "/var/db/repos/local".to_string(),
+        label: "local".into(),
+        priority: 1,
+    });
+    let mut python = common::fixture_package("dev-lang", "python").clone();
+    for v in &mut python.versions {
+        v.overlay_key = 1;
+        v.reponame = "local".into();
+    }
+    let new_package = Package {
+        category: "app-local".to_string(),
+        name: "tool".to_string(),
+        licenses: "local-license".to_string(),
+        versions: vec![python.versions[0].clone()],
+        ..Default::default()
+    };
+    let gentoo_lua = common::fixture_package("dev-lang", "lua").clone();
+    let inputs = vec![
+        (header.clone(), packages.clone()),
+        (overlay_header, vec![python, new_package, gentoo_lua]),
+    ];
+    let (merged_header, merged) = merge_databases(inputs);
+    assert_eq!(merged_header.overlays.len(), 2);
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let mut writer = EixWriter::new(std::fs::File::create(file.path()).unwrap());
+    writer.write_database(&merged_header, &merged).unwrap();
+    drop(writer);
+
+    let parsed = ParsedDb::load(file.path()).unwrap();
+    assert_eq!(parsed.packages.len(), packages.len() + 1);
+    assert_eq!(parsed.header.size, header.size + 1);
+    let find = |cat: &str, name: &str| {
+        parsed
+            .packages
+            .iter()
+            .find(|p| p.category == cat && p.name == name)
+            .unwrap()
+    };
+    let python = find("dev-lang", "python");
+    let original = common::fixture_package("dev-lang", "python");
+    // The local overlay has the higher priority and shadows gentoo
+    assert_eq!(python.versions.len(), original.versions.len());
+    assert!(python.versions.iter().all(|v| &*v.reponame == "local"));
+    // The shared gentoo versions of lua are not duplicated
+    assert_eq!(
+        find("dev-lang", "lua").versions.len(),
+        common::fixture_package("dev-lang", "lua").versions.len()
+    );
+    assert_eq!(find("app-local", "tool").licenses, "local-license");
+}